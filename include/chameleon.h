@@ -0,0 +1,66 @@
+/*
+ * C interface to chameleon, built from src/ffi.rs with the ffi feature:
+ *
+ *     cargo rustc --lib --release --crate-type cdylib --features ffi
+ *
+ * Keep in sync with src/ffi.rs.
+ */
+
+#ifndef CHAMELEON_H
+#define CHAMELEON_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Status codes returned by chameleon_decode_png. Each is the code of an
+ * ErrorKind on the Rust side, and the exit code the chameleon command line
+ * tool uses for the same kind of failure. They never change once assigned.
+ */
+#define CHAMELEON_OK 0
+#define CHAMELEON_ERROR_NULL_POINTER 1
+#define CHAMELEON_ERROR_IO 2
+#define CHAMELEON_ERROR_INVALID_FORMAT 3
+#define CHAMELEON_ERROR_COMPRESSION 4
+#define CHAMELEON_ERROR_LIMIT_EXCEEDED 5
+#define CHAMELEON_ERROR_UNEXPECTED 6
+#define CHAMELEON_ERROR_CANCELLED 7
+
+/*
+ * A decoded image. data holds len bytes of RGBA pixels, from left to right,
+ * top to bottom. The pixels are owned by chameleon, release them with
+ * chameleon_image_free and never with free().
+ */
+typedef struct chameleon_image {
+    uint32_t width;
+    uint32_t height;
+    uint8_t *data;
+    size_t len;
+} chameleon_image;
+
+/*
+ * Decodes the PNG file held in the len bytes at buf. buf is only read during
+ * the call and stays owned by the caller. On success *out is filled in and
+ * CHAMELEON_OK is returned, otherwise *out is left untouched.
+ */
+int32_t chameleon_decode_png(const uint8_t *buf, size_t len, chameleon_image *out);
+
+/*
+ * Frees the pixels of an image filled in by chameleon_decode_png and nulls
+ * its data pointer. Passing NULL, or an image that was already freed, does
+ * nothing.
+ */
+void chameleon_image_free(chameleon_image *image);
+
+/* Describes a status code. The returned string is static, do not free it. */
+const char *chameleon_status_message(int32_t status);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CHAMELEON_H */
@@ -0,0 +1,299 @@
+//! Animations as a list of frames, the common ground between animated
+//! formats. APNG and GIF both describe an animation the same way: a canvas,
+//! and frames drawn onto part of it in turn, each shown for a delay and then
+//! disposed of in one of a few ways. Codecs read into and write out of
+//! Frames, so converting between them, or changing the frame rate, happens
+//! here once.
+//!
+//! '''
+//! let mut frames = Frames::new(64, 64).loops(0);
+//! frames.push(Frame::new(first, Duration::from_millis(100)))?;
+//! frames.push(Frame::new(second, Duration::from_millis(100)).offset(16, 16))?;
+//!
+//! for (canvas, delay) in frames.canvases() {
+//!     show(&canvas, delay);
+//! }
+//! '''
+
+use alloc::{vec, vec::Vec};
+use core::{slice, time::Duration};
+
+use crate::{
+    draw::{self, Blend},
+    image::{Image, ImageError, PixelFormat},
+};
+
+/// What happens to the area of the canvas a frame covered once its delay is
+/// over, before the next frame is drawn. APNG's dispose_op and GIF's
+/// disposal method both work this way.
+///
+/// # Members
+///
+/// * 'Keep' - The frame is left in place, the next one is drawn over it.
+/// * 'Background' - The area is cleared to transparent black.
+/// * 'Previous' - The area goes back to how it was before the frame was
+///   drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Disposal {
+    #[default]
+    Keep,
+    Background,
+    Previous,
+}
+
+/// A single frame of an animation.
+///
+/// # Fields
+///
+/// * 'image' - The pixels of the frame, which can be smaller than the
+///   canvas.
+/// * 'delay' - How long the frame is shown for.
+/// * 'x' - The left edge of the frame on the canvas in pixels.
+/// * 'y' - The top edge of the frame on the canvas in pixels.
+/// * 'disposal' - What happens to the frame's area afterwards, see
+///   Disposal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub image: Image,
+    pub delay: Duration,
+    pub x: usize,
+    pub y: usize,
+    pub disposal: Disposal,
+}
+
+impl Frame {
+    /// Creates a Frame covering the canvas from the top left corner, kept in
+    /// place after its delay.
+    ///
+    /// # Arguments
+    ///
+    /// * 'image' - The pixels of the frame.
+    /// * 'delay' - How long the frame is shown for.
+    ///
+    /// # Returns
+    ///
+    /// The Frame.
+    pub fn new(image: Image, delay: Duration) -> Self {
+        Self {
+            image,
+            delay,
+            x: 0,
+            y: 0,
+            disposal: Disposal::Keep,
+        }
+    }
+    /// Sets where the frame's top left corner goes on the canvas.
+    pub fn offset(mut self, x: usize, y: usize) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+    /// Sets what happens to the frame's area after its delay.
+    pub fn disposal(mut self, disposal: Disposal) -> Self {
+        self.disposal = disposal;
+        self
+    }
+}
+
+/// An animation, a canvas size and the frames drawn onto it in order.
+///
+/// # Fields
+///
+/// * 'width' - The width of the canvas in pixels.
+/// * 'height' - The height of the canvas in pixels.
+/// * 'loops' - How many times the animation plays, 0 to repeat forever.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frames {
+    pub width: usize,
+    pub height: usize,
+    pub loops: u32,
+    frames: Vec<Frame>,
+}
+
+impl Frames {
+    /// Creates an empty animation that repeats forever.
+    ///
+    /// # Arguments
+    ///
+    /// * 'width' - The width of the canvas in pixels.
+    /// * 'height' - The height of the canvas in pixels.
+    ///
+    /// # Returns
+    ///
+    /// The Frames, with no frames yet.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            loops: 0,
+            frames: Vec::new(),
+        }
+    }
+    /// Sets how many times the animation plays, 0 to repeat forever.
+    pub fn loops(mut self, loops: u32) -> Self {
+        self.loops = loops;
+        self
+    }
+    /// Adds a frame to the end of the animation.
+    ///
+    /// # Arguments
+    ///
+    /// * 'frame' - The Frame to add.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or an ImageError if the frame doesn't fit on the
+    /// canvas at its offset or its buffer doesn't match its dimensions.
+    pub fn push(&mut self, frame: Frame) -> Result<(), ImageError> {
+        frame.image.check_size()?;
+
+        let right = frame.x.checked_add(frame.image.width);
+        let bottom = frame.y.checked_add(frame.image.height);
+        if right.is_none_or(|right| right > self.width)
+            || bottom.is_none_or(|bottom| bottom > self.height)
+        {
+            return Err(ImageError::InvalidDimensions(
+                frame.image.width,
+                frame.image.height,
+            ));
+        }
+
+        self.frames.push(frame);
+        Ok(())
+    }
+    /// Gets the number of frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+    /// Whether there are no frames.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+    /// Iterates over the frames in order.
+    pub fn iter(&self) -> slice::Iter<'_, Frame> {
+        self.frames.iter()
+    }
+    /// Gets how long one play through of the animation takes.
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.delay).sum()
+    }
+    /// Iterates over what the whole canvas looks like while each frame is
+    /// shown, with every earlier frame and its disposal taken into account.
+    /// This is what a viewer displays, and what formats without partial
+    /// frames need.
+    ///
+    /// # Returns
+    ///
+    /// An iterator of the Rgba8 canvas and the delay of each frame.
+    pub fn canvases(&self) -> Canvases<'_> {
+        Canvases {
+            frames: self.frames.iter(),
+            canvas: Image {
+                width: self.width,
+                height: self.height,
+                format: PixelFormat::Rgba8,
+                data: vec![0; self.width * self.height * 4],
+            },
+        }
+    }
+    /// Converts the animation to a constant frame rate, e.g. for formats or
+    /// players that can't vary the delay. Each new frame is a whole canvas
+    /// showing whichever frame was on screen when it starts.
+    ///
+    /// # Arguments
+    ///
+    /// * 'interval' - The delay of every new frame, the inverse of the frame
+    ///   rate.
+    ///
+    /// # Returns
+    ///
+    /// The resampled Frames, or a copy of the animation if interval or the
+    /// animation's duration is zero.
+    pub fn resample(&self, interval: Duration) -> Frames {
+        let total = self.duration();
+        if interval.is_zero() || total.is_zero() {
+            return self.clone();
+        }
+
+        let mut resampled = Frames::new(self.width, self.height).loops(self.loops);
+        let mut canvases = self.canvases();
+        let mut current = canvases.next();
+        // When the frame in current stops being shown.
+        let mut ends = current.as_ref().map_or(Duration::ZERO, |(_, delay)| *delay);
+        let mut time = Duration::ZERO;
+
+        while time < total {
+            while time >= ends {
+                current = canvases.next();
+                ends += current.as_ref().map_or(Duration::ZERO, |(_, delay)| *delay);
+            }
+
+            if let Some((canvas, _)) = &current {
+                resampled.frames.push(Frame::new(canvas.clone(), interval));
+            }
+            time += interval;
+        }
+
+        resampled
+    }
+}
+
+impl<'a> IntoIterator for &'a Frames {
+    type Item = &'a Frame;
+    type IntoIter = slice::Iter<'a, Frame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.iter()
+    }
+}
+
+impl IntoIterator for Frames {
+    type Item = Frame;
+    type IntoIter = vec::IntoIter<Frame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.into_iter()
+    }
+}
+
+/// An iterator over the canvas as each frame is shown, see
+/// Frames::canvases.
+pub struct Canvases<'a> {
+    frames: slice::Iter<'a, Frame>,
+    canvas: Image,
+}
+
+impl Iterator for Canvases<'_> {
+    type Item = (Image, Duration);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = self.frames.next()?;
+        let before = match frame.disposal {
+            Disposal::Previous => Some(self.canvas.clone()),
+            _ => None,
+        };
+
+        // Frames are checked to fit the canvas when they're pushed, so
+        // neither of these can fail.
+        let _ = draw::composite(
+            &mut self.canvas,
+            &frame.image,
+            frame.x,
+            frame.y,
+            Blend::Srgb,
+        );
+        let shown = self.canvas.clone();
+
+        match (frame.disposal, before) {
+            (Disposal::Background, _) => {
+                let (width, height) = (frame.image.width, frame.image.height);
+                let _ = draw::fill(&mut self.canvas, frame.x, frame.y, width, height, [0; 4]);
+            }
+            (Disposal::Previous, Some(before)) => self.canvas = before,
+            _ => {}
+        }
+
+        Some((shown, frame.delay))
+    }
+}
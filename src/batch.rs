@@ -0,0 +1,560 @@
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::String,
+    vec::Vec,
+};
+use core::fmt::{self, Display};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    num::NonZeroUsize,
+    panic,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::UNIX_EPOCH,
+};
+
+use crate::compression::checksums::Crc32;
+use crate::formats::png::{DecodeOptions, DecoderError, Png};
+
+/// The name of the file, in the output directory, a Manifest is kept in by
+/// the command line tool.
+pub const MANIFEST_NAME: &str = ".chameleon-manifest";
+
+/// The reason Manifest::resume gives files it skips.
+pub const DONE: &str = "converted before and unchanged since";
+
+/// Options for decoding many files at once.
+///
+/// # Fields
+///
+/// * 'threads' - The number of worker threads to spawn. Defaults to the
+///   available parallelism of the machine, and is never more than
+///   the number of files being decoded.
+/// * 'decode' - The DecodeOptions every file is decoded with.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    pub threads: usize,
+    pub decode: DecodeOptions,
+}
+
+impl BatchOptions {
+    /// Creates a new BatchOptions using one thread per available core.
+    ///
+    /// # Returns
+    ///
+    /// A BatchOptions with default values.
+    pub fn new() -> Self {
+        Self {
+            threads: thread::available_parallelism()
+                .map(NonZeroUsize::get)
+                .unwrap_or(1),
+            decode: DecodeOptions::new(),
+        }
+    }
+    /// Sets the number of worker threads.
+    ///
+    /// # Arguments
+    ///
+    /// * 'threads' - The number of threads to use, values below 1 are
+    ///   treated as 1.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+    /// Sets the DecodeOptions every file is decoded with.
+    pub fn decode_options(mut self, decode: DecodeOptions) -> Self {
+        self.decode = decode;
+        self
+    }
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decodes every file in paths across a scoped pool of worker threads.
+/// Each worker pulls the next undecoded path from a shared counter, so a
+/// single large image doesn't hold up the rest of the queue.
+///
+/// # Arguments
+///
+/// * 'paths' - The paths of the PNG files to decode.
+/// * 'options' - A BatchOptions describing how to distribute the work.
+///
+/// # Returns
+///
+/// A Vec containing the result of decoding each file, in the same order as
+/// the paths given.
+///
+/// # Examples
+///
+/// '''
+/// let results = batch::decode_all(&["a.png", "b.png"], &BatchOptions::new());
+///
+/// for result in results {
+///     let png = result?;
+///     println!("{:?}", png.dimensions);
+/// }
+/// '''
+pub fn decode_all<P: AsRef<Path> + Sync>(
+    paths: &[P],
+    options: &BatchOptions,
+) -> Vec<Result<Png, DecoderError>> {
+    let threads = options.threads.clamp(1, paths.len().max(1));
+    let next = AtomicUsize::new(0);
+
+    let mut decoded = thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        if idx >= paths.len() {
+                            break;
+                        }
+                        results.push((idx, options.decode.decode(&paths[idx])));
+                    }
+                    results
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect::<Vec<_>>()
+    });
+
+    // Put the results back into the order the paths were given in.
+    decoded.sort_by_key(|(idx, _)| *idx);
+
+    decoded.into_iter().map(|(_, result)| result).collect()
+}
+
+/// What converting a file would do with it, see plan_dir.
+///
+/// # Members
+///
+/// * 'Convert' - The file is read and written to a new output file.
+/// * 'Overwrite' - The file is read and written over an output file that
+///   already exists.
+/// * 'Skip' - The file is left alone, for the reason given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Convert,
+    Overwrite,
+    Skip(&'static str),
+}
+
+/// One file of a planned conversion.
+///
+/// # Fields
+///
+/// * 'input' - The path of the file that would be read.
+/// * 'output' - The path it would be written to.
+/// * 'action' - What would be done with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub action: Action,
+}
+
+// One line a step, laid out the way --dry-run prints them.
+impl Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (input, output) = (self.input.display(), self.output.display());
+        match self.action {
+            Action::Convert => write!(f, "convert {input} -> {output}"),
+            Action::Overwrite => write!(f, "overwrite {input} -> {output}"),
+            Action::Skip(reason) => write!(f, "skip {input}: {reason}"),
+        }
+    }
+}
+
+/// Plans converting a single file, without reading it.
+///
+/// # Arguments
+///
+/// * 'input' - The path of the file to convert.
+/// * 'output' - The path to write it to.
+///
+/// # Returns
+///
+/// The Step, an overwrite if output already exists.
+pub fn plan_file<P: AsRef<Path>, Q: AsRef<Path>>(input: P, output: Q) -> Step {
+    let output = output.as_ref().to_path_buf();
+    let action = match output.exists() {
+        true => Action::Overwrite,
+        false => Action::Convert,
+    };
+
+    Step {
+        input: input.as_ref().to_path_buf(),
+        output,
+        action,
+    }
+}
+
+/// Plans converting every file in a directory and its subdirectories,
+/// without reading any of them. Each is written to the same place under
+/// output_dir as it has under input_dir, with its extension changed.
+///
+/// Files that aren't PNG files are skipped, as are files already under
+/// output_dir when it's inside input_dir, and files that would be written
+/// to the same output as one before them, e.g. a.PNG after a.png. Symbolic
+/// links to directories aren't followed, so a link back up the tree can't
+/// loop forever.
+///
+/// # Arguments
+///
+/// * 'input_dir' - The directory to convert.
+/// * 'output_dir' - The directory to write the converted files under.
+/// * 'extension' - The extension of the converted files, which decides
+///   their format.
+///
+/// # Returns
+///
+/// A result containing either a Step for every file, sorted by input path,
+/// or an io::Error if a directory couldn't be listed.
+///
+/// # Examples
+///
+/// '''
+/// for step in batch::plan_dir("./assets", "./converted", "ppm")? {
+///     println!("{step}");
+/// }
+/// '''
+pub fn plan_dir<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_dir: P,
+    output_dir: Q,
+    extension: &str,
+) -> Result<Vec<Step>, io::Error> {
+    let (input_dir, output_dir) = (input_dir.as_ref(), output_dir.as_ref());
+    let mut paths = Vec::new();
+    let mut dirs = Vec::from([input_dir.to_path_buf()]);
+
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            // The entry's own type doesn't follow symbolic links, path's does.
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else if !path.is_dir() {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+
+    // Compared whole, as the same directory can be named more than one way.
+    let canonical_input = fs::canonicalize(input_dir)?;
+    let nested = fs::canonicalize(output_dir)
+        .ok()
+        .filter(|dir| *dir != canonical_input);
+    let mut outputs = BTreeSet::new();
+    let steps = paths.into_iter().map(|input| {
+        let relative = input.strip_prefix(input_dir).unwrap_or(&input);
+        let output = output_dir.join(relative).with_extension(extension);
+        let skip = if !input
+            .extension()
+            .is_some_and(|ex| ex.eq_ignore_ascii_case("png"))
+        {
+            Some("not a PNG file")
+        } else if nested
+            .as_ref()
+            .is_some_and(|dir| canonical_input.join(relative).starts_with(dir))
+        {
+            Some("already in the output directory")
+        } else if !outputs.insert(output.clone()) {
+            Some("another file is written to the same output")
+        } else {
+            None
+        };
+
+        match skip {
+            Some(reason) => Step {
+                input,
+                output,
+                action: Action::Skip(reason),
+            },
+            None => plan_file(input, output),
+        }
+    });
+
+    Ok(steps.collect())
+}
+
+/// What a file looked like when it was converted, to tell whether it has
+/// changed since.
+///
+/// # Fields
+///
+/// * 'size' - The size of the file in bytes.
+/// * 'modified' - Its modification time, in nanoseconds since the Unix
+///   epoch, or 0 if the platform doesn't record one.
+/// * 'hash' - The CRC32 of its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stamp {
+    pub size: u64,
+    pub modified: u128,
+    pub hash: u32,
+}
+
+impl Stamp {
+    /// Reads the size and modification time of a file, and hashes its
+    /// contents.
+    ///
+    /// # Arguments
+    ///
+    /// * 'path' - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the Stamp or an io::Error if the file
+    /// can't be read.
+    pub fn of<P: AsRef<Path>>(path: P) -> Result<Self, io::Error> {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+
+        let mut crc = Crc32::new();
+        let mut buffer = [0; 1 << 16];
+        loop {
+            match file.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => crc.update(&buffer[..read]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(Self {
+            size: metadata.len(),
+            modified: modified(&metadata),
+            hash: crc.finish(),
+        })
+    }
+}
+
+/// Gets a file's modification time in nanoseconds since the Unix epoch.
+fn modified(metadata: &fs::Metadata) -> u128 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_nanos())
+}
+
+/// A record of the files a batch job has converted, kept in a file as it
+/// goes, so a job that was interrupted can carry on where it stopped rather
+/// than starting again. A line is appended as each file is finished, so the
+/// record survives however the job ends.
+///
+/// A file counts as done if it was converted with the same options, its
+/// output still exists and it's unchanged since: the same size and
+/// modification time, or failing that the same contents, so files that
+/// were only touched aren't converted again.
+///
+/// # Examples
+///
+/// '''
+/// let options = "png level=best reproducible";
+/// let mut manifest = Manifest::load("./converted/.chameleon-manifest", options)?;
+///
+/// for mut step in batch::plan_dir("./assets", "./converted", "png")? {
+///     manifest.resume(&mut step)?;
+///     if step.action != Action::Skip(batch::DONE) {
+///         let stamp = Stamp::of(&step.input)?;
+///         convert(&step)?;
+///         manifest.record(&step, stamp)?;
+///     }
+/// }
+/// '''
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    path: PathBuf,
+    options: String,
+    done: BTreeMap<String, Stamp>,
+}
+
+impl Manifest {
+    /// Reads the manifest kept at the given path, or starts an empty one if
+    /// there's no file there yet. Lines that can't be read, such as one cut
+    /// off when a job was killed, are ignored, as are files recorded with
+    /// other options.
+    ///
+    /// # Arguments
+    ///
+    /// * 'path' - Where the manifest is kept.
+    /// * 'options' - What the files are converted with, e.g. the format,
+    ///   compression level and flags, written the same way every
+    ///   run. Changing them converts every file again.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the Manifest or an io::Error if the file
+    /// exists but can't be read.
+    pub fn load<P: AsRef<Path>>(path: P, options: &str) -> Result<Self, io::Error> {
+        let path = path.as_ref().to_path_buf();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        // Laid out as the input's size, modification time and hash, then the
+        // options and the output path, escaped so they hold no tabs or
+        // newlines.
+        let done = text
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                let stamp = Stamp {
+                    size: fields.next()?.parse().ok()?,
+                    modified: fields.next()?.parse().ok()?,
+                    hash: u32::from_str_radix(fields.next()?, 16).ok()?,
+                };
+                if unescape(fields.next()?)? != options {
+                    return None;
+                }
+                let key = unescape(fields.next()?)?;
+
+                fields.next().is_none().then_some((key, stamp))
+            })
+            .collect();
+
+        Ok(Self {
+            path,
+            options: String::from(options),
+            done,
+        })
+    }
+    /// Whether a step's input was converted before and hasn't changed
+    /// since, with its output still there.
+    ///
+    /// # Arguments
+    ///
+    /// * 'step' - The step, as planned.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either whether the step is done, or an io::Error
+    /// if the input had to be hashed and couldn't be read.
+    pub fn is_done(&self, step: &Step) -> Result<bool, io::Error> {
+        let Some(stamp) = self.done.get(&self.key(step)) else {
+            return Ok(false);
+        };
+        if !step.output.exists() {
+            return Ok(false);
+        }
+
+        let metadata = fs::metadata(&step.input)?;
+        if metadata.len() != stamp.size {
+            return Ok(false);
+        }
+
+        Ok(modified(&metadata) == stamp.modified || Stamp::of(&step.input)?.hash == stamp.hash)
+    }
+    /// Marks a step to be skipped, with DONE as the reason, if it's done.
+    ///
+    /// # Arguments
+    ///
+    /// * 'step' - The step, as planned.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or an io::Error from Manifest::is_done.
+    pub fn resume(&self, step: &mut Step) -> Result<(), io::Error> {
+        if !matches!(step.action, Action::Skip(_)) && self.is_done(step)? {
+            step.action = Action::Skip(DONE);
+        }
+
+        Ok(())
+    }
+    /// Records that a step's input has been converted, appending it to the
+    /// manifest's file straight away.
+    ///
+    /// # Arguments
+    ///
+    /// * 'step' - The step that was just carried out.
+    /// * 'stamp' - The Stamp of the input, taken before it was converted,
+    ///   so a change made while converting isn't taken for what was
+    ///   converted.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or an io::Error if the manifest can't be written.
+    pub fn record(&mut self, step: &Step, stamp: Stamp) -> Result<(), io::Error> {
+        let key = self.key(step);
+        let line = format!(
+            "{}\t{}\t{:08x}\t{}\t{}\n",
+            stamp.size,
+            stamp.modified,
+            stamp.hash,
+            escape(&self.options),
+            escape(&key)
+        );
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        self.done.insert(key, stamp);
+
+        Ok(())
+    }
+    /// Gets what a step is recorded under, its output path relative to the
+    /// directory the manifest is in, so it's the same however the
+    /// directories were named on the command line.
+    fn key(&self, step: &Step) -> String {
+        let dir = self.path.parent().unwrap_or(Path::new(""));
+        let output = step.output.strip_prefix(dir).unwrap_or(&step.output);
+
+        String::from(output.to_string_lossy())
+    }
+}
+
+/// Escapes backslashes, tabs and line breaks, so a field of a manifest line
+/// can hold any text.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Undoes escape, or None if text holds an escape it never writes.
+fn unescape(text: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        unescaped.push(match c {
+            '\\' => match chars.next()? {
+                '\\' => '\\',
+                't' => '\t',
+                'n' => '\n',
+                'r' => '\r',
+                _ => return None,
+            },
+            c => c,
+        });
+    }
+
+    Some(unescaped)
+}
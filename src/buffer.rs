@@ -0,0 +1,83 @@
+use alloc::vec::Vec;
+
+/// What a buffer asked for from a BufferProvider is going to hold.
+///
+/// # Members
+///
+/// * 'Compressed' - The zlib stream gathered from every IDAT chunk. Given
+///   back to the provider once it has been decompressed.
+/// * 'Decompressed' - The filtered scanlines inflated from the zlib
+///   stream, every one for interlaced images, otherwise a window of
+///   the last few. Given back to the provider once they are
+///   unfiltered.
+/// * 'Pixels' - The decoded pixels. These are handed to the caller and
+///   never given back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BufferKind {
+    Compressed,
+    Decompressed,
+    Pixels,
+}
+
+/// Supplies the large buffers used while decoding, so embedders can take
+/// them from a pool, an arena, or memory shared with a GPU rather than the
+/// decoder allocating a fresh Vec each time. Small, short-lived allocations,
+/// like the Huffman tables, are still made by the decoder, see Scratch.
+///
+/// The capacity asked for is the size the decoder expects to need. Buffers
+/// may be returned with more, and are grown with the global allocator if
+/// they turn out to be too small.
+///
+/// # Examples
+///
+/// '''
+/// struct Pool(Mutex<Vec<Vec<u8>>>);
+///
+/// impl BufferProvider for Pool {
+///     fn allocate(&self, _kind: BufferKind, capacity: usize) -> Vec<u8> {
+///         let mut buffer = self.0.lock().unwrap().pop().unwrap_or_default();
+///         buffer.clear();
+///         buffer.reserve(capacity);
+///         buffer
+///     }
+///     fn release(&self, _kind: BufferKind, buffer: Vec<u8>) {
+///         self.0.lock().unwrap().push(buffer);
+///     }
+/// }
+///
+/// let rgb = png.rgb_bytes_with(&pool)?;
+/// '''
+pub trait BufferProvider {
+    /// Supplies a buffer for the decoder to fill.
+    ///
+    /// # Arguments
+    ///
+    /// * 'kind' - What the buffer is going to hold.
+    /// * 'capacity' - The number of bytes the decoder expects to write.
+    ///
+    /// # Returns
+    ///
+    /// A buffer, which the decoder clears before use.
+    fn allocate(&self, kind: BufferKind, capacity: usize) -> Vec<u8>;
+    /// Takes back a buffer the decoder has finished with. Does nothing by
+    /// default, which frees the buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * 'kind' - What the buffer held.
+    /// * 'buffer' - The buffer, with its contents left in place.
+    fn release(&self, kind: BufferKind, buffer: Vec<u8>) {
+        let _ = (kind, buffer);
+    }
+}
+
+/// The BufferProvider used when none is given, which allocates every buffer
+/// with the global allocator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DefaultBuffers;
+
+impl BufferProvider for DefaultBuffers {
+    fn allocate(&self, _kind: BufferKind, capacity: usize) -> Vec<u8> {
+        Vec::with_capacity(capacity)
+    }
+}
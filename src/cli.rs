@@ -1,17 +1,25 @@
-use std::env;
-use std::error::Error;
-use std::fmt::{self, Display};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::error::Error;
+use core::fmt::{self, Display};
 use std::path::PathBuf;
+use std::{env, eprintln, println};
+
+use crate::color;
+use crate::compression::deflate::{CompressionLevel, DeflateOptions, Strategy};
+use crate::generate::Pattern;
 
 /// Custom error type for command line errors.
 ///
 /// # Fields
 ///
 /// * 'MissingArgument' - Contains a static str for immediately returning
-///                 what argument is missing.
+///   what argument is missing.
 /// * 'InvalidArgument' - Contains a String for returning the InvalidArgument. A String
-///                 is used instead of a &'static str because it allows you to handle
-///                 the value once the string that cause the error goes out of scope.
+///   is used instead of a &'static str because it allows you to handle
+///   the value once the string that cause the error goes out of scope.
 ///
 /// # Examples
 ///
@@ -65,8 +73,35 @@ impl Error for CliError {}
 /// # Attributes
 ///
 /// * 'path' - A PathBuf containing the path to the image.
+/// * 'input_url' - The http:// or https:// URL given in place of a path,
+///   only accepted with the http feature.
+/// * 'label' - Text given with --label to stamp onto the top left corner of
+///   the output, '{time}' is replaced with the input's
+///   modification time.
+/// * 'font_path' - A BDF font given with --font to draw the label with, in
+///   place of the built in 6x10 font.
+/// * 'report_path' - Where the scan command writes its JSON report, given
+///   with --report.
+/// * 'pattern' - The Pattern, width and height the generate command draws,
+///   colored with --color, --cell and --seed.
+/// * 'trim' - The threshold given with --trim, the output being cropped to
+///   leave out margins the color of the top left pixel, see
+///   Image::trim.
+/// * 'chroma_key' - The color given with --chroma-key, pixels of exactly
+///   that color being made transparent in the output.
+/// * 'background' - The color given with --background, the output being
+///   drawn over it so it has no transparency left.
+/// * 'matte' - The color or checkerboard given with --matte, drawn under
+///   transparent images written to formats without alpha,
+///   see draw::flatten.
+/// * 'compression' - The level given with --level, none, fast, default or
+///   best, and the strategy given with --strategy, default,
+///   filtered, huffman-only or rle, that PNG output is
+///   compressed with.
+/// * 'format' - The extension given with --format, png or ppm, that the
+///   files of a directory are converted to. Defaults to png.
 /// * 'flags' - A vector containing the given flags for manipulating
-///                 the image.
+///   the image.
 ///
 /// # Examples
 ///
@@ -81,17 +116,65 @@ impl Error for CliError {}
 /// '''
 pub struct InputArguments {
     pub input_path: PathBuf,
+    pub input_url: Option<String>,
     pub output_path: Option<PathBuf>,
+    pub label: Option<String>,
+    pub font_path: Option<PathBuf>,
+    pub report_path: Option<PathBuf>,
+    pub pattern: Option<(Pattern, usize, usize)>,
+    pub trim: Option<u8>,
+    pub chroma_key: Option<[u8; 4]>,
+    pub background: Option<[u8; 4]>,
+    pub matte: Option<Pattern>,
+    pub compression: DeflateOptions,
+    pub format: Option<String>,
     pub flags: Vec<Flags>,
 }
 
 /// A enum containing possible flags for operating on
-/// images. Not yet implemented, but eventually the goal
-/// will be to support dithering to pallete, and more
-/// as the scope of this project inevitably increases.
-#[derive(Debug)]
+/// images. Eventually the goal will be to support dithering
+/// to pallete, and more as the scope of this project
+/// inevitably increases.
+///
+/// # Members
+///
+/// * 'Info' - Set by the info command. Prints a summary of the image rather
+///   than writing an output file.
+/// * 'Scan' - Set by the scan command. Checks the chunks of every PNG file
+///   in a directory tree without decoding them, see scan.
+/// * 'Verify' - Set by the verify command. Encodes the image again and
+///   checks it decodes to the same pixels, see roundtrip.
+/// * 'Fingerprint' - Set by the fingerprint command. Prints a hash of the
+///   decoded pixels, see Fingerprint.
+/// * 'Generate' - Set by the generate command. Draws a test pattern rather
+///   than reading an input, see generate.
+/// * 'Reproducible' - Set by --reproducible. The output file is the same
+///   every time it's built from the same input, any timestamp
+///   is zeroed to Time::EPOCH.
+/// * 'NoReduce' - Set by --no-reduce. PNG output keeps the RGB color type
+///   rather than the smallest one that holds the image.
+/// * 'KeepMetadata' - Set by --keep-metadata. The input's text, physical
+///   dimensions and EXIF data are written to the output, where
+///   its format can hold them, see Metadata.
+/// * 'DryRun' - Set by --dry-run. Prints which files a conversion would
+///   read, write, overwrite or skip, and why, without reading
+///   or writing any of them, see batch::plan_dir.
+/// * 'Resume' - Set by --resume. Converting a directory records each file
+///   finished in a manifest in the output directory, and skips
+///   files it lists whose input hasn't changed, see
+///   batch::Manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Flags {
-    NotYetImplemented,
+    Info,
+    Scan,
+    Verify,
+    Fingerprint,
+    Generate,
+    Reproducible,
+    NoReduce,
+    KeepMetadata,
+    DryRun,
+    Resume,
 }
 
 impl InputArguments {
@@ -105,9 +188,26 @@ impl InputArguments {
     pub fn build() -> Result<InputArguments, CliError> {
         let mut path_flags = InputArguments {
             input_path: PathBuf::new(),
+            input_url: None,
             output_path: None,
+            label: None,
+            font_path: None,
+            report_path: None,
+            pattern: None,
+            trim: None,
+            chroma_key: None,
+            background: None,
+            matte: None,
+            compression: DeflateOptions::new(),
+            format: None,
             flags: Vec::new(),
         };
+        // The generate command's pattern is only built once every option
+        // is read, as they may follow it.
+        let mut generate = None;
+        let mut colors = Vec::new();
+        let mut cell = 8;
+        let mut seed = 0;
 
         let mut args = env::args().skip(1).enumerate();
 
@@ -120,20 +220,88 @@ impl InputArguments {
         while let Some((i, arg)) = args.next() {
             match (i, arg.trim()) {
                 (_, "-i") | (_, "-input") => {
-                    let (_, input_path) = args.next().ok_or(CliError::MissingArgument(
+                    let (_, input) = args.next().ok_or(CliError::MissingArgument(
                         "Error: Missing input path. Is -input/-i followed by a valid path?",
                     ))?;
 
-                    let file_path = PathBuf::from(&input_path);
-                    match file_path.exists() {
-                        true => {
-                            path_flags.input_path = file_path;
-                        }
-                        false => {
-                            eprintln!("Invalid input path given. Does the path exist?");
-                            return Err(CliError::InvalidArgument(input_path));
-                        }
-                    };
+                    path_flags.set_input(input)?;
+                }
+                (0, "info") => {
+                    let (_, input) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing input path. Is info followed by a valid path or URL?",
+                    ))?;
+
+                    path_flags.set_input(input)?;
+                    path_flags.flags.push(Flags::Info);
+                }
+                (0, "scan") => {
+                    let (_, input) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing directory. Is scan followed by a valid path?",
+                    ))?;
+
+                    path_flags.set_input(input)?;
+                    path_flags.flags.push(Flags::Scan);
+                }
+                (0, "verify") => {
+                    let (_, input) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing input path. Is verify followed by a valid path?",
+                    ))?;
+
+                    path_flags.set_input(input)?;
+                    path_flags.flags.push(Flags::Verify);
+                }
+                (0, "fingerprint") => {
+                    let (_, input) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing input path. Is fingerprint followed by a valid path or URL?",
+                    ))?;
+
+                    path_flags.set_input(input)?;
+                    path_flags.flags.push(Flags::Fingerprint);
+                }
+                (0, "generate") => {
+                    let (_, name) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing pattern. Is generate followed by solid, gradient, checkerboard or noise?",
+                    ))?;
+                    let (_, size) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing size. Is the pattern followed by WIDTHxHEIGHT?",
+                    ))?;
+
+                    let (width, height) = size
+                        .split_once('x')
+                        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                        .ok_or(CliError::InvalidArgument(size))?;
+                    generate = Some((name, width, height));
+                    path_flags.flags.push(Flags::Generate);
+                }
+                (_, "-color") | (_, "--color") => {
+                    let (_, color) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing color, --color called without a hex color or name following.",
+                    ))?;
+
+                    colors.push(color::parse(&color).ok_or(CliError::InvalidArgument(color))?);
+                }
+                (_, "-cell") | (_, "--cell") => {
+                    let (_, size) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing cell size, --cell called without pixels following.",
+                    ))?;
+
+                    cell = size.parse().map_err(|_| CliError::InvalidArgument(size))?;
+                }
+                (_, "-seed") | (_, "--seed") => {
+                    let (_, number) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing seed, --seed called without a number following.",
+                    ))?;
+
+                    seed = number
+                        .parse()
+                        .map_err(|_| CliError::InvalidArgument(number))?;
+                }
+                (_, "-report") | (_, "--report") => {
+                    let (_, report_path) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing report path, --report called without path following.",
+                    ))?;
+
+                    path_flags.report_path = Some(PathBuf::from(report_path));
                 }
                 (_, "-o") | (_, "-out") | (_, "-output") => {
                     let (_, output_path) = args.next().ok_or(CliError::MissingArgument(
@@ -149,16 +317,156 @@ impl InputArguments {
                 (0, "usage") => usage(),
                 // Here flags will be implemented as added match arms appending Flags enum members
                 // to path_flags.flags.
+                (_, "-reproducible") | (_, "--reproducible") => {
+                    path_flags.flags.push(Flags::Reproducible);
+                }
+                (_, "-no-reduce") | (_, "--no-reduce") => {
+                    path_flags.flags.push(Flags::NoReduce);
+                }
+                (_, "-keep-metadata") | (_, "--keep-metadata") => {
+                    path_flags.flags.push(Flags::KeepMetadata);
+                }
+                (_, "-dry-run") | (_, "--dry-run") => {
+                    path_flags.flags.push(Flags::DryRun);
+                }
+                (_, "-resume") | (_, "--resume") => {
+                    path_flags.flags.push(Flags::Resume);
+                }
+                (_, "-format") | (_, "--format") => {
+                    let (_, format) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing format, --format called without png or ppm following.",
+                    ))?;
+
+                    match format.as_str() {
+                        "png" | "ppm" => path_flags.format = Some(format),
+                        _ => return Err(CliError::InvalidArgument(format)),
+                    }
+                }
+                (_, "-level") | (_, "--level") => {
+                    let (_, level) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing level, --level called without none, fast, default or best following.",
+                    ))?;
+
+                    path_flags.compression.level = match level.as_str() {
+                        "none" => CompressionLevel::None,
+                        "fast" => CompressionLevel::Fast,
+                        "default" => CompressionLevel::Default,
+                        "best" => CompressionLevel::Best,
+                        _ => return Err(CliError::InvalidArgument(level)),
+                    };
+                }
+                (_, "-strategy") | (_, "--strategy") => {
+                    let (_, strategy) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing strategy, --strategy called without default, filtered, huffman-only or rle following.",
+                    ))?;
+
+                    path_flags.compression.strategy = match strategy.as_str() {
+                        "default" => Strategy::Default,
+                        "filtered" => Strategy::Filtered,
+                        "huffman-only" => Strategy::HuffmanOnly,
+                        "rle" => Strategy::Rle,
+                        _ => return Err(CliError::InvalidArgument(strategy)),
+                    };
+                }
+                (_, "-trim") | (_, "--trim") => {
+                    let (_, threshold) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing threshold, --trim called without a number from 0 to 255 following.",
+                    ))?;
+
+                    path_flags.trim = Some(
+                        threshold
+                            .parse()
+                            .map_err(|_| CliError::InvalidArgument(threshold))?,
+                    );
+                }
+                (_, "-label") | (_, "--label") => {
+                    let (_, label) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing label text, --label called without text following.",
+                    ))?;
+
+                    path_flags.label = Some(label);
+                }
+                (_, "-chroma-key") | (_, "--chroma-key") => {
+                    let (_, color) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing color, --chroma-key called without a hex color or name following.",
+                    ))?;
+
+                    path_flags.chroma_key =
+                        Some(color::parse(&color).ok_or(CliError::InvalidArgument(color))?);
+                }
+                (_, "-background") | (_, "--background") => {
+                    let (_, color) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing color, --background called without a hex color or name following.",
+                    ))?;
+
+                    path_flags.background =
+                        Some(color::parse(&color).ok_or(CliError::InvalidArgument(color))?);
+                }
+                (_, "-matte") | (_, "--matte") => {
+                    let (_, matte) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing matte, --matte called without a color or checkerboard following.",
+                    ))?;
+
+                    path_flags.matte = Some(match matte.as_str() {
+                        // The light gray and white squares image editors show
+                        // transparency with.
+                        "checkerboard" => Pattern::Checkerboard {
+                            size: 8,
+                            colors: [[255, 255, 255, 255], [204, 204, 204, 255]],
+                        },
+                        _ => Pattern::Solid(
+                            color::parse(&matte).ok_or(CliError::InvalidArgument(matte))?,
+                        ),
+                    });
+                }
+                (_, "-font") | (_, "--font") => {
+                    let (_, font_path) = args.next().ok_or(CliError::MissingArgument(
+                        "Error: Missing font path, --font called without a BDF file following.",
+                    ))?;
+
+                    path_flags.font_path = Some(PathBuf::from(font_path));
+                }
                 (_, arg) => {
                     return Err(CliError::InvalidArgument(arg.to_string()));
                 }
             }
         }
-        if path_flags.output_path.is_none() && path_flags.input_path.exists() {
-            let output_path = match path_flags.input_path.clone().parent() {
+        if let Some((name, width, height)) = generate {
+            // Black to white unless colors are given.
+            let first = colors.first().copied().unwrap_or([0, 0, 0, 255]);
+            let second = colors.get(1).copied().unwrap_or([255, 255, 255, 255]);
+            let pattern = match name.as_str() {
+                "solid" => Pattern::Solid(first),
+                "gradient" => Pattern::Gradient(first, second),
+                "checkerboard" => Pattern::Checkerboard {
+                    size: cell,
+                    colors: [first, second],
+                },
+                "noise" => Pattern::Noise(seed),
+                _ => return Err(CliError::InvalidArgument(name)),
+            };
+
+            if path_flags.output_path.is_none() {
+                return Err(CliError::MissingArgument(
+                    "Error: Missing output path, generate needs -output to write to.",
+                ));
+            }
+            path_flags.pattern = Some((pattern, width, height));
+        }
+        if path_flags.output_path.is_none()
+            && path_flags.input_path.exists()
+            && !path_flags.flags.contains(&Flags::Info)
+            && !path_flags.flags.contains(&Flags::Scan)
+            && !path_flags.flags.contains(&Flags::Verify)
+            && !path_flags.flags.contains(&Flags::Fingerprint)
+        {
+            let mut output_path = match path_flags.input_path.clone().parent() {
                 Some(p) => p.join("output"),
                 None => PathBuf::from("output"),
             };
+            if let Some(extension) = path_flags.input_path.extension() {
+                output_path.set_extension(extension);
+            }
 
             eprintln!("Warning: No output path given, defaulted to the same directory and extension as the input file but with name 'output'.");
             path_flags.output_path = Some(output_path);
@@ -166,6 +474,41 @@ impl InputArguments {
 
         Ok(path_flags)
     }
+    /// Sets the image to read, either a path to a file that exists or, with
+    /// the http feature, an http:// or https:// URL.
+    ///
+    /// # Arguments
+    ///
+    /// * 'input' - The path or URL given on the command line.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or a CliError if the path doesn't exist or a URL was
+    /// given without the http feature.
+    fn set_input(&mut self, input: String) -> Result<(), CliError> {
+        if input.starts_with("http://") || input.starts_with("https://") {
+            if !cfg!(feature = "http") {
+                eprintln!("URLs need chameleon to be built with the http feature.");
+                return Err(CliError::InvalidArgument(input));
+            }
+
+            self.input_url = Some(input);
+            return Ok(());
+        }
+
+        let file_path = PathBuf::from(&input);
+        match file_path.exists() {
+            true => {
+                self.input_path = file_path;
+            }
+            false => {
+                eprintln!("Invalid input path given. Does the path exist?");
+                return Err(CliError::InvalidArgument(input));
+            }
+        };
+
+        Ok(())
+    }
 }
 
 pub fn usage() {
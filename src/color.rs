@@ -0,0 +1,387 @@
+//! Conversions between the sRGB color space, which 8 bit images are almost
+//! always stored in, and linear light, where blending and averaging colors
+//! behaves physically.
+//!
+//! sRGB spends more of its values on dark shades, as eyes are more sensitive
+//! to them, so mixing sRGB values directly comes out too dark. Convert to
+//! linear light, do the math there, and convert back:
+//!
+//! '''
+//! let mix = (color::srgb_to_linear(a) + color::srgb_to_linear(b)) / 2.0;
+//! let average = color::linear_to_srgb(mix);
+//! '''
+//!
+//! Colors given by people, on the command line or in a config file, are
+//! read with parse, which takes hex or CSS color names, and written back
+//! with Hex:
+//!
+//! '''
+//! let color = color::parse("rebeccapurple").ok_or("unknown color")?;
+//! assert_eq!(color::Hex(color).to_string(), "#663399");
+//! '''
+
+use core::fmt::{self, Display};
+
+/// Converts an 8 bit sRGB sample to linear light, with the transfer
+/// function from IEC 61966-2-1.
+///
+/// # Arguments
+///
+/// * 'value' - The sRGB sample, from 0 to 255.
+///
+/// # Returns
+///
+/// The intensity in linear light, from 0.0 to 1.0.
+pub fn srgb_to_linear(value: u8) -> f32 {
+    SRGB_TO_LINEAR[value as usize]
+}
+
+/// Converts an intensity in linear light back to an 8 bit sRGB sample,
+/// rounding to the nearest one.
+///
+/// # Arguments
+///
+/// * 'value' - The intensity in linear light, clamped to 0.0 to 1.0.
+///
+/// # Returns
+///
+/// The sRGB sample, from 0 to 255.
+pub fn linear_to_srgb(value: f32) -> u8 {
+    // Count the midpoints between neighbouring samples that value is past.
+    ROUNDING.partition_point(|&midpoint| midpoint <= value) as u8
+}
+
+/// Raises base to the power of exponent, for the gamma curves core has no
+/// powf for. Accurate to about 1e-12 over the 0.0 to 1.0 range samples
+/// fall in.
+///
+/// # Arguments
+///
+/// * 'base' - The value to raise, 0.0 or more.
+/// * 'exponent' - The power to raise it to.
+///
+/// # Returns
+///
+/// base to the power of exponent, 0.0 when base is 0.0 or less.
+pub const fn powf(base: f64, exponent: f64) -> f64 {
+    if base <= 0.0 {
+        return 0.0;
+    }
+
+    exp(exponent * ln(base))
+}
+
+/// Parses a color the way CSS writes them: #rgb, #rgba, #rrggbb or
+/// #rrggbbaa hex digits, or one of the named colors from CSS Color 4, like
+/// 'rebeccapurple' or 'transparent'. The '#' can be left off, and names and
+/// digits can be in any case.
+///
+/// # Arguments
+///
+/// * 'color' - The color as written.
+///
+/// # Returns
+///
+/// The red, green, blue and alpha of the color, opaque if no alpha was
+/// given, or None if it isn't a color.
+pub fn parse(color: &str) -> Option<[u8; 4]> {
+    let color = color.trim();
+    if let Ok(i) = NAMED.binary_search_by(|(name, _)| {
+        let name = name.bytes();
+        name.cmp(color.bytes().map(|byte| byte.to_ascii_lowercase()))
+    }) {
+        let [r, g, b] = NAMED[i].1;
+        return Some([r, g, b, 255]);
+    }
+    if color.eq_ignore_ascii_case("transparent") {
+        return Some([0; 4]);
+    }
+
+    let digits = color.strip_prefix('#').unwrap_or(color).as_bytes();
+    let mut rgba = [255; 4];
+    match digits.len() {
+        // Short forms repeat each digit, so 'f80' is 'ff8800'.
+        3 | 4 => {
+            for (sample, &digit) in rgba.iter_mut().zip(digits) {
+                *sample = hex_digit(digit)? * 0x11;
+            }
+        }
+        6 | 8 => {
+            for (sample, pair) in rgba.iter_mut().zip(digits.chunks_exact(2)) {
+                *sample = (hex_digit(pair[0])? << 4) | hex_digit(pair[1])?;
+            }
+        }
+        _ => return None,
+    }
+
+    Some(rgba)
+}
+
+/// Displays a color as hex the way parse reads it back, #rrggbb, with the
+/// alpha added on as #rrggbbaa when the color isn't opaque.
+///
+/// # Examples
+///
+/// '''
+/// println!("background {}", color::Hex([255, 136, 0, 255])); // #ff8800
+/// println!("shadow {}", color::Hex([0, 0, 0, 128])); // #00000080
+/// '''
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hex(pub [u8; 4]);
+
+impl Display for Hex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b, a] = self.0;
+        write!(f, "#{r:02x}{g:02x}{b:02x}")?;
+        match a {
+            255 => Ok(()),
+            _ => write!(f, "{a:02x}"),
+        }
+    }
+}
+
+/// The value of a single hex digit.
+fn hex_digit(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+/// The natural logarithm of a positive, finite value.
+const fn ln(value: f64) -> f64 {
+    // Split value into mantissa * 2^exponent, with the mantissa from 1 to 2.
+    let bits = value.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i64 - 1023;
+    let mantissa = f64::from_bits((bits & !(0x7ff << 52)) | (1023 << 52));
+
+    // ln(m) = 2 atanh((m - 1) / (m + 1)), where the series converges fast as
+    // the ratio is at most 1/3.
+    let ratio = (mantissa - 1.0) / (mantissa + 1.0);
+    let square = ratio * ratio;
+    let mut term = ratio;
+    let mut sum = 0.0;
+    let mut n = 1;
+    while n < 40 {
+        sum += term / n as f64;
+        term *= square;
+        n += 2;
+    }
+
+    2.0 * sum + exponent as f64 * core::f64::consts::LN_2
+}
+
+/// e raised to the power of value.
+const fn exp(value: f64) -> f64 {
+    // Too small for a normal f64.
+    if value < -708.0 {
+        return 0.0;
+    }
+
+    // e^v = 2^k * e^r, with k the nearest whole number to v / ln(2), so r
+    // is small enough for the Taylor series.
+    let nearest = value / core::f64::consts::LN_2 + 0.5;
+    let mut k = nearest as i64;
+    if k as f64 > nearest {
+        k -= 1;
+    }
+    if k > 1023 {
+        k = 1023;
+    }
+    let r = value - k as f64 * core::f64::consts::LN_2;
+
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    let mut n = 1;
+    while n < 20 {
+        term *= r / n as f64;
+        sum += term;
+        n += 1;
+    }
+
+    sum * f64::from_bits(((k + 1023) as u64) << 52)
+}
+
+/// The linear intensity of each 8 bit sRGB sample.
+const SRGB_TO_LINEAR: [f32; 256] = {
+    let mut table = [0.0; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = decode(i as f64 / 255.0) as f32;
+        i += 1;
+    }
+    table
+};
+
+/// The linear intensity half way between each pair of neighbouring sRGB
+/// samples, (i + 0.5) / 255 in sRGB.
+const ROUNDING: [f32; 255] = {
+    let mut table = [0.0; 255];
+    let mut i = 0;
+    while i < 255 {
+        table[i] = decode((i as f64 + 0.5) / 255.0) as f32;
+        i += 1;
+    }
+    table
+};
+
+/// The sRGB transfer function from IEC 61966-2-1, taking a sample from
+/// 0.0 to 1.0 to linear light. Only used to build the tables above at
+/// compile time.
+const fn decode(value: f64) -> f64 {
+    match value <= 0.04045 {
+        true => value / 12.92,
+        false => powf((value + 0.055) / 1.055, 2.4),
+    }
+}
+
+/// The named colors of CSS Color 4 other than transparent, sorted so they
+/// can be binary searched.
+const NAMED: [(&str, [u8; 3]); 148] = [
+    ("aliceblue", [0xf0, 0xf8, 0xff]),
+    ("antiquewhite", [0xfa, 0xeb, 0xd7]),
+    ("aqua", [0x00, 0xff, 0xff]),
+    ("aquamarine", [0x7f, 0xff, 0xd4]),
+    ("azure", [0xf0, 0xff, 0xff]),
+    ("beige", [0xf5, 0xf5, 0xdc]),
+    ("bisque", [0xff, 0xe4, 0xc4]),
+    ("black", [0x00, 0x00, 0x00]),
+    ("blanchedalmond", [0xff, 0xeb, 0xcd]),
+    ("blue", [0x00, 0x00, 0xff]),
+    ("blueviolet", [0x8a, 0x2b, 0xe2]),
+    ("brown", [0xa5, 0x2a, 0x2a]),
+    ("burlywood", [0xde, 0xb8, 0x87]),
+    ("cadetblue", [0x5f, 0x9e, 0xa0]),
+    ("chartreuse", [0x7f, 0xff, 0x00]),
+    ("chocolate", [0xd2, 0x69, 0x1e]),
+    ("coral", [0xff, 0x7f, 0x50]),
+    ("cornflowerblue", [0x64, 0x95, 0xed]),
+    ("cornsilk", [0xff, 0xf8, 0xdc]),
+    ("crimson", [0xdc, 0x14, 0x3c]),
+    ("cyan", [0x00, 0xff, 0xff]),
+    ("darkblue", [0x00, 0x00, 0x8b]),
+    ("darkcyan", [0x00, 0x8b, 0x8b]),
+    ("darkgoldenrod", [0xb8, 0x86, 0x0b]),
+    ("darkgray", [0xa9, 0xa9, 0xa9]),
+    ("darkgreen", [0x00, 0x64, 0x00]),
+    ("darkgrey", [0xa9, 0xa9, 0xa9]),
+    ("darkkhaki", [0xbd, 0xb7, 0x6b]),
+    ("darkmagenta", [0x8b, 0x00, 0x8b]),
+    ("darkolivegreen", [0x55, 0x6b, 0x2f]),
+    ("darkorange", [0xff, 0x8c, 0x00]),
+    ("darkorchid", [0x99, 0x32, 0xcc]),
+    ("darkred", [0x8b, 0x00, 0x00]),
+    ("darksalmon", [0xe9, 0x96, 0x7a]),
+    ("darkseagreen", [0x8f, 0xbc, 0x8f]),
+    ("darkslateblue", [0x48, 0x3d, 0x8b]),
+    ("darkslategray", [0x2f, 0x4f, 0x4f]),
+    ("darkslategrey", [0x2f, 0x4f, 0x4f]),
+    ("darkturquoise", [0x00, 0xce, 0xd1]),
+    ("darkviolet", [0x94, 0x00, 0xd3]),
+    ("deeppink", [0xff, 0x14, 0x93]),
+    ("deepskyblue", [0x00, 0xbf, 0xff]),
+    ("dimgray", [0x69, 0x69, 0x69]),
+    ("dimgrey", [0x69, 0x69, 0x69]),
+    ("dodgerblue", [0x1e, 0x90, 0xff]),
+    ("firebrick", [0xb2, 0x22, 0x22]),
+    ("floralwhite", [0xff, 0xfa, 0xf0]),
+    ("forestgreen", [0x22, 0x8b, 0x22]),
+    ("fuchsia", [0xff, 0x00, 0xff]),
+    ("gainsboro", [0xdc, 0xdc, 0xdc]),
+    ("ghostwhite", [0xf8, 0xf8, 0xff]),
+    ("gold", [0xff, 0xd7, 0x00]),
+    ("goldenrod", [0xda, 0xa5, 0x20]),
+    ("gray", [0x80, 0x80, 0x80]),
+    ("green", [0x00, 0x80, 0x00]),
+    ("greenyellow", [0xad, 0xff, 0x2f]),
+    ("grey", [0x80, 0x80, 0x80]),
+    ("honeydew", [0xf0, 0xff, 0xf0]),
+    ("hotpink", [0xff, 0x69, 0xb4]),
+    ("indianred", [0xcd, 0x5c, 0x5c]),
+    ("indigo", [0x4b, 0x00, 0x82]),
+    ("ivory", [0xff, 0xff, 0xf0]),
+    ("khaki", [0xf0, 0xe6, 0x8c]),
+    ("lavender", [0xe6, 0xe6, 0xfa]),
+    ("lavenderblush", [0xff, 0xf0, 0xf5]),
+    ("lawngreen", [0x7c, 0xfc, 0x00]),
+    ("lemonchiffon", [0xff, 0xfa, 0xcd]),
+    ("lightblue", [0xad, 0xd8, 0xe6]),
+    ("lightcoral", [0xf0, 0x80, 0x80]),
+    ("lightcyan", [0xe0, 0xff, 0xff]),
+    ("lightgoldenrodyellow", [0xfa, 0xfa, 0xd2]),
+    ("lightgray", [0xd3, 0xd3, 0xd3]),
+    ("lightgreen", [0x90, 0xee, 0x90]),
+    ("lightgrey", [0xd3, 0xd3, 0xd3]),
+    ("lightpink", [0xff, 0xb6, 0xc1]),
+    ("lightsalmon", [0xff, 0xa0, 0x7a]),
+    ("lightseagreen", [0x20, 0xb2, 0xaa]),
+    ("lightskyblue", [0x87, 0xce, 0xfa]),
+    ("lightslategray", [0x77, 0x88, 0x99]),
+    ("lightslategrey", [0x77, 0x88, 0x99]),
+    ("lightsteelblue", [0xb0, 0xc4, 0xde]),
+    ("lightyellow", [0xff, 0xff, 0xe0]),
+    ("lime", [0x00, 0xff, 0x00]),
+    ("limegreen", [0x32, 0xcd, 0x32]),
+    ("linen", [0xfa, 0xf0, 0xe6]),
+    ("magenta", [0xff, 0x00, 0xff]),
+    ("maroon", [0x80, 0x00, 0x00]),
+    ("mediumaquamarine", [0x66, 0xcd, 0xaa]),
+    ("mediumblue", [0x00, 0x00, 0xcd]),
+    ("mediumorchid", [0xba, 0x55, 0xd3]),
+    ("mediumpurple", [0x93, 0x70, 0xdb]),
+    ("mediumseagreen", [0x3c, 0xb3, 0x71]),
+    ("mediumslateblue", [0x7b, 0x68, 0xee]),
+    ("mediumspringgreen", [0x00, 0xfa, 0x9a]),
+    ("mediumturquoise", [0x48, 0xd1, 0xcc]),
+    ("mediumvioletred", [0xc7, 0x15, 0x85]),
+    ("midnightblue", [0x19, 0x19, 0x70]),
+    ("mintcream", [0xf5, 0xff, 0xfa]),
+    ("mistyrose", [0xff, 0xe4, 0xe1]),
+    ("moccasin", [0xff, 0xe4, 0xb5]),
+    ("navajowhite", [0xff, 0xde, 0xad]),
+    ("navy", [0x00, 0x00, 0x80]),
+    ("oldlace", [0xfd, 0xf5, 0xe6]),
+    ("olive", [0x80, 0x80, 0x00]),
+    ("olivedrab", [0x6b, 0x8e, 0x23]),
+    ("orange", [0xff, 0xa5, 0x00]),
+    ("orangered", [0xff, 0x45, 0x00]),
+    ("orchid", [0xda, 0x70, 0xd6]),
+    ("palegoldenrod", [0xee, 0xe8, 0xaa]),
+    ("palegreen", [0x98, 0xfb, 0x98]),
+    ("paleturquoise", [0xaf, 0xee, 0xee]),
+    ("palevioletred", [0xdb, 0x70, 0x93]),
+    ("papayawhip", [0xff, 0xef, 0xd5]),
+    ("peachpuff", [0xff, 0xda, 0xb9]),
+    ("peru", [0xcd, 0x85, 0x3f]),
+    ("pink", [0xff, 0xc0, 0xcb]),
+    ("plum", [0xdd, 0xa0, 0xdd]),
+    ("powderblue", [0xb0, 0xe0, 0xe6]),
+    ("purple", [0x80, 0x00, 0x80]),
+    ("rebeccapurple", [0x66, 0x33, 0x99]),
+    ("red", [0xff, 0x00, 0x00]),
+    ("rosybrown", [0xbc, 0x8f, 0x8f]),
+    ("royalblue", [0x41, 0x69, 0xe1]),
+    ("saddlebrown", [0x8b, 0x45, 0x13]),
+    ("salmon", [0xfa, 0x80, 0x72]),
+    ("sandybrown", [0xf4, 0xa4, 0x60]),
+    ("seagreen", [0x2e, 0x8b, 0x57]),
+    ("seashell", [0xff, 0xf5, 0xee]),
+    ("sienna", [0xa0, 0x52, 0x2d]),
+    ("silver", [0xc0, 0xc0, 0xc0]),
+    ("skyblue", [0x87, 0xce, 0xeb]),
+    ("slateblue", [0x6a, 0x5a, 0xcd]),
+    ("slategray", [0x70, 0x80, 0x90]),
+    ("slategrey", [0x70, 0x80, 0x90]),
+    ("snow", [0xff, 0xfa, 0xfa]),
+    ("springgreen", [0x00, 0xff, 0x7f]),
+    ("steelblue", [0x46, 0x82, 0xb4]),
+    ("tan", [0xd2, 0xb4, 0x8c]),
+    ("teal", [0x00, 0x80, 0x80]),
+    ("thistle", [0xd8, 0xbf, 0xd8]),
+    ("tomato", [0xff, 0x63, 0x47]),
+    ("turquoise", [0x40, 0xe0, 0xd0]),
+    ("violet", [0xee, 0x82, 0xee]),
+    ("wheat", [0xf5, 0xde, 0xb3]),
+    ("white", [0xff, 0xff, 0xff]),
+    ("whitesmoke", [0xf5, 0xf5, 0xf5]),
+    ("yellow", [0xff, 0xff, 0x00]),
+    ("yellowgreen", [0x9a, 0xcd, 0x32]),
+];
@@ -0,0 +1,226 @@
+//! The zlib compression the PNG encoder and decoder go through, so it can be
+//! swapped for another implementation, a faster one or a reference codec to
+//! test against. Native, chameleon's own pure Rust implementation, is used
+//! unless another is given to DecodeOptions::decompressor or
+//! EncodeOptions::compressor. With the zlib feature, Flate2 backs both with
+//! the flate2 crate.
+//!
+//! Backends are passed as &'static references, as they're usually unit
+//! structs, so options holding them stay cheap to copy.
+//!
+//! '''
+//! let png = Png::decoder().decompressor(&Flate2).decode("./in.png")?;
+//! let bytes = image.encode_png(&EncodeOptions::new().compressor(&Flate2))?;
+//! '''
+
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use super::{deflate::DeflateOptions, zlib::ZlibStream};
+use crate::error::Error;
+
+/// Turns data into a zlib stream, see RFC 1950.
+pub trait Compressor: Debug + Send + Sync {
+    /// Compresses data.
+    ///
+    /// # Arguments
+    ///
+    /// * 'data' - The bytes to compress.
+    ///
+    /// # Returns
+    ///
+    /// The zlib stream, header and adler32 included.
+    fn compress(&self, data: &[u8]) -> Vec<u8>;
+    /// Compresses data at a level and with a strategy. Backends that can't
+    /// be tuned leave this as compress.
+    ///
+    /// # Arguments
+    ///
+    /// * 'data' - The bytes to compress.
+    /// * 'options' - The level and strategy to compress with.
+    ///
+    /// # Returns
+    ///
+    /// The zlib stream, header and adler32 included.
+    fn compress_with(&self, data: &[u8], options: &DeflateOptions) -> Vec<u8> {
+        let _ = options;
+        self.compress(data)
+    }
+}
+
+/// Inflates a zlib stream, see RFC 1950.
+pub trait Decompressor: Debug + Send + Sync {
+    /// Decompresses a stream a piece at a time, handing each piece to each
+    /// in order. How large the pieces are is up to the backend.
+    ///
+    /// # Arguments
+    ///
+    /// * 'stream' - The whole zlib stream.
+    /// * 'checksum' - Whether to check the adler32 at the end of the stream.
+    /// * 'check_window' - Whether a match copying from further back than
+    ///   the window the header declares is an error. Native checks it,
+    ///   Flate2 never does, so it reads the stream either way.
+    /// * 'buffer' - A buffer the backend may hold its output in, see
+    ///   BufferProvider.
+    /// * 'each' - Called with each piece of the output. Its first error
+    ///   stops decompression and is returned.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the buffer, which is given back to the
+    /// BufferProvider, or a DecompressError.
+    fn decompress(
+        &self,
+        stream: &[u8],
+        checksum: bool,
+        check_window: bool,
+        buffer: Vec<u8>,
+        each: &mut dyn FnMut(&[u8]) -> Result<(), Error>,
+    ) -> Result<Vec<u8>, DecompressError>;
+}
+
+/// Why a Decompressor stopped.
+///
+/// # Fields
+///
+/// * 'error' - The error, from the stream itself or from the callback.
+/// * 'bit' - How far into the stream, in bits, the backend got before a
+///   problem with the stream stopped it, if it can tell.
+#[derive(Debug)]
+pub struct DecompressError {
+    pub error: Error,
+    pub bit: Option<usize>,
+}
+
+impl From<Error> for DecompressError {
+    fn from(error: Error) -> Self {
+        Self { error, bit: None }
+    }
+}
+
+/// Chameleon's own zlib implementation. Inflating reads the whole stream
+/// and hands on output once it's past the 32K window, and compressing
+/// writes whichever of dynamic Huffman, fixed Huffman or stored blocks is
+/// smallest, see ZlibStream::compress_with. A match copying from further
+/// back than the window the header declares is an error unless the window
+/// isn't being checked, see ZlibStream::ignore_window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Native;
+
+impl Compressor for Native {
+    fn compress(&self, data: &[u8]) -> Vec<u8> {
+        ZlibStream::compress(data)
+    }
+    fn compress_with(&self, data: &[u8], options: &DeflateOptions) -> Vec<u8> {
+        ZlibStream::compress_with(data, options)
+    }
+}
+
+impl Decompressor for Native {
+    fn decompress(
+        &self,
+        stream: &[u8],
+        checksum: bool,
+        check_window: bool,
+        buffer: Vec<u8>,
+        each: &mut dyn FnMut(&[u8]) -> Result<(), Error>,
+    ) -> Result<Vec<u8>, DecompressError> {
+        let mut zlib = ZlibStream::build(stream).map_err(Error::from)?;
+        if !check_window {
+            zlib.ignore_window();
+        }
+
+        let result = match checksum {
+            true => zlib.decompress_with(buffer, each),
+            false => zlib.deflate.decompress_with(buffer, each),
+        };
+        result.map_err(|error| {
+            let bit = match error {
+                Error::Deflate(_) => {
+                    Some(zlib.header.end_idx * 8 + zlib.deflate.bitstream.position())
+                }
+                _ => None,
+            };
+            DecompressError { error, bit }
+        })
+    }
+}
+
+/// The flate2 crate, with whichever of its backends is enabled, its pure
+/// Rust miniz_oxide by default or the system zlib with flate2's zlib
+/// feature. flate2 takes a level but not a strategy, so the strategy in
+/// DeflateOptions is ignored, and it doesn't check matches against the
+/// window the header declares, so streams that declare too small a window
+/// are read whether or not the window is checked.
+#[cfg(feature = "zlib")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Flate2;
+
+#[cfg(feature = "zlib")]
+impl Compressor for Flate2 {
+    fn compress(&self, data: &[u8]) -> Vec<u8> {
+        self.compress_with(data, &DeflateOptions::new())
+    }
+    fn compress_with(&self, data: &[u8], options: &DeflateOptions) -> Vec<u8> {
+        use std::io::Write;
+
+        let level = flate2::Compression::new(options.level.zlib_level());
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), level);
+        // Writing to a Vec can't fail.
+        let _ = encoder.write_all(data);
+        encoder.finish().unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "zlib")]
+impl Decompressor for Flate2 {
+    fn decompress(
+        &self,
+        stream: &[u8],
+        checksum: bool,
+        check_window: bool,
+        mut buffer: Vec<u8>,
+        each: &mut dyn FnMut(&[u8]) -> Result<(), Error>,
+    ) -> Result<Vec<u8>, DecompressError> {
+        use flate2::{Decompress, FlushDecompress, Status};
+        use std::io;
+
+        // flate2 lets matches reach back 32K whatever the header declares.
+        let _ = check_window;
+
+        // flate2 always checks the adler32, the raw deflate data is
+        // inflated instead when it shouldn't be.
+        let (mut inflater, mut input) = match checksum {
+            true => (Decompress::new(true), stream),
+            false => (Decompress::new(false), stream.get(2..).unwrap_or_default()),
+        };
+        // Bits are counted from the start of stream, header and all.
+        let skipped = stream.len() - input.len();
+        buffer.clear();
+        buffer.reserve(32 * 1024);
+
+        loop {
+            let before = inflater.total_in();
+            buffer.clear();
+            let status = inflater
+                .decompress_vec(input, &mut buffer, FlushDecompress::None)
+                .map_err(|e| DecompressError {
+                    error: Error::Io(io::Error::new(io::ErrorKind::InvalidData, e)),
+                    bit: Some((skipped + inflater.total_in() as usize) * 8),
+                })?;
+            input = &input[(inflater.total_in() - before) as usize..];
+            each(&buffer)?;
+
+            match status {
+                Status::StreamEnd => return Ok(buffer),
+                _ if input.is_empty() && buffer.is_empty() => {
+                    return Err(DecompressError {
+                        error: Error::Io(io::ErrorKind::UnexpectedEof.into()),
+                        bit: Some((skipped + inflater.total_in() as usize) * 8),
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -1,212 +1,164 @@
-use std::{error::Error, fmt::Display};
+//! Reads a byte buffer a few bits at a time, in the order deflate packs
+//! them, see 3.1.1 of RFC 1951. Bits are taken from each byte starting
+//! with the least significant, and a field of several bits is either stored
+//! with its least significant bit first, like lengths and extra bits, or
+//! with its most significant bit first, like Huffman codes.
 
-#[derive(Debug)]
-pub enum BitVecError {
-    OutOfBounds(usize),
-}
-
-impl Display for BitVecError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            BitVecError::OutOfBounds(len) => {
-                write!(f, "Error: Given value is out of bounds: {}", len)
-            }
-        }
-    }
-}
-
-impl Error for BitVecError {}
+use alloc::vec::Vec;
 
-/// A struct for storing bits in u64 buffers. Will waste memory, as even a bit
-/// vector of 1 bit takes up a whole u64, however, the vector won't need to be
-/// reallocated as often.
+/// A reader over the bits of a byte buffer, with no limit on its length
+/// other than the buffer's. Nothing is read past the end: reads that would
+/// go past it return None and leave the position where it was.
 ///
 /// # Fields
 ///
-/// * 'buffer' - A vector of u64 values acting as a bit buffer.
-/// * 'len' - A usize value containing the length of the bit vector.
-/// * 'idx' - A usize value representing the current index in the buffer.
-///         len % 64 the index within the current u64.
-#[derive(Debug, Clone)]
-pub struct BitVector64 {
-    pub buffer: Vec<u64>,
-    pub len: usize,
-    pub idx: usize,
+/// * 'bytes' - The bytes being read.
+/// * 'position' - The number of bits read so far.
+///
+/// # Examples
+///
+/// '''
+/// let mut reader = BitReader::new(&[0b1010_1101, 0xff]);
+///
+/// assert_eq!(reader.read_bits_lsb(3), Some(0b101));
+/// assert_eq!(reader.read_bits_msb(2), Some(0b10));
+/// reader.align_to_byte();
+/// assert_eq!(reader.read_bytes(1), Some(&[0xff][..]));
+/// assert_eq!(reader.remaining(), 0);
+/// '''
+#[derive(Debug, Clone, Default)]
+pub struct BitReader {
+    bytes: Vec<u8>,
+    position: usize,
 }
 
-impl BitVector64 {
-    /// Creates a new empty BitVector64.
+impl BitReader {
+    /// Creates a BitReader at the start of the given bytes.
     ///
-    /// # Returns
+    /// # Arguments
     ///
-    /// A BitVector64 with default values.
-    pub fn new() -> Self {
+    /// * 'bytes' - The bytes to read, copied into the reader.
+    pub fn new(bytes: &[u8]) -> Self {
         Self {
-            buffer: vec![0],
-            len: 0,
-            idx: 0,
+            bytes: bytes.to_vec(),
+            position: 0,
         }
     }
-    /// Pushes the len least significant bits of the given buffer to the
-    /// BitVector64.
+    /// Gets the number of bits read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+    /// Gets the total number of bits in the reader.
+    pub fn len(&self) -> usize {
+        self.bytes.len() * 8
+    }
+    /// Checks whether the reader has no bits at all.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+    /// Gets the number of bits left to read.
+    pub fn remaining(&self) -> usize {
+        self.len() - self.position
+    }
+    /// Gets the next count bits without moving past them, for decoding a
+    /// Huffman code whose length isn't known until it's been looked up.
     ///
     /// # Arguments
     ///
-    /// * 'buffer' - The bit buffer to push the bits from.
-    /// * 'len' - The number of bits to push.
+    /// * 'count' - The number of bits to get, at most 32.
     ///
     /// # Returns
     ///
-    /// A result containing either a unit value, or a BitVecError. Returns
-    /// an error if the len is longer than 64, because it takes a u64 buffer.
-    pub fn push_buffer(&mut self, buffer: u64, len: usize) -> Result<(), BitVecError> {
-        // Check if len is out of bounds.
-        if len > 64 {
-            return Err(BitVecError::OutOfBounds(len));
+    /// The bits, the first one in the least significant place. Any past the
+    /// end of the buffer are 0.
+    #[inline]
+    pub fn peek_bits(&self, count: u8) -> u32 {
+        let count = (count as usize).min(32).min(self.remaining());
+        if count == 0 {
+            return 0;
         }
-        let bit_idx = self.len % 64;
-        let buf_idx = self.buffer.len() - 1;
-        self.len += len;
 
-        let shifted_buffer = buffer << (64 - len);
+        // 32 bits starting partway through a byte span at most 5 bytes.
+        let start = self.position / 8;
+        let end = (start + 8).min(self.bytes.len());
+        let mut word = [0; 8];
+        word[..end - start].copy_from_slice(&self.bytes[start..end]);
 
-        if (bit_idx + len) >= 64 {
-            self.buffer.push(0);
-            self.buffer[buf_idx] |= shifted_buffer >> bit_idx;
-            self.buffer[buf_idx + 1] = shifted_buffer << (64 - bit_idx);
-        } else {
-            self.buffer[buf_idx] |= shifted_buffer >> bit_idx;
-        }
-
-        Ok(())
+        let bits = u64::from_le_bytes(word) >> (self.position % 8);
+        (bits & ((1 << count) - 1)) as u32
     }
-    /// Accepts a u8 representation of a bit, and pushes that bit to the
-    /// vector. Reallocates the vector once the current vector is full,
-    /// not once called while the current vector is full.
+    /// Reads count bits stored least significant first, the way deflate
+    /// stores everything but Huffman codes.
     ///
     /// # Arguments
     ///
-    /// * 'bit' - A single u8 either 1 or 0 representing the bit to push.
+    /// * 'count' - The number of bits to read, at most 32.
     ///
     /// # Returns
     ///
-    /// Returns nothing, or an error if bit is neither 0 or 1.
+    /// The bits, or None if there aren't count left.
     #[inline]
-    pub fn push_bit(&mut self, bit: u8) -> Result<(), BitVecError> {
-        let bit_idx = self.len % 64;
-        let buf_idx = self.buffer.len() - 1;
-
-        if bit_idx >= 63 {
-            self.buffer.push(0);
-        }
-
-        match bit {
-            0 => {
-                self.len += 1;
-            }
-            1 => {
-                self.buffer[buf_idx] |= 1 << (63 - bit_idx);
-                self.len += 1;
-            }
-            _ => {
-                return Err(BitVecError::OutOfBounds(bit as usize));
-            }
-        }
-        Ok(())
+    pub fn read_bits_lsb(&mut self, count: u8) -> Option<u32> {
+        let bits = self.peek_bits(count);
+        self.skip(count as usize).then_some(bits)
     }
-    /// Builds a BitVector64 from a byte-aligned big-endian byte array.
+    /// Reads count bits stored most significant first, the way deflate
+    /// stores Huffman codes.
     ///
     /// # Arguments
     ///
-    /// * 'raw' - The byte array to build from.
+    /// * 'count' - The number of bits to read, at most 32.
     ///
     /// # Returns
     ///
-    /// The constructed bit vector, with the first element being the least
-    /// significant bit of the first byte, and the last element being the
-    /// most significant bit of the last byte.
-    pub fn from_be_bytes(raw: &[u8]) -> Self {
-        let mut bit_vector = BitVector64::new();
-
-        for byte in raw.iter().map(|x| x.reverse_bits()) {
-            // Will never panic because push_buffer only returns an error if
-            // len is more than 64.
-            bit_vector.push_buffer(byte as u64, 8).unwrap();
+    /// The bits, or None if there aren't count left.
+    pub fn read_bits_msb(&mut self, count: u8) -> Option<u32> {
+        match count {
+            0 => Some(0),
+            _ => Some(self.read_bits_lsb(count)?.reverse_bits() >> (32 - count as u32)),
         }
-
-        bit_vector
     }
-    /// Builds a BitVector64 from a byte-aligned little-endian byte array.
+    /// Moves past count bits.
     ///
     /// # Arguments
     ///
-    /// * 'raw' - The byte array to build from.
+    /// * 'count' - The number of bits to skip.
     ///
     /// # Returns
     ///
-    /// The constructed bit vector, with the first element being the most
-    /// significant bit of the first byte, and the last being the least
-    /// significant bit from the last byte.
-    pub fn from_le_bytes(raw: &[u8]) -> Self {
-        let mut bit_vector = BitVector64::new();
-
-        for byte in raw {
-            bit_vector.push_buffer(byte.to_owned() as u64, 8).unwrap();
+    /// Whether there were count bits left. If not, nothing is skipped.
+    #[inline]
+    pub fn skip(&mut self, count: usize) -> bool {
+        let enough = count <= self.remaining();
+        if enough {
+            self.position += count;
         }
-
-        bit_vector
+        enough
+    }
+    /// Moves past whatever is left of the current byte, for the byte
+    /// aligned data in stored blocks.
+    pub fn align_to_byte(&mut self) {
+        self.position = self.position.next_multiple_of(8).min(self.len());
     }
-    /// Removes the first bit in the stream and returns it. Only reallocates
-    /// the vector once the current bit buffer is empty.
+    /// Reads len whole bytes. The reader has to be at the start of a byte,
+    /// see align_to_byte.
+    ///
+    /// # Arguments
+    ///
+    /// * 'len' - The number of bytes to read.
     ///
     /// # Returns
     ///
-    /// An option containing either a u8 either 0 or 1, or None.
-    pub fn pop_front(&mut self) -> Option<u8> {
-        if self.len == 0 {
-            None
-        } else if self.idx < 64 {
-            let value = ((self.buffer[0] & (1u64 << (63 - self.idx))) >> (63 - self.idx)) as u8;
-            self.buffer[0] &= !(1u64 << (63 - self.idx));
-            self.len -= 1;
-            self.idx += 1;
-            Some(value)
-        } else {
-            self.buffer.remove(0);
-            self.idx = 0;
-            self.pop_front()
+    /// The bytes, or None if the reader isn't at the start of a byte or
+    /// there aren't len left.
+    pub fn read_bytes(&mut self, len: usize) -> Option<&[u8]> {
+        if !self.position.is_multiple_of(8) {
+            return None;
         }
-    }
-}
-
-impl Default for BitVector64 {
-    fn default() -> Self {
-        Self::new()
-    }
-}
 
-impl Iterator for BitVector64 {
-    type Item = u8;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.idx < self.len {
-            let byte_idx = self.idx / 64;
-            let bit_idx = 63 - (self.idx % 64);
-            let current_byte = self.buffer[byte_idx];
-            let bit = (current_byte >> bit_idx) & 1;
-            self.idx += 1;
-            Some(bit as u8)
-        } else {
-            None
-        }
-    }
-}
-impl Display for BitVector64 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let iter = self.clone();
-        for bit in iter {
-            write!(f, "{}", bit)?;
-        }
-        Ok(())
+        let start = self.position / 8;
+        let bytes = self.bytes.get(start..start.checked_add(len)?)?;
+        self.position += len * 8;
+        Some(bytes)
     }
 }
@@ -0,0 +1,36 @@
+/// A running adler32 checksum, for data that arrives a piece at a time, see
+/// 8.2 of RFC 1950.
+#[derive(Debug, Clone, Copy)]
+pub struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    pub fn new() -> Self {
+        Self { a: 1, b: 0 }
+    }
+    /// Adds the next piece of data to the checksum.
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.a = (self.a + byte as u32) % 65521;
+            self.b = (self.b + self.a) % 65521;
+        }
+    }
+    /// Gets the checksum of all the data so far.
+    pub fn finish(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
+
+impl Default for Adler32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let mut adler = Adler32::new();
+    adler.update(data);
+    adler.finish()
+}
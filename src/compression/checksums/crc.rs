@@ -0,0 +1,93 @@
+/// A running CRC32, for data that arrives a piece at a time, such as a
+/// chunk written out before all of it has been read.
+#[derive(Debug, Clone, Copy)]
+pub struct Crc32(u32);
+
+impl Crc32 {
+    pub fn new() -> Self {
+        Self(!0)
+    }
+    /// Adds the next piece of data to the checksum, eight bytes at a time
+    /// while there are that many left.
+    pub fn update(&mut self, data: &[u8]) {
+        let mut crc = self.0;
+        let mut words = data.chunks_exact(8);
+        for word in &mut words {
+            let low = crc ^ u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+            let high = u32::from_le_bytes([word[4], word[5], word[6], word[7]]);
+            crc = SLICES[7][low as u8 as usize]
+                ^ SLICES[6][(low >> 8) as u8 as usize]
+                ^ SLICES[5][(low >> 16) as u8 as usize]
+                ^ SLICES[4][(low >> 24) as usize]
+                ^ SLICES[3][high as u8 as usize]
+                ^ SLICES[2][(high >> 8) as u8 as usize]
+                ^ SLICES[1][(high >> 16) as u8 as usize]
+                ^ SLICES[0][(high >> 24) as usize];
+        }
+        for &byte in words.remainder() {
+            crc = SLICES[0][((crc as u8) ^ byte) as usize] ^ (crc >> 8);
+        }
+        self.0 = crc;
+    }
+    /// Gets the checksum of all the data so far.
+    pub fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn hash(buf: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(buf);
+    crc.finish()
+}
+
+/// The reversed CRC32 polynomial, see the PNG specification annex D.
+const POLYNOMIAL: u32 = 0xedb8_8320;
+
+/// Tables for working through eight bytes at once, slicing-by-8. The first
+/// is the same as CRC32_TABLE, and each after it gives the effect of a byte
+/// one further from the end of the eight, so the lookups for all eight can
+/// be done independently and xored together.
+static SLICES: [[u32; 256]; 8] = slices();
+
+/// Builds SLICES at compile time.
+const fn slices() -> [[u32; 256]; 8] {
+    let mut tables = [[0; 256]; 8];
+
+    let mut idx = 0;
+    while idx < 256 {
+        let mut crc = idx as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => POLYNOMIAL ^ (crc >> 1),
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+        tables[0][idx] = crc;
+        idx += 1;
+    }
+
+    let mut table = 1;
+    while table < 8 {
+        let mut idx = 0;
+        while idx < 256 {
+            let last = tables[table - 1][idx];
+            tables[table][idx] = (last >> 8) ^ tables[0][(last & 0xff) as usize];
+            idx += 1;
+        }
+        table += 1;
+    }
+
+    tables
+}
+
+/// Static CRC32 lookup table.
+pub static CRC32_TABLE: [u32; 256] = SLICES[0];
@@ -0,0 +1,152 @@
+//! A CRC of any width from 8 to 64 bits, described by the parameters of
+//! the Rocksoft model every CRC catalogue uses: the polynomial, the
+//! starting value, whether bits are taken least significant first, and
+//! what the result is xored with. The table for each set of parameters is
+//! built at compile time.
+
+/// A running CRC with the given parameters, for data that arrives a piece
+/// at a time. The aliases below cover the common ones.
+///
+/// # Parameters
+///
+/// * 'WIDTH' - The number of bits in the CRC, from 8 to 64.
+/// * 'POLY' - The polynomial, most significant bit first and without its
+///   top term, the way catalogues list it.
+/// * 'INIT' - The value the register starts with.
+/// * 'REFLECT' - Whether each byte is taken least significant bit first,
+///   and the result reversed to match, as it is for CRC32.
+/// * 'XOROUT' - The value the result is xored with.
+///
+/// # Examples
+///
+/// '''
+/// let mut crc = Crc32c::new();
+/// crc.update(b"1234");
+/// crc.update(b"56789");
+/// assert_eq!(crc.finish(), 0xe306_9283);
+/// '''
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crc<
+    const WIDTH: u8,
+    const POLY: u64,
+    const INIT: u64,
+    const REFLECT: bool,
+    const XOROUT: u64,
+>(u64);
+
+/// CRC-16/ARC, used by LHA and ARC archives.
+pub type Crc16Arc = Crc<16, 0x8005, 0, true, 0>;
+/// CRC-16/IBM-3740, often called CRC-16/CCITT-FALSE.
+pub type Crc16Ibm3740 = Crc<16, 0x1021, 0xffff, false, 0>;
+/// CRC-16/XMODEM, used by XMODEM and ZMODEM.
+pub type Crc16Xmodem = Crc<16, 0x1021, 0, false, 0>;
+/// CRC-32/ISO-HDLC, the CRC32 of PNG, gzip and zip, see crc::Crc32 for a
+/// faster one.
+pub type Crc32IsoHdlc = Crc<32, 0x04c1_1db7, 0xffff_ffff, true, 0xffff_ffff>;
+/// CRC-32/BZIP2, the same polynomial as CRC32 taken most significant bit
+/// first.
+pub type Crc32Bzip2 = Crc<32, 0x04c1_1db7, 0xffff_ffff, false, 0xffff_ffff>;
+/// CRC-32C, Castagnoli's polynomial, used by iSCSI, ext4 and Btrfs.
+pub type Crc32c = Crc<32, 0x1edc_6f41, 0xffff_ffff, true, 0xffff_ffff>;
+/// CRC-64/XZ, used by xz.
+pub type Crc64Xz = Crc<64, 0x42f0_e1eb_a9ea_3693, { u64::MAX }, true, { u64::MAX }>;
+
+impl<const WIDTH: u8, const POLY: u64, const INIT: u64, const REFLECT: bool, const XOROUT: u64>
+    Crc<WIDTH, POLY, INIT, REFLECT, XOROUT>
+{
+    /// The bits of a WIDTH bit value.
+    const MASK: u64 = u64::MAX >> (64 - WIDTH as u32);
+
+    /// The effect of each byte on the register, built once per set of
+    /// parameters.
+    const TABLE: [u64; 256] = table(WIDTH, POLY, REFLECT);
+
+    /// Creates a Crc with nothing added yet.
+    ///
+    /// # Returns
+    ///
+    /// A Crc holding INIT, reversed if REFLECT is set, as the register
+    /// shifts the other way.
+    pub fn new() -> Self {
+        const { assert!(WIDTH >= 8 && WIDTH <= 64, "CRC widths go from 8 to 64 bits") };
+        match REFLECT {
+            true => Self(reflect(INIT, WIDTH)),
+            false => Self(INIT & Self::MASK),
+        }
+    }
+    /// Adds the next piece of data to the checksum, a byte at a time.
+    pub fn update(&mut self, data: &[u8]) {
+        let mut crc = self.0;
+        match REFLECT {
+            true => {
+                for &byte in data {
+                    crc = Self::TABLE[((crc as u8) ^ byte) as usize] ^ (crc >> 8);
+                }
+            }
+            false => {
+                let shift = WIDTH as u32 - 8;
+                for &byte in data {
+                    let idx = ((crc >> shift) as u8) ^ byte;
+                    crc = (Self::TABLE[idx as usize] ^ (crc << 8)) & Self::MASK;
+                }
+            }
+        }
+        self.0 = crc;
+    }
+    /// Gets the checksum of all the data so far.
+    pub fn finish(&self) -> u64 {
+        (self.0 ^ XOROUT) & Self::MASK
+    }
+    /// Gets the checksum of data in one go.
+    pub fn checksum(data: &[u8]) -> u64 {
+        let mut crc = Self::new();
+        crc.update(data);
+        crc.finish()
+    }
+}
+
+impl<const WIDTH: u8, const POLY: u64, const INIT: u64, const REFLECT: bool, const XOROUT: u64>
+    Default for Crc<WIDTH, POLY, INIT, REFLECT, XOROUT>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reverses the low width bits of value.
+const fn reflect(value: u64, width: u8) -> u64 {
+    value.reverse_bits() >> (64 - width as u32)
+}
+
+/// Builds the table of a CRC at compile time. Reflected CRCs shift right,
+/// with the polynomial reversed to match, and the rest shift left.
+const fn table(width: u8, poly: u64, reflected: bool) -> [u64; 256] {
+    let mut table = [0; 256];
+    if width < 8 || width > 64 {
+        return table;
+    }
+    let mask = u64::MAX >> (64 - width as u32);
+    let top = 1 << (width - 1);
+
+    let mut idx = 0;
+    while idx < 256 {
+        let mut crc = match reflected {
+            true => idx as u64,
+            false => (idx as u64) << (width - 8),
+        };
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match reflected {
+                true if crc & 1 == 1 => (crc >> 1) ^ reflect(poly, width),
+                true => crc >> 1,
+                false if crc & top != 0 => ((crc << 1) ^ poly) & mask,
+                false => (crc << 1) & mask,
+            };
+            bit += 1;
+        }
+        table[idx] = crc;
+        idx += 1;
+    }
+
+    table
+}
@@ -0,0 +1,77 @@
+//! The checksums formats store with their data: the CRC32 of PNG chunks and
+//! gzip files, the adler32 of zlib streams, and any other CRC a format
+//! needs, see engine::Crc. Each takes data a piece at a time with update,
+//! and gives the checksum of everything so far with finish.
+
+pub mod adler;
+pub mod crc;
+pub mod engine;
+
+pub use adler::Adler32;
+pub use crc::Crc32;
+pub use engine::{
+    Crc, Crc16Arc, Crc16Ibm3740, Crc16Xmodem, Crc32Bzip2, Crc32IsoHdlc, Crc32c, Crc64Xz,
+};
+
+/// A checksum worked out a piece at a time, for code that doesn't mind
+/// which one it's given.
+///
+/// # Examples
+///
+/// '''
+/// fn trailer<C: Checksum>(data: &[u8]) -> C::Output {
+///     let mut checksum = C::default();
+///     checksum.update(data);
+///     checksum.finish()
+/// }
+/// '''
+pub trait Checksum: Default {
+    /// The type of the finished checksum.
+    type Output;
+
+    /// Adds the next piece of data to the checksum.
+    fn update(&mut self, data: &[u8]);
+    /// Gets the checksum of all the data so far.
+    fn finish(&self) -> Self::Output;
+    /// Gets the checksum of data in one go.
+    fn checksum(data: &[u8]) -> Self::Output {
+        let mut checksum = Self::default();
+        checksum.update(data);
+        checksum.finish()
+    }
+}
+
+impl Checksum for Crc32 {
+    type Output = u32;
+
+    fn update(&mut self, data: &[u8]) {
+        Crc32::update(self, data);
+    }
+    fn finish(&self) -> u32 {
+        Crc32::finish(self)
+    }
+}
+
+impl Checksum for Adler32 {
+    type Output = u32;
+
+    fn update(&mut self, data: &[u8]) {
+        Adler32::update(self, data);
+    }
+    fn finish(&self) -> u32 {
+        Adler32::finish(self)
+    }
+}
+
+impl<const WIDTH: u8, const POLY: u64, const INIT: u64, const REFLECT: bool, const XOROUT: u64>
+    Checksum for Crc<WIDTH, POLY, INIT, REFLECT, XOROUT>
+{
+    type Output = u64;
+
+    fn update(&mut self, data: &[u8]) {
+        Crc::update(self, data);
+    }
+    fn finish(&self) -> u64 {
+        Crc::finish(self)
+    }
+}
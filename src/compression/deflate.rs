@@ -0,0 +1,654 @@
+//! Deflate compression, see RFC 1951. Data is turned into a run of literals
+//! and back-references, see lz77::tokenize, and each block is written with whichever
+//! of Huffman codes built for it, the fixed Huffman codes or stored bytes
+//! comes out smaller.
+
+use alloc::vec::Vec;
+
+use super::{
+    lz77::{self, MatchOptions, Token},
+    prefix::{
+        canonical_codes, code_lengths, CODE_LENGTH_ORDER, DISTANCE_BASE, DISTANCE_EXTRA_BITS,
+        FIXED_CODE_LENGTHS, LENGTH_BASE, LENGTH_EXTRA_BITS, MAX_CODE_LENGTH,
+    },
+    zlib::MAX_STORED_BLOCK,
+};
+
+/// The literal/length symbol ending every compressed block.
+const END_OF_BLOCK: usize = 256;
+
+/// Every distance code is 5 bits in a fixed block, see 3.2.6 of RFC 1951.
+const FIXED_DISTANCE_LENGTHS: [u8; 30] = [5; 30];
+
+/// The number of literal/length symbols a block can use, 286 and 287 never
+/// appear in compressed data.
+const LITERAL_SYMBOLS: usize = 286;
+
+/// The longest code a code length symbol can have, as their lengths are
+/// stored in 3 bits.
+const MAX_CODE_LENGTH_LENGTH: u8 = 7;
+
+/// The shortest match the filtered strategy takes, zlib drops anything
+/// shorter as noise.
+const FILTERED_MIN_LENGTH: usize = 6;
+
+/// How hard to compress, the presets of zlib's levels 0, 1, 6 and 9.
+///
+/// # Members
+///
+/// * 'None' - Stores the data without compressing it, level 0.
+/// * 'Fast' - Short hash chains and no lazy matching, level 1.
+/// * 'Default' - zlib's default, level 6.
+/// * 'Best' - The longest chains and matches, level 9.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CompressionLevel {
+    None,
+    Fast,
+    #[default]
+    Default,
+    Best,
+}
+
+impl CompressionLevel {
+    /// Gets the zlib level the preset stands for.
+    ///
+    /// # Returns
+    ///
+    /// The level, from 0 to 9.
+    pub fn zlib_level(&self) -> u32 {
+        match self {
+            CompressionLevel::None => 0,
+            CompressionLevel::Fast => 1,
+            CompressionLevel::Default => 6,
+            CompressionLevel::Best => 9,
+        }
+    }
+    /// Gets the match finder parameters zlib uses at the level. None has
+    /// no matches to find, it gets Fast's.
+    ///
+    /// # Returns
+    ///
+    /// The MatchOptions for the level.
+    pub fn match_options(&self) -> MatchOptions {
+        match self {
+            CompressionLevel::None | CompressionLevel::Fast => MatchOptions::new()
+                .good_length(4)
+                .max_lazy(0)
+                .nice_length(8)
+                .max_chain(4),
+            CompressionLevel::Default => MatchOptions::new(),
+            CompressionLevel::Best => MatchOptions::new()
+                .good_length(32)
+                .max_lazy(258)
+                .nice_length(258)
+                .max_chain(4096),
+        }
+    }
+}
+
+/// Which matches to look for, with the same meaning as zlib's strategies.
+///
+/// # Members
+///
+/// * 'Default' - Any match the level finds.
+/// * 'Filtered' - Only matches longer than 5 bytes, leaving the rest to the
+///   Huffman codes, for data like filtered PNG rows that is mostly
+///   small values spread at random.
+/// * 'HuffmanOnly' - No matches at all, only literals.
+/// * 'Rle' - Only runs of the same byte, matches at a distance of 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Strategy {
+    #[default]
+    Default,
+    Filtered,
+    HuffmanOnly,
+    Rle,
+}
+
+/// How compress_options compresses.
+///
+/// # Fields
+///
+/// * 'level' - How hard to compress.
+/// * 'strategy' - Which matches to look for, ignored at CompressionLevel::None.
+///
+/// # Examples
+///
+/// '''
+/// let options = DeflateOptions::new()
+///     .level(CompressionLevel::Best)
+///     .strategy(Strategy::Filtered);
+/// let compressed = deflate::compress_options(&data, &options);
+/// '''
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeflateOptions {
+    pub level: CompressionLevel,
+    pub strategy: Strategy,
+}
+
+impl DeflateOptions {
+    /// Creates DeflateOptions at the default level and strategy.
+    ///
+    /// # Returns
+    ///
+    /// DeflateOptions with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Sets how hard to compress.
+    pub fn level(mut self, level: CompressionLevel) -> Self {
+        self.level = level;
+        self
+    }
+    /// Sets which matches to look for.
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+}
+
+/// Packs bits into bytes least significant bit first, the order deflate
+/// stores everything in apart from Huffman codes, see 3.1.1 of RFC 1951.
+#[derive(Debug, Default)]
+pub(crate) struct BitWriter {
+    out: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    pub(crate) fn new(out: Vec<u8>) -> Self {
+        Self {
+            out,
+            bits: 0,
+            count: 0,
+        }
+    }
+    /// Writes the len least significant bits of value, up to 32.
+    pub(crate) fn write_bits(&mut self, value: u32, len: u32) {
+        let mask = (1u64 << len) - 1;
+        self.bits |= (value as u64 & mask) << self.count;
+        self.count += len;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+    /// Pads the last byte with zeroes so the next write starts a new byte.
+    pub(crate) fn align(&mut self) {
+        if self.count > 0 {
+            self.out.push(self.bits as u8);
+            self.bits = 0;
+            self.count = 0;
+        }
+    }
+    /// Writes whole bytes, after aligning.
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) {
+        self.align();
+        self.out.extend_from_slice(bytes);
+    }
+    /// Aligns and gives back the bytes written.
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        self.align();
+        self.out
+    }
+}
+
+/// The canonical Huffman code of each symbol in an alphabet, built from the
+/// code lengths the way PrefixTree::from_lengths builds its tree, see 3.2.2
+/// of RFC 1951.
+///
+/// # Fields
+///
+/// * 'codes' - The code of each symbol, bit reversed, as codes are stored
+///   most significant bit first while BitWriter writes least
+///   significant bit first.
+/// * 'lengths' - The length of each symbol's code, 0 for symbols without
+///   one.
+#[derive(Debug, Clone)]
+pub(crate) struct Codes {
+    codes: Vec<u16>,
+    lengths: Vec<u8>,
+}
+
+impl Codes {
+    /// Assigns codes from code lengths of at most 15 bits.
+    pub(crate) fn from_lengths(lengths: &[u8]) -> Self {
+        let codes = canonical_codes(lengths)
+            .into_iter()
+            .zip(lengths)
+            .map(|(code, &length)| match length {
+                0 => 0,
+                _ => (code as u16).reverse_bits() >> (16 - length),
+            })
+            .collect();
+
+        Self {
+            codes,
+            lengths: lengths.to_vec(),
+        }
+    }
+    /// Writes the code for symbol.
+    pub(crate) fn write(&self, writer: &mut BitWriter, symbol: usize) {
+        writer.write_bits(self.codes[symbol] as u32, self.lengths[symbol] as u32);
+    }
+}
+
+/// The Huffman codes of a dynamic block, and the header describing them,
+/// see 3.2.7 of RFC 1951.
+///
+/// # Fields
+///
+/// * 'literal_lengths' - The code length of each literal/length symbol,
+///   cut down to the last one used, but never fewer than 257.
+/// * 'distance_lengths' - The code length of each distance symbol, cut down
+///   to the last one used, but never fewer than 1.
+/// * 'runs' - Both sets of lengths, one after the other, as code length
+///   symbols with the value of their extra bits. 16 repeats the last
+///   length, and 17 and 18 give runs of zeroes.
+/// * 'code_length_lengths' - The code length of each code length symbol.
+/// * 'hclen' - The number of code length code lengths written.
+struct Dynamic {
+    literal_lengths: Vec<u8>,
+    distance_lengths: Vec<u8>,
+    runs: Vec<(u8, u8)>,
+    code_length_lengths: Vec<u8>,
+    hclen: usize,
+}
+
+impl Dynamic {
+    /// Builds codes fitted to the symbols tokens use.
+    fn new(tokens: &[Token]) -> Self {
+        let mut literals = [0u32; LITERAL_SYMBOLS];
+        let mut distances = [0u32; 30];
+        for token in tokens {
+            match *token {
+                Token::Literal(byte) => literals[byte as usize] += 1,
+                Token::Match { length, distance } => {
+                    literals[257 + length_code(length)] += 1;
+                    distances[distance_code(distance)] += 1;
+                }
+            }
+        }
+        literals[END_OF_BLOCK] = 1;
+
+        // Will never panic, 286 literal/length and 30 distance symbols fit
+        // in 15 bit codes.
+        let mut literal_lengths = code_lengths(&literals, MAX_CODE_LENGTH).unwrap();
+        let mut distance_lengths = code_lengths(&distances, MAX_CODE_LENGTH).unwrap();
+        trim(&mut literal_lengths, 257);
+        trim(&mut distance_lengths, 1);
+
+        let mut all = literal_lengths.clone();
+        all.extend_from_slice(&distance_lengths);
+        let runs = run_lengths(&all);
+
+        let mut frequencies = [0u32; 19];
+        for &(symbol, _) in &runs {
+            frequencies[symbol as usize] += 1;
+        }
+        // Will never panic, 19 code length symbols fit in 7 bit codes.
+        let code_length_lengths = code_lengths(&frequencies, MAX_CODE_LENGTH_LENGTH).unwrap();
+        let hclen = CODE_LENGTH_ORDER
+            .iter()
+            .rposition(|&symbol| code_length_lengths[symbol] != 0)
+            .map_or(0, |last| last + 1)
+            .max(4);
+
+        Self {
+            literal_lengths,
+            distance_lengths,
+            runs,
+            code_length_lengths,
+            hclen,
+        }
+    }
+    /// Works out how many bits the header and tokens take up together.
+    fn cost(&self, tokens: &[Token]) -> usize {
+        let runs: usize = self
+            .runs
+            .iter()
+            .map(|&(symbol, _)| {
+                self.code_length_lengths[symbol as usize] as usize + run_extra_bits(symbol) as usize
+            })
+            .sum();
+
+        5 + 5
+            + 4
+            + self.hclen * 3
+            + runs
+            + cost(tokens, &self.literal_lengths, &self.distance_lengths)
+    }
+    /// Writes the header, then tokens.
+    fn write(&self, writer: &mut BitWriter, tokens: &[Token]) {
+        writer.write_bits(self.literal_lengths.len() as u32 - 257, 5);
+        writer.write_bits(self.distance_lengths.len() as u32 - 1, 5);
+        writer.write_bits(self.hclen as u32 - 4, 4);
+        for &symbol in &CODE_LENGTH_ORDER[..self.hclen] {
+            writer.write_bits(self.code_length_lengths[symbol] as u32, 3);
+        }
+
+        let code_length_codes = Codes::from_lengths(&self.code_length_lengths);
+        for &(symbol, extra) in &self.runs {
+            code_length_codes.write(writer, symbol as usize);
+            writer.write_bits(extra as u32, run_extra_bits(symbol));
+        }
+
+        write_tokens(
+            writer,
+            tokens,
+            &Codes::from_lengths(&self.literal_lengths),
+            &Codes::from_lengths(&self.distance_lengths),
+        );
+    }
+}
+
+/// Drops the unused symbols from the end of lengths, keeping at least min.
+fn trim(lengths: &mut Vec<u8>, min: usize) {
+    let used = lengths
+        .iter()
+        .rposition(|&length| length != 0)
+        .map_or(0, |last| last + 1);
+    lengths.truncate(used.max(min));
+}
+
+/// Gets the number of extra bits after a code length symbol.
+fn run_extra_bits(symbol: u8) -> u32 {
+    match symbol {
+        16 => 2,
+        17 => 3,
+        18 => 7,
+        _ => 0,
+    }
+}
+
+/// Turns code lengths into code length symbols, see 3.2.7 of RFC 1951.
+///
+/// # Arguments
+///
+/// * 'lengths' - The code lengths of both alphabets, one after the other.
+///
+/// # Returns
+///
+/// Each code length symbol with the value of its extra bits.
+fn run_lengths(lengths: &[u8]) -> Vec<(u8, u8)> {
+    let mut runs = Vec::new();
+    let mut idx = 0;
+
+    while idx < lengths.len() {
+        let length = lengths[idx];
+        let run = lengths[idx..].iter().take_while(|&&l| l == length).count();
+
+        if length == 0 && run >= 3 {
+            let run = run.min(138);
+            runs.push(match run {
+                3..=10 => (17, (run - 3) as u8),
+                _ => (18, (run - 11) as u8),
+            });
+            idx += run;
+            continue;
+        }
+
+        // The length itself, then repeats of it 3 to 6 at a time. Anything
+        // left over is picked up on the next time round.
+        runs.push((length, 0));
+        idx += 1;
+        let mut rest = run - 1;
+        while rest >= 3 {
+            let repeat = rest.min(6);
+            runs.push((16, (repeat - 3) as u8));
+            rest -= repeat;
+            idx += repeat;
+        }
+    }
+
+    runs
+}
+
+/// Gets the length code, as an index into LENGTH_BASE, for a match length.
+fn length_code(length: u16) -> usize {
+    LENGTH_BASE.partition_point(|&base| base <= length) - 1
+}
+
+/// Gets the distance code, an index into DISTANCE_BASE, for a distance.
+fn distance_code(distance: u16) -> usize {
+    DISTANCE_BASE.partition_point(|&base| base <= distance) - 1
+}
+
+/// Works out how many bits tokens take up with the given code lengths,
+/// the end of block symbol included.
+///
+/// # Arguments
+///
+/// * 'tokens' - The tokens of the block.
+/// * 'literal_lengths' - The code length of each literal/length symbol.
+/// * 'distance_lengths' - The code length of each distance symbol.
+///
+/// # Returns
+///
+/// The number of bits after the block header.
+fn cost(tokens: &[Token], literal_lengths: &[u8], distance_lengths: &[u8]) -> usize {
+    let symbols: usize = tokens
+        .iter()
+        .map(|token| match *token {
+            Token::Literal(byte) => literal_lengths[byte as usize] as usize,
+            Token::Match { length, distance } => {
+                let length = length_code(length);
+                let distance = distance_code(distance);
+                (literal_lengths[257 + length]
+                    + LENGTH_EXTRA_BITS[length]
+                    + distance_lengths[distance]
+                    + DISTANCE_EXTRA_BITS[distance]) as usize
+            }
+        })
+        .sum();
+
+    symbols + literal_lengths[END_OF_BLOCK] as usize
+}
+
+/// Writes tokens followed by the end of block symbol.
+///
+/// # Arguments
+///
+/// * 'writer' - Where to write the symbols.
+/// * 'tokens' - The tokens of the block.
+/// * 'literals' - The literal/length codes.
+/// * 'distances' - The distance codes.
+pub(crate) fn write_tokens(
+    writer: &mut BitWriter,
+    tokens: &[Token],
+    literals: &Codes,
+    distances: &Codes,
+) {
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => literals.write(writer, byte as usize),
+            Token::Match { length, distance } => {
+                let code = length_code(length);
+                literals.write(writer, 257 + code);
+                writer.write_bits(
+                    (length - LENGTH_BASE[code]) as u32,
+                    LENGTH_EXTRA_BITS[code] as u32,
+                );
+
+                let code = distance_code(distance);
+                distances.write(writer, code);
+                writer.write_bits(
+                    (distance - DISTANCE_BASE[code]) as u32,
+                    DISTANCE_EXTRA_BITS[code] as u32,
+                );
+            }
+        }
+    }
+    literals.write(writer, END_OF_BLOCK);
+}
+
+/// Writes one block holding tokens, with codes built for them, the fixed
+/// Huffman codes or stored bytes, whichever is smaller.
+///
+/// # Arguments
+///
+/// * 'writer' - Where to write the block.
+/// * 'tokens' - The tokens of the block.
+/// * 'data' - The bytes tokens span, at most MAX_STORED_BLOCK.
+/// * 'last' - Whether this is the final block of the stream.
+fn write_block(writer: &mut BitWriter, tokens: &[Token], data: &[u8], last: bool) {
+    let dynamic = Dynamic::new(tokens);
+    let dynamic_cost = dynamic.cost(tokens);
+    let fixed = cost(tokens, &FIXED_CODE_LENGTHS, &FIXED_DISTANCE_LENGTHS);
+    // Stored blocks pad to a byte, then take 4 bytes for their length.
+    let stored = 7 + 32 + data.len() * 8;
+
+    if dynamic_cost < fixed && dynamic_cost <= stored {
+        writer.write_bits(last as u32, 1);
+        writer.write_bits(2, 2);
+        dynamic.write(writer, tokens);
+    } else if fixed <= stored {
+        writer.write_bits(last as u32, 1);
+        writer.write_bits(1, 2);
+        write_tokens(
+            writer,
+            tokens,
+            &Codes::from_lengths(&FIXED_CODE_LENGTHS),
+            &Codes::from_lengths(&FIXED_DISTANCE_LENGTHS),
+        );
+    } else {
+        write_stored_block(writer, data, last);
+    }
+}
+
+/// Writes one stored block holding data, see 3.2.4 of RFC 1951.
+///
+/// # Arguments
+///
+/// * 'writer' - Where to write the block.
+/// * 'data' - The bytes to store, at most MAX_STORED_BLOCK.
+/// * 'last' - Whether this is the final block of the stream.
+fn write_stored_block(writer: &mut BitWriter, data: &[u8], last: bool) {
+    let len = data.len() as u16;
+    writer.write_bits(last as u32, 1);
+    writer.write_bits(0, 2);
+    writer.write_bytes(&len.to_le_bytes());
+    writer.write_bytes(&(!len).to_le_bytes());
+    writer.write_bytes(data);
+}
+
+/// Compresses data into raw deflate data, without a zlib header or
+/// checksum, searching for matches with the default MatchOptions.
+///
+/// # Arguments
+///
+/// * 'data' - The bytes to compress.
+///
+/// # Returns
+///
+/// The deflate data.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    compress_with(data, &MatchOptions::new())
+}
+
+/// Compresses data into raw deflate data, without a zlib header or
+/// checksum. The data is split into blocks spanning at most
+/// MAX_STORED_BLOCK bytes, so any block that doesn't compress can be
+/// stored instead.
+///
+/// # Arguments
+///
+/// * 'data' - The bytes to compress.
+/// * 'options' - How hard to search for matches.
+///
+/// # Returns
+///
+/// The deflate data.
+pub fn compress_with(data: &[u8], options: &MatchOptions) -> Vec<u8> {
+    write_blocks(data, &lz77::tokenize(data, options))
+}
+
+/// Compresses data into raw deflate data, without a zlib header or
+/// checksum, at a level and with a strategy the way zlib would.
+///
+/// # Arguments
+///
+/// * 'data' - The bytes to compress.
+/// * 'options' - The level and strategy to compress with.
+///
+/// # Returns
+///
+/// The deflate data.
+pub fn compress_options(data: &[u8], options: &DeflateOptions) -> Vec<u8> {
+    let matches = match options.level {
+        CompressionLevel::None => return write_stored(data),
+        level => level.match_options(),
+    };
+    let tokens = match options.strategy {
+        Strategy::Default => lz77::tokenize(data, &matches),
+        Strategy::Filtered => lz77::tokenize(data, &matches.min_length(FILTERED_MIN_LENGTH)),
+        Strategy::HuffmanOnly => data.iter().map(|&byte| Token::Literal(byte)).collect(),
+        Strategy::Rle => lz77::tokenize_runs(data),
+    };
+
+    write_blocks(data, &tokens)
+}
+
+/// Writes data as nothing but stored blocks, each at most
+/// MAX_STORED_BLOCK bytes.
+///
+/// # Arguments
+///
+/// * 'data' - The bytes to store.
+///
+/// # Returns
+///
+/// The deflate data.
+fn write_stored(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::new(Vec::with_capacity(data.len() + 16));
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        write_stored_block(&mut writer, &[], true);
+    }
+    while let Some(chunk) = chunks.next() {
+        write_stored_block(&mut writer, chunk, chunks.peek().is_none());
+    }
+
+    writer.finish()
+}
+
+/// Writes tokens as blocks spanning at most MAX_STORED_BLOCK bytes, so
+/// any block that doesn't compress can be stored instead.
+///
+/// # Arguments
+///
+/// * 'data' - The bytes tokens span.
+/// * 'tokens' - The tokens to write.
+///
+/// # Returns
+///
+/// The deflate data.
+fn write_blocks(data: &[u8], tokens: &[Token]) -> Vec<u8> {
+    let mut writer = BitWriter::new(Vec::with_capacity(data.len() / 2 + 16));
+
+    let (mut start, mut offset) = (0, 0);
+    loop {
+        let (mut end, mut span) = (start, 0);
+        while end < tokens.len() && span + tokens[end].span() <= MAX_STORED_BLOCK {
+            span += tokens[end].span();
+            end += 1;
+        }
+
+        let last = end == tokens.len();
+        write_block(
+            &mut writer,
+            &tokens[start..end],
+            &data[offset..offset + span],
+            last,
+        );
+        if last {
+            return writer.finish();
+        }
+        (start, offset) = (end, offset + span);
+    }
+}
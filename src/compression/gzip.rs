@@ -0,0 +1,551 @@
+//! The gzip file format, see RFC 1952. A gzip file wraps deflate data in a
+//! header, holding the original file name and modification time among
+//! other things, and a trailer with the CRC32 and length of the
+//! decompressed data.
+//!
+//! A file can hold several members one after another, each with a header
+//! and trailer of its own, and decompresses to their data joined together.
+//! pigz and bgzip write files like that, see GzipMembers.
+
+use alloc::{string::String, vec::Vec};
+use core::{error::Error, fmt::Display};
+
+use super::{
+    checksums::crc::{self, Crc32},
+    deflate,
+    inflate::{DeflateError, DeflateStream},
+};
+use crate::error;
+
+/// The two bytes every gzip file starts with.
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The compression method byte for deflate, the only one defined.
+const CM_DEFLATE: u8 = 8;
+
+/// The bits of the FLG byte, see 2.3.1 of RFC 1952.
+const FTEXT: u8 = 1;
+const FHCRC: u8 = 1 << 1;
+const FEXTRA: u8 = 1 << 2;
+const FNAME: u8 = 1 << 3;
+const FCOMMENT: u8 = 1 << 4;
+const RESERVED: u8 = 0b1110_0000;
+
+/// The length of the CRC32 and ISIZE after the deflate data.
+const TRAILER_LEN: usize = 8;
+
+/// Errors from reading a gzip file, see GzipStream::build.
+///
+/// # Members
+///
+/// * 'InvalidHeader' - The header is malformed, or the file is too short
+///   to hold it and the trailer, with what's wrong.
+#[derive(Debug)]
+pub enum GzipError {
+    InvalidHeader(&'static str),
+}
+
+impl Display for GzipError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            GzipError::InvalidHeader(s) => {
+                write!(f, "Error: Invalid gzip header, {}", s)
+            }
+        }
+    }
+}
+
+impl Error for GzipError {}
+
+/// The header of a gzip file, see 2.3.1 of RFC 1952.
+///
+/// # Fields
+///
+/// * 'text' - Whether the compressor thought the data was text, FTEXT.
+/// * 'mtime' - When the original file was last modified, in seconds since
+///   the Unix epoch, 0 if it isn't known.
+/// * 'xfl' - Extra flags, 2 for the slowest compression and 4 for the
+///   fastest.
+/// * 'os' - The operating system the file was compressed on, 255 if it
+///   isn't known.
+/// * 'extra' - The extra field, if FEXTRA is set.
+/// * 'name' - The original file name, if FNAME is set. It's stored as
+///   ISO 8859-1, which every byte of maps straight to a char.
+/// * 'comment' - The comment, if FCOMMENT is set, ISO 8859-1 like name.
+/// * 'header_crc' - The CRC16 of the header, if FHCRC is set, checked while
+///   parsing.
+/// * 'end_idx' - The index of the first byte after the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GzipHeader {
+    pub text: bool,
+    pub mtime: u32,
+    pub xfl: u8,
+    pub os: u8,
+    pub extra: Option<Vec<u8>>,
+    pub name: Option<String>,
+    pub comment: Option<String>,
+    pub header_crc: Option<u16>,
+    pub end_idx: usize,
+}
+
+impl GzipHeader {
+    /// Creates a header for writing with nothing in it but what's required:
+    /// no name, a modification time of 0 and an unknown operating system,
+    /// so the same data always gives the same file.
+    ///
+    /// # Returns
+    ///
+    /// A GzipHeader with default values.
+    pub fn new() -> Self {
+        Self {
+            text: false,
+            mtime: 0,
+            xfl: 0,
+            os: 255,
+            extra: None,
+            name: None,
+            comment: None,
+            header_crc: None,
+            end_idx: 0,
+        }
+    }
+    /// Sets the original file name.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+    /// Sets the comment.
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+    /// Sets when the original file was last modified, in seconds since the
+    /// Unix epoch.
+    pub fn mtime(mut self, mtime: u32) -> Self {
+        self.mtime = mtime;
+        self
+    }
+    /// Writes the header. FHCRC is set, with the header's CRC16 worked out
+    /// afresh, if header_crc is Some. end_idx is ignored, and an extra field
+    /// is cut short at 65535 bytes, the most its length can say.
+    ///
+    /// # Arguments
+    ///
+    /// * 'out' - Where to write the header.
+    pub fn write(&self, out: &mut Vec<u8>) {
+        let start = out.len();
+        let flags = [
+            (self.text, FTEXT),
+            (self.header_crc.is_some(), FHCRC),
+            (self.extra.is_some(), FEXTRA),
+            (self.name.is_some(), FNAME),
+            (self.comment.is_some(), FCOMMENT),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |flags, (_, flag)| flags | flag);
+
+        out.extend_from_slice(&GZIP_MAGIC);
+        out.extend_from_slice(&[CM_DEFLATE, flags]);
+        out.extend_from_slice(&self.mtime.to_le_bytes());
+        out.extend_from_slice(&[self.xfl, self.os]);
+        if let Some(extra) = &self.extra {
+            let extra = &extra[..extra.len().min(u16::MAX as usize)];
+            out.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+            out.extend_from_slice(extra);
+        }
+        for text in [&self.name, &self.comment].into_iter().flatten() {
+            out.extend(latin1(text));
+            out.push(0);
+        }
+        if self.header_crc.is_some() {
+            let header_crc = crc::hash(&out[start..]) as u16;
+            out.extend_from_slice(&header_crc.to_le_bytes());
+        }
+    }
+    /// Parses the header at the start of bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * 'bytes' - The gzip file, or at least its header.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the GzipHeader, or a GzipError if the
+    /// header is malformed or cut short.
+    pub fn build(bytes: &[u8]) -> Result<Self, GzipError> {
+        const SHORT: GzipError = GzipError::InvalidHeader("the file ends inside the header.");
+
+        let fixed = bytes.get(..10).ok_or(SHORT)?;
+        if fixed[..2] != GZIP_MAGIC {
+            return Err(GzipError::InvalidHeader(
+                "the file doesn't start with 1f 8b.",
+            ));
+        }
+        if fixed[2] != CM_DEFLATE {
+            return Err(GzipError::InvalidHeader(
+                "the compression method isn't deflate.",
+            ));
+        }
+        let flags = fixed[3];
+        if flags & RESERVED != 0 {
+            return Err(GzipError::InvalidHeader("reserved flag bits are set."));
+        }
+
+        let mut end_idx = 10;
+        let extra = match flags & FEXTRA {
+            0 => None,
+            _ => {
+                let len = bytes.get(end_idx..end_idx + 2).ok_or(SHORT)?;
+                let len = u16::from_le_bytes([len[0], len[1]]) as usize;
+                let extra = bytes.get(end_idx + 2..end_idx + 2 + len).ok_or(SHORT)?;
+                end_idx += 2 + len;
+                Some(extra.to_vec())
+            }
+        };
+
+        let mut latin1 = |flag: u8| -> Result<Option<String>, GzipError> {
+            if flags & flag == 0 {
+                return Ok(None);
+            }
+            let rest = bytes.get(end_idx..).unwrap_or_default();
+            let len = rest.iter().position(|&b| b == 0).ok_or(SHORT)?;
+            end_idx += len + 1;
+            Ok(Some(rest[..len].iter().map(|&b| b as char).collect()))
+        };
+        let name = latin1(FNAME)?;
+        let comment = latin1(FCOMMENT)?;
+
+        let header_crc = match flags & FHCRC {
+            0 => None,
+            _ => {
+                let stored = bytes.get(end_idx..end_idx + 2).ok_or(SHORT)?;
+                let stored = u16::from_le_bytes([stored[0], stored[1]]);
+                if crc::hash(&bytes[..end_idx]) as u16 != stored {
+                    return Err(GzipError::InvalidHeader(
+                        "the header CRC16 doesn't match the header.",
+                    ));
+                }
+                end_idx += 2;
+                Some(stored)
+            }
+        };
+
+        Ok(Self {
+            text: flags & FTEXT != 0,
+            mtime: u32::from_le_bytes([fixed[4], fixed[5], fixed[6], fixed[7]]),
+            xfl: fixed[8],
+            os: fixed[9],
+            extra,
+            name,
+            comment,
+            header_crc,
+            end_idx,
+        })
+    }
+}
+
+impl Default for GzipHeader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Encodes text as ISO 8859-1 for a gzip header, with characters it doesn't
+/// have replaced by '?' and zero bytes, which would end the field, left out.
+fn latin1(text: &str) -> impl Iterator<Item = u8> + '_ {
+    text.chars()
+        .filter(|&c| c != '\0')
+        .map(|c| u8::try_from(c).unwrap_or(b'?'))
+}
+
+/// A gzip file with a single member, its header parsed and its deflate data
+/// ready to decompress. The trailer is taken from the end of the file, so
+/// files with several members need GzipMembers.
+///
+/// # Fields
+///
+/// * 'header' - The parsed header.
+/// * 'deflate' - The deflate data.
+/// * 'crc32' - The CRC32 of the decompressed data, from the trailer.
+/// * 'isize' - The length of the decompressed data modulo 2^32, from the
+///   trailer.
+///
+/// # Examples
+///
+/// '''
+/// let mut gzip = GzipStream::build(&fs::read("./notes.txt.gz")?)?;
+/// let name = gzip.header.name.clone();
+/// let text = gzip.decompress()?;
+/// '''
+#[derive(Debug)]
+pub struct GzipStream {
+    pub header: GzipHeader,
+    pub deflate: DeflateStream,
+    pub crc32: u32,
+    pub isize: u32,
+}
+
+impl GzipStream {
+    /// Compresses data into a gzip file, see deflate::compress.
+    ///
+    /// # Arguments
+    ///
+    /// * 'data' - The bytes to compress.
+    /// * 'header' - The header to write, see GzipHeader::new.
+    ///
+    /// # Returns
+    ///
+    /// The gzip file.
+    ///
+    /// # Examples
+    ///
+    /// '''
+    /// let header = GzipHeader::new().name("notes.txt");
+    /// fs::write("./notes.txt.gz", GzipStream::compress(&text, &header))?;
+    /// '''
+    pub fn compress(data: &[u8], header: &GzipHeader) -> Vec<u8> {
+        let mut file = Vec::with_capacity(data.len() / 2 + 32);
+        header.write(&mut file);
+        file.extend(deflate::compress(data));
+        file.extend_from_slice(&crc::hash(data).to_le_bytes());
+        file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+
+        file
+    }
+    /// Splits a gzip file holding a single member into its header, deflate
+    /// data and the trailer at the end of the file, without decompressing
+    /// anything. Use GzipMembers for files with more than one member.
+    ///
+    /// # Arguments
+    ///
+    /// * 'bytes' - The whole gzip file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the GzipStream, or a GzipError if the
+    /// header is malformed or the file is too short to hold the trailer.
+    pub fn build(bytes: &[u8]) -> Result<Self, GzipError> {
+        let header = GzipHeader::build(bytes)?;
+        if bytes.len() < header.end_idx + TRAILER_LEN {
+            return Err(GzipError::InvalidHeader(
+                "the file is too short to hold the CRC32 and length after the header.",
+            ));
+        }
+
+        let trailer = &bytes[bytes.len() - TRAILER_LEN..];
+        let deflate = DeflateStream::build(&bytes[header.end_idx..bytes.len() - TRAILER_LEN]);
+
+        Ok(Self {
+            header,
+            deflate,
+            crc32: u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]),
+            isize: u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]),
+        })
+    }
+    /// Decompresses the data, then checks its CRC32 and length against the
+    /// trailer.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the decompressed data, or a DeflateError.
+    pub fn decompress(&mut self) -> Result<Vec<u8>, DeflateError> {
+        let data = self.deflate.decompress()?;
+        self.check(crc::hash(&data), data.len())?;
+
+        Ok(data)
+    }
+    /// Decompresses the data a piece at a time, see
+    /// DeflateStream::decompress_with. The trailer is checked once the last
+    /// piece has been handed to each.
+    ///
+    /// # Arguments
+    ///
+    /// * 'buffer' - The buffer to hold the output in.
+    /// * 'each' - Called with each piece of the output, in order.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the emptied buffer, or the first error
+    /// from decompressing or from each.
+    pub fn decompress_with<E: From<DeflateError>>(
+        &mut self,
+        buffer: Vec<u8>,
+        each: &mut dyn FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<Vec<u8>, E> {
+        let mut crc = Crc32::new();
+        let mut len = 0;
+        let buffer = self.deflate.decompress_with(buffer, &mut |data| {
+            crc.update(data);
+            len += data.len();
+            each(data)
+        })?;
+        self.check(crc.finish(), len)?;
+
+        Ok(buffer)
+    }
+    /// Checks the CRC32 and length of the decompressed data against the
+    /// trailer.
+    ///
+    /// # Arguments
+    ///
+    /// * 'actual' - The CRC32 of the decompressed data.
+    /// * 'len' - The length of the decompressed data.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or a DeflateError::ChecksumMismatch if the CRC32
+    /// differs, or a DecompressionError if the length does.
+    pub fn check(&self, actual: u32, len: usize) -> Result<(), DeflateError> {
+        check_trailer(self.crc32, self.isize, actual, len)
+    }
+}
+
+/// Checks the CRC32 and length of decompressed data against a trailer.
+///
+/// # Arguments
+///
+/// * 'crc32' - The CRC32 from the trailer.
+/// * 'isize' - The length from the trailer.
+/// * 'actual' - The CRC32 of the decompressed data.
+/// * 'len' - The length of the decompressed data.
+///
+/// # Returns
+///
+/// An empty result, or a DeflateError::ChecksumMismatch if the CRC32
+/// differs, or a DecompressionError if the length does.
+fn check_trailer(crc32: u32, isize: u32, actual: u32, len: usize) -> Result<(), DeflateError> {
+    if actual != crc32 {
+        return Err(DeflateError::ChecksumMismatch {
+            expected: crc32,
+            actual,
+        });
+    }
+    if len as u32 != isize {
+        return Err(DeflateError::DecompressionError(
+            "the gzip trailer's length doesn't match the decompressed data.",
+        ));
+    }
+
+    Ok(())
+}
+
+/// One member of a gzip file, decompressed.
+///
+/// # Fields
+///
+/// * 'header' - The member's header.
+/// * 'data' - The decompressed data, checked against the member's trailer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GzipMember {
+    pub header: GzipHeader,
+    pub data: Vec<u8>,
+}
+
+/// An iterator over the members of a gzip file, decompressing each in turn.
+/// Once a member's trailer has been read, the next starts straight after
+/// it, until the file ends. Anything after the last member that isn't
+/// another one is an error, as is a damaged member, after which the
+/// iterator stops.
+///
+/// # Fields
+///
+/// * 'bytes' - The gzip file.
+/// * 'deflate' - The deflate data of the whole file, restarted at each
+///   member, so the file is only copied once.
+/// * 'started' - Whether the first member has been read, as a file needs
+///   at least one.
+/// * 'failed' - Whether a member couldn't be read.
+///
+/// # Examples
+///
+/// '''
+/// let bytes = fs::read("./reads.fastq.gz")?;
+/// for member in GzipMembers::new(&bytes) {
+///     let member = member?;
+///     println!("{:?}: {} bytes", member.header.name, member.data.len());
+/// }
+/// '''
+#[derive(Debug)]
+pub struct GzipMembers<'a> {
+    bytes: &'a [u8],
+    deflate: DeflateStream,
+    started: bool,
+    failed: bool,
+}
+
+impl<'a> GzipMembers<'a> {
+    /// Creates a GzipMembers at the start of a gzip file.
+    ///
+    /// # Arguments
+    ///
+    /// * 'bytes' - The gzip file.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            deflate: DeflateStream::build(bytes),
+            started: false,
+            failed: false,
+        }
+    }
+    /// Gets the index of the first byte after the members read so far.
+    pub fn offset(&self) -> usize {
+        self.deflate.bitstream.position() / 8
+    }
+    /// Reads and decompresses the member starting at offset.
+    fn member(&mut self) -> Result<GzipMember, error::Error> {
+        let header = GzipHeader::build(&self.bytes[self.offset()..])?;
+        self.deflate.bitstream.skip(header.end_idx * 8);
+        self.deflate.restart();
+
+        let data = self.deflate.decompress_into(Vec::new())?;
+        self.deflate.bitstream.align_to_byte();
+        let trailer = self
+            .deflate
+            .bitstream
+            .read_bytes(TRAILER_LEN)
+            .ok_or(DeflateError::UnexpectedEof("the gzip trailer"))?;
+        check_trailer(
+            u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]),
+            u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]),
+            crc::hash(&data),
+            data.len(),
+        )?;
+
+        Ok(GzipMember { header, data })
+    }
+}
+
+impl Iterator for GzipMembers<'_> {
+    type Item = Result<GzipMember, error::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || (self.started && self.offset() == self.bytes.len()) {
+            return None;
+        }
+        self.started = true;
+
+        let member = self.member();
+        self.failed = member.is_err();
+        Some(member)
+    }
+}
+
+/// Decompresses every member of a gzip file, see GzipMembers.
+///
+/// # Arguments
+///
+/// * 'bytes' - The gzip file.
+///
+/// # Returns
+///
+/// A result containing either the data of every member joined together, or
+/// the first error.
+pub fn decompress_all(bytes: &[u8]) -> Result<Vec<u8>, error::Error> {
+    let mut data = Vec::new();
+    for member in GzipMembers::new(bytes) {
+        data.extend(member?.data);
+    }
+
+    Ok(data)
+}
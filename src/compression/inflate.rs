@@ -1,22 +1,67 @@
-use std::{error::Error, fmt::Display};
+use alloc::vec::Vec;
+use core::{error::Error, fmt::Display, mem};
 
 use crate::{
-    compression::bits::BitVector64,
+    compression::bits::BitReader,
     compression::prefix::{
-        PrefixTree, DISTANCE_BASE, DISTANCE_EXTRA_BITS, FIXED_CODE_LENGTHS, LENGTH_BASE,
-        LENGTH_EXTRA_BITS,
+        DecodeTable, PrefixTree, CODE_LENGTH_ORDER, DISTANCE_BASE, DISTANCE_EXTRA_BITS,
+        FIXED_CODE_LENGTHS, LENGTH_BASE, LENGTH_EXTRA_BITS, MAX_CODE_LENGTH,
     },
+    compression::scratch::Scratch,
 };
 
+pub use crate::compression::prefix::{PrefixError, KRAFT_COMPLETE};
+
+/// The furthest back a match can copy from, see 3.2.5 of RFC 1951.
+pub const WINDOW_SIZE: usize = 32 * 1024;
+
+/// The smallest window a zlib header can declare, a CINFO of 0.
+pub const MIN_WINDOW_SIZE: usize = 256;
+
+/// How many bits the primary tables for literal/length and distance codes
+/// take, zlib's choices. Longer codes are rare enough that going on to a
+/// subtable for them costs less than the bigger tables would to build.
+const LITERAL_TABLE_BITS: u8 = 9;
+const DISTANCE_TABLE_BITS: u8 = 6;
+
+/// Why a deflate stream couldn't be decompressed.
+///
+/// # Members
+///
+/// * 'InvalidBlockError' - A block header or stored block is malformed.
+/// * 'InvalidSymbolError' - A symbol that isn't allowed where it was found,
+///   with the symbol.
+/// * 'DecompressionError' - The data is otherwise inconsistent.
+/// * 'UnexpectedEof' - The data ended partway through a block, with what
+///   was being read.
+/// * 'DistanceTooFarBack' - A match copies from before the start of the
+///   output. Holds the match's distance and how much output there is.
+/// * 'DistanceBeyondWindow' - A match copies from further back than the
+///   window the stream declared, see DeflateStream::set_window. Holds
+///   the match's distance and the window size.
+/// * 'InvalidCodeLengths' - A dynamic block's code lengths can't describe
+///   its codes, or a code was read that they give no symbol.
+/// * 'InvalidPrefixCode' - A dynamic block's code lengths don't make a
+///   complete prefix code, with the PrefixError saying how.
+/// * 'ChecksumMismatch' - The data decompressed, but its checksum differs
+///   from the one stored with it, the adler32 trailer of a zlib
+///   stream or the CRC32 of a gzip file. Holds the stored and the
+///   computed checksums.
 #[derive(Debug)]
 pub enum DeflateError {
     InvalidBlockError(&'static str),
     InvalidSymbolError(usize, &'static str),
     DecompressionError(&'static str),
+    ChecksumMismatch { expected: u32, actual: u32 },
+    UnexpectedEof(&'static str),
+    DistanceTooFarBack { distance: usize, available: usize },
+    DistanceBeyondWindow { distance: usize, window: usize },
+    InvalidCodeLengths(&'static str),
+    InvalidPrefixCode(PrefixError),
 }
 
 impl Display for DeflateError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             DeflateError::InvalidBlockError(s) => {
                 write!(f, "InvalidBlock Error: {}", s)
@@ -27,81 +72,357 @@ impl Display for DeflateError {
             DeflateError::DecompressionError(s) => {
                 write!(f, "DecompressionError: {}", s)
             }
+            DeflateError::ChecksumMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "ChecksumMismatch: the data's checksum is {:08x}, but {:08x} is stored with it.",
+                    actual, expected
+                )
+            }
+            DeflateError::UnexpectedEof(s) => {
+                write!(f, "UnexpectedEof: the data ended before {}.", s)
+            }
+            DeflateError::DistanceTooFarBack {
+                distance,
+                available,
+            } => {
+                write!(
+                    f,
+                    "DistanceTooFarBack: a match copies from {} bytes back, but there are only {}.",
+                    distance, available
+                )
+            }
+            DeflateError::DistanceBeyondWindow { distance, window } => {
+                write!(
+                    f,
+                    "DistanceBeyondWindow: a match copies from {} bytes back, past the {} byte window.",
+                    distance, window
+                )
+            }
+            DeflateError::InvalidCodeLengths(s) => {
+                write!(f, "InvalidCodeLengths: {}", s)
+            }
+            DeflateError::InvalidPrefixCode(e) => {
+                write!(f, "InvalidPrefixCode: {}", e)
+            }
         }
     }
 }
 
-impl Error for DeflateError {}
+impl Error for DeflateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DeflateError::InvalidPrefixCode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct DeflateStream {
-    compressed: Vec<u8>,
     decompressed: Vec<u8>,
-    pub bitstream: BitVector64,
+    pub bitstream: BitReader,
     finished: bool,
+    last: bool,
+    block: Option<Block>,
+    scratch: Scratch,
+    window: usize,
+}
+
+/// A block partway through being decoded, so decoding can stop once enough
+/// output is held and carry on from the same place later.
+///
+/// # Members
+///
+/// * 'Stored' - A stored block, with the number of bytes left to copy.
+/// * 'Coded' - A block of literals and matches, with its tables, and
+///   whether they're the fixed ones, which go back into the Scratch once
+///   the block is done.
+#[derive(Debug)]
+enum Block {
+    Stored(usize),
+    Coded {
+        ll_table: DecodeTable,
+        dist_table: DecodeTable,
+        fixed: bool,
+    },
 }
 
 impl DeflateStream {
     pub fn build(compressed: &[u8]) -> Self {
-        let bitstream = BitVector64::from_be_bytes(compressed);
         Self {
-            compressed: compressed.to_vec(),
             decompressed: Vec::new(),
-            bitstream,
+            bitstream: BitReader::new(compressed),
             finished: false,
+            last: false,
+            block: None,
+            scratch: Scratch::new(),
+            window: WINDOW_SIZE,
         }
     }
+    /// Sets the furthest back a match may copy from. A match past it is a
+    /// DistanceBeyondWindow error, as the encoder promised the decoder it
+    /// would only need to keep that much output. Defaults to WINDOW_SIZE,
+    /// the most deflate allows.
+    ///
+    /// # Arguments
+    ///
+    /// * 'window' - The window size, from MIN_WINDOW_SIZE to WINDOW_SIZE.
+    ///   Values outside that range are clamped to it.
+    pub fn set_window(&mut self, window: usize) {
+        self.window = window.clamp(MIN_WINDOW_SIZE, WINDOW_SIZE);
+    }
+    /// Gets the furthest back a match may copy from, see set_window.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+    /// Gets ready to decompress another deflate stream, starting wherever
+    /// the bitstream is, for data holding several one after another, like
+    /// the members of a gzip file. Any output not yet handed over is
+    /// dropped, as matches can't reach back into an earlier stream.
+    pub fn restart(&mut self) {
+        self.decompressed.clear();
+        self.finished = false;
+        self.last = false;
+        self.block = None;
+    }
+    /// Gives the stream a Scratch to use for its working buffers, usually
+    /// one taken from a previous stream with take_scratch.
+    ///
+    /// # Arguments
+    ///
+    /// * 'scratch' - The Scratch to use.
+    pub fn set_scratch(&mut self, scratch: Scratch) {
+        self.scratch = scratch;
+    }
+    /// Takes the Scratch out of the stream so it can be reused, leaving an
+    /// empty one in its place.
+    ///
+    /// # Returns
+    ///
+    /// The Scratch the stream was using.
+    pub fn take_scratch(&mut self) -> Scratch {
+        mem::take(&mut self.scratch)
+    }
     pub fn decompress(&mut self) -> Result<Vec<u8>, DeflateError> {
+        self.inflate()?;
+        Ok(self.decompressed.clone())
+    }
+    /// Decompresses the stream into the given buffer rather than one of the
+    /// stream's own, for when the caller wants to decide where the output
+    /// lives, see BufferProvider.
+    ///
+    /// # Arguments
+    ///
+    /// * 'buffer' - The buffer to decompress into. It's cleared first, and
+    ///   grown if it's too small to hold the output.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the buffer holding the decompressed data,
+    /// or a DeflateError.
+    pub fn decompress_into(&mut self, mut buffer: Vec<u8>) -> Result<Vec<u8>, DeflateError> {
+        buffer.clear();
+        self.decompressed = buffer;
+        self.inflate()?;
+        Ok(mem::take(&mut self.decompressed))
+    }
+    /// Decompresses the stream a piece at a time, handing each piece to each
+    /// once it's too far back for a match to copy from. Blocks are decoded
+    /// partway when need be, so at most 64K of output, plus the end of the
+    /// last match, is held at once however large the blocks are.
+    ///
+    /// # Arguments
+    ///
+    /// * 'buffer' - The buffer to hold the output in. It's cleared first.
+    /// * 'each' - Called with each piece of the output, in order.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the buffer, emptied of output, or the
+    /// first error from decompressing or from each.
+    pub fn decompress_with<E: From<DeflateError>>(
+        &mut self,
+        mut buffer: Vec<u8>,
+        each: &mut dyn FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<Vec<u8>, E> {
+        buffer.clear();
+        self.decompressed = buffer;
+
         while !self.finished {
-            // Initialize header.
-            let mut header: [u8; 3] = [0; 3];
-
-            // Iterate through header, popping the first 3 items from the
-            // bitstream and adding them to header.
-            for header_bit in header.iter_mut() {
-                if let Some(b) = self.bitstream.next() {
-                    *header_bit = b;
-                } else {
-                    return Err(DeflateError::InvalidBlockError(
-                        "Block ran out of bits before a header was specified.",
-                    ));
-                }
+            self.inflate_until(WINDOW_SIZE * 2)?;
+
+            // Draining a little at a time would spend longer moving the
+            // window than decoding.
+            if self.decompressed.len() >= WINDOW_SIZE * 2 {
+                let done = self.decompressed.len() - WINDOW_SIZE;
+                each(&self.decompressed[..done])?;
+                self.decompressed.drain(..done);
             }
+        }
+        if !self.decompressed.is_empty() {
+            each(&self.decompressed)?;
+        }
 
-            self.finished = matches!(header[0], 1);
+        let mut buffer = mem::take(&mut self.decompressed);
+        buffer.clear();
+        Ok(buffer)
+    }
+    /// Decompresses the next piece of the stream, the rest of a block or as
+    /// much of it as brings the output held to 64K, for callers pulling
+    /// output a piece at a time rather than having it handed to them by
+    /// decompress_with. Output too far back for a match to copy from is
+    /// moved onto the end of out, along with everything left once the final
+    /// block is done.
+    ///
+    /// # Arguments
+    ///
+    /// * 'out' - The buffer output is appended to.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either whether there was anything left to
+    /// decompress, or a DeflateError.
+    pub fn inflate_next(&mut self, out: &mut Vec<u8>) -> Result<bool, DeflateError> {
+        if self.finished {
+            let more = !self.decompressed.is_empty();
+            out.append(&mut self.decompressed);
+            return Ok(more);
+        }
 
-            // Main decompression loop.
-            match (header[1], header[2]) {
-                (0, 0) => {
-                    self.block_type_0()?;
-                }
-                (1, 0) => {
-                    self.block_type_1()?;
+        self.inflate_until(WINDOW_SIZE * 2)?;
+        if self.finished {
+            out.append(&mut self.decompressed);
+        } else if self.decompressed.len() >= WINDOW_SIZE * 2 {
+            let done = self.decompressed.len() - WINDOW_SIZE;
+            out.extend_from_slice(&self.decompressed[..done]);
+            self.decompressed.drain(..done);
+        }
+
+        Ok(true)
+    }
+    /// Decodes blocks into self.decompressed until the final block is done.
+    fn inflate(&mut self) -> Result<(), DeflateError> {
+        while !self.finished {
+            self.inflate_until(usize::MAX)?;
+        }
+        Ok(())
+    }
+    /// Decodes onto the end of self.decompressed until it holds limit bytes
+    /// or the block being decoded ends, starting the next block first if
+    /// none is partway through. Matches are copied whole, so up to 257
+    /// bytes more than limit can end up held.
+    fn inflate_until(&mut self, limit: usize) -> Result<(), DeflateError> {
+        let block = match self.block.take() {
+            Some(block) => block,
+            None => self.start_block()?,
+        };
+
+        self.block = match block {
+            Block::Stored(left) => {
+                let len = left.min(limit.saturating_sub(self.decompressed.len()));
+                let stored = self
+                    .bitstream
+                    .read_bytes(len)
+                    .ok_or(DeflateError::UnexpectedEof("a stored block's data"))?;
+                self.decompressed.extend_from_slice(stored);
+                (left > len).then_some(Block::Stored(left - len))
+            }
+            Block::Coded {
+                ll_table,
+                dist_table,
+                fixed,
+            } => match (self.inflate_symbols(&ll_table, &dist_table, limit), fixed) {
+                (Ok(false), _) => Some(Block::Coded {
+                    ll_table,
+                    dist_table,
+                    fixed,
+                }),
+                (result, true) => {
+                    self.scratch.fixed = Some((ll_table, dist_table));
+                    result?;
+                    None
                 }
-                (0, 1) => {
-                    self.block_type_2()?;
+                (result, false) => {
+                    result?;
+                    None
                 }
-                _ => return Err(DeflateError::InvalidBlockError("Invalid BTYPE.")),
-            }
+            },
+        };
+
+        self.finished = self.last && self.block.is_none();
+        Ok(())
+    }
+    /// Reads the header of the next block, and whatever comes before its
+    /// data.
+    fn start_block(&mut self) -> Result<Block, DeflateError> {
+        // BFINAL, then the 2 bit BTYPE.
+        let Some(header) = self.bitstream.read_bits_lsb(3) else {
+            return Err(DeflateError::InvalidBlockError(
+                "Block ran out of bits before a header was specified.",
+            ));
+        };
+
+        self.last = header & 1 == 1;
+
+        match header >> 1 {
+            0 => self.block_type_0(),
+            1 => Ok(self.block_type_1()),
+            2 => self.block_type_2(),
+            _ => Err(DeflateError::InvalidBlockError("Invalid BTYPE.")),
         }
-        Ok(self.decompressed.clone())
     }
-    fn block_type_0(&mut self) -> Result<(), DeflateError> {
-        let len = self
-            .bitstream
-            .by_ref()
-            .skip(5)
-            .take(16)
-            .fold(0u16, |acc, bit| (acc << 1) | (bit as u16))
-            .reverse_bits();
-
-        // Take the subsequent 16 bits as a u16.
-        let nlen = self
-            .bitstream
-            .by_ref()
-            .take(16)
-            .fold(0u16, |acc, bit| (acc << 1) | (bit as u16))
-            .reverse_bits();
+    /// Reads count bits, least significant first, the order deflate packs
+    /// everything but Huffman codes in.
+    ///
+    /// # Arguments
+    ///
+    /// * 'count' - The number of bits to read, at most 16.
+    /// * 'reading' - What the bits are, for the error if they've run out.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the bits, or DeflateError::UnexpectedEof.
+    fn read_bits(&mut self, count: u8, reading: &'static str) -> Result<u16, DeflateError> {
+        match self.bitstream.read_bits_lsb(count) {
+            Some(bits) => Ok(bits as u16),
+            None => Err(DeflateError::UnexpectedEof(reading)),
+        }
+    }
+    /// Looks the next code up in table and moves past it.
+    ///
+    /// # Arguments
+    ///
+    /// * 'table' - The table the symbol's code is from.
+    /// * 'reading' - What the symbol is, for the error if the bits run out.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the symbol, or a DeflateError if the bits
+    /// run out or they start a code no symbol has.
+    #[inline]
+    fn read_symbol(
+        &mut self,
+        table: &DecodeTable,
+        reading: &'static str,
+    ) -> Result<usize, DeflateError> {
+        let left = self.bitstream.remaining();
+        match table.decode(self.bitstream.peek_bits(MAX_CODE_LENGTH)) {
+            Some((symbol, length)) if self.bitstream.skip(length as usize) => Ok(symbol),
+            // Past the end the bits read as 0, which might not start a code
+            // even if the bits that are there could have.
+            _ if left < MAX_CODE_LENGTH as usize => Err(DeflateError::UnexpectedEof(reading)),
+            _ => Err(DeflateError::InvalidCodeLengths(
+                "a code was read that no symbol has.",
+            )),
+        }
+    }
+    fn block_type_0(&mut self) -> Result<Block, DeflateError> {
+        // Skip the remaining bits in the current byte.
+        self.bitstream.align_to_byte();
+
+        let len = self.read_bits(16, "a stored block's length")?;
+        let nlen = self.read_bits(16, "a stored block's length")?;
 
         if len != !nlen {
             return Err(DeflateError::InvalidBlockError(
@@ -109,234 +430,284 @@ impl DeflateStream {
             ));
         }
 
-        // Figure out what byte the current index is in.
-        let byte_idx = self.bitstream.idx / 8;
-
-        self.compressed[byte_idx..len as usize + byte_idx]
-            .iter()
-            .for_each(|x| self.decompressed.push(*x));
-
-        Ok(())
+        Ok(Block::Stored(len as usize))
+    }
+    fn block_type_1(&mut self) -> Block {
+        // The fixed tables are the same every time, so they're only built
+        // once per Scratch.
+        // Will never panic, both sets of lengths make complete codes.
+        let (ll_table, dist_table) = self.scratch.fixed.take().unwrap_or_else(|| {
+            (
+                PrefixTree::from_lengths(&FIXED_CODE_LENGTHS)
+                    .unwrap()
+                    .compile(LITERAL_TABLE_BITS),
+                // Distance codes 30 and 31 get codes too, so using them is
+                // reported as an invalid symbol rather than a code no symbol
+                // has.
+                PrefixTree::from_lengths(&[5; 32])
+                    .unwrap()
+                    .compile(DISTANCE_TABLE_BITS),
+            )
+        });
+
+        Block::Coded {
+            ll_table,
+            dist_table,
+            fixed: true,
+        }
     }
-    fn block_type_1(&mut self) -> Result<(), DeflateError> {
-        let mut prefix_tree = PrefixTree::from_lengths(&FIXED_CODE_LENGTHS);
-
-        //let mut output = Vec::new();
-
-        // Iterate through the bitstream.
-        while let Some(bit) = self.bitstream.by_ref().next() {
-            // Walk the tree and if there is a value, take it and continue.
-            if let Some(value) = prefix_tree.walk(bit) {
-                // If the value less than 256, it is a literal and should be
-                // pushed unaltered to the output stream.
-                if value < 256 {
-                    self.decompressed.push(value as u8);
-                // If it is in the range from 257..285 it is a length code.
-                } else if let 257..=285 = value {
-                    // Get the base and number of extra bits.
-                    let mut length = LENGTH_BASE[value - 257];
-                    let len_extra = LENGTH_EXTRA_BITS[value - 257];
-                    // If length has extra bits, iterate through them, and add
-                    // the value to the base length.
-                    if len_extra > 0 {
-                        let additional_length = self
-                            .bitstream
-                            .by_ref()
-                            .take(len_extra as usize)
-                            .fold(0u16, |acc, bit| (acc << 1) | bit as u16)
-                            .reverse_bits()
-                            >> (16 - len_extra);
-                        length += additional_length;
-                    }
-
-                    // After every length code is a 5 bit distance code.
-                    let mut distance: usize = self
-                        .bitstream
-                        .by_ref()
-                        .take(5)
-                        .fold(0usize, |acc, bit| (acc << 1) | bit as usize);
-
-                    let dist_extra = DISTANCE_EXTRA_BITS[distance];
-                    let dist_base = DISTANCE_BASE[distance];
-
-                    if dist_extra > 0 {
-                        let additional_distance = self
-                            .bitstream
-                            .by_ref()
-                            .take(dist_extra as usize)
-                            .fold(0u16, |acc, bit| (acc << 1) | bit as u16)
-                            .reverse_bits()
-                            >> (16 - dist_extra);
-                        distance = (dist_base + additional_distance) as usize;
-                    } else {
-                        distance = dist_base as usize;
-                    }
-
-                    let start_idx = self.decompressed.len() - distance;
-                    let end_idx = start_idx + length as usize;
-
-                    for idx in start_idx..end_idx {
-                        self.decompressed.push(self.decompressed[idx]);
-                    }
-                } else if value == 256 {
-                    break;
+    /// Decodes literals and matches with the given tables until the end of
+    /// the block, or until the output holds limit bytes.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either whether the block ended, or a
+    /// DeflateError.
+    fn inflate_symbols(
+        &mut self,
+        ll_table: &DecodeTable,
+        dist_table: &DecodeTable,
+        limit: usize,
+    ) -> Result<bool, DeflateError> {
+        while self.decompressed.len() < limit {
+            let sym = self.read_symbol(ll_table, "the end of the block")?;
+            match sym {
+                // Literals are pushed unaltered to the output stream.
+                0..256 => self.decompressed.push(sym as u8),
+                256 => return Ok(true),
+                // Length codes are followed by their extra bits, then a
+                // distance code.
+                257..=285 => {
+                    let len_extra = LENGTH_EXTRA_BITS[sym - 257];
+                    let length =
+                        LENGTH_BASE[sym - 257] + self.read_bits(len_extra, "a match length")?;
+                    let distance = self.read_symbol(dist_table, "a match distance")?;
+
+                    self.copy_match(length as usize, distance)?;
+                }
+                _ => {
+                    return Err(DeflateError::InvalidSymbolError(
+                        sym,
+                        "literal/length codes 286 and 287 are not used.",
+                    ))
                 }
             }
         }
+        Ok(false)
+    }
+    /// Reads the extra bits of a distance code, then copies length bytes
+    /// from that far back in the output to the end of it.
+    fn copy_match(&mut self, length: usize, distance_code: usize) -> Result<(), DeflateError> {
+        let (Some(&dist_extra), Some(&dist_base)) = (
+            DISTANCE_EXTRA_BITS.get(distance_code),
+            DISTANCE_BASE.get(distance_code),
+        ) else {
+            return Err(DeflateError::InvalidSymbolError(
+                distance_code,
+                "distance codes 30 and 31 are not used.",
+            ));
+        };
+
+        let distance =
+            dist_base as usize + self.read_bits(dist_extra, "a match distance")? as usize;
+        if distance > self.window {
+            return Err(DeflateError::DistanceBeyondWindow {
+                distance,
+                window: self.window,
+            });
+        }
+
+        let start_idx = self.decompressed.len().checked_sub(distance).ok_or(
+            DeflateError::DistanceTooFarBack {
+                distance,
+                available: self.decompressed.len(),
+            },
+        )?;
+
+        // The copy may overlap the bytes it is producing, so it has to go a
+        // byte at a time.
+        for idx in start_idx..start_idx + length {
+            self.decompressed.push(self.decompressed[idx]);
+        }
 
         Ok(())
     }
-    fn block_type_2(&mut self) -> Result<(), DeflateError> {
+    fn block_type_2(&mut self) -> Result<Block, DeflateError> {
         // # of literal/length codes - 257 (257..286)
-        let hlit = self
-            .bitstream
-            .by_ref()
-            .take(5)
-            .fold(0u16, |acc, bit| (acc << 1) | bit as u16)
-            .reverse_bits()
-            >> (16 - 5);
-
+        let hlit = self.read_bits(5, "a block's code lengths")? as usize;
         // # of distance codes - 1 (1..32)
-        let hdist = self
-            .bitstream
-            .by_ref()
-            .take(5)
-            .fold(0u8, |acc, bit| (acc << 1) | bit)
-            .reverse_bits()
-            >> (8 - 5);
-
+        let hdist = self.read_bits(5, "a block's code lengths")? as usize;
         // # of code length codes - 4 (4..19)
-        let hclen = self
-            .bitstream
-            .by_ref()
-            .take(4)
-            .fold(0u8, |acc, bit| (acc << 1) | bit)
-            .reverse_bits()
-            >> (8 - 4);
-
-        // Code lengths for the code lengths.
-        let cl_len_vec = self
-            .bitstream
-            .by_ref()
-            .take(((hclen + 4) * 3) as usize)
-            .collect::<Vec<_>>();
-
-        //
-        let mut cl_lengths_sorted = [0; 19];
+        let hclen = self.read_bits(4, "a block's code lengths")? as usize;
+
+        // 30 and 31 can be written, but only 286 literal/length and 30
+        // distance codes exist.
+        if hlit > 29 {
+            return Err(DeflateError::InvalidCodeLengths(
+                "HLIT gives more than 286 literal/length codes.",
+            ));
+        }
+        if hdist > 29 {
+            return Err(DeflateError::InvalidCodeLengths(
+                "HDIST gives more than 30 distance codes.",
+            ));
+        }
 
-        const LENGTH_ORDER: [usize; 19] = [
-            16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
-        ];
+        let mut cl_lengths_sorted = [0; 19];
 
-        // Put code lengths into cl_lengths in the order:
+        // Read the 3 bit code lengths for the code lengths, and put them into
+        // cl_lengths in the order:
         // 16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15
-        for (i, len) in cl_len_vec.chunks(3).enumerate() {
-            let value = len.iter().rev().fold(0u8, |acc, bit| (acc << 1) | *bit);
-
-            cl_lengths_sorted[LENGTH_ORDER[i]] = value;
+        for &symbol in CODE_LENGTH_ORDER.iter().take(hclen + 4) {
+            cl_lengths_sorted[symbol] = self.read_bits(3, "a block's code lengths")? as u8;
         }
 
-        // Generate the code length prefix tree.
-        let mut code_length_tree = PrefixTree::from_lengths(&cl_lengths_sorted);
-
-        let mut code_lengths: Vec<u8> = Vec::new();
-
-        while code_lengths.len() < (hlit as usize + 257 + hdist as usize + 1) {
-            if let Some(bit) = self.bitstream.by_ref().next() {
-                if let Some(symbol) = code_length_tree.walk(bit) {
-                    match symbol {
-                        0..16 => code_lengths.push(symbol as u8),
-                        16..=18 => {
-                            let (number_of_extra, base) = match symbol {
-                                16 => (2, 3usize),
-                                17 => (3, 3usize),
-                                _ => (7, 11usize),
-                            };
-                            let _extra_bits: usize = (self
-                                .bitstream
-                                .by_ref()
-                                .take(number_of_extra)
-                                .fold(0u8, |acc, bit| (acc << 1) | bit)
-                                .reverse_bits()
-                                >> (8 - number_of_extra))
-                                as usize;
-
-                            if symbol == 16 {
-                                for _ in 0..(base + _extra_bits) {
-                                    code_lengths.push(*code_lengths.last().unwrap());
-                                }
-                            } else {
-                                code_lengths.resize(code_lengths.len() + base + _extra_bits, 0);
-                            }
-                        }
-                        _ => {}
-                    }
+        // Generate the code length table, code length codes are at most 7
+        // bits so it never needs a subtable.
+        let code_length_table =
+            PrefixTree::from_lengths_with(&cl_lengths_sorted, &mut self.scratch)
+                .map_err(DeflateError::InvalidPrefixCode)?
+                .compile(7);
+
+        // Borrow the code length buffer from the scratch arena, it is handed
+        // back once the trees have been built.
+        let mut code_lengths = mem::take(&mut self.scratch.code_lengths);
+        code_lengths.clear();
+
+        let total = hlit + 257 + hdist + 1;
+        while code_lengths.len() < total {
+            let symbol = self.read_symbol(&code_length_table, "a block's code lengths")?;
+            let (number_of_extra, base) = match symbol {
+                0..16 => {
+                    code_lengths.push(symbol as u8);
+                    continue;
                 }
+                16 => (2, 3),
+                17 => (3, 3),
+                _ => (7, 11),
+            };
+            let repeat = base + self.read_bits(number_of_extra, "a block's code lengths")? as usize;
+
+            if code_lengths.len() + repeat > total {
+                return Err(DeflateError::InvalidCodeLengths(
+                    "a repeat runs past the last code length.",
+                ));
+            }
+            if symbol == 16 {
+                let &last = code_lengths.last().ok_or(DeflateError::InvalidSymbolError(
+                    symbol,
+                    "repeats a code length before any were given.",
+                ))?;
+                code_lengths.resize(code_lengths.len() + repeat, last);
+            } else {
+                code_lengths.resize(code_lengths.len() + repeat, 0);
             }
         }
 
-        let mut ll_tree = PrefixTree::from_lengths(&code_lengths[0..(hlit as usize + 257)]);
-        let mut dist_tree = PrefixTree::from_lengths(&code_lengths[(hlit as usize + 257)..]);
+        if code_lengths[256] == 0 {
+            return Err(DeflateError::InvalidCodeLengths(
+                "the end of block code has no length.",
+            ));
+        }
 
-        // Nearly identical logic to block type 1.
-        while let Some(bit) = self.bitstream.by_ref().next() {
-            if let Some(sym) = ll_tree.walk(bit) {
-                if sym < 256 {
-                    self.decompressed.push(sym as u8);
-                } else if let 257..285 = sym {
-                    let mut length = LENGTH_BASE[sym - 257];
-                    let len_extra = LENGTH_EXTRA_BITS[sym - 257];
+        let ll_table =
+            PrefixTree::from_lengths_with(&code_lengths[0..(hlit + 257)], &mut self.scratch)
+                .map_err(DeflateError::InvalidPrefixCode)?
+                .compile(LITERAL_TABLE_BITS);
+        let dist_table =
+            PrefixTree::from_lengths_with(&code_lengths[(hlit + 257)..], &mut self.scratch)
+                .map_err(DeflateError::InvalidPrefixCode)?
+                .compile(DISTANCE_TABLE_BITS);
+
+        self.scratch.code_lengths = code_lengths;
+
+        Ok(Block::Coded {
+            ll_table,
+            dist_table,
+            fixed: false,
+        })
+    }
+}
 
-                    if len_extra > 0 {
-                        let additional_length = self
-                            .bitstream
-                            .by_ref()
-                            .take(len_extra as usize)
-                            .fold(0u16, |acc, bit| (acc << 1) | bit as u16)
-                            .reverse_bits()
-                            >> (16 - len_extra);
-                        length += additional_length;
-                    }
-
-                    // Distance codes are encoded.
-                    let mut distance: usize;
-                    loop {
-                        if let Some(bit) = self.bitstream.by_ref().next() {
-                            if let Some(dist) = dist_tree.walk(bit) {
-                                distance = dist;
-                                break;
-                            }
-                        }
-                    }
-
-                    let dist_extra = DISTANCE_EXTRA_BITS[distance];
-                    let dist_base = DISTANCE_BASE[distance];
-
-                    if dist_extra > 0 {
-                        let additional_distance = self
-                            .bitstream
-                            .by_ref()
-                            .take(dist_extra as usize)
-                            .fold(0u16, |acc, bit| (acc << 1) | bit as u16)
-                            .reverse_bits()
-                            >> (16 - dist_extra);
-                        distance = (dist_base + additional_distance) as usize;
-                    } else {
-                        distance = dist_base as usize;
-                    }
-
-                    let start_idx = self.decompressed.len().saturating_sub(distance);
-                    let end_idx = start_idx + length as usize;
-
-                    for idx in start_idx..end_idx {
-                        self.decompressed.push(self.decompressed[idx]);
-                    }
-                } else if sym == 256 {
-                    break;
-                }
-            }
+/// Decompresses a raw deflate stream as it's read, through io::Read. Each
+/// read decodes only as far as it takes to have something to hand over,
+/// stopping partway through a block if need be, so at most 64K of output is
+/// held at once however large the blocks are. The compressed data is
+/// copied in whole when the reader is built, so only the output is bounded.
+///
+/// # Fields
+///
+/// * 'stream' - The stream being decompressed.
+/// * 'pending' - Output decoded but not yet read.
+/// * 'offset' - How much of pending has been read.
+/// * 'done' - Whether the stream has nothing left to decompress.
+///
+/// # Examples
+///
+/// '''
+/// let mut reader = InflateReader::new(&compressed);
+/// io::copy(&mut reader, &mut file)?;
+/// '''
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct InflateReader {
+    stream: DeflateStream,
+    pending: Vec<u8>,
+    offset: usize,
+    done: bool,
+}
+
+#[cfg(feature = "std")]
+impl InflateReader {
+    /// Creates an InflateReader over raw deflate data.
+    ///
+    /// # Arguments
+    ///
+    /// * 'compressed' - The deflate data, without a zlib or gzip wrapper.
+    pub fn new(compressed: &[u8]) -> Self {
+        Self::from_stream(DeflateStream::build(compressed))
+    }
+    /// Creates an InflateReader over a DeflateStream that's already been
+    /// built, such as the one inside a ZlibStream. Nothing it checks after
+    /// the deflate data, like an adler32 trailer, is checked here.
+    ///
+    /// # Arguments
+    ///
+    /// * 'stream' - The stream to decompress.
+    pub fn from_stream(stream: DeflateStream) -> Self {
+        Self {
+            stream,
+            pending: Vec::new(),
+            offset: 0,
+            done: false,
         }
+    }
+    /// Gives back the stream being decompressed, dropping any output that
+    /// hasn't been read.
+    pub fn into_inner(self) -> DeflateStream {
+        self.stream
+    }
+}
 
-        Ok(())
+#[cfg(feature = "std")]
+impl std::io::Read for InflateReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // A piece can be decoded without any output being far enough back
+        // to hand over, so keep going until there's something or nothing
+        // left.
+        while self.offset == self.pending.len() && !self.done {
+            self.pending.clear();
+            self.offset = 0;
+            self.done = !self
+                .stream
+                .inflate_next(&mut self.pending)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        }
+
+        let available = &self.pending[self.offset..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.offset += len;
+        Ok(len)
     }
 }
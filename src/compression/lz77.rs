@@ -0,0 +1,334 @@
+//! Finds the back-references deflate compresses with, see 4 of RFC 1951.
+//! Every position is hashed on its next three bytes, and the positions
+//! sharing a hash are chained together, newest first, so earlier places a
+//! run of bytes appeared can be searched for the longest match. A match is
+//! only taken once the next position has been checked for a longer one,
+//! lazy matching as zlib calls it.
+
+use alloc::{vec, vec::Vec};
+
+/// The furthest back a match can copy from, see 3.2.5 of RFC 1951.
+pub const WINDOW_SIZE: usize = 32 * 1024;
+
+/// The shortest and longest matches deflate can store.
+pub const MIN_MATCH: usize = 3;
+pub const MAX_MATCH: usize = 258;
+
+/// The number of bits in a hash of the next MIN_MATCH bytes.
+const HASH_BITS: u32 = 15;
+
+/// Marks the end of a hash chain.
+const NONE: u32 = u32::MAX;
+
+/// A piece of deflate data before it's given codes.
+///
+/// # Members
+///
+/// * 'Literal' - A byte copied to the output as it is.
+/// * 'Match' - A copy of length bytes from distance bytes back in the
+///   output, length from 3 to 258 and distance from 1 to 32768.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Literal(u8),
+    Match { length: u16, distance: u16 },
+}
+
+impl Token {
+    /// Gets the number of bytes of output the token stands for.
+    pub fn span(&self) -> usize {
+        match self {
+            Token::Literal(_) => 1,
+            Token::Match { length, .. } => *length as usize,
+        }
+    }
+}
+
+/// How hard the match finder looks, with the same meaning as the
+/// parameters of the same names in zlib. Longer chains and a higher
+/// nice_length find longer matches, more slowly. The defaults are zlib's
+/// for its default level, 6.
+///
+/// # Fields
+///
+/// * 'good_length' - Once a match this long has been found, only a quarter
+///   of max_chain is searched for a longer one at the next position.
+/// * 'max_lazy' - Matches this long are taken straight away, without
+///   checking the next position for a longer one. 0 turns lazy
+///   matching off.
+/// * 'nice_length' - Searching stops as soon as a match this long is found,
+///   at most 258.
+/// * 'max_chain' - The most earlier positions checked for each match.
+/// * 'min_length' - The shortest match taken, from 3 to 258. zlib's
+///   filtered strategy only takes matches longer than 5.
+///
+/// # Examples
+///
+/// '''
+/// let options = MatchOptions::new().max_chain(4096).nice_length(258);
+/// let tokens = lz77::tokenize(&data, &options);
+/// '''
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchOptions {
+    pub good_length: usize,
+    pub max_lazy: usize,
+    pub nice_length: usize,
+    pub max_chain: usize,
+    pub min_length: usize,
+}
+
+impl MatchOptions {
+    /// Creates MatchOptions with zlib's parameters for level 6.
+    ///
+    /// # Returns
+    ///
+    /// MatchOptions with default values.
+    pub fn new() -> Self {
+        Self {
+            good_length: 8,
+            max_lazy: 16,
+            nice_length: 128,
+            max_chain: 128,
+            min_length: MIN_MATCH,
+        }
+    }
+    /// Sets how long a match has to be before less searching is done for
+    /// the next one.
+    pub fn good_length(mut self, good_length: usize) -> Self {
+        self.good_length = good_length;
+        self
+    }
+    /// Sets how long a match has to be to be taken without lazy matching.
+    pub fn max_lazy(mut self, max_lazy: usize) -> Self {
+        self.max_lazy = max_lazy;
+        self
+    }
+    /// Sets how long a match has to be to stop searching.
+    pub fn nice_length(mut self, nice_length: usize) -> Self {
+        self.nice_length = nice_length;
+        self
+    }
+    /// Sets the most earlier positions checked for each match.
+    pub fn max_chain(mut self, max_chain: usize) -> Self {
+        self.max_chain = max_chain;
+        self
+    }
+    /// Sets the shortest match taken.
+    pub fn min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The hash chains over data, filled in one position at a time.
+///
+/// # Fields
+///
+/// * 'head' - The newest position with each hash.
+/// * 'prev' - The position before each one with the same hash, indexed by
+///   position modulo the window, as older ones can't be matched.
+/// * 'inserted' - The next position to add to the chains.
+struct Chains<'a> {
+    data: &'a [u8],
+    options: &'a MatchOptions,
+    head: Vec<u32>,
+    prev: Vec<u32>,
+    inserted: usize,
+}
+
+impl<'a> Chains<'a> {
+    fn new(data: &'a [u8], options: &'a MatchOptions) -> Self {
+        Self {
+            data,
+            options,
+            head: vec![NONE; 1 << HASH_BITS],
+            prev: vec![NONE; WINDOW_SIZE.min(data.len()).max(1)],
+            inserted: 0,
+        }
+    }
+    /// Hashes the MIN_MATCH bytes at pos.
+    fn hash(&self, pos: usize) -> usize {
+        let bytes = u32::from_le_bytes([self.data[pos], self.data[pos + 1], self.data[pos + 2], 0]);
+        (bytes.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+    }
+    /// Adds every position before end to the chains.
+    fn insert_until(&mut self, end: usize) {
+        let end = end.min((self.data.len() + 1).saturating_sub(MIN_MATCH));
+        while self.inserted < end {
+            let pos = self.inserted;
+            let hash = self.hash(pos);
+            let slot = pos % self.prev.len();
+            self.prev[slot] = self.head[hash];
+            self.head[hash] = pos as u32;
+            self.inserted += 1;
+        }
+    }
+    /// Finds the longest match for the bytes at pos, then adds pos to the
+    /// chains.
+    ///
+    /// # Arguments
+    ///
+    /// * 'pos' - Where the match would start.
+    /// * 'prev_length' - The length of the match at the position before,
+    ///   which a match here has to beat, 0 if there isn't one.
+    ///
+    /// # Returns
+    ///
+    /// The length and distance of the match, or a length of 0 if there's
+    /// none longer than prev_length and shorter than min_length.
+    fn find(&mut self, pos: usize, prev_length: usize) -> (usize, usize) {
+        self.insert_until(pos);
+        let max = (self.data.len() - pos).min(MAX_MATCH);
+        let min = self.options.min_length.clamp(MIN_MATCH, MAX_MATCH);
+        if max < min {
+            self.insert_until(pos + 1);
+            return (0, 0);
+        }
+
+        let mut chain = self.options.max_chain.max(1);
+        if prev_length >= self.options.good_length {
+            chain = (chain / 4).max(1);
+        }
+        let nice = self.options.nice_length.min(max);
+        let target = &self.data[pos..pos + max];
+        let (mut best, mut distance) = (prev_length.max(min - 1), 0);
+        if best >= max {
+            self.insert_until(pos + 1);
+            return (0, 0);
+        }
+
+        let mut candidate = self.head[self.hash(pos)];
+        while candidate != NONE && chain > 0 {
+            let start = candidate as usize;
+            if pos - start > WINDOW_SIZE {
+                break;
+            }
+            // A longer match has to get past the end of the best so far.
+            if self.data[start + best] == target[best] {
+                let length = self.data[start..]
+                    .iter()
+                    .zip(target)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best {
+                    (best, distance) = (length, pos - start);
+                    if length >= nice {
+                        break;
+                    }
+                }
+            }
+
+            // Chains only ever go back, anything else is a slot reused by a
+            // newer position.
+            let older = self.prev[start % self.prev.len()];
+            if older == NONE || older as usize >= start {
+                break;
+            }
+            candidate = older;
+            chain -= 1;
+        }
+
+        self.insert_until(pos + 1);
+        match distance {
+            0 => (0, 0),
+            _ => (best, distance),
+        }
+    }
+}
+
+/// Turns data into tokens, literals and the matches found in the hash
+/// chains.
+///
+/// # Arguments
+///
+/// * 'data' - The bytes to tokenize.
+/// * 'options' - How hard to look for matches.
+///
+/// # Returns
+///
+/// The tokens, which span data exactly.
+pub fn tokenize(data: &[u8], options: &MatchOptions) -> Vec<Token> {
+    let mut chains = Chains::new(data, options);
+    let mut tokens = Vec::with_capacity(data.len() / 2);
+    let mut ahead = None;
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let (length, distance) = match ahead.take() {
+            Some(found) => found,
+            None => chains.find(pos, 0),
+        };
+        if length == 0 {
+            tokens.push(Token::Literal(data[pos]));
+            pos += 1;
+            continue;
+        }
+
+        // If the next position has a longer match, this one becomes a
+        // literal and that match is considered in its place.
+        if length < options.max_lazy {
+            let next = chains.find(pos + 1, length);
+            if next.0 > 0 {
+                tokens.push(Token::Literal(data[pos]));
+                pos += 1;
+                ahead = Some(next);
+                continue;
+            }
+        }
+
+        tokens.push(Token::Match {
+            length: length as u16,
+            distance: distance as u16,
+        });
+        pos += length;
+    }
+
+    tokens
+}
+
+/// Turns data into tokens the way zlib's run length strategy does, only
+/// matching runs of the byte before, at a distance of 1. Much faster than
+/// tokenize, and good enough for images whose rows have been filtered to
+/// long runs of the same byte.
+///
+/// # Arguments
+///
+/// * 'data' - The bytes to tokenize.
+///
+/// # Returns
+///
+/// The tokens, which span data exactly.
+pub fn tokenize_runs(data: &[u8]) -> Vec<Token> {
+    let mut tokens = Vec::with_capacity(data.len() / 2);
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let length = match pos {
+            0 => 0,
+            _ => data[pos..]
+                .iter()
+                .take(MAX_MATCH)
+                .take_while(|&&byte| byte == data[pos - 1])
+                .count(),
+        };
+        if length < MIN_MATCH {
+            tokens.push(Token::Literal(data[pos]));
+            pos += 1;
+            continue;
+        }
+
+        tokens.push(Token::Match {
+            length: length as u16,
+            distance: 1,
+        });
+        pos += length;
+    }
+
+    tokens
+}
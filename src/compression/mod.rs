@@ -1,6 +1,12 @@
-pub mod adler;
+pub mod backend;
 pub mod bits;
-pub mod crc;
+pub mod checksums;
+pub mod deflate;
+pub mod gzip;
 pub mod inflate;
+pub mod lz77;
 pub mod prefix;
+pub mod scratch;
 pub mod zlib;
+
+pub use checksums::crc;
@@ -1,4 +1,7 @@
-use std::{cell::RefCell, cmp::Ordering, fmt, fmt::Display, rc::Rc};
+use alloc::{format, string::String, vec, vec::Vec};
+use core::{cmp::Ordering, error::Error, fmt, fmt::Display};
+
+use super::scratch::Scratch;
 
 /// Code lengths from section 3.2.6 of RFC 1951.
 pub const FIXED_CODE_LENGTHS: [u8; 288] = [
@@ -36,6 +39,70 @@ pub const DISTANCE_BASE: [u16; 30] = [
     2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
 ];
 
+/// The order the code lengths of the code length alphabet are stored in at
+/// the start of a dynamic block, see 3.2.7 of RFC 1951.
+pub const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// The longest code a literal/length or distance symbol can have.
+pub const MAX_CODE_LENGTH: u8 = 15;
+
+/// The Kraft sum of a complete prefix code, in the 2^-15ths PrefixError
+/// counts it in.
+pub const KRAFT_COMPLETE: u32 = 1 << MAX_CODE_LENGTH;
+
+/// Why a set of code lengths can't make a prefix code.
+///
+/// # Members
+///
+/// * 'OverSubscribed' - There are more codes of some lengths than there are
+///   codes of those lengths to give out, so some would be the start
+///   of others. Holds the Kraft sum of the lengths in 2^-15ths, over
+///   KRAFT_COMPLETE.
+/// * 'Incomplete' - Some codes are left over, which no symbol has. Holds
+///   the Kraft sum, under KRAFT_COMPLETE.
+/// * 'TooLong' - A length is over MAX_CODE_LENGTH, with the length.
+/// * 'TooManySymbols' - More symbols are used than codes of at most
+///   max_length bits can tell apart, or max_length is 0, so code_lengths
+///   can't give them all codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixError {
+    OverSubscribed { kraft: u32 },
+    Incomplete { kraft: u32 },
+    TooLong(u8),
+    TooManySymbols { used: usize, max_length: u8 },
+}
+
+impl Display for PrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefixError::OverSubscribed { kraft } => write!(
+                f,
+                "OverSubscribed: the code lengths' Kraft sum is {}/{}, more codes than there are.",
+                kraft, KRAFT_COMPLETE
+            ),
+            PrefixError::Incomplete { kraft } => write!(
+                f,
+                "Incomplete: the code lengths' Kraft sum is {}/{}, leaving codes no symbol has.",
+                kraft, KRAFT_COMPLETE
+            ),
+            PrefixError::TooLong(len) => write!(
+                f,
+                "TooLong: a code length of {} is over the longest allowed, {}.",
+                len, MAX_CODE_LENGTH
+            ),
+            PrefixError::TooManySymbols { used, max_length } => write!(
+                f,
+                "TooManySymbols: {} symbols can't all have codes of at most {} bits.",
+                used, max_length
+            ),
+        }
+    }
+}
+
+impl Error for PrefixError {}
+
 /// A struct for representing codes of differing bit lengths, codes are stored
 /// little endian, meant to be read from most significant bit to least
 /// significant bit.
@@ -44,18 +111,18 @@ pub const DISTANCE_BASE: [u16; 30] = [
 ///
 /// * 'buffer' - A u32 acting as a bit buffer.
 /// * 'length' - A u8 specifying how many of the bits in the buffer are actually
-///         a part of the code. A length of 2 would mean that the 2 least
-///         significant bits hold the code.
+///   a part of the code. A length of 2 would mean that the 2 least
+///   significant bits hold the code.
 ///
 /// # Methods
 ///
 /// * 'new' - Generates a new empty Code.
 /// * 'from' - Accepts a buffer and a length and creates a Code struct from
-///         those given values.
+///   those given values.
 /// * 'push' - Accepts a buffer and a length and pushes length bits of value
-///         into the bit buffer.
+///   into the bit buffer.
 /// * 'push_bit' - Accepts a single u8 which is normalized to represent either
-///         a 0 or 1, and pushes it to the buffer.
+///   a 0 or 1, and pushes it to the buffer.
 ///
 /// # Examples
 ///
@@ -96,7 +163,7 @@ impl Code {
     ///
     /// * 'code' - The u32 value containing the binary code.
     /// * 'length' - A u8 representing the number of bits of code are a part
-    ///         of the binary code.
+    ///   of the binary code.
     ///
     /// # Returns
     ///
@@ -108,18 +175,6 @@ impl Code {
             index: 0,
         }
     }
-    /// Accepts a length and a u32 as a buffer, and pushes length bits of that
-    /// buffer into self.code and increments self.length by the appropriate
-    /// amount.
-    ///
-    /// # Arguments
-    ///
-    /// * 'buffer' - A u32 acting as a bit buffer containing the bits to push.
-    /// * 'length' - The number of bits to push.
-    pub fn push(&mut self, buffer: u32, length: u8) {
-        self.buffer = (self.buffer << length) | buffer;
-        self.length += length;
-    }
     /// Accepts either a 0 or 1 and pushes that bit to self. If a non-binary
     /// value is entered it will correct it to a 1 instead of raising an error.
     ///
@@ -131,7 +186,8 @@ impl Code {
             0 => 0,
             1 => 1,
             _ => {
-                eprintln!("Warning: Non-binary value passed to push, value corrected to a 1.");
+                #[cfg(feature = "std")]
+                std::eprintln!("Warning: Non-binary value passed to push, value corrected to a 1.");
                 1
             }
         };
@@ -160,7 +216,7 @@ impl Iterator for Code {
 }
 
 impl Display for Code {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
             "{:0length$b}",
@@ -175,26 +231,26 @@ impl Display for Code {
 /// # Fields
 ///
 /// * 'value' - An option containing a usize or None, None represents that
-///         the node is a branch rather than a leaf, if a value is present,
-///         the node should be on the edge of the tree.
+///   the node is a branch rather than a leaf, if a value is present,
+///   the node should be on the edge of the tree.
 /// * 'significance' - A u64 value used to sort the nodes on the tree. If
-///         implementing a frequency based Huffman tree, significance can
-///         be used to represent the frequency of each node. If, used to
-///         generate prefix codes, significance represents the code.
+///   implementing a frequency based Huffman tree, significance can
+///   be used to represent the frequency of each node. If, used to
+///   generate prefix codes, significance represents the code.
 /// * 'code' - An instance of the Code struct which contains a u32 bit buffer
-///         containing the code, and a length representing what quantity of bits
-///         in the buffer are part of the code.
-/// * 'left' - An option holding a Rc<RefCell<>> reference to the child node attached to
-///         the left.
-/// * 'right' - An option holding a Rc<RefCell<>> reference to the child node attached to
-///         the right.
+///   containing the code, and a length representing what quantity of bits
+///   in the buffer are part of the code.
+/// * 'left' - An option holding the index in PrefixTree::nodes of the child
+///   node attached to the left.
+/// * 'right' - An option holding the index in PrefixTree::nodes of the child
+///   node attached to the right.
 #[derive(Debug, Clone)]
 pub struct Node {
     pub value: Option<usize>,
     pub significance: u64,
     pub code: Code,
-    pub left: Option<Rc<RefCell<Node>>>,
-    pub right: Option<Rc<RefCell<Node>>>,
+    pub left: Option<usize>,
+    pub right: Option<usize>,
 }
 
 impl Node {
@@ -215,7 +271,7 @@ impl Node {
 }
 
 impl Display for Node {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "({:?}, {})", self.value, self.code)
     }
 }
@@ -248,17 +304,16 @@ impl Default for Node {
     }
 }
 
-/// A binary tree containing prefix codes.
+/// A binary tree containing prefix codes. The nodes are kept in a Vec and
+/// refer to their children by index, rather than through shared pointers,
+/// so a tree is plain owned data that is Send and Sync.
 ///
 /// # Fields
 ///
-/// * 'root' - The root node to which all others are connected.
-/// * 'leaves' - A vector containing all the leaf nodes/nodes with values.
-/// * 'current' - The most recent node to be traversed.
-#[derive(Debug)]
+/// * 'nodes' - Every node in the tree, the root node is always at index 0.
+#[derive(Debug, Clone)]
 pub struct PrefixTree {
-    pub root: Rc<RefCell<Node>>,
-    pub current: Rc<RefCell<Node>>,
+    pub nodes: Vec<Node>,
 }
 
 impl PrefixTree {
@@ -269,8 +324,7 @@ impl PrefixTree {
     /// A PrefixTree with default values.
     pub fn new() -> Self {
         Self {
-            root: Rc::new(RefCell::new(Node::new())),
-            current: Rc::new(RefCell::new(Node::new())),
+            nodes: vec![Node::new()],
         }
     }
     /// Accepts a code as input and then creates the branches required to reach
@@ -279,9 +333,9 @@ impl PrefixTree {
     /// # Arguments
     ///
     /// * 'code' - A Code struct containing the address of the node to be
-    ///         added to the tree.
+    ///   added to the tree.
     /// * 'value' - A usize value containing the value to be stored at the
-    ///         new node.
+    ///   new node.
     ///
     /// # Examples
     ///
@@ -292,58 +346,72 @@ impl PrefixTree {
     ///
     /// tree.insert_code(new_code, 255);
     ///
-    /// let mut value = 0;
-    /// for bit in code {
-    ///     if let Some(v) = tree.walk(bit) {
-    ///         value = v;
-    ///     }
-    /// }
-    ///
-    /// assert_eq!(value, 255);
+    /// // The code is read first bit lowest, so 0b110.
+    /// assert_eq!(tree.compile(9).decode(0b110), Some((255, 3)));
     /// '''
     pub fn insert_code(&mut self, code: Code, value: usize) {
-        let mut current = Rc::clone(&self.root);
+        let mut current = 0;
         let mut current_code = Code::new();
         for bit in code {
-            match bit {
-                0 => {
-                    if current.borrow().left.is_none() {
-                        current.borrow_mut().left = Some(Rc::new(RefCell::new(Node::new())));
-                    }
-                    let left = current.borrow().left.as_ref().unwrap().clone();
-                    current = left;
-                    current_code.push_bit(bit);
-                    current.borrow_mut().code = current_code;
-                }
-                1 => {
-                    if current.borrow().right.is_none() {
-                        current.borrow_mut().right = Some(Rc::new(RefCell::new(Node::new())));
+            let child = match bit {
+                0 => self.nodes[current].left,
+                1 => self.nodes[current].right,
+                _ => continue,
+            };
+            let child = match child {
+                Some(child) => child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(Node::new());
+                    if bit == 0 {
+                        self.nodes[current].left = Some(child);
+                    } else {
+                        self.nodes[current].right = Some(child);
                     }
-                    let left = current.borrow().right.as_ref().unwrap().clone();
-                    current = left;
-                    current_code.push_bit(bit);
-                    current.borrow_mut().code = current_code;
+                    child
                 }
-                _ => {}
-            }
+            };
+            current = child;
+            current_code.push_bit(bit);
+            self.nodes[current].code = current_code;
         }
-        current.borrow_mut().value = Some(value);
-        current.borrow_mut().code = code;
-        self.current = self.root.clone();
+        self.nodes[current].value = Some(value);
+        self.nodes[current].code = code;
     }
     /// Generates a prefix code tree from the given bit lengths.
     ///
     /// # Arguments
     ///
     /// * 'code_lengths' An array of u8 values representing the number of
-    ///         bits in the code to represent a certain symbol, the symbol
-    ///         is the index of the value. So, bit_lengths[1] would equal
-    ///         the length of the code for 1.
+    ///   bits in the code to represent a certain symbol, the symbol
+    ///   is the index of the value. So, bit_lengths[1] would equal
+    ///   the length of the code for 1.
     ///
     /// # Returns
     ///
-    /// A new instance of PrefixTree built from the bit lengths provided.
-    pub fn from_lengths(code_lengths: &[u8]) -> Self {
+    /// A result containing either a new instance of PrefixTree built from
+    /// the bit lengths provided, or a PrefixError if they don't make a
+    /// complete prefix code. As 3.2.7 of RFC 1951 allows, no codes at all
+    /// and a single code of 1 bit are accepted too.
+    pub fn from_lengths(code_lengths: &[u8]) -> Result<Self, PrefixError> {
+        Self::from_lengths_with(code_lengths, &mut Scratch::new())
+    }
+    /// The same as from_lengths, but keeps its working buffers in the given
+    /// Scratch instead of allocating new ones.
+    ///
+    /// # Arguments
+    ///
+    /// * 'code_lengths' - The code length of each symbol, see from_lengths.
+    /// * 'scratch' - The Scratch to borrow working buffers from.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either a new instance of PrefixTree, or a
+    /// PrefixError, see from_lengths.
+    pub fn from_lengths_with(
+        code_lengths: &[u8],
+        scratch: &mut Scratch,
+    ) -> Result<Self, PrefixError> {
         // Define an array to hold the amount of times a code length appears.
         // The index is the code length, and the value at the index is the
         // number of occurances.
@@ -363,8 +431,27 @@ impl PrefixTree {
             acc
         });
 
+        if max_length > MAX_CODE_LENGTH as usize {
+            return Err(PrefixError::TooLong(max_length as u8));
+        }
+
+        // Each code of length l takes up 2^-l of the codes there could be,
+        // counted here in 2^-15ths. A complete code takes up all of them.
+        let kraft: u32 = (1..=max_length)
+            .map(|len| occurances[len] << (MAX_CODE_LENGTH as usize - len))
+            .sum();
+        let single = code_lengths.iter().filter(|&&len| len > 0).count() == 1;
+        if kraft > KRAFT_COMPLETE {
+            return Err(PrefixError::OverSubscribed { kraft });
+        }
+        if kraft < KRAFT_COMPLETE && kraft > 0 && !(single && max_length == 1) {
+            return Err(PrefixError::Incomplete { kraft });
+        }
+
         // Intialize next_code and code as zeroes.
-        let mut next_code = vec![0; max_length + 1];
+        let next_code = &mut scratch.next_code;
+        next_code.clear();
+        next_code.resize(max_length + 1, 0);
         let mut code = 0;
         occurances[0] = 0;
 
@@ -373,7 +460,9 @@ impl PrefixTree {
             next_code[i] = code;
         }
 
-        let mut codes = vec![None; code_lengths.len()];
+        let codes = &mut scratch.codes;
+        codes.clear();
+        codes.resize(code_lengths.len(), None);
 
         for j in 0..code_lengths.len() {
             let len = code_lengths[j] as usize;
@@ -387,70 +476,84 @@ impl PrefixTree {
 
         for (index, code) in codes.iter().enumerate() {
             if let Some(c) = code {
-                let code_struct = Code::from(c.to_owned(), code_lengths[index]);
+                let code_struct = Code::from(*c, code_lengths[index]);
                 tree.insert_code(code_struct, index);
             }
         }
 
-        tree
+        Ok(tree)
     }
-    /// Accepts a u8 representing a binary value and walks that direction on
-    /// the tree. Will panic if a non-binary value is given.
+    /// Compiles the tree into a DecodeTable, which decodes a whole code
+    /// with one or two lookups instead of a step per bit.
     ///
     /// # Arguments
     ///
-    /// * 'direction' - A u8 containing which direction on the tree to step.
-    ///         Can be either 0 or 1.
+    /// * 'primary_bits' - How many bits the first lookup takes. Codes no
+    ///   longer than this are decoded by it alone, longer ones go on
+    ///   to a subtable.
     ///
     /// # Returns
     ///
-    /// If the node is a branch and does not hold a value, None will be
-    /// returned, otherwise, the value at that leaf will be returned.
-    ///
-    /// # Examples
-    ///
-    /// '''
-    /// let mut tree = PrefixTree::new();
-    ///
-    /// tree.insert_code(Code::from(0b111, 3), 255);
-    ///
-    /// assert_eq!(tree.walk(1), None);
-    /// assert_eq!(tree.walk(1), None);
-    /// assert_eq!(tree.walk(1), Some(255));
-    /// '''
-    pub fn walk(&mut self, direction: u8) -> Option<usize> {
-        assert!(direction < 2);
-
-        match direction {
-            0 => {
-                let tmp = self.current.clone();
-                let tmp_borrow = tmp.borrow();
-                if let Some(left) = tmp_borrow.left.clone() {
-                    self.current = left.clone();
-                    if let Some(value) = self.current.clone().borrow().value {
-                        self.current = self.root.clone();
-                        return Some(value);
-                    } else {
-                        return None;
-                    }
-                }
+    /// A DecodeTable holding every code in the tree.
+    pub fn compile(&self, primary_bits: u8) -> DecodeTable {
+        let mask = (1usize << primary_bits) - 1;
+        // Bit reversed, as the first bit of a code is the first one read,
+        // and the table is indexed with the first bit read lowest.
+        let leaves = || {
+            self.nodes.iter().filter_map(|node| {
+                let length = node.code.length;
+                let value = node.value?;
+                (length > 0).then(|| {
+                    let reversed = node.code.buffer.reverse_bits() >> (32 - length as u32);
+                    (reversed as usize, length, value)
+                })
+            })
+        };
+
+        // Each subtable has to be big enough for the longest code it holds.
+        let mut subtable_bits = vec![0u8; mask + 1];
+        for (reversed, length, _) in leaves() {
+            if length > primary_bits {
+                let bits = &mut subtable_bits[reversed & mask];
+                *bits = (*bits).max(length - primary_bits);
             }
-            1 => {
-                let tmp = self.current.clone();
-                let tmp_borrow = tmp.borrow();
-                if let Some(right) = tmp_borrow.right.clone() {
-                    self.current = right.clone();
-                    if let Some(value) = self.current.clone().borrow().value {
-                        self.current = self.root.clone();
-                        return Some(value);
-                    } else {
-                        return None;
-                    }
+        }
+
+        let mut entries = vec![Entry::Empty; mask + 1];
+        for (index, &bits) in subtable_bits.iter().enumerate() {
+            if bits > 0 {
+                entries[index] = Entry::Subtable {
+                    offset: entries.len() as u32,
+                    bits,
+                };
+                entries.resize(entries.len() + (1 << bits), Entry::Empty);
+            }
+        }
+
+        // A code fills every entry whose low bits are the code, whatever
+        // the bits after it.
+        for (reversed, length, value) in leaves() {
+            let symbol = Entry::Symbol {
+                symbol: value as u16,
+                length,
+            };
+            if length <= primary_bits {
+                for index in (reversed..=mask).step_by(1 << length) {
+                    entries[index] = symbol;
+                }
+            } else if let Entry::Subtable { offset, bits } = entries[reversed & mask] {
+                let rest = reversed >> primary_bits;
+                let end = (1usize << bits) - 1;
+                for index in (rest..=end).step_by(1 << (length - primary_bits)) {
+                    entries[offset as usize + index] = symbol;
                 }
             }
-            _ => {}
         }
-        None
+
+        DecodeTable {
+            primary_bits,
+            entries,
+        }
     }
 }
 
@@ -463,34 +566,259 @@ impl Default for PrefixTree {
 impl fmt::Display for PrefixTree {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fn format_node(
-            node: &Option<Rc<RefCell<Node>>>,
+            tree: &PrefixTree,
+            node: Option<usize>,
             prefix: String,
             is_right: bool,
             f: &mut fmt::Formatter<'_>,
         ) -> fmt::Result {
-            if let Some(node) = node {
+            if let Some(node) = node.map(|index| &tree.nodes[index]) {
                 writeln!(
                     f,
                     "{}{}({}{})",
                     prefix,
                     if is_right { "├── " } else { "└── " },
-                    node.borrow().code,
-                    if let Some(value) = node.borrow().value {
+                    node.code,
+                    if let Some(value) = node.value {
                         format!(": {}", value)
                     } else {
                         String::new()
                     }
                 )?;
                 let new_prefix = format!("{}{}", prefix, if is_right { "│   " } else { "    " });
-                format_node(&node.borrow().right, new_prefix.clone(), true, f)?;
-                format_node(&node.borrow().left, new_prefix, false, f)?;
+                format_node(tree, node.right, new_prefix.clone(), true, f)?;
+                format_node(tree, node.left, new_prefix, false, f)?;
             }
             Ok(())
         }
 
-        writeln!(f, "{}", self.root.borrow())?;
-        format_node(&self.root.borrow().right, String::new(), true, f)?;
-        format_node(&self.root.borrow().left, String::new(), false, f)
+        let root = &self.nodes[0];
+        writeln!(f, "{}", root)?;
+        format_node(self, root.right, String::new(), true, f)?;
+        format_node(self, root.left, String::new(), false, f)
+    }
+}
+
+/// An entry in a DecodeTable.
+///
+/// # Members
+///
+/// * 'Empty' - No code starts with these bits.
+/// * 'Symbol' - A code starts with these bits, with its symbol and length.
+/// * 'Subtable' - Codes longer than the primary lookup start with these
+///   bits, and the bits after them index a subtable holding 1 << bits
+///   entries from offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    Empty,
+    Symbol { symbol: u16, length: u8 },
+    Subtable { offset: u32, bits: u8 },
+}
+
+/// A PrefixTree compiled into lookup tables, the way zlib decodes Huffman
+/// codes. The next primary_bits bits, first read lowest, index the primary
+/// table, which gives either a symbol and the length of its code, or a
+/// subtable indexed by the bits after them for the codes too long for it.
+///
+/// # Fields
+///
+/// * 'primary_bits' - How many bits index the primary table.
+/// * 'entries' - The primary table, followed by every subtable.
+///
+/// # Examples
+///
+/// '''
+/// let table = PrefixTree::from_lengths(&FIXED_CODE_LENGTHS)?.compile(9);
+///
+/// // 0b0011_0000, the code for 0, read first bit lowest.
+/// assert_eq!(table.decode(0b0000_1100), Some((0, 8)));
+/// '''
+#[derive(Debug, Clone)]
+pub struct DecodeTable {
+    primary_bits: u8,
+    entries: Vec<Entry>,
+}
+
+impl DecodeTable {
+    /// Decodes the code at the start of bits.
+    ///
+    /// # Arguments
+    ///
+    /// * 'bits' - The next bits of the stream, first read lowest, at least
+    ///   as many as the longest code. Any past the end of the stream
+    ///   should be 0.
+    ///
+    /// # Returns
+    ///
+    /// The symbol and the length of its code, or None if no code starts
+    /// with bits.
+    #[inline]
+    pub fn decode(&self, bits: u32) -> Option<(usize, u8)> {
+        let bits = bits as usize;
+        let mut entry = self.entries[bits & ((1 << self.primary_bits) - 1)];
+        if let Entry::Subtable {
+            offset,
+            bits: sub_bits,
+        } = entry
+        {
+            let index = (bits >> self.primary_bits) & ((1 << sub_bits) - 1);
+            entry = self.entries[offset as usize + index];
+        }
+
+        match entry {
+            Entry::Symbol { symbol, length } => Some((symbol as usize, length)),
+            _ => None,
+        }
     }
 }
 
+/// An item in one level of package-merge, a symbol or a package of two
+/// items from the level before.
+#[derive(Debug, Clone, Copy)]
+enum Item {
+    Leaf(usize),
+    Package(usize, usize),
+}
+
+/// Works out the optimal code lengths of at most max_length bits for an
+/// alphabet, with the package-merge algorithm. Every used symbol starts out
+/// as a coin at each length from max_length up to 1; at each step up, the
+/// lightest pairs of coins are packaged together and merged back in with
+/// the symbols. The 2n - 2 lightest coins of the last step are taken, and
+/// each symbol's code length is the number of its coins among them. At
+/// least two symbols are always given codes, so the code is complete even
+/// if one symbol or none is used.
+///
+/// # Arguments
+///
+/// * 'frequencies' - How many times each symbol is used.
+/// * 'max_length' - The longest a code can be, at least 1. There have to
+///   be no more than 2^max_length symbols used.
+///
+/// # Returns
+///
+/// A result containing either the code length of each symbol, 0 for
+/// symbols without a code, or PrefixError::TooManySymbols.
+///
+/// # Examples
+///
+/// '''
+/// assert_eq!(code_lengths(&[1, 1, 2, 4], 15)?, [3, 3, 2, 1]);
+/// assert_eq!(code_lengths(&[1, 1, 2, 4], 2)?, [2, 2, 2, 2]);
+/// assert!(code_lengths(&[1, 1, 2, 4], 1).is_err());
+/// '''
+pub fn code_lengths(frequencies: &[u32], max_length: u8) -> Result<Vec<u8>, PrefixError> {
+    let mut used: Vec<usize> = (0..frequencies.len())
+        .filter(|&symbol| frequencies[symbol] > 0)
+        .collect();
+    for (symbol, &frequency) in frequencies.iter().enumerate() {
+        if used.len() >= 2 {
+            break;
+        }
+        if frequency == 0 {
+            used.push(symbol);
+        }
+    }
+    used.sort_by_key(|&symbol| (frequencies[symbol], symbol));
+
+    // Past 2^63 the shift overflows, but no more symbols than that fit in
+    // memory anyway.
+    let fits = 1usize
+        .checked_shl(max_length as u32)
+        .is_none_or(|codes| used.len() <= codes);
+    if max_length == 0 || !fits {
+        return Err(PrefixError::TooManySymbols {
+            used: used.len(),
+            max_length,
+        });
+    }
+
+    let mut lengths = vec![0; frequencies.len()];
+    if used.len() < 2 {
+        return Ok(lengths);
+    }
+
+    let leaves: Vec<(u64, Item)> = used
+        .iter()
+        .map(|&symbol| (frequencies[symbol] as u64, Item::Leaf(symbol)))
+        .collect();
+
+    // levels[0] is the longest codes, each level after it one bit shorter.
+    let mut levels = vec![leaves.clone()];
+    for _ in 1..max_length {
+        let below = &levels[levels.len() - 1];
+        let packages = (0..below.len() / 2).map(|pair| {
+            let (a, b) = (2 * pair, 2 * pair + 1);
+            (below[a].0 + below[b].0, Item::Package(a, b))
+        });
+
+        // Merged by weight, symbols before packages of the same weight.
+        let mut merged = Vec::with_capacity(leaves.len() + below.len() / 2);
+        let mut packages = packages.peekable();
+        let mut symbols = leaves.iter().copied().peekable();
+        loop {
+            let next = match (symbols.peek(), packages.peek()) {
+                (Some(symbol), Some(package)) if symbol.0 <= package.0 => symbols.next(),
+                (_, Some(_)) => packages.next(),
+                (Some(_), None) => symbols.next(),
+                (None, None) => break,
+            };
+            merged.extend(next);
+        }
+        levels.push(merged);
+    }
+
+    // Count each symbol's coins, following packages down the levels.
+    let mut pending: Vec<(usize, usize)> = (0..2 * used.len() - 2)
+        .map(|index| (levels.len() - 1, index))
+        .collect();
+    while let Some((level, index)) = pending.pop() {
+        match levels[level].get(index).map(|item| item.1) {
+            Some(Item::Leaf(symbol)) => lengths[symbol] += 1,
+            Some(Item::Package(a, b)) => pending.extend([(level - 1, a), (level - 1, b)]),
+            None => {}
+        }
+    }
+
+    Ok(lengths)
+}
+
+/// Assigns canonical codes from code lengths, the same codes from_lengths
+/// gives, see 3.2.2 of RFC 1951. Codes of each length are handed out in
+/// symbol order, each length starting where the shorter ones left off.
+///
+/// # Arguments
+///
+/// * 'lengths' - The code length of each symbol, at most MAX_CODE_LENGTH,
+///   0 for symbols without a code.
+///
+/// # Returns
+///
+/// The code of each symbol, most significant bit first, 0 for symbols
+/// without one.
+pub fn canonical_codes(lengths: &[u8]) -> Vec<u32> {
+    let mut occurances = [0u32; MAX_CODE_LENGTH as usize + 1];
+    for &length in lengths {
+        occurances[length as usize] += 1;
+    }
+    occurances[0] = 0;
+
+    let mut next_code = [0u32; MAX_CODE_LENGTH as usize + 1];
+    let mut code = 0;
+    for bits in 1..next_code.len() {
+        code = (code + occurances[bits - 1]) << 1;
+        next_code[bits] = code;
+    }
+
+    lengths
+        .iter()
+        .map(|&length| match length {
+            0 => 0,
+            _ => {
+                let code = next_code[length as usize];
+                next_code[length as usize] += 1;
+                code
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,55 @@
+use alloc::vec::Vec;
+
+use super::prefix::DecodeTable;
+
+/// A small arena of reusable buffers for the short-lived allocations made
+/// while decoding dynamic Huffman blocks. Each buffer is cleared rather than
+/// freed between uses, so once it has grown to fit the largest block it has
+/// seen no further allocations are needed.
+///
+/// A single Scratch can be moved between DeflateStreams with
+/// DeflateStream::take_scratch and DeflateStream::set_scratch, which saves
+/// the allocator a lot of work when decoding thousands of small images.
+///
+/// # Fields
+///
+/// * 'code_lengths' - Holds the literal/length and distance code lengths
+///   decoded from the header of a dynamic block.
+/// * 'next_code' - Holds the smallest unused code for each code length while
+///   building a PrefixTree.
+/// * 'codes' - Holds the code assigned to each symbol while building a
+///   PrefixTree.
+/// * 'fixed' - The literal/length and distance tables for fixed Huffman
+///   blocks, built the first time one is decoded.
+///
+/// # Examples
+///
+/// '''
+/// let mut scratch = Scratch::new();
+///
+/// for bytes in streams {
+///     let mut deflate = DeflateStream::build(&bytes);
+///     deflate.set_scratch(scratch);
+///     let data = deflate.decompress()?;
+///     scratch = deflate.take_scratch();
+/// }
+/// '''
+#[derive(Debug, Default, Clone)]
+pub struct Scratch {
+    pub(crate) code_lengths: Vec<u8>,
+    pub(crate) next_code: Vec<u32>,
+    pub(crate) codes: Vec<Option<u32>>,
+    pub(crate) fixed: Option<(DecodeTable, DecodeTable)>,
+}
+
+impl Scratch {
+    /// Creates a new, empty Scratch. No memory is allocated until it is
+    /// first used.
+    ///
+    /// # Returns
+    ///
+    /// A Scratch with empty buffers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
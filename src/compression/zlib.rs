@@ -1,19 +1,52 @@
-use std::{error::Error, fmt::Display};
+use alloc::vec::Vec;
+use core::{error::Error, fmt::Display};
 
-use crate::compression::adler::adler32;
+use crate::compression::checksums::adler::{adler32, Adler32};
+use crate::error;
 
 use super::{
-    bits::BitVector64,
-    inflate::{DeflateError, DeflateStream},
+    deflate::{self, CompressionLevel, DeflateOptions},
+    inflate::{DeflateError, DeflateStream, WINDOW_SIZE},
 };
 
+/// The most data a stored deflate block can hold, see 3.2.4 of RFC 1951.
+pub(crate) const MAX_STORED_BLOCK: usize = 0xffff;
+
+/// Deflate with a 32K window, and a check value making the header a
+/// multiple of 31, see 2.2 of RFC 1950.
+pub(crate) const ZLIB_HEADER: [u8; 2] = [0x78, 0x01];
+
+/// Builds the header of a zlib stream compressed at a level. FLEVEL only
+/// tells a reader roughly how hard the data was compressed, 0 for zlib's
+/// levels 0 and 1, 1 for 2 to 5, 2 for 6 and 3 for 7 to 9, and the check
+/// value makes the header a multiple of 31 again, see 2.2 of RFC 1950.
+///
+/// # Arguments
+///
+/// * 'level' - The level the data is compressed at.
+///
+/// # Returns
+///
+/// The two header bytes, 0x78 then 0x01, 0x5e, 0x9c or 0xda.
+pub(crate) fn zlib_header(level: CompressionLevel) -> [u8; 2] {
+    let flevel = match level.zlib_level() {
+        0..=1 => 0,
+        2..=5 => 1,
+        6 => 2,
+        _ => 3,
+    };
+    let flg = flevel << 6;
+    let check = 31 - (u16::from_be_bytes([ZLIB_HEADER[0], flg]) % 31) as u8;
+    [ZLIB_HEADER[0], flg | (check % 31)]
+}
+
 #[derive(Debug)]
 pub enum ZlibError {
     InvalidHeader(&'static str),
 }
 
 impl Display for ZlibError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             ZlibError::InvalidHeader(s) => {
                 write!(f, "Error: Invalid header, {}", s)
@@ -35,42 +68,43 @@ pub struct ZlibHeader {
 
 impl ZlibHeader {
     pub fn build(bytes: &[u8]) -> Result<Self, ZlibError> {
-        // Took in the order the bytes appear when hexdumped.
-        let mut header_stream = BitVector64::from_le_bytes(&bytes[0..2]);
-
         let mut end_idx = 2;
 
-        let cinfo = header_stream
-            .by_ref()
-            .take(4)
-            .fold(0u8, |acc, bit| (acc << 1) | bit);
-        let cm = header_stream
-            .by_ref()
-            .take(4)
-            .fold(0u8, |acc, bit| (acc << 1) | bit);
-
-        let flevel = header_stream
-            .by_ref()
-            .take(2)
-            .fold(0u8, |acc, bit| (acc << 1) | bit);
-
-        let fdict_bool = header_stream
-            .by_ref()
-            .take(1)
-            .fold(0u8, |acc, bit| (acc << 1) | bit)
-            == 1;
-
-        let fcheck = u16::from_be_bytes([bytes[0], bytes[1]]);
-
-        if fcheck % 31 != 0 {
+        let &[cmf, flg, ..] = bytes else {
+            return Err(ZlibError::InvalidHeader(
+                "stream is too short to contain CMF and FLG.",
+            ));
+        };
+
+        // CMF holds CINFO in its high nibble and CM in its low one, FLG
+        // holds FLEVEL in its top 2 bits and FDICT below them.
+        let cinfo = cmf >> 4;
+        let cm = cmf & 0x0f;
+        let flevel = flg >> 6;
+        let fdict_bool = flg & 0x20 != 0;
+
+        let fcheck = u16::from_be_bytes([cmf, flg]);
+
+        if !fcheck.is_multiple_of(31) {
             return Err(ZlibError::InvalidHeader(
                 "taking the first two bytes as a u16 does not result in a value divisible by 31.",
             ));
         }
+        // Deflate can't reach back further than 32K, 2^(7 + 8).
+        if cinfo > 7 {
+            return Err(ZlibError::InvalidHeader(
+                "CINFO declares a window larger than 32K.",
+            ));
+        }
 
         let fdict = if fdict_bool {
             end_idx += 4;
-            Some(u32::from_be_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]))
+            let Some(&[a, b, c, d]) = bytes.get(2..6) else {
+                return Err(ZlibError::InvalidHeader(
+                    "FDICT is set, but the stream is too short to contain DICTID.",
+                ));
+            };
+            Some(u32::from_be_bytes([a, b, c, d]))
         } else {
             None
         };
@@ -83,6 +117,15 @@ impl ZlibHeader {
             end_idx,
         })
     }
+    /// Gets the window size CINFO declares, the furthest back any match in
+    /// the stream copies from, see 2.2 of RFC 1950.
+    ///
+    /// # Returns
+    ///
+    /// The window size in bytes, a power of 2 from 256 to 32768.
+    pub fn window_size(&self) -> usize {
+        1 << (self.cinfo + 8)
+    }
 }
 
 #[derive(Debug)]
@@ -96,9 +139,16 @@ impl ZlibStream {
     pub fn build(bytes: &[u8]) -> Result<Self, ZlibError> {
         let header = ZlibHeader::build(bytes)?;
 
-        let deflate = DeflateStream::build(&bytes[header.end_idx..bytes.len() - 4]);
+        // The header, with DICTID if FDICT is set, and the four byte adler32
+        // trailer at minimum.
+        if bytes.len() < header.end_idx + 4 {
+            return Err(ZlibError::InvalidHeader(
+                "stream is too short to contain a header and checksum.",
+            ));
+        }
 
-        //println!("{}", deflate.bitstream);
+        let mut deflate = DeflateStream::build(&bytes[header.end_idx..bytes.len() - 4]);
+        deflate.set_window(header.window_size());
 
         let adler32 = u32::from_be_bytes([
             bytes[bytes.len() - 4],
@@ -113,13 +163,261 @@ impl ZlibStream {
             adler32,
         })
     }
+    /// Lets matches copy from as far back as deflate allows, whatever
+    /// window the header declares, for recovering streams from encoders
+    /// that write too small a CINFO. libpng has the same option.
+    pub fn ignore_window(&mut self) {
+        self.deflate.set_window(WINDOW_SIZE);
+    }
     #[inline]
     pub fn decompress(&mut self) -> Result<Vec<u8>, DeflateError> {
         let data = self.deflate.decompress()?;
-        let adler32 = adler32(&data);
+        self.check(adler32(&data))?;
 
-        assert!(adler32 == self.adler32);
+        Ok(data)
+    }
+    /// Decompresses the stream into the given buffer, see
+    /// DeflateStream::decompress_into.
+    ///
+    /// # Arguments
+    ///
+    /// * 'buffer' - The buffer to decompress into.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the buffer holding the decompressed data,
+    /// or a DeflateError.
+    pub fn decompress_into(&mut self, buffer: Vec<u8>) -> Result<Vec<u8>, DeflateError> {
+        let data = self.deflate.decompress_into(buffer)?;
+        self.check(adler32(&data))?;
 
         Ok(data)
     }
+    /// Decompresses the stream a piece at a time, see
+    /// DeflateStream::decompress_with. The checksum is checked once the
+    /// last piece has been handed to each.
+    ///
+    /// # Arguments
+    ///
+    /// * 'buffer' - The buffer to hold the output in.
+    /// * 'each' - Called with each piece of the output, in order.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the emptied buffer, or the first error
+    /// from decompressing or from each.
+    pub fn decompress_with<E: From<DeflateError>>(
+        &mut self,
+        buffer: Vec<u8>,
+        each: &mut dyn FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<Vec<u8>, E> {
+        let mut adler = Adler32::new();
+        let buffer = self.deflate.decompress_with(buffer, &mut |data| {
+            adler.update(data);
+            each(data)
+        })?;
+
+        self.check(adler.finish())?;
+
+        Ok(buffer)
+    }
+    /// Checks the adler32 of the decompressed data against the trailer.
+    ///
+    /// # Arguments
+    ///
+    /// * 'actual' - The adler32 of the decompressed data.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or a DeflateError::ChecksumMismatch holding both
+    /// values if they differ.
+    pub fn check(&self, actual: u32) -> Result<(), DeflateError> {
+        match actual == self.adler32 {
+            true => Ok(()),
+            false => Err(DeflateError::ChecksumMismatch {
+                expected: self.adler32,
+                actual,
+            }),
+        }
+    }
+    /// Compresses data into a zlib stream, the header, deflate data and
+    /// adler32 trailer ZlibStream::build reads back, see deflate::compress.
+    ///
+    /// # Arguments
+    ///
+    /// * 'data' - The bytes to compress.
+    ///
+    /// # Returns
+    ///
+    /// The zlib stream.
+    pub fn compress(data: &[u8]) -> Vec<u8> {
+        let mut stream = zlib_header(CompressionLevel::Default).to_vec();
+        stream.extend(deflate::compress(data));
+        stream.extend_from_slice(&adler32(data).to_be_bytes());
+
+        stream
+    }
+    /// Compresses data into a zlib stream at a level and with a strategy,
+    /// see deflate::compress_options. The header's FLEVEL is set from the
+    /// level, see zlib_header.
+    ///
+    /// # Arguments
+    ///
+    /// * 'data' - The bytes to compress.
+    /// * 'options' - The level and strategy to compress with.
+    ///
+    /// # Returns
+    ///
+    /// The zlib stream.
+    pub fn compress_with(data: &[u8], options: &DeflateOptions) -> Vec<u8> {
+        let mut stream = zlib_header(options.level).to_vec();
+        stream.extend(deflate::compress_options(data, options));
+        stream.extend_from_slice(&adler32(data).to_be_bytes());
+
+        stream
+    }
+}
+
+/// Appends a stored deflate block holding data, at most MAX_STORED_BLOCK
+/// bytes, see 3.2.4 of RFC 1951.
+#[cfg(all(feature = "std", feature = "png"))]
+pub(crate) fn stored_block(stream: &mut Vec<u8>, data: &[u8], last: bool) {
+    let len = data.len() as u16;
+
+    // BFINAL and a BTYPE of 00, the rest of the byte is padding.
+    stream.push(last as u8);
+    stream.extend_from_slice(&len.to_le_bytes());
+    stream.extend_from_slice(&(!len).to_le_bytes());
+    stream.extend_from_slice(data);
+}
+
+/// An iterator over zlib streams written one after another, decompressing
+/// each in turn, for data that was compressed a piece at a time and the
+/// pieces joined. Once a stream's adler32 has been read, the next starts
+/// straight after it, until the data ends. Anything after the last stream
+/// that isn't another one is an error, as is a damaged stream, after which
+/// the iterator stops.
+///
+/// # Fields
+///
+/// * 'bytes' - The streams.
+/// * 'deflate' - The deflate data of all of them, restarted at each stream,
+///   so the data is only copied once.
+/// * 'started' - Whether the first stream has been read, as there has to
+///   be at least one.
+/// * 'failed' - Whether a stream couldn't be read.
+/// * 'ignore_window' - Whether matches may reach back past the window each
+///   stream's header declares, see ZlibStream::ignore_window.
+///
+/// # Examples
+///
+/// '''
+/// let data: Vec<u8> = ZlibStreams::new(&bytes)
+///     .collect::<Result<Vec<_>, _>>()?
+///     .concat();
+/// '''
+#[derive(Debug)]
+pub struct ZlibStreams<'a> {
+    bytes: &'a [u8],
+    deflate: DeflateStream,
+    started: bool,
+    failed: bool,
+    ignore_window: bool,
+}
+
+impl<'a> ZlibStreams<'a> {
+    /// Creates a ZlibStreams at the start of the first stream.
+    ///
+    /// # Arguments
+    ///
+    /// * 'bytes' - The streams.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            deflate: DeflateStream::build(bytes),
+            started: false,
+            failed: false,
+            ignore_window: false,
+        }
+    }
+    /// Lets matches copy from as far back as deflate allows, whatever
+    /// window each stream's header declares, see ZlibStream::ignore_window.
+    pub fn ignore_window(mut self) -> Self {
+        self.ignore_window = true;
+        self
+    }
+    /// Gets the index of the first byte after the streams read so far.
+    pub fn offset(&self) -> usize {
+        self.deflate.bitstream.position() / 8
+    }
+    /// Reads and decompresses the stream starting at offset, checking the
+    /// declared window the way ZlibStream::build does unless it's being
+    /// ignored.
+    fn stream(&mut self) -> Result<Vec<u8>, error::Error> {
+        let rest = &self.bytes[self.offset()..];
+        if rest.len() < 6 {
+            return Err(ZlibError::InvalidHeader(
+                "stream is too short to contain a header and checksum.",
+            )
+            .into());
+        }
+        let header = ZlibHeader::build(rest)?;
+        self.deflate.bitstream.skip(header.end_idx * 8);
+        self.deflate.set_window(match self.ignore_window {
+            true => WINDOW_SIZE,
+            false => header.window_size(),
+        });
+        self.deflate.restart();
+
+        let data = self.deflate.decompress_into(Vec::new())?;
+        self.deflate.bitstream.align_to_byte();
+        let trailer = self
+            .deflate
+            .bitstream
+            .read_bytes(4)
+            .ok_or(DeflateError::UnexpectedEof("the adler32 checksum"))?;
+        let (expected, actual) = (
+            u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]),
+            adler32(&data),
+        );
+        if expected != actual {
+            return Err(DeflateError::ChecksumMismatch { expected, actual }.into());
+        }
+
+        Ok(data)
+    }
+}
+
+impl Iterator for ZlibStreams<'_> {
+    type Item = Result<Vec<u8>, error::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || (self.started && self.offset() == self.bytes.len()) {
+            return None;
+        }
+        self.started = true;
+
+        let stream = self.stream();
+        self.failed = stream.is_err();
+        Some(stream)
+    }
+}
+
+/// Decompresses every zlib stream in bytes, see ZlibStreams.
+///
+/// # Arguments
+///
+/// * 'bytes' - The streams.
+///
+/// # Returns
+///
+/// A result containing either the data of every stream joined together,
+/// or the first error.
+pub fn decompress_all(bytes: &[u8]) -> Result<Vec<u8>, error::Error> {
+    let mut data = Vec::new();
+    for stream in ZlibStreams::new(bytes) {
+        data.extend(stream?);
+    }
+
+    Ok(data)
 }
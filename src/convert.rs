@@ -0,0 +1,189 @@
+//! Conversions between every PixelFormat, so encoders and image operations
+//! can take pixels in any layout without each carrying their own partial
+//! conversions. Every conversion goes through 16 bit RGBA, which holds any
+//! supported pixel exactly.
+//!
+//! '''
+//! let mut rgba = Vec::new();
+//! convert::convert_pixels(PixelFormat::GrayAlpha8, PixelFormat::Rgba8, &row, &mut rgba)?;
+//!
+//! // Reduce 16 bit samples to 8 bits with a custom Dither instead of
+//! // keeping their high byte.
+//! convert::convert_pixels_with(PixelFormat::Rgb16, PixelFormat::Rgb8, &row, &mut rgb, &mut Round)?;
+//! '''
+
+use alloc::vec::Vec;
+
+use crate::image::{ImageError, PixelFormat};
+
+/// Decides how 16 bit samples are reduced to 8 bits when converting to an 8
+/// bit PixelFormat, e.g. to add noise or diffuse the rounding error between
+/// neighbouring pixels instead of leaving bands in smooth gradients.
+///
+/// # Examples
+///
+/// '''
+/// // Adds the error of the last sample of each channel to the next one.
+/// struct Diffuse([i32; 4]);
+///
+/// impl Dither for Diffuse {
+///     fn reduce(&mut self, _pixel: usize, channel: usize, value: u16) -> u8 {
+///         let wanted = value as i32 + self.0[channel];
+///         let reduced = (wanted / 257).clamp(0, 255);
+///         self.0[channel] = wanted - reduced * 257;
+///         reduced as u8
+///     }
+/// }
+/// '''
+pub trait Dither {
+    /// Reduces one sample to 8 bits. Called for every sample written to an 8
+    /// bit PixelFormat, in order, including those converted from 8 bit
+    /// formats, which arrive as exact multiples of 257.
+    ///
+    /// # Arguments
+    ///
+    /// * 'pixel' - The position of the pixel in the buffer being converted.
+    /// * 'channel' - The position of the sample in the pixel.
+    /// * 'value' - The sample at 16 bits.
+    ///
+    /// # Returns
+    ///
+    /// The sample at 8 bits.
+    fn reduce(&mut self, pixel: usize, channel: usize, value: u16) -> u8;
+}
+
+/// Keeps the high byte of each sample, the same reduction Png::rgb makes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Truncate;
+
+impl Dither for Truncate {
+    fn reduce(&mut self, _pixel: usize, _channel: usize, value: u16) -> u8 {
+        (value >> 8) as u8
+    }
+}
+
+/// Rounds each sample to the nearest 8 bit value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Round;
+
+impl Dither for Round {
+    fn reduce(&mut self, _pixel: usize, _channel: usize, value: u16) -> u8 {
+        ((value as u32 * 255 + 32767) / 65535) as u8
+    }
+}
+
+/// Converts a buffer of pixels from one PixelFormat to another, keeping the
+/// high byte of 16 bit samples reduced to 8 bits, see convert_pixels_with.
+///
+/// # Arguments
+///
+/// * 'src_format' - The layout of each pixel in src.
+/// * 'dst_format' - The layout to convert to.
+/// * 'src' - The pixels to convert.
+/// * 'dst' - The buffer the converted pixels are appended to.
+///
+/// # Returns
+///
+/// An empty result, or an ImageError if src isn't a whole number of pixels.
+pub fn convert_pixels(
+    src_format: PixelFormat,
+    dst_format: PixelFormat,
+    src: &[u8],
+    dst: &mut Vec<u8>,
+) -> Result<(), ImageError> {
+    convert_pixels_with(src_format, dst_format, src, dst, &mut Truncate)
+}
+
+/// Converts a buffer of pixels from one PixelFormat to another. Gray is
+/// copied into red, green and blue, color is made gray with the Rec. 709
+/// luma weights, a missing alpha is fully opaque, and alpha is dropped
+/// when the new format has none. 8 bit samples are scaled up to 16 bits by
+/// multiplying by 257, and 16 bit samples are reduced to 8 bits by dither.
+///
+/// # Arguments
+///
+/// * 'src_format' - The layout of each pixel in src.
+/// * 'dst_format' - The layout to convert to.
+/// * 'src' - The pixels to convert, 16 bit samples big endian as in PNG.
+/// * 'dst' - The buffer the converted pixels are appended to.
+/// * 'dither' - Reduces samples written to an 8 bit format, see Dither.
+///
+/// # Returns
+///
+/// An empty result, or an ImageError if src isn't a whole number of pixels.
+pub fn convert_pixels_with(
+    src_format: PixelFormat,
+    dst_format: PixelFormat,
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    dither: &mut dyn Dither,
+) -> Result<(), ImageError> {
+    let bytes_per_pixel = src_format.bytes_per_pixel();
+    if !src.len().is_multiple_of(bytes_per_pixel) {
+        let expected = src.len().next_multiple_of(bytes_per_pixel);
+        return Err(ImageError::BufferSize(expected, src.len()));
+    }
+
+    dst.reserve(src.len() / bytes_per_pixel * dst_format.bytes_per_pixel());
+    for (i, pixel) in src.chunks_exact(bytes_per_pixel).enumerate() {
+        push(dst_format, rgba16(src_format, pixel), i, dst, dither);
+    }
+
+    Ok(())
+}
+
+/// Reads one pixel as 16 bit RGBA.
+pub(crate) fn rgba16(format: PixelFormat, pixel: &[u8]) -> [u16; 4] {
+    let sample = |i: usize| match format.bit_depth() {
+        8 => pixel[i] as u16 * 257,
+        _ => u16::from_be_bytes([pixel[i * 2], pixel[i * 2 + 1]]),
+    };
+
+    match format {
+        PixelFormat::Gray8 | PixelFormat::Gray16 => [sample(0), sample(0), sample(0), u16::MAX],
+        PixelFormat::GrayAlpha8 | PixelFormat::GrayAlpha16 => {
+            [sample(0), sample(0), sample(0), sample(1)]
+        }
+        PixelFormat::Rgb8 | PixelFormat::Rgb16 => [sample(0), sample(1), sample(2), u16::MAX],
+        PixelFormat::Rgba8 | PixelFormat::Rgba16 => [sample(0), sample(1), sample(2), sample(3)],
+    }
+}
+
+/// Reads one pixel as 8 bit RGBA, keeping the high byte of 16 bit samples.
+pub(crate) fn rgba8(format: PixelFormat, pixel: &[u8]) -> [u8; 4] {
+    rgba16(format, pixel).map(|sample| (sample >> 8) as u8)
+}
+
+/// Appends one 16 bit RGBA pixel to out in a PixelFormat.
+pub(crate) fn push(
+    format: PixelFormat,
+    [r, g, b, a]: [u16; 4],
+    pixel: usize,
+    out: &mut Vec<u8>,
+    dither: &mut dyn Dither,
+) {
+    let gray = luma(r, g, b);
+    let samples: &[u16] = match format {
+        PixelFormat::Gray8 | PixelFormat::Gray16 => &[gray],
+        PixelFormat::GrayAlpha8 | PixelFormat::GrayAlpha16 => &[gray, a],
+        PixelFormat::Rgb8 | PixelFormat::Rgb16 => &[r, g, b],
+        PixelFormat::Rgba8 | PixelFormat::Rgba16 => &[r, g, b, a],
+    };
+
+    for (channel, &sample) in samples.iter().enumerate() {
+        match format.bit_depth() {
+            8 => out.push(dither.reduce(pixel, channel, sample)),
+            _ => out.extend_from_slice(&sample.to_be_bytes()),
+        }
+    }
+}
+
+/// The gray value of a color, weighted by how bright each primary looks,
+/// see Rec. ITU-R BT.709. Gray colors are kept exactly.
+fn luma(r: u16, g: u16, b: u16) -> u16 {
+    if r == g && g == b {
+        return r;
+    }
+
+    ((r as u32 * 2126 + g as u32 * 7152 + b as u32 * 722 + 5000) / 10000) as u16
+}
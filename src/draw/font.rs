@@ -0,0 +1,439 @@
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+use core::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+/// The largest font bounding box accepted from a BDF file, in pixels along
+/// either side. Anything bigger is far beyond a label font and most likely
+/// a corrupt file.
+const MAX_CELL_SIZE: usize = 256;
+
+/// A fixed height bitmap font for drawing text onto an Image, see draw::text.
+/// Every glyph is drawn into a cell the size of the font's bounding box, and
+/// moves the pen along by its own advance, so proportional fonts work too.
+///
+/// # Examples
+///
+/// '''
+/// // The built in 6x10 font.
+/// let font = Font::fixed();
+///
+/// // Or any BDF font, e.g. the X11 misc-fixed fonts.
+/// let font = Font::from_bdf(&fs::read_to_string("./9x15.bdf")?)?;
+/// '''
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Font {
+    width: usize,
+    height: usize,
+    glyphs: BTreeMap<char, Glyph>,
+    default: Option<char>,
+}
+
+/// A single character of a Font, its pixels cover the whole cell from left
+/// to right, top to bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Glyph {
+    advance: usize,
+    pixels: Vec<bool>,
+}
+
+impl Font {
+    /// Gets the built in font, the public domain 6x10 misc-fixed font from
+    /// X11, covering printable ASCII.
+    pub fn fixed() -> Self {
+        let glyphs = FIXED_6X10
+            .iter()
+            .zip(' '..='~')
+            .map(|(rows, c)| {
+                let pixels = rows
+                    .iter()
+                    .flat_map(|row| (0..6).map(move |x| row & (0x80 >> x) != 0))
+                    .collect();
+
+                (c, Glyph { advance: 6, pixels })
+            })
+            .collect();
+
+        Self {
+            width: 6,
+            height: 10,
+            glyphs,
+            default: Some('?'),
+        }
+    }
+    /// Parses a font in the Glyph Bitmap Distribution Format, the plain text
+    /// format of the X11 bitmap fonts. Glyphs without a Unicode encoding
+    /// are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * 'source' - The contents of the BDF file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the Font or a FontError if the file isn't
+    /// valid BDF.
+    pub fn from_bdf(source: &str) -> Result<Self, FontError> {
+        let mut font = Self {
+            width: 0,
+            height: 0,
+            glyphs: BTreeMap::new(),
+            default: None,
+        };
+        // The offset of the bounding box from the origin, and its height
+        // above the baseline.
+        let mut x_offset = 0;
+        let mut ascent = 0;
+        let mut default = None;
+
+        let mut lines = source.lines().enumerate().map(|(i, line)| (i + 1, line));
+        while let Some((number, line)) = lines.next() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("FONTBOUNDINGBOX") => {
+                    let [width, height, x, y] = numbers(words, number)?;
+                    if !(1..=MAX_CELL_SIZE as i32).contains(&width)
+                        || !(1..=MAX_CELL_SIZE as i32).contains(&height)
+                    {
+                        return Err(FontError::InvalidBdf(
+                            number,
+                            "the bounding box is empty or too large.",
+                        ));
+                    }
+
+                    font.width = width as usize;
+                    font.height = height as usize;
+                    x_offset = x;
+                    ascent = height.checked_add(y).ok_or(FontError::InvalidBdf(
+                        number,
+                        "the bounding box offset is out of range.",
+                    ))?;
+                }
+                Some("DEFAULT_CHAR") => {
+                    let [code] = numbers(words, number)?;
+                    default = u32::try_from(code).ok().and_then(char::from_u32);
+                }
+                Some("STARTCHAR") => {
+                    if font.width == 0 {
+                        return Err(FontError::InvalidBdf(
+                            number,
+                            "a glyph comes before FONTBOUNDINGBOX.",
+                        ));
+                    }
+
+                    if let Some((c, glyph)) = font.read_glyph(&mut lines, x_offset, ascent)? {
+                        font.glyphs.insert(c, glyph);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if font.glyphs.is_empty() {
+            return Err(FontError::InvalidBdf(
+                source.lines().count(),
+                "the font has no glyphs.",
+            ));
+        }
+
+        font.default = default.filter(|c| font.glyphs.contains_key(c));
+        Ok(font)
+    }
+    /// Reads the lines of one glyph from a BDF file, from just after its
+    /// STARTCHAR line up to and including ENDCHAR.
+    fn read_glyph<'a>(
+        &self,
+        lines: &mut impl Iterator<Item = (usize, &'a str)>,
+        x_offset: i32,
+        ascent: i32,
+    ) -> Result<Option<(char, Glyph)>, FontError> {
+        let mut encoding = None;
+        let mut advance = self.width;
+        let mut bbx = [0; 4];
+        let mut last = 0;
+
+        while let Some((number, line)) = lines.next() {
+            last = number;
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("ENCODING") => {
+                    let [code] = numbers(words.take(1), number)?;
+                    encoding = u32::try_from(code).ok().and_then(char::from_u32);
+                }
+                Some("DWIDTH") => {
+                    let [x] = numbers(words.take(1), number)?;
+                    advance = x.max(0) as usize;
+                }
+                Some("BBX") => {
+                    bbx = numbers(words, number)?;
+                }
+                Some("BITMAP") => {
+                    let [width, height, x, y] = bbx;
+                    let mut pixels = vec![false; self.width * self.height];
+                    // Where the glyph's bounding box starts in the font's
+                    // cell, which a corrupt file can push out of range.
+                    let out_of_range = || {
+                        FontError::InvalidBdf(number, "the glyph's bounding box is out of range.")
+                    };
+                    let top = y
+                        .checked_add(height)
+                        .and_then(|bottom| ascent.checked_sub(bottom))
+                        .ok_or_else(out_of_range)?;
+                    let left = x.checked_sub(x_offset).ok_or_else(out_of_range)?;
+
+                    for row in 0..height.max(0) {
+                        let (number, line) = lines.next().ok_or(FontError::InvalidBdf(
+                            last,
+                            "the file ends part way through a bitmap.",
+                        ))?;
+                        last = number;
+                        let bits = hex_row(line.trim(), width.max(0) as usize, number)?;
+
+                        // Place the glyph's bounding box in the font's cell,
+                        // dropping anything that falls outside of it.
+                        let cell_y = top.checked_add(row).ok_or_else(out_of_range)?;
+                        for (column, set) in bits.into_iter().enumerate() {
+                            let cell_x =
+                                left.checked_add(column as i32).ok_or_else(out_of_range)?;
+                            if set
+                                && (0..self.width as i32).contains(&cell_x)
+                                && (0..self.height as i32).contains(&cell_y)
+                            {
+                                pixels[cell_y as usize * self.width + cell_x as usize] = true;
+                            }
+                        }
+                    }
+
+                    return match lines.next() {
+                        Some((_, line)) if line.trim() == "ENDCHAR" => {
+                            Ok(encoding.map(|c| (c, Glyph { advance, pixels })))
+                        }
+                        Some((number, _)) => Err(FontError::InvalidBdf(
+                            number,
+                            "expected ENDCHAR after the bitmap.",
+                        )),
+                        None => Err(FontError::InvalidBdf(last, "the file ends before ENDCHAR.")),
+                    };
+                }
+                Some("ENDCHAR") => {
+                    return Err(FontError::InvalidBdf(number, "a glyph has no BITMAP."));
+                }
+                _ => {}
+            }
+        }
+
+        Err(FontError::InvalidBdf(
+            last,
+            "the file ends part way through a glyph.",
+        ))
+    }
+    /// Gets the width of the font's cell in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    /// Gets the height of each line of text in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    /// Measures the area text takes up when drawn with the font.
+    ///
+    /// # Arguments
+    ///
+    /// * 'text' - The text to measure, each '\n' starts a new line.
+    ///
+    /// # Returns
+    ///
+    /// The width and height of the text in pixels.
+    pub fn measure(&self, text: &str) -> (usize, usize) {
+        let width = text
+            .split('\n')
+            .map(|line| line.chars().map(|c| self.advance(c)).sum())
+            .max()
+            .unwrap_or(0);
+
+        (width, text.split('\n').count() * self.height)
+    }
+    /// Gets the glyph drawn for a character, the font's default character
+    /// stands in for any it doesn't have.
+    pub(crate) fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&c)
+            .or_else(|| self.default.and_then(|c| self.glyphs.get(&c)))
+    }
+    /// Gets how far the pen moves after drawing a character, a missing
+    /// character with no default still takes up a whole cell.
+    pub(crate) fn advance(&self, c: char) -> usize {
+        self.glyph(c).map_or(self.width, |glyph| glyph.advance)
+    }
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        Self::fixed()
+    }
+}
+
+impl Glyph {
+    /// Checks whether the pixel at x, y of the cell is set.
+    pub(crate) fn is_set(&self, width: usize, x: usize, y: usize) -> bool {
+        self.pixels[y * width + x]
+    }
+}
+
+/// Reads the first count numbers from the rest of a line.
+fn numbers<'a, const N: usize>(
+    mut words: impl Iterator<Item = &'a str>,
+    line: usize,
+) -> Result<[i32; N], FontError> {
+    let mut values = [0; N];
+    for value in values.iter_mut() {
+        *value = words
+            .next()
+            .and_then(|word| word.parse().ok())
+            .ok_or(FontError::InvalidBdf(line, "expected a number."))?;
+    }
+
+    Ok(values)
+}
+
+/// Unpacks one row of a glyph's bitmap, hex digits with the leftmost pixel
+/// in the most significant bit, padded to a whole number of bytes.
+fn hex_row(row: &str, width: usize, line: usize) -> Result<Vec<bool>, FontError> {
+    let bytes = (0..width.div_ceil(8))
+        .map(|i| {
+            row.get(i * 2..i * 2 + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or(FontError::InvalidBdf(line, "invalid bitmap row."))
+        })
+        .collect::<Result<Vec<u8>, FontError>>()?;
+
+    Ok((0..width)
+        .map(|x| bytes[x / 8] & (0x80 >> (x % 8)) != 0)
+        .collect())
+}
+
+/// Errors from loading a Font.
+///
+/// # Members
+///
+/// * 'InvalidBdf' - The BDF file is malformed. Holds the line number the
+///   problem was found on and a description of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FontError {
+    InvalidBdf(usize, &'static str),
+}
+
+impl Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::InvalidBdf(line, reason) => {
+                write!(f, "Error: Invalid BDF font on line {}, {}", line, reason)
+            }
+        }
+    }
+}
+
+impl Error for FontError {}
+
+/// The printable ASCII characters, from ' ' to '~', of the 6x10 misc-fixed
+/// font. Each row is a byte with the leftmost pixel in the most significant
+/// bit, the baseline is below the eighth row.
+#[rustfmt::skip]
+const FIXED_6X10: [[u8; 10]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x20, 0x00, 0x00], // '!'
+    [0x00, 0x50, 0x50, 0x50, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x00, 0x50, 0x50, 0xF8, 0x50, 0xF8, 0x50, 0x50, 0x00, 0x00], // '#'
+    [0x00, 0x20, 0x70, 0xA0, 0x70, 0x28, 0x70, 0x20, 0x00, 0x00], // '$'
+    [0x00, 0x48, 0xA8, 0x50, 0x20, 0x50, 0xA8, 0x90, 0x00, 0x00], // '%'
+    [0x00, 0x40, 0xA0, 0xA0, 0x40, 0xA8, 0x90, 0x68, 0x00, 0x00], // '&'
+    [0x00, 0x20, 0x20, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // "'"
+    [0x00, 0x10, 0x20, 0x40, 0x40, 0x40, 0x20, 0x10, 0x00, 0x00], // '('
+    [0x00, 0x40, 0x20, 0x10, 0x10, 0x10, 0x20, 0x40, 0x00, 0x00], // ')'
+    [0x00, 0x00, 0x88, 0x50, 0xF8, 0x50, 0x88, 0x00, 0x00, 0x00], // '*'
+    [0x00, 0x00, 0x20, 0x20, 0xF8, 0x20, 0x20, 0x00, 0x00, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x30, 0x20, 0x40, 0x00], // ','
+    [0x00, 0x00, 0x00, 0x00, 0xF8, 0x00, 0x00, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x70, 0x20, 0x00], // '.'
+    [0x00, 0x08, 0x08, 0x10, 0x20, 0x40, 0x80, 0x80, 0x00, 0x00], // '/'
+    [0x00, 0x20, 0x50, 0x88, 0x88, 0x88, 0x50, 0x20, 0x00, 0x00], // '0'
+    [0x00, 0x20, 0x60, 0xA0, 0x20, 0x20, 0x20, 0xF8, 0x00, 0x00], // '1'
+    [0x00, 0x70, 0x88, 0x08, 0x30, 0x40, 0x80, 0xF8, 0x00, 0x00], // '2'
+    [0x00, 0xF8, 0x08, 0x10, 0x30, 0x08, 0x88, 0x70, 0x00, 0x00], // '3'
+    [0x00, 0x10, 0x30, 0x50, 0x90, 0xF8, 0x10, 0x10, 0x00, 0x00], // '4'
+    [0x00, 0xF8, 0x80, 0xB0, 0xC8, 0x08, 0x88, 0x70, 0x00, 0x00], // '5'
+    [0x00, 0x30, 0x40, 0x80, 0xB0, 0xC8, 0x88, 0x70, 0x00, 0x00], // '6'
+    [0x00, 0xF8, 0x08, 0x10, 0x10, 0x20, 0x40, 0x40, 0x00, 0x00], // '7'
+    [0x00, 0x70, 0x88, 0x88, 0x70, 0x88, 0x88, 0x70, 0x00, 0x00], // '8'
+    [0x00, 0x70, 0x88, 0x98, 0x68, 0x08, 0x10, 0x60, 0x00, 0x00], // '9'
+    [0x00, 0x00, 0x20, 0x70, 0x20, 0x00, 0x20, 0x70, 0x20, 0x00], // ':'
+    [0x00, 0x00, 0x20, 0x70, 0x20, 0x00, 0x30, 0x20, 0x40, 0x00], // ';'
+    [0x00, 0x08, 0x10, 0x20, 0x40, 0x20, 0x10, 0x08, 0x00, 0x00], // '<'
+    [0x00, 0x00, 0x00, 0xF8, 0x00, 0xF8, 0x00, 0x00, 0x00, 0x00], // '='
+    [0x00, 0x40, 0x20, 0x10, 0x08, 0x10, 0x20, 0x40, 0x00, 0x00], // '>'
+    [0x00, 0x70, 0x88, 0x10, 0x20, 0x20, 0x00, 0x20, 0x00, 0x00], // '?'
+    [0x00, 0x70, 0x88, 0x98, 0xA8, 0xB0, 0x80, 0x70, 0x00, 0x00], // '@'
+    [0x00, 0x20, 0x50, 0x88, 0x88, 0xF8, 0x88, 0x88, 0x00, 0x00], // 'A'
+    [0x00, 0xF0, 0x48, 0x48, 0x70, 0x48, 0x48, 0xF0, 0x00, 0x00], // 'B'
+    [0x00, 0x70, 0x88, 0x80, 0x80, 0x80, 0x88, 0x70, 0x00, 0x00], // 'C'
+    [0x00, 0xF0, 0x48, 0x48, 0x48, 0x48, 0x48, 0xF0, 0x00, 0x00], // 'D'
+    [0x00, 0xF8, 0x80, 0x80, 0xF0, 0x80, 0x80, 0xF8, 0x00, 0x00], // 'E'
+    [0x00, 0xF8, 0x80, 0x80, 0xF0, 0x80, 0x80, 0x80, 0x00, 0x00], // 'F'
+    [0x00, 0x70, 0x88, 0x80, 0x80, 0x98, 0x88, 0x70, 0x00, 0x00], // 'G'
+    [0x00, 0x88, 0x88, 0x88, 0xF8, 0x88, 0x88, 0x88, 0x00, 0x00], // 'H'
+    [0x00, 0x70, 0x20, 0x20, 0x20, 0x20, 0x20, 0x70, 0x00, 0x00], // 'I'
+    [0x00, 0x38, 0x10, 0x10, 0x10, 0x10, 0x90, 0x60, 0x00, 0x00], // 'J'
+    [0x00, 0x88, 0x90, 0xA0, 0xC0, 0xA0, 0x90, 0x88, 0x00, 0x00], // 'K'
+    [0x00, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0xF8, 0x00, 0x00], // 'L'
+    [0x00, 0x88, 0x88, 0xD8, 0xA8, 0x88, 0x88, 0x88, 0x00, 0x00], // 'M'
+    [0x00, 0x88, 0x88, 0xC8, 0xA8, 0x98, 0x88, 0x88, 0x00, 0x00], // 'N'
+    [0x00, 0x70, 0x88, 0x88, 0x88, 0x88, 0x88, 0x70, 0x00, 0x00], // 'O'
+    [0x00, 0xF0, 0x88, 0x88, 0xF0, 0x80, 0x80, 0x80, 0x00, 0x00], // 'P'
+    [0x00, 0x70, 0x88, 0x88, 0x88, 0x88, 0xA8, 0x70, 0x08, 0x00], // 'Q'
+    [0x00, 0xF0, 0x88, 0x88, 0xF0, 0xA0, 0x90, 0x88, 0x00, 0x00], // 'R'
+    [0x00, 0x70, 0x88, 0x80, 0x70, 0x08, 0x88, 0x70, 0x00, 0x00], // 'S'
+    [0x00, 0xF8, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00], // 'T'
+    [0x00, 0x88, 0x88, 0x88, 0x88, 0x88, 0x88, 0x70, 0x00, 0x00], // 'U'
+    [0x00, 0x88, 0x88, 0x88, 0x50, 0x50, 0x50, 0x20, 0x00, 0x00], // 'V'
+    [0x00, 0x88, 0x88, 0x88, 0xA8, 0xA8, 0xD8, 0x88, 0x00, 0x00], // 'W'
+    [0x00, 0x88, 0x88, 0x50, 0x20, 0x50, 0x88, 0x88, 0x00, 0x00], // 'X'
+    [0x00, 0x88, 0x88, 0x50, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00], // 'Y'
+    [0x00, 0xF8, 0x08, 0x10, 0x20, 0x40, 0x80, 0xF8, 0x00, 0x00], // 'Z'
+    [0x00, 0x70, 0x40, 0x40, 0x40, 0x40, 0x40, 0x70, 0x00, 0x00], // '['
+    [0x00, 0x80, 0x80, 0x40, 0x20, 0x10, 0x08, 0x08, 0x00, 0x00], // '\\'
+    [0x00, 0x70, 0x10, 0x10, 0x10, 0x10, 0x10, 0x70, 0x00, 0x00], // ']'
+    [0x00, 0x20, 0x50, 0x88, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF8, 0x00], // '_'
+    [0x20, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x00, 0x70, 0x08, 0x78, 0x88, 0x78, 0x00, 0x00], // 'a'
+    [0x00, 0x80, 0x80, 0xB0, 0xC8, 0x88, 0xC8, 0xB0, 0x00, 0x00], // 'b'
+    [0x00, 0x00, 0x00, 0x70, 0x88, 0x80, 0x88, 0x70, 0x00, 0x00], // 'c'
+    [0x00, 0x08, 0x08, 0x68, 0x98, 0x88, 0x98, 0x68, 0x00, 0x00], // 'd'
+    [0x00, 0x00, 0x00, 0x70, 0x88, 0xF8, 0x80, 0x70, 0x00, 0x00], // 'e'
+    [0x00, 0x30, 0x48, 0x40, 0xF0, 0x40, 0x40, 0x40, 0x00, 0x00], // 'f'
+    [0x00, 0x00, 0x00, 0x78, 0x88, 0x88, 0x78, 0x08, 0x88, 0x70], // 'g'
+    [0x00, 0x80, 0x80, 0xB0, 0xC8, 0x88, 0x88, 0x88, 0x00, 0x00], // 'h'
+    [0x00, 0x20, 0x00, 0x60, 0x20, 0x20, 0x20, 0x70, 0x00, 0x00], // 'i'
+    [0x00, 0x08, 0x00, 0x18, 0x08, 0x08, 0x08, 0x48, 0x48, 0x30], // 'j'
+    [0x00, 0x80, 0x80, 0x88, 0x90, 0xE0, 0x90, 0x88, 0x00, 0x00], // 'k'
+    [0x00, 0x60, 0x20, 0x20, 0x20, 0x20, 0x20, 0x70, 0x00, 0x00], // 'l'
+    [0x00, 0x00, 0x00, 0xD0, 0xA8, 0xA8, 0xA8, 0x88, 0x00, 0x00], // 'm'
+    [0x00, 0x00, 0x00, 0xB0, 0xC8, 0x88, 0x88, 0x88, 0x00, 0x00], // 'n'
+    [0x00, 0x00, 0x00, 0x70, 0x88, 0x88, 0x88, 0x70, 0x00, 0x00], // 'o'
+    [0x00, 0x00, 0x00, 0xB0, 0xC8, 0x88, 0xC8, 0xB0, 0x80, 0x80], // 'p'
+    [0x00, 0x00, 0x00, 0x68, 0x98, 0x88, 0x98, 0x68, 0x08, 0x08], // 'q'
+    [0x00, 0x00, 0x00, 0xB0, 0xC8, 0x80, 0x80, 0x80, 0x00, 0x00], // 'r'
+    [0x00, 0x00, 0x00, 0x70, 0x80, 0x70, 0x08, 0xF0, 0x00, 0x00], // 's'
+    [0x00, 0x40, 0x40, 0xF0, 0x40, 0x40, 0x48, 0x30, 0x00, 0x00], // 't'
+    [0x00, 0x00, 0x00, 0x88, 0x88, 0x88, 0x98, 0x68, 0x00, 0x00], // 'u'
+    [0x00, 0x00, 0x00, 0x88, 0x88, 0x50, 0x50, 0x20, 0x00, 0x00], // 'v'
+    [0x00, 0x00, 0x00, 0x88, 0x88, 0xA8, 0xA8, 0x50, 0x00, 0x00], // 'w'
+    [0x00, 0x00, 0x00, 0x88, 0x50, 0x20, 0x50, 0x88, 0x00, 0x00], // 'x'
+    [0x00, 0x00, 0x00, 0x88, 0x88, 0x98, 0x68, 0x08, 0x88, 0x70], // 'y'
+    [0x00, 0x00, 0x00, 0xF8, 0x10, 0x20, 0x40, 0xF8, 0x00, 0x00], // 'z'
+    [0x00, 0x18, 0x20, 0x10, 0x60, 0x10, 0x20, 0x18, 0x00, 0x00], // '{'
+    [0x00, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00], // '|'
+    [0x00, 0x60, 0x10, 0x20, 0x18, 0x20, 0x10, 0x60, 0x00, 0x00], // '}'
+    [0x00, 0x48, 0xA8, 0x90, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '~'
+];
@@ -0,0 +1,264 @@
+//! Drawing onto an Image, for stamping labels and timestamps onto pictures
+//! as they're converted, and compositing one image over another.
+//!
+//! '''
+//! let mut image = Image::try_from(Png::from_path("./in.png")?)?;
+//!
+//! draw::fill(&mut image, 0, 0, 62, 12, [0, 0, 0, 255])?;
+//! draw::text(&mut image, 1, 1, "2024-01-01", [255, 255, 255, 255], &Font::fixed())?;
+//! draw::composite(&mut image, &watermark, 8, 8, Blend::Linear)?;
+//! let flat = draw::flatten(&image, Pattern::Solid([255, 255, 255, 255]), Blend::Srgb)?;
+//! '''
+
+mod font;
+
+pub use font::{Font, FontError};
+
+use alloc::vec::Vec;
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+use crate::convert::{self, Truncate};
+use crate::generate::Pattern;
+use crate::image::{Image, ImageError, PixelFormat};
+
+/// How draw::composite mixes the colors of partly transparent pixels with
+/// the pixels under them.
+///
+/// # Members
+///
+/// * 'Srgb' - Mixes the stored sRGB values directly. This is what most
+///   software does, but the edges of antialiased shapes come out too
+///   dark, as sRGB isn't proportional to light.
+/// * 'Linear' - Converts to linear light, mixes, and converts back, see
+///   the color module. Slower, but edges blend the way light does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Blend {
+    #[default]
+    Srgb,
+    Linear,
+}
+
+impl Blend {
+    /// Mixes two samples, weight being how much of top to take.
+    fn mix(self, top: u8, bottom: u8, weight: f32) -> f32 {
+        match self {
+            Blend::Srgb => (top as f32 * weight + bottom as f32 * (1.0 - weight)) / 255.0,
+            Blend::Linear => srgb_to_linear(top) * weight + srgb_to_linear(bottom) * (1.0 - weight),
+        }
+    }
+    /// Converts a mixed sample back to 8 bit sRGB.
+    fn finish(self, value: f32) -> u8 {
+        match self {
+            Blend::Srgb => (value * 255.0 + 0.5) as u8,
+            Blend::Linear => linear_to_srgb(value),
+        }
+    }
+}
+
+/// Draws one image over another with the over operator, the top image's
+/// alpha deciding how much of the bottom shows through. Anything falling
+/// outside of the bottom image is clipped.
+///
+/// # Arguments
+///
+/// * 'bottom' - The image to draw onto.
+/// * 'top' - The image to draw, treated as opaque if its PixelFormat has
+///   no alpha.
+/// * 'x' - The left edge of top in pixels.
+/// * 'y' - The top edge of top in pixels.
+/// * 'blend' - How colors are mixed, see Blend.
+///
+/// # Returns
+///
+/// An empty result, or an ImageError if either image's buffer doesn't match
+/// its dimensions. Images of any PixelFormat can be mixed, but 16 bit images
+/// are blended at 8 bit precision.
+pub fn composite(
+    bottom: &mut Image,
+    top: &Image,
+    x: usize,
+    y: usize,
+    blend: Blend,
+) -> Result<(), ImageError> {
+    bottom.check_size()?;
+    top.check_size()?;
+
+    let bytes = bottom.format.bytes_per_pixel();
+    let top_bytes = top.format.bytes_per_pixel();
+    let width = top.width.min(bottom.width.saturating_sub(x));
+    let height = top.height.min(bottom.height.saturating_sub(y));
+    let mut pixel = Vec::with_capacity(bytes);
+
+    for row in 0..height {
+        for column in 0..width {
+            let source = &top.data[(row * top.width + column) * top_bytes..][..top_bytes];
+            let start = ((y + row) * bottom.width + x + column) * bytes;
+            let source = convert::rgba8(top.format, source);
+            let target = convert::rgba8(bottom.format, &bottom.data[start..start + bytes]);
+
+            let top_alpha = source[3] as f32 / 255.0;
+            let bottom_alpha = target[3] as f32 / 255.0;
+            // The share of the bottom color left showing through the top.
+            let through = bottom_alpha * (1.0 - top_alpha);
+            let alpha = top_alpha + through;
+            if alpha == 0.0 {
+                continue;
+            }
+
+            let mut mixed = [0; 4];
+            for i in 0..3 {
+                let value = blend.mix(source[i], target[i], top_alpha / alpha);
+                mixed[i] = blend.finish(value) as u16 * 257;
+            }
+            mixed[3] = (alpha * 255.0 + 0.5) as u16 * 257;
+
+            pixel.clear();
+            convert::push(bottom.format, mixed, 0, &mut pixel, &mut Truncate);
+            bottom.data[start..start + bytes].copy_from_slice(&pixel);
+        }
+    }
+
+    Ok(())
+}
+
+/// Draws an image over a matte, a solid color or other Pattern, leaving no
+/// transparency, for writing images with alpha to formats without it
+/// rather than dropping the alpha and showing whatever color the
+/// transparent pixels happen to hold.
+///
+/// # Arguments
+///
+/// * 'image' - The image to flatten.
+/// * 'matte' - The Pattern to draw it over. Its alpha is ignored.
+/// * 'blend' - How colors are mixed, see Blend.
+///
+/// # Returns
+///
+/// A result containing either the flattened Rgb8 Image, or an ImageError if
+/// the image's buffer doesn't match its dimensions.
+///
+/// # Examples
+///
+/// '''
+/// let checkerboard = Pattern::Checkerboard {
+///     size: 8,
+///     colors: [[255, 255, 255, 255], [204, 204, 204, 255]],
+/// };
+/// let flat = draw::flatten(&image, checkerboard, Blend::Srgb)?;
+/// '''
+pub fn flatten(image: &Image, matte: Pattern, blend: Blend) -> Result<Image, ImageError> {
+    image.check_size()?;
+    let matte = Image::generate(image.width, image.height, matte)?;
+
+    let mut rgb = Vec::with_capacity(image.width * image.height * 3);
+    convert::convert_pixels(PixelFormat::Rgba8, PixelFormat::Rgb8, &matte.data, &mut rgb)?;
+    let mut flat = Image::new(image.width, image.height, PixelFormat::Rgb8, rgb)?;
+    composite(&mut flat, image, 0, 0, blend)?;
+
+    Ok(flat)
+}
+
+/// Draws text onto an image. Anything falling outside of the image is
+/// clipped.
+///
+/// # Arguments
+///
+/// * 'image' - The image to draw onto.
+/// * 'x' - The left edge of the text in pixels.
+/// * 'y' - The top edge of the first line of text in pixels.
+/// * 'text' - The text to draw, each '\n' starts a new line below the last.
+/// * 'color' - The red, green, blue and alpha of the text. The alpha is
+///   ignored for PixelFormats without alpha.
+/// * 'font' - The Font to draw with.
+///
+/// # Returns
+///
+/// An empty result, or an ImageError if the image's buffer doesn't match its
+/// dimensions.
+pub fn text(
+    image: &mut Image,
+    x: usize,
+    y: usize,
+    text: &str,
+    color: [u8; 4],
+    font: &Font,
+) -> Result<(), ImageError> {
+    image.check_size()?;
+    let color = pixel(image.format, color);
+
+    for (line, characters) in text.split('\n').enumerate() {
+        let top = y + line * font.height();
+        let mut left = x;
+
+        for c in characters.chars() {
+            if let Some(glyph) = font.glyph(c) {
+                for row in 0..font.height() {
+                    for column in 0..font.width() {
+                        if glyph.is_set(font.width(), column, row) {
+                            set_pixel(image, left + column, top + row, &color);
+                        }
+                    }
+                }
+            }
+            left += font.advance(c);
+        }
+    }
+
+    Ok(())
+}
+
+/// Fills a rectangle of an image with a single color, e.g. to give text a
+/// background it can be read against. Anything falling outside of the image
+/// is clipped.
+///
+/// # Arguments
+///
+/// * 'image' - The image to draw onto.
+/// * 'x' - The left edge of the rectangle in pixels.
+/// * 'y' - The top edge of the rectangle in pixels.
+/// * 'width' - The width of the rectangle in pixels.
+/// * 'height' - The height of the rectangle in pixels.
+/// * 'color' - The red, green, blue and alpha to fill with. The alpha is
+///   ignored for PixelFormats without alpha.
+///
+/// # Returns
+///
+/// An empty result, or an ImageError if the image's buffer doesn't match its
+/// dimensions.
+pub fn fill(
+    image: &mut Image,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    color: [u8; 4],
+) -> Result<(), ImageError> {
+    image.check_size()?;
+    let color = pixel(image.format, color);
+
+    for row in y..y.saturating_add(height).min(image.height) {
+        for column in x..x.saturating_add(width).min(image.width) {
+            set_pixel(image, column, row, &color);
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts an RGBA color to the bytes of one pixel in a PixelFormat.
+fn pixel(format: PixelFormat, color: [u8; 4]) -> Vec<u8> {
+    let mut pixel = Vec::with_capacity(format.bytes_per_pixel());
+    let _ = convert::convert_pixels(PixelFormat::Rgba8, format, &color, &mut pixel);
+    pixel
+}
+
+/// Sets a single pixel, doing nothing if it's outside of the image.
+fn set_pixel(image: &mut Image, x: usize, y: usize, pixel: &[u8]) {
+    if x >= image.width || y >= image.height {
+        return;
+    }
+
+    let start = (y * image.width + x) * pixel.len();
+    image.data[start..start + pixel.len()].copy_from_slice(pixel);
+}
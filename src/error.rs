@@ -0,0 +1,306 @@
+use core::fmt::{self, Display};
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "std")]
+use crate::cli::CliError;
+#[cfg(feature = "png")]
+use crate::formats::png::DecoderError;
+use crate::{
+    compression::{gzip::GzipError, inflate::DeflateError, zlib::ZlibError},
+    draw::FontError,
+    image::ImageError,
+};
+
+/// A crate wide error type wrapping the error types of each module, so
+/// anything that can fail in chameleon can be handled through one type.
+/// Every module error converts into it with From, so ? works across them.
+///
+/// # Members
+///
+/// * 'Decoder' - Wraps a DecoderError from decoding a PNG file. Only
+///   available with the png feature.
+/// * 'Image' - Wraps an ImageError from creating or encoding an Image.
+/// * 'Zlib' - Wraps a ZlibError from parsing a zlib stream.
+/// * 'Deflate' - Wraps a DeflateError from decompressing a deflate stream.
+/// * 'Gzip' - Wraps a GzipError from parsing a gzip file.
+/// * 'Font' - Wraps a FontError from loading a font to draw text with.
+/// * 'Cli' - Wraps a CliError from parsing the command line arguments. Only
+///   available with the std feature.
+/// * 'Io' - Wraps a std::io::Error from reading or writing files. Only
+///   available with the std feature.
+///
+/// # Examples
+///
+/// '''
+/// fn dimensions(path: &str) -> Result<(usize, usize), chameleon::Error> {
+///     let png = Png::from_path(path)?;
+///     Ok(png.dimensions)
+/// }
+///
+/// match dimensions("./example.png") {
+///     Ok(dimensions) => println!("{:?}", dimensions),
+///     Err(e) if e.kind() == ErrorKind::Io => eprintln!("Could not read file."),
+///     Err(e) => eprintln!("{e}"),
+/// }
+/// '''
+#[derive(Debug)]
+pub enum Error {
+    #[cfg(feature = "png")]
+    Decoder(DecoderError),
+    Image(ImageError),
+    Zlib(ZlibError),
+    Deflate(DeflateError),
+    Gzip(GzipError),
+    Font(FontError),
+    #[cfg(feature = "std")]
+    Cli(CliError),
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+/// A broad category for an Error, the set of kinds and what falls into each
+/// is kept stable so callers can branch on them.
+///
+/// # Members
+///
+/// * 'Usage' - The arguments were missing or invalid, code 1.
+/// * 'Io' - Reading or writing failed at the operating system level, code 2.
+/// * 'InvalidFormat' - The file is not a valid image, or is structured in a
+///   way that breaks the specification of its format, code 3.
+/// * 'Compression' - The compressed data inside the file is corrupt, code 4.
+/// * 'LimitExceeded' - The image is larger than the limits it was decoded
+///   with, code 5.
+/// * 'Unexpected' - Something went wrong that doesn't fit in any other kind,
+///   code 6.
+/// * 'Cancelled' - The decode was stopped by a progress callback, code 7.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorKind {
+    Io,
+    InvalidFormat,
+    Compression,
+    LimitExceeded,
+    Usage,
+    Cancelled,
+    Unexpected,
+}
+
+impl ErrorKind {
+    /// Gets the stable numeric code of the kind. These are the status codes
+    /// returned by the C interface and the exit codes of the command line
+    /// tool, and never change once assigned.
+    ///
+    /// # Returns
+    ///
+    /// The code of the kind, from 1 to 7.
+    pub const fn code(&self) -> i32 {
+        match self {
+            ErrorKind::Usage => 1,
+            ErrorKind::Io => 2,
+            ErrorKind::InvalidFormat => 3,
+            ErrorKind::Compression => 4,
+            ErrorKind::LimitExceeded => 5,
+            ErrorKind::Unexpected => 6,
+            ErrorKind::Cancelled => 7,
+        }
+    }
+}
+
+impl Error {
+    /// Gets the category of the error.
+    ///
+    /// # Returns
+    ///
+    /// The ErrorKind the error falls into.
+    pub fn kind(&self) -> ErrorKind {
+        match self.code() / 100 {
+            1 => ErrorKind::Usage,
+            2 => ErrorKind::Io,
+            3 => ErrorKind::InvalidFormat,
+            4 => ErrorKind::Compression,
+            5 => ErrorKind::LimitExceeded,
+            7 => ErrorKind::Cancelled,
+            _ => ErrorKind::Unexpected,
+        }
+    }
+    /// Gets the stable numeric code of the underlying error. Codes never
+    /// change once assigned, and the hundreds are always the code of the
+    /// ErrorKind, so code() / 100 == kind().code().
+    ///
+    /// '''
+    /// 101 Missing command line argument.
+    /// 102 Invalid command line argument.
+    /// 201 I/O error.
+    /// 301 Not a PNG file.
+    /// 302 Invalid chunk.
+    /// 303 Invalid color type.
+    /// 304 Invalid bit depth.
+    /// 305 Invalid interlace method.
+    /// 306 Invalid filter type.
+    /// 307 Image buffer is the wrong size.
+    /// 308 Image dimensions are not supported.
+    /// 309 Invalid BDF font.
+    /// 310 Image stride is shorter than a row.
+    /// 401 Invalid zlib header.
+    /// 402 Invalid deflate block.
+    /// 403 Invalid deflate symbol.
+    /// 404 Deflate decompression failed.
+    /// 405 Invalid gzip header.
+    /// 501 Image exceeds the decoder's limits.
+    /// 502 Image dimensions are too large to address.
+    /// 601 Unexplained error.
+    /// 701 Decode cancelled.
+    /// '''
+    ///
+    /// # Returns
+    ///
+    /// The code of the error.
+    pub fn code(&self) -> i32 {
+        match self {
+            #[cfg(feature = "png")]
+            Error::Decoder(e) => match e.root() {
+                #[cfg(feature = "std")]
+                DecoderError::IoError(_) => 201,
+                DecoderError::NotPngFile => 301,
+                DecoderError::InvalidChunk(_) => 302,
+                DecoderError::InvalidColorType(_) => 303,
+                DecoderError::InvalidBitDepth(_) => 304,
+                DecoderError::InvalidInterlace(_) => 305,
+                DecoderError::InvalidFilter(_) => 306,
+                DecoderError::ZlibError(e) => zlib_code(e),
+                DecoderError::DeflateError(e) => deflate_code(e),
+                DecoderError::InvalidDimensions(..) => 308,
+                DecoderError::LimitExceeded(..) => 501,
+                DecoderError::DimensionOverflow(..) => 502,
+                DecoderError::Unexplainable | DecoderError::Context(..) => 601,
+                DecoderError::Cancelled => 701,
+            },
+            Error::Image(ImageError::BufferSize(..)) => 307,
+            Error::Image(ImageError::InvalidDimensions(..)) => 308,
+            Error::Font(FontError::InvalidBdf(..)) => 309,
+            Error::Image(ImageError::InvalidStride(..)) => 310,
+            Error::Zlib(e) => zlib_code(e),
+            Error::Deflate(e) => deflate_code(e),
+            Error::Gzip(GzipError::InvalidHeader(_)) => 405,
+            #[cfg(feature = "std")]
+            Error::Cli(CliError::MissingArgument(_)) => 101,
+            #[cfg(feature = "std")]
+            Error::Cli(CliError::InvalidArgument(_)) => 102,
+            #[cfg(feature = "std")]
+            Error::Io(_) => 201,
+        }
+    }
+}
+
+fn zlib_code(error: &ZlibError) -> i32 {
+    match error {
+        ZlibError::InvalidHeader(_) => 401,
+    }
+}
+
+fn deflate_code(error: &DeflateError) -> i32 {
+    match error {
+        // Like a bad checksum, these kept the codes they had before they
+        // had variants of their own.
+        DeflateError::InvalidBlockError(_)
+        | DeflateError::UnexpectedEof(_)
+        | DeflateError::InvalidCodeLengths(_)
+        | DeflateError::InvalidPrefixCode(_) => 402,
+        DeflateError::InvalidSymbolError(..) => 403,
+        DeflateError::DecompressionError(_)
+        | DeflateError::ChecksumMismatch { .. }
+        | DeflateError::DistanceTooFarBack { .. }
+        | DeflateError::DistanceBeyondWindow { .. } => 404,
+    }
+}
+
+// Displays the wrapped error unaltered.
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "png")]
+            Error::Decoder(e) => write!(f, "{e}"),
+            Error::Image(e) => write!(f, "{e}"),
+            Error::Zlib(e) => write!(f, "{e}"),
+            Error::Deflate(e) => write!(f, "{e}"),
+            Error::Gzip(e) => write!(f, "{e}"),
+            Error::Font(e) => write!(f, "{e}"),
+            #[cfg(feature = "std")]
+            Error::Cli(e) => write!(f, "{e}"),
+            #[cfg(feature = "std")]
+            Error::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+// The wrapped error is displayed as is, so its source is passed through
+// rather than the wrapped error itself.
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "png")]
+            Error::Decoder(e) => e.source(),
+            Error::Image(e) => e.source(),
+            Error::Zlib(e) => e.source(),
+            Error::Deflate(e) => e.source(),
+            Error::Gzip(e) => e.source(),
+            Error::Font(e) => e.source(),
+            #[cfg(feature = "std")]
+            Error::Cli(e) => e.source(),
+            #[cfg(feature = "std")]
+            Error::Io(e) => e.source(),
+        }
+    }
+}
+
+#[cfg(feature = "png")]
+impl From<DecoderError> for Error {
+    fn from(error: DecoderError) -> Self {
+        Error::Decoder(error)
+    }
+}
+
+impl From<ImageError> for Error {
+    fn from(error: ImageError) -> Self {
+        Error::Image(error)
+    }
+}
+
+impl From<ZlibError> for Error {
+    fn from(error: ZlibError) -> Self {
+        Error::Zlib(error)
+    }
+}
+
+impl From<DeflateError> for Error {
+    fn from(error: DeflateError) -> Self {
+        Error::Deflate(error)
+    }
+}
+
+impl From<GzipError> for Error {
+    fn from(error: GzipError) -> Self {
+        Error::Gzip(error)
+    }
+}
+
+impl From<FontError> for Error {
+    fn from(error: FontError) -> Self {
+        Error::Font(error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<CliError> for Error {
+    fn from(error: CliError) -> Self {
+        Error::Cli(error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
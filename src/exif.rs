@@ -0,0 +1,163 @@
+//! Reading the few EXIF fields most programs want from an image, without a
+//! full EXIF library. EXIF is a TIFF structure of tagged entries, carried
+//! in PNG files by the eXIf chunk, see Png::exif.
+//!
+//! '''
+//! if let Some(exif) = png.exif().and_then(Exif::parse) {
+//!     println!("{:?} taken {:?}", exif.orientation, exif.date_time_original);
+//! }
+//! '''
+
+use crate::formats::png::Time;
+
+/// The Orientation tag, in the first IFD.
+const ORIENTATION: u16 = 0x0112;
+/// The DateTime tag, in the first IFD.
+const DATE_TIME: u16 = 0x0132;
+/// The tag pointing to the Exif IFD.
+const EXIF_IFD: u16 = 0x8769;
+/// The DateTimeOriginal tag, in the Exif IFD.
+const DATE_TIME_ORIGINAL: u16 = 0x9003;
+
+/// The TIFF type of a 16 bit unsigned integer.
+const SHORT: u16 = 3;
+/// The TIFF type of a 32 bit unsigned integer.
+const LONG: u16 = 4;
+/// The TIFF type of a null terminated ASCII string.
+const ASCII: u16 = 2;
+
+/// The fields read from EXIF data. Any that are missing or malformed are
+/// None.
+///
+/// # Fields
+///
+/// * 'orientation' - How the stored pixels have to be turned to be upright,
+///   from 1 to 8: 1 as stored, 3 rotated 180 degrees, 6 rotated 90
+///   degrees clockwise, 8 rotated 90 degrees counterclockwise, and 2,
+///   4, 5 and 7 the same mirrored.
+/// * 'date_time' - When the file was last changed, from DateTime.
+/// * 'date_time_original' - When the picture was taken, from
+///   DateTimeOriginal.
+///
+/// Times are as written, which is usually the camera's local time, as EXIF
+/// doesn't say which time zone they're in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Exif {
+    pub orientation: Option<u16>,
+    pub date_time: Option<Time>,
+    pub date_time_original: Option<Time>,
+}
+
+impl Exif {
+    /// Reads the fields from EXIF data.
+    ///
+    /// # Arguments
+    ///
+    /// * 'bytes' - The EXIF data, starting with the TIFF header, as in an
+    ///   eXIf chunk. A leading "Exif\0\0", as in a JPEG APP1 segment,
+    ///   is skipped.
+    ///
+    /// # Returns
+    ///
+    /// The Exif, or None if the TIFF header or first IFD is malformed.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.strip_prefix(b"Exif\0\0").unwrap_or(bytes);
+        let tiff = Tiff {
+            bytes,
+            big_endian: match bytes.get(..4)? {
+                b"MM\0*" => true,
+                b"II*\0" => false,
+                _ => return None,
+            },
+        };
+
+        let first = tiff.u32(4)? as usize;
+        // The IFD must at least hold its count of entries.
+        tiff.u16(first)?;
+
+        let mut exif = Exif {
+            orientation: tiff
+                .find(first, ORIENTATION, SHORT)
+                .and_then(|entry| tiff.u16(entry.checked_add(8)?))
+                .filter(|orientation| (1..=8).contains(orientation)),
+            date_time: tiff.time(first, DATE_TIME),
+            date_time_original: None,
+        };
+        if let Some(entry) = tiff.find(first, EXIF_IFD, LONG) {
+            let ifd = tiff.u32(entry.checked_add(8)?)? as usize;
+            exif.date_time_original = tiff.time(ifd, DATE_TIME_ORIGINAL);
+        }
+
+        Some(exif)
+    }
+}
+
+/// TIFF data in either byte order.
+struct Tiff<'a> {
+    bytes: &'a [u8],
+    big_endian: bool,
+}
+
+impl Tiff<'_> {
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = *self.bytes.get(offset..)?.first_chunk()?;
+
+        Some(match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    }
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes = *self.bytes.get(offset..)?.first_chunk()?;
+
+        Some(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    }
+    /// Finds the entry for a tag in the IFD at offset, checking its type.
+    ///
+    /// # Returns
+    ///
+    /// The offset of the 12 byte entry, or None if there isn't one of that
+    /// type.
+    fn find(&self, ifd: usize, tag: u16, kind: u16) -> Option<usize> {
+        let count = self.u16(ifd)? as usize;
+
+        // Offsets come from the file, so they're checked rather than trusted
+        // to fit in a usize.
+        (0..count)
+            .map_while(|i| ifd.checked_add(2)?.checked_add(i.checked_mul(12)?))
+            .find(|&entry| self.u16(entry) == Some(tag))
+            .filter(|&entry| entry.checked_add(2).and_then(|at| self.u16(at)) == Some(kind))
+    }
+    /// Reads a time written as "YYYY:MM:DD HH:MM:SS" from an ASCII entry.
+    fn time(&self, ifd: usize, tag: u16) -> Option<Time> {
+        let entry = self.find(ifd, tag, ASCII)?;
+        // The 19 characters and a null don't fit in the entry, so it holds
+        // their offset.
+        let offset = self.u32(entry.checked_add(8)?)? as usize;
+        let text = self.bytes.get(offset..)?.get(..19)?;
+
+        let number = |range: core::ops::Range<usize>| -> Option<u16> {
+            let digits = text.get(range)?;
+            digits.iter().try_fold(0u16, |total, &digit| {
+                digit
+                    .is_ascii_digit()
+                    .then(|| total * 10 + (digit - b'0') as u16)
+            })
+        };
+        let time = Time {
+            year: number(0..4)?,
+            month: number(5..7)? as u8,
+            day: number(8..10)? as u8,
+            hour: number(11..13)? as u8,
+            minute: number(14..16)? as u8,
+            second: number(17..19)? as u8,
+        };
+
+        // Unknown times are written as spaces or zeros.
+        (time.year != 0).then_some(time)
+    }
+}
@@ -0,0 +1,145 @@
+//! A C interface to the decoder, enabled with the ffi feature. The matching
+//! header is include/chameleon.h, and a shared library can be built with:
+//!
+//! '''
+//! cargo rustc --lib --release --crate-type cdylib --features ffi
+//! '''
+//!
+//! Images returned by chameleon_decode_png are owned by chameleon, and must
+//! be given back with chameleon_image_free rather than passed to free().
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{ffi::c_char, ptr, slice};
+use std::panic;
+
+use crate::{
+    error::{Error, ErrorKind},
+    formats::png::Png,
+};
+
+/// The call succeeded.
+pub const CHAMELEON_OK: i32 = 0;
+/// A pointer argument was null, the Usage kind.
+pub const CHAMELEON_ERROR_NULL_POINTER: i32 = ErrorKind::Usage.code();
+/// The input could not be read.
+pub const CHAMELEON_ERROR_IO: i32 = ErrorKind::Io.code();
+/// The input is not a valid image.
+pub const CHAMELEON_ERROR_INVALID_FORMAT: i32 = ErrorKind::InvalidFormat.code();
+/// The compressed image data is corrupt.
+pub const CHAMELEON_ERROR_COMPRESSION: i32 = ErrorKind::Compression.code();
+/// The image is larger than the decoder allows.
+pub const CHAMELEON_ERROR_LIMIT_EXCEEDED: i32 = ErrorKind::LimitExceeded.code();
+/// Something unexpected went wrong, including a panic inside chameleon.
+pub const CHAMELEON_ERROR_UNEXPECTED: i32 = ErrorKind::Unexpected.code();
+/// The decode was cancelled.
+pub const CHAMELEON_ERROR_CANCELLED: i32 = ErrorKind::Cancelled.code();
+
+/// A decoded image, laid out to match chameleon_image in the C header.
+///
+/// # Fields
+///
+/// * 'width' - The width of the image in pixels.
+/// * 'height' - The height of the image in pixels.
+/// * 'data' - The pixels as RGBA bytes, from left to right, top to bottom.
+/// * 'len' - The number of bytes pointed to by data.
+#[repr(C)]
+pub struct ChameleonImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// Decodes a PNG file held in memory.
+///
+/// # Arguments
+///
+/// * 'buf' - A pointer to the entire PNG file.
+/// * 'len' - The number of bytes in buf.
+/// * 'out' - Where to write the decoded image, left untouched on failure.
+///
+/// # Returns
+///
+/// CHAMELEON_OK, or one of the CHAMELEON_ERROR_* codes, which are the same
+/// as ErrorKind::code.
+///
+/// # Safety
+///
+/// buf must point to len readable bytes, and out must point to a writable
+/// ChameleonImage.
+#[no_mangle]
+pub unsafe extern "C" fn chameleon_decode_png(
+    buf: *const u8,
+    len: usize,
+    out: *mut ChameleonImage,
+) -> i32 {
+    if buf.is_null() || out.is_null() {
+        return CHAMELEON_ERROR_NULL_POINTER;
+    }
+
+    let bytes = slice::from_raw_parts(buf, len);
+
+    // Unwinding into C is undefined behaviour, so panics become an error code.
+    let decoded = panic::catch_unwind(|| -> Result<(Png, Vec<u8>), Error> {
+        let png = Png::decoder().decode_bytes(bytes)?;
+        let data = png.rgba_bytes()?;
+        Ok((png, data))
+    });
+
+    match decoded {
+        Ok(Ok((png, data))) => {
+            let data = Box::into_raw(data.into_boxed_slice());
+            out.write(ChameleonImage {
+                width: png.dimensions.0 as u32,
+                height: png.dimensions.1 as u32,
+                data: data as *mut u8,
+                len: data.len(),
+            });
+            CHAMELEON_OK
+        }
+        Ok(Err(e)) => e.kind().code(),
+        Err(_) => CHAMELEON_ERROR_UNEXPECTED,
+    }
+}
+
+/// Frees the pixels of an image returned by chameleon_decode_png, and sets
+/// its data pointer to null so a second call does nothing.
+///
+/// # Safety
+///
+/// image must be null, or point to a ChameleonImage filled in by
+/// chameleon_decode_png.
+#[no_mangle]
+pub unsafe extern "C" fn chameleon_image_free(image: *mut ChameleonImage) {
+    if image.is_null() || (*image).data.is_null() {
+        return;
+    }
+
+    let image = &mut *image;
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+        image.data, image.len,
+    )));
+    image.data = ptr::null_mut();
+    image.len = 0;
+}
+
+/// Describes a status code.
+///
+/// # Returns
+///
+/// A static, null terminated string that must not be freed.
+#[no_mangle]
+pub extern "C" fn chameleon_status_message(status: i32) -> *const c_char {
+    let message: &'static [u8] = match status {
+        CHAMELEON_OK => b"ok\0",
+        CHAMELEON_ERROR_NULL_POINTER => b"a pointer argument was null\0",
+        CHAMELEON_ERROR_IO => b"the input could not be read\0",
+        CHAMELEON_ERROR_INVALID_FORMAT => b"the input is not a valid image\0",
+        CHAMELEON_ERROR_COMPRESSION => b"the compressed image data is corrupt\0",
+        CHAMELEON_ERROR_LIMIT_EXCEEDED => b"the image is larger than the decoder allows\0",
+        CHAMELEON_ERROR_UNEXPECTED => b"something unexpected went wrong\0",
+        CHAMELEON_ERROR_CANCELLED => b"the decode was cancelled\0",
+        _ => b"unknown status\0",
+    };
+    message.as_ptr() as *const c_char
+}
@@ -0,0 +1,215 @@
+//! Fingerprints of the pixels of an image, for checking two files hold the
+//! same picture however differently they're stored. Two PNG files with
+//! different color types, bit depths, filters, compression or chunks get
+//! the same Fingerprint as long as every pixel decodes to the same color.
+//!
+//! A Fingerprint is the SHA-256 of the width and height as 32 bit big endian
+//! integers, followed by every pixel as 16 bit big endian RGBA, top to
+//! bottom and left to right, so other tools can compute it too. 8 bit
+//! samples are scaled up to 16 bits by multiplying by 257.
+//!
+//! '''
+//! let a = Fingerprint::of_png(&Png::from_path("./a.png")?)?;
+//! let b = Fingerprint::of_png(&Png::from_path("./b.png")?)?;
+//!
+//! assert_eq!(a, b, "the images differ");
+//! '''
+
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+
+use crate::{
+    convert,
+    formats::png::{DecoderError, Png},
+    image::{ImageError, ImageView, PixelFormat},
+};
+
+/// The SHA-256 of an image's pixels in a canonical form, see the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fingerprint(pub [u8; 32]);
+
+impl Fingerprint {
+    /// Fingerprints the pixels of a PNG file, decoding a row at a time.
+    /// Alpha from tRNS is included, and Transformations aren't applied.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the Fingerprint, or a DecoderError if the
+    /// image can't be decoded.
+    pub fn of_png(png: &Png) -> Result<Self, DecoderError> {
+        let mut hasher = Hasher::new(png.dimensions);
+        let format = png.rgba_format();
+
+        png.rgba_rows(&mut |_, row| {
+            hasher.add_row(format, row);
+            Ok(())
+        })?;
+
+        Ok(hasher.finish())
+    }
+    /// Fingerprints the pixels of an image in memory.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the Fingerprint, or an ImageError if the
+    /// view's stride or buffer is too small.
+    pub fn of_image(image: &ImageView) -> Result<Self, ImageError> {
+        image.check_size()?;
+        let mut hasher = Hasher::new((image.width, image.height));
+
+        for row in image.rows() {
+            hasher.add_row(image.format, row);
+        }
+
+        Ok(hasher.finish())
+    }
+}
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+/// Feeds rows of pixels into a Sha256 in the canonical form.
+struct Hasher {
+    sha: Sha256,
+    row: Vec<u8>,
+}
+
+impl Hasher {
+    fn new((width, height): (usize, usize)) -> Self {
+        let mut sha = Sha256::new();
+        sha.update(&(width as u32).to_be_bytes());
+        sha.update(&(height as u32).to_be_bytes());
+
+        Self {
+            sha,
+            row: Vec::new(),
+        }
+    }
+    fn add_row(&mut self, format: PixelFormat, row: &[u8]) {
+        self.row.clear();
+        for pixel in row.chunks_exact(format.bytes_per_pixel()) {
+            for sample in convert::rgba16(format, pixel) {
+                self.row.extend_from_slice(&sample.to_be_bytes());
+            }
+        }
+        self.sha.update(&self.row);
+    }
+    fn finish(self) -> Fingerprint {
+        Fingerprint(self.sha.finish())
+    }
+}
+
+/// The first 32 bits of the fractional parts of the cube roots of the first
+/// 64 primes, see FIPS 180-4.
+#[rustfmt::skip]
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256, from FIPS 180-4, fed a piece at a time.
+struct Sha256 {
+    state: [u32; 8],
+    block: [u8; 64],
+    // The bytes of block filled so far.
+    filled: usize,
+    len: u64,
+}
+
+impl Sha256 {
+    fn new() -> Self {
+        Self {
+            state: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+                0x5be0cd19,
+            ],
+            block: [0; 64],
+            filled: 0,
+            len: 0,
+        }
+    }
+    fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+
+        while !data.is_empty() {
+            let take = (64 - self.filled).min(data.len());
+            self.block[self.filled..self.filled + take].copy_from_slice(&data[..take]);
+            self.filled += take;
+            data = &data[take..];
+
+            if self.filled == 64 {
+                self.compress();
+                self.filled = 0;
+            }
+        }
+    }
+    fn finish(mut self) -> [u8; 32] {
+        let bits = self.len * 8;
+
+        // A 1 bit, zeros up to 8 bytes short of a block, then the length.
+        self.update(&[0x80]);
+        while self.filled != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bits.to_be_bytes());
+
+        let mut digest = [0; 32];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+
+        digest
+    }
+    /// Mixes a full block into the state.
+    fn compress(&mut self) {
+        let mut w = [0u32; 64];
+        for (word, bytes) in w.iter_mut().zip(self.block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for (&k, &w) in K.iter().zip(&w) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(k)
+                .wrapping_add(w);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(majority);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
@@ -1,2 +1,4 @@
+#[cfg(feature = "png")]
 pub mod png;
+#[cfg(feature = "ppm")]
 pub mod ppm;
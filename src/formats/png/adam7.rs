@@ -0,0 +1,229 @@
+//! Adam7 interlacing, see Chapter 8 of the spec. The image is stored as
+//! seven smaller images, or passes, each filling in more of the pixels so a
+//! rough version can be shown before the whole file has arrived.
+
+use alloc::{vec, vec::Vec};
+use core::mem;
+#[cfg(feature = "std")]
+use std::{
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use super::{filter, DecoderError, Filters};
+
+/// The starting column, starting row, column step and row step of each
+/// pass, from 8.2 of the spec.
+const PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Gets the size of the decompressed image data of an interlaced image,
+/// every scanline of every pass with its filter type byte. Saturates rather
+/// than overflowing, the caller having checked the non-interlaced size.
+pub(crate) fn data_len((width, height): (usize, usize), bits: usize) -> usize {
+    PASSES
+        .iter()
+        .map(|&(x0, y0, dx, dy)| {
+            let pass_width = width.saturating_sub(x0).div_ceil(dx);
+            let pass_height = height.saturating_sub(y0).div_ceil(dy);
+            match pass_width == 0 || pass_height == 0 {
+                true => 0,
+                false => {
+                    let stride = pass_width.saturating_mul(bits).div_ceil(8);
+                    pass_height.saturating_mul(stride + 1)
+                }
+            }
+        })
+        .fold(0, usize::saturating_add)
+}
+
+/// Reconstructs the scanlines of every pass and puts their pixels back in
+/// place in the full image. The passes are filtered independently of each
+/// other, so with the std feature large images have them reconstructed on up
+/// to threads worker threads, each taking the next pass left.
+///
+/// # Arguments
+///
+/// * 'data' - The decompressed image data, each pass one after the other,
+///   every scanline starting with its filter type byte.
+/// * 'dimensions' - The width and height of the full image.
+/// * 'bits' - The number of bits per complete pixel. Pixels smaller than a
+///   byte are packed together, from the most significant bit down.
+/// * 'threads' - The most threads to reconstruct passes on, 1 to do them
+///   all on the calling thread.
+///
+/// # Returns
+///
+/// A result containing either the scanlines of the full image from top to
+/// bottom, packed the same way as a non-interlaced image's, or a
+/// DecoderError if the data is too short or a filter type is invalid.
+pub(crate) fn deinterlace(
+    data: &[u8],
+    (width, height): (usize, usize),
+    bits: usize,
+    threads: usize,
+) -> Result<Vec<u8>, DecoderError> {
+    // Filters work on whole bytes, even when pixels are smaller.
+    let bpp = bits.div_ceil(8).max(1);
+    let row_len = (width.saturating_mul(bits)).div_ceil(8);
+    let mut pixels = vec![0; super::image_len((row_len, height), 1)?];
+
+    // Each pass's share of the data, cut short if the data is.
+    let mut offset = 0;
+    let passes = PASSES
+        .iter()
+        .map(|&(x0, y0, dx, dy)| {
+            let pass_width = width.saturating_sub(x0).div_ceil(dx);
+            let stride = (pass_width * bits).div_ceil(8);
+            // Empty passes have no scanlines at all, not even filter bytes.
+            let pass_height = match pass_width {
+                0 => 0,
+                _ => height.saturating_sub(y0).div_ceil(dy),
+            };
+            let len = (stride + 1) * pass_height;
+            let start = offset.min(data.len());
+            offset += len;
+            (&data[start..offset.min(data.len())], stride, pass_height)
+        })
+        .collect::<Vec<_>>();
+
+    let reconstructed = reconstruct_passes(&passes, bpp, threads);
+
+    for ((x0, y0, dx, dy), (result, &(_, stride, pass_height))) in PASSES
+        .into_iter()
+        .zip(reconstructed.into_iter().zip(&passes))
+    {
+        let pass = result?;
+        let pass_width = width.saturating_sub(x0).div_ceil(dx);
+
+        for (j, current) in pass
+            .chunks_exact(stride.max(1))
+            .take(pass_height)
+            .enumerate()
+        {
+            let row = &mut pixels[(y0 + j * dy) * row_len..][..row_len];
+            if bits >= 8 {
+                for (i, pixel) in current.chunks_exact(bpp).enumerate() {
+                    let start = (x0 + i * dx) * bpp;
+                    row[start..start + bpp].copy_from_slice(pixel);
+                }
+            } else {
+                let mask = (1u8 << bits) - 1;
+                for i in 0..pass_width {
+                    let value = current[i * bits / 8] >> (8 - bits - i * bits % 8) & mask;
+                    let bit = (x0 + i * dx) * bits;
+                    row[bit / 8] |= value << (8 - bits - bit % 8);
+                }
+            }
+        }
+    }
+
+    Ok(pixels)
+}
+
+/// Reconstructs every pass, on worker threads when there are enough of them
+/// and enough data to be worth it.
+///
+/// # Arguments
+///
+/// * 'passes' - The data, stride and height of each pass.
+/// * 'bpp' - The number of bytes filters treat as a pixel.
+/// * 'threads' - The most threads to use.
+///
+/// # Returns
+///
+/// The result of reconstructing each pass, in order, see reconstruct_pass.
+fn reconstruct_passes(
+    passes: &[(&[u8], usize, usize)],
+    bpp: usize,
+    threads: usize,
+) -> Vec<Result<Vec<u8>, DecoderError>> {
+    let reconstruct = |&(data, stride, height): &(&[u8], usize, usize)| {
+        reconstruct_pass(data, stride, height, bpp)
+    };
+
+    #[cfg(feature = "std")]
+    {
+        let len = passes.iter().map(|(data, ..)| data.len()).sum::<usize>();
+        if threads > 1 && len >= super::MIN_PARALLEL_LEN {
+            // Later passes are larger, so they're handed out first.
+            let taken = AtomicUsize::new(0);
+            let mut done = thread::scope(|scope| {
+                let workers = (0..threads.min(passes.len()))
+                    .map(|_| {
+                        scope.spawn(|| {
+                            let mut results = Vec::new();
+                            while let Some(idx) = passes
+                                .len()
+                                .checked_sub(taken.fetch_add(1, Ordering::Relaxed) + 1)
+                            {
+                                results.push((idx, reconstruct(&passes[idx])));
+                            }
+                            results
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                workers
+                    .into_iter()
+                    .flat_map(|worker| worker.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+                    .collect::<Vec<_>>()
+            });
+            done.sort_by_key(|(idx, _)| *idx);
+
+            return done.into_iter().map(|(_, result)| result).collect();
+        }
+    }
+    #[cfg(not(feature = "std"))]
+    let _ = threads;
+
+    passes.iter().map(reconstruct).collect()
+}
+
+/// Reconstructs the scanlines of one pass, each filtered against the one
+/// above it in the pass, the first against nothing.
+///
+/// # Arguments
+///
+/// * 'data' - The pass's share of the image data, with filter type bytes.
+/// * 'stride' - The length of one of its scanlines, without the filter byte.
+/// * 'height' - The number of scanlines in the pass, 0 for an empty pass.
+/// * 'bpp' - The number of bytes filters treat as a pixel.
+///
+/// # Returns
+///
+/// A result containing either the reconstructed scanlines, one after the
+/// other, or a DecoderError if the data is too short or a filter type is
+/// invalid.
+fn reconstruct_pass(
+    data: &[u8],
+    stride: usize,
+    height: usize,
+    bpp: usize,
+) -> Result<Vec<u8>, DecoderError> {
+    let mut pixels = Vec::with_capacity(stride * height);
+    let mut last = Vec::new();
+    let mut current = Vec::new();
+
+    for j in 0..height {
+        let scanline = data.get(j * (stride + 1)..(j + 1) * (stride + 1)).ok_or(
+            DecoderError::InvalidChunk("the image data ends part way through an interlaced pass."),
+        )?;
+
+        let filter = Filters::try_from(scanline[0])?;
+        current.clear();
+        filter::reconstruct(filter, &scanline[1..], &last, bpp, &mut current);
+        pixels.extend_from_slice(&current);
+        mem::swap(&mut last, &mut current);
+    }
+
+    Ok(pixels)
+}
@@ -0,0 +1,44 @@
+use super::ColorType;
+
+/// The color an image should be shown against, stored in the bKGD chunk,
+/// see 11.3.5.1 of the spec. Samples are at the image's bit depth.
+///
+/// # Members
+///
+/// * 'Gray' - The gray level, for grayscale images with or without alpha.
+/// * 'Rgb' - The red, green and blue, for RGB images with or without
+///   alpha.
+/// * 'Index' - The index of a palette entry, for indexed images.
+///
+/// # Examples
+///
+/// '''
+/// let png = Png::from_path("./tests/samples/bgwn6a08.png")?;
+/// assert_eq!(png.background(), Some(Background::Rgb(255, 255, 255)));
+/// '''
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Background {
+    Gray(u16),
+    Rgb(u16, u16, u16),
+    Index(u8),
+}
+
+impl Background {
+    /// Reads a background from the data of a bKGD chunk, or None if it's the
+    /// wrong length for the color type.
+    pub(crate) fn from_bytes(color_type: ColorType, bytes: &[u8]) -> Option<Self> {
+        let sample = |i: usize| u16::from_be_bytes([bytes[i], bytes[i + 1]]);
+
+        match (color_type, bytes.len()) {
+            (ColorType::Grayscale | ColorType::GrayscaleAlpha, 2) => {
+                Some(Background::Gray(sample(0)))
+            }
+            (ColorType::RGB | ColorType::RGBA, 6) => {
+                Some(Background::Rgb(sample(0), sample(2), sample(4)))
+            }
+            (ColorType::PalleteIndex, 1) => Some(Background::Index(bytes[0])),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Apple's CgBI variant of PNG, which Xcode writes into iOS app bundles. A
+//! CgBI chunk comes before IHDR, the image data is a raw deflate stream
+//! without the zlib header and adler32 around it, and 8 bit RGB and RGBA
+//! pixels are stored as BGR and BGRA, with red, green and blue
+//! premultiplied by alpha. Decoding these is opt in, see DecodeOptions::cgbi.
+
+use alloc::vec::Vec;
+
+use super::ColorType;
+
+/// The zlib header put in front of the raw deflate data so it can go
+/// through a Decompressor, a 32K window with no preset dictionary.
+pub(super) const ZLIB_HEADER: [u8; 2] = [0x78, 0x01];
+
+/// Stands in for the adler32 after the raw deflate data. It's never
+/// checked, but ZlibStream expects the stream to end with one.
+pub(super) const ZLIB_TRAILER: [u8; 4] = [0; 4];
+
+/// How many bits ZLIB_HEADER moves the deflate data along by, taken back
+/// off positions in the stream so errors point at the right byte of IDAT.
+pub(super) const HEADER_BITS: usize = ZLIB_HEADER.len() * 8;
+
+/// Copies a reconstructed scanline into out with its pixels put back in the
+/// order and form the rest of the decoder expects: red first, and alpha
+/// that isn't premultiplied. Only 8 bit RGB and RGBA are changed, the
+/// formats Apple's tools write.
+///
+/// # Arguments
+///
+/// * 'scanline' - The reconstructed scanline, without its filter byte.
+/// * 'color_type' - The ColorType from IHDR.
+/// * 'bit_depth' - The bit depth from IHDR.
+/// * 'out' - Where to write the restored scanline, cleared first.
+pub(super) fn restore(scanline: &[u8], color_type: ColorType, bit_depth: u8, out: &mut Vec<u8>) {
+    out.clear();
+    out.extend_from_slice(scanline);
+    if bit_depth != 8 {
+        return;
+    }
+
+    match color_type {
+        ColorType::RGB => {
+            for pixel in out.chunks_exact_mut(3) {
+                pixel.swap(0, 2);
+            }
+        }
+        ColorType::RGBA => {
+            for pixel in out.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+                // Fully transparent pixels have lost their color, and fully
+                // opaque ones are unchanged by premultiplying.
+                let alpha = pixel[3] as u32;
+                if alpha == 0 || alpha == 255 {
+                    continue;
+                }
+                for sample in &mut pixel[..3] {
+                    *sample = ((*sample as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+                }
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,110 @@
+use core::{
+    fmt::{self, Debug, Display},
+    str,
+};
+
+use super::DecoderError;
+
+/// The 4 byte type of a chunk. Each byte is an ASCII letter, and the case of
+/// each letter is a property bit, see 5.4 of the spec:
+///
+/// '''
+/// IHDR  first letter:  uppercase = critical, lowercase = ancillary.
+/// ||||  second letter: uppercase = public, lowercase = private.
+/// ||||  third letter:  reserved, always uppercase.
+/// ||||  fourth letter: uppercase = unsafe to copy, lowercase = safe to copy.
+/// '''
+///
+/// # Examples
+///
+/// '''
+/// let ctype = ChunkType::new(*b"tEXt")?;
+///
+/// assert!(ctype.is_ancillary());
+/// assert!(ctype.is_safe_to_copy());
+/// assert_eq!(ctype.to_string(), "tEXt");
+/// '''
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "[u8; 4]")
+)]
+pub struct ChunkType([u8; 4]);
+
+impl ChunkType {
+    pub const IHDR: ChunkType = ChunkType(*b"IHDR");
+    pub const PLTE: ChunkType = ChunkType(*b"PLTE");
+    pub const IDAT: ChunkType = ChunkType(*b"IDAT");
+    pub const IEND: ChunkType = ChunkType(*b"IEND");
+    pub const EXIF: ChunkType = ChunkType(*b"eXIf");
+    pub const CGBI: ChunkType = ChunkType(*b"CgBI");
+
+    /// Creates a ChunkType from the 4 type bytes of a chunk.
+    ///
+    /// # Arguments
+    ///
+    /// * 'bytes' - The chunk type bytes, as they appear in the file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the ChunkType or a DecoderError if any
+    /// byte isn't an ASCII letter.
+    pub fn new(bytes: [u8; 4]) -> Result<Self, DecoderError> {
+        if !bytes.iter().all(u8::is_ascii_alphabetic) {
+            return Err(DecoderError::InvalidChunk("chunk type is invalid."));
+        }
+
+        Ok(Self(bytes))
+    }
+    /// Gets the type bytes.
+    pub fn as_bytes(&self) -> &[u8; 4] {
+        &self.0
+    }
+    /// Gets the type as a string, e.g. "IHDR".
+    pub fn as_str(&self) -> &str {
+        // Every byte was checked to be an ASCII letter.
+        str::from_utf8(&self.0).unwrap_or_default()
+    }
+    /// Whether the chunk is needed to display the image. A decoder that
+    /// doesn't recognise a critical chunk can't decode the image.
+    pub fn is_critical(&self) -> bool {
+        self.0[0].is_ascii_uppercase()
+    }
+    /// Whether the chunk can be ignored by a decoder that doesn't recognise
+    /// it.
+    pub fn is_ancillary(&self) -> bool {
+        !self.is_critical()
+    }
+    /// Whether the chunk is private to an application, rather than defined
+    /// by the spec or registered.
+    pub fn is_private(&self) -> bool {
+        self.0[1].is_ascii_lowercase()
+    }
+    /// Whether an editor that doesn't recognise the chunk may copy it into
+    /// a modified file even if the image data has changed.
+    pub fn is_safe_to_copy(&self) -> bool {
+        self.0[3].is_ascii_lowercase()
+    }
+}
+
+impl TryFrom<[u8; 4]> for ChunkType {
+    type Error = DecoderError;
+
+    fn try_from(bytes: [u8; 4]) -> Result<Self, Self::Error> {
+        Self::new(bytes)
+    }
+}
+
+impl Display for ChunkType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// Shows the type as text rather than as an array of bytes.
+impl Debug for ChunkType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ChunkType({:?})", self.as_str())
+    }
+}
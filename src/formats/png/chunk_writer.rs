@@ -0,0 +1,187 @@
+use alloc::vec::Vec;
+use std::io::{self, Read, Write};
+
+use super::{ChunkType, MAX_CHUNK_LEN, PNG_HEADER};
+use crate::{compression::checksums::Crc32, error::Error};
+
+/// How much of a source is read at a time when copying it into a chunk.
+const COPY_BUFFER: usize = 1 << 16;
+
+/// Writes chunks straight to an io::Write, for files with payloads too large
+/// to hold in memory, such as a huge ICC profile or raw data in a private
+/// chunk. A chunk's data can be copied from an io::Read as it's written, with
+/// its CRC32 worked out along the way, so only a small buffer is held.
+///
+/// The length of a chunk comes before its data, so it has to be known up
+/// front. Image data is the exception, as it can be split over any number of
+/// IDAT chunks, see ChunkWriter::write_chunks_from.
+///
+/// Nothing checks the chunks are in an order the spec allows.
+///
+/// # Examples
+///
+/// '''
+/// let mut writer = ChunkWriter::new(BufWriter::new(File::create("./out.png")?));
+/// writer.write_signature()?;
+/// writer.write_chunk(ChunkType::IHDR, &ihdr)?;
+/// let payload = File::open("./payload.bin")?;
+/// let len = payload.metadata()?.len() as u32;
+/// writer.write_chunk_from(ChunkType::new(*b"raWd")?, payload, len)?;
+/// writer.write_chunks_from(ChunkType::IDAT, File::open("./image.zlib")?, 1 << 20)?;
+/// writer.write_chunk(ChunkType::IEND, &[])?;
+/// '''
+#[derive(Debug)]
+pub struct ChunkWriter<W: Write> {
+    writer: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> ChunkWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: Vec::new(),
+        }
+    }
+    /// Writes the PNG signature, which starts every file.
+    pub fn write_signature(&mut self) -> Result<(), Error> {
+        self.writer.write_all(&PNG_HEADER)?;
+
+        Ok(())
+    }
+    /// Writes a chunk whose data is already in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * 'ctype' - The type of the chunk.
+    /// * 'data' - The chunk's data.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or an Error if the data is too long for a chunk or
+    /// writing fails.
+    pub fn write_chunk(&mut self, ctype: ChunkType, data: &[u8]) -> Result<(), Error> {
+        let len = chunk_len(data.len())?;
+        let mut crc = Crc32::new();
+        crc.update(ctype.as_bytes());
+        crc.update(data);
+
+        self.writer.write_all(&len.to_be_bytes())?;
+        self.writer.write_all(ctype.as_bytes())?;
+        self.writer.write_all(data)?;
+        self.writer.write_all(&crc.finish().to_be_bytes())?;
+
+        Ok(())
+    }
+    /// Writes a chunk, copying its data from reader a piece at a time.
+    ///
+    /// # Arguments
+    ///
+    /// * 'ctype' - The type of the chunk.
+    /// * 'reader' - The source of the chunk's data. Exactly len bytes are
+    ///   read from it.
+    /// * 'len' - The length of the chunk's data.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or an Error if len is too long for a chunk, reader
+    /// ends before len bytes, or reading or writing fails. Once anything has
+    /// been written, the chunk is left cut short.
+    pub fn write_chunk_from<R: Read>(
+        &mut self,
+        ctype: ChunkType,
+        mut reader: R,
+        len: u32,
+    ) -> Result<(), Error> {
+        chunk_len(len as usize)?;
+        self.writer.write_all(&len.to_be_bytes())?;
+        self.writer.write_all(ctype.as_bytes())?;
+
+        let mut crc = Crc32::new();
+        crc.update(ctype.as_bytes());
+        self.buffer.resize(COPY_BUFFER, 0);
+        let mut remaining = len as usize;
+        while remaining > 0 {
+            let piece = &mut self.buffer[..remaining.min(COPY_BUFFER)];
+            let read = match reader.read(piece) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            crc.update(&piece[..read]);
+            self.writer.write_all(&piece[..read])?;
+            remaining -= read;
+        }
+        self.writer.write_all(&crc.finish().to_be_bytes())?;
+
+        Ok(())
+    }
+    /// Writes everything left in reader as a run of chunks of the same
+    /// type, each holding at most max_len bytes, for IDAT or another chunk
+    /// the spec allows to be split. Each chunk is read whole before it's
+    /// written, so up to max_len bytes are held at once.
+    ///
+    /// # Arguments
+    ///
+    /// * 'ctype' - The type of the chunks.
+    /// * 'reader' - The source of the data, read to its end.
+    /// * 'max_len' - The most data to put in one chunk, kept between 1 and
+    ///   the longest a chunk can be.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the number of chunks written, none if
+    /// reader was already empty, or an Error if reading or writing fails.
+    pub fn write_chunks_from<R: Read>(
+        &mut self,
+        ctype: ChunkType,
+        mut reader: R,
+        max_len: usize,
+    ) -> Result<usize, Error> {
+        let max_len = max_len.clamp(1, MAX_CHUNK_LEN) as u64;
+        let mut buffer = Vec::new();
+        let mut chunks = 0;
+
+        loop {
+            buffer.clear();
+            (&mut reader).take(max_len).read_to_end(&mut buffer)?;
+            if buffer.is_empty() {
+                return Ok(chunks);
+            }
+            self.write_chunk(ctype, &buffer)?;
+            chunks += 1;
+        }
+    }
+    /// Flushes the writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+
+        Ok(())
+    }
+    /// Gets the writer, for writing bytes that aren't whole chunks.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+    /// Gives back the writer, without flushing it.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Checks a length fits in a chunk.
+///
+/// # Returns
+///
+/// A result containing either the length as the chunk's length field, or
+/// an Error if it's longer than the spec allows.
+fn chunk_len(len: usize) -> Result<u32, Error> {
+    match len <= MAX_CHUNK_LEN {
+        true => Ok(len as u32),
+        false => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "chunk data is longer than 2^31 - 1 bytes.",
+        )
+        .into()),
+    }
+}
@@ -0,0 +1,547 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::mem;
+
+#[cfg(feature = "std")]
+use std::{
+    io::{self, Read, Write},
+    panic, thread,
+};
+
+use super::{
+    filter, rewrite::write_chunk, EncodeOptions, FilterStrategy, Filters, Palette, Time, PNG_HEADER,
+};
+#[cfg(feature = "std")]
+use super::{ChunkType, ChunkWriter};
+#[cfg(feature = "std")]
+use crate::{
+    compression::{
+        checksums::Adler32,
+        zlib::{stored_block, MAX_STORED_BLOCK, ZLIB_HEADER},
+    },
+    error::Error,
+};
+use crate::{
+    convert,
+    image::{ImageError, ImageView, PixelFormat},
+};
+
+/// The largest width or height a PNG file can hold, see 11.2.2 of the spec.
+const MAX_DIMENSION: usize = 0x7fff_ffff;
+/// The fewest bytes of scanlines worth giving a thread of their own.
+#[cfg(feature = "std")]
+const MIN_BAND_SIZE: usize = 1 << 16;
+
+/// Encodes an image as a PNG file, reading its rows in place.
+///
+/// # Arguments
+///
+/// * 'image' - The ImageView to encode, checked by the caller.
+/// * 'options' - The EncodeOptions to encode with.
+///
+/// # Returns
+///
+/// A result containing either the PNG file as bytes or an ImageError if the
+/// image can't be stored in a PNG file.
+pub(crate) fn encode(image: &ImageView, options: &EncodeOptions) -> Result<Vec<u8>, ImageError> {
+    if !(1..=MAX_DIMENSION).contains(&image.width) || !(1..=MAX_DIMENSION).contains(&image.height) {
+        return Err(ImageError::InvalidDimensions(image.width, image.height));
+    }
+
+    let layout = match options.reduce {
+        true => Layout::reduced(image),
+        false => Layout::of(image.format),
+    };
+
+    let stride = (image.width * layout.bits_per_pixel()).div_ceil(8);
+    let rows = image.rows().collect::<Vec<_>>();
+
+    #[cfg(feature = "std")]
+    let filtered = {
+        let bands = options
+            .threads
+            .clamp(1, (stride * rows.len() / MIN_BAND_SIZE).max(1));
+        filter_bands(&rows, bands, image.format, &layout, options.filter, stride)
+    };
+    #[cfg(not(feature = "std"))]
+    let filtered = filter_band(&rows, None, image.format, &layout, options.filter, stride);
+
+    let mut file = Vec::new();
+    write_header(&mut file, (image.width, image.height), &layout, options);
+    write_chunk(
+        &mut file,
+        b"IDAT",
+        &options
+            .compressor
+            .compress_with(&filtered, &options.compression),
+    );
+    write_chunk(&mut file, b"IEND", &[]);
+
+    Ok(file)
+}
+
+/// Converts and filters a band of rows.
+///
+/// # Arguments
+///
+/// * 'rows' - The rows of pixels in the band.
+/// * 'previous' - The row of pixels just above the band, or None if the
+///   band starts at the top of the image.
+/// * 'format' - The PixelFormat of the rows.
+/// * 'layout' - The Layout the rows are written in.
+/// * 'strategy' - How each scanline's filter is picked.
+/// * 'stride' - The length of a scanline in bytes, without its filter type.
+///
+/// # Returns
+///
+/// The filtered scanlines, each starting with its filter type.
+fn filter_band(
+    rows: &[&[u8]],
+    previous: Option<&[u8]>,
+    format: PixelFormat,
+    layout: &Layout,
+    strategy: FilterStrategy,
+    stride: usize,
+) -> Vec<u8> {
+    let mut filtered = Vec::with_capacity((stride + 1) * rows.len());
+    let mut scanline = Vec::with_capacity(stride);
+    let mut last = Vec::with_capacity(stride);
+    if let Some(pixels) = previous {
+        layout.write_row(pixels, format, &mut last);
+    }
+
+    for pixels in rows {
+        scanline.clear();
+        layout.write_row(pixels, format, &mut scanline);
+
+        layout.filter_row(strategy, &scanline, &last, &mut filtered);
+        mem::swap(&mut last, &mut scanline);
+    }
+
+    filtered
+}
+
+/// Splits the rows into bands and filters each on a scoped thread of its
+/// own, see filter_band. Scanlines are filtered against the unfiltered row
+/// above them, so the bands are independent, and joined in order they give
+/// the same bytes as filtering every row on one thread.
+#[cfg(feature = "std")]
+fn filter_bands(
+    rows: &[&[u8]],
+    bands: usize,
+    format: PixelFormat,
+    layout: &Layout,
+    strategy: FilterStrategy,
+    stride: usize,
+) -> Vec<u8> {
+    if bands <= 1 {
+        return filter_band(rows, None, format, layout, strategy, stride);
+    }
+    let per_band = rows.len().div_ceil(bands);
+
+    thread::scope(|scope| {
+        let workers = (0..rows.len())
+            .step_by(per_band)
+            .map(|start| {
+                let band = &rows[start..(start + per_band).min(rows.len())];
+                let previous = start.checked_sub(1).map(|y| rows[y]);
+                scope.spawn(move || filter_band(band, previous, format, layout, strategy, stride))
+            })
+            .collect::<Vec<_>>();
+
+        let mut filtered = Vec::with_capacity((stride + 1) * rows.len());
+        for worker in workers {
+            filtered.extend(worker.join().unwrap_or_else(|e| panic::resume_unwind(e)));
+        }
+        filtered
+    })
+}
+
+/// Encodes a PNG file a row at a time, writing it out as it goes, for images
+/// too large to hold in memory at once. Only the row before the one being
+/// written and a stored deflate block of filtered data are held.
+///
+/// The color type is picked from the PixelFormat alone, as reducing it
+/// would mean seeing every pixel before the header could be written, so
+/// EncodeOptions::reduce is ignored. The image data is always written as
+/// stored blocks, EncodeOptions::compressor and EncodeOptions::compression
+/// are ignored too.
+///
+/// Chunks too large to hold in memory can be copied in from an io::Read
+/// before the first IDAT chunk goes out, see StreamEncoder::write_chunk_from.
+///
+/// # Examples
+///
+/// '''
+/// let file = BufWriter::new(File::create("./huge.png")?);
+/// let mut encoder = StreamEncoder::new(file, width, height, PixelFormat::Rgb8, &EncodeOptions::new())?;
+///
+/// for row in rows {
+///     encoder.write_row(&row)?;
+/// }
+/// encoder.finish()?;
+/// '''
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct StreamEncoder<W: Write> {
+    writer: ChunkWriter<W>,
+    dimensions: (usize, usize),
+    format: PixelFormat,
+    layout: Layout,
+    filter: FilterStrategy,
+    rows: usize,
+    scanline: Vec<u8>,
+    last: Vec<u8>,
+    // Filtered scanlines waiting to fill a stored block.
+    pending: Vec<u8>,
+    adler: Adler32,
+    // Whether the zlib header has gone out in an IDAT chunk yet.
+    started: bool,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> StreamEncoder<W> {
+    /// Starts a PNG file, writing everything that comes before the image
+    /// data.
+    ///
+    /// # Arguments
+    ///
+    /// * 'writer' - Where to write the file.
+    /// * 'width' - The width of the image in pixels.
+    /// * 'height' - The height of the image in pixels.
+    /// * 'format' - The PixelFormat of the rows, and the color type and bit
+    ///   depth the file is written with.
+    /// * 'options' - The EncodeOptions to encode with.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the StreamEncoder or an Error if the
+    /// dimensions can't be stored in a PNG file or writing fails.
+    pub fn new(
+        mut writer: W,
+        width: usize,
+        height: usize,
+        format: PixelFormat,
+        options: &EncodeOptions,
+    ) -> Result<Self, Error> {
+        if !(1..=MAX_DIMENSION).contains(&width) || !(1..=MAX_DIMENSION).contains(&height) {
+            return Err(ImageError::InvalidDimensions(width, height).into());
+        }
+
+        let layout = Layout::of(format);
+        let mut header = Vec::new();
+        write_header(&mut header, (width, height), &layout, options);
+        writer.write_all(&header)?;
+
+        Ok(Self {
+            writer: ChunkWriter::new(writer),
+            dimensions: (width, height),
+            format,
+            layout,
+            filter: options.filter,
+            rows: 0,
+            scanline: Vec::new(),
+            last: Vec::new(),
+            pending: Vec::with_capacity(MAX_STORED_BLOCK),
+            adler: Adler32::new(),
+            started: false,
+        })
+    }
+    /// Writes an ancillary chunk, copying its data from reader a piece at a
+    /// time, see ChunkWriter::write_chunk_from. The image data has to come
+    /// in one run of IDAT chunks, so this only works until the first of
+    /// them has been written, which happens once rows fill a stored block.
+    ///
+    /// # Arguments
+    ///
+    /// * 'ctype' - The type of the chunk, which has to be ancillary.
+    /// * 'reader' - The source of the chunk's data.
+    /// * 'len' - The length of the chunk's data.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or an Error if the chunk is critical, image data has
+    /// already been written, or copying the chunk fails.
+    pub fn write_chunk_from<R: Read>(
+        &mut self,
+        ctype: ChunkType,
+        reader: R,
+        len: u32,
+    ) -> Result<(), Error> {
+        let refused = if ctype.is_critical() {
+            "only ancillary chunks can be added."
+        } else if self.started {
+            "chunks can't be added once image data is written."
+        } else {
+            return self.writer.write_chunk_from(ctype, reader, len);
+        };
+
+        Err(io::Error::new(io::ErrorKind::InvalidInput, refused).into())
+    }
+    /// Filters the next row of the image and writes it out once a stored
+    /// block fills.
+    ///
+    /// # Arguments
+    ///
+    /// * 'row' - The row's pixels, in the PixelFormat given to new.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or an Error if the row is the wrong length, every
+    /// row has already been written, or writing fails.
+    pub fn write_row(&mut self, row: &[u8]) -> Result<(), Error> {
+        let (width, height) = self.dimensions;
+        let row_len = width * self.format.bytes_per_pixel();
+        if row.len() != row_len {
+            return Err(ImageError::BufferSize(row_len, row.len()).into());
+        }
+        if self.rows == height {
+            return Err(ImageError::BufferSize(row_len * height, row_len * (height + 1)).into());
+        }
+
+        self.scanline.clear();
+        self.layout.write_row(row, self.format, &mut self.scanline);
+
+        let start = self.pending.len();
+        self.layout
+            .filter_row(self.filter, &self.scanline, &self.last, &mut self.pending);
+        self.adler.update(&self.pending[start..]);
+        mem::swap(&mut self.last, &mut self.scanline);
+        self.rows += 1;
+
+        while self.pending.len() >= MAX_STORED_BLOCK {
+            let rest = self.pending.split_off(MAX_STORED_BLOCK);
+            let block = mem::replace(&mut self.pending, rest);
+            self.write_idat(&block, false)?;
+        }
+
+        Ok(())
+    }
+    /// Writes the last of the image data and the IEND chunk.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the writer, or an Error if fewer rows were
+    /// written than the image is tall or writing fails.
+    pub fn finish(mut self) -> Result<W, Error> {
+        let (width, height) = self.dimensions;
+        if self.rows < height {
+            let row_len = width * self.format.bytes_per_pixel();
+            return Err(ImageError::BufferSize(row_len * height, row_len * self.rows).into());
+        }
+
+        let block = mem::take(&mut self.pending);
+        self.write_idat(&block, true)?;
+
+        self.writer.write_chunk(ChunkType::IEND, &[])?;
+        self.writer.flush()?;
+
+        Ok(self.writer.into_inner())
+    }
+    /// Writes an IDAT chunk holding one stored block, with the zlib header
+    /// before the first and the checksum after the last.
+    fn write_idat(&mut self, block: &[u8], last: bool) -> Result<(), Error> {
+        let mut data = Vec::with_capacity(block.len() + 11);
+        if !mem::replace(&mut self.started, true) {
+            data.extend_from_slice(&ZLIB_HEADER);
+        }
+        stored_block(&mut data, block, last);
+        if last {
+            data.extend_from_slice(&self.adler.finish().to_be_bytes());
+        }
+
+        self.writer.write_chunk(ChunkType::IDAT, &data)
+    }
+}
+
+/// Writes the PNG signature and every chunk that comes before the image
+/// data.
+fn write_header(
+    file: &mut Vec<u8>,
+    (width, height): (usize, usize),
+    layout: &Layout,
+    options: &EncodeOptions,
+) {
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(height as u32).to_be_bytes());
+    // Bit depth, color type, then compression, filter and interlace methods.
+    ihdr.extend_from_slice(&[layout.bit_depth(), layout.color_type(), 0, 0, 0]);
+
+    file.extend_from_slice(&PNG_HEADER);
+    write_chunk(file, b"IHDR", &ihdr);
+    if let Some(time) = options.time {
+        let time = if options.reproducible {
+            Time::EPOCH
+        } else {
+            time
+        };
+        write_chunk(file, b"tIME", &time.to_bytes());
+    }
+    if let Layout::Indexed { palette, .. } = layout {
+        write_chunk(file, b"PLTE", &palette.plte_bytes());
+        if palette.has_alpha() {
+            write_chunk(file, b"tRNS", &palette.trns_bytes());
+        }
+    }
+    if let Some(physical) = options.metadata.physical {
+        write_chunk(file, b"pHYs", &physical.to_bytes());
+    }
+    if let Some(exif) = &options.metadata.exif {
+        write_chunk(file, b"eXIf", exif);
+    }
+    for text in &options.metadata.text {
+        if let Some((ctype, data)) = text.to_chunk(options.compressor, &options.compression) {
+            write_chunk(file, &ctype, &data);
+        }
+    }
+    // A critical chunk would change how the image is decoded.
+    for (ctype, data) in options
+        .metadata
+        .chunks
+        .iter()
+        .filter(|(ctype, _)| ctype.is_ancillary())
+    {
+        write_chunk(file, ctype.as_bytes(), data);
+    }
+}
+
+/// The color type and bit depth an image is written with.
+///
+/// # Members
+///
+/// * 'Direct' - The samples of each pixel in a PixelFormat, which decides
+///   the color type and bit depth.
+/// * 'Indexed' - Indices into a palette, packed at 1, 2, 4 or 8 bits.
+#[derive(Debug)]
+enum Layout {
+    Direct(PixelFormat),
+    Indexed {
+        palette: Palette,
+        indices: BTreeMap<[u8; 4], u8>,
+        bit_depth: u8,
+    },
+}
+
+impl Layout {
+    /// The layout matching a PixelFormat, with nothing reduced.
+    fn of(format: PixelFormat) -> Self {
+        Layout::Direct(format)
+    }
+    /// Picks the smallest layout that holds every pixel of the image
+    /// exactly.
+    fn reduced(image: &ImageView) -> Self {
+        let analysis = image.analyze();
+
+        if let Some(mut colors) = analysis.colors {
+            let bit_depth = match colors.len() {
+                0..=2 => 1,
+                3..=4 => 2,
+                5..=16 => 4,
+                _ => 8,
+            };
+
+            // Grayscale takes a byte a pixel like an 8 bit palette, without
+            // the PLTE chunk.
+            if !(analysis.grayscale && analysis.opaque && bit_depth == 8) {
+                // Transparent entries go first, so tRNS can leave out the
+                // opaque ones after them.
+                colors.sort_by_key(|color| color[3] == 255);
+                let indices = colors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &color)| (color, i as u8))
+                    .collect();
+
+                return Layout::Indexed {
+                    palette: Palette::new(colors),
+                    indices,
+                    bit_depth,
+                };
+            }
+        }
+
+        Layout::Direct(
+            match (analysis.grayscale, analysis.opaque, analysis.bit_depth) {
+                (true, true, 8) => PixelFormat::Gray8,
+                (true, false, 8) => PixelFormat::GrayAlpha8,
+                (false, true, 8) => PixelFormat::Rgb8,
+                (false, false, 8) => PixelFormat::Rgba8,
+                (true, true, _) => PixelFormat::Gray16,
+                (true, false, _) => PixelFormat::GrayAlpha16,
+                (false, true, _) => PixelFormat::Rgb16,
+                (false, false, _) => PixelFormat::Rgba16,
+            },
+        )
+    }
+    /// The color type byte of IHDR.
+    fn color_type(&self) -> u8 {
+        match self {
+            Layout::Direct(PixelFormat::Gray8 | PixelFormat::Gray16) => 0,
+            Layout::Direct(PixelFormat::Rgb8 | PixelFormat::Rgb16) => 2,
+            Layout::Indexed { .. } => 3,
+            Layout::Direct(PixelFormat::GrayAlpha8 | PixelFormat::GrayAlpha16) => 4,
+            Layout::Direct(PixelFormat::Rgba8 | PixelFormat::Rgba16) => 6,
+        }
+    }
+    /// The bit depth byte of IHDR.
+    fn bit_depth(&self) -> u8 {
+        match self {
+            Layout::Direct(format) => format.bit_depth(),
+            Layout::Indexed { bit_depth, .. } => *bit_depth,
+        }
+    }
+    fn bits_per_pixel(&self) -> usize {
+        match self {
+            Layout::Direct(format) => format.bytes_per_pixel() * 8,
+            Layout::Indexed { bit_depth, .. } => *bit_depth as usize,
+        }
+    }
+    /// Filters a scanline in the layout, appending its filter type byte and
+    /// the filtered bytes to out.
+    fn filter_row(&self, strategy: FilterStrategy, row: &[u8], previous: &[u8], out: &mut Vec<u8>) {
+        let bpp = self.bits_per_pixel().div_ceil(8);
+        let filter = match strategy {
+            FilterStrategy::Fixed(filter) => filter,
+            // Only indexed images are written under 8 bits per sample.
+            FilterStrategy::Adaptive => match self {
+                Layout::Direct(_) => filter::choose(row, previous, bpp),
+                Layout::Indexed { .. } => Filters::None,
+            },
+        };
+
+        out.push(filter as u8);
+        filter::filter(filter, row, previous, bpp, out);
+    }
+    /// Converts a row of pixels to the layout, appending it to out.
+    fn write_row(&self, pixels: &[u8], format: PixelFormat, out: &mut Vec<u8>) {
+        match self {
+            // Rows are always whole pixels, so this can't fail.
+            Layout::Direct(layout) => {
+                let _ = convert::convert_pixels(format, *layout, pixels, out);
+            }
+            Layout::Indexed {
+                indices, bit_depth, ..
+            } => {
+                // Pack the indices from the most significant bit down, see
+                // 7.2 of the spec.
+                let per_byte = 8 / *bit_depth as usize;
+                let mut byte = 0u16;
+                let mut filled = 0;
+
+                for pixel in pixels.chunks_exact(format.bytes_per_pixel()) {
+                    let color = convert::rgba8(format, pixel);
+                    byte = byte << bit_depth | indices[&color] as u16;
+                    filled += 1;
+                    if filled == per_byte {
+                        out.push(byte as u8);
+                        byte = 0;
+                        filled = 0;
+                    }
+                }
+                if filled > 0 {
+                    out.push((byte << (*bit_depth as usize * (per_byte - filled))) as u8);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! The scanline filters from Chapter 6 of the spec, in both directions, for
+//! anyone building a PNG encoder or decoder on top of chameleon.
+//!
+//! Each scanline is filtered on its own, byte by byte, regardless of bit
+//! depth. Filters predict each byte from its neighbours: the corresponding
+//! byte of the pixel to the left, of the pixel above, and of the pixel above
+//! and to the left. Before the first pixel, and above the first scanline,
+//! the neighbours are taken to be 0.
+//!
+//! '''
+//! let mut filtered = Vec::new();
+//! filter::filter(Filters::Paeth, &row, &previous, 3, &mut filtered);
+//!
+//! let mut restored = Vec::new();
+//! filter::reconstruct(Filters::Paeth, &filtered, &previous, 3, &mut restored);
+//!
+//! assert_eq!(restored, row);
+//! '''
+
+use alloc::vec::Vec;
+
+use super::DecoderError;
+
+/// Enum for storing each filter type described in Chapter 6 of the spec.
+/// Each filter is defined by a single byte before each scanline, and
+/// applies to each byte, regardless of bit depth. Most pixels have more
+/// than one bytes worth of information, and so in these cases, the filter
+/// is applied referencing the corrosponding byte of the previous pixel.
+/// So, if the color type is RGB with a bit-depth of 8, each sample for
+/// red would be filtered together, and then each sample for blue, and so
+/// on.
+///
+/// # Members
+///
+/// * 'None' - No filter is applied.
+/// * 'Sub' - Each byte transmits the difference between itself and the last
+///   corrosponding byte.
+/// * 'Up' - Each byte is the same as sub however it transmits the difference
+///   between the current byte and the corrosponding byte from the pixel
+///   directly above it (same position in the previous scanline).
+/// * 'Average' - Subtracts the average of the bytes in the pixels to the left
+///   and above from the current byte.
+/// * 'Paeth' - A bit too complex to be worth summarizing, it's described in
+///   section 6.6 of the specification.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Filters {
+    None,
+    Sub,
+    Up,
+    Average,
+    Paeth,
+}
+
+impl TryFrom<u8> for Filters {
+    type Error = DecoderError;
+
+    /// Reads the filter type byte at the start of a scanline.
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        match byte {
+            0 => Ok(Filters::None),
+            1 => Ok(Filters::Sub),
+            2 => Ok(Filters::Up),
+            3 => Ok(Filters::Average),
+            4 => Ok(Filters::Paeth),
+            other => Err(DecoderError::InvalidFilter(other)),
+        }
+    }
+}
+
+impl From<Filters> for u8 {
+    /// Gets the filter type byte written at the start of a scanline.
+    fn from(filter: Filters) -> Self {
+        filter as u8
+    }
+}
+
+/// How an encoder picks the filter for each scanline.
+///
+/// # Members
+///
+/// * 'Fixed' - Every scanline is filtered with the same filter.
+/// * 'Adaptive' - Each scanline is filtered with whichever filter gives the
+///   smallest sum of absolute differences, see choose. Indexed images
+///   and those under 8 bits per sample always use Filters::None, as
+///   the spec suggests in 12.8, since their bytes aren't samples that
+///   can be predicted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterStrategy {
+    Fixed(Filters),
+    #[default]
+    Adaptive,
+}
+
+impl From<Filters> for FilterStrategy {
+    fn from(filter: Filters) -> Self {
+        FilterStrategy::Fixed(filter)
+    }
+}
+
+/// Picks the filter for a scanline with the minimum sum of absolute
+/// differences heuristic from 12.8 of the spec. Each filtered byte is taken
+/// as a signed difference, and the filter whose differences add up to the
+/// least is the one most likely to compress well. Ties go to the simpler
+/// filter.
+///
+/// # Arguments
+///
+/// * 'row' - The unfiltered scanline.
+/// * 'previous' - The unfiltered scanline above, or an empty slice for the
+///   first scanline.
+/// * 'bpp' - The number of bytes per complete pixel, see
+///   ColorType::bytes_per_pixel. Treated as 1 if 0.
+///
+/// # Returns
+///
+/// The filter to apply.
+pub fn choose(row: &[u8], previous: &[u8], bpp: usize) -> Filters {
+    let bpp = bpp.max(1);
+    let mut best = (Filters::None, u64::MAX);
+
+    for filter in [
+        Filters::None,
+        Filters::Sub,
+        Filters::Up,
+        Filters::Average,
+        Filters::Paeth,
+    ] {
+        let mut sum = 0;
+        for (i, &byte) in row.iter().enumerate() {
+            let left = if i >= bpp { row[i - bpp] } else { 0 };
+            let difference = byte.wrapping_sub(predict(filter, i, left, previous, bpp));
+            sum += (difference as i8).unsigned_abs() as u64;
+            // This filter can't win any more.
+            if sum >= best.1 {
+                break;
+            }
+        }
+        if sum < best.1 {
+            best = (filter, sum);
+        }
+    }
+
+    best.0
+}
+
+/// Filters a scanline, the step an encoder takes before compression.
+///
+/// # Arguments
+///
+/// * 'filter' - The filter to apply. The filter type byte is not written,
+///   push 'filter as u8' first when building image data.
+/// * 'row' - The unfiltered scanline.
+/// * 'previous' - The unfiltered scanline above, or an empty slice for the
+///   first scanline.
+/// * 'bpp' - The number of bytes per complete pixel, see
+///   ColorType::bytes_per_pixel. Treated as 1 if 0.
+/// * 'out' - The filtered bytes are appended to this.
+pub fn filter(filter: Filters, row: &[u8], previous: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    let bpp = bpp.max(1);
+    out.reserve(row.len());
+
+    for (i, &byte) in row.iter().enumerate() {
+        let left = if i >= bpp { row[i - bpp] } else { 0 };
+        out.push(byte.wrapping_sub(predict(filter, i, left, previous, bpp)));
+    }
+}
+
+/// Reverses a filter, the step a decoder takes after decompression.
+///
+/// # Arguments
+///
+/// * 'filter' - The filter the scanline was filtered with.
+/// * 'row' - The filtered scanline, without its filter type byte.
+/// * 'previous' - The already reconstructed scanline above, or an empty
+///   slice for the first scanline.
+/// * 'bpp' - The number of bytes per complete pixel, see
+///   ColorType::bytes_per_pixel. Treated as 1 if 0.
+/// * 'out' - The reconstructed bytes are appended to this.
+pub fn reconstruct(filter: Filters, row: &[u8], previous: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    let bpp = bpp.max(1);
+    let start = out.len();
+    out.reserve(row.len());
+
+    for (i, &byte) in row.iter().enumerate() {
+        let left = if i >= bpp { out[start + i - bpp] } else { 0 };
+        out.push(byte.wrapping_add(predict(filter, i, left, previous, bpp)));
+    }
+}
+
+/// The Paeth predictor from 6.6 of the spec. Picks whichever of the three
+/// neighbours is closest to left + above - upper_left.
+///
+/// # Arguments
+///
+/// * 'left' - The corresponding byte of the pixel to the left.
+/// * 'above' - The corresponding byte of the pixel above.
+/// * 'upper_left' - The corresponding byte of the pixel above and to the
+///   left.
+///
+/// # Returns
+///
+/// The predicted byte.
+pub fn paeth_predictor(left: u8, above: u8, upper_left: u8) -> u8 {
+    let (a, b, c) = (left as i16, above as i16, upper_left as i16);
+    let p = a + b - c;
+    let pa = p.abs_diff(a);
+    let pb = p.abs_diff(b);
+    let pc = p.abs_diff(c);
+    if pa <= pb && pa <= pc {
+        left
+    } else if pb <= pc {
+        above
+    } else {
+        upper_left
+    }
+}
+
+/// Predicts the byte at index i of a scanline from its neighbours. Missing
+/// bytes of previous are taken to be 0.
+fn predict(filter: Filters, i: usize, left: u8, previous: &[u8], bpp: usize) -> u8 {
+    let above = previous.get(i).copied().unwrap_or(0);
+    let upper_left = if i >= bpp {
+        previous.get(i - bpp).copied().unwrap_or(0)
+    } else {
+        0
+    };
+
+    match filter {
+        Filters::None => 0,
+        Filters::Sub => left,
+        Filters::Up => above,
+        Filters::Average => ((left as u16 + above as u16) / 2) as u8,
+        Filters::Paeth => paeth_predictor(left, above, upper_left),
+    }
+}
@@ -0,0 +1,231 @@
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+#[cfg(feature = "std")]
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use super::{
+    check_dimensions, data_len, image_len, ColorType, DecoderError, Interlace, Png, PNG_HEADER,
+};
+
+/// The length of the signature and a whole IHDR chunk, CRC included.
+const HEADER_LEN: usize = 33;
+
+/// What the IHDR chunk says about an image, from Png::probe without the rest
+/// of the file being read, or from Png::info.
+///
+/// # Fields
+///
+/// * 'dimensions' - The width and height in pixels.
+/// * 'bit_depth' - The number of bits in each sample, or each palette index.
+/// * 'color_type' - The ColorType of the image data.
+/// * 'interlace' - How the image data is laid out, see Interlace.
+/// * 'chunks' - The number of chunks in the file, IHDR and IEND included,
+///   if they were counted by Png::probe_chunks.
+///
+/// # Examples
+///
+/// '''
+/// let info = Png::probe("./huge.png")?;
+/// if info.dimensions.0 * info.dimensions.1 > 100_000_000 {
+///     return Err("too large to open".into());
+/// }
+/// '''
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PngInfo {
+    pub dimensions: (usize, usize),
+    pub bit_depth: u8,
+    pub color_type: ColorType,
+    pub interlace: Interlace,
+    pub chunks: Option<usize>,
+}
+
+impl PngInfo {
+    /// Reads a PngInfo from the data of an IHDR chunk, checking it the same
+    /// way decoding does.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the PngInfo, or a DecoderError if IHDR
+    /// has the wrong length or holds a value the spec doesn't allow.
+    pub(super) fn from_ihdr(ihdr: &[u8]) -> Result<Self, DecoderError> {
+        if ihdr.len() != 13 {
+            return Err(DecoderError::InvalidChunk(
+                "IHDR chunk is missing or has the wrong length.",
+            ));
+        }
+
+        let dimensions = Png::ihdr_dimensions(ihdr);
+        check_dimensions(dimensions)?;
+
+        let bit_depth = ihdr[8];
+
+        let color_type = ColorType::try_from(ihdr[9])?;
+
+        // Each color type only allows certain bit depths, see 11.2.2 of the spec.
+        let valid_bit_depth = match color_type {
+            ColorType::Grayscale => matches!(bit_depth, 1 | 2 | 4 | 8 | 16),
+            ColorType::PalleteIndex => matches!(bit_depth, 1 | 2 | 4 | 8),
+            ColorType::RGB | ColorType::GrayscaleAlpha | ColorType::RGBA => {
+                matches!(bit_depth, 8 | 16)
+            }
+        };
+
+        if !valid_bit_depth {
+            return Err(DecoderError::InvalidBitDepth(bit_depth));
+        }
+
+        // Every buffer sized from the dimensions holds at most 8 bytes a
+        // pixel, Rgba16, or the image data itself, so checking those here
+        // keeps the arithmetic after this from overflowing.
+        image_len(dimensions, 8)?;
+        data_len(dimensions, color_type, bit_depth)?;
+
+        let interlace = Interlace::try_from(ihdr[12])?;
+
+        Ok(Self {
+            dimensions,
+            bit_depth,
+            color_type,
+            interlace,
+            chunks: None,
+        })
+    }
+}
+
+// Laid out the way the info command prints it.
+impl Display for PngInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{}, {} at {} bits per sample, interlace {}",
+            self.dimensions.0, self.dimensions.1, self.color_type, self.bit_depth, self.interlace
+        )?;
+        match self.chunks {
+            Some(chunks) => write!(f, ", {chunks} chunks"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Png {
+    /// Gets what IHDR says about the image.
+    ///
+    /// # Returns
+    ///
+    /// The PngInfo, without a count of the chunks.
+    pub fn info(&self) -> PngInfo {
+        PngInfo {
+            dimensions: self.dimensions,
+            bit_depth: self.bit_depth,
+            color_type: self.color_type,
+            interlace: self.interlace,
+            chunks: None,
+        }
+    }
+    /// Reads the signature and IHDR chunk of the PNG file at the given path,
+    /// and nothing after them, to find its size and format without decoding
+    /// it. The CRC of IHDR isn't checked.
+    ///
+    /// # Arguments
+    ///
+    /// * 'path' - The file path to the PNG file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the PngInfo, or a DecoderError if the
+    /// file can't be read, isn't a PNG file or has an invalid IHDR.
+    #[cfg(feature = "std")]
+    pub fn probe<P: AsRef<Path>>(path: P) -> Result<PngInfo, DecoderError> {
+        let path = path.as_ref();
+
+        File::open(path)
+            .map_err(DecoderError::from)
+            .and_then(Self::probe_reader)
+            .map_err(|e| e.with_path(path))
+    }
+    /// Reads the signature and IHDR chunk from anything that implements
+    /// io::Read, see Png::probe. Only the first 33 bytes are read.
+    ///
+    /// # Arguments
+    ///
+    /// * 'reader' - The source of the PNG file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the PngInfo or a DecoderError.
+    #[cfg(feature = "std")]
+    pub fn probe_reader<R: Read>(reader: R) -> Result<PngInfo, DecoderError> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        reader.take(HEADER_LEN as u64).read_to_end(&mut header)?;
+
+        Self::probe_bytes(&header)
+    }
+    /// Reads the signature and IHDR chunk from the start of a PNG file
+    /// already in memory, see Png::probe.
+    ///
+    /// # Arguments
+    ///
+    /// * 'bytes' - The PNG file, or at least its first 33 bytes.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the PngInfo or a DecoderError.
+    pub fn probe_bytes(bytes: &[u8]) -> Result<PngInfo, DecoderError> {
+        if bytes.len() < 8 || bytes[0..8] != PNG_HEADER {
+            return Err(DecoderError::NotPngFile);
+        }
+
+        match bytes.get(8..HEADER_LEN - 4) {
+            Some(chunk) if chunk[0..8] == [0, 0, 0, 13, b'I', b'H', b'D', b'R'] => {
+                PngInfo::from_ihdr(&chunk[8..])
+            }
+            _ => Err(DecoderError::InvalidChunk(
+                "IHDR chunk is missing or has the wrong length.",
+            )),
+        }
+    }
+    /// Reads the signature and IHDR chunk like Png::probe_reader, then
+    /// counts the chunks after them up to IEND or the end of the file. Only
+    /// chunk headers are kept, the data of each chunk is read past.
+    ///
+    /// # Arguments
+    ///
+    /// * 'reader' - The source of the PNG file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the PngInfo with its chunks counted, or a
+    /// DecoderError. A chunk cut off by the end of the file isn't counted.
+    #[cfg(feature = "std")]
+    pub fn probe_chunks<R: Read>(mut reader: R) -> Result<PngInfo, DecoderError> {
+        let mut info = Self::probe_reader(&mut reader)?;
+        let mut chunks = 1;
+
+        let mut header = [0; 8];
+        loop {
+            match reader.read_exact(&mut header) {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                result => result?,
+            }
+            let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+            let skip = length as u64 + 4;
+            if io::copy(&mut (&mut reader).take(skip), &mut io::sink())? < skip {
+                break;
+            }
+
+            chunks += 1;
+            if &header[4..8] == b"IEND" {
+                break;
+            }
+        }
+
+        info.chunks = Some(chunks);
+        Ok(info)
+    }
+}
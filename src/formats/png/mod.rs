@@ -1,12 +1,72 @@
-use std::{
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
     error::Error,
     fmt::{self, Display},
-    fs, io,
-    path::Path,
+    mem,
+    ops::ControlFlow,
     str,
 };
+#[cfg(feature = "async")]
+use futures_io::AsyncRead;
+#[cfg(feature = "std")]
+use std::{
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::buffer::{BufferKind, BufferProvider, DefaultBuffers};
+use crate::color;
+use crate::compression::{
+    backend::{DecompressError, Decompressor},
+    crc,
+    inflate::DeflateError,
+    zlib::ZlibError,
+};
+use crate::error;
+use crate::image::{Image, ImageError, ImageView, PixelFormat};
+use crate::metadata::Metadata;
+use crate::scale::{self, BoxFilter};
+
+mod adam7;
+mod background;
+mod cgbi;
+mod chunk_type;
+#[cfg(feature = "std")]
+mod chunk_writer;
+mod encoder;
+pub mod filter;
+mod info;
+mod options;
+mod palette;
+mod physical;
+mod rewrite;
+mod rows;
+mod stream;
+mod text;
+mod time;
+mod validate;
 
-use crate::compression::{crc, zlib::ZlibStream};
+pub use background::Background;
+pub use chunk_type::ChunkType;
+#[cfg(feature = "std")]
+pub use chunk_writer::ChunkWriter;
+pub(crate) use encoder::encode;
+#[cfg(feature = "std")]
+pub use encoder::StreamEncoder;
+pub use filter::{FilterStrategy, Filters};
+pub use info::PngInfo;
+pub use options::{ChecksumPolicy, DecodeOptions, EncodeOptions, Limits, Transformations};
+pub use palette::Palette;
+pub use physical::{PhysicalDimensions, PhysicalUnit};
+pub use rows::Rows;
+pub use stream::StreamDecoder;
+pub use text::TextChunk;
+pub use time::Time;
+pub use validate::Violation;
 
 // +-----------+
 // | CONSTANTS |
@@ -14,16 +74,29 @@ use crate::compression::{crc, zlib::ZlibStream};
 
 pub const PNG_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
 
-const VALID_CHUNK_TYPES: [&str; 18] = [
-    "IHDR", "PLTE", "IDAT", "IEND", "cHRM", "gAMA", "iCCP", "sBIT", "sRGB", "bKGD", "hIST", "tRNS",
-    "pHYs", "sPLT", "tIME", "iTXt", "tEXt", "zTXt",
-];
+/// The longest a chunk's data can be, see 5.3 of the spec.
+const MAX_CHUNK_LEN: usize = 0x7fff_ffff;
+
+/// The exponent of the display Transformations::gamma corrects images for,
+/// that of sRGB and most monitors.
+const DISPLAY_GAMMA: f64 = 2.2;
 
 //      +------------------+
 //      | PNG OPTION ENUMS |
 //      +------------------+
 
-#[derive(Debug)]
+/// The way each pixel is stored, from the color type byte in IHDR.
+///
+/// # Members
+///
+/// * 'Grayscale' - A single gray sample, color type 0.
+/// * 'RGB' - Red, green and blue samples, color type 2.
+/// * 'PalleteIndex' - A single index into the PLTE chunk, color type 3.
+/// * 'GrayscaleAlpha' - A gray sample followed by an alpha sample, color
+///   type 4.
+/// * 'RGBA' - Red, green, blue and alpha samples, color type 6.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColorType {
     Grayscale,
     RGB,
@@ -32,12 +105,238 @@ pub enum ColorType {
     RGBA,
 }
 
-#[derive(Debug)]
+impl ColorType {
+    /// Gets the number of samples making up each pixel.
+    ///
+    /// # Returns
+    ///
+    /// The number of samples per pixel, from 1 to 4.
+    pub fn samples(&self) -> usize {
+        match self {
+            ColorType::Grayscale => 1,
+            ColorType::RGB => 3,
+            ColorType::PalleteIndex => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::RGBA => 4,
+        }
+    }
+    /// Gets the number of bytes making up each pixel, the distance the
+    /// filters look back for the pixel to the left, see 9.2 of the spec.
+    ///
+    /// # Arguments
+    ///
+    /// * 'bit_depth' - The number of bits in each sample.
+    ///
+    /// # Returns
+    ///
+    /// The bytes per complete pixel, rounded up to 1 for pixels smaller than
+    /// a byte.
+    pub fn bytes_per_pixel(&self, bit_depth: u8) -> usize {
+        (self.samples() * bit_depth as usize).div_ceil(8).max(1)
+    }
+}
+
+impl TryFrom<u8> for ColorType {
+    type Error = DecoderError;
+
+    /// Reads the color type byte from IHDR.
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        match byte {
+            0 => Ok(ColorType::Grayscale),
+            2 => Ok(ColorType::RGB),
+            3 => Ok(ColorType::PalleteIndex),
+            4 => Ok(ColorType::GrayscaleAlpha),
+            6 => Ok(ColorType::RGBA),
+            other => Err(DecoderError::InvalidColorType(other)),
+        }
+    }
+}
+
+impl From<ColorType> for u8 {
+    /// Gets the color type byte written to IHDR.
+    fn from(color_type: ColorType) -> Self {
+        match color_type {
+            ColorType::Grayscale => 0,
+            ColorType::RGB => 2,
+            ColorType::PalleteIndex => 3,
+            ColorType::GrayscaleAlpha => 4,
+            ColorType::RGBA => 6,
+        }
+    }
+}
+
+impl Display for ColorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorType::Grayscale => write!(f, "grayscale"),
+            ColorType::RGB => write!(f, "RGB"),
+            ColorType::PalleteIndex => write!(f, "palette index"),
+            ColorType::GrayscaleAlpha => write!(f, "grayscale with alpha"),
+            ColorType::RGBA => write!(f, "RGBA"),
+        }
+    }
+}
+
+/// The interlace method, from the interlace byte in IHDR.
+///
+/// # Members
+///
+/// * 'None' - Scanlines are stored from top to bottom.
+/// * 'Adam7' - Pixels are stored in seven passes of increasing detail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Interlace {
     None,
     Adam7,
 }
 
+impl TryFrom<u8> for Interlace {
+    type Error = DecoderError;
+
+    /// Reads the interlace method byte from IHDR.
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        match byte {
+            0 => Ok(Interlace::None),
+            1 => Ok(Interlace::Adam7),
+            other => Err(DecoderError::InvalidInterlace(other)),
+        }
+    }
+}
+
+impl From<Interlace> for u8 {
+    /// Gets the interlace method byte written to IHDR.
+    fn from(interlace: Interlace) -> Self {
+        match interlace {
+            Interlace::None => 0,
+            Interlace::Adam7 => 1,
+        }
+    }
+}
+
+impl Display for Interlace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Interlace::None => write!(f, "none"),
+            Interlace::Adam7 => write!(f, "Adam7"),
+        }
+    }
+}
+
+/// How far through decoding the scanlines Png::rgb_with_progress is.
+///
+/// # Fields
+///
+/// * 'rows_done' - The number of scanlines decoded so far.
+/// * 'rows_total' - The height of the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub rows_done: usize,
+    pub rows_total: usize,
+}
+
+/// Appends the samples of a row to out as RGB, copying the gray sample of
+/// grayscale pixels into red, green and blue and leaving alpha out. Palette
+/// indices are copied as they are, see Png::to_rgb.
+fn expand_rgb<T: Copy>(color_type: ColorType, samples: &[T], out: &mut Vec<T>) {
+    match color_type {
+        ColorType::Grayscale => samples.iter().for_each(|&gray| out.extend([gray; 3])),
+        ColorType::GrayscaleAlpha => samples
+            .chunks_exact(2)
+            .for_each(|pixel| out.extend([pixel[0]; 3])),
+        ColorType::RGBA => samples
+            .chunks_exact(4)
+            .for_each(|pixel| out.extend_from_slice(&pixel[..3])),
+        ColorType::RGB | ColorType::PalleteIndex => out.extend_from_slice(samples),
+    }
+}
+
+/// Applies Transformations::gamma to the red, green and blue of 8 bit
+/// pixels, leaving alpha as it is.
+///
+/// # Arguments
+///
+/// * 'pixels' - The pixels, red, green and blue first.
+/// * 'bytes_per_pixel' - The number of bytes in each pixel.
+/// * 'table' - The table from Png::gamma_table, or None to leave the
+///   pixels as they are.
+fn correct_gamma(pixels: &mut [u8], bytes_per_pixel: usize, table: Option<&[u8; 256]>) {
+    let Some(table) = table else {
+        return;
+    };
+
+    for pixel in pixels.chunks_exact_mut(bytes_per_pixel) {
+        pixel[..3]
+            .iter_mut()
+            .for_each(|sample| *sample = table[*sample as usize]);
+    }
+}
+
+/// Appends the samples of a row to out as RGBA like expand_rgb, with
+/// opaque as the alpha of pixels that have none.
+fn expand_rgba<T: Copy>(color_type: ColorType, samples: &[T], opaque: T, out: &mut Vec<T>) {
+    match color_type {
+        ColorType::Grayscale => samples
+            .iter()
+            .for_each(|&gray| out.extend([gray, gray, gray, opaque])),
+        ColorType::GrayscaleAlpha => samples
+            .chunks_exact(2)
+            .for_each(|pixel| out.extend([pixel[0], pixel[0], pixel[0], pixel[1]])),
+        ColorType::RGB => samples
+            .chunks_exact(3)
+            .for_each(|pixel| out.extend([pixel[0], pixel[1], pixel[2], opaque])),
+        ColorType::RGBA | ColorType::PalleteIndex => out.extend_from_slice(samples),
+    }
+}
+
+/// Checks neither the width nor the height is 0. Such images have no
+/// pixels, and the slice math of decoding them has nothing to work on.
+///
+/// # Returns
+///
+/// An empty result, or DecoderError::InvalidDimensions.
+fn check_dimensions((width, height): (usize, usize)) -> Result<(), DecoderError> {
+    match width == 0 || height == 0 {
+        true => Err(DecoderError::InvalidDimensions(width, height)),
+        false => Ok(()),
+    }
+}
+
+/// Gets the size of width by height pixels of bytes_per_pixel bytes, with
+/// checked math so huge dimensions from IHDR fail cleanly rather than
+/// overflowing or asking for more memory than can be addressed.
+///
+/// # Returns
+///
+/// The size in bytes, or DecoderError::DimensionOverflow if it's larger
+/// than isize::MAX.
+pub(crate) fn image_len(
+    (width, height): (usize, usize),
+    bytes_per_pixel: usize,
+) -> Result<usize, DecoderError> {
+    width
+        .checked_mul(bytes_per_pixel)
+        .and_then(|row| row.checked_mul(height))
+        .filter(|&len| len <= isize::MAX as usize)
+        .ok_or(DecoderError::DimensionOverflow(width, height))
+}
+
+/// Gets the size of the decompressed image data of a non-interlaced image,
+/// every scanline with its filter type byte, checked like image_len.
+fn data_len(
+    (width, height): (usize, usize),
+    color_type: ColorType,
+    bit_depth: u8,
+) -> Result<usize, DecoderError> {
+    let bits = color_type.samples() * bit_depth as usize;
+
+    width
+        .checked_mul(bits)
+        .map(|row| row.div_ceil(8) + 1)
+        .and_then(|row| row.checked_mul(height))
+        .filter(|&len| len <= isize::MAX as usize)
+        .ok_or(DecoderError::DimensionOverflow(width, height))
+}
+
 //      +-------------+
 //      | FILE FORMAT |
 //      +-------------+
@@ -51,6 +350,22 @@ pub enum Interlace {
 /// * 'bit_depth' -
 /// * 'color_type' -
 /// * 'interlace' -
+/// * 'gamma' - The gamma the samples were encoded with, from the gAMA
+///   chunk, e.g. 0.45455 for 1 / 2.2. None if the file has no gAMA
+///   chunk or it's invalid.
+/// * 'transformations' - The Transformations Png::rgb applies to the pixels.
+/// * 'checksums' - Whether Png::rgb checks the adler32 of the image data,
+///   from the DecodeOptions it was decoded with.
+/// * 'decompressor' - What Png::rgb inflates the image data with, from the
+///   DecodeOptions it was decoded with.
+/// * 'limits' - The Limits from the DecodeOptions it was decoded with, of
+///   which max_decompressed is checked as the image data and text are
+///   inflated.
+/// * 'threads' - The most threads Png::rgb decodes large images on, from the
+///   DecodeOptions it was decoded with.
+/// * 'ignore_window' - Whether matches in the compressed data may reach
+///   back past the window its zlib header declares, from the
+///   DecodeOptions it was decoded with.
 ///
 /// # Examples
 ///
@@ -72,157 +387,1512 @@ pub struct Png {
     pub bit_depth: u8,
     pub color_type: ColorType,
     pub interlace: Interlace,
+    pub gamma: Option<f32>,
+    pub transformations: Transformations,
+    pub checksums: ChecksumPolicy,
+    pub decompressor: &'static dyn Decompressor,
+    pub limits: Limits,
+    pub threads: usize,
+    pub ignore_window: bool,
 }
 
-impl Png {
-    /// Creates a Png struct from the given path.
+impl Png {
+    /// Creates a Png struct from the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * 'path' - The file path to the PNG file, can be any type that implements into path.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the constructed Png or a DecoderError.
+    ///
+    #[cfg(feature = "std")]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Png, DecoderError> {
+        DecodeOptions::new().decode(path)
+    }
+    /// Creates a Png struct from anything that implements io::Read, such as a
+    /// socket, an entry in an archive or an in-memory Cursor. The reader is
+    /// read to the end before decoding starts.
+    ///
+    /// # Arguments
+    ///
+    /// * 'reader' - The source of the PNG file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the constructed Png or a DecoderError.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: Read>(reader: R) -> Result<Png, DecoderError> {
+        DecodeOptions::new().decode_reader(reader)
+    }
+    /// Creates a Png struct from a PNG file already in memory, such as one
+    /// embedded with include_bytes!. Works without the std feature.
+    ///
+    /// # Arguments
+    ///
+    /// * 'bytes' - The entire PNG file as bytes.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the constructed Png or a DecoderError.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Png, DecoderError> {
+        DecodeOptions::new().decode_bytes(bytes)
+    }
+    /// Creates a Png struct from an AsyncRead, without blocking the executor
+    /// while waiting for bytes. The signature and limits are checked as the
+    /// bytes arrive, see StreamDecoder.
+    ///
+    /// # Arguments
+    ///
+    /// * 'reader' - The source of the PNG file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the constructed Png or a DecoderError.
+    #[cfg(feature = "async")]
+    pub async fn from_async_reader<R: AsyncRead + Unpin>(reader: R) -> Result<Png, DecoderError> {
+        DecodeOptions::new().decode_async_reader(reader).await
+    }
+    /// Fetches a PNG file over HTTP or HTTPS and decodes it, stopping early
+    /// if the file turns out to be invalid.
+    ///
+    /// # Arguments
+    ///
+    /// * 'url' - The http:// or https:// URL of the PNG file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the constructed Png or a DecoderError.
+    #[cfg(feature = "http")]
+    pub fn from_url(url: &str) -> Result<Png, DecoderError> {
+        DecodeOptions::new().decode_url(url)
+    }
+    /// Starts building a set of DecodeOptions, for when the defaults used by
+    /// from_path aren't enough.
+    ///
+    /// # Returns
+    ///
+    /// A DecodeOptions with default values, decode with DecodeOptions::decode.
+    pub fn decoder() -> DecodeOptions {
+        DecodeOptions::new()
+    }
+    /// Gets the modification time from the tIME chunk.
+    ///
+    /// # Returns
+    ///
+    /// The time, or None if the file has no tIME chunk or it isn't 7 bytes
+    /// long.
+    pub fn time(&self) -> Option<Time> {
+        let chunk = self
+            .data
+            .ancillary_chunks
+            .iter()
+            .find(|chunk| chunk.ctype.as_bytes() == b"tIME")?;
+
+        Time::from_bytes(&chunk.data)
+    }
+    /// Gets the color the image should be shown against from the bKGD
+    /// chunk.
+    ///
+    /// # Returns
+    ///
+    /// The Background, or None if the file has no bKGD chunk or it's the
+    /// wrong length for the color type.
+    pub fn background(&self) -> Option<Background> {
+        let chunk = self
+            .data
+            .ancillary_chunks
+            .iter()
+            .find(|chunk| chunk.ctype.as_bytes() == b"bKGD")?;
+
+        Background::from_bytes(self.color_type, &chunk.data)
+    }
+    /// Gets the intended size or shape of the pixels from the pHYs chunk,
+    /// for working out DPI or the pixels' aspect ratio.
+    ///
+    /// # Returns
+    ///
+    /// The PhysicalDimensions, or None if the file has no pHYs chunk or it
+    /// can't be read.
+    pub fn physical_dimensions(&self) -> Option<PhysicalDimensions> {
+        let chunk = self
+            .data
+            .ancillary_chunks
+            .iter()
+            .find(|chunk| chunk.ctype.as_bytes() == b"pHYs")?;
+
+        PhysicalDimensions::from_bytes(&chunk.data)
+    }
+    /// Gets the EXIF data from the eXIf chunk, as camera and editors write
+    /// it, see Exif::parse for reading the common fields.
+    ///
+    /// # Returns
+    ///
+    /// The EXIF data starting with its TIFF header, or None if the file has
+    /// no eXIf chunk.
+    pub fn exif(&self) -> Option<&[u8]> {
+        let chunk = self
+            .data
+            .ancillary_chunks
+            .iter()
+            .find(|chunk| chunk.ctype == ChunkType::EXIF)?;
+
+        Some(&chunk.data)
+    }
+    /// Gets the metadata to carry over when converting the image to another
+    /// file, see EncodeOptions::metadata.
+    ///
+    /// # Returns
+    ///
+    /// The Metadata, with the text from Png::text, the dimensions from
+    /// Png::physical_dimensions and the EXIF data from Png::exif. Any other
+    /// ancillary chunks that are safe to copy, whether chameleon knows them
+    /// or not, are kept as they are.
+    pub fn metadata(&self) -> Metadata {
+        // Chunks Metadata holds in its own fields.
+        const HELD: [&[u8; 4]; 5] = [b"tEXt", b"zTXt", b"iTXt", b"pHYs", b"eXIf"];
+
+        Metadata {
+            text: self.text(),
+            physical: self.physical_dimensions(),
+            exif: self.exif().map(<[u8]>::to_vec),
+            chunks: self
+                .data
+                .ancillary_chunks
+                .iter()
+                .filter(|chunk| {
+                    chunk.ctype.is_safe_to_copy() && !HELD.contains(&chunk.ctype.as_bytes())
+                })
+                .map(|chunk| (chunk.ctype, chunk.data.clone()))
+                .collect(),
+        }
+    }
+    /// Gets the text from every tEXt, zTXt and iTXt chunk, decompressing it
+    /// where needed.
+    ///
+    /// # Returns
+    ///
+    /// The TextChunks in the order they appear in the file. Chunks missing
+    /// a field, whose text can't be decompressed or, for iTXt, isn't valid
+    /// UTF-8 are left out.
+    pub fn text(&self) -> Vec<TextChunk> {
+        self.data
+            .ancillary_chunks
+            .iter()
+            .filter_map(|chunk| TextChunk::from_chunk(chunk.ctype.as_bytes(), &chunk.data, self))
+            .collect()
+    }
+    #[cfg(feature = "std")]
+    fn parse<R: Read>(mut reader: R, options: &DecodeOptions) -> Result<Png, DecoderError> {
+        let mut file_bytes = Vec::new();
+        reader.read_to_end(&mut file_bytes)?;
+
+        Self::parse_bytes(&file_bytes, options)
+    }
+    fn parse_bytes(file_bytes: &[u8], options: &DecodeOptions) -> Result<Png, DecoderError> {
+        if file_bytes.len() < 8 || file_bytes[0..8] != PNG_HEADER {
+            return Err(DecoderError::NotPngFile);
+        }
+
+        Self::from_data(PngData::build_with(file_bytes, options)?, options)
+    }
+    /// Checks the IHDR chunk of a file's chunks and builds a Png from them.
+    fn from_data(data: PngData, options: &DecodeOptions) -> Result<Png, DecoderError> {
+        if data.ihdr.data.len() != 13 {
+            return Err(DecoderError::InvalidChunk(
+                "IHDR chunk is missing or has the wrong length.",
+            ));
+        }
+
+        // The limits are checked before anything else is, so an image too
+        // large to be let in fails on that whatever else is wrong with it.
+        let dimensions = Self::ihdr_dimensions(&data.ihdr.data);
+        check_dimensions(dimensions)?;
+        options.limits.check(dimensions)?;
+
+        let PngInfo {
+            bit_depth,
+            color_type,
+            interlace,
+            ..
+        } = PngInfo::from_ihdr(&data.ihdr.data)?;
+
+        let gamma = Self::parse_gamma(&data);
+
+        Ok(Png {
+            data,
+            dimensions,
+            bit_depth,
+            color_type,
+            interlace,
+            gamma,
+            transformations: options.transformations,
+            checksums: options.checksums,
+            decompressor: options.decompressor,
+            limits: options.limits,
+            threads: options.threads,
+            ignore_window: options.ignore_window,
+        })
+    }
+    /// Checks the dimensions, which are public so may have changed since
+    /// parsing, are neither 0 nor past the Limits, before anything the size
+    /// of a row or the image is allocated.
+    pub(crate) fn check_limits(&self) -> Result<(), DecoderError> {
+        check_dimensions(self.dimensions)?;
+        self.limits.check(self.dimensions)
+    }
+    /// Reads the gamma from the gAMA chunk, which holds it times 100000 as
+    /// a 4 byte integer, see 11.3.3.2 of the spec.
+    fn parse_gamma(data: &PngData) -> Option<f32> {
+        let chunk = data
+            .ancillary_chunks
+            .iter()
+            .find(|chunk| chunk.ctype.as_bytes() == b"gAMA")?;
+        let value = u32::from_be_bytes(chunk.data.as_slice().try_into().ok()?);
+
+        // A gamma of 0 can't be corrected for.
+        (value != 0).then_some(value as f32 / 100_000.0)
+    }
+    /// Reads the width and height from the data of an IHDR chunk, which must
+    /// be at least 8 bytes long.
+    fn ihdr_dimensions(ihdr: &[u8]) -> (usize, usize) {
+        (
+            ihdr[0..4]
+                .iter()
+                .fold(0usize, |acc, byte| (acc << 8) | *byte as usize),
+            ihdr[4..8]
+                .iter()
+                .fold(0usize, |acc, byte| (acc << 8) | *byte as usize),
+        )
+    }
+    /// Converts the PNG file into a vector of rgb tuples. Gray samples are
+    /// copied into red, green and blue, and alpha samples are left out. 16
+    /// bit samples are reduced to 8 bits by keeping their high byte, see
+    /// Png::rgb16 for the full precision.
+    ///
+    /// # Returns
+    ///
+    /// A Vec<(u8, u8, u8)> containing each pixel from left to right, top to
+    /// bottom.
+    pub fn rgb(&self) -> Result<Vec<(u8, u8, u8)>, DecoderError> {
+        self.rgb_with_progress(0, |_| ControlFlow::Continue(()))
+    }
+    /// Converts the PNG file into a vector of rgb tuples like Png::rgb, calling
+    /// back as the scanlines are decoded so a long decode can be shown as a
+    /// progress bar, or stopped part way through.
+    ///
+    /// # Arguments
+    ///
+    /// * 'every' - How many scanlines to decode between each call, 0 to never
+    ///   call back. The callback is always called after the last row.
+    /// * 'callback' - Given the Progress so far, returns ControlFlow::Break to
+    ///   cancel the decode or ControlFlow::Continue to carry on.
+    ///
+    /// # Returns
+    ///
+    /// A Vec<(u8, u8, u8)> containing each pixel from left to right, top to
+    /// bottom, or DecoderError::Cancelled if the callback returned Break. The
+    /// partly decoded pixels are dropped when cancelled.
+    ///
+    /// # Examples
+    ///
+    /// '''
+    /// let rgb = png.rgb_with_progress(64, |progress| {
+    ///     bar.set(progress.rows_done, progress.rows_total);
+    ///     if cancel_button.pressed() {
+    ///         ControlFlow::Break(())
+    ///     } else {
+    ///         ControlFlow::Continue(())
+    ///     }
+    /// })?;
+    /// '''
+    pub fn rgb_with_progress<F>(
+        &self,
+        every: usize,
+        mut callback: F,
+    ) -> Result<Vec<(u8, u8, u8)>, DecoderError>
+    where
+        F: FnMut(Progress) -> ControlFlow<()>,
+    {
+        let rows_total = self.dimensions.1;
+
+        let mut report = |row: usize| {
+            let progress = Progress {
+                rows_done: row + 1,
+                rows_total,
+            };
+            let due = progress.rows_done.is_multiple_of(every) || progress.rows_done == rows_total;
+
+            if every != 0 && due && callback(progress).is_break() {
+                return Err(DecoderError::Cancelled);
+            }
+            Ok(())
+        };
+
+        let pixels = self.decode_rgb(&DefaultBuffers, &mut report)?;
+
+        Ok(pixels
+            .chunks_exact(3)
+            .map(|values| (values[0], values[1], values[2]))
+            .collect())
+    }
+    /// Converts the PNG file into interleaved RGB bytes like Png::rgb, with
+    /// the large buffers used along the way, and the one returned, supplied
+    /// by a BufferProvider.
+    ///
+    /// # Arguments
+    ///
+    /// * 'provider' - The BufferProvider to take buffers from.
+    ///
+    /// # Returns
+    ///
+    /// A buffer from provider holding 3 bytes for each pixel, from left to
+    /// right, top to bottom.
+    pub fn rgb_bytes_with(&self, provider: &dyn BufferProvider) -> Result<Vec<u8>, DecoderError> {
+        self.decode_rgb(provider, &mut |_| Ok(()))
+    }
+    /// Converts the PNG file into a vector of rgba tuples. Gray samples are
+    /// copied into red, green and blue, and pixels without an alpha sample
+    /// are opaque unless the tRNS chunk says otherwise: palette entries take
+    /// their alpha from it, and grayscale and RGB pixels matching the color
+    /// it holds are fully transparent. 16 bit samples keep their high byte.
+    ///
+    /// # Returns
+    ///
+    /// A Vec<(u8, u8, u8, u8)> containing each pixel from left to right, top
+    /// to bottom.
+    pub fn rgba(&self) -> Result<Vec<(u8, u8, u8, u8)>, DecoderError> {
+        let pixels = self.decode_rgba()?;
+
+        Ok(pixels
+            .chunks_exact(4)
+            .map(|values| (values[0], values[1], values[2], values[3]))
+            .collect())
+    }
+    /// Converts the PNG file into a vector of rgb tuples with each pixel
+    /// blended against the background color from the bKGD chunk by its
+    /// alpha, the way viewers show transparent images. Alpha is taken from
+    /// the image or its tRNS chunk like Png::rgba. Images without a usable
+    /// bKGD chunk are blended against white.
+    ///
+    /// # Returns
+    ///
+    /// A Vec<(u8, u8, u8)> containing each pixel from left to right, top to
+    /// bottom.
+    pub fn rgb_composited(&self) -> Result<Vec<(u8, u8, u8)>, DecoderError> {
+        let background = self.background_rgb()?;
+        let len = image_len(self.dimensions, 3)?;
+        let mut pixels = Vec::new();
+
+        self.for_each_rgba_row(false, &mut |_, row| {
+            // Reserved once the first row is ready, see Png::samples.
+            if pixels.capacity() == 0 {
+                pixels.reserve_exact(len);
+            }
+            for pixel in row.chunks_exact(4) {
+                let alpha = pixel[3] as u32;
+                pixels.extend((0..3).map(|i| {
+                    let blended = pixel[i] as u32 * alpha + background[i] as u32 * (255 - alpha);
+                    ((blended + 127) / 255) as u8
+                }));
+            }
+            Ok(())
+        })?;
+
+        if pixels.len() != len {
+            return Err(DecoderError::Unexplainable);
+        }
+        self.transform(&mut pixels, 3);
+
+        Ok(pixels
+            .chunks_exact(3)
+            .map(|values| (values[0], values[1], values[2]))
+            .collect())
+    }
+    /// Gets the background from the bKGD chunk as 8 bit RGB, scaled the
+    /// same way as the samples of the image, or white if there isn't one or
+    /// its palette index is out of range.
+    fn background_rgb(&self) -> Result<[u8; 3], DecoderError> {
+        let scale = |sample: u16| match self.bit_depth {
+            16 => (sample >> 8) as u8,
+            8 => sample as u8,
+            depth => {
+                let max = (1u8 << depth) - 1;
+                (sample as u8 & max) * (255 / max)
+            }
+        };
+
+        Ok(match self.background() {
+            Some(Background::Gray(gray)) => [scale(gray); 3],
+            Some(Background::Rgb(red, green, blue)) => [scale(red), scale(green), scale(blue)],
+            Some(Background::Index(index)) => self
+                .decode_palette()?
+                .and_then(|palette| palette.entries.get(index as usize).copied())
+                .map_or([255; 3], |entry| [entry[0], entry[1], entry[2]]),
+            None => [255; 3],
+        })
+    }
+    /// Decodes the samples of every pixel without converting their color,
+    /// one byte per sample, e.g. the gray value of grayscale images or the
+    /// index into the palette of indexed ones. 16 bit samples keep their
+    /// high byte, and 1, 2 and 4 bit samples are unpacked. Transformations
+    /// aren't applied.
+    ///
+    /// # Returns
+    ///
+    /// A Vec<u8> holding color_type.samples() bytes for each pixel, from left
+    /// to right, top to bottom.
+    pub fn samples(&self) -> Result<Vec<u8>, DecoderError> {
+        let len = image_len(self.dimensions, self.color_type.samples())?;
+        let mut samples = Vec::new();
+
+        self.for_each_row(&DefaultBuffers, &mut |_, scanline| {
+            // Reserved once the first scanline is ready, so a header
+            // claiming a huge image costs nothing until its data does.
+            if samples.capacity() == 0 {
+                samples.reserve_exact(len);
+            }
+            self.to_8_bit(scanline, &mut samples);
+            Ok(())
+        })?;
+
+        Ok(samples)
+    }
+    /// Converts the PNG file into a vector of 16 bit rgb tuples, keeping the
+    /// full precision of 16 bit images. 8 bit samples are scaled up to the
+    /// same range, multiplying by 257 so 255 becomes 65535.
+    ///
+    /// # Returns
+    ///
+    /// A Vec<(u16, u16, u16)> containing each pixel from left to right, top
+    /// to bottom.
+    pub fn rgb16(&self) -> Result<Vec<(u16, u16, u16)>, DecoderError> {
+        let (width, height) = self.dimensions;
+        let palette = self.decode_palette()?;
+        image_len(self.dimensions, mem::size_of::<(u16, u16, u16)>())?;
+        let mut pixels = Vec::new();
+        let mut samples = Vec::with_capacity(width * self.color_type.samples());
+        let mut rgb = Vec::with_capacity(width * 3);
+        // 16 bit samples are corrected with a table of every value.
+        let table = match self.bit_depth {
+            16 => self.gamma_exponent().map(|exponent| {
+                (0..=u16::MAX)
+                    .map(|sample| {
+                        let value = color::powf(sample as f64 / 65535.0, exponent);
+                        (value * 65535.0 + 0.5) as u16
+                    })
+                    .collect::<Vec<_>>()
+            }),
+            _ => None,
+        };
+        let gamma = self.gamma_table();
+
+        self.for_each_row(&DefaultBuffers, &mut |_, scanline| {
+            let wide = match self.bit_depth {
+                16 => {
+                    let samples = scanline
+                        .chunks_exact(2)
+                        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                        .collect::<Vec<_>>();
+                    let mut wide = Vec::with_capacity(width * 3);
+                    expand_rgb(self.color_type, &samples, &mut wide);
+                    if let Some(table) = &table {
+                        wide.iter_mut()
+                            .for_each(|sample| *sample = table[*sample as usize]);
+                    }
+                    wide
+                }
+                _ => {
+                    samples.clear();
+                    rgb.clear();
+                    self.to_8_bit(scanline, &mut samples);
+                    self.to_rgb(&samples, palette.as_ref(), &mut rgb)?;
+                    correct_gamma(&mut rgb, 3, gamma.as_ref());
+                    rgb.iter().map(|&sample| sample as u16 * 257).collect()
+                }
+            };
+            if wide.len() != width * 3 {
+                return Err(DecoderError::Unexplainable);
+            }
+
+            // Reserved once the first scanline is ready, see Png::samples.
+            if pixels.capacity() == 0 {
+                pixels.reserve_exact(width * height);
+            }
+            pixels.extend(wide.chunks_exact(3).map(|rgb| (rgb[0], rgb[1], rgb[2])));
+            Ok(())
+        })?;
+
+        if self.transformations.bgr {
+            for (r, _, b) in pixels.iter_mut() {
+                mem::swap(r, b);
+            }
+        }
+        if self.transformations.flip_vertical && width > 0 {
+            pixels = pixels.chunks(width).rev().flatten().copied().collect();
+        }
+
+        Ok(pixels)
+    }
+    /// Decodes a smaller copy of the image, no larger than max_dim along
+    /// either side, see scale::fit. Each scanline is averaged into the
+    /// thumbnail as soon as it's reconstructed, so the full size pixels are
+    /// never held in memory at once. Alpha is premultiplied while
+    /// averaging, like Image::downscale. Transformations are applied.
+    ///
+    /// # Arguments
+    ///
+    /// * 'max_dim' - The most pixels the thumbnail can have along either
+    ///   side.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the thumbnail as an Rgba8 Image, with
+    /// alpha from the image or its tRNS chunk like Png::rgba, or a
+    /// DecoderError if the image data can't be decoded.
+    pub fn thumbnail(&self, max_dim: usize) -> Result<Image, DecoderError> {
+        self.check_limits()?;
+        let (width, height) = self.dimensions;
+        let (thumb_width, thumb_height) = scale::fit(width, height, max_dim);
+        let mut filter =
+            BoxFilter::new(width, height, thumb_width, thumb_height, PixelFormat::Rgba8);
+        let mut rgba = Vec::with_capacity(width * 4);
+        let gamma = self.gamma_table();
+
+        self.for_each_rgba_row(false, &mut |row, pixels| {
+            if pixels.len() != width * 4 {
+                return Err(DecoderError::Unexplainable);
+            }
+            rgba.clear();
+            rgba.extend_from_slice(pixels);
+            self.transform_pixels(&mut rgba, 4, gamma.as_ref());
+
+            let row = match self.transformations.flip_vertical {
+                true => height - 1 - row,
+                false => row,
+            };
+            filter.add_row(row, &rgba);
+            Ok(())
+        })?;
+
+        Ok(filter.finish())
+    }
+    /// Decodes the image into interleaved RGB bytes, calling report after
+    /// each scanline.
+    fn decode_rgb(
+        &self,
+        provider: &dyn BufferProvider,
+        report: &mut dyn FnMut(usize) -> Result<(), DecoderError>,
+    ) -> Result<Vec<u8>, DecoderError> {
+        self.check_limits()?;
+        let palette = self.decode_palette()?;
+        let pixels_len = image_len(self.dimensions, 3)?;
+        let mut samples = Vec::with_capacity(self.dimensions.0 * self.color_type.samples());
+        // Taken once the first scanline is ready, after the buffers for
+        // decompression.
+        let mut pixels: Option<Vec<u8>> = None;
+
+        self.for_each_row(provider, &mut |row, scanline| {
+            let pixels = pixels.get_or_insert_with(|| {
+                let mut buffer = provider.allocate(BufferKind::Pixels, pixels_len);
+                buffer.clear();
+                buffer
+            });
+            samples.clear();
+            self.to_8_bit(scanline, &mut samples);
+            self.to_rgb(&samples, palette.as_ref(), pixels)?;
+            report(row)
+        })?;
+        let mut pixels = pixels.unwrap_or_default();
+
+        if !pixels.len().is_multiple_of(3) {
+            return Err(DecoderError::Unexplainable);
+        }
+        self.transform(&mut pixels, 3);
+
+        Ok(pixels)
+    }
+    /// Decodes the image into interleaved RGBA bytes, applying tRNS, see
+    /// Png::rgba.
+    fn decode_rgba(&self) -> Result<Vec<u8>, DecoderError> {
+        let len = image_len(self.dimensions, 4)?;
+        let mut pixels = Vec::new();
+
+        self.for_each_rgba_row(false, &mut |_, row| {
+            // Reserved once the first row is ready, see Png::samples.
+            if pixels.capacity() == 0 {
+                pixels.reserve_exact(len);
+            }
+            pixels.extend_from_slice(row);
+            Ok(())
+        })?;
+
+        if pixels.len() != len {
+            return Err(DecoderError::Unexplainable);
+        }
+        self.transform(&mut pixels, 4);
+
+        Ok(pixels)
+    }
+    /// Decodes the image a row at a time, handing each row to each as RGBA
+    /// as soon as it's reconstructed, for images too large to hold whole.
+    /// Alpha comes from the image or its tRNS chunk like Png::rgba, but 16
+    /// bit images keep their full precision. Transformations aren't
+    /// applied.
+    ///
+    /// # Arguments
+    ///
+    /// * 'each' - Given the index and pixels of each row from top to bottom,
+    ///   in the PixelFormat from Png::rgba_format. Returning an error
+    ///   stops the decode.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or the first DecoderError from decoding or from
+    /// each.
+    pub fn rgba_rows(&self, each: &mut RowCallback<'_>) -> Result<(), DecoderError> {
+        self.for_each_rgba_row(self.bit_depth == 16, each)
+    }
+    /// Decodes the image a row at a time as it's iterated over, the pull
+    /// based version of Png::rgba_rows, see Rows.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the Rows, or a DecoderError if the image
+    /// data is missing or its zlib header is invalid.
+    pub fn rows(&self) -> Result<Rows<'_>, DecoderError> {
+        Rows::new(self)
+    }
+    /// Gets the PixelFormat of the rows from Png::rgba_rows.
+    ///
+    /// # Returns
+    ///
+    /// PixelFormat::Rgba16 for 16 bit images, otherwise PixelFormat::Rgba8.
+    pub fn rgba_format(&self) -> PixelFormat {
+        match self.bit_depth {
+            16 => PixelFormat::Rgba16,
+            _ => PixelFormat::Rgba8,
+        }
+    }
+    /// Gets the PixelFormat Png::decode_into writes, so a buffer can be
+    /// sized for it beforehand.
+    ///
+    /// # Returns
+    ///
+    /// PixelFormat::Rgba8 if the image has an alpha channel or a tRNS chunk,
+    /// otherwise PixelFormat::Rgb8.
+    pub fn pixel_format(&self) -> PixelFormat {
+        match self.color_type.samples().is_multiple_of(2) || self.trns().is_some() {
+            true => PixelFormat::Rgba8,
+            false => PixelFormat::Rgb8,
+        }
+    }
+    /// Decodes the image straight into a buffer the caller owns, e.g. one
+    /// mapped for a texture upload, as interleaved 8 bit RGB or RGBA bytes
+    /// in the PixelFormat from Png::pixel_format. No buffer the size of the
+    /// image is allocated along the way. Pixels are converted as Png::rgba
+    /// converts them, and Transformations are applied.
+    ///
+    /// # Arguments
+    ///
+    /// * 'buffer' - Where to write the pixels, from left to right, top to
+    ///   bottom, with no padding between rows. Anything past the last
+    ///   row is left alone.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either an ImageView over the pixels written,
+    /// describing their layout, or an Error if the image can't be decoded
+    /// or ImageError::BufferSize if buffer is too small.
+    ///
+    /// # Examples
+    ///
+    /// '''
+    /// let (_, len) = png.aligned_layout(1)?;
+    /// let mut texture = vec![0; len];
+    /// let view = png.decode_into(&mut texture)?;
+    ///
+    /// upload(view.width, view.height, view.format, view.data);
+    /// '''
+    pub fn decode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<ImageView<'b>, error::Error> {
+        self.decode_into_aligned(buffer, 1)
+    }
+    /// Gets the distance between the starts of rows Png::decode_into_aligned
+    /// writes, and the size of buffer it needs.
+    ///
+    /// # Arguments
+    ///
+    /// * 'alignment' - The number of bytes each row's length is rounded up
+    ///   to a multiple of, 0 or 1 for no padding.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the stride and the length of buffer, which
+    /// leaves out the padding after the last row, or a DecoderError if the
+    /// buffer would be too large to address.
+    pub fn aligned_layout(&self, alignment: usize) -> Result<(usize, usize), DecoderError> {
+        let (width, height) = self.dimensions;
+        let row = image_len((width, 1), self.pixel_format().bytes_per_pixel())?;
+        let overflow = || DecoderError::DimensionOverflow(width, height);
+
+        let stride = row
+            .checked_next_multiple_of(alignment.max(1))
+            .ok_or_else(overflow)?;
+        let len = match height {
+            0 => 0,
+            height => (height - 1)
+                .checked_mul(stride)
+                .and_then(|rows| rows.checked_add(row))
+                .filter(|&len| len <= isize::MAX as usize)
+                .ok_or_else(overflow)?,
+        };
+
+        Ok((stride, len))
+    }
+    /// Decodes the image into a buffer the caller owns like
+    /// Png::decode_into, with each row padded to a multiple of alignment
+    /// bytes, such as the 4 bytes Windows bitmaps use or the 256 bytes some
+    /// GPU APIs want for texture copies, so the buffer can be handed on
+    /// without repacking it.
+    ///
+    /// # Arguments
+    ///
+    /// * 'buffer' - Where to write the pixels, at least the length from
+    ///   Png::aligned_layout. The padding after each row and anything
+    ///   past the last row are left alone.
+    /// * 'alignment' - The number of bytes each row's length is rounded up
+    ///   to a multiple of, 0 or 1 for no padding.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either an ImageView over the pixels written,
+    /// whose stride is the padded row length, or an Error if the image
+    /// can't be decoded or ImageError::BufferSize if buffer is too small.
+    ///
+    /// # Examples
+    ///
+    /// '''
+    /// let (stride, len) = png.aligned_layout(256)?;
+    /// let mut staging = vec![0; len];
+    /// let view = png.decode_into_aligned(&mut staging, 256)?;
+    ///
+    /// copy_buffer_to_texture(view.data, view.stride, view.width, view.height);
+    /// '''
+    pub fn decode_into_aligned<'b>(
+        &self,
+        buffer: &'b mut [u8],
+        alignment: usize,
+    ) -> Result<ImageView<'b>, error::Error> {
+        let (width, height) = self.dimensions;
+        let format = self.pixel_format();
+        let bytes_per_pixel = format.bytes_per_pixel();
+        let (stride, len) = self.aligned_layout(alignment)?;
+        let buffer_len = buffer.len();
+        let pixels = buffer
+            .get_mut(..len)
+            .ok_or(ImageError::BufferSize(len, buffer_len))?;
+
+        // Rows are written straight to where flipping would move them, as
+        // the padding between them keeps the buffer from being flipped
+        // whole.
+        let row_len = width * bytes_per_pixel;
+        let flip = self.transformations.flip_vertical;
+        let gamma = self.gamma_table();
+        let mut rows = 0;
+        self.for_each_rgba_row(false, &mut |row, rgba| {
+            let row = match flip {
+                true => height
+                    .checked_sub(row + 1)
+                    .ok_or(DecoderError::Unexplainable)?,
+                false => row,
+            };
+            let out = pixels
+                .get_mut(row * stride..row * stride + row_len)
+                .ok_or(DecoderError::Unexplainable)?;
+            match format {
+                PixelFormat::Rgba8 => out.copy_from_slice(rgba),
+                _ => {
+                    for (out, rgba) in out.chunks_exact_mut(3).zip(rgba.chunks_exact(4)) {
+                        out.copy_from_slice(&rgba[..3]);
+                    }
+                }
+            }
+            self.transform_pixels(out, bytes_per_pixel, gamma.as_ref());
+            rows += 1;
+            Ok(())
+        })?;
+
+        if rows != height {
+            return Err(DecoderError::Unexplainable.into());
+        }
+
+        Ok(ImageView::new(width, height, format, stride, pixels)?)
+    }
+    /// Hands each row to each as RGBA, applying tRNS.
+    ///
+    /// # Arguments
+    ///
+    /// * 'wide' - Whether to keep 16 bit samples whole, as Rgba16, rather
+    ///   than keeping their high byte as Rgba8. Only 16 bit images can
+    ///   be decoded wide.
+    /// * 'each' - Given the index and pixels of each row.
+    fn for_each_rgba_row(
+        &self,
+        wide: bool,
+        each: &mut RowCallback<'_>,
+    ) -> Result<(), DecoderError> {
+        let mut rgba = RgbaRows::new(self, wide)?;
+
+        self.for_each_row(&DefaultBuffers, &mut |row, scanline| {
+            each(row, rgba.convert(scanline)?)
+        })
+    }
+    /// Applies the Transformations to decoded pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * 'pixels' - The pixels from left to right, top to bottom, with red
+    ///   first.
+    /// * 'bytes_per_pixel' - The number of bytes in each pixel.
+    fn transform(&self, pixels: &mut [u8], bytes_per_pixel: usize) {
+        self.transform_pixels(pixels, bytes_per_pixel, self.gamma_table().as_ref());
+
+        let row_len = self.dimensions.0 * bytes_per_pixel;
+        if self.transformations.flip_vertical && row_len > 0 {
+            let rows = pixels.len() / row_len;
+            for top in 0..rows / 2 {
+                let (upper, lower) = pixels.split_at_mut((rows - 1 - top) * row_len);
+                upper[top * row_len..(top + 1) * row_len].swap_with_slice(&mut lower[..row_len]);
+            }
+        }
+    }
+    /// Applies the Transformations that change each pixel on its own,
+    /// everything but flip_vertical, to a run of whole pixels, with gamma
+    /// from Png::gamma_table.
+    fn transform_pixels(
+        &self,
+        pixels: &mut [u8],
+        bytes_per_pixel: usize,
+        gamma: Option<&[u8; 256]>,
+    ) {
+        correct_gamma(pixels, bytes_per_pixel, gamma);
+        if self.transformations.bgr {
+            for pixel in pixels.chunks_exact_mut(bytes_per_pixel) {
+                pixel.swap(0, 2);
+            }
+        }
+    }
+    /// Gets the power Transformations::gamma raises samples to, taking them
+    /// from the gamma of the file to that of the display, see 12.5 of the
+    /// spec.
+    ///
+    /// # Returns
+    ///
+    /// The exponent, or None if the transformation is off or the file has
+    /// no gamma.
+    fn gamma_exponent(&self) -> Option<f64> {
+        let gamma = self.gamma.filter(|_| self.transformations.gamma)?;
+
+        Some(1.0 / (gamma as f64 * DISPLAY_GAMMA))
+    }
+    /// Builds the table Transformations::gamma maps 8 bit samples through,
+    /// once for each decode rather than for each row.
+    ///
+    /// # Returns
+    ///
+    /// The corrected value of each sample, or None if the transformation is
+    /// off or the file has no gamma.
+    fn gamma_table(&self) -> Option<[u8; 256]> {
+        let exponent = self.gamma_exponent()?;
+
+        Some(core::array::from_fn(|sample| {
+            (color::powf(sample as f64 / 255.0, exponent) * 255.0 + 0.5) as u8
+        }))
+    }
+    /// Gets the number of bytes in a scanline of the given width, not
+    /// including the filter type byte.
+    fn stride(&self, width: usize) -> usize {
+        (width * self.color_type.samples() * self.bit_depth as usize).div_ceil(8)
+    }
+    /// Appends the samples of a reconstructed scanline to out as 8 bit
+    /// samples, keeping the high byte of 16 bit ones and unpacking 1, 2 and
+    /// 4 bit ones into a byte each. Sub-byte grayscale samples are scaled
+    /// to the full range, palette indices are kept as they are.
+    fn to_8_bit(&self, scanline: &[u8], out: &mut Vec<u8>) {
+        match self.bit_depth {
+            16 => out.extend(scanline.iter().step_by(2)),
+            8 => out.extend_from_slice(scanline),
+            depth => {
+                let scale = match self.color_type {
+                    ColorType::PalleteIndex => 1,
+                    _ => 255 / ((1u8 << depth) - 1),
+                };
+                out.extend(self.unpack(scanline).map(|sample| sample * scale));
+            }
+        }
+    }
+    /// Appends the samples of a reconstructed scanline to out at the image's
+    /// own bit depth, without scaling them.
+    fn raw_samples(&self, scanline: &[u8], out: &mut Vec<u16>) {
+        match self.bit_depth {
+            16 => out.extend(
+                scanline
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]])),
+            ),
+            8 => out.extend(scanline.iter().map(|&sample| sample as u16)),
+            _ => out.extend(self.unpack(scanline).map(u16::from)),
+        }
+    }
+    /// Unpacks the 1, 2 or 4 bit samples of a scanline into a byte each, from
+    /// the most significant bit down.
+    fn unpack<'a>(&self, scanline: &'a [u8]) -> impl Iterator<Item = u8> + 'a {
+        let depth = self.bit_depth as usize;
+        let mask = (1u8 << depth) - 1;
+        // The end of the last byte is padding when the samples don't fill
+        // it.
+        let samples = self.dimensions.0 * self.color_type.samples();
+
+        (0..samples).map_while(move |i| {
+            let bit = i * depth;
+            Some(scanline.get(bit / 8)? >> (8 - depth - bit % 8) & mask)
+        })
+    }
+    /// Appends a row of 8 bit samples to out as RGB, looking the indices of
+    /// indexed images up in their palette, see expand_rgb for the rest.
+    fn to_rgb(
+        &self,
+        samples: &[u8],
+        palette: Option<&Palette>,
+        out: &mut Vec<u8>,
+    ) -> Result<(), DecoderError> {
+        match (self.color_type, palette) {
+            (ColorType::PalleteIndex, Some(palette)) => {
+                for &index in samples {
+                    // Indices past the end of the palette aren't allowed,
+                    // see 11.2.3 of the spec.
+                    let entry = palette
+                        .entries
+                        .get(index as usize)
+                        .ok_or(DecoderError::InvalidChunk("palette index is out of range."))?;
+                    out.extend_from_slice(&entry[..3]);
+                }
+            }
+            _ => expand_rgb(self.color_type, samples, out),
+        }
+
+        Ok(())
+    }
+    /// Appends a row of 8 bit samples to out as RGBA, looking the indices of
+    /// indexed images up in their palette, see expand_rgba for the rest.
+    fn to_rgba(
+        &self,
+        samples: &[u8],
+        palette: Option<&Palette>,
+        out: &mut Vec<u8>,
+    ) -> Result<(), DecoderError> {
+        match (self.color_type, palette) {
+            (ColorType::PalleteIndex, Some(palette)) => {
+                for &index in samples {
+                    let entry = palette
+                        .entries
+                        .get(index as usize)
+                        .ok_or(DecoderError::InvalidChunk("palette index is out of range."))?;
+                    out.extend_from_slice(entry);
+                }
+            }
+            _ => expand_rgba(self.color_type, samples, 255, out),
+        }
+
+        Ok(())
+    }
+    /// Reads the one color the tRNS chunk of a grayscale or RGB image makes
+    /// fully transparent, see 11.3.2.1 of the spec.
+    ///
+    /// # Returns
+    ///
+    /// The samples of the color at the image's bit depth, or None if there's
+    /// no tRNS chunk, it's the wrong length, or the image has alpha or a
+    /// palette instead.
+    fn transparent_color(&self) -> Option<Vec<u16>> {
+        let samples = match self.color_type {
+            ColorType::Grayscale => 1,
+            ColorType::RGB => 3,
+            _ => return None,
+        };
+        let trns = &self.trns()?.data;
+        if trns.len() != samples * 2 {
+            return None;
+        }
+
+        Some(
+            trns.chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect(),
+        )
+    }
+    /// Reads the palette an indexed image needs to be decoded.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the Palette of an indexed image, None for other
+    /// color types, or a DecoderError if an indexed image's PLTE chunk is
+    /// missing or damaged.
+    fn decode_palette(&self) -> Result<Option<Palette>, DecoderError> {
+        if self.color_type != ColorType::PalleteIndex {
+            return Ok(None);
+        }
+
+        match self.palette() {
+            Some(palette) if !palette.is_empty() => Ok(Some(palette)),
+            _ => Err(DecoderError::InvalidChunk(
+                "the PLTE chunk of an indexed image is missing or damaged.",
+            )),
+        }
+    }
+    /// Decompresses the image data and reconstructs each scanline in turn,
+    /// handing them to each from top to bottom as they're finished, so only
+    /// two scanlines of pixels and a window of the decompressed data are
+    /// held at once. Interlaced images are the exception, every pass has to
+    /// be reconstructed before the first row is whole. Transformations
+    /// aren't applied.
+    pub(crate) fn for_each_row(
+        &self,
+        provider: &dyn BufferProvider,
+        each: &mut RowCallback<'_>,
+    ) -> Result<(), DecoderError> {
+        self.check_limits()?;
+        if self.data.idat.is_empty() {
+            return Err(DecoderError::InvalidChunk(
+                "no IDAT chunks, the image data is missing or damaged.",
+            ));
+        }
+
+        // Concatenate the data from all IDAT chunks.
+        let cgbi = self.is_cgbi();
+        let compressed_len = self.data.idat.iter().map(|ch| ch.data.len()).sum();
+        let mut zlib_bytes = provider.allocate(BufferKind::Compressed, compressed_len);
+        zlib_bytes.clear();
+        self.zlib_stream(&mut zlib_bytes);
+
+        // The length of each scanline, not including the filter type byte.
+        let stride = self.stride(self.dimensions.0);
+        let bpp = self.color_type.bytes_per_pixel(self.bit_depth);
+        let checksum = self.checksums != ChecksumPolicy::Ignore && !cgbi;
+        // CgBI scanlines are reconstructed as they're stored, then put back
+        // in order before being handed on.
+        let mut restored = Vec::new();
+        let mut each = |row: usize, scanline: &[u8]| match cgbi {
+            true => {
+                cgbi::restore(scanline, self.color_type, self.bit_depth, &mut restored);
+                each(row, &restored)
+            }
+            false => each(row, scanline),
+        };
+        let mut inflated = 0usize;
+        let mut count = |piece: &[u8]| {
+            inflated = inflated.saturating_add(piece.len());
+            self.limits.check_decompressed(inflated)
+        };
+
+        // Interlaced images only make whole scanlines once every pass has
+        // been put back together, so they're inflated in one go.
+        let result = if self.interlace == Interlace::Adam7 {
+            // Checked first, so the size of the passes can't overflow.
+            data_len(self.dimensions, self.color_type, self.bit_depth)?;
+            let bits = self.color_type.samples() * self.bit_depth as usize;
+            let decompressed_len = adam7::data_len(self.dimensions, bits);
+            let mut data = provider.allocate(BufferKind::Decompressed, decompressed_len);
+            data.clear();
+            // Data past the last pass is ignored, so it isn't kept either.
+            let mut push = |piece: &[u8]| {
+                count(piece)?;
+                let room = decompressed_len - data.len();
+                data.extend_from_slice(&piece[..piece.len().min(room)]);
+                Ok(())
+            };
+            self.decompressor
+                .decompress(
+                    &zlib_bytes,
+                    checksum,
+                    !self.ignore_window,
+                    Vec::new(),
+                    &mut push,
+                )
+                .map(|_| data)
+        } else {
+            // Otherwise each scanline is reconstructed as soon as it has
+            // been inflated, so only a window of the data is held at once.
+            let buffer = provider.allocate(BufferKind::Decompressed, STREAM_BUFFER);
+            let mut scanlines = Scanlines::new(stride, bpp, self.dimensions.1);
+            #[cfg(feature = "std")]
+            let pipelined = self.threads > 1
+                && data_len(self.dimensions, self.color_type, self.bit_depth)? >= MIN_PARALLEL_LEN;
+            #[cfg(not(feature = "std"))]
+            let pipelined = false;
+
+            let inflated = match pipelined {
+                #[cfg(feature = "std")]
+                true => self.inflate_pipelined(&zlib_bytes, checksum, buffer, count, |piece| {
+                    scanlines.push(piece, &mut each)
+                }),
+                _ => {
+                    let mut push = |piece: &[u8]| {
+                        count(piece)?;
+                        scanlines.push(piece, &mut each).map_err(error::Error::from)
+                    };
+                    self.decompressor.decompress(
+                        &zlib_bytes,
+                        checksum,
+                        !self.ignore_window,
+                        buffer,
+                        &mut push,
+                    )
+                }
+            };
+            inflated.and_then(|buffer| {
+                scanlines.finish().map_err(error::Error::from)?;
+                Ok(buffer)
+            })
+        };
+        provider.release(BufferKind::Compressed, zlib_bytes);
+        let data = result.map_err(|e| self.decompress_error(e))?;
+
+        if self.interlace == Interlace::Adam7 {
+            let bits = self.color_type.samples() * self.bit_depth as usize;
+            let pixels = adam7::deinterlace(&data, self.dimensions, bits, self.threads);
+            provider.release(BufferKind::Decompressed, data);
+
+            for (row, scanline) in pixels?.chunks(stride).enumerate() {
+                each(row, scanline)?;
+            }
+            return Ok(());
+        }
+        provider.release(BufferKind::Decompressed, data);
+
+        Ok(())
+    }
+    /// Inflates the image data on a thread of its own, handing each piece
+    /// back to push on this one, so reconstructing scanlines overlaps with
+    /// inflating the data after them. Only a few pieces are held between the
+    /// two at once.
     ///
     /// # Arguments
     ///
-    /// * 'path' - The file path to the PNG file, can be any type that implements into path.
+    /// * 'zlib_bytes' - The image data.
+    /// * 'checksum' - Whether to check its adler32.
+    /// * 'buffer' - The buffer the Decompressor may hold its output in.
+    /// * 'count' - Called on the inflating thread with each piece, to check
+    ///   it against the limits.
+    /// * 'push' - Called on this thread with each piece in order. Its first
+    ///   error stops inflating and is returned.
     ///
     /// # Returns
     ///
-    /// A result containing either the constructed Png or a DecoderError.
-    ///
-    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Png, DecoderError> {
-        let path = path.as_ref();
+    /// A result containing either the buffer or a DecompressError, as from
+    /// Decompressor::decompress.
+    #[cfg(feature = "std")]
+    fn inflate_pipelined(
+        &self,
+        zlib_bytes: &[u8],
+        checksum: bool,
+        buffer: Vec<u8>,
+        mut count: impl FnMut(&[u8]) -> Result<(), DecoderError> + Send,
+        mut push: impl FnMut(&[u8]) -> Result<(), DecoderError>,
+    ) -> Result<Vec<u8>, DecompressError> {
+        use std::{panic, sync::mpsc, thread};
+
+        let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(PIPELINE_DEPTH);
+        thread::scope(|scope| {
+            let inflater = scope.spawn(move || {
+                let mut send = |piece: &[u8]| {
+                    count(piece)?;
+                    // Only fails once push has, whose error is returned.
+                    sender
+                        .send(piece.to_vec())
+                        .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe).into())
+                };
+                self.decompressor.decompress(
+                    zlib_bytes,
+                    checksum,
+                    !self.ignore_window,
+                    buffer,
+                    &mut send,
+                )
+            });
 
-        let file_bytes = fs::read(path)?;
+            let pushed = receiver.iter().try_for_each(|piece| push(&piece));
+            // Stops the inflating thread at its next piece.
+            drop(receiver);
+            let inflated = inflater.join().unwrap_or_else(|e| panic::resume_unwind(e));
 
-        if file_bytes[0..8] != PNG_HEADER {
-            return Err(DecoderError::NotPngFile);
+            pushed.map_err(|e| DecompressError::from(error::Error::from(e)))?;
+            inflated
+        })
+    }
+    /// Decodes the image into interleaved RGBA bytes, for the bindings that
+    /// hand pixels to other languages.
+    #[cfg(any(feature = "wasm", feature = "ffi"))]
+    pub(crate) fn rgba_bytes(&self) -> Result<Vec<u8>, DecoderError> {
+        self.decode_rgba()
+    }
+    /// Turns an error from the Decompressor back into a DecoderError,
+    /// pointing at the IDAT chunk and bit it stopped on. Errors from
+    /// reconstructing the scanlines are passed on as they are.
+    fn decompress_error(&self, mut failure: DecompressError) -> DecoderError {
+        if self.is_cgbi() {
+            failure.bit = failure.bit.map(|bit| bit.saturating_sub(cgbi::HEADER_BITS));
         }
+        let decoder_error = match failure.error {
+            error::Error::Decoder(e) => return e,
+            error::Error::Deflate(e) => DecoderError::from(e),
+            error::Error::Zlib(e) => DecoderError::from(e),
+            #[cfg(feature = "std")]
+            error::Error::Io(e) => DecoderError::from(e),
+            _ => DecoderError::Unexplainable,
+        };
 
-        let data = PngData::build(&file_bytes)?;
+        match failure.bit {
+            Some(bit) => self.in_idat(decoder_error, bit / 8).at_bit(bit),
+            None => self.in_idat(decoder_error, 0),
+        }
+    }
+    /// Whether the file is in Apple's CgBI variant of PNG, which is only
+    /// read when DecodeOptions::cgbi is set. The pixels are put back in
+    /// order and their alpha unpremultiplied as they're decoded, so every
+    /// decode gives the same pixels as for an ordinary PNG file.
+    pub fn is_cgbi(&self) -> bool {
+        self.data
+            .ancillary_chunks
+            .iter()
+            .any(|chunk| chunk.ctype == ChunkType::CGBI)
+    }
+    /// Appends the data of every IDAT chunk to bytes, as a zlib stream. The
+    /// raw deflate data of a CgBI file is wrapped in a zlib header and an
+    /// adler32 that isn't checked.
+    fn zlib_stream(&self, bytes: &mut Vec<u8>) {
+        let cgbi = self.is_cgbi();
+        if cgbi {
+            bytes.extend_from_slice(&cgbi::ZLIB_HEADER);
+        }
+        for chunk in &self.data.idat {
+            bytes.extend_from_slice(&chunk.data);
+        }
+        if cgbi {
+            bytes.extend_from_slice(&cgbi::ZLIB_TRAILER);
+        }
+    }
+    /// Attaches the IDAT chunk containing the given position of the
+    /// concatenated zlib stream to an error.
+    fn in_idat(&self, error: DecoderError, position: usize) -> DecoderError {
+        let mut remaining = position;
+        for chunk in &self.data.idat {
+            if remaining < chunk.length {
+                return error.in_chunk(chunk.ctype.as_str(), chunk.offset);
+            }
+            remaining -= chunk.length;
+        }
+        match self.data.idat.last() {
+            Some(chunk) => error.in_chunk(chunk.ctype.as_str(), chunk.offset),
+            None => error,
+        }
+    }
+}
 
-        let dimensions = (
-            data.ihdr.data[0..4]
-                .iter()
-                .fold(0usize, |acc, byte| (acc << 8) | *byte as usize),
-            data.ihdr.data[4..8]
-                .iter()
-                .fold(0usize, |acc, byte| (acc << 8) | *byte as usize),
-        );
+/// Called with the index and pixels of each scanline as it's decoded, see
+/// Png::for_each_row.
+type RowCallback<'a> = dyn FnMut(usize, &[u8]) -> Result<(), DecoderError> + 'a;
 
-        let bit_depth = data.ihdr.data[8];
+/// How much of the decompressed data to ask a BufferProvider for when
+/// streaming scanlines, enough for the window deflate copies from and the
+/// block being decoded.
+const STREAM_BUFFER: usize = 128 * 1024;
 
-        let color_type = match data.ihdr.data[9] {
-            0 => ColorType::Grayscale,
-            2 => ColorType::RGB,
-            3 => ColorType::PalleteIndex,
-            4 => ColorType::GrayscaleAlpha,
-            6 => ColorType::RGBA,
-            other => {
-                return Err(DecoderError::InvalidColorType(other));
-            }
-        };
+/// The least decompressed image data worth spreading over threads, below
+/// which starting them costs more than they save.
+#[cfg(feature = "std")]
+const MIN_PARALLEL_LEN: usize = 1 << 20;
 
-        let interlace = match data.ihdr.data[12] {
-            0 => Interlace::None,
-            1 => Interlace::Adam7,
-            other => {
-                return Err(DecoderError::InvalidInterlace(other));
-            }
-        };
+/// How many inflated pieces can wait for their scanlines to be
+/// reconstructed when inflating runs on a thread of its own.
+#[cfg(feature = "std")]
+const PIPELINE_DEPTH: usize = 4;
 
-        //let interlace = matches!()
+/// Splits decompressed data arriving a piece at a time into scanlines,
+/// reconstructing each as soon as it's whole.
+struct Scanlines {
+    stride: usize,
+    bpp: usize,
+    height: usize,
+    row: usize,
+    // The start of a scanline split between two pieces.
+    pending: Vec<u8>,
+    // The reconstructed scanline above, empty for the first one.
+    last: Vec<u8>,
+    current: Vec<u8>,
+}
 
-        Ok(Png {
-            data,
-            dimensions,
-            bit_depth,
-            color_type,
-            interlace,
-        })
+impl Scanlines {
+    fn new(stride: usize, bpp: usize, height: usize) -> Self {
+        Self {
+            stride,
+            bpp,
+            height,
+            row: 0,
+            pending: Vec::with_capacity(stride + 1),
+            last: Vec::with_capacity(stride),
+            current: Vec::with_capacity(stride),
+        }
     }
-    /// Converts the PNG file into a vector of rgb tuples.
-    ///
-    /// # Returns
-    ///
-    /// A Vec<(u8, u8, u8)> containing each pixel from left to right, top to
-    /// bottom.
-    pub fn rgb(&self) -> Result<Vec<(u8, u8, u8)>, DecoderError> {
-        // Concatenate the data from all IDAT chunks.
-        let zlib_bytes = self
-            .data
-            .idat
-            .iter()
-            .flat_map(|ch| &ch.data)
-            .cloned()
-            .collect::<Vec<_>>();
+    /// Reconstructs every scanline the data completes, handing each to
+    /// each in turn.
+    fn push(&mut self, mut data: &[u8], each: &mut RowCallback<'_>) -> Result<(), DecoderError> {
+        let len = self.stride + 1;
 
-        let mut zlib = ZlibStream::build(&zlib_bytes).unwrap();
+        if !self.pending.is_empty() {
+            let needed = (len - self.pending.len()).min(data.len());
+            self.pending.extend_from_slice(&data[..needed]);
+            data = &data[needed..];
+            if self.pending.len() < len {
+                return Ok(());
+            }
 
-        let data = zlib.decompress().unwrap();
+            let pending = mem::take(&mut self.pending);
+            self.reconstruct(&pending, each)?;
+            self.pending = pending;
+            self.pending.clear();
+        }
 
-        // Get the number of samples per pixel.
-        let samples: usize = match self.color_type {
-            ColorType::Grayscale => 1,
-            ColorType::RGB => 3,
-            ColorType::PalleteIndex => 1,
-            ColorType::GrayscaleAlpha => 2,
-            ColorType::RGBA => 4,
-        };
+        let mut scanlines = data.chunks_exact(len);
+        for scanline in scanlines.by_ref() {
+            self.reconstruct(scanline, each)?;
+        }
+        self.pending.extend_from_slice(scanlines.remainder());
 
-        println!("{:?}", self.data);
+        Ok(())
+    }
+    /// Checks every scanline of the image arrived whole, once the data has
+    /// ended. Data past the last scanline is ignored.
+    fn finish(&mut self) -> Result<(), DecoderError> {
+        if self.row < self.height {
+            return Err(DecoderError::InvalidChunk(
+                "the image data ends before the last scanline.",
+            ));
+        }
 
-        for byte in &data {
-            print!("{:08b} ", byte);
+        Ok(())
+    }
+    fn reconstruct(
+        &mut self,
+        scanline: &[u8],
+        each: &mut RowCallback<'_>,
+    ) -> Result<(), DecoderError> {
+        if self.row == self.height {
+            return Ok(());
         }
-        println!();
 
-        let bpp = samples as u8 * (self.bit_depth / 8);
-        println!("bpp: {}, bit_depth: {}", bpp, self.bit_depth);
+        let filter = Filters::try_from(scanline[0])?;
+        self.current.clear();
+        filter::reconstruct(
+            filter,
+            &scanline[1..],
+            &self.last,
+            self.bpp,
+            &mut self.current,
+        );
 
-        // Split the data into each individual scanline.
-        let scanlines = data
-            .chunks((samples * self.dimensions.0) + 1)
-            .collect::<Vec<_>>();
+        each(self.row, &self.current)?;
+        self.row += 1;
+        mem::swap(&mut self.last, &mut self.current);
 
-        let mut last = vec![0u8; samples * self.dimensions.0];
+        Ok(())
+    }
+}
 
-        let mut defiltered_scanlines: Vec<Vec<u8>> = Vec::with_capacity(scanlines.len());
-        println!("{:?}", self.interlace);
-        for scanline in scanlines {
-            println!("{:?} ", scanline);
-            match scanline[0] {
-                0 => {
-                    defiltered_scanlines.push(scanline[1..].to_vec());
-                }
-                1 => {
-                    defiltered_scanlines.push(rfsub(&scanline[1..], bpp as usize));
-                }
-                2 => {
-                    defiltered_scanlines.push(rfup(&scanline[1..], &last));
-                }
-                3 => {
-                    defiltered_scanlines.push(rfaverage(&scanline[1..], &last, bpp as usize));
-                }
-                4 => {
-                    defiltered_scanlines.push(rfpaeth(&scanline[1..], &last, bpp as usize));
-                }
-                _ => {}
-            }
-            last = defiltered_scanlines.last().unwrap_or(&Vec::new()).clone();
-        }
+/// Converts reconstructed scanlines to RGBA a row at a time, applying tRNS,
+/// see Png::rgba_rows.
+struct RgbaRows<'a> {
+    png: &'a Png,
+    wide: bool,
+    palette: Option<Palette>,
+    // The tRNS color, at the image's own bit depth.
+    key: Option<Vec<u16>>,
+    samples: Vec<u8>,
+    raw: Vec<u16>,
+    wide_pixels: Vec<u16>,
+    pixels: Vec<u8>,
+}
+
+impl<'a> RgbaRows<'a> {
+    /// Reads the palette and tRNS chunks the rows need.
+    ///
+    /// # Arguments
+    ///
+    /// * 'wide' - Whether to keep 16 bit samples whole, as Rgba16, rather
+    ///   than keeping their high byte as Rgba8.
+    fn new(png: &'a Png, wide: bool) -> Result<Self, DecoderError> {
+        let width = png.dimensions.0;
+        let format = match wide {
+            true => PixelFormat::Rgba16,
+            false => PixelFormat::Rgba8,
+        };
 
-        for scanline in &defiltered_scanlines {
-            println!("{:?}", scanline);
+        Ok(Self {
+            png,
+            wide,
+            palette: png.decode_palette()?,
+            key: png.transparent_color(),
+            samples: Vec::with_capacity(width * png.color_type.samples()),
+            raw: Vec::new(),
+            wide_pixels: Vec::new(),
+            pixels: Vec::with_capacity(width * format.bytes_per_pixel()),
+        })
+    }
+    /// Converts one reconstructed scanline.
+    fn convert(&mut self, scanline: &[u8]) -> Result<&[u8], DecoderError> {
+        let png = self.png;
+        let format = match self.wide {
+            true => PixelFormat::Rgba16,
+            false => PixelFormat::Rgba8,
+        };
+        self.pixels.clear();
+        self.raw.clear();
+        if self.wide || self.key.is_some() {
+            png.raw_samples(scanline, &mut self.raw);
         }
+        // The color is compared at the image's own bit depth, so 16 bit
+        // colors sharing a high byte aren't mistaken for it.
+        let (key, raw) = (&self.key, &self.raw);
+        let transparent = |i: usize| {
+            key.as_ref()
+                .is_some_and(|key| raw.get(i * key.len()..(i + 1) * key.len()) == Some(key))
+        };
 
-        let mut output = Vec::new();
-        println!("{:?}", self.color_type);
-        for line in defiltered_scanlines {
-            for values in line.chunks(3) {
-                if values.len() != 3 {
-                    return Err(DecoderError::Unexplainable);
+        if self.wide {
+            self.wide_pixels.clear();
+            expand_rgba(png.color_type, raw, u16::MAX, &mut self.wide_pixels);
+            for (i, pixel) in self.wide_pixels.chunks_exact(4).enumerate() {
+                let alpha = if transparent(i) { 0 } else { pixel[3] };
+                for sample in [pixel[0], pixel[1], pixel[2], alpha] {
+                    self.pixels.extend_from_slice(&sample.to_be_bytes());
+                }
+            }
+        } else {
+            self.samples.clear();
+            png.to_8_bit(scanline, &mut self.samples);
+            png.to_rgba(&self.samples, self.palette.as_ref(), &mut self.pixels)?;
+            for (i, pixel) in self.pixels.chunks_exact_mut(4).enumerate() {
+                if transparent(i) {
+                    pixel[3] = 0;
                 }
-                output.push((values[0], values[1], values[2]));
             }
         }
 
-        Ok(output)
+        if self.pixels.len() != png.dimensions.0 * format.bytes_per_pixel() {
+            return Err(DecoderError::Unexplainable);
+        }
+        Ok(&self.pixels)
     }
 }
 
@@ -238,27 +1908,81 @@ impl Png {
 /// * 'data' - The data held within the chunk.
 /// * 'crc' - The CRC32 checksum.
 /// * 'size' - The overall size of the chunk (including the header and checksum).
+/// * 'offset' - The position of the start of the chunk in the file, in bytes.
 ///
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Chunk {
     pub length: usize,
-    pub ctype: String,
+    pub ctype: ChunkType,
     pub data: Vec<u8>,
     pub crc: u32,
     pub size: usize,
+    pub offset: usize,
 }
 
 impl Chunk {
     pub fn new() -> Self {
         Self {
             length: 0,
-            ctype: String::new(),
+            ctype: ChunkType::IHDR,
             data: Vec::new(),
             crc: 0,
             size: 0,
+            offset: 0,
+        }
+    }
+    /// Creates a chunk holding the given data, with the length, CRC32 and
+    /// size worked out from it. The offset is left at 0.
+    ///
+    /// # Arguments
+    ///
+    /// * 'ctype' - The type of the chunk.
+    /// * 'data' - The data held within the chunk.
+    ///
+    /// # Returns
+    ///
+    /// The Chunk.
+    pub fn with_data(ctype: ChunkType, data: Vec<u8>) -> Self {
+        let crc = crc::hash(&[&ctype.as_bytes()[..], &data].concat());
+
+        Self {
+            length: data.len(),
+            ctype,
+            size: data.len() + 12,
+            data,
+            crc,
+            offset: 0,
         }
     }
+    /// Serializes the chunk as it's stored in a file: its length, type,
+    /// data and CRC32. The length and CRC32 are worked out from the type
+    /// and data, so a chunk whose data has been changed is written
+    /// correctly.
+    ///
+    /// # Returns
+    ///
+    /// The chunk as bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.data.len() + 12);
+        rewrite::write_chunk(&mut bytes, self.ctype.as_bytes(), &self.data);
+
+        bytes
+    }
+    /// Whether the chunk's CRC32 matches its type and data.
+    pub fn crc_matches(&self) -> bool {
+        self.crc == crc::hash(&[&self.ctype.as_bytes()[..], &self.data].concat())
+    }
     pub fn from(bytes: &[u8]) -> Result<Self, DecoderError> {
+        Self::parse(bytes, ChecksumPolicy::Verify)
+    }
+    fn parse(bytes: &[u8], checksums: ChecksumPolicy) -> Result<Self, DecoderError> {
+        if bytes.len() < 12 {
+            return Err(DecoderError::InvalidChunk(
+                "not enough bytes left for a chunk.",
+            ));
+        }
+
         let mut byte_iterator = bytes.iter();
 
         let length = byte_iterator
@@ -266,21 +1990,11 @@ impl Chunk {
             .take(4)
             .fold(0usize, |acc, byte| (acc << 8) | *byte as usize);
 
-        let type_vec = byte_iterator.by_ref().take(4).cloned().collect::<Vec<_>>();
-        let ctype = match String::from_utf8(type_vec.clone()) {
-            Ok(str) => {
-                if VALID_CHUNK_TYPES.contains(&str.as_str()) {
-                    str
-                } else {
-                    return Err(DecoderError::InvalidChunk("chunk type is invalid."));
-                }
-            }
-            Err(_) => {
-                return Err(DecoderError::InvalidChunk(
-                    "could not convert type to utf-8.",
-                ));
-            }
-        };
+        let mut type_bytes = [0u8; 4];
+        for (to, from) in type_bytes.iter_mut().zip(byte_iterator.by_ref()) {
+            *to = *from;
+        }
+        let ctype = ChunkType::new(type_bytes)?;
 
         let data = byte_iterator
             .by_ref()
@@ -293,23 +2007,28 @@ impl Chunk {
             .take(4)
             .fold(0u32, |acc, byte| (acc << 8) | *byte as u32);
 
-        let to_hash = [type_vec, data.clone()].concat();
-
-        if crc != crc::hash(&to_hash) {
+        if data.len() != length {
             return Err(DecoderError::InvalidChunk(
-                "chunk CRC could not be verified.",
+                "chunk is longer than the bytes left in the file.",
             ));
         }
 
-        let size = length + 12;
-
-        Ok(Self {
+        let chunk = Self {
             length,
             ctype,
             data,
             crc,
-            size,
-        })
+            size: length + 12,
+            offset: 0,
+        };
+
+        if checksums == ChecksumPolicy::Verify && !chunk.crc_matches() {
+            return Err(DecoderError::InvalidChunk(
+                "chunk CRC could not be verified.",
+            ));
+        }
+
+        Ok(chunk)
     }
 }
 
@@ -327,6 +2046,8 @@ impl Default for Chunk {
 /// * 'ihdr' - An array storing the 13 byte IHDR chunk.
 /// * 'plte' - Contains the optional PLTE chunk.
 /// * 'IDAT' - Contains a vector of Vec<u8>'s containing the IDAT chunk/chunks.
+/// * 'crc_mismatches' - The type and offset of each chunk kept despite a
+///   CRC32 that doesn't match its data, see ChecksumPolicy::Tolerate.
 ///
 #[derive(Debug)]
 pub struct PngData {
@@ -335,6 +2056,7 @@ pub struct PngData {
     pub plte: Option<Chunk>,
     pub idat: Vec<Chunk>,
     pub ancillary_chunks: Vec<Chunk>,
+    pub crc_mismatches: Vec<(ChunkType, usize)>,
 }
 
 impl PngData {
@@ -345,123 +2067,143 @@ impl PngData {
     /// * 'raw_data' - A slice containing the entire PNG file as bytes.
     ///
     pub fn build(raw_data: &[u8]) -> Result<Self, DecoderError> {
-        if raw_data[0..8] != PNG_HEADER {
+        Self::build_with(raw_data, &DecodeOptions::new())
+    }
+    /// Parses the raw bytes of a PNG file using the given DecodeOptions.
+    ///
+    /// # Arguments
+    ///
+    /// * 'raw_data' - A slice containing the entire PNG file as bytes.
+    /// * 'options' - The DecodeOptions deciding how strictly chunks are
+    ///   checked.
+    ///
+    pub fn build_with(raw_data: &[u8], options: &DecodeOptions) -> Result<Self, DecoderError> {
+        if raw_data.len() < 8 || raw_data[0..8] != PNG_HEADER {
             return Err(DecoderError::NotPngFile);
         }
 
+        let mut chunks = PngDataBuilder::new(options);
         let mut index = 8;
-        let mut ancillary_chunks = Vec::with_capacity(3);
-
-        let mut ihdr = Chunk::new();
-        let mut idat = Vec::new();
-        let mut plte = None;
-
-        while let Ok(chunk) = Chunk::from(&raw_data[index..]) {
-            index += chunk.size;
-            match chunk.ctype.as_str() {
-                "IHDR" => ihdr = chunk,
-                "IDAT" => idat.push(chunk),
-                "PLTE" => plte = Some(chunk),
-                _ => ancillary_chunks.push(chunk),
+        while index < raw_data.len() {
+            match chunks.push(raw_data, index)? {
+                Some(size) => index += size,
+                None => break,
             }
         }
 
-        Ok(Self {
-            raw_data: raw_data.to_vec(),
-            ihdr,
-            plte,
-            idat,
-            ancillary_chunks,
-        })
+        Ok(chunks.finish(raw_data.to_vec()))
     }
 }
 
-//      +---------+
-//      | FILTERS |
-//      +---------+
-
-/// Enum for storing each filter type described in Chapter 6 of the spec.
-/// Each filter is defined by a single byte before each scanline, and
-/// applies to each byte, regardless of bit depth. Most pixels have more
-/// than one bytes worth of information, and so in these cases, the filter
-/// is applied referencing the corrosponding byte of the previous pixel.
-/// So, if the color type is RGB with a bit-depth of 8, each sample for
-/// red would be filtered together, and then each sample for blue, and so
-/// on.
+/// Sorts the chunks of a file into a PngData one at a time, so a file can be
+/// parsed as it arrives, see StreamDecoder.
 ///
-/// # Members
+/// # Fields
 ///
-/// * 'None' - No filter is applied.
-/// * 'Sub' - Each byte transmits the difference between itself and the last
-///         corrosponding byte.
-/// * 'Up' - Each byte is the same as sub however it transmits the difference
-///         between the current byte and the corrosponding byte from the pixel
-///         directly above it (same position in the previous scanline).
-/// * 'Average' - Subtracts the average of the bytes in the pixels to the left
-///         and above from the current byte.
-/// * 'Paeth' - A bit too complex to be worth summarizing, it's described in
-///         section 6.6 of the specification.
-///         
-pub enum Filters {
-    None,
-    Sub,
-    Up,
-    Average,
-    Paeth,
+/// * 'strict' - Whether a chunk that can't be parsed is an error, see
+///   DecodeOptions.
+/// * 'checksums' - How each chunk's CRC32 is checked.
+/// * 'stopped' - Whether a chunk that couldn't be parsed ended the file,
+///   when not strict.
+///
+/// The rest are the fields of the PngData being built.
+#[derive(Debug, Clone)]
+struct PngDataBuilder {
+    strict: bool,
+    cgbi: bool,
+    checksums: ChecksumPolicy,
+    stopped: bool,
+    ihdr: Chunk,
+    plte: Option<Chunk>,
+    idat: Vec<Chunk>,
+    ancillary_chunks: Vec<Chunk>,
+    crc_mismatches: Vec<(ChunkType, usize)>,
 }
 
-pub fn rfsub(scanline: &[u8], bpp: usize) -> Vec<u8> {
-    let mut buf = Vec::with_capacity(scanline.len());
-    for (i, &byte) in scanline.iter().enumerate() {
-        let left = if i >= bpp { buf[i - bpp] } else { 0 };
-        buf.push(byte.wrapping_add(left));
+impl PngDataBuilder {
+    /// Creates a PngDataBuilder that hasn't been given any chunks yet.
+    fn new(options: &DecodeOptions) -> Self {
+        Self {
+            strict: options.strict,
+            cgbi: options.cgbi,
+            checksums: options.checksums,
+            stopped: false,
+            ihdr: Chunk::new(),
+            plte: None,
+            idat: Vec::new(),
+            ancillary_chunks: Vec::with_capacity(3),
+            crc_mismatches: Vec::new(),
+        }
     }
-    buf
-}
-
-pub fn rfup(scanline: &[u8], last: &[u8]) -> Vec<u8> {
-    scanline
-        .iter()
-        .enumerate()
-        .map(|(i, &byte)| byte.wrapping_add(last[i]))
-        .collect()
-}
+    /// Parses the chunk starting at index and sorts it.
+    ///
+    /// # Arguments
+    ///
+    /// * 'raw_data' - The file from its start, at least up to the end of the
+    ///   chunk if it's whole.
+    /// * 'index' - Where the chunk starts.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the size of the chunk or None if parsing
+    /// has stopped at a bad chunk, or a DecoderError if the chunk is bad and
+    /// parsing is strict, or it's an unknown critical chunk.
+    fn push(&mut self, raw_data: &[u8], index: usize) -> Result<Option<usize>, DecoderError> {
+        if self.stopped {
+            return Ok(None);
+        }
 
-pub fn rfaverage(scanline: &[u8], last: &[u8], bpp: usize) -> Vec<u8> {
-    let mut buf = Vec::with_capacity(scanline.len());
-    for (i, &byte) in scanline.iter().enumerate() {
-        let left = if i >= bpp { buf[i - bpp] } else { 0 };
-        let above = if !last.is_empty() { last[i] } else { 0 };
-        let original = byte.wrapping_add(((left as u16 + above as u16) / 2) as u8);
-        buf.push(original);
-    }
-    buf
-}
-pub fn rfpaeth(scanline: &[u8], last: &[u8], bpp: usize) -> Vec<u8> {
-    let mut buf = Vec::with_capacity(scanline.len());
+        let mut chunk = match Chunk::parse(&raw_data[index..], self.checksums) {
+            Ok(chunk) => chunk,
+            // Point at the chunk using the type bytes, if there are any.
+            Err(e) if self.strict => {
+                let ctype = raw_data
+                    .get(index + 4..index + 8)
+                    .map(String::from_utf8_lossy)
+                    .unwrap_or_default();
+                return Err(e.in_chunk(&ctype, index));
+            }
+            Err(_) => {
+                self.stopped = true;
+                return Ok(None);
+            }
+        };
+        chunk.offset = index;
+        let size = chunk.size;
+        if self.checksums == ChecksumPolicy::Tolerate && !chunk.crc_matches() {
+            self.crc_mismatches.push((chunk.ctype, chunk.offset));
+        }
+        match chunk.ctype {
+            ChunkType::IHDR => self.ihdr = chunk,
+            ChunkType::IDAT => self.idat.push(chunk),
+            ChunkType::PLTE => self.plte = Some(chunk),
+            // Kept with the ancillary chunks, where Png::is_cgbi looks.
+            ChunkType::CGBI if self.cgbi => self.ancillary_chunks.push(chunk),
+            // The image can't be decoded without knowing what an unknown
+            // critical chunk changes, see 5.4 of the spec.
+            ctype if ctype.is_critical() && ctype != ChunkType::IEND => {
+                return Err(DecoderError::InvalidChunk("unknown critical chunk.")
+                    .in_chunk(ctype.as_str(), chunk.offset));
+            }
+            _ => self.ancillary_chunks.push(chunk),
+        }
 
-    for (i, &byte) in scanline.iter().enumerate() {
-        let left = if i >= bpp { buf[i - bpp] } else { 0 };
-        let above = last[i];
-        let upper_left = if i >= bpp { last[i - bpp] } else { 0 };
-        buf.push(byte.wrapping_add(fpaeth(left, above, upper_left)));
+        Ok(Some(size))
     }
-
-    buf
-}
-
-pub fn fpaeth(left: u8, above: u8, upper_left: u8) -> u8 {
-    let (a, b, c) = (left as i16, above as i16, upper_left as i16);
-    let p = a + b - c;
-    let pa = p.abs_diff(a);
-    let pb = p.abs_diff(b);
-    let pc = p.abs_diff(c);
-    if pa <= pb && pa <= pc {
-        left
-    } else if pb <= pc {
-        above
-    } else {
-        upper_left
+    /// Builds the PngData from the chunks pushed so far.
+    ///
+    /// # Arguments
+    ///
+    /// * 'raw_data' - The whole file.
+    fn finish(self, raw_data: Vec<u8>) -> PngData {
+        PngData {
+            raw_data,
+            ihdr: self.ihdr,
+            plte: self.plte,
+            idat: self.idat,
+            ancillary_chunks: self.ancillary_chunks,
+            crc_mismatches: self.crc_mismatches,
+        }
     }
 }
 
@@ -474,29 +2216,132 @@ pub fn fpaeth(left: u8, above: u8, upper_left: u8) -> u8 {
 /// # Members
 ///
 /// * 'NotPngFile' - Pretty self-explainatory, used when the file given is not
-///         a valid PNG file. Called when the input file either lacks the .png
-///         extension, or does not have the PNG header.
+///   a valid PNG file. Called when the input file either lacks the .png
+///   extension, or does not have the PNG header.
 /// * 'IoError' - A wrapper for the std::io::Error type to be called when the
-///         decoder tries something that causes an io::Error.
+///   decoder tries something that causes an io::Error.
 /// * 'InvalidChunk' - Called when the chunk being parsed is not valid, either
-///         because the header is incorrect, or the CRC32 deos not match the
-///         data. Holds a &str for communicating why the chunk is invalid.
+///   because the header is incorrect, or the CRC32 deos not match the
+///   data. Holds a &str for communicating why the chunk is invalid.
 /// * 'InvalidColorType' - Used if the byte for color type in IHDR is not set
-///         to either of the valid types: 1, 2, 3, 4, or 6. Holds the invalid
-///         color type byte.
+///   to either of the valid types: 1, 2, 3, 4, or 6. Holds the invalid
+///   color type byte.
+/// * 'InvalidBitDepth' - Used if the bit depth in IHDR isn't allowed for the
+///   color type. Holds the invalid bit depth.
 /// * 'InvalidInterlace' - Used if the byte for the interlace is invalid (not
-///         0 or 1). Holds the invalid interlace byte.
+///   0 or 1). Holds the invalid interlace byte.
+/// * 'LimitExceeded' - Used if the image is larger than the Limits given in
+///   the DecodeOptions. Holds what was too large and its value.
+/// * 'InvalidDimensions' - Used if the width or height in IHDR is 0, which
+///   11.2.2 of the spec doesn't allow. Holds the width and height.
+/// * 'DimensionOverflow' - Used if the image data or decoded pixels of an
+///   image this size wouldn't fit in memory, e.g. 0x7FFFFFFF by
+///   0x7FFFFFFF, so it can't be decoded whatever the limits. Holds the
+///   width and height.
+/// * 'InvalidFilter' - Used if a scanline begins with a filter type byte
+///   that isn't one of the five from Chapter 6 of the spec. Holds the
+///   invalid filter type byte.
+/// * 'ZlibError' - A wrapper for errors raised while parsing the zlib stream
+///   made up of the IDAT chunks.
+/// * 'DeflateError' - A wrapper for errors raised while decompressing the
+///   image data.
+/// * 'Context' - Another DecoderError along with an ErrorContext describing
+///   where in the file it happened.
+/// * 'Cancelled' - Used when the callback given to Png::rgb_with_progress
+///   asks for the decode to stop.
 ///         
 #[derive(Debug)]
 pub enum DecoderError {
     NotPngFile,
+    #[cfg(feature = "std")]
     IoError(io::Error),
     InvalidChunk(&'static str),
     InvalidColorType(u8),
+    InvalidBitDepth(u8),
     InvalidInterlace(u8),
+    LimitExceeded(&'static str, usize),
+    InvalidDimensions(usize, usize),
+    DimensionOverflow(usize, usize),
+    InvalidFilter(u8),
+    ZlibError(ZlibError),
+    DeflateError(DeflateError),
+    Context(ErrorContext, Box<DecoderError>),
+    Cancelled,
     Unexplainable,
 }
 
+/// Describes where in a file an error happened, every field is optional as
+/// not every error knows every detail.
+///
+/// # Fields
+///
+/// * 'path' - The path of the file being decoded.
+/// * 'chunk' - The type of the chunk being decoded and its offset in the
+///   file.
+/// * 'bit' - The position in the compressed data that decompression stopped
+///   at, in bits.
+#[derive(Debug, Default, Clone)]
+pub struct ErrorContext {
+    #[cfg(feature = "std")]
+    pub path: Option<PathBuf>,
+    pub chunk: Option<(String, usize)>,
+    pub bit: Option<usize>,
+}
+
+impl DecoderError {
+    /// Gets the context attached to the error, if any.
+    ///
+    /// # Returns
+    ///
+    /// An option containing a reference to the ErrorContext.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            DecoderError::Context(context, _) => Some(context),
+            _ => None,
+        }
+    }
+    /// Gets the underlying error, without any context wrapped around it.
+    /// Useful for matching on what actually went wrong.
+    ///
+    /// # Returns
+    ///
+    /// A reference to the innermost DecoderError.
+    pub fn root(&self) -> &DecoderError {
+        match self {
+            DecoderError::Context(_, inner) => inner.root(),
+            other => other,
+        }
+    }
+    /// Gets mutable access to the error's context, wrapping the error in an
+    /// empty context first if it doesn't have one.
+    fn context_mut(&mut self) -> &mut ErrorContext {
+        if !matches!(self, DecoderError::Context(..)) {
+            let inner = mem::replace(self, DecoderError::Unexplainable);
+            *self = DecoderError::Context(ErrorContext::default(), Box::new(inner));
+        }
+        match self {
+            DecoderError::Context(context, _) => context,
+            _ => unreachable!(),
+        }
+    }
+    /// Attaches the path of the file being decoded to the error.
+    #[cfg(feature = "std")]
+    pub(crate) fn with_path(mut self, path: &Path) -> Self {
+        self.context_mut().path = Some(path.to_path_buf());
+        self
+    }
+    /// Attaches the type and offset of the chunk being decoded to the error.
+    pub(crate) fn in_chunk(mut self, ctype: &str, offset: usize) -> Self {
+        self.context_mut().chunk = Some((ctype.to_string(), offset));
+        self
+    }
+    /// Attaches the bit position decompression stopped at to the error.
+    pub(crate) fn at_bit(mut self, bit: usize) -> Self {
+        self.context_mut().bit = Some(bit);
+        self
+    }
+}
+
 // Defines how DecoderErrors are displayed.
 impl Display for DecoderError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -504,6 +2349,7 @@ impl Display for DecoderError {
             DecoderError::NotPngFile => {
                 write!(f, "Error: File is not a valid PNG file.")
             }
+            #[cfg(feature = "std")]
             DecoderError::IoError(e) => {
                 write!(f, "Error: The decoder caused an io::Error, '{e}'")
             }
@@ -517,9 +2363,67 @@ impl Display for DecoderError {
                     t
                 )
             }
+            DecoderError::InvalidBitDepth(d) => {
+                write!(
+                    f,
+                    "Error: Invalid bit depth {} for the color type, see PNG Specification 11.2.2.",
+                    d
+                )
+            }
             DecoderError::InvalidInterlace(i) => {
                 write!(f, "Error: Invalid interlace value {}, only 0 (none) or 1 (Adam7 interlace) are currently valid.", i)
             }
+            DecoderError::LimitExceeded(what, value) => {
+                write!(
+                    f,
+                    "Error: Image {} of {} is larger than the decoder's limit.",
+                    what, value
+                )
+            }
+            DecoderError::InvalidDimensions(width, height) => {
+                write!(
+                    f,
+                    "Error: Invalid image dimensions {}x{}, see PNG Specification 11.2.2, both must be at least 1.",
+                    width, height
+                )
+            }
+            DecoderError::DimensionOverflow(width, height) => {
+                write!(
+                    f,
+                    "Error: Image dimensions {}x{} are too large to address in memory.",
+                    width, height
+                )
+            }
+            DecoderError::InvalidFilter(t) => {
+                write!(
+                    f,
+                    "Error: Invalid filter type {}, see PNG Specification 6.2 for valid types.",
+                    t
+                )
+            }
+            DecoderError::ZlibError(e) => {
+                write!(f, "Error: Could not read the image data, '{e}'")
+            }
+            DecoderError::DeflateError(e) => {
+                write!(f, "Error: Could not decompress the image data, '{e}'")
+            }
+            DecoderError::Context(context, inner) => {
+                #[cfg(feature = "std")]
+                if let Some(path) = &context.path {
+                    write!(f, "{}: ", path.display())?;
+                }
+                if let Some((ctype, offset)) = &context.chunk {
+                    write!(f, "{} chunk at offset {:#x}: ", ctype, offset)?;
+                }
+                write!(f, "{inner}")?;
+                if let Some(bit) = context.bit {
+                    write!(f, " at bit {}", bit)?;
+                }
+                Ok(())
+            }
+            DecoderError::Cancelled => {
+                write!(f, "Error: The decode was cancelled.")
+            }
             DecoderError::Unexplainable => {
                 write!(f, "Error: Something unexpected happened :(")
             }
@@ -528,11 +2432,38 @@ impl Display for DecoderError {
 }
 
 // Allows for conversion from io::Error to DecoderError.
+#[cfg(feature = "std")]
 impl From<io::Error> for DecoderError {
     fn from(error: io::Error) -> Self {
         DecoderError::IoError(error)
     }
 }
 
-// Implements the Error interface for CliError.
-impl Error for DecoderError {}
+// Allows for conversion from ZlibError to DecoderError.
+impl From<ZlibError> for DecoderError {
+    fn from(error: ZlibError) -> Self {
+        DecoderError::ZlibError(error)
+    }
+}
+
+// Allows for conversion from DeflateError to DecoderError.
+impl From<DeflateError> for DecoderError {
+    fn from(error: DeflateError) -> Self {
+        DecoderError::DeflateError(error)
+    }
+}
+
+// Implements the Error interface for DecoderError. Context is transparent,
+// it is displayed as part of the wrapped error rather than in the chain.
+impl Error for DecoderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            DecoderError::IoError(e) => Some(e),
+            DecoderError::ZlibError(e) => Some(e),
+            DecoderError::DeflateError(e) => Some(e),
+            DecoderError::Context(_, inner) => inner.source(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,565 @@
+use core::ptr;
+#[cfg(feature = "http")]
+use std::io;
+#[cfg(feature = "std")]
+use std::{fs::File, io::Read, num::NonZeroUsize, path::Path, thread};
+
+#[cfg(feature = "async")]
+use futures_io::AsyncRead;
+
+#[cfg(feature = "std")]
+use super::stream;
+use super::{DecoderError, FilterStrategy, Png, Time};
+use crate::compression::backend::{Compressor, Decompressor, Native};
+use crate::compression::deflate::{CompressionLevel, DeflateOptions, Strategy};
+use crate::metadata::Metadata;
+
+/// Options controlling how a PNG file is decoded, built up with chained
+/// method calls starting from Png::decoder().
+///
+/// # Fields
+///
+/// * 'strict' - When true, any chunk that can't be parsed is returned as an
+///   error. When false, parsing stops at the first bad chunk and
+///   decoding carries on with the chunks read up to that point.
+/// * 'limits' - The largest image that will be decoded, see Limits.
+/// * 'checksums' - Whether the CRC32 of each chunk is verified, see
+///   ChecksumPolicy.
+/// * 'transformations' - Changes applied to the decoded pixels, see
+///   Transformations.
+/// * 'decompressor' - What inflates the image data and compressed text,
+///   see Decompressor. Defaults to Native.
+/// * 'cgbi' - When true, files in Apple's CgBI variant of PNG, from iOS
+///   app bundles, are decoded to the pixels they hold rather than
+///   refused for their unknown critical CgBI chunk. See Png::is_cgbi.
+/// * 'threads' - The most threads large images are decoded on. Inflating
+///   runs alongside reconstructing the scanlines, and the passes of an
+///   interlaced image are reconstructed in parallel. Defaults to the
+///   available parallelism of the machine with the std feature, and 1
+///   without it, as no threads can be spawned.
+/// * 'ignore_window' - When true, matches in the image data and compressed
+///   text may copy from as far back as deflate allows, whatever window
+///   the zlib header declares, for files from encoders that write too
+///   small a CINFO. Only decompressors that check the window, like
+///   Native, are affected, Flate2 never checks it.
+///
+/// # Examples
+///
+/// '''
+/// let png = Png::decoder()
+///     .strict(true)
+///     .limits(Limits::new().max_width(4096).max_height(4096))
+///     .transformations(Transformations::new().flip_vertical(true))
+///     .decode("./example.png")?;
+/// '''
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecodeOptions {
+    pub strict: bool,
+    pub limits: Limits,
+    pub checksums: ChecksumPolicy,
+    pub transformations: Transformations,
+    #[cfg_attr(feature = "serde", serde(skip, default = "native_decompressor"))]
+    pub decompressor: &'static dyn Decompressor,
+    pub cgbi: bool,
+    pub threads: usize,
+    pub ignore_window: bool,
+}
+
+impl DecodeOptions {
+    /// Creates a DecodeOptions with the default settings, the same ones used
+    /// by Png::from_path.
+    ///
+    /// # Returns
+    ///
+    /// A DecodeOptions with default values.
+    pub fn new() -> Self {
+        Self {
+            strict: false,
+            limits: Limits::new(),
+            checksums: ChecksumPolicy::Verify,
+            transformations: Transformations::new(),
+            decompressor: &Native,
+            cgbi: false,
+            #[cfg(feature = "std")]
+            threads: thread::available_parallelism()
+                .map(NonZeroUsize::get)
+                .unwrap_or(1),
+            #[cfg(not(feature = "std"))]
+            threads: 1,
+            ignore_window: false,
+        }
+    }
+    /// Sets whether chunks that can't be parsed are treated as errors.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+    /// Sets the largest image that will be decoded.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+    /// Sets whether chunk checksums are verified.
+    pub fn checksums(mut self, checksums: ChecksumPolicy) -> Self {
+        self.checksums = checksums;
+        self
+    }
+    /// Sets the transformations applied to the decoded pixels.
+    pub fn transformations(mut self, transformations: Transformations) -> Self {
+        self.transformations = transformations;
+        self
+    }
+    /// Sets what inflates the compressed data.
+    pub fn decompressor(mut self, decompressor: &'static dyn Decompressor) -> Self {
+        self.decompressor = decompressor;
+        self
+    }
+    /// Sets whether Apple's CgBI files are decoded.
+    pub fn cgbi(mut self, cgbi: bool) -> Self {
+        self.cgbi = cgbi;
+        self
+    }
+    /// Sets the most threads large images are decoded on.
+    ///
+    /// # Arguments
+    ///
+    /// * 'threads' - The number of threads to use, values below 1 are
+    ///   treated as 1.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+    /// Sets whether matches may reach back past the window the zlib header
+    /// declares.
+    pub fn ignore_window(mut self, ignore_window: bool) -> Self {
+        self.ignore_window = ignore_window;
+        self
+    }
+    /// Decodes a PNG file that has already been read into memory using
+    /// these options.
+    ///
+    /// # Arguments
+    ///
+    /// * 'bytes' - The entire PNG file as bytes.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the constructed Png or a DecoderError.
+    pub fn decode_bytes(&self, bytes: &[u8]) -> Result<Png, DecoderError> {
+        Png::parse_bytes(bytes, self)
+    }
+    /// Decodes the PNG file at the given path using these options. The file
+    /// is read on a second thread while the chunks already read are parsed.
+    ///
+    /// # Arguments
+    ///
+    /// * 'path' - The file path to the PNG file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the constructed Png or a DecoderError.
+    #[cfg(feature = "std")]
+    pub fn decode<P: AsRef<Path>>(&self, path: P) -> Result<Png, DecoderError> {
+        let path = path.as_ref();
+
+        File::open(path)
+            .map_err(DecoderError::from)
+            .and_then(|file| stream::decode_prefetched(file, self))
+            .map_err(|e| e.with_path(path))
+    }
+    /// Decodes a PNG file from anything that implements io::Read using these
+    /// options. The reader is read to the end before decoding starts.
+    ///
+    /// # Arguments
+    ///
+    /// * 'reader' - The source of the PNG file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the constructed Png or a DecoderError.
+    #[cfg(feature = "std")]
+    pub fn decode_reader<R: Read>(&self, reader: R) -> Result<Png, DecoderError> {
+        Png::parse(reader, self)
+    }
+    /// Fetches a PNG file over HTTP or HTTPS and decodes it using these
+    /// options, checking the signature and limits as the response arrives
+    /// so an oversized image is dropped before it's downloaded.
+    ///
+    /// # Arguments
+    ///
+    /// * 'url' - The http:// or https:// URL of the PNG file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the constructed Png or a DecoderError.
+    /// Failed requests, including error statuses, are DecoderError::Io.
+    #[cfg(feature = "http")]
+    pub fn decode_url(&self, url: &str) -> Result<Png, DecoderError> {
+        ureq::get(url)
+            .call()
+            .map_err(|e| DecoderError::from(io::Error::other(e)))
+            .and_then(|response| stream::decode_read(response.into_reader(), self))
+            .map_err(|e| e.with_path(Path::new(url)))
+    }
+    /// Decodes a PNG file from an AsyncRead using these options, checking the
+    /// signature and limits as the bytes arrive.
+    ///
+    /// # Arguments
+    ///
+    /// * 'reader' - The source of the PNG file.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the constructed Png or a DecoderError.
+    #[cfg(feature = "async")]
+    pub async fn decode_async_reader<R: AsyncRead + Unpin>(
+        &self,
+        reader: R,
+    ) -> Result<Png, DecoderError> {
+        stream::decode_async(reader, self).await
+    }
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The Decompressor deserialized DecodeOptions use, as a backend can't be
+/// serialized.
+#[cfg(feature = "serde")]
+fn native_decompressor() -> &'static dyn Decompressor {
+    &Native
+}
+
+/// The largest image the decoder will accept. The dimensions and number of
+/// pixels are checked against IHDR before any image data is decompressed,
+/// and the decompressed size as the data is inflated, so a small file that
+/// inflates to gigabytes is stopped early. The defaults allow the largest
+/// width and height the PNG specification does, but no more than 2^28
+/// pixels, and as many decompressed bytes as such an image at 16 bits per
+/// sample with alpha can hold, so untrusted files are safe to decode
+/// without setting any.
+///
+/// # Fields
+///
+/// * 'max_width' - The maximum width in pixels.
+/// * 'max_height' - The maximum height in pixels.
+/// * 'max_pixels' - The maximum width times height.
+/// * 'max_decompressed' - The most bytes any one zlib stream, the image
+///   data or the text of a compressed text chunk, may inflate to.
+///   Text chunks past it are left out, like other text that can't be
+///   decompressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Limits {
+    pub max_width: usize,
+    pub max_height: usize,
+    pub max_pixels: usize,
+    pub max_decompressed: usize,
+}
+
+/// The default Limits::max_pixels.
+const DEFAULT_MAX_PIXELS: usize = 1 << 28;
+
+impl Limits {
+    /// Creates Limits allowing any width and height the PNG specification
+    /// does, within the default number of pixels and decompressed bytes.
+    ///
+    /// # Returns
+    ///
+    /// Limits with default values.
+    pub fn new() -> Self {
+        Self {
+            max_width: 0x7fff_ffff,
+            max_height: 0x7fff_ffff,
+            max_pixels: DEFAULT_MAX_PIXELS,
+            // 8 bytes for each pixel, and at most one filter type byte for
+            // each, as every scanline of every Adam7 pass holds a pixel.
+            max_decompressed: DEFAULT_MAX_PIXELS * 9,
+        }
+    }
+    /// Sets the maximum width in pixels.
+    pub fn max_width(mut self, max_width: usize) -> Self {
+        self.max_width = max_width;
+        self
+    }
+    /// Sets the maximum height in pixels.
+    pub fn max_height(mut self, max_height: usize) -> Self {
+        self.max_height = max_height;
+        self
+    }
+    /// Sets the maximum width times height.
+    pub fn max_pixels(mut self, max_pixels: usize) -> Self {
+        self.max_pixels = max_pixels;
+        self
+    }
+    /// Sets the most bytes a zlib stream may inflate to.
+    pub fn max_decompressed(mut self, max_decompressed: usize) -> Self {
+        self.max_decompressed = max_decompressed;
+        self
+    }
+    /// Checks the dimensions of an image against these limits.
+    ///
+    /// # Arguments
+    ///
+    /// * 'dimensions' - The width and height of the image in pixels.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or a DecoderError::LimitExceeded naming the first
+    /// dimension that is too large, or the number of pixels.
+    pub fn check(&self, dimensions: (usize, usize)) -> Result<(), DecoderError> {
+        if dimensions.0 > self.max_width {
+            return Err(DecoderError::LimitExceeded("width", dimensions.0));
+        }
+        if dimensions.1 > self.max_height {
+            return Err(DecoderError::LimitExceeded("height", dimensions.1));
+        }
+        let pixels = dimensions.0.saturating_mul(dimensions.1);
+        if pixels > self.max_pixels {
+            return Err(DecoderError::LimitExceeded("pixels", pixels));
+        }
+
+        Ok(())
+    }
+    /// Checks how much of a zlib stream has been inflated so far against
+    /// max_decompressed.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or a DecoderError::LimitExceeded if len is past it.
+    pub(crate) fn check_decompressed(&self, len: usize) -> Result<(), DecoderError> {
+        match len > self.max_decompressed {
+            true => Err(DecoderError::LimitExceeded("decompressed bytes", len)),
+            false => Ok(()),
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether the checksum at the end of each chunk, and the adler32 at the end
+/// of the image data, are checked.
+///
+/// # Members
+///
+/// * 'Verify' - Chunks with a CRC32 that doesn't match their data are
+///   treated as invalid, as is image data that doesn't match its
+///   adler32.
+/// * 'Ignore' - Neither checksum is computed at all, which is faster and
+///   lets files with damaged checksums but intact data be read.
+/// * 'Tolerate' - Chunks with a CRC32 that doesn't match their data are
+///   kept, and listed in PngData::crc_mismatches, so a damaged file
+///   is read as far as it goes with a record of what was wrong. Image
+///   data that doesn't match its adler32 is still invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChecksumPolicy {
+    #[default]
+    Verify,
+    Ignore,
+    Tolerate,
+}
+
+/// Changes applied to the pixels returned by Png::rgb.
+///
+/// # Fields
+///
+/// * 'flip_vertical' - Return the scanlines from bottom to top, the order
+///   OpenGL and BMP expect.
+/// * 'bgr' - Swap the red and blue samples of each pixel, the order a lot of
+///   Windows APIs expect.
+/// * 'gamma' - Correct the samples from the gamma in the file's gAMA chunk
+///   to that of a typical display, 2.2, so images made with another
+///   gamma don't come out washed out or too dark. Files without gAMA
+///   are left as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transformations {
+    pub flip_vertical: bool,
+    pub bgr: bool,
+    pub gamma: bool,
+}
+
+impl Transformations {
+    /// Creates a Transformations that leaves the pixels unchanged.
+    ///
+    /// # Returns
+    ///
+    /// A Transformations with every transformation disabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Sets whether the scanlines are returned from bottom to top.
+    pub fn flip_vertical(mut self, flip_vertical: bool) -> Self {
+        self.flip_vertical = flip_vertical;
+        self
+    }
+    /// Sets whether the red and blue samples are swapped.
+    pub fn bgr(mut self, bgr: bool) -> Self {
+        self.bgr = bgr;
+        self
+    }
+    /// Sets whether samples are corrected for the gamma in the gAMA chunk.
+    pub fn gamma(mut self, gamma: bool) -> Self {
+        self.gamma = gamma;
+        self
+    }
+}
+
+/// Options controlling how an Image is encoded as a PNG file, passed to
+/// Image::encode_png.
+///
+/// Encoding is deterministic, the same Image and EncodeOptions always give
+/// byte-identical files on any platform. Nothing depends on hashing, the
+/// number of threads or the clock, so a tIME chunk is only written when a
+/// time is given.
+///
+/// # Fields
+///
+/// * 'filter' - How the filter applied to each scanline before compression
+///   is picked, see FilterStrategy. Defaults to
+///   FilterStrategy::Adaptive.
+/// * 'time' - The modification time written to a tIME chunk, or None to
+///   leave the chunk out. Defaults to None.
+/// * 'reproducible' - Writes Time::EPOCH in place of any time, so files
+///   built from the same pixels match no matter when they were built.
+///   Defaults to false.
+/// * 'reduce' - Stores the image with the smallest color type that holds
+///   its pixels exactly, see Image::analyze. Opaque RGBA is written as
+///   RGB, gray pixels as grayscale and images with 256 colors or fewer
+///   as indexed, with the bit depth as low as the palette allows.
+///   Defaults to true.
+/// * 'compressor' - What compresses the image data, see Compressor.
+///   Defaults to Native.
+/// * 'compression' - The level and strategy the compressor works at, see
+///   DeflateOptions, used for the image data and compressed text.
+///   Defaults to CompressionLevel::Default and Strategy::Default.
+/// * 'metadata' - Text, physical dimensions and EXIF data written to tEXt,
+///   zTXt, iTXt, pHYs and eXIf chunks, and other chunks written as
+///   they are, e.g. carried over from the file being converted with
+///   Png::metadata. Text with a keyword PNG can't hold, and critical
+///   chunks, are left out. Defaults to none.
+/// * 'threads' - The most threads scanlines are converted and filtered on,
+///   each taking a band of rows. Small images use fewer. Defaults to
+///   the available parallelism of the machine with the std feature,
+///   and 1 without it, as no threads can be spawned.
+#[derive(Debug, Clone)]
+pub struct EncodeOptions {
+    pub filter: FilterStrategy,
+    pub time: Option<Time>,
+    pub reproducible: bool,
+    pub reduce: bool,
+    pub compressor: &'static dyn Compressor,
+    pub compression: DeflateOptions,
+    pub metadata: Metadata,
+    pub threads: usize,
+}
+
+impl EncodeOptions {
+    /// Creates an EncodeOptions with the default settings.
+    ///
+    /// # Returns
+    ///
+    /// An EncodeOptions with default values.
+    pub fn new() -> Self {
+        Self {
+            filter: FilterStrategy::Adaptive,
+            time: None,
+            reproducible: false,
+            reduce: true,
+            compressor: &Native,
+            compression: DeflateOptions::new(),
+            metadata: Metadata::new(),
+            #[cfg(feature = "std")]
+            threads: thread::available_parallelism()
+                .map(NonZeroUsize::get)
+                .unwrap_or(1),
+            #[cfg(not(feature = "std"))]
+            threads: 1,
+        }
+    }
+    /// Sets how each scanline's filter is picked, either a FilterStrategy
+    /// or one of the Filters to apply to every scanline.
+    pub fn filter<F: Into<FilterStrategy>>(mut self, filter: F) -> Self {
+        self.filter = filter.into();
+        self
+    }
+    /// Sets the modification time written to a tIME chunk, or None to leave
+    /// the chunk out.
+    pub fn time(mut self, time: Option<Time>) -> Self {
+        self.time = time;
+        self
+    }
+    /// Sets whether any time is replaced with Time::EPOCH.
+    pub fn reproducible(mut self, reproducible: bool) -> Self {
+        self.reproducible = reproducible;
+        self
+    }
+    /// Sets whether the image is stored with the smallest color type that
+    /// holds it, or false to keep the color type of its PixelFormat.
+    pub fn reduce(mut self, reduce: bool) -> Self {
+        self.reduce = reduce;
+        self
+    }
+    /// Sets what compresses the image data.
+    pub fn compressor(mut self, compressor: &'static dyn Compressor) -> Self {
+        self.compressor = compressor;
+        self
+    }
+    /// Sets how hard the compressor works, see CompressionLevel.
+    pub fn level(mut self, level: CompressionLevel) -> Self {
+        self.compression.level = level;
+        self
+    }
+    /// Sets which matches the compressor looks for, see Strategy.
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.compression.strategy = strategy;
+        self
+    }
+    /// Sets the text, physical dimensions, EXIF data and other chunks written
+    /// with the image.
+    pub fn metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+    /// Sets the most threads scanlines are filtered on.
+    ///
+    /// # Arguments
+    ///
+    /// * 'threads' - The number of threads to use, values below 1 are
+    ///   treated as 1.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+}
+
+// Backends are compared by address, the same static is the same backend.
+impl PartialEq for EncodeOptions {
+    fn eq(&self, other: &Self) -> bool {
+        self.filter == other.filter
+            && self.time == other.time
+            && self.reproducible == other.reproducible
+            && self.reduce == other.reduce
+            && ptr::addr_eq(self.compressor, other.compressor)
+            && self.compression == other.compression
+            && self.metadata == other.metadata
+            && self.threads == other.threads
+    }
+}
+
+impl Eq for EncodeOptions {}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,168 @@
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+
+use super::DecoderError;
+use crate::color::Hex;
+
+/// The most entries a PLTE chunk can hold, see 11.2.3 of the spec.
+pub(crate) const MAX_PALETTE_ENTRIES: usize = 256;
+
+/// The colors an indexed image's pixels point into, read from the PLTE
+/// chunk with the alpha of each entry taken from tRNS. Changing a Palette
+/// and writing it back with Png::set_palette recolors the image without
+/// touching its IDAT data.
+///
+/// # Fields
+///
+/// * 'entries' - The red, green, blue and alpha of each entry, in index
+///   order. Entries that tRNS doesn't cover have an alpha of 255.
+///
+/// # Examples
+///
+/// '''
+/// let mut palette = png.palette().ok_or("not an indexed image")?;
+///
+/// palette.replace([255, 0, 0, 255], [0, 0, 255, 255]);
+/// png.set_palette(&palette)?;
+///
+/// fs::write("./blue.png", png.to_bytes())?;
+/// '''
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Palette {
+    pub entries: Vec<[u8; 4]>,
+}
+
+impl Palette {
+    /// Creates a Palette from a list of entries.
+    ///
+    /// # Arguments
+    ///
+    /// * 'entries' - The red, green, blue and alpha of each entry.
+    ///
+    /// # Returns
+    ///
+    /// A Palette holding entries.
+    pub fn new(entries: Vec<[u8; 4]>) -> Self {
+        Self { entries }
+    }
+    /// Reads a Palette from the data of a PLTE chunk and, if there is one, a
+    /// tRNS chunk.
+    ///
+    /// # Returns
+    ///
+    /// The Palette, or None if PLTE isn't a whole number of entries or tRNS
+    /// is longer than PLTE.
+    pub(crate) fn from_chunks(plte: &[u8], trns: Option<&[u8]>) -> Option<Self> {
+        let trns = trns.unwrap_or_default();
+
+        if !plte.len().is_multiple_of(3) || trns.len() > plte.len() / 3 {
+            return None;
+        }
+
+        let entries = plte
+            .chunks_exact(3)
+            .enumerate()
+            .map(|(i, rgb)| [rgb[0], rgb[1], rgb[2], trns.get(i).copied().unwrap_or(255)])
+            .collect();
+
+        Some(Self { entries })
+    }
+    /// Gets the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /// Whether there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Whether any entry is not fully opaque, in which case a tRNS chunk is
+    /// needed to store the palette.
+    pub fn has_alpha(&self) -> bool {
+        self.entries.iter().any(|entry| entry[3] != 255)
+    }
+    /// Changes every entry with a function, e.g. to shift the hue of a
+    /// sprite.
+    ///
+    /// # Arguments
+    ///
+    /// * 'f' - Given each entry, returns the entry to replace it with.
+    pub fn remap<F: FnMut([u8; 4]) -> [u8; 4]>(&mut self, mut f: F) {
+        for entry in self.entries.iter_mut() {
+            *entry = f(*entry);
+        }
+    }
+    /// Replaces every entry matching a color with another.
+    ///
+    /// # Arguments
+    ///
+    /// * 'from' - The color to look for.
+    /// * 'to' - The color to put in its place.
+    ///
+    /// # Returns
+    ///
+    /// The number of entries replaced.
+    pub fn replace(&mut self, from: [u8; 4], to: [u8; 4]) -> usize {
+        let mut replaced = 0;
+        for entry in self.entries.iter_mut().filter(|entry| **entry == from) {
+            *entry = to;
+            replaced += 1;
+        }
+        replaced
+    }
+    /// Rearranges the entries. The image data isn't changed, so every pixel
+    /// takes on the color that has moved to its index, which is how palette
+    /// swaps and color cycling work.
+    ///
+    /// # Arguments
+    ///
+    /// * 'order' - For each new index, the old index of the entry to put
+    ///   there. Old entries may be repeated or left out.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or a DecoderError if order refers to an entry that
+    /// doesn't exist, in which case the palette is left unchanged.
+    pub fn reorder(&mut self, order: &[usize]) -> Result<(), DecoderError> {
+        let entries = order
+            .iter()
+            .map(|&index| self.entries.get(index).copied())
+            .collect::<Option<Vec<_>>>()
+            .ok_or(DecoderError::InvalidChunk("palette index is out of range."))?;
+
+        self.entries = entries;
+        Ok(())
+    }
+    /// Gets the data of a PLTE chunk holding the colors of each entry.
+    pub(crate) fn plte_bytes(&self) -> Vec<u8> {
+        self.entries
+            .iter()
+            .flat_map(|entry| [entry[0], entry[1], entry[2]])
+            .collect()
+    }
+    /// Gets the data of a tRNS chunk holding the alpha of each entry, with
+    /// the fully opaque entries at the end left out as the spec allows.
+    pub(crate) fn trns_bytes(&self) -> Vec<u8> {
+        let len = self
+            .entries
+            .iter()
+            .rposition(|entry| entry[3] != 255)
+            .map_or(0, |last| last + 1);
+
+        self.entries[..len].iter().map(|entry| entry[3]).collect()
+    }
+}
+
+// Lists the entries in index order as hex, separated by spaces, see Hex.
+impl Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, &entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", Hex(entry))?;
+        }
+
+        Ok(())
+    }
+}
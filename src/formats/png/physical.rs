@@ -0,0 +1,99 @@
+/// The intended size or shape of an image's pixels, stored in the pHYs
+/// chunk, see 11.3.5.3 of the spec.
+///
+/// # Fields
+///
+/// * 'pixels_per_unit' - How many pixels fit in a unit horizontally and
+///   vertically.
+/// * 'unit' - What the unit is, see PhysicalUnit.
+///
+/// # Examples
+///
+/// '''
+/// if let Some(dpi) = png.physical_dimensions().and_then(|p| p.dpi()) {
+///     println!("{:.0}x{:.0} dpi", dpi.0, dpi.1);
+/// }
+/// '''
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicalDimensions {
+    pub pixels_per_unit: (u32, u32),
+    pub unit: PhysicalUnit,
+}
+
+/// The unit of a PhysicalDimensions.
+///
+/// # Members
+///
+/// * 'Unknown' - Only the ratio of the two is known, which gives the
+///   aspect ratio of the pixels.
+/// * 'Meter' - The pixels per meter, from which DPI can be worked out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PhysicalUnit {
+    Unknown,
+    Meter,
+}
+
+impl PhysicalDimensions {
+    /// Gets the pixels per inch horizontally and vertically.
+    ///
+    /// # Returns
+    ///
+    /// The horizontal and vertical DPI, or None if the unit is unknown.
+    pub fn dpi(&self) -> Option<(f64, f64)> {
+        // An inch is exactly 0.0254 meters.
+        let dpi = |pixels: u32| pixels as f64 * 0.0254;
+
+        match self.unit {
+            PhysicalUnit::Meter => Some((dpi(self.pixels_per_unit.0), dpi(self.pixels_per_unit.1))),
+            PhysicalUnit::Unknown => None,
+        }
+    }
+    /// Gets the width of each pixel divided by its height, e.g. 2.0 for
+    /// pixels meant to be shown twice as wide as they are tall.
+    ///
+    /// # Returns
+    ///
+    /// The aspect ratio, or None if either count of pixels is 0.
+    pub fn aspect_ratio(&self) -> Option<f64> {
+        let (x, y) = self.pixels_per_unit;
+        if x == 0 || y == 0 {
+            return None;
+        }
+
+        // Fewer pixels to the unit across means wider pixels.
+        Some(y as f64 / x as f64)
+    }
+    /// Reads the dimensions from the data of a pHYs chunk, or None if it
+    /// isn't 9 bytes long or the unit isn't one the spec defines.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let &[x0, x1, x2, x3, y0, y1, y2, y3, unit] = bytes else {
+            return None;
+        };
+        let unit = match unit {
+            0 => PhysicalUnit::Unknown,
+            1 => PhysicalUnit::Meter,
+            _ => return None,
+        };
+
+        Some(Self {
+            pixels_per_unit: (
+                u32::from_be_bytes([x0, x1, x2, x3]),
+                u32::from_be_bytes([y0, y1, y2, y3]),
+            ),
+            unit,
+        })
+    }
+    /// Gets the 9 bytes of a pHYs chunk holding these dimensions.
+    pub(crate) fn to_bytes(self) -> [u8; 9] {
+        let [x0, x1, x2, x3] = self.pixels_per_unit.0.to_be_bytes();
+        let [y0, y1, y2, y3] = self.pixels_per_unit.1.to_be_bytes();
+        let unit = match self.unit {
+            PhysicalUnit::Unknown => 0,
+            PhysicalUnit::Meter => 1,
+        };
+
+        [x0, x1, x2, x3, y0, y1, y2, y3, unit]
+    }
+}
@@ -0,0 +1,261 @@
+use alloc::vec::Vec;
+use core::iter;
+
+use super::{
+    cgbi, palette::MAX_PALETTE_ENTRIES, ChecksumPolicy, Chunk, ChunkType, ColorType, DecoderError,
+    Palette, Png, PngData, MAX_CHUNK_LEN, PNG_HEADER,
+};
+use crate::compression::{backend::Compressor, checksums::crc, deflate::DeflateOptions};
+
+/// The type of the chunk holding the alpha of each palette entry.
+const TRNS: [u8; 4] = *b"tRNS";
+
+impl Png {
+    /// Gets the palette from the PLTE chunk, with the alpha of each entry
+    /// from tRNS for indexed images.
+    ///
+    /// # Returns
+    ///
+    /// The Palette, or None if the file has no PLTE chunk or it's malformed.
+    pub fn palette(&self) -> Option<Palette> {
+        let plte = self.data.plte.as_ref()?;
+        let trns = match self.color_type {
+            ColorType::PalleteIndex => self.trns().map(|chunk| &chunk.data[..]),
+            _ => None,
+        };
+
+        Palette::from_chunks(&plte.data, trns)
+    }
+    /// Replaces the PLTE chunk, and the tRNS chunk of an indexed image, with
+    /// the contents of a Palette. The image data is left alone, so pixels
+    /// keep their indices and take on the new colors. Write the file back
+    /// out with Png::to_bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * 'palette' - The Palette to store.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or a DecoderError if the palette is empty, has more
+    /// than 256 entries, or the image is grayscale and can't have one.
+    pub fn set_palette(&mut self, palette: &Palette) -> Result<(), DecoderError> {
+        if palette.is_empty() || palette.len() > MAX_PALETTE_ENTRIES {
+            return Err(DecoderError::InvalidChunk(
+                "palette must have from 1 to 256 entries.",
+            ));
+        }
+        if matches!(
+            self.color_type,
+            ColorType::Grayscale | ColorType::GrayscaleAlpha
+        ) {
+            return Err(DecoderError::InvalidChunk(
+                "grayscale images can't have a palette.",
+            ));
+        }
+
+        // New chunks go just before the first IDAT, where both PLTE and tRNS
+        // are allowed, see 5.6 of the spec.
+        let first_idat = self.data.idat.first().map_or(usize::MAX, |ch| ch.offset);
+
+        let plte_offset = self
+            .data
+            .plte
+            .as_ref()
+            .map_or(first_idat.saturating_sub(2), |ch| ch.offset);
+        let mut plte = Chunk::with_data(ChunkType::PLTE, palette.plte_bytes());
+        plte.offset = plte_offset;
+        self.data.plte = Some(plte);
+
+        // tRNS only means per-entry alpha for indexed images.
+        if self.color_type != ColorType::PalleteIndex {
+            return Ok(());
+        }
+
+        let existing = self
+            .data
+            .ancillary_chunks
+            .iter()
+            .position(|ch| ch.ctype.as_bytes() == &TRNS);
+        let trns_offset = existing.map_or(first_idat.saturating_sub(1), |i| {
+            self.data.ancillary_chunks[i].offset
+        });
+        if let Some(i) = existing {
+            self.data.ancillary_chunks.remove(i);
+        }
+
+        if palette.has_alpha() {
+            let mut trns = Chunk::with_data(ChunkType::new(TRNS)?, palette.trns_bytes());
+            trns.offset = trns_offset;
+            self.data.ancillary_chunks.push(trns);
+        }
+
+        Ok(())
+    }
+    /// Gets the image data as it's stored, the data of every IDAT chunk one
+    /// after the other. This is a zlib stream, or raw deflate data for a
+    /// CgBI file, see Png::is_cgbi.
+    ///
+    /// # Returns
+    ///
+    /// The image data, still compressed.
+    pub fn idat_bytes(&self) -> Vec<u8> {
+        self.data
+            .idat
+            .iter()
+            .flat_map(|chunk| chunk.data.iter().copied())
+            .collect()
+    }
+    /// Inflates the image data and compresses it again with another
+    /// Compressor at the level and strategy given, replacing the IDAT chunks
+    /// with new ones holding the result. The filtered scanlines are kept as
+    /// they are, so only the compression changes, and the pixels are the
+    /// same. Write the file back out with Png::to_bytes.
+    ///
+    /// The new image data goes in as few IDAT chunks as it fits in, where
+    /// the first of the old ones was.
+    ///
+    /// # Arguments
+    ///
+    /// * 'compressor' - What compresses the image data, e.g. Flate2 with
+    ///   the zlib feature.
+    /// * 'options' - The level and strategy to compress with, see
+    ///   Compressor::compress_with.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or a DecoderError if the image data can't be
+    /// inflated, in which case the IDAT chunks are left alone.
+    ///
+    /// # Examples
+    ///
+    /// '''
+    /// let mut png = Png::from_path("./in.png")?;
+    /// let before = png.idat_bytes().len();
+    /// png.recompress(&Flate2, &DeflateOptions::new().level(CompressionLevel::Best))?;
+    /// if png.idat_bytes().len() < before {
+    ///     fs::write("./in.png", png.to_bytes())?;
+    /// }
+    /// '''
+    pub fn recompress(
+        &mut self,
+        compressor: &dyn Compressor,
+        options: &DeflateOptions,
+    ) -> Result<(), DecoderError> {
+        let mut zlib_bytes = Vec::new();
+        self.zlib_stream(&mut zlib_bytes);
+        let checksum = self.checksums != ChecksumPolicy::Ignore && !self.is_cgbi();
+
+        let mut data = Vec::new();
+        let mut push = |piece: &[u8]| {
+            data.extend_from_slice(piece);
+            Ok(self.limits.check_decompressed(data.len())?)
+        };
+        self.decompressor
+            .decompress(
+                &zlib_bytes,
+                checksum,
+                !self.ignore_window,
+                Vec::new(),
+                &mut push,
+            )
+            .map_err(|e| self.decompress_error(e))?;
+
+        let mut compressed = compressor.compress_with(&data, options);
+        // CgBI files hold the deflate data without its zlib wrapper.
+        if self.is_cgbi() {
+            compressed.truncate(compressed.len().saturating_sub(cgbi::ZLIB_TRAILER.len()));
+            compressed.drain(..cgbi::ZLIB_HEADER.len().min(compressed.len()));
+        }
+
+        // Sorting keeps chunks with the same offset in order, see
+        // PngData::write.
+        let offset = self.data.idat.first().map_or(0, |chunk| chunk.offset);
+        self.data.idat = compressed
+            .chunks(MAX_CHUNK_LEN)
+            .map(|piece| {
+                let mut chunk = Chunk::with_data(ChunkType::IDAT, piece.to_vec());
+                chunk.offset = offset;
+                chunk
+            })
+            .collect();
+
+        Ok(())
+    }
+    /// Writes the file back out from its chunks, in the order they were
+    /// read. Chunks changed since decoding, e.g. by Png::set_palette, are
+    /// written as they are now, and the IDAT chunks are copied unchanged
+    /// without decompressing them. Every CRC is recomputed, see
+    /// PngData::write.
+    ///
+    /// # Returns
+    ///
+    /// The PNG file as bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.data.write()
+    }
+    /// Finds the tRNS chunk, if there is one.
+    pub(super) fn trns(&self) -> Option<&Chunk> {
+        self.data
+            .ancillary_chunks
+            .iter()
+            .find(|chunk| chunk.ctype.as_bytes() == &TRNS)
+    }
+}
+
+impl PngData {
+    /// Reassembles a PNG file from the chunks, without decoding anything,
+    /// so chunks can be stripped, moved or added, e.g. a tEXt comment, and
+    /// the file written back out. Chunks are written in order of their
+    /// offsets, so set a new or moved chunk's offset to where it should go,
+    /// e.g. one less than the first IDAT's to go just before it. Lengths
+    /// and CRCs are recomputed from each chunk's type and data, and IEND is
+    /// added if it's missing.
+    ///
+    /// # Returns
+    ///
+    /// The PNG file as bytes.
+    ///
+    /// # Examples
+    ///
+    /// '''
+    /// let mut png = Png::from_path("./in.png")?;
+    /// // Strip the text chunks.
+    /// png.data.ancillary_chunks.retain(|chunk| &chunk.ctype.as_bytes()[1..] != b"TXt");
+    ///
+    /// fs::write("./out.png", png.data.write())?;
+    /// '''
+    pub fn write(&self) -> Vec<u8> {
+        let mut chunks = iter::once(&self.ihdr)
+            .chain(&self.plte)
+            .chain(&self.idat)
+            .chain(&self.ancillary_chunks)
+            .collect::<Vec<_>>();
+        chunks.sort_by_key(|chunk| chunk.offset);
+
+        let size = chunks
+            .iter()
+            .map(|chunk| chunk.data.len() + 12)
+            .sum::<usize>();
+        let mut file = Vec::with_capacity(PNG_HEADER.len() + size + 12);
+        file.extend_from_slice(&PNG_HEADER);
+
+        for chunk in &chunks {
+            write_chunk(&mut file, chunk.ctype.as_bytes(), &chunk.data);
+        }
+        // Files read without strict checking may have stopped short of IEND.
+        if chunks.last().map(|chunk| chunk.ctype) != Some(ChunkType::IEND) {
+            write_chunk(&mut file, ChunkType::IEND.as_bytes(), &[]);
+        }
+
+        file
+    }
+}
+
+/// Appends a chunk, with its length and CRC32, to the end of file.
+pub(crate) fn write_chunk(file: &mut Vec<u8>, ctype: &[u8; 4], data: &[u8]) {
+    file.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    file.extend_from_slice(ctype);
+    file.extend_from_slice(data);
+    file.extend_from_slice(&crc::hash(&[&ctype[..], data].concat()).to_be_bytes());
+}
@@ -0,0 +1,218 @@
+use alloc::vec::Vec;
+use core::mem;
+
+use super::{cgbi, filter, ChecksumPolicy, DecoderError, Filters, Interlace, Png, RgbaRows};
+use crate::compression::{checksums::Adler32, inflate::DeflateError, zlib::ZlibStream};
+
+/// An iterator over the rows of a Png as RGBA, from Png::rows. The image
+/// data is inflated a deflate block at a time as rows are asked for, so
+/// only the window deflate copies from, the block being decoded and two
+/// scanlines are held at once. Rows come in the PixelFormat from
+/// Png::rgba_format, with alpha from tRNS, and Transformations aren't
+/// applied, like Png::rgba_rows.
+///
+/// Inflating is always done by chameleon's own inflater, as a Decompressor
+/// hands its output on rather than being asked for it. Interlaced images
+/// are decoded whole before the first row, as every pass is needed for it.
+///
+/// The checksum of the image data is checked after the last row, so an
+/// image with a bad adler32 gives every row and then an error.
+///
+/// # Examples
+///
+/// '''
+/// for row in png.rows()? {
+///     let row = row?;
+///     // ...
+/// }
+/// '''
+pub struct Rows<'a> {
+    png: &'a Png,
+    zlib: ZlibStream,
+    adler: Option<Adler32>,
+    rgba: RgbaRows<'a>,
+    // Inflated data not yet reconstructed, starting at offset.
+    data: Vec<u8>,
+    offset: usize,
+    // The bytes inflated so far, checked against Limits::max_decompressed.
+    inflated: usize,
+    stride: usize,
+    bpp: usize,
+    row: usize,
+    // The reconstructed scanline above, empty for the first one.
+    last: Vec<u8>,
+    current: Vec<u8>,
+    // The last scanline put back in order, for CgBI files only.
+    restored: Vec<u8>,
+    // Every row of an interlaced image, decoded up front.
+    whole: Option<Vec<u8>>,
+    done: bool,
+}
+
+impl<'a> Rows<'a> {
+    /// Checks the image data can be decoded and reads its zlib header.
+    pub(super) fn new(png: &'a Png) -> Result<Self, DecoderError> {
+        png.check_limits()?;
+        if png.data.idat.is_empty() {
+            return Err(DecoderError::InvalidChunk(
+                "no IDAT chunks, the image data is missing or damaged.",
+            ));
+        }
+
+        let whole = match png.interlace {
+            Interlace::Adam7 => {
+                let mut pixels = Vec::new();
+                png.rgba_rows(&mut |_, row| {
+                    pixels.extend_from_slice(row);
+                    Ok(())
+                })?;
+                Some(pixels)
+            }
+            Interlace::None => None,
+        };
+
+        let mut zlib_bytes = Vec::new();
+        png.zlib_stream(&mut zlib_bytes);
+        let mut zlib = ZlibStream::build(&zlib_bytes).map_err(|e| png.in_idat(e.into(), 0))?;
+        if png.ignore_window {
+            zlib.ignore_window();
+        }
+
+        Ok(Self {
+            png,
+            zlib,
+            adler: (png.checksums != ChecksumPolicy::Ignore && !png.is_cgbi()).then(Adler32::new),
+            rgba: RgbaRows::new(png, png.bit_depth == 16)?,
+            data: Vec::new(),
+            offset: 0,
+            inflated: 0,
+            stride: png.stride(png.dimensions.0),
+            bpp: png.color_type.bytes_per_pixel(png.bit_depth),
+            row: 0,
+            last: Vec::new(),
+            current: Vec::new(),
+            restored: Vec::new(),
+            whole,
+            done: false,
+        })
+    }
+    /// Inflates the next block onto the end of data.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either whether there was anything left to
+    /// inflate, or a DecoderError pointing at where inflating stopped.
+    fn inflate(&mut self) -> Result<bool, DecoderError> {
+        let start = self.data.len();
+        let more = match self.zlib.deflate.inflate_next(&mut self.data) {
+            Ok(more) => more,
+            Err(e) => return Err(self.located(e)),
+        };
+        if let Some(adler) = &mut self.adler {
+            adler.update(&self.data[start..]);
+        }
+        self.inflated = self.inflated.saturating_add(self.data.len() - start);
+        self.png.limits.check_decompressed(self.inflated)?;
+
+        Ok(more)
+    }
+    /// Points an error at the IDAT chunk and bit inflating stopped on.
+    fn located(&self, error: DeflateError) -> DecoderError {
+        let mut bit = self.zlib.header.end_idx * 8 + self.zlib.deflate.bitstream.position();
+        if self.png.is_cgbi() {
+            bit -= cgbi::HEADER_BITS;
+        }
+
+        self.png.in_idat(error.into(), bit / 8).at_bit(bit)
+    }
+    /// Reconstructs and converts the next row.
+    fn next_row(&mut self) -> Result<Vec<u8>, DecoderError> {
+        let len = self.stride + 1;
+
+        while self.data.len() - self.offset < len {
+            // Move what's left of the last piece to the front, so data only
+            // grows by a block's worth at a time.
+            self.data.drain(..self.offset);
+            self.offset = 0;
+            if !self.inflate()? {
+                return Err(DecoderError::InvalidChunk(
+                    "the image data ends before the last scanline.",
+                ));
+            }
+        }
+
+        let scanline = &self.data[self.offset..self.offset + len];
+        let filter = Filters::try_from(scanline[0])?;
+        self.current.clear();
+        filter::reconstruct(
+            filter,
+            &scanline[1..],
+            &self.last,
+            self.bpp,
+            &mut self.current,
+        );
+        self.offset += len;
+        self.row += 1;
+        mem::swap(&mut self.last, &mut self.current);
+
+        // last stays as stored, as the next scanline is filtered against it.
+        if !self.png.is_cgbi() {
+            return Ok(self.rgba.convert(&self.last)?.to_vec());
+        }
+        let png = self.png;
+        cgbi::restore(
+            &self.last,
+            png.color_type,
+            png.bit_depth,
+            &mut self.restored,
+        );
+        Ok(self.rgba.convert(&self.restored)?.to_vec())
+    }
+    /// Inflates the rest of the data after the last row, so its checksum
+    /// can be checked.
+    fn finish(&mut self) -> Result<(), DecoderError> {
+        if self.adler.is_none() {
+            return Ok(());
+        }
+        while self.inflate()? {
+            self.data.clear();
+        }
+
+        match &self.adler {
+            Some(adler) => self.zlib.check(adler.finish()).map_err(|e| self.located(e)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Iterator for Rows<'_> {
+    type Item = Result<Vec<u8>, DecoderError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let height = self.png.dimensions.1;
+        if let Some(whole) = &self.whole {
+            if self.row == height {
+                return None;
+            }
+            let row_len = whole.len() / height;
+            let row = whole[self.row * row_len..(self.row + 1) * row_len].to_vec();
+            self.row += 1;
+            return Some(Ok(row));
+        }
+
+        let result = match self.row < height {
+            true => self.next_row(),
+            false => {
+                self.done = true;
+                return self.finish().err().map(Err);
+            }
+        };
+        self.done = result.is_err();
+
+        Some(result)
+    }
+}
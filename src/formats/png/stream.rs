@@ -0,0 +1,255 @@
+#[cfg(feature = "async")]
+use core::{future, pin::Pin};
+
+#[cfg(feature = "std")]
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "async")]
+use futures_io::AsyncRead;
+#[cfg(feature = "std")]
+use std::{
+    io::{self, ErrorKind, Read},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use super::{DecodeOptions, DecoderError, Png, PngDataBuilder, PNG_HEADER};
+
+/// The size of each of the two buffers decode_prefetched reads into.
+#[cfg(feature = "std")]
+const PREFETCH_SIZE: usize = 64 * 1024;
+
+/// A decoder that is fed a PNG file a piece at a time, as the bytes arrive
+/// from a socket or an upload. The signature and the IHDR limits are checked
+/// as soon as enough bytes have been pushed, so an oversized or invalid file
+/// is rejected without waiting for the rest of it. Each chunk is parsed and
+/// its CRC32 checked as soon as it's whole, so little is left for finish.
+///
+/// # Fields
+///
+/// * 'options' - The DecodeOptions used to decode the file.
+/// * 'buffer' - The bytes pushed so far.
+/// * 'checked' - How many bytes at the start of buffer have already been
+///   checked, either the signature or whole chunks.
+/// * 'chunks' - The chunks parsed so far.
+///
+/// # Examples
+///
+/// '''
+/// let mut decoder = StreamDecoder::new(Png::decoder());
+///
+/// while let Some(bytes) = upload.next_piece()? {
+///     decoder.push(&bytes)?;
+/// }
+///
+/// let png = decoder.finish()?;
+/// '''
+#[derive(Debug, Clone)]
+pub struct StreamDecoder {
+    options: DecodeOptions,
+    buffer: Vec<u8>,
+    checked: usize,
+    chunks: PngDataBuilder,
+}
+
+impl StreamDecoder {
+    /// Creates a StreamDecoder that hasn't been given any bytes yet.
+    ///
+    /// # Arguments
+    ///
+    /// * 'options' - The DecodeOptions to decode the file with.
+    ///
+    /// # Returns
+    ///
+    /// An empty StreamDecoder.
+    pub fn new(options: DecodeOptions) -> Self {
+        Self {
+            chunks: PngDataBuilder::new(&options),
+            options,
+            buffer: Vec::new(),
+            checked: 0,
+        }
+    }
+    /// Appends the next piece of the file, checking anything that has become
+    /// complete.
+    ///
+    /// # Arguments
+    ///
+    /// * 'bytes' - The bytes following those already pushed.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or a DecoderError if the file is already known to be
+    /// invalid or too large, or a chunk is bad and parsing is strict.
+    pub fn push(&mut self, bytes: &[u8]) -> Result<(), DecoderError> {
+        self.buffer.extend_from_slice(bytes);
+
+        if self.checked == 0 {
+            if self.buffer.len() < 8 {
+                return Ok(());
+            }
+            if self.buffer[0..8] != PNG_HEADER {
+                return Err(DecoderError::NotPngFile);
+            }
+            self.checked = 8;
+        }
+
+        // Skip over each chunk that has fully arrived. IHDR is always first,
+        // so the limits are checked at the first complete chunk.
+        while let Some(header) = self.buffer.get(self.checked..self.checked + 8) {
+            let length = header[0..4]
+                .iter()
+                .fold(0usize, |acc, byte| (acc << 8) | *byte as usize);
+            let end = self.checked.saturating_add(length).saturating_add(12);
+
+            if end > self.buffer.len() {
+                break;
+            }
+            if self.checked == 8 && &header[4..8] == b"IHDR" && length >= 8 {
+                let ihdr = &self.buffer[self.checked + 8..];
+                self.options.limits.check(Png::ihdr_dimensions(ihdr))?;
+            }
+            self.chunks.push(&self.buffer, self.checked)?;
+
+            self.checked = end;
+        }
+
+        Ok(())
+    }
+    /// Decodes the file once every byte of it has been pushed.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the constructed Png or a DecoderError.
+    pub fn finish(mut self) -> Result<Png, DecoderError> {
+        if self.checked == 0 {
+            return Err(DecoderError::NotPngFile);
+        }
+
+        // Whatever is left is a chunk that never arrived whole, parsed the
+        // same way as if the file had been read all at once.
+        let mut index = self.checked;
+        while index < self.buffer.len() {
+            match self.chunks.push(&self.buffer, index)? {
+                Some(size) => index += size,
+                None => break,
+            }
+        }
+
+        Png::from_data(self.chunks.finish(self.buffer), &self.options)
+    }
+}
+
+/// Reads a PNG file from a Read into a StreamDecoder a piece at a time and
+/// decodes it, so a bad or oversized file is given up on early.
+#[cfg(feature = "http")]
+pub(crate) fn decode_read<R: Read>(
+    mut reader: R,
+    options: &DecodeOptions,
+) -> Result<Png, DecoderError> {
+    let mut decoder = StreamDecoder::new(options.clone());
+    let mut buffer = [0u8; 8192];
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        decoder.push(&buffer[..read])?;
+    }
+
+    decoder.finish()
+}
+
+/// Reads a PNG file from a Read into a StreamDecoder, reading on a thread of
+/// its own into two buffers in turn while the chunks in the other are
+/// parsed, so reading and parsing overlap rather than one waiting on the
+/// other.
+#[cfg(feature = "std")]
+pub(crate) fn decode_prefetched<R: Read + Send>(
+    mut reader: R,
+    options: &DecodeOptions,
+) -> Result<Png, DecoderError> {
+    let mut decoder = StreamDecoder::new(options.clone());
+    // Read buffers go one way and, once parsed, come back empty the other.
+    let (full_sender, full) = mpsc::sync_channel::<io::Result<Vec<u8>>>(1);
+    let (empty, empty_receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            let mut buffer = vec![0; PREFETCH_SIZE];
+            loop {
+                let read = match reader.read(&mut buffer) {
+                    Ok(0) => return,
+                    Ok(read) => read,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        let _ = full_sender.send(Err(e));
+                        return;
+                    }
+                };
+                buffer.truncate(read);
+
+                // Either fails once parsing has stopped early.
+                if full_sender.send(Ok(buffer)).is_err() {
+                    return;
+                }
+                buffer = match empty_receiver.recv() {
+                    Ok(buffer) => buffer,
+                    Err(_) => return,
+                };
+                buffer.resize(PREFETCH_SIZE, 0);
+            }
+        });
+
+        // The second buffer, read into while the first is parsed.
+        let _ = empty.send(Vec::new());
+        parse_prefetched(&mut decoder, full, empty)
+    })?;
+
+    decoder.finish()
+}
+
+/// Pushes each buffer the reader fills into the decoder and hands it back.
+/// Taking the ends of both channels means they're dropped on an error,
+/// which stops the reader.
+#[cfg(feature = "std")]
+fn parse_prefetched(
+    decoder: &mut StreamDecoder,
+    full: Receiver<io::Result<Vec<u8>>>,
+    empty: Sender<Vec<u8>>,
+) -> Result<(), DecoderError> {
+    for buffer in full {
+        let buffer = buffer?;
+        decoder.push(&buffer)?;
+        let _ = empty.send(buffer);
+    }
+
+    Ok(())
+}
+
+/// Reads a PNG file from an AsyncRead into a StreamDecoder and decodes it,
+/// yielding to the executor whenever the reader has nothing to give.
+#[cfg(feature = "async")]
+pub(crate) async fn decode_async<R: AsyncRead + Unpin>(
+    mut reader: R,
+    options: &DecodeOptions,
+) -> Result<Png, DecoderError> {
+    let mut decoder = StreamDecoder::new(options.clone());
+    let mut buffer = [0u8; 8192];
+
+    loop {
+        let read = future::poll_fn(|cx| Pin::new(&mut reader).poll_read(cx, &mut buffer)).await?;
+
+        if read == 0 {
+            break;
+        }
+
+        decoder.push(&buffer[..read])?;
+    }
+
+    decoder.finish()
+}
@@ -0,0 +1,204 @@
+use alloc::{string::String, vec::Vec};
+use core::str;
+
+use super::Png;
+use crate::{
+    compression::{backend::Compressor, deflate::DeflateOptions, inflate::DeflateError},
+    error::Error,
+};
+
+/// The most bytes a compressed text chunk may inflate to, so a few bytes of
+/// zTXt or iTXt can't be made to fill memory.
+const MAX_TEXT_LEN: usize = 8 * 1024 * 1024;
+
+/// A keyword and its text, from a tEXt, zTXt or iTXt chunk, see 11.3.4 of
+/// the spec. Text from tEXt and zTXt is Latin-1, and is converted to UTF-8
+/// here.
+///
+/// # Fields
+///
+/// * 'keyword' - What the text is, e.g. Title, Author or Comment.
+/// * 'text' - The text itself, decompressed if it was compressed.
+/// * 'compressed' - Whether the text was compressed, as it always is in zTXt
+///   and may be in iTXt.
+/// * 'language' - The language of the text as an RFC 3066 tag, e.g. en-GB.
+///   Only iTXt has one, and it may be empty.
+/// * 'translated_keyword' - The keyword in that language. Only iTXt has one,
+///   and it may be empty.
+///
+/// # Examples
+///
+/// '''
+/// for chunk in png.text() {
+///     println!("{}: {}", chunk.keyword, chunk.text);
+/// }
+/// '''
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextChunk {
+    pub keyword: String,
+    pub text: String,
+    pub compressed: bool,
+    pub language: Option<String>,
+    pub translated_keyword: Option<String>,
+}
+
+impl TextChunk {
+    /// Reads a TextChunk from the type and data of a chunk, or None if it
+    /// isn't a text chunk, is missing a field, or its text can't be
+    /// decompressed with the Png's decompressor, within its limits, or isn't
+    /// valid UTF-8.
+    pub(crate) fn from_chunk(ctype: &[u8; 4], data: &[u8], png: &Png) -> Option<Self> {
+        // Every text chunk starts with a Latin-1 keyword ended by a null.
+        let (keyword, rest) = split_null(data)?;
+        let keyword = latin1(keyword);
+
+        match ctype {
+            b"tEXt" => Some(Self {
+                keyword,
+                text: latin1(rest),
+                compressed: false,
+                language: None,
+                translated_keyword: None,
+            }),
+            b"zTXt" => {
+                // Deflate is the only compression method, method 0.
+                let (&0, compressed) = rest.split_first()? else {
+                    return None;
+                };
+
+                Some(Self {
+                    keyword,
+                    text: latin1(&inflate(compressed, png)?),
+                    compressed: true,
+                    language: None,
+                    translated_keyword: None,
+                })
+            }
+            b"iTXt" => {
+                let (&[flag, method], rest) = rest.split_first_chunk()?;
+                let (language, rest) = split_null(rest)?;
+                let (translated_keyword, text) = split_null(rest)?;
+                let text = match (flag, method) {
+                    (0, _) => String::from(str::from_utf8(text).ok()?),
+                    (1, 0) => String::from_utf8(inflate(text, png)?).ok()?,
+                    _ => return None,
+                };
+
+                Some(Self {
+                    keyword,
+                    text,
+                    compressed: flag == 1,
+                    language: Some(String::from(str::from_utf8(language).ok()?)),
+                    translated_keyword: Some(String::from(
+                        str::from_utf8(translated_keyword).ok()?,
+                    )),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl TextChunk {
+    /// Gets the type and data of the chunk to store this text in. Latin-1
+    /// text without a language is written as tEXt, or zTXt if compressed,
+    /// and anything else as iTXt.
+    ///
+    /// # Arguments
+    ///
+    /// * 'compressor' - What compresses the text, if compressed.
+    /// * 'options' - The level and strategy to compress it with.
+    ///
+    /// # Returns
+    ///
+    /// The chunk type and data, or None if the keyword isn't 1 to 79
+    /// Latin-1 characters, as 11.3.4.2 of the spec requires.
+    pub(crate) fn to_chunk(
+        &self,
+        compressor: &dyn Compressor,
+        options: &DeflateOptions,
+    ) -> Option<([u8; 4], Vec<u8>)> {
+        let mut data = to_latin1(&self.keyword).filter(|k| (1..=79).contains(&k.len()))?;
+        data.push(0);
+
+        match (
+            &self.language,
+            &self.translated_keyword,
+            to_latin1(&self.text),
+        ) {
+            (None, None, Some(text)) if self.compressed => {
+                // Compression method 0, deflate.
+                data.push(0);
+                data.extend_from_slice(&compressor.compress_with(&text, options));
+                Some((*b"zTXt", data))
+            }
+            (None, None, Some(text)) => {
+                data.extend_from_slice(&text);
+                Some((*b"tEXt", data))
+            }
+            (language, translated_keyword, _) => {
+                data.extend_from_slice(&[self.compressed as u8, 0]);
+                data.extend_from_slice(language.as_deref().unwrap_or_default().as_bytes());
+                data.push(0);
+                data.extend_from_slice(
+                    translated_keyword.as_deref().unwrap_or_default().as_bytes(),
+                );
+                data.push(0);
+                match self.compressed {
+                    true => data.extend_from_slice(
+                        &compressor.compress_with(self.text.as_bytes(), options),
+                    ),
+                    false => data.extend_from_slice(self.text.as_bytes()),
+                }
+                Some((*b"iTXt", data))
+            }
+        }
+    }
+}
+
+/// Splits bytes at the first null, leaving it out of both halves.
+fn split_null(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let end = bytes.iter().position(|&byte| byte == 0)?;
+
+    Some((&bytes[..end], &bytes[end + 1..]))
+}
+
+/// Converts Latin-1 to a String, each byte being the code point of the same
+/// value.
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| byte as char).collect()
+}
+
+/// Converts a str to Latin-1, or None if it has a character Latin-1 can't
+/// hold.
+fn to_latin1(text: &str) -> Option<Vec<u8>> {
+    text.chars().map(|c| u8::try_from(c).ok()).collect()
+}
+
+/// Decompresses a zlib stream, or None if it's damaged or inflates to more
+/// than MAX_TEXT_LEN bytes or the Png's Limits::max_decompressed.
+fn inflate(bytes: &[u8], png: &Png) -> Option<Vec<u8>> {
+    let mut text = Vec::new();
+    let limit = MAX_TEXT_LEN.min(png.limits.max_decompressed);
+
+    png.decompressor
+        .decompress(
+            bytes,
+            true,
+            !png.ignore_window,
+            Vec::new(),
+            &mut |piece: &[u8]| {
+                if text.len() + piece.len() > limit {
+                    return Err(Error::Deflate(DeflateError::DecompressionError(
+                        "text chunk inflates past the limit.",
+                    )));
+                }
+                text.extend_from_slice(piece);
+                Ok(())
+            },
+        )
+        .ok()?;
+
+    Some(text)
+}
@@ -0,0 +1,133 @@
+use core::fmt::{self, Display};
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The time an image was last modified, stored in the tIME chunk in UTC, see
+/// 11.3.6.1 of the spec.
+///
+/// # Fields
+///
+/// * 'year' - The full year, e.g. 2024 and not 24.
+/// * 'month' - The month, from 1 to 12.
+/// * 'day' - The day of the month, from 1 to 31.
+/// * 'hour' - The hour, from 0 to 23.
+/// * 'minute' - The minute, from 0 to 59.
+/// * 'second' - The second, from 0 to 60 to allow for leap seconds.
+///
+/// # Examples
+///
+/// '''
+/// let options = EncodeOptions::new().time(Some(Time::now()));
+/// '''
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Time {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl Time {
+    /// The start of the Unix epoch, 1970-01-01 00:00:00. Reproducible
+    /// encodes write this in place of any other time.
+    pub const EPOCH: Time = Time {
+        year: 1970,
+        month: 1,
+        day: 1,
+        hour: 0,
+        minute: 0,
+        second: 0,
+    };
+
+    /// Gets the current time from the system clock.
+    ///
+    /// # Returns
+    ///
+    /// The current time in UTC, or Time::EPOCH if the clock is set before it.
+    #[cfg(feature = "std")]
+    pub fn now() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+
+        Self::from_unix(seconds)
+    }
+    /// Converts a count of seconds since the Unix epoch to a calendar date
+    /// and time, using the days from civil algorithm by Howard Hinnant.
+    #[cfg(feature = "std")]
+    fn from_unix(seconds: u64) -> Self {
+        let days = seconds / 86_400;
+        let rest = seconds % 86_400;
+
+        // Shift the epoch to 0000-03-01, so leap days fall at the end of
+        // each year, then split into 400 year eras.
+        let z = days + 719_468;
+        let era = z / 146_097;
+        let day_of_era = z % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + (month <= 2) as u64;
+
+        Self {
+            year: year.min(u16::MAX as u64) as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (rest / 3_600) as u8,
+            minute: (rest % 3_600 / 60) as u8,
+            second: (rest % 60) as u8,
+        }
+    }
+    /// Reads a time from the data of a tIME chunk, or None if it isn't 7
+    /// bytes long.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let &[high, low, month, day, hour, minute, second] = bytes else {
+            return None;
+        };
+
+        Some(Self {
+            year: u16::from_be_bytes([high, low]),
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+    /// Gets the 7 bytes of a tIME chunk holding this time.
+    pub(crate) fn to_bytes(self) -> [u8; 7] {
+        let [high, low] = self.year.to_be_bytes();
+
+        [
+            high,
+            low,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+        ]
+    }
+}
+
+// Displayed in ISO 8601 order, e.g. 2024-01-31 23:59:59, as stamped onto
+// images by the command line tool's --label.
+impl Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
@@ -0,0 +1,208 @@
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+
+use super::{ChunkType, ColorType, Png};
+
+/// Chunks that come before PLTE and the image data, see 5.6 of the spec.
+const BEFORE_PLTE: [&[u8; 4]; 5] = [b"cHRM", b"gAMA", b"iCCP", b"sBIT", b"sRGB"];
+/// Chunks that come after PLTE, if there is one, and before the image data.
+const AFTER_PLTE: [&[u8; 4]; 3] = [b"bKGD", b"hIST", b"tRNS"];
+/// Other chunks that come before the image data.
+const BEFORE_IDAT: [&[u8; 4]; 3] = [b"pHYs", b"sPLT", b"eXIf"];
+/// Chunks a file may only have one of.
+const ONCE: [&[u8; 4]; 14] = [
+    b"IHDR", b"PLTE", b"IEND", b"cHRM", b"gAMA", b"iCCP", b"sBIT", b"sRGB", b"bKGD", b"hIST",
+    b"tRNS", b"pHYs", b"tIME", b"eXIf",
+];
+
+/// A way a file breaks the spec's rules on which chunks it has and where,
+/// found by Png::validate. The decoder reads files with most of these
+/// anyway, as other decoders do.
+///
+/// # Members
+///
+/// * 'IhdrNotFirst' - The first chunk isn't IHDR.
+/// * 'Duplicate' - A chunk only allowed once appears again, at offset.
+/// * 'Misplaced' - A chunk at offset is out of order, with the rule it
+///   breaks.
+/// * 'SplitIdat' - The IDAT chunk at offset doesn't follow straight on from
+///   the ones before it.
+/// * 'MissingIdat' - There's no image data.
+/// * 'MissingPlte' - An indexed image has no palette.
+/// * 'PlteNotAllowed' - A grayscale image, of the given ColorType, has a
+///   palette.
+/// * 'MissingIend' - The file ends without an IEND chunk.
+/// * 'Unreadable' - The chunk at offset is cut short or its header is
+///   malformed, so nothing after it could be checked.
+/// * 'TrailingData' - There are len bytes after IEND, starting at offset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    IhdrNotFirst,
+    Duplicate {
+        ctype: ChunkType,
+        offset: usize,
+    },
+    Misplaced {
+        ctype: ChunkType,
+        offset: usize,
+        rule: &'static str,
+    },
+    SplitIdat {
+        offset: usize,
+    },
+    MissingIdat,
+    MissingPlte,
+    PlteNotAllowed(ColorType),
+    MissingIend,
+    Unreadable {
+        offset: usize,
+    },
+    TrailingData {
+        offset: usize,
+        len: usize,
+    },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::IhdrNotFirst => write!(f, "IHDR isn't the first chunk."),
+            Violation::Duplicate { ctype, offset } => write!(
+                f,
+                "{ctype} chunk at byte {offset} repeats a chunk only allowed once."
+            ),
+            Violation::Misplaced {
+                ctype,
+                offset,
+                rule,
+            } => write!(f, "{ctype} chunk at byte {offset} is out of order, {rule}"),
+            Violation::SplitIdat { offset } => write!(
+                f,
+                "IDAT chunk at byte {offset} doesn't follow on from the other IDAT chunks."
+            ),
+            Violation::MissingIdat => write!(f, "no IDAT chunks, the image data is missing."),
+            Violation::MissingPlte => write!(f, "palette image has no PLTE chunk."),
+            Violation::PlteNotAllowed(color_type) => {
+                write!(f, "PLTE chunk isn't allowed in {color_type} images.")
+            }
+            Violation::MissingIend => write!(f, "no IEND chunk, the file may be truncated."),
+            Violation::Unreadable { offset } => {
+                write!(f, "the chunk at byte {offset} is malformed or cut short.")
+            }
+            Violation::TrailingData { offset, len } => {
+                write!(f, "{len} bytes after IEND, starting at byte {offset}.")
+            }
+        }
+    }
+}
+
+impl Png {
+    /// Checks the chunks of the file as it was read against the spec's rules
+    /// on ordering, see 5.6 of the spec: IHDR first, PLTE and the chunks
+    /// that describe it before the image data, consecutive IDAT chunks, one
+    /// each of IHDR, IEND and the other chunks only allowed once, and
+    /// nothing after IEND. Only the chunk headers are read, the image data
+    /// isn't decompressed.
+    ///
+    /// # Returns
+    ///
+    /// Every Violation found, in the order they appear in the file, or an
+    /// empty Vec if the file follows the rules.
+    ///
+    /// # Examples
+    ///
+    /// '''
+    /// for violation in png.validate() {
+    ///     eprintln!("warning: {violation}");
+    /// }
+    /// '''
+    pub fn validate(&self) -> Vec<Violation> {
+        let raw_data = &self.data.raw_data;
+        let mut violations = Vec::new();
+        let mut seen: Vec<ChunkType> = Vec::new();
+        let mut iend = None;
+        let mut index = 8;
+
+        while index < raw_data.len() {
+            let header = raw_data.get(index..index + 8);
+            let length = header
+                .map(|header| u32::from_be_bytes([header[0], header[1], header[2], header[3]]));
+            let ctype = header.and_then(|header| {
+                ChunkType::new([header[4], header[5], header[6], header[7]]).ok()
+            });
+            let end = length.and_then(|length| {
+                (length as usize)
+                    .checked_add(12)
+                    .and_then(|len| index.checked_add(len))
+            });
+            let (Some(ctype), Some(end)) = (ctype, end.filter(|&end| end <= raw_data.len())) else {
+                violations.push(Violation::Unreadable { offset: index });
+                break;
+            };
+
+            let plte = seen.contains(&ChunkType::PLTE);
+            let idat = seen.contains(&ChunkType::IDAT);
+            let offset = index;
+            let misplaced = |rule| Violation::Misplaced {
+                ctype,
+                offset,
+                rule,
+            };
+            let bytes = ctype.as_bytes();
+
+            if index == 8 && ctype != ChunkType::IHDR {
+                violations.push(Violation::IhdrNotFirst);
+            }
+            if ONCE.contains(&bytes) && seen.contains(&ctype) {
+                violations.push(Violation::Duplicate { ctype, offset });
+            }
+            if ctype == ChunkType::IDAT && idat && seen.last() != Some(&ChunkType::IDAT) {
+                violations.push(Violation::SplitIdat { offset });
+            }
+            if ctype == ChunkType::PLTE && idat {
+                violations.push(misplaced("it must come before IDAT."));
+            }
+            if BEFORE_PLTE.contains(&bytes) && (plte || idat) {
+                violations.push(misplaced("it must come before PLTE and IDAT."));
+            }
+            if AFTER_PLTE.contains(&bytes) {
+                if idat {
+                    violations.push(misplaced("it must come before IDAT."));
+                } else if !plte && self.data.plte.is_some() {
+                    violations.push(misplaced("it must come after PLTE."));
+                }
+            }
+            if BEFORE_IDAT.contains(&bytes) && idat {
+                violations.push(misplaced("it must come before IDAT."));
+            }
+
+            seen.push(ctype);
+            index = end;
+            if ctype == ChunkType::IEND {
+                iend = Some(end);
+                break;
+            }
+        }
+
+        if !seen.contains(&ChunkType::IDAT) {
+            violations.push(Violation::MissingIdat);
+        }
+        match (self.color_type, seen.contains(&ChunkType::PLTE)) {
+            (ColorType::PalleteIndex, false) => violations.push(Violation::MissingPlte),
+            (ColorType::Grayscale | ColorType::GrayscaleAlpha, true) => {
+                violations.push(Violation::PlteNotAllowed(self.color_type))
+            }
+            _ => {}
+        }
+        match iend {
+            Some(end) if end < raw_data.len() => violations.push(Violation::TrailingData {
+                offset: end,
+                len: raw_data.len() - end,
+            }),
+            Some(_) => {}
+            None => violations.push(Violation::MissingIend),
+        }
+
+        violations
+    }
+}
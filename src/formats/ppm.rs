@@ -1,4 +1,11 @@
-use std::{fs, io, path::Path};
+use alloc::{format, vec::Vec};
+#[cfg(feature = "std")]
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+};
+
 pub struct Ppm {
     pub header: Vec<u8>,
     pub dimensions: (usize, usize),
@@ -17,8 +24,8 @@ impl Ppm {
             data: data.to_vec(),
         }
     }
-    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
-        let mut file_bytes = Vec::with_capacity(self.data.len());
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut file_bytes = Vec::with_capacity(self.header.len() + self.data.len() * 3 + 1);
 
         file_bytes.extend_from_slice(&self.header);
 
@@ -28,7 +35,15 @@ impl Ppm {
 
         file_bytes.push(0x0a);
 
-        fs::write(path, &file_bytes)?;
-        Ok(())
+        file_bytes
+    }
+    #[cfg(feature = "std")]
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_to(File::create(path)?)
+    }
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())?;
+        writer.flush()
     }
 }
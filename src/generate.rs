@@ -0,0 +1,131 @@
+//! Synthesized test patterns, so fixtures for codec round trips can be made
+//! by the crate itself rather than checked in. Every pattern is
+//! deterministic, the same arguments always give the same pixels.
+//!
+//! '''
+//! let image = Image::gradient(64, 16, [0, 0, 0, 255], [255, 255, 255, 255])?;
+//!
+//! fs::write("./small_gradient.png", image.encode_png(&EncodeOptions::new())?)?;
+//! '''
+
+use alloc::vec::Vec;
+
+use crate::image::{Image, ImageError, PixelFormat};
+
+/// A pattern Image::generate can fill an image with.
+///
+/// # Members
+///
+/// * 'Solid' - Every pixel the same color.
+/// * 'Gradient' - Blends from the first color at the left edge to the
+///   second at the right.
+/// * 'Checkerboard' - Squares of size pixels, alternating between two
+///   colors, the first in the top left corner.
+/// * 'Noise' - Opaque pixels of random color, from a seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Pattern {
+    Solid([u8; 4]),
+    Gradient([u8; 4], [u8; 4]),
+    Checkerboard { size: usize, colors: [[u8; 4]; 2] },
+    Noise(u64),
+}
+
+impl Image {
+    /// Creates an Rgba8 image filled with a Pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * 'width' - The width of the image in pixels.
+    /// * 'height' - The height of the image in pixels.
+    /// * 'pattern' - The Pattern to fill it with.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the Image, or an ImageError if the image
+    /// would be too large to address.
+    pub fn generate(width: usize, height: usize, pattern: Pattern) -> Result<Image, ImageError> {
+        let len = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(4))
+            .ok_or(ImageError::InvalidDimensions(width, height))?;
+        let mut data = Vec::with_capacity(len);
+        let mut random = SplitMix64(match pattern {
+            Pattern::Noise(seed) => seed,
+            _ => 0,
+        });
+
+        for y in 0..height {
+            for x in 0..width {
+                let color = match pattern {
+                    Pattern::Solid(color) => color,
+                    Pattern::Gradient(from, to) => {
+                        // Rounded, so both ends land exactly on their colors.
+                        let span = width.saturating_sub(1).max(1) as u64;
+                        let mix = |(from, to): (u8, u8)| {
+                            let (from, to) = (from as u64, to as u64);
+                            let x = x as u64;
+                            ((from * (span - x) + to * x + span / 2) / span) as u8
+                        };
+                        [0, 1, 2, 3].map(|i| mix((from[i], to[i])))
+                    }
+                    Pattern::Checkerboard { size, colors } => {
+                        let size = size.max(1);
+                        colors[(x / size + y / size) % 2]
+                    }
+                    Pattern::Noise(_) => {
+                        let [r, g, b, ..] = random.next().to_le_bytes();
+                        [r, g, b, 255]
+                    }
+                };
+                data.extend_from_slice(&color);
+            }
+        }
+
+        Image::new(width, height, PixelFormat::Rgba8, data)
+    }
+    /// Creates an Rgba8 image of a single color, see Pattern::Solid.
+    pub fn solid(width: usize, height: usize, color: [u8; 4]) -> Result<Image, ImageError> {
+        Image::generate(width, height, Pattern::Solid(color))
+    }
+    /// Creates an Rgba8 image blending from one color at the left edge to
+    /// another at the right, see Pattern::Gradient.
+    pub fn gradient(
+        width: usize,
+        height: usize,
+        from: [u8; 4],
+        to: [u8; 4],
+    ) -> Result<Image, ImageError> {
+        Image::generate(width, height, Pattern::Gradient(from, to))
+    }
+    /// Creates an Rgba8 checkerboard of size pixel squares, see
+    /// Pattern::Checkerboard.
+    pub fn checkerboard(
+        width: usize,
+        height: usize,
+        size: usize,
+        colors: [[u8; 4]; 2],
+    ) -> Result<Image, ImageError> {
+        Image::generate(width, height, Pattern::Checkerboard { size, colors })
+    }
+    /// Creates an Rgba8 image of random opaque colors, the same for the
+    /// same seed on every platform, see Pattern::Noise.
+    pub fn noise(width: usize, height: usize, seed: u64) -> Result<Image, ImageError> {
+        Image::generate(width, height, Pattern::Noise(seed))
+    }
+}
+
+/// The SplitMix64 generator, small, fast and good enough for test noise,
+/// see Steele, Lea and Flood, "Fast Splittable Pseudorandom Number
+/// Generators".
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
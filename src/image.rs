@@ -0,0 +1,700 @@
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+use crate::convert;
+use crate::scale::{self, BoxFilter, Insets};
+use crate::window::Windows;
+#[cfg(feature = "png")]
+use crate::{
+    buffer::DefaultBuffers,
+    formats::png::{self, DecoderError, EncodeOptions, Png},
+};
+
+/// The layout of each pixel in an Image. 16 bit samples are stored big
+/// endian, as they are in PNG files. See the convert module for converting
+/// between them.
+///
+/// # Members
+///
+/// * 'Rgb8' - Red, green and blue samples, one byte each.
+/// * 'Rgba8' - Red, green, blue and alpha samples, one byte each.
+/// * 'Gray8' - A gray sample, one byte.
+/// * 'GrayAlpha8' - Gray and alpha samples, one byte each.
+/// * 'Gray16' - A gray sample, two bytes.
+/// * 'GrayAlpha16' - Gray and alpha samples, two bytes each.
+/// * 'Rgb16' - Red, green and blue samples, two bytes each.
+/// * 'Rgba16' - Red, green, blue and alpha samples, two bytes each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PixelFormat {
+    Rgb8,
+    Rgba8,
+    Gray8,
+    GrayAlpha8,
+    Gray16,
+    GrayAlpha16,
+    Rgb16,
+    Rgba16,
+}
+
+impl PixelFormat {
+    /// Gets the number of samples making up each pixel.
+    pub fn channels(&self) -> usize {
+        match self {
+            PixelFormat::Gray8 | PixelFormat::Gray16 => 1,
+            PixelFormat::GrayAlpha8 | PixelFormat::GrayAlpha16 => 2,
+            PixelFormat::Rgb8 | PixelFormat::Rgb16 => 3,
+            PixelFormat::Rgba8 | PixelFormat::Rgba16 => 4,
+        }
+    }
+    /// Gets the number of bits in each sample, 8 or 16.
+    pub fn bit_depth(&self) -> u8 {
+        match self {
+            PixelFormat::Rgb8
+            | PixelFormat::Rgba8
+            | PixelFormat::Gray8
+            | PixelFormat::GrayAlpha8 => 8,
+            _ => 16,
+        }
+    }
+    /// Gets the number of bytes making up each pixel.
+    pub fn bytes_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth() as usize / 8
+    }
+    /// Whether the last sample of each pixel is alpha.
+    pub fn has_alpha(&self) -> bool {
+        self.channels().is_multiple_of(2)
+    }
+}
+
+/// The most distinct colors Image::analyze lists, the size of the largest
+/// palette a PNG file can hold.
+const MAX_COLORS: usize = 256;
+
+/// A decoded image that isn't tied to any file format, the common ground
+/// between formats. Conversions to and from the format specific types live
+/// here, e.g. Image::try_from(png) and Image::encode_png.
+///
+/// # Fields
+///
+/// * 'width' - The width of the image in pixels.
+/// * 'height' - The height of the image in pixels.
+/// * 'format' - The layout of each pixel in data.
+/// * 'data' - The pixels from left to right, top to bottom, with no padding
+///   between rows.
+///
+/// # Examples
+///
+/// '''
+/// let image = Image::try_from(Png::from_path("./in.png")?)?;
+///
+/// fs::write("./out.png", image.encode_png(&EncodeOptions::new())?)?;
+/// '''
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub format: PixelFormat,
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    /// Creates an Image from a buffer of pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * 'width' - The width of the image in pixels.
+    /// * 'height' - The height of the image in pixels.
+    /// * 'format' - The layout of each pixel in data.
+    /// * 'data' - The pixels from left to right, top to bottom.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the Image or an ImageError if data isn't
+    /// exactly the right size for the dimensions and format. Images with a
+    /// width or height of 0 are allowed, and drawing on them does nothing,
+    /// but they can't be shrunk or encoded.
+    pub fn new(
+        width: usize,
+        height: usize,
+        format: PixelFormat,
+        data: Vec<u8>,
+    ) -> Result<Self, ImageError> {
+        let image = Self {
+            width,
+            height,
+            format,
+            data,
+        };
+        image.check_size()?;
+
+        Ok(image)
+    }
+    /// Checks that data is exactly the size the dimensions and format call
+    /// for, as the fields are public and can be changed after creation.
+    pub(crate) fn check_size(&self) -> Result<(), ImageError> {
+        let expected = self
+            .width
+            .checked_mul(self.height)
+            .and_then(|pixels| pixels.checked_mul(self.format.bytes_per_pixel()))
+            .ok_or(ImageError::InvalidDimensions(self.width, self.height))?;
+
+        if self.data.len() != expected {
+            return Err(ImageError::BufferSize(expected, self.data.len()));
+        }
+
+        Ok(())
+    }
+    /// Borrows the whole image as an ImageView.
+    pub fn view(&self) -> ImageView<'_> {
+        ImageView {
+            width: self.width,
+            height: self.height,
+            format: self.format,
+            stride: self.width * self.format.bytes_per_pixel(),
+            data: &self.data,
+        }
+    }
+    /// Iterates over every pixel with the block of pixels around it, see
+    /// ImageView::windows.
+    pub fn windows(&self, radius: usize) -> Windows<'_> {
+        self.view().windows(radius)
+    }
+    /// Borrows a rectangle of the image without copying it, e.g. to encode
+    /// a crop, see ImageView.
+    ///
+    /// # Arguments
+    ///
+    /// * 'x' - The left edge of the rectangle in pixels.
+    /// * 'y' - The top edge of the rectangle in pixels.
+    /// * 'width' - The width of the rectangle in pixels.
+    /// * 'height' - The height of the rectangle in pixels.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the ImageView or an ImageError if the
+    /// rectangle doesn't fit in the image.
+    pub fn region(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<ImageView<'_>, ImageError> {
+        self.check_size()?;
+        self.view().region(x, y, width, height)
+    }
+    /// Borrows the image without the margins around it that are the same
+    /// color as its top left pixel, e.g. the blank border of a scan or the
+    /// transparent padding around an icon, see ImageView::trim.
+    ///
+    /// # Arguments
+    ///
+    /// * 'threshold' - How far each sample can be from the top left pixel's,
+    ///   on a scale of 0 to 255, and still count as margin.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the ImageView or an ImageError if the
+    /// image's buffer doesn't match its dimensions.
+    pub fn trim(&self, threshold: u8) -> Result<ImageView<'_>, ImageError> {
+        self.check_size()?;
+        self.view().trim(threshold)
+    }
+    /// Looks through the pixels for properties that allow the image to be
+    /// stored more compactly, see Analysis.
+    ///
+    /// # Returns
+    ///
+    /// The Analysis of the image's pixels.
+    pub fn analyze(&self) -> Analysis {
+        self.view().analyze()
+    }
+    /// Shrinks the image with a box filter, averaging the block of pixels
+    /// under each new pixel. Formats with alpha are averaged with
+    /// premultiplied alpha, so transparent pixels don't tint their neighbours.
+    ///
+    /// # Arguments
+    ///
+    /// * 'width' - The new width in pixels, from 1 to the current width.
+    /// * 'height' - The new height in pixels, from 1 to the current height.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the shrunk Image or an ImageError if the
+    /// new dimensions are 0 or larger than the image.
+    pub fn downscale(&self, width: usize, height: usize) -> Result<Image, ImageError> {
+        self.check_size()?;
+
+        if !(1..=self.width).contains(&width) || !(1..=self.height).contains(&height) {
+            return Err(ImageError::InvalidDimensions(width, height));
+        }
+
+        let mut filter = BoxFilter::new(self.width, self.height, width, height, self.format);
+        let stride = self.width * self.format.bytes_per_pixel();
+        for (y, row) in self.data.chunks_exact(stride).enumerate() {
+            filter.add_row(y, row);
+        }
+
+        Ok(filter.finish())
+    }
+    /// Shrinks the image to fit in a square, keeping its aspect ratio, see
+    /// scale::fit and Image::downscale.
+    ///
+    /// # Arguments
+    ///
+    /// * 'max_dim' - The most pixels the thumbnail can have along either
+    ///   side.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the thumbnail or an ImageError if the
+    /// image is empty.
+    pub fn thumbnail(&self, max_dim: usize) -> Result<Image, ImageError> {
+        let (width, height) = scale::fit(self.width, self.height, max_dim);
+        self.downscale(width, height)
+    }
+    /// Resizes the image with 9-slice scaling, as is done with the buttons
+    /// and panels of user interfaces. The corners are kept as they are, the
+    /// edges are stretched along their length and the center both ways, so
+    /// rounded corners and borders don't blur or change thickness. Stretched
+    /// parts take the nearest pixel, so they can shrink as well as grow.
+    ///
+    /// # Arguments
+    ///
+    /// * 'insets' - The widths of the borders kept at their own size, see
+    ///   Insets.
+    /// * 'target_w' - The new width in pixels, at least the left and right
+    ///   insets together.
+    /// * 'target_h' - The new height in pixels, at least the top and bottom
+    ///   insets together.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the scaled Image or an ImageError if the
+    /// insets don't fit in the image or the new size, the center would have
+    /// to be stretched from nothing, or the new size is too large to
+    /// address.
+    pub fn scale_9slice(
+        &self,
+        insets: Insets,
+        target_w: usize,
+        target_h: usize,
+    ) -> Result<Image, ImageError> {
+        scale::nine_slice(self, insets, target_w, target_h)
+    }
+    /// Encodes the image as a PNG file.
+    ///
+    /// # Arguments
+    ///
+    /// * 'options' - The EncodeOptions to encode with.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the PNG file as bytes or an ImageError if
+    /// the image can't be stored in a PNG file.
+    #[cfg(feature = "png")]
+    pub fn encode_png(&self, options: &EncodeOptions) -> Result<Vec<u8>, ImageError> {
+        self.check_size()?;
+        png::encode(&self.view(), options)
+    }
+}
+
+/// A borrowed rectangle of pixels whose rows can be further apart than
+/// their width, e.g. a region of a larger Image or a buffer with padding at
+/// the end of each row. Encoders read through a view, so cropping before
+/// encoding doesn't copy the pixels.
+///
+/// # Fields
+///
+/// * 'width' - The width of the view in pixels.
+/// * 'height' - The height of the view in pixels.
+/// * 'format' - The layout of each pixel in data.
+/// * 'stride' - The distance in bytes from the start of one row to the
+///   start of the next, at least width times the bytes per pixel.
+/// * 'data' - The pixels, starting with the first pixel of the top row.
+///   The last row doesn't need the padding up to stride.
+///
+/// # Examples
+///
+/// '''
+/// let image = Image::try_from(Png::from_path("./in.png")?)?;
+/// let face = image.region(40, 16, 64, 64)?;
+///
+/// fs::write("./face.png", face.encode_png(&EncodeOptions::new())?)?;
+/// '''
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageView<'a> {
+    pub width: usize,
+    pub height: usize,
+    pub format: PixelFormat,
+    pub stride: usize,
+    pub data: &'a [u8],
+}
+
+impl<'a> ImageView<'a> {
+    /// Creates an ImageView over a buffer of pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * 'width' - The width of the view in pixels.
+    /// * 'height' - The height of the view in pixels.
+    /// * 'format' - The layout of each pixel in data.
+    /// * 'stride' - The distance in bytes between the starts of rows.
+    /// * 'data' - The pixels from left to right, top to bottom.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the ImageView or an ImageError if the
+    /// stride is shorter than a row or data is too small to hold every row.
+    pub fn new(
+        width: usize,
+        height: usize,
+        format: PixelFormat,
+        stride: usize,
+        data: &'a [u8],
+    ) -> Result<Self, ImageError> {
+        let view = Self {
+            width,
+            height,
+            format,
+            stride,
+            data,
+        };
+        view.check_size()?;
+
+        Ok(view)
+    }
+    /// Checks that the stride holds a row and data holds every row, as the
+    /// fields are public and can be changed after creation.
+    pub(crate) fn check_size(&self) -> Result<(), ImageError> {
+        let row = self
+            .width
+            .checked_mul(self.format.bytes_per_pixel())
+            .ok_or(ImageError::InvalidDimensions(self.width, self.height))?;
+        if self.stride < row {
+            return Err(ImageError::InvalidStride(self.stride, row));
+        }
+
+        let expected = match self.height {
+            0 => 0,
+            height => (height - 1)
+                .checked_mul(self.stride)
+                .and_then(|rows| rows.checked_add(row))
+                .ok_or(ImageError::InvalidDimensions(self.width, self.height))?,
+        };
+        if self.data.len() < expected {
+            return Err(ImageError::BufferSize(expected, self.data.len()));
+        }
+
+        Ok(())
+    }
+    /// Narrows the view to a rectangle inside of it, without copying.
+    ///
+    /// # Arguments
+    ///
+    /// * 'x' - The left edge of the rectangle in pixels.
+    /// * 'y' - The top edge of the rectangle in pixels.
+    /// * 'width' - The width of the rectangle in pixels.
+    /// * 'height' - The height of the rectangle in pixels.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the narrower ImageView or an ImageError if
+    /// the rectangle doesn't fit in the view.
+    pub fn region(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<ImageView<'a>, ImageError> {
+        self.check_size()?;
+
+        let fits = |start: usize, size: usize, limit: usize| {
+            start.checked_add(size).is_some_and(|end| end <= limit)
+        };
+        if !fits(x, width, self.width) || !fits(y, height, self.height) {
+            return Err(ImageError::InvalidDimensions(width, height));
+        }
+
+        // An empty rectangle can sit at the very end, past the last byte.
+        let start = match (width, height) {
+            (0, _) | (_, 0) => 0,
+            _ => y * self.stride + x * self.format.bytes_per_pixel(),
+        };
+
+        Ok(ImageView {
+            width,
+            height,
+            format: self.format,
+            stride: self.stride,
+            data: &self.data[start..],
+        })
+    }
+    /// Narrows the view to leave out the margins that are the same color as
+    /// its top left pixel, without copying. Pixels that are fully
+    /// transparent count as the same color as each other, whatever their
+    /// red, green and blue.
+    ///
+    /// # Arguments
+    ///
+    /// * 'threshold' - How far each sample can be from the top left pixel's,
+    ///   on a scale of 0 to 255, and still count as margin. 16 bit
+    ///   samples are compared on a scale of 0 to 65535, with the
+    ///   threshold scaled to match.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the narrower ImageView, 0 by 0 if every
+    /// pixel is margin, or an ImageError if the view's stride or buffer is
+    /// too small.
+    pub fn trim(&self, threshold: u8) -> Result<ImageView<'a>, ImageError> {
+        self.check_size()?;
+        if self.width == 0 || self.height == 0 {
+            return Ok(*self);
+        }
+
+        let bytes = self.format.bytes_per_pixel();
+        let pixel = |x: usize, y: usize| {
+            convert::rgba16(
+                self.format,
+                &self.data[y * self.stride + x * bytes..][..bytes],
+            )
+        };
+        let margin = pixel(0, 0);
+        let threshold = threshold as u16 * 257;
+        let is_margin = |x: usize, y: usize| {
+            let color = pixel(x, y);
+            (color[3] == 0 && margin[3] == 0)
+                || color
+                    .iter()
+                    .zip(margin)
+                    .all(|(&sample, margin)| sample.abs_diff(margin) <= threshold)
+        };
+
+        let row_is_margin = |y: usize| (0..self.width).all(|x| is_margin(x, y));
+        let Some(top) = (0..self.height).find(|&y| !row_is_margin(y)) else {
+            return self.region(0, 0, 0, 0);
+        };
+        // There's a row that isn't margin, so there's a column too.
+        let bottom = (top..self.height)
+            .rfind(|&y| !row_is_margin(y))
+            .unwrap_or(top)
+            + 1;
+        let column_is_margin = |x: usize| (top..bottom).all(|y| is_margin(x, y));
+        let left = (0..self.width).find(|&x| !column_is_margin(x)).unwrap_or(0);
+        let right = (left..self.width)
+            .rfind(|&x| !column_is_margin(x))
+            .unwrap_or(left)
+            + 1;
+
+        self.region(left, top, right - left, bottom - top)
+    }
+    /// Iterates over the rows of the view from top to bottom, each without
+    /// the padding up to stride. Stops early if data runs out.
+    pub fn rows(&self) -> impl Iterator<Item = &'a [u8]> + 'a {
+        let (data, stride) = (self.data, self.stride);
+        let row = self.width * self.format.bytes_per_pixel();
+
+        (0..self.height).map_while(move |y| data.get(y.checked_mul(stride)?..)?.get(..row))
+    }
+    /// Iterates over every pixel of the view from left to right, top to
+    /// bottom, each as a Window giving its position and the pixels around
+    /// it, with the edges of the view repeated past them. Yields nothing if
+    /// data doesn't hold every row.
+    ///
+    /// # Arguments
+    ///
+    /// * 'radius' - How far the block around each pixel reaches on every
+    ///   side, e.g. 1 for 3x3 blocks.
+    ///
+    /// # Returns
+    ///
+    /// The Windows iterator.
+    ///
+    /// # Examples
+    ///
+    /// '''
+    /// // A 3x3 median of the red channel.
+    /// let reds = image.windows(1).map(|window| {
+    ///     let mut reds = window.pixels().map(|[r, ..]| r).collect::<Vec<_>>();
+    ///     reds.sort();
+    ///     reds[4]
+    /// });
+    /// '''
+    pub fn windows(&self, radius: usize) -> Windows<'a> {
+        Windows::new(*self, radius)
+    }
+    /// Looks through the pixels for properties that allow the view to be
+    /// stored more compactly, see Analysis.
+    ///
+    /// # Returns
+    ///
+    /// The Analysis of the view's pixels.
+    pub fn analyze(&self) -> Analysis {
+        let bytes_per_pixel = self.format.bytes_per_pixel();
+        let mut opaque = true;
+        let mut grayscale = true;
+        let mut eight_bit = true;
+        let mut colors = BTreeSet::new();
+
+        for pixel in self
+            .rows()
+            .flat_map(|row| row.chunks_exact(bytes_per_pixel))
+        {
+            let color = convert::rgba16(self.format, pixel);
+
+            opaque &= color[3] == u16::MAX;
+            grayscale &= color[0] == color[1] && color[1] == color[2];
+            // 8 bit samples scaled up to 16 bits are multiples of 257.
+            eight_bit &= color.iter().all(|sample| sample % 257 == 0);
+            // Stop counting once there are too many colors for a palette.
+            if eight_bit && colors.len() <= MAX_COLORS {
+                colors.insert(color.map(|sample| (sample / 257) as u8));
+            }
+        }
+
+        Analysis {
+            opaque,
+            grayscale,
+            bit_depth: if eight_bit { 8 } else { 16 },
+            colors: match eight_bit && colors.len() <= MAX_COLORS {
+                true => Some(colors.into_iter().collect()),
+                false => None,
+            },
+        }
+    }
+    /// Copies the pixels into a tightly packed Image.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the Image or an ImageError if the view's
+    /// stride or buffer is too small.
+    pub fn to_image(&self) -> Result<Image, ImageError> {
+        self.check_size()?;
+
+        Ok(Image {
+            width: self.width,
+            height: self.height,
+            format: self.format,
+            data: self.rows().flatten().copied().collect(),
+        })
+    }
+    /// Encodes the view as a PNG file, reading the pixels in place.
+    ///
+    /// # Arguments
+    ///
+    /// * 'options' - The EncodeOptions to encode with.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the PNG file as bytes or an ImageError if
+    /// the view can't be stored in a PNG file.
+    #[cfg(feature = "png")]
+    pub fn encode_png(&self, options: &EncodeOptions) -> Result<Vec<u8>, ImageError> {
+        self.check_size()?;
+        png::encode(self, options)
+    }
+}
+
+impl<'a> From<&'a Image> for ImageView<'a> {
+    fn from(image: &'a Image) -> Self {
+        image.view()
+    }
+}
+
+/// What Image::analyze found out about an image's pixels, used by the PNG
+/// encoder to pick the smallest color type that holds them exactly.
+///
+/// # Fields
+///
+/// * 'opaque' - Every pixel is fully opaque, always true for formats
+///   without alpha.
+/// * 'grayscale' - Every pixel has equal red, green and blue samples.
+/// * 'bit_depth' - The fewest bits per sample that hold every sample
+///   exactly, 8 or 16.
+/// * 'colors' - The distinct red, green, blue and alpha colors in the
+///   image, sorted, or None if there are more than 256 or they need
+///   16 bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Analysis {
+    pub opaque: bool,
+    pub grayscale: bool,
+    pub bit_depth: u8,
+    pub colors: Option<Vec<[u8; 4]>>,
+}
+
+#[cfg(feature = "png")]
+impl TryFrom<&Png> for Image {
+    type Error = DecoderError;
+
+    fn try_from(png: &Png) -> Result<Self, Self::Error> {
+        Ok(Self {
+            width: png.dimensions.0,
+            height: png.dimensions.1,
+            format: PixelFormat::Rgb8,
+            data: png.rgb_bytes_with(&DefaultBuffers)?,
+        })
+    }
+}
+
+#[cfg(feature = "png")]
+impl TryFrom<Png> for Image {
+    type Error = DecoderError;
+
+    fn try_from(png: Png) -> Result<Self, Self::Error> {
+        Self::try_from(&png)
+    }
+}
+
+/// Errors from creating or converting an Image.
+///
+/// # Members
+///
+/// * 'BufferSize' - The pixel buffer isn't the size the dimensions and
+///   format call for. Holds the expected and actual sizes in bytes.
+/// * 'InvalidDimensions' - The dimensions are too large to address, or
+///   can't be stored in the target format. Holds the width and height.
+/// * 'InvalidStride' - An ImageView's rows are closer together than their
+///   width. Holds the stride and the row size in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+    BufferSize(usize, usize),
+    InvalidDimensions(usize, usize),
+    InvalidStride(usize, usize),
+}
+
+impl Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::BufferSize(expected, actual) => {
+                write!(
+                    f,
+                    "Error: Image buffer is {} bytes, but the dimensions and format need {}.",
+                    actual, expected
+                )
+            }
+            ImageError::InvalidDimensions(width, height) => {
+                write!(
+                    f,
+                    "Error: Image dimensions {}x{} are not supported.",
+                    width, height
+                )
+            }
+            ImageError::InvalidStride(stride, row) => {
+                write!(
+                    f,
+                    "Error: Image stride is {} bytes, shorter than a {} byte row.",
+                    stride, row
+                )
+            }
+        }
+    }
+}
+
+impl Error for ImageError {}
@@ -1,3 +1,77 @@
+// Everything but the file system, threading and command line pieces works
+// with just alloc, those are gated behind the default std feature. Each
+// image format has its own feature too, see Cargo.toml.
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod animation;
+#[cfg(all(feature = "std", feature = "png"))]
+pub mod batch;
+pub mod buffer;
+#[cfg(feature = "std")]
 pub mod cli;
+pub mod color;
 pub mod compression;
+pub mod convert;
+pub mod draw;
+pub mod error;
+#[cfg(feature = "png")]
+pub mod exif;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "png")]
+pub mod fingerprint;
 pub mod formats;
+pub mod generate;
+pub mod image;
+#[cfg(feature = "png")]
+pub mod metadata;
+pub mod prelude;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(all(feature = "std", feature = "png"))]
+pub mod roundtrip;
+pub mod scale;
+#[cfg(all(feature = "std", feature = "png"))]
+pub mod scan;
+#[cfg(all(feature = "std", feature = "png"))]
+pub mod tile;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod window;
+
+pub use error::{Error, ErrorKind};
+
+// Decoders, options and errors are plain owned data with no interior
+// mutability or reference counting, so they can be sent to and shared
+// between threads. This stops compiling if that ever changes.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<Error>();
+    assert_send_sync::<image::Image>();
+    assert_send_sync::<buffer::DefaultBuffers>();
+    assert_send_sync::<draw::Font>();
+    assert_send_sync::<compression::inflate::DeflateStream>();
+    assert_send_sync::<compression::zlib::ZlibStream>();
+    assert_send_sync::<compression::scratch::Scratch>();
+    assert_send_sync::<compression::prefix::PrefixTree>();
+    #[cfg(feature = "png")]
+    {
+        assert_send_sync::<formats::png::Png>();
+        assert_send_sync::<formats::png::DecodeOptions>();
+        assert_send_sync::<formats::png::StreamDecoder>();
+        assert_send_sync::<formats::png::DecoderError>();
+    }
+    #[cfg(feature = "ppm")]
+    assert_send_sync::<formats::ppm::Ppm>();
+    #[cfg(all(feature = "std", feature = "png"))]
+    {
+        assert_send_sync::<batch::BatchOptions>();
+        assert_send_sync::<formats::png::StreamEncoder<alloc::vec::Vec<u8>>>();
+        assert_send_sync::<formats::png::ChunkWriter<alloc::vec::Vec<u8>>>();
+    }
+};
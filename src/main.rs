@@ -1,35 +1,339 @@
-use std::error::Error;
-
-use chameleon::cli;
+use chameleon::batch::{self, Action, Manifest, Stamp};
+use chameleon::cli::{self, CliError, Flags};
+use chameleon::draw::{self, Blend};
 use chameleon::formats;
+use chameleon::prelude::{
+    EncodeOptions, ErrorKind, Fingerprint, Font, Image, Pattern, PixelFormat, Time,
+};
+use chameleon::{convert, Error};
+use chameleon::{roundtrip, scan};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+// Exits with the code of the error's ErrorKind, so scripts can branch on the
+// kind of failure, see Error::code.
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{e} (error {})", e.code());
+        process::exit(e.kind().code());
+    }
+}
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn run() -> Result<(), Error> {
     // Get the command line arguments.
     let args = match cli::InputArguments::build() {
         Ok(args) => args,
         Err(e) => {
             cli::usage();
-            return Err(Box::new(e));
+            return Err(e.into());
         }
     };
 
+    if args.flags.contains(&Flags::Scan) {
+        let entries = scan::scan_dir(&args.input_path)?;
+        match &args.report_path {
+            Some(report_path) => fs::write(report_path, scan::to_json(&entries))?,
+            None => entries.iter().for_each(|entry| println!("{entry}")),
+        }
+        return Ok(());
+    }
+
+    if args.flags.contains(&Flags::Verify) {
+        let report = roundtrip::verify(&args.input_path)?;
+        println!("{report}");
+        // Pixels that didn't survive are a bug in chameleon rather than in
+        // the file, so they exit as Unexpected.
+        if !report.is_exact() {
+            process::exit(ErrorKind::Unexpected.code());
+        }
+        return Ok(());
+    }
+
+    if let (Some((pattern, width, height)), Some(output_path)) = (args.pattern, &args.output_path) {
+        let image = Image::generate(width, height, pattern)?;
+        let options = EncodeOptions::new()
+            .reproducible(args.flags.contains(&Flags::Reproducible))
+            .reduce(!args.flags.contains(&Flags::NoReduce))
+            .level(args.compression.level)
+            .strategy(args.compression.strategy);
+        return write_output(&image, output_path, &options, args.matte);
+    }
+
+    if args.flags.contains(&Flags::Info) {
+        // Only the header of a local file is read, however large it is.
+        let info = match &args.input_url {
+            Some(_) => read_input(&args)?.info(),
+            None => formats::png::Png::probe(&args.input_path)?,
+        };
+        println!("{info}");
+        return Ok(());
+    }
+
+    // A dry run never reads or writes an image, whatever else is asked for.
+    let input_dir = args.input_url.is_none() && args.input_path.is_dir();
+    if args.flags.contains(&Flags::DryRun) {
+        let Some(output_path) = &args.output_path else {
+            return Err(CliError::MissingArgument(
+                "Error: Missing output path, --dry-run needs -output to plan the conversion.",
+            )
+            .into());
+        };
+        if input_dir {
+            return convert_dir(&args, true);
+        }
+        let input = args.input_url.as_deref().map(PathBuf::from);
+        println!(
+            "{}",
+            batch::plan_file(input.as_deref().unwrap_or(&args.input_path), output_path)
+        );
+        return Ok(());
+    }
+    if input_dir {
+        return convert_dir(&args, false);
+    }
+
+    let picture = read_input(&args)?;
+
+    if args.flags.contains(&Flags::Fingerprint) {
+        // Laid out like sha256sum, so the output can be diffed the same way.
+        let name = args.input_url.as_deref().map(PathBuf::from);
+        let name = name.as_deref().unwrap_or(&args.input_path);
+        println!("{}  {}", Fingerprint::of_png(&picture)?, name.display());
+        return Ok(());
+    }
+
+    match &args.output_path {
+        Some(output_path) => convert(&picture, output_path, &args),
+        None => Ok(()),
+    }
+}
+
+// Converts every PNG file under the input directory into the output
+// directory, keeping the layout of the tree. With --dry-run the plan is
+// printed and nothing is read or written. With --resume files finished by
+// an earlier run are skipped, and each one finished now is recorded.
+fn convert_dir(args: &cli::InputArguments, dry_run: bool) -> Result<(), Error> {
+    let Some(output_dir) = &args.output_path else {
+        return Ok(());
+    };
+    let format = args.format.as_deref().unwrap_or("png");
+    let mut manifest = match args.flags.contains(&Flags::Resume) {
+        true => Some(Manifest::load(
+            output_dir.join(batch::MANIFEST_NAME),
+            &manifest_options(args, format),
+        )?),
+        false => None,
+    };
+
+    for mut step in batch::plan_dir(&args.input_path, output_dir, format)? {
+        if let Some(manifest) = &manifest {
+            manifest.resume(&mut step)?;
+        }
+        if dry_run {
+            println!("{step}");
+            continue;
+        }
+        if let Action::Skip(_) = step.action {
+            continue;
+        }
+        if let Some(parent) = step.output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Stamped before it's read, so a change made while converting isn't
+        // recorded as converted.
+        let stamp = match manifest {
+            Some(_) => Some(Stamp::of(&step.input)?),
+            None => None,
+        };
+        let picture = formats::png::Png::from_path(&step.input)?;
+        convert(&picture, &step.output, args)?;
+        if let (Some(manifest), Some(stamp)) = (&mut manifest, stamp) {
+            manifest.record(&step, stamp)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Describes everything on the command line that changes what a file is
+// converted to, so a manifest only skips files converted the same way.
+// Flags are sorted, as the order they're given in doesn't matter.
+fn manifest_options(args: &cli::InputArguments, format: &str) -> String {
+    let mut flags = args
+        .flags
+        .iter()
+        .filter(|flag| !matches!(flag, Flags::DryRun | Flags::Resume))
+        .map(|flag| format!("{flag:?}"))
+        .collect::<Vec<_>>();
+    flags.sort();
+    flags.dedup();
+
+    format!(
+        "{format} {:?} {flags:?} trim={:?} chroma_key={:?} background={:?} matte={:?} label={:?} font={:?}",
+        args.compression,
+        args.trim,
+        args.chroma_key,
+        args.background,
+        args.matte,
+        args.label,
+        args.font_path
+    )
+}
+
+// Applies the edits given on the command line to a decoded image and writes
+// it to the output path.
+fn convert(
+    picture: &formats::png::Png,
+    output_path: &Path,
+    args: &cli::InputArguments,
+) -> Result<(), Error> {
+    // Decoded with alpha when the image has any, so it's kept through to
+    // formats that can hold it and drawn over the matte for those that can't.
+    // Sized with checked math, so dimensions too large to address are an
+    // error rather than an overflow.
+    let (_, len) = picture.aligned_layout(1)?;
+    let mut pixels = vec![0; len];
+    let mut image = picture.decode_into(&mut pixels)?.to_image()?;
+    if let Some(threshold) = args.trim {
+        image = image.trim(threshold)?.to_image()?;
+    }
+    if let Some(key) = args.chroma_key {
+        image = chroma_key(&image, key)?;
+    }
+    if let Some(background) = args.background {
+        image = draw::flatten(&image, Pattern::Solid(background), Blend::Srgb)?;
+    }
+    let reproducible = args.flags.contains(&Flags::Reproducible);
+    if let Some(label) = &args.label {
+        // Stamp the time the output would be given, so the label and the
+        // tIME chunk agree.
+        let time = match reproducible {
+            true => Time::EPOCH,
+            false => picture.time().unwrap_or_else(Time::now),
+        };
+        let label = label.replace("{time}", &time.to_string());
+        stamp(&mut image, &label, args.font_path.as_deref())?;
+    }
+
+    // Carry the modification time over from the input, if it has one.
+    let mut options = EncodeOptions::new()
+        .time(picture.time())
+        .reproducible(reproducible)
+        .reduce(!args.flags.contains(&Flags::NoReduce))
+        .level(args.compression.level)
+        .strategy(args.compression.strategy);
+    if args.flags.contains(&Flags::KeepMetadata) {
+        options = options.metadata(picture.metadata());
+    }
+
+    write_output(&image, output_path, &options, args.matte)
+}
+
+// Writes the image in the format the output path's extension names. Formats
+// without alpha get transparent images drawn over the matte, white unless
+// one was given.
+fn write_output(
+    image: &Image,
+    output_path: &Path,
+    options: &EncodeOptions,
+    matte: Option<Pattern>,
+) -> Result<(), Error> {
+    match output_path.extension().and_then(|ex| ex.to_str()) {
+        Some("png") => fs::write(output_path, image.encode_png(options)?)?,
+        #[cfg(feature = "ppm")]
+        Some("ppm") => {
+            let flat;
+            let image = match image.analyze().opaque {
+                true => image,
+                false => {
+                    let matte = matte.unwrap_or_else(|| {
+                        eprintln!("Warning: PPM has no alpha, transparent pixels were drawn over white. Use --matte to pick another color.");
+                        Pattern::Solid([255, 255, 255, 255])
+                    });
+                    flat = draw::flatten(image, matte, Blend::Srgb)?;
+                    &flat
+                }
+            };
+            let mut rgb = Vec::new();
+            convert::convert_pixels(image.format, PixelFormat::Rgb8, &image.data, &mut rgb)?;
+            let pixels = rgb
+                .chunks_exact(3)
+                .map(|pixel| (pixel[0], pixel[1], pixel[2]))
+                .collect::<Vec<_>>();
+            formats::ppm::Ppm::build(&pixels, image.width, image.height).write(output_path)?
+        }
+        _ => {
+            return Err(cli::CliError::InvalidArgument(format!(
+                "Invalid output path: {:?}",
+                output_path
+            ))
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+// Draws a label in the top left corner of the image, white on a black box
+// so it can be read against any picture.
+fn stamp(image: &mut Image, label: &str, font_path: Option<&Path>) -> Result<(), Error> {
+    let font = match font_path {
+        Some(path) => Font::from_bdf(&fs::read_to_string(path)?)?,
+        None => Font::fixed(),
+    };
+    let (width, height) = font.measure(label);
+
+    draw::fill(image, 0, 0, width + 2, height + 2, [0, 0, 0, 255])?;
+    draw::text(image, 1, 1, label, [255, 255, 255, 255], &font)?;
+
+    Ok(())
+}
+
+// Makes every pixel of the key color transparent, matching on red, green
+// and blue only, so a flat backdrop can be dropped from a picture.
+fn chroma_key(image: &Image, key: [u8; 4]) -> Result<Image, Error> {
+    let mut rgba = Vec::new();
+    convert::convert_pixels(image.format, PixelFormat::Rgba8, &image.data, &mut rgba)?;
+    for pixel in rgba.chunks_exact_mut(4) {
+        if pixel[..3] == key[..3] {
+            pixel[3] = 0;
+        }
+    }
+
+    Ok(Image::new(
+        image.width,
+        image.height,
+        PixelFormat::Rgba8,
+        rgba,
+    )?)
+}
+
+// Decodes the image named on the command line, fetching it first if a URL
+// was given.
+fn read_input(args: &cli::InputArguments) -> Result<formats::png::Png, Error> {
+    #[cfg(feature = "http")]
+    if let Some(url) = &args.input_url {
+        return Ok(formats::png::Png::from_url(url)?);
+    }
+
     let extension = args.input_path.extension().ok_or_else(|| {
         cli::CliError::InvalidArgument(format!("Invalid path: {:?}", args.input_path))
     })?;
 
-    let picture = match extension.to_str() {
+    match extension.to_str() {
         Some(ex) => match ex {
-            "png" => formats::png::Png::from_path(args.input_path)?,
-            _ => todo!(),
-        },
-        None => {
-            eprint!("Failed to convert extension from OsStr, to &str.");
-            return Err(Box::new(cli::CliError::InvalidArgument(format!(
-                "Invalid path: {:?}",
+            "png" => Ok(formats::png::Png::from_path(&args.input_path)?),
+            _ => Err(cli::CliError::InvalidArgument(format!(
+                "Unsupported input format: {:?}",
                 args.input_path
-            ))));
-        }
-    };
-
-    Ok(())
+            ))
+            .into()),
+        },
+        None => Err(
+            cli::CliError::InvalidArgument(format!("Invalid path: {:?}", args.input_path)).into(),
+        ),
+    }
 }
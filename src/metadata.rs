@@ -0,0 +1,50 @@
+//! Metadata carried from one image file to another when converting, kept
+//! apart from any one format so each encoder can take what it can hold and
+//! leave the rest. PNG files hold all of it, PPM files none.
+//!
+//! '''
+//! let png = Png::from_path("./in.png")?;
+//! let options = EncodeOptions::new().metadata(png.metadata());
+//!
+//! fs::write("./out.png", Image::try_from(&png)?.encode_png(&options)?)?;
+//! '''
+
+use alloc::vec::Vec;
+
+use crate::formats::png::{ChunkType, PhysicalDimensions, TextChunk};
+
+/// What an image file says about its image, besides the pixels. The
+/// modification time isn't part of it, see EncodeOptions::time.
+///
+/// # Fields
+///
+/// * 'text' - Keywords and their text, e.g. a title or copyright notice,
+///   see TextChunk.
+/// * 'physical' - The intended size or shape of the pixels, see
+///   PhysicalDimensions.
+/// * 'exif' - EXIF data starting with its TIFF header, see Exif.
+/// * 'chunks' - Other PNG chunks, e.g. private ones, by type with their
+///   data. Only ancillary chunks marked safe to copy belong here, as
+///   the pixels they came with may have changed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metadata {
+    pub text: Vec<TextChunk>,
+    pub physical: Option<PhysicalDimensions>,
+    pub exif: Option<Vec<u8>>,
+    pub chunks: Vec<(ChunkType, Vec<u8>)>,
+}
+
+impl Metadata {
+    /// Creates a Metadata with nothing in it.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Whether there's no metadata at all.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+            && self.physical.is_none()
+            && self.exif.is_none()
+            && self.chunks.is_empty()
+    }
+}
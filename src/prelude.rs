@@ -0,0 +1,33 @@
+//! The types most programs using chameleon need, so they can be brought in
+//! with a single glob import:
+//!
+//! '''
+//! use chameleon::prelude::*;
+//!
+//! let png = Png::decoder().limits(Limits::new().max_width(4096)).decode(path)?;
+//! '''
+
+pub use crate::animation::{Disposal, Frame, Frames};
+pub use crate::buffer::{BufferKind, BufferProvider, DefaultBuffers};
+pub use crate::convert::{Dither, Round, Truncate};
+pub use crate::draw::{Blend, Font, FontError};
+pub use crate::error::{Error, ErrorKind};
+#[cfg(feature = "png")]
+pub use crate::exif::Exif;
+#[cfg(feature = "png")]
+pub use crate::fingerprint::Fingerprint;
+#[cfg(feature = "png")]
+pub use crate::formats::png::{
+    Background, ChecksumPolicy, ColorType, DecodeOptions, DecoderError, EncodeOptions,
+    FilterStrategy, Filters, Interlace, Limits, Palette, PhysicalDimensions, PhysicalUnit, Png,
+    PngInfo, TextChunk, Time, Transformations, Violation,
+};
+#[cfg(all(feature = "std", feature = "png"))]
+pub use crate::formats::png::{ChunkWriter, StreamEncoder};
+#[cfg(feature = "ppm")]
+pub use crate::formats::ppm::Ppm;
+pub use crate::generate::Pattern;
+pub use crate::image::{Analysis, Image, ImageError, ImageView, PixelFormat};
+#[cfg(feature = "png")]
+pub use crate::metadata::Metadata;
+pub use crate::scale::Insets;
@@ -0,0 +1,176 @@
+//! Python bindings, enabled with the python feature. The extension module is
+//! built with maturin from the pyproject.toml at the root of the repository:
+//!
+//! '''
+//! maturin develop --release
+//! '''
+//!
+//! Decoded images expose the numpy array interface, so numpy.asarray(image)
+//! gives a (height, width, 3) array of uint8 without copying the pixels.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use std::path::PathBuf;
+
+use pyo3::{
+    exceptions::{PyOSError, PyValueError},
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
+
+use crate::{
+    error::{Error, ErrorKind},
+    formats::{png::Png, ppm::Ppm},
+};
+
+/// A decoded image, as seen from Python.
+///
+/// # Fields
+///
+/// * 'width' - The width of the image in pixels.
+/// * 'height' - The height of the image in pixels.
+/// * 'data' - The pixels as RGB bytes, from left to right, top to bottom.
+#[pyclass(name = "Image", module = "chameleon", frozen)]
+pub struct PyImage {
+    #[pyo3(get)]
+    width: usize,
+    #[pyo3(get)]
+    height: usize,
+    #[pyo3(get)]
+    data: Py<PyBytes>,
+}
+
+#[pymethods]
+impl PyImage {
+    /// The number of samples in each pixel, always 3 as pixels are RGB.
+    #[getter]
+    fn channels(&self) -> usize {
+        3
+    }
+    /// The numpy array interface, describing data as a (height, width, 3)
+    /// array of unsigned bytes.
+    #[getter]
+    fn __array_interface__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let interface = PyDict::new(py);
+        interface.set_item("version", 3)?;
+        interface.set_item("shape", (self.height, self.width, 3))?;
+        interface.set_item("typestr", "|u1")?;
+        interface.set_item("data", self.data.clone_ref(py))?;
+        Ok(interface)
+    }
+    fn __repr__(&self) -> String {
+        format!("Image(width={}, height={})", self.width, self.height)
+    }
+}
+
+/// Raises an Error as the closest matching Python exception.
+fn to_py_err<E: Into<Error>>(error: E) -> PyErr {
+    let error = error.into();
+
+    match error.kind() {
+        ErrorKind::Io => PyOSError::new_err(error.to_string()),
+        _ => PyValueError::new_err(error.to_string()),
+    }
+}
+
+/// Decodes a Png into an Image.
+fn to_image(py: Python<'_>, png: &Png) -> PyResult<PyImage> {
+    let rgb = png.rgb().map_err(to_py_err)?;
+
+    let mut data = Vec::with_capacity(rgb.len() * 3);
+    for (r, g, b) in rgb {
+        data.extend_from_slice(&[r, g, b]);
+    }
+
+    Ok(PyImage {
+        width: png.dimensions.0,
+        height: png.dimensions.1,
+        data: PyBytes::new(py, &data).unbind(),
+    })
+}
+
+/// Decodes a PNG file held in memory.
+///
+/// # Arguments
+///
+/// * 'data' - The entire PNG file as bytes.
+///
+/// # Returns
+///
+/// The decoded Image, or raises ValueError if the file is invalid.
+#[pyfunction]
+fn decode(py: Python<'_>, data: &[u8]) -> PyResult<PyImage> {
+    let png = Png::decoder().decode_bytes(data).map_err(to_py_err)?;
+
+    to_image(py, &png)
+}
+
+/// Decodes the PNG file at the given path.
+///
+/// # Arguments
+///
+/// * 'path' - The path to the PNG file, a str or os.PathLike.
+///
+/// # Returns
+///
+/// The decoded Image, or raises OSError if the file can't be read and
+/// ValueError if it is invalid.
+#[pyfunction]
+fn decode_file(py: Python<'_>, path: PathBuf) -> PyResult<PyImage> {
+    let png = Png::from_path(path).map_err(to_py_err)?;
+
+    to_image(py, &png)
+}
+
+/// Encodes an Image as a binary PPM file, the only format chameleon can
+/// write so far.
+///
+/// # Arguments
+///
+/// * 'image' - The Image to encode.
+///
+/// # Returns
+///
+/// The PPM file as bytes.
+#[pyfunction]
+fn encode<'py>(py: Python<'py>, image: &PyImage) -> Bound<'py, PyBytes> {
+    let data = image.data.as_bytes(py);
+
+    let pixels = data
+        .chunks_exact(3)
+        .map(|p| (p[0], p[1], p[2]))
+        .collect::<Vec<(u8, u8, u8)>>();
+
+    let ppm = Ppm::build(&pixels, image.width, image.height);
+
+    PyBytes::new(py, &ppm.to_bytes())
+}
+
+/// Converts a PNG file held in memory into a binary PPM file.
+///
+/// # Arguments
+///
+/// * 'data' - The entire PNG file as bytes.
+///
+/// # Returns
+///
+/// The PPM file as bytes, or raises ValueError if the PNG file is invalid.
+#[pyfunction]
+fn convert<'py>(py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+    let image = decode(py, data)?;
+
+    Ok(encode(py, &image))
+}
+
+#[pymodule]
+fn chameleon(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyImage>()?;
+    m.add_function(wrap_pyfunction!(decode, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_file, m)?)?;
+    m.add_function(wrap_pyfunction!(encode, m)?)?;
+    m.add_function(wrap_pyfunction!(convert, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,199 @@
+//! Checks that a PNG file survives being decoded, encoded again with
+//! chameleon's encoder and decoded a second time, for validating pipelines
+//! that pass images through chameleon.
+//!
+//! '''
+//! let report = roundtrip::verify("./photo.png")?;
+//!
+//! assert!(report.is_exact(), "{report}");
+//! '''
+
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+use std::{fs, path::Path};
+
+use crate::{
+    convert,
+    error::Error,
+    formats::png::{DecoderError, EncodeOptions, Png},
+    image::{Image, ImageError, PixelFormat},
+};
+
+/// How far apart the samples of two images are.
+///
+/// # Fields
+///
+/// * 'samples' - The number of samples, counting each channel of each
+///   pixel, that differ.
+/// * 'max' - The largest difference between two samples.
+/// * 'psnr' - The peak signal to noise ratio in decibels, higher being
+///   closer. None when the images are identical, as it's infinite.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Difference {
+    pub samples: usize,
+    pub max: u16,
+    pub psnr: Option<f64>,
+}
+
+/// What a round trip through the encoder found.
+///
+/// # Fields
+///
+/// * 'dimensions' - The width and height in pixels.
+/// * 'format' - The PixelFormat the pixels were compared in, Rgba16 for 16
+///   bit images, otherwise Rgba8.
+/// * 'original_len' - The size of the original file in bytes.
+/// * 'encoded_len' - The size of the file chameleon encoded in bytes.
+/// * 'difference' - How far the pixels decoded from each file are apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Report {
+    pub dimensions: (usize, usize),
+    pub format: PixelFormat,
+    pub original_len: usize,
+    pub encoded_len: usize,
+    pub difference: Difference,
+}
+
+impl Report {
+    /// Whether every pixel came back exactly as it was.
+    pub fn is_exact(&self) -> bool {
+        self.difference.samples == 0
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} {:?}, {} bytes re-encoded as {}, ",
+            self.dimensions.0, self.dimensions.1, self.format, self.original_len, self.encoded_len
+        )?;
+        match self.difference.psnr {
+            None => write!(f, "lossless"),
+            Some(psnr) => write!(
+                f,
+                "{} samples differ by up to {}, PSNR {:.2} dB",
+                self.difference.samples, self.difference.max, psnr
+            ),
+        }
+    }
+}
+
+/// Decodes a PNG file, encodes it again with default EncodeOptions, decodes
+/// that, and compares the pixels, see verify_bytes.
+///
+/// # Arguments
+///
+/// * 'path' - The path of the PNG file.
+///
+/// # Returns
+///
+/// A result containing either the Report, or an Error if the file can't be
+/// read or either file can't be decoded.
+pub fn verify<P: AsRef<Path>>(path: P) -> Result<Report, Error> {
+    let path = path.as_ref();
+    let bytes = fs::read(path)?;
+
+    verify_bytes(&bytes, &EncodeOptions::new()).map_err(|e| match e {
+        Error::Decoder(e) => Error::Decoder(e.with_path(path)),
+        other => other,
+    })
+}
+
+/// Decodes a PNG file, encodes it again, decodes that, and compares the
+/// pixels. Alpha from tRNS is compared, and 16 bit images at their full
+/// precision. Transformations aren't applied.
+///
+/// # Arguments
+///
+/// * 'bytes' - The PNG file.
+/// * 'options' - The EncodeOptions to encode it again with.
+///
+/// # Returns
+///
+/// A result containing either the Report, or an Error if either file can't
+/// be decoded or the image can't be encoded.
+pub fn verify_bytes(bytes: &[u8], options: &EncodeOptions) -> Result<Report, Error> {
+    let png = Png::decoder().decode_bytes(bytes)?;
+    let original = decode(&png, png.rgba_format())?;
+
+    let encoded = original.encode_png(options)?;
+    let png = Png::decoder().decode_bytes(&encoded)?;
+    // Reducing the color type may have dropped 16 bit samples that fit in 8
+    // bits to 8 bits, so the second decode is brought back to the first's
+    // format.
+    let decoded = decode(&png, original.format)?;
+
+    Ok(Report {
+        dimensions: (original.width, original.height),
+        format: original.format,
+        original_len: bytes.len(),
+        encoded_len: encoded.len(),
+        difference: compare(&original, &decoded)?,
+    })
+}
+
+/// Compares the pixels of two images the same size and format.
+///
+/// # Returns
+///
+/// A result containing either the Difference, or an ImageError if the
+/// images' sizes or formats don't match.
+pub fn compare(a: &Image, b: &Image) -> Result<Difference, ImageError> {
+    a.check_size()?;
+    b.check_size()?;
+    if (a.width, a.height, a.format) != (b.width, b.height, b.format) {
+        return Err(ImageError::InvalidDimensions(b.width, b.height));
+    }
+
+    let samples = |image: &Image| -> Vec<u16> {
+        match image.format.bit_depth() {
+            16 => image
+                .data
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect(),
+            _ => image.data.iter().map(|&sample| sample as u16).collect(),
+        }
+    };
+    let (a_samples, b_samples) = (samples(a), samples(b));
+
+    let mut difference = Difference {
+        samples: 0,
+        max: 0,
+        psnr: None,
+    };
+    let mut squares = 0.0;
+    for (&a, &b) in a_samples.iter().zip(&b_samples) {
+        let error = a.abs_diff(b);
+        if error > 0 {
+            difference.samples += 1;
+            difference.max = difference.max.max(error);
+            squares += error as f64 * error as f64;
+        }
+    }
+
+    if difference.samples > 0 {
+        let peak = match a.format.bit_depth() {
+            16 => u16::MAX as f64,
+            _ => u8::MAX as f64,
+        };
+        let mean = squares / a_samples.len() as f64;
+        difference.psnr = Some(10.0 * (peak * peak / mean).log10());
+    }
+
+    Ok(difference)
+}
+
+/// Decodes every pixel of png as RGBA in the given format.
+fn decode(png: &Png, format: PixelFormat) -> Result<Image, Error> {
+    let (width, height) = png.dimensions;
+    let mut data = Vec::new();
+
+    png.rgba_rows(&mut |_, row| {
+        convert::convert_pixels(png.rgba_format(), format, row, &mut data)
+            .map_err(|_| DecoderError::Unexplainable)
+    })?;
+
+    Ok(Image::new(width, height, format, data)?)
+}
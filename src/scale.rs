@@ -0,0 +1,286 @@
+//! Shrinking images with a box filter, each output pixel is the average of
+//! the block of input pixels it covers. Alpha is premultiplied while
+//! averaging, so transparent pixels don't bleed their color into the edges
+//! of opaque ones.
+//!
+//! thumbnail goes straight from a PNG file to a smaller one, feeding each
+//! scanline into the filter as it's decoded rather than decoding the whole
+//! image first, which keeps memory down when serving galleries of large
+//! pictures:
+//!
+//! '''
+//! let small = scale::thumbnail(&fs::read("./photo.png")?, 256, &EncodeOptions::new())?;
+//! '''
+
+use alloc::{vec, vec::Vec};
+
+use crate::image::{Image, ImageError, PixelFormat};
+#[cfg(feature = "png")]
+use crate::{
+    error::Error,
+    formats::png::{EncodeOptions, Png},
+};
+
+/// Works out the size of a thumbnail, keeping the aspect ratio of the
+/// original. Images that already fit are left at their own size.
+///
+/// # Arguments
+///
+/// * 'width' - The width of the original image in pixels.
+/// * 'height' - The height of the original image in pixels.
+/// * 'max_dim' - The most pixels the thumbnail can have along either side,
+///   treated as 1 if 0.
+///
+/// # Returns
+///
+/// The width and height of the thumbnail, each at least 1 unless the
+/// original is empty.
+pub fn fit(width: usize, height: usize, max_dim: usize) -> (usize, usize) {
+    let max_dim = max_dim.max(1);
+    let longest = width.max(height);
+    if longest <= max_dim {
+        return (width, height);
+    }
+
+    // Round to the nearest pixel, in u128 so huge images can't overflow.
+    let shrink = |side: usize| {
+        let scaled = (side as u128 * max_dim as u128 + longest as u128 / 2) / longest as u128;
+        (scaled as usize).max(1)
+    };
+
+    (shrink(width), shrink(height))
+}
+
+/// Decodes a PNG file and encodes a thumbnail of it no larger than max_dim
+/// along either side, see Png::thumbnail.
+///
+/// # Arguments
+///
+/// * 'png' - The PNG file as bytes.
+/// * 'max_dim' - The most pixels the thumbnail can have along either side.
+/// * 'options' - The EncodeOptions to encode the thumbnail with.
+///
+/// # Returns
+///
+/// A result containing either the thumbnail as a PNG file or an Error if
+/// the file can't be decoded.
+#[cfg(feature = "png")]
+pub fn thumbnail(png: &[u8], max_dim: usize, options: &EncodeOptions) -> Result<Vec<u8>, Error> {
+    let image = Png::decoder().decode_bytes(png)?.thumbnail(max_dim)?;
+
+    Ok(image.encode_png(options)?)
+}
+
+/// The borders of an image that Image::scale_9slice keeps at their own size,
+/// in pixels. The corners they make are copied as they are, the edges
+/// between them are stretched along their length and the center both ways.
+///
+/// # Fields
+///
+/// * 'left' - The width of the left edge.
+/// * 'top' - The height of the top edge.
+/// * 'right' - The width of the right edge.
+/// * 'bottom' - The height of the bottom edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Insets {
+    pub left: usize,
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+}
+
+impl Insets {
+    /// Creates Insets the same on every side.
+    pub fn uniform(inset: usize) -> Self {
+        Self {
+            left: inset,
+            top: inset,
+            right: inset,
+            bottom: inset,
+        }
+    }
+}
+
+/// Scales an image with 9-slice scaling, see Image::scale_9slice.
+pub(crate) fn nine_slice(
+    image: &Image,
+    insets: Insets,
+    width: usize,
+    height: usize,
+) -> Result<Image, ImageError> {
+    image.check_size()?;
+    let bytes = image.format.bytes_per_pixel();
+    let len = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(bytes))
+        .ok_or(ImageError::InvalidDimensions(width, height))?;
+
+    let columns = slice(image.width, insets.left, insets.right, width)
+        .ok_or(ImageError::InvalidDimensions(width, height))?;
+    let rows = slice(image.height, insets.top, insets.bottom, height)
+        .ok_or(ImageError::InvalidDimensions(width, height))?;
+
+    let mut data = Vec::with_capacity(len);
+    for &y in &rows {
+        let row = &image.data[y * image.width * bytes..][..image.width * bytes];
+        for &x in &columns {
+            data.extend_from_slice(&row[x * bytes..][..bytes]);
+        }
+    }
+
+    Image::new(width, height, image.format, data)
+}
+
+/// Maps each position along one side of a 9-slice scaled image to the
+/// position it's copied from, the start and end kept and the middle
+/// stretched to fill the rest with the nearest pixel.
+///
+/// # Returns
+///
+/// The source position of each position, or None if the insets don't fit
+/// in either size, or the middle has to be filled but is empty.
+fn slice(source_size: usize, start: usize, end: usize, size: usize) -> Option<Vec<usize>> {
+    let middle = source_size.checked_sub(start.checked_add(end)?)?;
+    let stretched = size.checked_sub(start + end)?;
+    if middle == 0 && stretched > 0 {
+        return None;
+    }
+
+    let middle = (0..stretched).map(|i| start + scale(i * 2 + 1, middle, stretched * 2));
+    Some(
+        (0..start)
+            .chain(middle)
+            .chain(source_size - end..source_size)
+            .collect(),
+    )
+}
+
+/// A box filter fed one row of the input at a time, in any order. Only the
+/// sums for the output are kept, never the input pixels.
+pub(crate) struct BoxFilter {
+    width: usize,
+    height: usize,
+    format: PixelFormat,
+    // The output column of each input column.
+    columns: Vec<usize>,
+    // How many input columns and rows fall in each output column and row.
+    column_counts: Vec<u64>,
+    row_counts: Vec<u64>,
+    source_height: usize,
+    // The channel totals of each output pixel, every channel but alpha is
+    // premultiplied by it.
+    sums: Vec<u64>,
+}
+
+impl BoxFilter {
+    /// Creates a filter shrinking an image of source_width by source_height
+    /// to width by height, neither of which can be larger than the source.
+    pub(crate) fn new(
+        source_width: usize,
+        source_height: usize,
+        width: usize,
+        height: usize,
+        format: PixelFormat,
+    ) -> Self {
+        let columns: Vec<usize> = (0..source_width)
+            .map(|x| scale(x, width, source_width))
+            .collect();
+
+        let mut column_counts = vec![0; width];
+        for &column in &columns {
+            column_counts[column] += 1;
+        }
+        let mut row_counts = vec![0; height];
+        for y in 0..source_height {
+            row_counts[scale(y, height, source_height)] += 1;
+        }
+
+        Self {
+            width,
+            height,
+            format,
+            columns,
+            column_counts,
+            row_counts,
+            source_height,
+            sums: vec![0; width * height * format.channels()],
+        }
+    }
+    /// Adds a row of the source image to the output.
+    ///
+    /// # Arguments
+    ///
+    /// * 'y' - The row's position in the source image.
+    /// * 'row' - The row's pixels, in the filter's PixelFormat.
+    pub(crate) fn add_row(&mut self, y: usize, row: &[u8]) {
+        let channels = self.format.channels();
+        let bytes = self.format.bytes_per_pixel();
+        let wide = self.format.bit_depth() == 16;
+        let start = scale(y, self.height, self.source_height) * self.width;
+
+        for (pixel, &column) in row.chunks_exact(bytes).zip(&self.columns) {
+            let sums = &mut self.sums[(start + column) * channels..][..channels];
+            let sample = |i: usize| match wide {
+                true => u16::from_be_bytes([pixel[i * 2], pixel[i * 2 + 1]]) as u64,
+                false => pixel[i] as u64,
+            };
+
+            if self.format.has_alpha() {
+                let alpha = sample(channels - 1);
+                for (i, sum) in sums[..channels - 1].iter_mut().enumerate() {
+                    *sum += sample(i) * alpha;
+                }
+                sums[channels - 1] += alpha;
+            } else {
+                for (i, sum) in sums.iter_mut().enumerate() {
+                    *sum += sample(i);
+                }
+            }
+        }
+    }
+    /// Averages the sums into the finished Image.
+    pub(crate) fn finish(self) -> Image {
+        let channels = self.format.channels();
+        let wide = self.format.bit_depth() == 16;
+        let mut data = Vec::with_capacity(self.width * self.height * self.format.bytes_per_pixel());
+        let mut push = |value: u64| match wide {
+            true => data.extend_from_slice(&(value as u16).to_be_bytes()),
+            false => data.push(value as u8),
+        };
+
+        for (i, sums) in self.sums.chunks_exact(channels).enumerate() {
+            let count = self.row_counts[i / self.width] * self.column_counts[i % self.width];
+            let average = |sum: u64, count: u64| match count {
+                0 => 0,
+                _ => (sum + count / 2) / count,
+            };
+
+            if self.format.has_alpha() {
+                // Dividing by the total alpha undoes the premultiply.
+                let alpha = sums[channels - 1];
+                for &sum in &sums[..channels - 1] {
+                    push(average(sum, alpha));
+                }
+                push(average(alpha, count));
+            } else {
+                for &sum in sums {
+                    push(average(sum, count));
+                }
+            }
+        }
+
+        Image {
+            width: self.width,
+            height: self.height,
+            format: self.format,
+            data,
+        }
+    }
+}
+
+/// Maps a position in the source to the output, in u64 so it can't overflow
+/// on 32 bit targets.
+fn scale(position: usize, size: usize, source_size: usize) -> usize {
+    (position as u64 * size as u64 / source_size as u64) as usize
+}
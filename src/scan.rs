@@ -0,0 +1,272 @@
+//! A fast audit of every image in a directory tree. Only the chunks are
+//! parsed and checked, the image data is never decompressed, so large asset
+//! repositories can be checked for broken files and missing metadata in
+//! about the time it takes to read them.
+//!
+//! '''
+//! let entries = scan::scan_dir("./assets")?;
+//!
+//! fs::write("./report.json", scan::to_json(&entries))?;
+//! '''
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Display, Write};
+use std::{fs, io, path::Path, path::PathBuf};
+
+use crate::formats::png::{ChecksumPolicy, ChunkType, ColorType, DecodeOptions, Interlace, Png};
+
+/// The header of a scanned image.
+///
+/// # Fields
+///
+/// * 'dimensions' - The width and height in pixels.
+/// * 'color_type' - The ColorType from IHDR.
+/// * 'bit_depth' - The number of bits in each sample.
+/// * 'interlace' - The Interlace method from IHDR.
+/// * 'chunks' - The type of every ancillary chunk in the file, in order,
+///   e.g. tIME or tEXt, showing which metadata is present.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanInfo {
+    pub dimensions: (usize, usize),
+    pub color_type: ColorType,
+    pub bit_depth: u8,
+    pub interlace: Interlace,
+    pub chunks: Vec<String>,
+}
+
+/// What scanning one file found.
+///
+/// # Fields
+///
+/// * 'path' - The path of the file.
+/// * 'size' - The size of the file in bytes.
+/// * 'info' - The header of the image, or None if it couldn't be read.
+/// * 'warnings' - Problems that didn't stop the header being read, e.g. a
+///   chunk with a bad CRC or a missing IEND chunk.
+/// * 'error' - Why the header couldn't be read, if it couldn't.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanEntry {
+    pub path: PathBuf,
+    pub size: usize,
+    pub info: Option<ScanInfo>,
+    pub warnings: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl Display for ScanEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.path.display())?;
+        match (&self.info, &self.error) {
+            (Some(info), _) => write!(
+                f,
+                "{}x{}, {} at {} bits per sample, interlace {}",
+                info.dimensions.0,
+                info.dimensions.1,
+                info.color_type,
+                info.bit_depth,
+                info.interlace
+            )?,
+            (None, Some(error)) => write!(f, "{error}")?,
+            (None, None) => write!(f, "unreadable")?,
+        }
+        for warning in &self.warnings {
+            write!(f, "\n  warning: {warning}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Scans every PNG file in a directory and its subdirectories, see
+/// scan_file. Other files are skipped, as are symbolic links to
+/// directories, so a link back up the tree can't loop forever.
+///
+/// # Arguments
+///
+/// * 'dir' - The directory to scan.
+///
+/// # Returns
+///
+/// A result containing either a ScanEntry for each PNG file, sorted by
+/// path, or an io::Error if a directory couldn't be listed.
+pub fn scan_dir<P: AsRef<Path>>(dir: P) -> Result<Vec<ScanEntry>, io::Error> {
+    let mut paths = Vec::new();
+    let mut dirs = Vec::from([dir.as_ref().to_path_buf()]);
+
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            // The entry's own type doesn't follow symbolic links, path's does.
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else if !path.is_dir()
+                && path
+                    .extension()
+                    .is_some_and(|ex| ex.eq_ignore_ascii_case("png"))
+            {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+
+    Ok(paths.iter().map(scan_file).collect())
+}
+
+/// Reads the chunks of a PNG file without decompressing the image data,
+/// checking the CRC of each and the structure of the file.
+///
+/// # Arguments
+///
+/// * 'path' - The path of the file to scan.
+///
+/// # Returns
+///
+/// The ScanEntry for the file. Failures are recorded in it rather than
+/// returned, so one bad file doesn't stop a scan.
+pub fn scan_file<P: AsRef<Path>>(path: P) -> ScanEntry {
+    let mut entry = ScanEntry {
+        path: path.as_ref().to_path_buf(),
+        size: 0,
+        info: None,
+        warnings: Vec::new(),
+        error: None,
+    };
+
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            entry.error = Some(e.to_string());
+            return entry;
+        }
+    };
+    entry.size = bytes.len();
+
+    // Parse as strictly as possible first, then loosen until the header can
+    // be read. The first failure is the most specific about what's wrong.
+    let attempts = [
+        Png::decoder().strict(true),
+        Png::decoder()
+            .strict(true)
+            .checksums(ChecksumPolicy::Ignore),
+        Png::decoder().checksums(ChecksumPolicy::Ignore),
+    ];
+    let mut first_error = None;
+    let png =
+        attempts.iter().find_map(
+            |options: &DecodeOptions| match options.decode_bytes(&bytes) {
+                Ok(png) => Some(png),
+                Err(e) => {
+                    first_error.get_or_insert(e.to_string());
+                    None
+                }
+            },
+        );
+
+    let Some(png) = png else {
+        entry.error = first_error;
+        return entry;
+    };
+    entry.warnings.extend(first_error);
+    entry
+        .warnings
+        .extend(png.validate().iter().map(ToString::to_string));
+
+    entry.info = Some(ScanInfo {
+        dimensions: png.dimensions,
+        color_type: png.color_type,
+        bit_depth: png.bit_depth,
+        interlace: png.interlace,
+        chunks: png
+            .data
+            .ancillary_chunks
+            .iter()
+            .filter(|chunk| chunk.ctype != ChunkType::IEND)
+            .map(|chunk| chunk.ctype.as_str().to_string())
+            .collect(),
+    });
+
+    entry
+}
+
+/// Writes scan results as a JSON array with an object for each file, for
+/// the report given to 'chameleon scan --report'.
+///
+/// # Arguments
+///
+/// * 'entries' - The ScanEntry of each file.
+///
+/// # Returns
+///
+/// The JSON text.
+pub fn to_json(entries: &[ScanEntry]) -> String {
+    let mut json = String::from("[");
+
+    for (i, entry) in entries.iter().enumerate() {
+        json.push_str(if i == 0 { "\n  {" } else { ",\n  {" });
+        json.push_str("\n    \"path\": ");
+        json_string(&mut json, &entry.path.to_string_lossy());
+        let _ = write!(json, ",\n    \"size\": {}", entry.size);
+
+        if let Some(info) = &entry.info {
+            let _ = write!(
+                json,
+                ",\n    \"format\": \"PNG\",\n    \"width\": {},\n    \"height\": {},\n    \"bit_depth\": {}",
+                info.dimensions.0, info.dimensions.1, info.bit_depth
+            );
+            json.push_str(",\n    \"color_type\": ");
+            json_string(&mut json, &info.color_type.to_string());
+            json.push_str(",\n    \"interlace\": ");
+            json_string(&mut json, &info.interlace.to_string());
+            json.push_str(",\n    \"chunks\": ");
+            json_array(&mut json, &info.chunks);
+        }
+
+        json.push_str(",\n    \"warnings\": ");
+        json_array(&mut json, &entry.warnings);
+        json.push_str(",\n    \"error\": ");
+        match &entry.error {
+            Some(error) => json_string(&mut json, error),
+            None => json.push_str("null"),
+        }
+        json.push_str("\n  }");
+    }
+
+    json.push_str(if entries.is_empty() { "]\n" } else { "\n]\n" });
+    json
+}
+
+/// Appends an array of JSON strings.
+fn json_array(json: &mut String, values: &[String]) {
+    json.push('[');
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            json.push_str(", ");
+        }
+        json_string(json, value);
+    }
+    json.push(']');
+}
+
+/// Appends a quoted JSON string, escaping it as 7 of RFC 8259 requires.
+fn json_string(json: &mut String, value: &str) {
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
@@ -0,0 +1,677 @@
+//! Processing images too large to hold in memory. Rows are decoded one at a
+//! time with Png::rgba_rows, gathered into bands of tiles for each
+//! operation, and written out with a StreamEncoder as soon as they're
+//! finished, so only a few bands of rows are ever held, however large the
+//! image.
+//!
+//! '''
+//! let png = Png::from_path("./scan.png")?;
+//! let file = BufWriter::new(File::create("./small.png")?);
+//!
+//! TileProcessor::new()
+//!     .then(Convolve::box_blur(1))
+//!     .then(MapColors::new(|[r, g, b, a]| [b, g, r, a]))
+//!     .then(Exposure::new(0.5))
+//!     .resize(png.dimensions.0 / 4, png.dimensions.1 / 4)
+//!     .run(&png, file, &EncodeOptions::new())?;
+//! '''
+
+use alloc::{boxed::Box, vec, vec::Vec};
+use std::{io::Write, sync::OnceLock};
+
+use crate::{
+    color,
+    convert::{self, Round},
+    error::Error,
+    formats::png::{DecoderError, EncodeOptions, Png, StreamEncoder},
+    image::{ImageError, ImageView, PixelFormat},
+    scale::BoxFilter,
+};
+
+/// The width and height of a tile when none is given.
+const DEFAULT_TILE_SIZE: usize = 256;
+
+/// A rectangle of the image handed to a TileOp, with the pixels around it
+/// the op asked for.
+///
+/// # Fields
+///
+/// * 'x' - The column of the tile's left edge in the image.
+/// * 'y' - The row of the tile's top edge in the image.
+/// * 'width' - The width of the tile in pixels.
+/// * 'height' - The height of the tile in pixels.
+/// * 'context' - The tile and up to TileOp::margin pixels on every side of
+///   it, fewer where the image ends.
+/// * 'offset' - Where the tile's top left pixel is in context.
+/// * 'image' - The width and height of the whole image, for ops that
+///   depend on where a pixel is in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile<'a> {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub context: ImageView<'a>,
+    pub offset: (usize, usize),
+    pub image: (usize, usize),
+}
+
+impl Tile<'_> {
+    /// Reads a pixel of the context as 16 bit RGBA, clamping positions
+    /// outside of it to the nearest edge.
+    ///
+    /// # Arguments
+    ///
+    /// * 'x' - The column relative to the tile's left edge.
+    /// * 'y' - The row relative to the tile's top edge.
+    ///
+    /// # Returns
+    ///
+    /// The red, green, blue and alpha of the pixel.
+    pub fn pixel(&self, x: isize, y: isize) -> [u16; 4] {
+        let clamp = |position: isize, offset: usize, size: usize| {
+            (offset as isize + position).clamp(0, size as isize - 1) as usize
+        };
+        let x = clamp(x, self.offset.0, self.context.width);
+        let y = clamp(y, self.offset.1, self.context.height);
+        let bytes = self.context.format.bytes_per_pixel();
+        let start = y * self.context.stride + x * bytes;
+
+        convert::rgba16(
+            self.context.format,
+            &self.context.data[start..start + bytes],
+        )
+    }
+}
+
+/// An operation run over an image one tile at a time by a TileProcessor.
+pub trait TileOp {
+    /// The pixels of context the op needs on every side of a tile, e.g. the
+    /// radius of a convolution kernel. Defaults to 0.
+    fn margin(&self) -> usize {
+        0
+    }
+    /// Processes one tile.
+    ///
+    /// # Arguments
+    ///
+    /// * 'tile' - The Tile to process.
+    /// * 'out' - The buffer to append the processed pixels to, width by
+    ///   height of them in the format of tile.context, row by row.
+    fn apply(&mut self, tile: &Tile, out: &mut Vec<u8>);
+}
+
+/// Changes the color of every pixel on its own, e.g. to adjust levels or
+/// swap channels. The function is given and returns 16 bit RGBA.
+pub struct MapColors<F> {
+    map: F,
+}
+
+impl<F: FnMut([u16; 4]) -> [u16; 4]> MapColors<F> {
+    pub fn new(map: F) -> Self {
+        Self { map }
+    }
+}
+
+impl<F: FnMut([u16; 4]) -> [u16; 4]> TileOp for MapColors<F> {
+    fn apply(&mut self, tile: &Tile, out: &mut Vec<u8>) {
+        for y in 0..tile.height as isize {
+            for x in 0..tile.width as isize {
+                let color = (self.map)(tile.pixel(x, y));
+                convert::push(tile.context.format, color, 0, out, &mut Round);
+            }
+        }
+    }
+}
+
+/// Convolves the image with a square kernel, e.g. to blur or sharpen it.
+/// Colors are weighted by alpha, so transparent pixels don't bleed into
+/// opaque ones, and pixels past the edge of the image repeat the edge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Convolve {
+    size: usize,
+    weights: Vec<f32>,
+}
+
+impl Convolve {
+    /// Creates a Convolve from a kernel's weights.
+    ///
+    /// # Arguments
+    ///
+    /// * 'size' - The width and height of the kernel, which must be odd so
+    ///   it has a center.
+    /// * 'weights' - The size * size weights from left to right, top to
+    ///   bottom. They should add up to 1 to keep the brightness.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the Convolve, or an ImageError if size is
+    /// even or there aren't size * size weights.
+    pub fn new(size: usize, weights: &[f32]) -> Result<Self, ImageError> {
+        if size.is_multiple_of(2) {
+            return Err(ImageError::InvalidDimensions(size, size));
+        }
+        if weights.len() != size * size {
+            return Err(ImageError::BufferSize(size * size, weights.len()));
+        }
+
+        Ok(Self {
+            size,
+            weights: weights.to_vec(),
+        })
+    }
+    /// Creates a blur averaging every pixel within radius of each pixel.
+    pub fn box_blur(radius: usize) -> Self {
+        let size = radius * 2 + 1;
+
+        Self {
+            size,
+            weights: vec![1.0 / (size * size) as f32; size * size],
+        }
+    }
+}
+
+impl TileOp for Convolve {
+    fn margin(&self) -> usize {
+        self.size / 2
+    }
+    fn apply(&mut self, tile: &Tile, out: &mut Vec<u8>) {
+        let radius = self.margin() as isize;
+
+        for y in 0..tile.height as isize {
+            for x in 0..tile.width as isize {
+                let mut sums = [0.0f32; 4];
+                // The colors without weighting by alpha, for when it's all
+                // transparent.
+                let mut plain = [0.0f32; 3];
+
+                for (i, &weight) in self.weights.iter().enumerate() {
+                    let dx = (i % self.size) as isize - radius;
+                    let dy = (i / self.size) as isize - radius;
+                    let [r, g, b, a] = tile.pixel(x + dx, y + dy).map(|s| s as f32);
+
+                    let alpha = a * weight;
+                    for (channel, value) in [r, g, b].into_iter().enumerate() {
+                        sums[channel] += value * alpha;
+                        plain[channel] += value * weight;
+                    }
+                    sums[3] += alpha;
+                }
+
+                // Dividing by the total alpha undoes the weighting.
+                let sample = |sum: f32| (sum + 0.5).clamp(0.0, u16::MAX as f32) as u16;
+                let color = match sums[3] > 0.0 {
+                    true => [0, 1, 2].map(|i| sample(sums[i] / sums[3])),
+                    false => plain.map(sample),
+                };
+                let pixel = [color[0], color[1], color[2], sample(sums[3])];
+                convert::push(tile.context.format, pixel, 0, out, &mut Round);
+            }
+        }
+    }
+}
+
+/// Brightens or darkens the image by a number of stops, each one doubling
+/// or halving the light, the way a longer or shorter exposure would.
+/// Colors are scaled in linear light and clipped at white.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Exposure {
+    gain: f32,
+}
+
+impl Exposure {
+    /// Creates an Exposure changing the image by stops, brighter when
+    /// positive.
+    pub fn new(stops: f32) -> Self {
+        Self { gain: stop(stops) }
+    }
+}
+
+impl TileOp for Exposure {
+    fn apply(&mut self, tile: &Tile, out: &mut Vec<u8>) {
+        let gains = [self.gain; 3];
+        scale_light(tile, out, |_, _| gains);
+    }
+}
+
+/// Corrects the white balance of the image by scaling its red, green and
+/// blue in linear light, e.g. to take the orange cast out of a photo taken
+/// under tungsten lights.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WhiteBalance {
+    gains: [f32; 3],
+}
+
+impl WhiteBalance {
+    /// Creates a WhiteBalance from how far to move the colors along the
+    /// two axes white balance is usually set on.
+    ///
+    /// # Arguments
+    ///
+    /// * 'temperature' - Stops to move from blue towards yellow, warmer
+    ///   when positive. Red is scaled up by half of it and blue
+    ///   down by half.
+    /// * 'tint' - Stops to move from green towards magenta, green being
+    ///   scaled down by it.
+    pub fn new(temperature: f32, tint: f32) -> Self {
+        Self::gains([
+            stop(temperature / 2.0),
+            stop(-tint),
+            stop(-temperature / 2.0),
+        ])
+    }
+    /// Creates a WhiteBalance from the factor to scale the linear light of
+    /// red, green and blue by.
+    pub fn gains(gains: [f32; 3]) -> Self {
+        Self { gains }
+    }
+}
+
+impl TileOp for WhiteBalance {
+    fn apply(&mut self, tile: &Tile, out: &mut Vec<u8>) {
+        let gains = self.gains;
+        scale_light(tile, out, |_, _| gains);
+    }
+}
+
+/// Darkens the image towards its corners, like the falloff of a lens, or
+/// with a negative strength brightens them to correct that falloff. Light
+/// is scaled by 1 - strength * d^2 in linear light, d being how far a pixel
+/// is from the center of the image, 0 there and 1 in the corners.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vignette {
+    strength: f32,
+}
+
+impl Vignette {
+    /// Creates a Vignette taking strength of the light away in the corners,
+    /// 1.0 leaving them black.
+    pub fn new(strength: f32) -> Self {
+        Self { strength }
+    }
+}
+
+impl TileOp for Vignette {
+    fn apply(&mut self, tile: &Tile, out: &mut Vec<u8>) {
+        let (width, height) = (tile.image.0 as f32, tile.image.1 as f32);
+        let corner = (width * width + height * height) / 4.0;
+
+        scale_light(tile, out, |x, y| {
+            let dx = x as f32 + 0.5 - width / 2.0;
+            let dy = y as f32 + 0.5 - height / 2.0;
+            let gain = (1.0 - self.strength * (dx * dx + dy * dy) / corner).max(0.0);
+            [gain; 3]
+        });
+    }
+}
+
+/// The factor stops of light scale by.
+fn stop(stops: f32) -> f32 {
+    color::powf(2.0, stops as f64) as f32
+}
+
+/// Scales the red, green and blue of every pixel of a tile in linear light,
+/// by the gains gains gives for its column and row in the image. Alpha is
+/// kept as it is.
+fn scale_light(tile: &Tile, out: &mut Vec<u8>, mut gains: impl FnMut(usize, usize) -> [f32; 3]) {
+    let linear = linear();
+
+    for y in 0..tile.height {
+        for x in 0..tile.width {
+            let mut pixel = tile.pixel(x as isize, y as isize);
+            let gains = gains(tile.x + x, tile.y + y);
+
+            for (sample, gain) in pixel.iter_mut().zip(gains) {
+                let value = linear[*sample as usize] * gain;
+                // The nearest 16 bit sample to value.
+                let above = linear.partition_point(|&l| l < value);
+                *sample = match above {
+                    0 => 0,
+                    above if above == linear.len() => u16::MAX,
+                    above if value - linear[above - 1] < linear[above] - value => above as u16 - 1,
+                    above => above as u16,
+                };
+            }
+            convert::push(tile.context.format, pixel, 0, out, &mut Round);
+        }
+    }
+}
+
+/// The linear intensity of every 16 bit sRGB sample, see color, worked out
+/// the first time it's needed.
+fn linear() -> &'static [f32] {
+    static LINEAR: OnceLock<Vec<f32>> = OnceLock::new();
+
+    LINEAR.get_or_init(|| {
+        (0..=u16::MAX)
+            .map(|sample| {
+                let value = sample as f64 / u16::MAX as f64;
+                let linear = match value <= 0.04045 {
+                    true => value / 12.92,
+                    false => color::powf((value + 0.055) / 1.055, 2.4),
+                };
+                linear as f32
+            })
+            .collect()
+    })
+}
+
+/// Runs TileOps over a PNG file, and optionally shrinks it, writing the
+/// result to a new PNG file without ever holding either image whole.
+/// Pixels are processed as RGBA, at 16 bits for 16 bit images.
+///
+/// Each op holds about tile_size plus twice its margin rows of the image,
+/// and the decoder a window of the decompressed data. Interlaced images
+/// are the exception, every pass has to be decoded before the first row is
+/// whole, see Png::rgba_rows.
+///
+/// # Examples
+///
+/// '''
+/// let sharpen = Convolve::new(3, &[0.0, -1.0, 0.0, -1.0, 5.0, -1.0, 0.0, -1.0, 0.0])?;
+///
+/// TileProcessor::new().tile_size(512).then(sharpen).run(&png, file, &EncodeOptions::new())?;
+/// '''
+pub struct TileProcessor {
+    tile_size: usize,
+    ops: Vec<Box<dyn TileOp>>,
+    resize: Option<(usize, usize)>,
+    format: Option<PixelFormat>,
+}
+
+impl TileProcessor {
+    /// Creates a TileProcessor with no ops and 256 by 256 tiles.
+    pub fn new() -> Self {
+        Self {
+            tile_size: DEFAULT_TILE_SIZE,
+            ops: Vec::new(),
+            resize: None,
+            format: None,
+        }
+    }
+    /// Sets the width and height of each tile, treated as 1 if 0.
+    pub fn tile_size(mut self, tile_size: usize) -> Self {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+    /// Adds an op, run on the output of the ops added before it.
+    pub fn then<T: TileOp + 'static>(mut self, op: T) -> Self {
+        self.ops.push(Box::new(op));
+        self
+    }
+    /// Shrinks the image with a box filter after every op has run, see
+    /// Image::downscale.
+    pub fn resize(mut self, width: usize, height: usize) -> Self {
+        self.resize = Some((width, height));
+        self
+    }
+    /// Sets the PixelFormat the output is written in. Defaults to
+    /// Png::rgba_format of the input.
+    pub fn format(mut self, format: PixelFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+    /// Decodes png, runs every op and writes the result as a PNG file.
+    /// Transformations aren't applied.
+    ///
+    /// # Arguments
+    ///
+    /// * 'png' - The image to process.
+    /// * 'writer' - Where to write the new PNG file.
+    /// * 'options' - The EncodeOptions to encode with, see StreamEncoder.
+    ///
+    /// # Returns
+    ///
+    /// A result containing either the writer, or an Error if the image
+    /// can't be decoded, the new size is larger than the image, an op gives
+    /// back the wrong number of pixels, or writing fails.
+    pub fn run<W: Write>(
+        &mut self,
+        png: &Png,
+        writer: W,
+        options: &EncodeOptions,
+    ) -> Result<W, Error> {
+        let (width, height) = png.dimensions;
+        let format = png.rgba_format();
+        let (out_width, out_height) = self.resize.unwrap_or((width, height));
+        if !(1..=width).contains(&out_width) || !(1..=height).contains(&out_height) {
+            return Err(ImageError::InvalidDimensions(out_width, out_height).into());
+        }
+
+        let out_format = self.format.unwrap_or(format);
+        let mut encoder = StreamEncoder::new(writer, out_width, out_height, out_format, options)?;
+        let mut converted = Vec::new();
+        let mut write = |row: &[u8]| -> Result<(), Error> {
+            converted.clear();
+            convert::convert_pixels(format, out_format, row, &mut converted)?;
+            encoder.write_row(&converted)
+        };
+
+        let mut resize = Resize::new((width, height), (out_width, out_height), format);
+        let tile_size = self.tile_size;
+        let mut stages = self
+            .ops
+            .iter_mut()
+            .map(|op| Stage::new(op.as_mut(), (width, height), format, tile_size))
+            .collect::<Vec<_>>();
+
+        // Errors from the ops and the encoder stop the decode, and are given
+        // back in place of the DecoderError that stops it with.
+        let mut failure = None;
+        let result = png.rgba_rows(&mut |_, row| {
+            feed(&mut stages, row, &mut |row| resize.push(row, &mut write)).map_err(|e| {
+                failure = Some(e);
+                DecoderError::Cancelled
+            })
+        });
+        if let Some(e) = failure {
+            return Err(e);
+        }
+        result?;
+
+        encoder.finish()
+    }
+}
+
+impl Default for TileProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hands a row to the first stage, whose finished rows go to the next and
+/// so on, the last stage's going to sink.
+fn feed(
+    stages: &mut [Stage],
+    row: &[u8],
+    sink: &mut dyn FnMut(&[u8]) -> Result<(), Error>,
+) -> Result<(), Error> {
+    match stages.split_first_mut() {
+        Some((stage, rest)) => stage.push(row, &mut |row| feed(rest, row, sink)),
+        None => sink(row),
+    }
+}
+
+/// One op's share of a TileProcessor, holding the rows of the image its
+/// next band of tiles needs.
+struct Stage<'a> {
+    op: &'a mut dyn TileOp,
+    margin: usize,
+    dimensions: (usize, usize),
+    format: PixelFormat,
+    tile_size: usize,
+    // The rows held, from first_row down.
+    rows: Vec<u8>,
+    first_row: usize,
+    received: usize,
+    // The first row of the next band.
+    next_row: usize,
+    band: Vec<u8>,
+    tile: Vec<u8>,
+}
+
+impl<'a> Stage<'a> {
+    fn new(
+        op: &'a mut dyn TileOp,
+        dimensions: (usize, usize),
+        format: PixelFormat,
+        tile_size: usize,
+    ) -> Self {
+        Self {
+            margin: op.margin(),
+            op,
+            dimensions,
+            format,
+            tile_size,
+            rows: Vec::new(),
+            first_row: 0,
+            received: 0,
+            next_row: 0,
+            band: Vec::new(),
+            tile: Vec::new(),
+        }
+    }
+    /// Takes the next row of the image, processing every band it completes
+    /// and handing the finished rows to sink.
+    fn push(
+        &mut self,
+        row: &[u8],
+        sink: &mut dyn FnMut(&[u8]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let (width, height) = self.dimensions;
+        let row_len = width * self.format.bytes_per_pixel();
+        self.rows.extend_from_slice(row);
+        self.received += 1;
+
+        while self.next_row < height {
+            let band_end = (self.next_row + self.tile_size).min(height);
+            // The rows below the band the tiles need as context.
+            if self.received < (band_end + self.margin).min(height) {
+                break;
+            }
+
+            self.process(band_end)?;
+            for row in self.band.chunks_exact(row_len) {
+                sink(row)?;
+            }
+            self.next_row = band_end;
+
+            // Keep only the rows above the next band the tiles need.
+            let keep = self.next_row.saturating_sub(self.margin);
+            self.rows.drain(..(keep - self.first_row) * row_len);
+            self.first_row = keep;
+        }
+
+        Ok(())
+    }
+    /// Runs the op on every tile of the band from next_row to band_end,
+    /// leaving the rows in self.band.
+    fn process(&mut self, band_end: usize) -> Result<(), Error> {
+        let (width, height) = self.dimensions;
+        let bytes = self.format.bytes_per_pixel();
+        let row_len = width * bytes;
+        let band_height = band_end - self.next_row;
+
+        let top = self.next_row.saturating_sub(self.margin);
+        let bottom = (band_end + self.margin).min(height);
+        let start = (top - self.first_row) * row_len;
+        let rows = ImageView::new(
+            width,
+            bottom - top,
+            self.format,
+            row_len,
+            &self.rows[start..start + (bottom - top) * row_len],
+        )?;
+
+        self.band.clear();
+        self.band.resize(band_height * row_len, 0);
+
+        for x in (0..width).step_by(self.tile_size) {
+            let tile_width = self.tile_size.min(width - x);
+            let left = x.saturating_sub(self.margin);
+            let right = (x + tile_width + self.margin).min(width);
+            let tile = Tile {
+                x,
+                y: self.next_row,
+                width: tile_width,
+                height: band_height,
+                context: rows.region(left, 0, right - left, bottom - top)?,
+                offset: (x - left, self.next_row - top),
+                image: self.dimensions,
+            };
+
+            self.tile.clear();
+            self.op.apply(&tile, &mut self.tile);
+            let tile_len = tile_width * band_height * bytes;
+            if self.tile.len() != tile_len {
+                return Err(ImageError::BufferSize(tile_len, self.tile.len()).into());
+            }
+
+            for (y, pixels) in self.tile.chunks_exact(tile_width * bytes).enumerate() {
+                let start = y * row_len + x * bytes;
+                self.band[start..start + pixels.len()].copy_from_slice(pixels);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Shrinks rows as they arrive, averaging the rows under each new row with
+/// a BoxFilter of its own.
+struct Resize {
+    source: (usize, usize),
+    size: (usize, usize),
+    format: PixelFormat,
+    // The row of the output being filled, and the input rows added to it.
+    row: usize,
+    added: usize,
+    filter: Option<BoxFilter>,
+}
+
+impl Resize {
+    fn new(source: (usize, usize), size: (usize, usize), format: PixelFormat) -> Self {
+        Self {
+            source,
+            size,
+            format,
+            row: 0,
+            added: 0,
+            filter: None,
+        }
+    }
+    /// The first row of the input falling in a row of the output, the same
+    /// split Image::downscale makes.
+    fn first_row(&self, row: usize) -> usize {
+        let (height, source_height) = (self.size.1 as u128, self.source.1 as u128);
+        ((row as u128 * source_height).div_ceil(height)) as usize
+    }
+    /// Adds the next row of the input, handing each finished row of the
+    /// output to sink.
+    fn push(
+        &mut self,
+        row: &[u8],
+        sink: &mut dyn FnMut(&[u8]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        if self.source == self.size {
+            return sink(row);
+        }
+
+        let rows = self.first_row(self.row + 1) - self.first_row(self.row);
+        let filter = self.filter.get_or_insert_with(|| {
+            BoxFilter::new(self.source.0, rows, self.size.0, 1, self.format)
+        });
+        filter.add_row(self.added, row);
+        self.added += 1;
+
+        if self.added == rows {
+            if let Some(filter) = self.filter.take() {
+                sink(&filter.finish().data)?;
+            }
+            self.row += 1;
+            self.added = 0;
+        }
+
+        Ok(())
+    }
+}
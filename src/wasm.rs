@@ -0,0 +1,77 @@
+//! JavaScript bindings for decoding images in the browser, enabled with the
+//! wasm feature. Nothing here touches the file system, images are passed in
+//! as bytes, e.g. from a fetch() response or a file input.
+//!
+//! Build with:
+//!
+//! '''
+//! cargo rustc --lib --release --crate-type cdylib --features wasm \
+//!     --target wasm32-unknown-unknown
+//! wasm-bindgen --target web --out-dir examples/wasm/pkg \
+//!     target/wasm32-unknown-unknown/release/chameleon.wasm
+//! '''
+//!
+//! and serve examples/wasm for a small demo page.
+
+use alloc::{string::ToString, vec::Vec};
+
+use wasm_bindgen::prelude::*;
+
+use crate::formats::png::Png;
+
+/// A decoded image, shaped like the ImageData type used by canvas, so it can
+/// be turned into one with
+/// new ImageData(new Uint8ClampedArray(image.data), image.width, image.height).
+///
+/// # Fields
+///
+/// * 'width' - The width of the image in pixels.
+/// * 'height' - The height of the image in pixels.
+/// * 'data' - The pixels as RGBA bytes, from left to right, top to bottom.
+#[wasm_bindgen]
+pub struct ImageData {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl ImageData {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    /// Copies the pixels out into a Uint8Array.
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+/// Decodes a PNG file held in memory.
+///
+/// # Arguments
+///
+/// * 'bytes' - The entire PNG file, a Uint8Array on the JavaScript side.
+///
+/// # Returns
+///
+/// The decoded ImageData, or throws an Error with the decoder's message.
+#[wasm_bindgen]
+pub fn decode_png(bytes: &[u8]) -> Result<ImageData, JsError> {
+    let png = Png::decoder()
+        .decode_bytes(bytes)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+
+    let data = png.rgba_bytes().map_err(|e| JsError::new(&e.to_string()))?;
+
+    Ok(ImageData {
+        width: png.dimensions.0 as u32,
+        height: png.dimensions.1 as u32,
+        data,
+    })
+}
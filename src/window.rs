@@ -0,0 +1,153 @@
+//! Walking an image a pixel at a time with the block of pixels around each,
+//! for writing local filters, e.g. a median or an edge detector, without
+//! indexing into rows or handling the edges by hand.
+//!
+//! '''
+//! // The brightest channel of each pixel's 3x3 block.
+//! let brightest = image
+//!     .windows(1)
+//!     .map(|window| window.pixels().map(|[r, g, b, _]| r.max(g).max(b)).max())
+//!     .collect::<Vec<_>>();
+//! '''
+
+use crate::{convert, image::ImageView};
+
+/// A pixel of an image, with the pixels within radius of it, from
+/// ImageView::windows.
+///
+/// # Fields
+///
+/// * 'x' - The column of the pixel.
+/// * 'y' - The row of the pixel.
+/// * 'radius' - How far the block reaches from the pixel on every side.
+/// * 'image' - The whole image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window<'a> {
+    pub x: usize,
+    pub y: usize,
+    pub radius: usize,
+    pub image: ImageView<'a>,
+}
+
+impl<'a> Window<'a> {
+    /// Creates a Window centered on a pixel of an image.
+    ///
+    /// # Arguments
+    ///
+    /// * 'image' - The whole image.
+    /// * 'x' - The column of the pixel.
+    /// * 'y' - The row of the pixel.
+    /// * 'radius' - How far the block reaches from the pixel on every side.
+    ///
+    /// # Returns
+    ///
+    /// The Window, or None if the pixel is outside the image, which it
+    /// always is for empty images, or the image's data doesn't hold every
+    /// row.
+    pub fn new(image: ImageView<'a>, x: usize, y: usize, radius: usize) -> Option<Self> {
+        if x >= image.width || y >= image.height || image.check_size().is_err() {
+            return None;
+        }
+
+        Some(Self {
+            x,
+            y,
+            radius,
+            image,
+        })
+    }
+    /// Reads a pixel near the center as 16 bit RGBA, clamping positions
+    /// outside the image to the nearest edge, so the edge repeats.
+    ///
+    /// # Arguments
+    ///
+    /// * 'dx' - The column relative to the center.
+    /// * 'dy' - The row relative to the center.
+    ///
+    /// # Returns
+    ///
+    /// The red, green, blue and alpha of the pixel, or transparent black if
+    /// the Window was built by hand around a pixel Window::new refuses.
+    pub fn pixel(&self, dx: isize, dy: isize) -> [u16; 4] {
+        let clamp = |position: usize, delta: isize, size: usize| {
+            position
+                .saturating_add_signed(delta)
+                .min(size.saturating_sub(1))
+        };
+        let x = clamp(self.x, dx, self.image.width);
+        let y = clamp(self.y, dy, self.image.height);
+        let bytes = self.image.format.bytes_per_pixel();
+        let pixel = y
+            .checked_mul(self.image.stride)
+            .and_then(|row| row.checked_add(x * bytes))
+            .and_then(|start| self.image.data.get(start..)?.get(..bytes));
+
+        pixel.map_or([0; 4], |pixel| convert::rgba16(self.image.format, pixel))
+    }
+    /// Gets the pixel at the center as 16 bit RGBA.
+    pub fn center(&self) -> [u16; 4] {
+        self.pixel(0, 0)
+    }
+    /// Iterates over the block of pixels within radius of the center, from
+    /// left to right, top to bottom, each as 16 bit RGBA, see
+    /// Window::pixel.
+    ///
+    /// # Returns
+    ///
+    /// An iterator over the (2 * radius + 1)² pixels of the block.
+    pub fn pixels(&self) -> impl Iterator<Item = [u16; 4]> + '_ {
+        let radius = self.radius as isize;
+
+        (-radius..=radius).flat_map(move |dy| (-radius..=radius).map(move |dx| self.pixel(dx, dy)))
+    }
+}
+
+/// An iterator over a Window for every pixel of an image, from left to
+/// right, top to bottom, from ImageView::windows.
+#[derive(Debug, Clone)]
+pub struct Windows<'a> {
+    image: ImageView<'a>,
+    radius: usize,
+    next: usize,
+    len: usize,
+}
+
+impl<'a> Windows<'a> {
+    /// Creates a Windows over every pixel of image, or none if its data
+    /// doesn't hold every row.
+    pub(crate) fn new(image: ImageView<'a>, radius: usize) -> Self {
+        let len = match image.check_size() {
+            Ok(()) => image.width * image.height,
+            Err(_) => 0,
+        };
+
+        Self {
+            image,
+            radius,
+            next: 0,
+            len,
+        }
+    }
+}
+
+impl<'a> Iterator for Windows<'a> {
+    type Item = Window<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.len {
+            return None;
+        }
+        let x = self.next % self.image.width;
+        let y = self.next / self.image.width;
+        self.next += 1;
+
+        Window::new(self.image, x, y, self.radius)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.len - self.next;
+
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for Windows<'_> {}
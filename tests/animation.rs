@@ -0,0 +1,88 @@
+#![cfg(feature = "std")]
+
+use std::time::Duration;
+
+use chameleon::prelude::*;
+
+fn solid(width: usize, height: usize, color: [u8; 4]) -> Image {
+    Image::new(
+        width,
+        height,
+        PixelFormat::Rgba8,
+        color.repeat(width * height),
+    )
+    .unwrap()
+}
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+/// Checks that each canvas has earlier frames drawn under it, and that every
+/// kind of disposal leaves the right thing behind for the next frame.
+#[test]
+pub fn canvases_apply_disposal() {
+    let delay = Duration::from_millis(100);
+    let mut frames = Frames::new(2, 1);
+    frames.push(Frame::new(solid(2, 1, RED), delay)).unwrap();
+    frames
+        .push(
+            Frame::new(solid(1, 1, BLUE), delay)
+                .offset(1, 0)
+                .disposal(Disposal::Previous),
+        )
+        .unwrap();
+    frames
+        .push(Frame::new(solid(1, 1, BLUE), delay).disposal(Disposal::Background))
+        .unwrap();
+    frames.push(Frame::new(solid(1, 1, [0; 4]), delay)).unwrap();
+
+    let canvases = frames
+        .canvases()
+        .map(|(canvas, _)| canvas.data)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        canvases,
+        [
+            [RED, RED].concat(),
+            [RED, BLUE].concat(),
+            [BLUE, RED].concat(),
+            [[0; 4], RED].concat(),
+        ]
+    );
+    assert_eq!(frames.duration(), delay * 4);
+}
+
+/// Checks that frames which don't fit on the canvas are refused.
+#[test]
+pub fn push_checks_the_canvas() {
+    let mut frames = Frames::new(2, 2);
+    let frame = Frame::new(solid(2, 2, RED), Duration::ZERO).offset(1, 0);
+
+    assert_eq!(frames.push(frame), Err(ImageError::InvalidDimensions(2, 2)));
+    assert!(frames.is_empty());
+}
+
+/// Checks that resampling shows each frame for as many ticks as its delay
+/// covers, and keeps the loop count.
+#[test]
+pub fn resample_to_a_constant_rate() {
+    let mut frames = Frames::new(1, 1).loops(3);
+    frames
+        .push(Frame::new(solid(1, 1, RED), Duration::from_millis(100)))
+        .unwrap();
+    frames
+        .push(Frame::new(solid(1, 1, BLUE), Duration::from_millis(50)))
+        .unwrap();
+
+    let resampled = frames.resample(Duration::from_millis(40));
+    let colors = resampled
+        .iter()
+        .map(|frame| frame.image.data.clone())
+        .collect::<Vec<_>>();
+
+    assert_eq!(resampled.loops, 3);
+    assert_eq!(colors, [RED, RED, RED, BLUE].map(Vec::from));
+    assert!(resampled
+        .iter()
+        .all(|frame| frame.delay == Duration::from_millis(40)));
+}
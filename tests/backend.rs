@@ -0,0 +1,646 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use chameleon::compression::backend::{Compressor, DecompressError, Decompressor, Native};
+use chameleon::compression::inflate::DeflateError;
+use chameleon::prelude::*;
+
+/// Counts the streams it handles, then hands them to Native.
+#[derive(Debug)]
+struct Counting(AtomicUsize);
+
+impl Compressor for Counting {
+    fn compress(&self, data: &[u8]) -> Vec<u8> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Native.compress(data)
+    }
+}
+
+impl Decompressor for Counting {
+    fn decompress(
+        &self,
+        stream: &[u8],
+        checksum: bool,
+        check_window: bool,
+        buffer: Vec<u8>,
+        each: &mut dyn FnMut(&[u8]) -> Result<(), Error>,
+    ) -> Result<Vec<u8>, DecompressError> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Native.decompress(stream, checksum, check_window, buffer, each)
+    }
+}
+
+/// Fails every stream, as if it were damaged 20 bits in.
+#[derive(Debug)]
+struct Broken;
+
+impl Decompressor for Broken {
+    fn decompress(
+        &self,
+        _stream: &[u8],
+        _checksum: bool,
+        _check_window: bool,
+        _buffer: Vec<u8>,
+        _each: &mut dyn FnMut(&[u8]) -> Result<(), Error>,
+    ) -> Result<Vec<u8>, DecompressError> {
+        Err(DecompressError {
+            error: DeflateError::DecompressionError("broken on purpose.").into(),
+            bit: Some(20),
+        })
+    }
+}
+
+/// Checks the encoder and decoder go through the backends they're given,
+/// for image data, interlaced image data and compressed text.
+#[test]
+pub fn backends_are_used() {
+    static COMPRESSOR: Counting = Counting(AtomicUsize::new(0));
+    static DECOMPRESSOR: Counting = Counting(AtomicUsize::new(0));
+
+    let decoder = Png::decoder().decompressor(&DECOMPRESSOR);
+    for name in ["basn2c08", "basi2c08"] {
+        let png = decoder
+            .decode(format!("./tests/samples/{name}.png"))
+            .unwrap();
+        let expected = Png::from_path(format!("./tests/samples/{name}.png")).unwrap();
+        assert_eq!(png.rgba().unwrap(), expected.rgba().unwrap(), "{name}");
+    }
+    assert_eq!(DECOMPRESSOR.0.load(Ordering::Relaxed), 2);
+
+    let png = decoder.decode("./tests/samples/ctzn0g04.png").unwrap();
+    assert!(!png.text().is_empty());
+    assert!(DECOMPRESSOR.0.load(Ordering::Relaxed) > 2);
+
+    let image = Image::noise(8, 8, 1).unwrap();
+    let bytes = image
+        .encode_png(&EncodeOptions::new().compressor(&COMPRESSOR))
+        .unwrap();
+    assert_eq!(COMPRESSOR.0.load(Ordering::Relaxed), 1);
+    assert_eq!(bytes, image.encode_png(&EncodeOptions::new()).unwrap());
+}
+
+/// Checks a backend's errors come back as DecoderErrors pointing at the
+/// IDAT chunk and bit it stopped on.
+#[test]
+pub fn backend_errors_are_located() {
+    let error = Png::decoder()
+        .decompressor(&Broken)
+        .decode("./tests/samples/basn2c08.png")
+        .unwrap()
+        .rgb()
+        .unwrap_err();
+
+    assert!(matches!(error.root(), DecoderError::DeflateError(_)));
+    let context = error.context().unwrap();
+    assert_eq!(context.bit, Some(20));
+    assert_eq!(context.chunk.as_ref().unwrap().0, "IDAT");
+}
+
+/// Checks chameleon's codec against flate2's, both ways, over PngSuite.
+#[cfg(feature = "zlib")]
+#[test]
+pub fn native_matches_flate2() {
+    use chameleon::compression::backend::Flate2;
+    use chameleon::roundtrip;
+    use std::fs;
+
+    for entry in fs::read_dir("./tests/samples").unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        if name.starts_with('x') || path.extension().is_none_or(|e| e != "png") {
+            continue;
+        }
+
+        let native = Png::from_path(&path).unwrap();
+        let flate2 = Png::decoder().decompressor(&Flate2).decode(&path).unwrap();
+        assert_eq!(native.rgba().unwrap(), flate2.rgba().unwrap(), "{name}");
+        assert_eq!(native.text(), flate2.text(), "{name}");
+        let unchecked = Png::decoder()
+            .decompressor(&Flate2)
+            .checksums(ChecksumPolicy::Ignore)
+            .decode(&path)
+            .unwrap();
+        assert_eq!(native.rgba().unwrap(), unchecked.rgba().unwrap(), "{name}");
+
+        let bytes = fs::read(&path).unwrap();
+        let options = EncodeOptions::new().compressor(&Flate2);
+        let report = roundtrip::verify_bytes(&bytes, &options).unwrap();
+        assert!(report.is_exact(), "{name}: {report}");
+    }
+}
+
+/// Checks flate2 counts the bit it stopped on from the start of the zlib
+/// stream whether or not the adler32 is checked.
+#[cfg(feature = "zlib")]
+#[test]
+pub fn flate2_errors_count_the_header() {
+    use chameleon::compression::backend::Flate2;
+
+    let data: Vec<u8> = (0..4000u32).map(|i| (i * 7 % 251) as u8).collect();
+    let stream = Native.compress(&data);
+    let truncated = &stream[..stream.len() / 2];
+
+    let bits = [true, false].map(|checksum| {
+        Flate2
+            .decompress(truncated, checksum, true, Vec::new(), &mut |_| Ok(()))
+            .unwrap_err()
+            .bit
+    });
+    assert_eq!(bits[0], bits[1]);
+    assert!(bits[0].unwrap() > 16);
+}
+
+/// Checks streams from ZlibStream::compress decompress back to their data,
+/// empty, spanning several blocks, and with a correct adler32, and that
+/// repetitive data comes out smaller.
+#[test]
+pub fn zlib_streams_round_trip() {
+    use chameleon::compression::{checksums::adler::adler32, zlib::ZlibStream};
+
+    let long: Vec<u8> = (0..200_000u32).map(|i| (i * 7 % 251) as u8).collect();
+    // A xorshift generator, for data that doesn't compress.
+    let mut state = 0x2545_f491u32;
+    let noise: Vec<u8> = (0..100_000)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect();
+    assert!(ZlibStream::compress(&long).len() < long.len() / 20);
+    assert!(ZlibStream::compress(&noise).len() < noise.len() + 100);
+
+    for data in [&[][..], b"chameleon", b"abcabcabcabcabcabcd", &long, &noise] {
+        let stream = ZlibStream::compress(data);
+        assert_eq!(stream[..2], [0x78, 0x9c]);
+        assert_eq!(
+            stream[stream.len() - 4..],
+            adler32(data).to_be_bytes(),
+            "{}",
+            data.len()
+        );
+        let mut zlib = ZlibStream::build(&stream).unwrap();
+        assert_eq!(zlib.decompress().unwrap(), data);
+    }
+}
+
+/// Checks flate2 inflates what ZlibStream::compress writes, for data with
+/// skewed byte frequencies and for text, and that dynamic blocks are picked
+/// for both.
+#[cfg(feature = "zlib")]
+#[test]
+pub fn flate2_inflates_dynamic_blocks() {
+    use chameleon::compression::{deflate, zlib::ZlibStream};
+    use std::io::Read;
+
+    // Byte i appears fib(i) times, shuffled.
+    let (mut a, mut b) = (1usize, 1usize);
+    let mut skewed = Vec::new();
+    for byte in 0..25u8 {
+        skewed.extend(std::iter::repeat_n(byte, a));
+        (a, b) = (b, a + b);
+    }
+    let mut state = 0x9e37_79b9u32;
+    for idx in (1..skewed.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        skewed.swap(idx, state as usize % (idx + 1));
+    }
+    let text = "the quick brown fox jumps over the lazy dog, again and again. "
+        .repeat(2000)
+        .into_bytes();
+
+    for data in [skewed, text] {
+        assert_eq!(deflate::compress(&data)[0] >> 1 & 3, 2);
+
+        let stream = ZlibStream::compress(&data);
+        let mut inflated = Vec::new();
+        flate2::read::ZlibDecoder::new(&stream[..])
+            .read_to_end(&mut inflated)
+            .unwrap();
+        assert_eq!(inflated, data);
+        assert_eq!(
+            ZlibStream::build(&stream).unwrap().decompress().unwrap(),
+            data
+        );
+    }
+}
+
+/// Checks code lengths are cut down to the longest allowed while still
+/// making a complete code, that at least two symbols get codes, that an
+/// empty alphabet doesn't panic, and that more symbols than the longest
+/// codes can tell apart are an error.
+#[test]
+pub fn code_lengths_are_limited() {
+    use chameleon::compression::prefix::{code_lengths, PrefixError};
+
+    let kraft = |lengths: &[u8]| -> f64 {
+        lengths
+            .iter()
+            .filter(|&&l| l > 0)
+            .map(|&l| 0.5f64.powi(l as i32))
+            .sum()
+    };
+
+    // Fibonacci frequencies give a Huffman tree as deep as it can be.
+    let (mut a, mut b) = (1u32, 1u32);
+    let mut frequencies = Vec::new();
+    for _ in 0..30 {
+        frequencies.push(a);
+        (a, b) = (b, a + b);
+    }
+    for max in [7, 15] {
+        let lengths = code_lengths(&frequencies, max).unwrap();
+        assert_eq!(lengths.iter().max(), Some(&max));
+        assert_eq!(kraft(&lengths), 1.0);
+        assert!(lengths.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+
+    let lengths = code_lengths(&[0, 0, 5, 0], 15).unwrap();
+    assert_eq!(lengths, [1, 0, 1, 0]);
+    assert_eq!(code_lengths(&[0; 4], 15).unwrap(), [1, 1, 0, 0]);
+    assert!(code_lengths(&[], 15).unwrap().is_empty());
+    assert_eq!(kraft(&code_lengths(&[1, 1, 2, 4], 15).unwrap()), 1.0);
+
+    assert_eq!(kraft(&code_lengths(&[1; 8], 3).unwrap()), 1.0);
+    assert_eq!(
+        code_lengths(&[1; 9], 3),
+        Err(PrefixError::TooManySymbols {
+            used: 9,
+            max_length: 3
+        })
+    );
+    assert_eq!(
+        code_lengths(&[0; 4], 0),
+        Err(PrefixError::TooManySymbols {
+            used: 2,
+            max_length: 0
+        })
+    );
+}
+
+/// Checks the tokens from lz77::tokenize rebuild their data, with matches
+/// inside the window, however hard the match finder is told to look.
+#[test]
+pub fn matches_rebuild_their_data() {
+    use chameleon::compression::lz77::{self, MatchOptions, Token};
+
+    let image = Image::noise(300, 200, 3).unwrap();
+    let mut data = image.data[..20_000].to_vec();
+    data.extend_from_slice(&image.data[..20_000]);
+    data.extend("to be or not to be, ".repeat(500).bytes());
+    data.extend_from_slice(&[7; 1000]);
+
+    for options in [
+        MatchOptions::new(),
+        MatchOptions::new().max_lazy(0),
+        MatchOptions::new().max_chain(1).nice_length(8),
+        MatchOptions::new()
+            .max_chain(4096)
+            .nice_length(258)
+            .max_lazy(258),
+    ] {
+        let tokens = lz77::tokenize(&data, &options);
+        let mut rebuilt: Vec<u8> = Vec::with_capacity(data.len());
+        for token in &tokens {
+            match *token {
+                Token::Literal(byte) => rebuilt.push(byte),
+                Token::Match { length, distance } => {
+                    assert!((3..=258).contains(&length), "{options:?}");
+                    assert!((1..=32768).contains(&distance), "{options:?}");
+                    let start = rebuilt.len() - distance as usize;
+                    for idx in start..start + length as usize {
+                        rebuilt.push(rebuilt[idx]);
+                    }
+                }
+            }
+        }
+        assert!(rebuilt == data, "{options:?}");
+        // Only the first copy of the noise is left as literals.
+        assert!(tokens.len() < 21_000, "{options:?}: {}", tokens.len());
+    }
+
+    let tokens = lz77::tokenize(b"abcabcabcabc", &MatchOptions::new());
+    assert_eq!(
+        tokens[3],
+        Token::Match {
+            length: 9,
+            distance: 3
+        }
+    );
+}
+
+/// Checks InflateReader hands over the same bytes as decompress, however
+/// small the reads, and reports damaged data as InvalidData.
+#[test]
+pub fn inflate_reader_reads_on_demand() {
+    use chameleon::compression::{deflate, inflate::InflateReader, zlib::ZlibStream};
+    use std::io::{self, Read};
+
+    let data: Vec<u8> = (0..150_000u32).map(|i| (i * 13 % 241) as u8).collect();
+    let compressed = deflate::compress(&data);
+
+    let mut whole = Vec::new();
+    InflateReader::new(&compressed)
+        .read_to_end(&mut whole)
+        .unwrap();
+    assert_eq!(whole, data);
+
+    let mut reader = InflateReader::new(&compressed);
+    let (mut pieces, mut buf) = (Vec::new(), [0; 7]);
+    loop {
+        let len = reader.read(&mut buf).unwrap();
+        if len == 0 {
+            break;
+        }
+        pieces.extend_from_slice(&buf[..len]);
+    }
+    assert_eq!(pieces, data);
+    assert_eq!(reader.read(&mut buf).unwrap(), 0);
+
+    let zlib = ZlibStream::build(&ZlibStream::compress(b"wrapped")).unwrap();
+    let mut wrapped = String::new();
+    InflateReader::from_stream(zlib.deflate)
+        .read_to_string(&mut wrapped)
+        .unwrap();
+    assert_eq!(wrapped, "wrapped");
+
+    let error = InflateReader::new(&[0b111])
+        .read_to_end(&mut Vec::new())
+        .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+/// Checks a block far larger than the window is decoded a piece at a time,
+/// with no more than 64K of output and the end of a match held at once.
+#[test]
+pub fn large_blocks_are_inflated_in_pieces() {
+    use chameleon::compression::inflate::{DeflateStream, InflateReader, WINDOW_SIZE};
+    use std::io::Read;
+
+    // One fixed Huffman block: a literal 0, then 1000 matches of 258 bytes
+    // from 1 back.
+    let (mut bytes, mut bit) = (Vec::new(), 0);
+    let mut push = |value: u32, count: u32, reversed: bool| {
+        for idx in 0..count {
+            let shift = if reversed { count - 1 - idx } else { idx };
+            if bit % 8 == 0 {
+                bytes.push(0);
+            }
+            *bytes.last_mut().unwrap() |= ((value >> shift & 1) as u8) << (bit % 8);
+            bit += 1;
+        }
+    };
+    push(0b011, 3, false);
+    push(0x30, 8, true);
+    for _ in 0..1000 {
+        push(0xc5, 8, true);
+        push(0, 5, true);
+    }
+    push(0, 7, true);
+    let data = vec![0; 258_001];
+
+    let mut stream = DeflateStream::build(&bytes);
+    let (mut out, mut piece) = (Vec::new(), Vec::new());
+    while stream.inflate_next(&mut piece).unwrap() {
+        assert!(piece.len() <= WINDOW_SIZE * 2 + 258, "{}", piece.len());
+        out.append(&mut piece);
+    }
+    assert_eq!(out, data);
+
+    let mut read = Vec::new();
+    InflateReader::new(&bytes).read_to_end(&mut read).unwrap();
+    assert_eq!(read, data);
+
+    let mut handed = Vec::new();
+    DeflateStream::build(&bytes)
+        .decompress_with(Vec::new(), &mut |piece: &[u8]| {
+            assert!(piece.len() <= WINDOW_SIZE * 2 + 258, "{}", piece.len());
+            handed.extend_from_slice(piece);
+            Ok::<_, chameleon::compression::inflate::DeflateError>(())
+        })
+        .unwrap();
+    assert_eq!(handed, data);
+    assert_eq!(DeflateStream::build(&bytes).decompress().unwrap(), data);
+}
+
+/// Checks codes longer than the first table lookup, which go on to a
+/// subtable, decode. Bytes that appear once among a lot of text get the
+/// longest codes there are.
+#[test]
+pub fn long_codes_are_decoded() {
+    use chameleon::compression::{deflate, inflate::DeflateStream};
+
+    let words = [
+        "the ", "quick ", "brown ", "fox ", "jumps ", "over ", "lazy ", "dogs. ",
+    ];
+    let mut state = 0x1234_5678u32;
+    let mut data = Vec::new();
+    for rare in 128..=255u8 {
+        for _ in 0..200 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            data.extend_from_slice(words[state as usize % words.len()].as_bytes());
+        }
+        data.push(rare);
+    }
+
+    let compressed = deflate::compress(&data);
+    assert!(compressed.len() < data.len() / 2);
+    assert_eq!(
+        DeflateStream::build(&compressed).decompress().unwrap(),
+        data
+    );
+}
+
+/// Checks package-merge finds code lengths as cheap as the best a search
+/// through every set of lengths finds, and that the codes handed out for
+/// them are the ones a PrefixTree decodes.
+#[test]
+pub fn package_merge_is_optimal() {
+    use chameleon::compression::prefix::{canonical_codes, code_lengths, PrefixTree};
+
+    let cost = |frequencies: &[u32], lengths: &[u8]| -> u64 {
+        frequencies
+            .iter()
+            .zip(lengths)
+            .map(|(&f, &l)| f as u64 * l as u64)
+            .sum()
+    };
+
+    let mut state = 0xdead_beefu32;
+    for trial in 0..200 {
+        let symbols = 2 + trial % 5;
+        let max = 3 + (trial % 2) as u8;
+        let frequencies: Vec<u32> = (0..symbols)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                1 + state % 50
+            })
+            .collect();
+
+        // Every set of lengths from 1 to max that fits, the cheapest kept.
+        let mut best = u64::MAX;
+        let mut lengths = vec![1u8; symbols];
+        'search: loop {
+            let kraft: u32 = lengths.iter().map(|&l| 1 << (max - l)).sum();
+            if kraft <= 1 << max {
+                best = best.min(cost(&frequencies, &lengths));
+            }
+            for length in lengths.iter_mut() {
+                if *length < max {
+                    *length += 1;
+                    continue 'search;
+                }
+                *length = 1;
+            }
+            break;
+        }
+
+        let found = code_lengths(&frequencies, max).unwrap();
+        assert!(found.iter().all(|&l| (1..=max).contains(&l)));
+        assert_eq!(cost(&frequencies, &found), best, "{frequencies:?}");
+    }
+
+    let lengths = code_lengths(&[10, 0, 3, 3, 7, 1, 1, 0, 2], 15).unwrap();
+    let table = PrefixTree::from_lengths(&lengths).unwrap().compile(9);
+    for (symbol, code) in canonical_codes(&lengths).into_iter().enumerate() {
+        let length = lengths[symbol];
+        if length > 0 {
+            // Tables are looked up with the first bit lowest.
+            let reversed = code.reverse_bits() >> (32 - length as u32);
+            assert_eq!(table.decode(reversed), Some((symbol, length)));
+        }
+    }
+    assert_eq!(canonical_codes(&[2, 1, 3, 3]), [0b10, 0b0, 0b110, 0b111]);
+}
+
+/// Checks every level and strategy gives a stream that inflates back to its
+/// data, that higher levels don't come out larger, and that each strategy
+/// only finds the matches it's meant to.
+#[test]
+pub fn levels_and_strategies_round_trip() {
+    use chameleon::compression::deflate::{self, CompressionLevel, DeflateOptions, Strategy};
+    use chameleon::compression::lz77::{self, Token};
+    use chameleon::compression::zlib::ZlibStream;
+
+    let image = Image::noise(100, 100, 5).unwrap();
+    let mut data = image.data[..10_000].to_vec();
+    data.extend(
+        "to be or not to be, that is the question. "
+            .repeat(800)
+            .bytes(),
+    );
+    data.extend_from_slice(&[9; 5000]);
+    data.extend_from_slice(&image.data[..10_000]);
+
+    let levels = [
+        CompressionLevel::None,
+        CompressionLevel::Fast,
+        CompressionLevel::Default,
+        CompressionLevel::Best,
+    ];
+    let strategies = [
+        Strategy::Default,
+        Strategy::Filtered,
+        Strategy::HuffmanOnly,
+        Strategy::Rle,
+    ];
+    for level in levels {
+        for strategy in strategies {
+            let options = DeflateOptions::new().level(level).strategy(strategy);
+            let stream = Native.compress_with(&data, &options);
+            let mut zlib = ZlibStream::build(&stream).unwrap();
+            assert!(zlib.decompress().unwrap() == data, "{options:?}");
+        }
+    }
+
+    // Nothing but stored blocks, a few bytes of framing on each.
+    let stored =
+        deflate::compress_options(&data, &DeflateOptions::new().level(CompressionLevel::None));
+    assert_eq!(stored[0] >> 1 & 3, 0);
+    assert!(stored.len() > data.len() && stored.len() < data.len() + 20);
+    assert_eq!(
+        deflate::compress_options(b"", &DeflateOptions::new().level(CompressionLevel::None)),
+        [1, 0, 0, 0xff, 0xff]
+    );
+
+    let size = |level| ZlibStream::compress_with(&data, &DeflateOptions::new().level(level)).len();
+    assert!(size(CompressionLevel::Best) <= size(CompressionLevel::Default));
+    assert!(size(CompressionLevel::Default) <= size(CompressionLevel::Fast));
+    assert!(size(CompressionLevel::Fast) < size(CompressionLevel::None) / 2);
+    assert_eq!(
+        ZlibStream::compress_with(&data, &DeflateOptions::new()),
+        ZlibStream::compress(&data)
+    );
+    for (level, flg) in [
+        (CompressionLevel::None, 0x01),
+        (CompressionLevel::Fast, 0x01),
+        (CompressionLevel::Default, 0x9c),
+        (CompressionLevel::Best, 0xda),
+    ] {
+        let stream = ZlibStream::compress_with(b"", &DeflateOptions::new().level(level));
+        assert_eq!(stream[..2], [0x78, flg], "{level:?}");
+        assert_eq!(u16::from_be_bytes([stream[0], stream[1]]) % 31, 0);
+    }
+
+    let tokens = lz77::tokenize(&data, &CompressionLevel::Best.match_options().min_length(6));
+    assert!(tokens
+        .iter()
+        .all(|token| token.span() == 1 || token.span() >= 6));
+    let tokens = lz77::tokenize_runs(&data);
+    assert!(tokens
+        .iter()
+        .all(|token| matches!(token, Token::Literal(_) | Token::Match { distance: 1, .. })));
+    assert!(tokens.len() < data.len() - 4900);
+}
+
+/// Checks zlib streams joined together are each decompressed in turn,
+/// including one declaring a small window, and that a damaged one stops
+/// the iterator with an error.
+#[test]
+pub fn zlib_streams_are_joined() {
+    use chameleon::compression::checksums::adler::adler32;
+    use chameleon::compression::deflate;
+    use chameleon::compression::zlib::{self, ZlibStream, ZlibStreams};
+
+    let pieces = [
+        b"the first stream".to_vec(),
+        Vec::new(),
+        "a longer stream that repeats itself. "
+            .repeat(2000)
+            .into_bytes(),
+    ];
+    let mut bytes = Vec::new();
+    for piece in &pieces {
+        bytes.extend(ZlibStream::compress(piece));
+    }
+    // A CINFO of 0, a 256 byte window.
+    bytes.extend_from_slice(&[0x08, 0x1d]);
+    bytes.extend(deflate::compress(b"abcabcabc"));
+    bytes.extend_from_slice(&adler32(b"abcabcabc").to_be_bytes());
+
+    let streams: Vec<_> = ZlibStreams::new(&bytes).map(Result::unwrap).collect();
+    assert_eq!(streams[..3], pieces);
+    assert_eq!(streams[3], b"abcabcabc");
+    assert_eq!(
+        zlib::decompress_all(&bytes).unwrap(),
+        [&pieces.concat()[..], b"abcabcabc"].concat()
+    );
+
+    let mut damaged = bytes.clone();
+    let end = ZlibStream::compress(&pieces[0]).len();
+    damaged[end - 1] ^= 1;
+    let results: Vec<_> = ZlibStreams::new(&damaged).collect();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_err());
+
+    assert!(zlib::decompress_all(&bytes[..bytes.len() - 2]).is_err());
+    assert!(zlib::decompress_all(b"").is_err());
+}
@@ -0,0 +1,174 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use std::fs;
+
+use chameleon::batch::{self, Action, BatchOptions, Manifest, Stamp};
+use chameleon::formats::png::Png;
+
+/// Checks that batch decoding returns the same results, in the same order,
+/// as decoding each file one at a time.
+#[test]
+pub fn decode_all_keeps_input_order() {
+    let mut paths = fs::read_dir("./tests/samples/")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let results = batch::decode_all(&paths, &BatchOptions::new().threads(4));
+
+    assert_eq!(results.len(), paths.len());
+
+    for (path, result) in paths.iter().zip(results) {
+        match (Png::from_path(path), result) {
+            (Ok(expected), Ok(png)) => {
+                assert_eq!(expected.data.raw_data, png.data.raw_data, "{:?}", path)
+            }
+            (Err(_), Err(_)) => {}
+            _ => panic!("batch result differs from sequential decode for {:?}", path),
+        }
+    }
+}
+
+/// Checks that planning a conversion says what would happen to each file,
+/// without writing anything.
+#[test]
+pub fn plan_dir_explains_each_file() {
+    let dir = std::env::temp_dir().join("chameleon_batch_plan");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    fs::create_dir_all(dir.join("out")).unwrap();
+    for name in [
+        "a.png",
+        "nested/b.PNG",
+        "nested/b.png",
+        "out/c.png",
+        "notes.txt",
+    ] {
+        fs::write(dir.join(name), b"").unwrap();
+    }
+    fs::write(dir.join("out/a.ppm"), b"").unwrap();
+
+    let steps = batch::plan_dir(&dir, dir.join("out"), "ppm").unwrap();
+    let actions: Vec<_> = steps
+        .iter()
+        .map(|step| (step.input.strip_prefix(&dir).unwrap(), step.action))
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            ("a.png".as_ref(), Action::Overwrite),
+            ("nested/b.PNG".as_ref(), Action::Convert),
+            (
+                "nested/b.png".as_ref(),
+                Action::Skip("another file is written to the same output")
+            ),
+            ("notes.txt".as_ref(), Action::Skip("not a PNG file")),
+            ("out/a.ppm".as_ref(), Action::Skip("not a PNG file")),
+            (
+                "out/c.png".as_ref(),
+                Action::Skip("already in the output directory")
+            ),
+        ]
+    );
+    assert_eq!(steps[1].output, dir.join("out/nested/b.ppm"));
+    assert!(steps[0].to_string().starts_with("overwrite "));
+    assert!(!dir.join("out/nested").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+/// Checks that symbolic links to directories aren't followed, so a link
+/// back to the directory being converted doesn't loop.
+#[cfg(unix)]
+#[test]
+pub fn plan_dir_skips_linked_directories() {
+    let dir = std::env::temp_dir().join("chameleon_batch_links");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.png"), b"").unwrap();
+    std::os::unix::fs::symlink(&dir, dir.join("loop")).unwrap();
+    std::os::unix::fs::symlink(&dir, dir.join("loop.png")).unwrap();
+
+    let steps = batch::plan_dir(&dir, dir.join("out"), "ppm").unwrap();
+    assert_eq!(steps.len(), 1);
+    assert_eq!(steps[0].input, dir.join("a.png"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+/// Checks that a manifest skips files converted before with the same
+/// options, until their input changes or their output goes, and that it's
+/// read back from its file.
+#[test]
+pub fn manifest_resumes_unchanged_files() {
+    let dir = std::env::temp_dir().join("chameleon_batch_manifest");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("in")).unwrap();
+    fs::create_dir_all(dir.join("out")).unwrap();
+    for name in ["a.png", "b.png"] {
+        fs::write(dir.join("in").join(name), name).unwrap();
+    }
+    let plan = || batch::plan_dir(dir.join("in"), dir.join("out"), "png").unwrap();
+    let manifest_path = dir.join("out").join(batch::MANIFEST_NAME);
+
+    let mut manifest = Manifest::load(&manifest_path, "png best").unwrap();
+    for step in plan() {
+        assert!(!manifest.is_done(&step).unwrap());
+        let stamp = Stamp::of(&step.input).unwrap();
+        fs::write(&step.output, b"").unwrap();
+        manifest.record(&step, stamp).unwrap();
+    }
+
+    let other = Manifest::load(&manifest_path, "png fast").unwrap();
+    assert!(plan().iter().all(|step| !other.is_done(step).unwrap()));
+
+    let manifest = Manifest::load(&manifest_path, "png best").unwrap();
+    let resumed = |manifest: &Manifest| {
+        plan()
+            .into_iter()
+            .map(|mut step| {
+                manifest.resume(&mut step).unwrap();
+                step.action
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(resumed(&manifest), [Action::Skip(batch::DONE); 2]);
+
+    fs::write(dir.join("in/a.png"), "changed").unwrap();
+    fs::remove_file(dir.join("out/b.png")).unwrap();
+    assert_eq!(resumed(&manifest), [Action::Overwrite, Action::Convert]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+/// Checks that options and paths holding tabs, newlines and backslashes are
+/// read back from the manifest's file as they were written.
+#[cfg(unix)]
+#[test]
+pub fn manifest_escapes_paths() {
+    let dir = std::env::temp_dir().join("chameleon_batch_escape");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("in")).unwrap();
+    fs::create_dir_all(dir.join("out")).unwrap();
+    for name in ["tab\there.png", "new\nline.png", "back\\slash\\n.png"] {
+        fs::write(dir.join("in").join(name), name).unwrap();
+    }
+    let plan = || batch::plan_dir(dir.join("in"), dir.join("out"), "png").unwrap();
+    let manifest_path = dir.join("out").join(batch::MANIFEST_NAME);
+    let options = "png\tbest\n";
+
+    let mut manifest = Manifest::load(&manifest_path, options).unwrap();
+    for step in plan() {
+        let stamp = Stamp::of(&step.input).unwrap();
+        fs::write(&step.output, b"").unwrap();
+        manifest.record(&step, stamp).unwrap();
+    }
+    let text = fs::read_to_string(&manifest_path).unwrap();
+    assert_eq!(text.lines().count(), 3);
+
+    let manifest = Manifest::load(&manifest_path, options).unwrap();
+    assert!(plan().iter().all(|step| manifest.is_done(step).unwrap()));
+
+    fs::remove_dir_all(dir).unwrap();
+}
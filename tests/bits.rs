@@ -0,0 +1,62 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use chameleon::compression::bits::BitReader;
+
+/// Checks fields are read in deflate's bit order either way round, that
+/// peeking doesn't move the reader, and that the position is kept.
+#[test]
+pub fn bits_are_read_in_deflate_order() {
+    let mut reader = BitReader::new(&[0b1010_1101, 0b0110_0011, 0xff, 0x12, 0x34]);
+    assert_eq!((reader.len(), reader.remaining()), (40, 40));
+
+    assert_eq!(reader.peek_bits(3), 0b101);
+    assert_eq!(reader.read_bits_lsb(3), Some(0b101));
+    assert_eq!(reader.read_bits_msb(2), Some(0b10));
+    assert_eq!(reader.position(), 5);
+
+    // Across the byte boundary, 3 bits from the first and 6 from the second.
+    assert_eq!(reader.read_bits_lsb(9), Some(0b1_0001_1101));
+    assert_eq!(reader.read_bits_lsb(0), Some(0));
+    assert_eq!(reader.read_bits_msb(0), Some(0));
+
+    assert!(reader.skip(1));
+    assert_eq!(reader.position(), 15);
+    reader.align_to_byte();
+    assert_eq!(reader.position(), 16);
+    reader.align_to_byte();
+    assert_eq!(reader.position(), 16);
+    assert_eq!(reader.read_bytes(1), Some(&[0xff][..]));
+
+    assert_eq!(reader.peek_bits(32), 0x3412);
+    assert_eq!(reader.read_bits_lsb(17), None);
+    assert!(!reader.skip(17));
+    assert_eq!(reader.position(), 24);
+    assert_eq!(reader.read_bits_lsb(16), Some(0x3412));
+    assert_eq!(reader.remaining(), 0);
+    assert_eq!(reader.read_bits_lsb(1), None);
+}
+
+/// Checks whole bytes are only read from the start of a byte, and reads of
+/// 32 bits work from any position.
+#[test]
+pub fn bytes_need_alignment() {
+    let bytes: Vec<u8> = (0..16).map(|i| i * 17).collect();
+    let mut reader = BitReader::new(&bytes);
+    reader.skip(4);
+    assert_eq!(reader.read_bytes(1), None);
+    reader.align_to_byte();
+    assert_eq!(reader.read_bytes(2), Some(&bytes[1..3]));
+    assert_eq!(reader.read_bytes(14), None);
+    assert_eq!(reader.position(), 24);
+
+    let word = u128::from_le_bytes(bytes.clone().try_into().unwrap());
+    for start in 0..=96 {
+        let mut reader = BitReader::new(&bytes);
+        reader.skip(start);
+        assert_eq!(reader.read_bits_lsb(32), Some((word >> start) as u32));
+    }
+
+    let empty = BitReader::new(&[]);
+    assert!(empty.is_empty());
+    assert_eq!(empty.peek_bits(8), 0);
+}
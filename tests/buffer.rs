@@ -0,0 +1,79 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use std::sync::Mutex;
+
+use chameleon::prelude::*;
+
+/// A BufferProvider that records every request and keeps released buffers
+/// for reuse.
+#[derive(Default)]
+struct Pool {
+    allocated: Mutex<Vec<BufferKind>>,
+    free: Mutex<Vec<Vec<u8>>>,
+}
+
+impl BufferProvider for Pool {
+    fn allocate(&self, kind: BufferKind, capacity: usize) -> Vec<u8> {
+        self.allocated.lock().unwrap().push(kind);
+
+        let mut buffer = self.free.lock().unwrap().pop().unwrap_or_default();
+        buffer.reserve(capacity);
+        buffer
+    }
+    fn release(&self, _kind: BufferKind, buffer: Vec<u8>) {
+        self.free.lock().unwrap().push(buffer);
+    }
+}
+
+/// Checks that the large buffers come from the provider, and that the pixels
+/// match those from Png::rgb.
+#[test]
+pub fn buffers_come_from_provider() {
+    let png = Png::from_path("./tests/samples/basn2c08.png").unwrap();
+    let pool = Pool::default();
+
+    let expected = png
+        .rgb()
+        .unwrap()
+        .into_iter()
+        .flat_map(|(r, g, b)| [r, g, b])
+        .collect::<Vec<_>>();
+
+    // The second decode reuses the buffers given back by the first.
+    for _ in 0..2 {
+        assert_eq!(png.rgb_bytes_with(&pool).unwrap(), expected);
+    }
+
+    assert_eq!(
+        *pool.allocated.lock().unwrap(),
+        [
+            BufferKind::Compressed,
+            BufferKind::Decompressed,
+            BufferKind::Pixels,
+        ]
+        .repeat(2)
+    );
+}
+
+/// Checks that transformations are applied to the bytes from a provider.
+#[test]
+pub fn provider_bytes_are_transformed() {
+    let plain = Png::from_path("./tests/samples/basn2c08.png")
+        .unwrap()
+        .rgb_bytes_with(&DefaultBuffers)
+        .unwrap();
+    let transformed = Png::decoder()
+        .transformations(Transformations::new().flip_vertical(true).bgr(true))
+        .decode("./tests/samples/basn2c08.png")
+        .unwrap()
+        .rgb_bytes_with(&DefaultBuffers)
+        .unwrap();
+
+    let expected = plain
+        .chunks(32 * 3)
+        .rev()
+        .flat_map(|row| row.chunks(3).flat_map(|p| [p[2], p[1], p[0]]))
+        .collect::<Vec<_>>();
+
+    assert_eq!(transformed, expected);
+}
@@ -0,0 +1,84 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use chameleon::formats::png::{Chunk, ChunkType};
+use chameleon::prelude::*;
+
+/// Builds a CgBI file, 2 by 2 RGBA, with the given filtered scanlines
+/// stored in a single uncompressed deflate block and no zlib wrapper.
+fn build(data: &[u8]) -> Vec<u8> {
+    let chunk = |ctype: &[u8; 4], data: &[u8]| {
+        Chunk::with_data(ChunkType::new(*ctype).unwrap(), data.to_vec()).to_bytes()
+    };
+
+    let mut ihdr = 2u32.to_be_bytes().to_vec();
+    ihdr.extend_from_slice(&2u32.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let len = data.len() as u16;
+    let mut deflate = vec![1];
+    deflate.extend_from_slice(&len.to_le_bytes());
+    deflate.extend_from_slice(&(!len).to_le_bytes());
+    deflate.extend_from_slice(data);
+
+    let mut bytes = vec![137, 80, 78, 71, 13, 10, 26, 10];
+    bytes.extend(chunk(b"CgBI", &[0x50, 0x00, 0x20, 0x02]));
+    bytes.extend(chunk(b"IHDR", &ihdr));
+    bytes.extend(chunk(b"IDAT", &deflate));
+    bytes.extend(chunk(b"IEND", &[]));
+    bytes
+}
+
+/// Checks CgBI files are only decoded when asked for, with their pixels
+/// swapped back to RGBA and unpremultiplied, whichever way they're read.
+#[test]
+pub fn cgbi_is_decoded_when_enabled() {
+    // BGRA, premultiplied. The second row repeats its first pixel with the
+    // Sub filter, which has to be undone before the pixels are restored.
+    let bytes = build(&[
+        0, 1, 2, 3, 255, 9, 9, 9, 0, //
+        1, 10, 20, 30, 128, 0, 0, 0, 0,
+    ]);
+    let expected = [
+        (3, 2, 1, 255),
+        (9, 9, 9, 0),
+        (60, 40, 20, 128),
+        (60, 40, 20, 128),
+    ];
+
+    let result = Png::decoder().decode_bytes(&bytes);
+    assert!(matches!(
+        result.as_ref().map_err(|e| e.root()),
+        Err(DecoderError::InvalidChunk(_))
+    ));
+
+    let png = Png::decoder().cgbi(true).decode_bytes(&bytes).unwrap();
+    assert!(png.is_cgbi());
+    assert_eq!(png.rgba().unwrap(), expected);
+
+    let rows: Vec<u8> = png.rows().unwrap().flat_map(|row| row.unwrap()).collect();
+    let flat: Vec<u8> = expected
+        .iter()
+        .flat_map(|&(r, g, b, a)| [r, g, b, a])
+        .collect();
+    assert_eq!(rows, flat);
+
+    let mut recompressed = Png::decoder().cgbi(true).decode_bytes(&bytes).unwrap();
+    recompressed
+        .recompress(
+            &chameleon::compression::backend::Native,
+            &chameleon::compression::deflate::DeflateOptions::new(),
+        )
+        .unwrap();
+    assert_ne!(recompressed.idat_bytes()[..2], [0x78, 0x01]);
+    let reread = Png::decoder()
+        .cgbi(true)
+        .decode_bytes(&recompressed.to_bytes())
+        .unwrap();
+    assert_eq!(reread.rgba().unwrap(), expected);
+
+    let plain = Png::decoder()
+        .cgbi(true)
+        .decode("./tests/samples/basn6a08.png")
+        .unwrap();
+    assert!(!plain.is_cgbi());
+}
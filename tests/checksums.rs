@@ -0,0 +1,80 @@
+#![cfg(feature = "std")]
+
+use chameleon::compression::checksums::{
+    adler, crc, Adler32, Checksum, Crc, Crc16Arc, Crc16Ibm3740, Crc16Xmodem, Crc32, Crc32Bzip2,
+    Crc32IsoHdlc, Crc32c, Crc64Xz,
+};
+
+/// Bytes from a xorshift generator, to checksum.
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x9e37_79b9u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+/// Checksums data with C all at once, and split at every position, checking
+/// the two agree.
+fn split<C: Checksum>(data: &[u8]) -> C::Output
+where
+    C::Output: PartialEq + std::fmt::Debug,
+{
+    let whole = C::checksum(data);
+    for at in 0..=data.len() {
+        let mut checksum = C::default();
+        checksum.update(&data[..at]);
+        checksum.update(&data[at..]);
+        assert_eq!(checksum.finish(), whole, "{at}");
+    }
+    whole
+}
+
+/// Checks each CRC gives the check value the CRC catalogue lists for it,
+/// the checksum of "123456789", however the data is split.
+#[test]
+pub fn crcs_match_the_catalogue() {
+    let check = b"123456789";
+
+    assert_eq!(split::<Crc16Arc>(check), 0xbb3d);
+    assert_eq!(split::<Crc16Ibm3740>(check), 0x29b1);
+    assert_eq!(split::<Crc16Xmodem>(check), 0x31c3);
+    assert_eq!(split::<Crc32IsoHdlc>(check), 0xcbf4_3926);
+    assert_eq!(split::<Crc32Bzip2>(check), 0xfc89_1918);
+    assert_eq!(split::<Crc32c>(check), 0xe306_9283);
+    assert_eq!(split::<Crc64Xz>(check), 0x995d_c9bb_df19_39fa);
+    // CRC-8/SMBUS and CRC-24/OPENPGP, widths without an alias.
+    assert_eq!(split::<Crc<8, 0x07, 0, false, 0>>(check), 0xf4);
+    assert_eq!(
+        split::<Crc<24, 0x86_4cfb, 0xb7_04ce, false, 0>>(check),
+        0x21_cf02
+    );
+    // CRC-16/KERMIT, reflected with an INIT that isn't symmetric.
+    assert_eq!(split::<Crc<16, 0x1021, 0, true, 0>>(check), 0x2189);
+    assert_eq!(split::<Crc<16, 0x1021, 0xc6c6, true, 0>>(check), 0xbf05);
+
+    assert_eq!(Crc32c::checksum(b""), 0);
+    assert_eq!(Crc16Ibm3740::checksum(b""), 0xffff);
+}
+
+/// Checks the generic CRC32 agrees with the slicing-by-8 one, both
+/// checksums agree with the functions they had before, and CRC32 is still
+/// at the path it had.
+#[test]
+pub fn checksums_agree() {
+    let data = noise(5000);
+
+    assert_eq!(split::<Crc32>(&data[..100]), crc::hash(&data[..100]));
+    assert_eq!(Crc32IsoHdlc::checksum(&data), crc::hash(&data) as u64);
+    assert_eq!(split::<Adler32>(&data[..100]), adler::adler32(&data[..100]));
+    assert_eq!(Adler32::checksum(b"Wikipedia"), 0x11e6_0398);
+
+    assert_eq!(
+        chameleon::compression::crc::hash(&data),
+        <Crc32 as Checksum>::checksum(&data)
+    );
+}
@@ -0,0 +1,496 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use std::fs;
+
+use chameleon::formats::png::{Chunk, ChunkType, Palette, PhysicalUnit, Png, TextChunk};
+use chameleon::prelude::{EncodeOptions, Exif, Image, Metadata, Time, Violation};
+
+/// Checks the property bits of a few chunk types from 5.4 of the spec.
+#[test]
+pub fn chunk_type_properties() {
+    let ihdr = ChunkType::IHDR;
+    assert!(ihdr.is_critical() && !ihdr.is_private() && !ihdr.is_safe_to_copy());
+
+    let text = ChunkType::new(*b"tEXt").unwrap();
+    assert!(text.is_ancillary() && !text.is_private() && text.is_safe_to_copy());
+
+    let private = ChunkType::new(*b"prVt").unwrap();
+    assert!(private.is_ancillary() && private.is_private());
+    assert_eq!(private.to_string(), "prVt");
+
+    assert!(ChunkType::new(*b"IH1R").is_err());
+}
+
+/// Checks that an ancillary chunk that isn't in the spec's list, eXIf here,
+/// is kept rather than stopping the decode.
+#[test]
+pub fn unknown_ancillary_chunks_are_kept() {
+    let png = Png::from_path("./tests/samples/exif2c08.png").unwrap();
+
+    assert!(png
+        .data
+        .ancillary_chunks
+        .iter()
+        .any(|chunk| chunk.ctype.as_bytes() == b"eXIf"));
+    assert!(png.rgb().is_ok());
+}
+
+/// Checks that private chunks are read, and carried over by Png::metadata
+/// when they're safe to copy and dropped when they aren't.
+#[test]
+pub fn private_chunks_carry_over() {
+    let mut png = Png::from_path("./tests/samples/basn2c08.png").unwrap();
+    let first_idat = png.data.idat[0].offset;
+    for (offset, ctype) in [(1, b"prVt"), (2, b"prVT")] {
+        let mut chunk = Chunk::with_data(ChunkType::new(*ctype).unwrap(), b"data".to_vec());
+        chunk.offset = first_idat - offset;
+        png.data.ancillary_chunks.push(chunk);
+    }
+
+    let png = Png::decoder().decode_bytes(&png.to_bytes()).unwrap();
+    let private = png.data.ancillary_chunks.iter();
+    assert_eq!(private.filter(|chunk| chunk.ctype.is_private()).count(), 2);
+    let metadata = png.metadata();
+    let safe = ChunkType::new(*b"prVt").unwrap();
+    assert_eq!(metadata.chunks, [(safe, b"data".to_vec())]);
+
+    let options = EncodeOptions::new().metadata(metadata);
+    let bytes = Image::try_from(&png).unwrap().encode_png(&options).unwrap();
+    let copy = Png::decoder().decode_bytes(&bytes).unwrap();
+    assert_eq!(copy.metadata(), png.metadata());
+
+    // Critical chunks aren't written, even when asked for.
+    let mut metadata = Metadata::new();
+    metadata
+        .chunks
+        .push((ChunkType::new(*b"PRVT").unwrap(), vec![]));
+    let options = EncodeOptions::new().metadata(metadata);
+    let bytes = Image::try_from(&png).unwrap().encode_png(&options).unwrap();
+    assert!(Png::decoder().decode_bytes(&bytes).is_ok());
+}
+
+/// Checks that writing a file back out without changes gives the same
+/// bytes.
+#[test]
+pub fn unchanged_files_are_rewritten_exactly() {
+    for name in ["basn2c08.png", "basn3p08.png", "tbbn3p08.png"] {
+        let bytes = fs::read(format!("./tests/samples/{name}")).unwrap();
+        let png = Png::decoder().decode_bytes(&bytes).unwrap();
+
+        assert_eq!(png.to_bytes(), bytes, "{}", name);
+    }
+}
+
+/// Checks that each chunk serializes to the bytes it was read from, and
+/// that a file can be reassembled with chunks stripped and added.
+#[test]
+pub fn chunks_are_reassembled() {
+    let bytes = fs::read("./tests/samples/basn2c08.png").unwrap();
+    let mut png = Png::decoder().decode_bytes(&bytes).unwrap();
+    for chunk in png.data.idat.iter().chain(&png.data.ancillary_chunks) {
+        let stored = &bytes[chunk.offset..chunk.offset + chunk.size];
+        assert_eq!(chunk.to_bytes(), stored, "{}", chunk.ctype);
+    }
+
+    png.data
+        .ancillary_chunks
+        .retain(|chunk| chunk.ctype.as_bytes() != b"gAMA");
+    let ctype = ChunkType::new(*b"tEXt").unwrap();
+    let mut comment = Chunk::with_data(ctype, b"Comment\0Added".to_vec());
+    comment.offset = png.data.idat[0].offset - 1;
+    png.data.ancillary_chunks.push(comment);
+
+    let rewritten = Png::decoder().decode_bytes(&png.data.write()).unwrap();
+    let types = rewritten
+        .data
+        .ancillary_chunks
+        .iter()
+        .map(|chunk| chunk.ctype);
+    assert!(types.map(|ctype| ctype.to_string()).eq(["tEXt", "IEND"]));
+    assert_eq!(rewritten.text()[0].text, "Added");
+    assert_eq!(rewritten.rgba().unwrap(), png.rgba().unwrap());
+}
+
+/// Checks that every PngSuite file that should decode follows the ordering
+/// rules, and that each kind of violation is found in a file built to break
+/// them.
+#[test]
+pub fn validate_finds_violations() {
+    for entry in fs::read_dir("./tests/samples").unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        if name.starts_with('x') || path.extension().is_none_or(|e| e != "png") {
+            continue;
+        }
+        let png = Png::from_path(&path).unwrap();
+        assert_eq!(png.validate(), [], "{name}");
+    }
+
+    // IHDR, gAMA, PLTE, IDAT then IEND.
+    let png = Png::from_path("./tests/samples/basn3p08.png").unwrap();
+    let chunk = |ctype: &[u8; 4], data: &[u8]| {
+        Chunk::with_data(ChunkType::new(*ctype).unwrap(), data.to_vec()).to_bytes()
+    };
+    let (first, second) = png.data.idat[0].data.split_at(100);
+    let parts = [
+        png.data.ihdr.to_bytes(),
+        png.data.plte.as_ref().unwrap().to_bytes(),
+        png.data.ancillary_chunks[0].to_bytes(),
+        chunk(b"IDAT", first),
+        chunk(b"tIME", &[7, 178, 1, 1, 0, 0, 0]),
+        chunk(b"IDAT", second),
+        chunk(b"tIME", &[7, 178, 1, 1, 0, 0, 0]),
+        chunk(b"IEND", &[]),
+        b"junk".to_vec(),
+    ];
+    let offsets = parts
+        .iter()
+        .scan(8, |offset, part| {
+            *offset += part.len();
+            Some(*offset - part.len())
+        })
+        .collect::<Vec<_>>();
+    let bytes = [&b"\x89PNG\r\n\x1a\n"[..], &parts.concat()].concat();
+
+    let broken = Png::decoder().decode_bytes(&bytes).unwrap();
+    assert_eq!(broken.rgba().unwrap(), png.rgba().unwrap());
+    let ctype = |ctype: &[u8; 4]| ChunkType::new(*ctype).unwrap();
+    assert_eq!(
+        broken.validate(),
+        [
+            Violation::Misplaced {
+                ctype: ctype(b"gAMA"),
+                offset: offsets[2],
+                rule: "it must come before PLTE and IDAT.",
+            },
+            Violation::SplitIdat { offset: offsets[5] },
+            Violation::Duplicate {
+                ctype: ctype(b"tIME"),
+                offset: offsets[6],
+            },
+            Violation::TrailingData {
+                offset: offsets[8],
+                len: 4,
+            },
+        ]
+    );
+
+    let truncated = Png::decoder()
+        .decode_bytes(&bytes[..offsets[4] + 5])
+        .unwrap();
+    assert_eq!(
+        truncated.validate(),
+        [
+            Violation::Misplaced {
+                ctype: ctype(b"gAMA"),
+                offset: offsets[2],
+                rule: "it must come before PLTE and IDAT.",
+            },
+            Violation::Unreadable { offset: offsets[4] },
+            Violation::MissingIend,
+        ]
+    );
+}
+
+/// Checks that a changed palette, including its alpha, is written back out
+/// while the image data is left alone.
+#[test]
+pub fn palette_round_trip() {
+    let mut png = Png::from_path("./tests/samples/tbbn3p08.png").unwrap();
+    let mut palette = png.palette().unwrap();
+    assert!(palette.has_alpha());
+
+    palette.remap(|[r, g, b, a]| [255 - r, 255 - g, 255 - b, a]);
+    palette.entries[0][3] = 255;
+    let last = palette.len() - 1;
+    palette.entries[last][3] = 128;
+    png.set_palette(&palette).unwrap();
+
+    let rewritten = Png::decoder().decode_bytes(&png.to_bytes()).unwrap();
+    assert_eq!(rewritten.palette(), Some(palette));
+
+    let idat = |png: &Png| {
+        png.data
+            .idat
+            .iter()
+            .map(|ch| ch.data.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(idat(&rewritten), idat(&png));
+}
+
+/// Checks the Palette editing methods.
+#[test]
+pub fn palette_editing() {
+    let red = [255, 0, 0, 255];
+    let green = [0, 255, 0, 255];
+    let blue = [0, 0, 255, 255];
+    let mut palette = Palette::new(vec![red, green, red]);
+
+    assert_eq!(palette.replace(red, blue), 2);
+    assert_eq!(palette.entries, vec![blue, green, blue]);
+
+    palette.reorder(&[1, 0]).unwrap();
+    assert_eq!(palette.entries, vec![green, blue]);
+    assert!(palette.reorder(&[2]).is_err());
+    assert_eq!(palette.entries, vec![green, blue]);
+
+    palette.entries[1][3] = 0;
+    assert_eq!(palette.to_string(), "#00ff00 #0000ff00");
+}
+
+/// Checks text is read from tEXt, inflated from zTXt and decoded as UTF-8
+/// from iTXt, compressed or not.
+#[test]
+pub fn text_chunks() {
+    let text = Png::from_path("./tests/samples/ct1n0g04.png")
+        .unwrap()
+        .text();
+    assert_eq!(text.len(), 6);
+    assert_eq!(
+        text[0],
+        TextChunk {
+            keyword: "Title".to_string(),
+            text: "PngSuite".to_string(),
+            compressed: false,
+            language: None,
+            translated_keyword: None,
+        }
+    );
+
+    let mut png = Png::from_path("./tests/samples/ctzn0g04.png").unwrap();
+    let copyright = &png.text()[2];
+    assert!(copyright.compressed);
+    assert_eq!(copyright.keyword, "Copyright");
+    assert_eq!(
+        copyright.text,
+        "Copyright Willem van Schaik, Singapore 1995-96"
+    );
+
+    let text = Png::from_path("./tests/samples/ctjn0g04.png")
+        .unwrap()
+        .text();
+    assert_eq!(text[0].language.as_deref(), Some("ja"));
+    assert_eq!(text[0].translated_keyword.as_deref(), Some("タイトル"));
+    assert_eq!(text[5].text, "フリーウェア。");
+
+    // Rewrap the zTXt's compressed text as a compressed iTXt.
+    let ztxt = png
+        .data
+        .ancillary_chunks
+        .iter()
+        .find(|chunk| chunk.ctype.as_bytes() == b"zTXt")
+        .unwrap();
+    let compressed = &ztxt.data["Copyright".len() + 2..];
+    let mut itxt = b"Copyright\0\x01\0en\0\0".to_vec();
+    itxt.extend_from_slice(compressed);
+    png.data
+        .ancillary_chunks
+        .push(Chunk::with_data(ChunkType::new(*b"iTXt").unwrap(), itxt));
+
+    let last = png.text().pop().unwrap();
+    assert!(last.compressed);
+    assert_eq!(last.language.as_deref(), Some("en"));
+    assert_eq!(last.text, copyright.text);
+}
+
+/// Checks the pHYs chunks of PngSuite's aspect ratio images, and that files
+/// without one have no PhysicalDimensions.
+#[test]
+pub fn physical_dimensions() {
+    let read = |name: &str| {
+        Png::from_path(format!("./tests/samples/{name}.png"))
+            .unwrap()
+            .physical_dimensions()
+    };
+
+    // 8x32 pixels 4 times as wide as they are tall, so shown square.
+    let tall = read("cdfn2c08").unwrap();
+    assert_eq!(tall.pixels_per_unit, (1, 4));
+    assert_eq!(tall.unit, PhysicalUnit::Unknown);
+    assert_eq!(tall.aspect_ratio(), Some(4.0));
+    assert_eq!(tall.dpi(), None);
+    assert_eq!(read("cdhn2c08").unwrap().aspect_ratio(), Some(0.25));
+    assert_eq!(read("cdsn2c08").unwrap().aspect_ratio(), Some(1.0));
+
+    let meters = read("cdun2c08").unwrap();
+    assert_eq!(meters.unit, PhysicalUnit::Meter);
+    let (x, y) = meters.dpi().unwrap();
+    assert!((x - 25.4).abs() < 1e-9 && (y - 25.4).abs() < 1e-9);
+
+    assert_eq!(read("basn2c08"), None);
+}
+
+/// Checks that text and pHYs carried over with Png::metadata come back the
+/// same from the new file, each text chunk keeping its type, and that text
+/// PNG can't hold is left out.
+#[test]
+pub fn metadata_carries_over() {
+    let types = |png: &Png| {
+        png.data
+            .ancillary_chunks
+            .iter()
+            .map(|chunk| chunk.ctype)
+            .filter(|ctype| {
+                ctype.as_bytes()[1..] == *b"TXt" || [b"pHYs", b"eXIf"].contains(&ctype.as_bytes())
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for name in [
+        "ct1n0g04", "ctzn0g04", "ctjn0g04", "cdun2c08", "exif2c08", "basn2c08",
+    ] {
+        let png = Png::from_path(format!("./tests/samples/{name}.png")).unwrap();
+        let options = EncodeOptions::new().metadata(png.metadata());
+        let bytes = Image::try_from(&png).unwrap().encode_png(&options).unwrap();
+
+        let copy = Png::decoder().decode_bytes(&bytes).unwrap();
+        assert_eq!(copy.metadata(), png.metadata(), "{name}");
+        assert_eq!(types(&copy), types(&png), "{name}");
+    }
+
+    let mut metadata = Metadata::new();
+    assert!(metadata.is_empty());
+    for keyword in ["", "タイトル", "Title"] {
+        metadata.text.push(TextChunk {
+            keyword: keyword.to_string(),
+            text: "Ω".to_string(),
+            compressed: false,
+            language: None,
+            translated_keyword: None,
+        });
+    }
+    let options = EncodeOptions::new().metadata(metadata);
+    let bytes = Image::solid(1, 1, [0; 4])
+        .unwrap()
+        .encode_png(&options)
+        .unwrap();
+    let text = Png::decoder().decode_bytes(&bytes).unwrap().text();
+    assert_eq!(text.len(), 1);
+    assert_eq!(text[0].keyword, "Title");
+    assert_eq!(text[0].text, "Ω");
+    assert_eq!(text[0].language.as_deref(), Some(""));
+}
+
+/// Checks the orientation and times are read from EXIF data in either byte
+/// order, and that broken data is refused.
+#[test]
+pub fn exif_fields() {
+    let png = Png::from_path("./tests/samples/exif2c08.png").unwrap();
+    let exif = Exif::parse(png.exif().unwrap()).unwrap();
+    assert_eq!(exif.orientation, Some(1));
+    assert_eq!(exif.date_time, None);
+    assert_eq!(
+        Png::from_path("./tests/samples/basn2c08.png")
+            .unwrap()
+            .exif(),
+        None
+    );
+
+    // Little endian, Orientation 6 and a pointer to an Exif IFD in IFD0,
+    // and DateTimeOriginal in the Exif IFD.
+    let mut bytes = b"Exif\0\0II*\0\x08\0\0\0".to_vec();
+    bytes.extend_from_slice(&[2, 0]);
+    bytes.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0]);
+    bytes.extend_from_slice(&[0x69, 0x87, 4, 0, 1, 0, 0, 0, 38, 0, 0, 0]);
+    bytes.extend_from_slice(&[0; 4]);
+    assert_eq!(bytes.len() - 6, 38);
+    bytes.extend_from_slice(&[1, 0]);
+    bytes.extend_from_slice(&[0x03, 0x90, 2, 0, 20, 0, 0, 0, 56, 0, 0, 0]);
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(b"2024:02:29 13:45:07\0");
+
+    let exif = Exif::parse(&bytes).unwrap();
+    assert_eq!(exif.orientation, Some(6));
+    assert_eq!(
+        exif.date_time_original,
+        Some(Time {
+            year: 2024,
+            month: 2,
+            day: 29,
+            hour: 13,
+            minute: 45,
+            second: 7,
+        })
+    );
+
+    // A DateTime whose text would start past the end of the address space.
+    let mut bytes = b"II*\0\x08\0\0\0".to_vec();
+    bytes.extend_from_slice(&[1, 0]);
+    bytes.extend_from_slice(&[0x32, 0x01, 2, 0, 20, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+    bytes.extend_from_slice(&[0; 4]);
+    assert_eq!(Exif::parse(&bytes).unwrap().date_time, None);
+
+    assert_eq!(Exif::parse(b"MM\0*\0\0\0\x08"), None);
+    assert_eq!(Exif::parse(b"JFIF"), None);
+}
+
+/// Checks recompressing the image data keeps the pixels and the other
+/// chunks, and joins the IDAT chunks of files that split it up.
+#[test]
+pub fn image_data_is_recompressed() {
+    use chameleon::compression::backend::Native;
+    use chameleon::compression::deflate::{CompressionLevel, DeflateOptions};
+    use chameleon::compression::zlib::ZlibStream;
+
+    for name in ["basn2c08", "basi0g01", "oi9n2c16", "ctzn0g04"] {
+        let mut png = Png::from_path(format!("./tests/samples/{name}.png")).unwrap();
+        let joined: Vec<u8> = png.data.idat.iter().flat_map(|c| c.data.clone()).collect();
+        assert_eq!(png.idat_bytes(), joined, "{name}");
+        let inflated = ZlibStream::build(&joined).unwrap().decompress().unwrap();
+
+        let stored = DeflateOptions::new().level(CompressionLevel::None);
+        png.recompress(&Native, &stored).unwrap();
+        let stored_len = png.idat_bytes().len();
+        assert!(stored_len > inflated.len(), "{name}");
+
+        png.recompress(&Native, &DeflateOptions::new()).unwrap();
+        assert!(png.idat_bytes().len() < stored_len, "{name}");
+        assert_eq!(png.data.idat.len(), 1, "{name}");
+        let stream = png.idat_bytes();
+        assert_eq!(
+            ZlibStream::build(&stream).unwrap().decompress().unwrap(),
+            inflated
+        );
+
+        let rewritten = Png::decoder()
+            .strict(true)
+            .decode_bytes(&png.to_bytes())
+            .unwrap();
+        let original = Png::from_path(format!("./tests/samples/{name}.png")).unwrap();
+        assert_eq!(
+            rewritten.rgba().unwrap(),
+            original.rgba().unwrap(),
+            "{name}"
+        );
+        assert_eq!(rewritten.text(), original.text(), "{name}");
+        assert!(rewritten.validate().is_empty(), "{name}");
+    }
+}
+
+/// Checks the CRC32 worked out eight bytes at a time matches the one a byte
+/// at a time, whatever pieces the data is split into.
+#[test]
+pub fn crc32_matches_bytewise() {
+    use chameleon::compression::crc::{self, Crc32, CRC32_TABLE};
+
+    let bytewise = |data: &[u8]| {
+        !data.iter().fold(!0u32, |crc, &byte| {
+            CRC32_TABLE[((crc as u8) ^ byte) as usize] ^ (crc >> 8)
+        })
+    };
+
+    assert_eq!(crc::hash(b"123456789"), 0xcbf4_3926);
+    assert_eq!(crc::hash(&[]), 0);
+    let data: Vec<u8> = (0..1000u32).map(|i| (i * 31 + i / 7) as u8).collect();
+    for len in [1, 7, 8, 9, 15, 16, 17, 100, 1000] {
+        let expected = bytewise(&data[..len]);
+        assert_eq!(crc::hash(&data[..len]), expected, "{len}");
+        for split in [0, 1, 3, 8, len / 2] {
+            let mut crc = Crc32::new();
+            crc.update(&data[..split.min(len)]);
+            crc.update(&data[split.min(len)..len]);
+            assert_eq!(crc.finish(), expected, "{len} split at {split}");
+        }
+    }
+}
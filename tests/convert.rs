@@ -0,0 +1,74 @@
+#![cfg(feature = "std")]
+
+use chameleon::convert;
+use chameleon::prelude::*;
+
+/// Checks conversions between gray and color, and that a missing alpha is
+/// made opaque.
+#[test]
+pub fn gray_and_color_convert() {
+    let mut rgba = Vec::new();
+    convert::convert_pixels(
+        PixelFormat::GrayAlpha8,
+        PixelFormat::Rgba8,
+        &[7, 9],
+        &mut rgba,
+    )
+    .unwrap();
+    assert_eq!(rgba, [7, 7, 7, 9]);
+
+    let mut gray = Vec::new();
+    convert::convert_pixels(
+        PixelFormat::Rgb8,
+        PixelFormat::Gray8,
+        &[40, 40, 40, 255, 0, 0],
+        &mut gray,
+    )
+    .unwrap();
+    assert_eq!(gray, [40, 54]);
+
+    let mut rgba = Vec::new();
+    convert::convert_pixels(PixelFormat::Gray16, PixelFormat::Rgba16, &[1, 2], &mut rgba).unwrap();
+    assert_eq!(rgba, [1, 2, 1, 2, 1, 2, 255, 255]);
+}
+
+/// Checks that 8 bit samples widen exactly, and that 16 bit samples are
+/// reduced by the Dither given.
+#[test]
+pub fn bit_depth_converts() {
+    let mut wide = Vec::new();
+    convert::convert_pixels(PixelFormat::Gray8, PixelFormat::Gray16, &[0x12], &mut wide).unwrap();
+    assert_eq!(wide, [0x12, 0x12]);
+
+    let samples = [0x12, 0xff, 0x12, 0x7f];
+    let mut truncated = Vec::new();
+    convert::convert_pixels(
+        PixelFormat::Gray16,
+        PixelFormat::Gray8,
+        &samples,
+        &mut truncated,
+    )
+    .unwrap();
+    assert_eq!(truncated, [0x12, 0x12]);
+
+    let mut rounded = Vec::new();
+    convert::convert_pixels_with(
+        PixelFormat::Gray16,
+        PixelFormat::Gray8,
+        &samples,
+        &mut rounded,
+        &mut Round,
+    )
+    .unwrap();
+    assert_eq!(rounded, [0x13, 0x12]);
+}
+
+/// Checks that a buffer holding part of a pixel is refused.
+#[test]
+pub fn partial_pixels_are_refused() {
+    let mut out = Vec::new();
+    assert_eq!(
+        convert::convert_pixels(PixelFormat::Rgb16, PixelFormat::Rgb8, &[0; 7], &mut out),
+        Err(ImageError::BufferSize(12, 7))
+    );
+}
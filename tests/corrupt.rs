@@ -0,0 +1,711 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use chameleon::formats::png::{Chunk, ChunkType};
+use chameleon::prelude::*;
+
+/// Decodes a file from tests/corrupt and converts it to RGB.
+///
+/// # Returns
+///
+/// The RGB pixels, or the code of the error from whichever step failed.
+fn decode(name: &str, options: DecodeOptions) -> Result<Vec<(u8, u8, u8)>, i32> {
+    let png = options
+        .decode(format!("./tests/corrupt/{name}.png"))
+        .map_err(|e| Error::from(e).code())?;
+
+    png.rgb().map_err(|e| Error::from(e).code())
+}
+
+fn original() -> Vec<(u8, u8, u8)> {
+    Png::from_path("./tests/samples/basn2c08.png")
+        .unwrap()
+        .rgb()
+        .unwrap()
+}
+
+/// A bad CRC is an error, found while parsing when strict and as missing
+/// image data otherwise, is skipped when checksums are ignored, and is
+/// recorded when they're tolerated.
+#[test]
+pub fn bad_crc() {
+    assert_eq!(decode("bad_crc", Png::decoder().strict(true)), Err(302));
+    assert_eq!(decode("bad_crc", Png::decoder()), Err(302));
+    assert_eq!(
+        decode("bad_crc", Png::decoder().checksums(ChecksumPolicy::Ignore)),
+        Ok(original())
+    );
+
+    let Err(error) = Png::decoder()
+        .strict(true)
+        .decode("./tests/corrupt/bad_crc.png")
+    else {
+        panic!("bad_crc decoded");
+    };
+    assert_eq!(
+        error.context().unwrap().chunk,
+        Some(("IDAT".to_string(), 49))
+    );
+
+    for strict in [false, true] {
+        let tolerant = Png::decoder()
+            .strict(strict)
+            .checksums(ChecksumPolicy::Tolerate);
+        let png = tolerant.decode("./tests/corrupt/bad_crc.png").unwrap();
+        assert_eq!(png.data.crc_mismatches, [(ChunkType::IDAT, 49)]);
+        assert_eq!(png.rgb().unwrap(), original());
+    }
+    let png = Png::decoder()
+        .checksums(ChecksumPolicy::Tolerate)
+        .decode("./tests/samples/basn2c08.png")
+        .unwrap();
+    assert!(png.data.crc_mismatches.is_empty());
+    assert_eq!(
+        decode(
+            "bad_adler",
+            Png::decoder().checksums(ChecksumPolicy::Tolerate)
+        ),
+        Err(404)
+    );
+}
+
+/// A file cut off part way through IDAT is an error however it's decoded.
+#[test]
+pub fn truncated_idat() {
+    for options in [
+        Png::decoder(),
+        Png::decoder().strict(true),
+        Png::decoder().checksums(ChecksumPolicy::Ignore),
+    ] {
+        assert_eq!(decode("truncated_idat", options), Err(302));
+    }
+}
+
+/// A missing IEND chunk doesn't stop the image being decoded.
+#[test]
+pub fn missing_iend() {
+    assert_eq!(
+        decode("missing_iend", Png::decoder().strict(true)),
+        Ok(original())
+    );
+}
+
+/// An unknown filter type is an error that holds the bad byte.
+#[test]
+pub fn invalid_filter() {
+    let png = Png::from_path("./tests/corrupt/invalid_filter.png").unwrap();
+
+    assert!(matches!(
+        png.rgb().map_err(|e| e.root().to_string()),
+        Err(message) if message.contains("Invalid filter type 5")
+    ));
+}
+
+/// Huffman code lengths that can't make a code are a compression error
+/// rather than a hang or a panic.
+#[test]
+pub fn bad_huffman() {
+    assert_eq!(decode("bad_huffman", Png::decoder()), Err(402));
+}
+
+/// A bad adler32 is an error holding both checksums, unless checksums are
+/// ignored.
+#[test]
+pub fn bad_adler() {
+    use chameleon::compression::{
+        checksums::adler::adler32, inflate::DeflateError, zlib::ZlibStream,
+    };
+
+    assert_eq!(decode("bad_adler", Png::decoder()), Err(404));
+
+    let png = Png::from_path("./tests/corrupt/bad_adler.png").unwrap();
+    let stream = png.idat_bytes();
+    let stored = u32::from_be_bytes(stream[stream.len() - 4..].try_into().unwrap());
+    let inflated = ZlibStream::build(&stream)
+        .unwrap()
+        .deflate
+        .decompress()
+        .unwrap();
+    let error = png.rgb().unwrap_err();
+    let DecoderError::DeflateError(DeflateError::ChecksumMismatch { expected, actual }) =
+        error.root()
+    else {
+        panic!("{error}");
+    };
+    assert_eq!((*expected, *actual), (stored, adler32(&inflated)));
+    assert_ne!(expected, actual);
+
+    assert_eq!(
+        decode(
+            "bad_adler",
+            Png::decoder().checksums(ChecksumPolicy::Ignore)
+        ),
+        Ok(original())
+    );
+}
+
+/// Pulling rows stops at the first damaged row, and a bad adler32 is found
+/// once every row has been given.
+#[test]
+pub fn rows_stop_at_damage() {
+    let rows = |name: &str, options: DecodeOptions| -> (usize, Option<i32>) {
+        let png = options
+            .decode(format!("./tests/corrupt/{name}.png"))
+            .unwrap();
+        let mut good = 0;
+        for row in png.rows().unwrap() {
+            match row {
+                Ok(_) => good += 1,
+                Err(e) => return (good, Some(Error::from(e).code())),
+            }
+        }
+        (good, None)
+    };
+
+    assert_eq!(rows("bad_adler", Png::decoder()), (32, Some(404)));
+    let unchecked = Png::decoder().checksums(ChecksumPolicy::Ignore);
+    assert_eq!(rows("bad_adler", unchecked), (32, None));
+    assert_eq!(rows("bad_huffman", Png::decoder()).1, Some(402));
+    assert_eq!(rows("invalid_filter", Png::decoder()).1, Some(306));
+
+    let png = Png::decoder()
+        .decode_bytes(&build(1, 2, 8, 0, &[0, 7, 0]))
+        .unwrap();
+    let mut rows = png.rows().unwrap();
+    assert_eq!(rows.next().unwrap().unwrap(), [7, 7, 7, 255]);
+    assert!(matches!(
+        rows.next(),
+        Some(Err(DecoderError::InvalidChunk(_)))
+    ));
+    assert!(rows.next().is_none());
+}
+
+/// Builds a PNG file around the given IHDR fields and decompressed image
+/// data, stored in a single uncompressed deflate block.
+fn build(width: u32, height: u32, bit_depth: u8, color_type: u8, data: &[u8]) -> Vec<u8> {
+    let len = data.len() as u16;
+    let mut zlib = vec![0x78, 0x01, 1];
+    zlib.extend_from_slice(&len.to_le_bytes());
+    zlib.extend_from_slice(&(!len).to_le_bytes());
+    zlib.extend_from_slice(data);
+    zlib.extend_from_slice(&chameleon::compression::checksums::adler::adler32(data).to_be_bytes());
+
+    build_zlib(width, height, bit_depth, color_type, &zlib)
+}
+
+/// Builds a PNG file around the given IHDR fields and zlib stream, held in
+/// a single IDAT chunk as it's given.
+fn build_zlib(width: u32, height: u32, bit_depth: u8, color_type: u8, zlib: &[u8]) -> Vec<u8> {
+    let chunk = |ctype: &[u8; 4], data: &[u8]| {
+        Chunk::with_data(ChunkType::new(*ctype).unwrap(), data.to_vec()).to_bytes()
+    };
+
+    let mut ihdr = width.to_be_bytes().to_vec();
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[bit_depth, color_type, 0, 0, 0]);
+
+    let mut bytes = vec![137, 80, 78, 71, 13, 10, 26, 10];
+    bytes.extend(chunk(b"IHDR", &ihdr));
+    bytes.extend(chunk(b"IDAT", zlib));
+    bytes.extend(chunk(b"IEND", &[]));
+    bytes
+}
+
+/// zlib streams too short for their header, the DICTID FDICT says follows
+/// it, or the adler32 trailer are errors rather than panics, read on their
+/// own or as a PNG's image data.
+#[test]
+pub fn short_zlib_streams() {
+    use chameleon::compression::zlib::{ZlibError, ZlibHeader, ZlibStream};
+
+    // 0x7820 is a multiple of 31 with FDICT set.
+    let stream = [0x78, 0x20, 0, 0, 0, 0, 0, 0, 0, 0];
+    for len in 0..6 {
+        assert!(
+            matches!(
+                ZlibHeader::build(&stream[..len]),
+                Err(ZlibError::InvalidHeader(_))
+            ),
+            "{len}"
+        );
+    }
+    assert_eq!(ZlibHeader::build(&stream).unwrap().end_idx, 6);
+    for len in 0..10 {
+        assert!(
+            matches!(
+                ZlibStream::build(&stream[..len]),
+                Err(ZlibError::InvalidHeader(_))
+            ),
+            "{len}"
+        );
+    }
+    assert!(ZlibStream::build(&stream).is_ok());
+
+    let png = Png::from_bytes(&build_zlib(1, 1, 8, 0, &stream[..6])).unwrap();
+    assert_eq!(png.rgb().map_err(|e| Error::from(e).code()), Err(401));
+    assert!(png.rgba_rows(&mut |_, _| Ok(())).is_err());
+    assert!(png.rows().is_err());
+}
+
+/// IHDR dimensions too large to address are an error when the header is
+/// read, rather than a capacity overflow panic when decoding allocates.
+#[test]
+pub fn huge_dimensions_overflow_cleanly() {
+    for (bit_depth, color_type) in [(16, 6), (1, 0)] {
+        let bytes = build(0x7fff_ffff, 0x7fff_ffff, bit_depth, color_type, &[]);
+        let result = Png::decoder()
+            .limits(Limits::new().max_pixels(usize::MAX))
+            .decode_bytes(&bytes);
+
+        assert!(matches!(
+            result.as_ref().map_err(|e| e.root()),
+            Err(DecoderError::DimensionOverflow(0x7fff_ffff, 0x7fff_ffff))
+        ));
+        assert_eq!(result.map_err(|e| Error::from(e).code()).err(), Some(502));
+    }
+
+    // Wide but short images fit, and fail later for their missing data.
+    let png = Png::decoder()
+        .limits(Limits::new().max_pixels(usize::MAX))
+        .decode_bytes(&build(0x7fff_ffff, 1, 8, 0, &[]))
+        .unwrap();
+    assert!(
+        matches!(png.rgb(), Err(e) if !matches!(e.root(), DecoderError::DimensionOverflow(..)))
+    );
+}
+
+/// A tiny file whose IHDR claims a huge image, too large to allocate but
+/// small enough to address, is an error for its missing data rather than
+/// an allocation failure, as nothing the size of the image is allocated
+/// before its data has been inflated.
+#[test]
+pub fn huge_dimensions_allocate_lazily() {
+    let bytes = build(268_435_488, 32, 8, 0, &[0; 16]);
+    assert!(bytes.len() < 200);
+    let png = Png::decoder()
+        .limits(Limits::new().max_pixels(usize::MAX))
+        .decode_bytes(&bytes)
+        .unwrap();
+
+    assert!(png.rgb().is_err());
+    assert!(png.rgba().is_err());
+    assert!(png.samples().is_err());
+    assert!(png.rgb16().is_err());
+    assert!(png.rgb_composited().is_err());
+}
+
+/// A width or height of 0 is an error, as 11.2.2 of the spec requires,
+/// including when the fields are changed after decoding.
+#[test]
+pub fn zero_dimensions() {
+    for (width, height) in [(0, 1), (1, 0), (0, 0)] {
+        let result = Png::decoder().decode_bytes(&build(width, height, 8, 0, &[0]));
+
+        assert!(matches!(
+            result.as_ref().map_err(|e| e.root()),
+            Err(DecoderError::InvalidDimensions(w, h)) if (*w, *h) == (width as usize, height as usize)
+        ));
+        assert_eq!(result.map_err(|e| Error::from(e).code()).err(), Some(308));
+    }
+
+    let mut png = Png::from_path("./tests/samples/basi0g08.png").unwrap();
+    png.dimensions.0 = 0;
+    assert!(matches!(
+        png.rgb().map_err(|e| e.root().to_string()),
+        Err(message) if message.contains("0x32")
+    ));
+}
+
+/// 1x1 images decode at every color type, interlaced or not.
+#[test]
+pub fn single_pixel() {
+    let png = Png::decoder()
+        .decode_bytes(&build(1, 1, 16, 6, &[0, 1, 2, 3, 4, 5, 6, 7, 8]))
+        .unwrap();
+    assert_eq!(png.rgb16().unwrap(), [(0x0102, 0x0304, 0x0506)]);
+    assert_eq!(png.rgba().unwrap(), [(1, 3, 5, 7)]);
+
+    for name in ["s01n3p01", "s01i3p01"] {
+        let png = Png::from_path(format!("./tests/samples/{name}.png")).unwrap();
+        assert_eq!(png.dimensions, (1, 1));
+        assert_eq!(png.rgba().unwrap().len(), 1);
+    }
+}
+
+/// Image data that ends part way through a scanline, even one shorter
+/// than a pixel, or before the last one, is an error from every decode.
+/// Data past the last scanline is ignored.
+#[test]
+pub fn short_scanlines() {
+    // Three bytes of an eight byte pixel, then a whole row with a bad
+    // second.
+    for data in [&[1, 1, 2, 3][..], &[4, 1, 2, 3, 4, 5, 6, 7, 8, 4, 1]] {
+        let png = Png::decoder()
+            .decode_bytes(&build(1, data.len() as u32 / 4, 16, 6, data))
+            .unwrap();
+
+        assert!(matches!(png.rgb(), Err(DecoderError::InvalidChunk(_))));
+        assert!(matches!(png.rgba(), Err(DecoderError::InvalidChunk(_))));
+        assert!(matches!(png.rgb16(), Err(DecoderError::InvalidChunk(_))));
+        assert!(matches!(png.samples(), Err(DecoderError::InvalidChunk(_))));
+        assert!(matches!(
+            png.thumbnail(1),
+            Err(DecoderError::InvalidChunk(_))
+        ));
+    }
+
+    let png = Png::decoder()
+        .decode_bytes(&build(1, 1, 8, 0, &[0, 7, 0, 9]))
+        .unwrap();
+    assert_eq!(png.rgb().unwrap(), [(7, 7, 7)]);
+}
+
+/// Limits stop an image with too many pixels at IHDR, and image data or
+/// text that inflates past max_decompressed part way through inflating it,
+/// whichever way the pixels are decoded.
+#[test]
+pub fn decompression_limits() {
+    let result = Png::decoder()
+        .limits(Limits::new().max_pixels(9_999))
+        .decode_bytes(&build(100, 100, 8, 0, &[]));
+    assert!(matches!(
+        result.as_ref().map_err(|e| e.root()),
+        Err(DecoderError::LimitExceeded("pixels", 10_000))
+    ));
+
+    // A single gray pixel followed by a thousand bytes of padding.
+    let options = Png::decoder().limits(Limits::new().max_decompressed(1_000));
+    let png = options
+        .decode_bytes(&build(1, 1, 8, 0, &[0; 1_002]))
+        .unwrap();
+    assert!(matches!(
+        png.rgb().unwrap_err().root(),
+        DecoderError::LimitExceeded("decompressed bytes", _)
+    ));
+    assert!(png.rows().unwrap().any(|row| row.is_err()));
+    let png = options.decode_bytes(&build(1, 1, 8, 0, &[0; 2])).unwrap();
+    assert_eq!(png.rgb().unwrap(), [(0, 0, 0)]);
+    let png = options.decode("./tests/samples/basi0g08.png").unwrap();
+    assert!(matches!(
+        png.rgb().unwrap_err().root(),
+        DecoderError::LimitExceeded("decompressed bytes", _)
+    ));
+
+    let options = Png::decoder().limits(Limits::new().max_decompressed(4));
+    let text = options
+        .decode("./tests/samples/ctzn0g04.png")
+        .unwrap()
+        .text();
+    assert!(!text.is_empty());
+    assert!(text.iter().all(|chunk| !chunk.compressed));
+}
+
+/// The default Limits stop a tiny file claiming a huge image at IHDR, from
+/// every way of decoding one, and dimensions raised after decoding are
+/// checked again before anything is allocated for them.
+#[test]
+pub fn default_limits() {
+    let bytes = build(268_435_488, 32, 8, 0, &[0; 16]);
+    let limited = |result: Result<Png, DecoderError>| {
+        matches!(
+            result.as_ref().map_err(|e| e.root()),
+            Err(DecoderError::LimitExceeded("pixels", _))
+        )
+    };
+    assert!(limited(Png::from_bytes(&bytes)));
+    assert!(limited(Png::from_reader(&bytes[..])));
+
+    let mut png = Png::from_bytes(&build(1, 1, 8, 0, &[0, 7])).unwrap();
+    png.dimensions = (1 << 20, 1 << 20);
+    let exceeded = |e: &DecoderError| matches!(e.root(), DecoderError::LimitExceeded("pixels", _));
+    assert!(exceeded(&png.rgb().unwrap_err()));
+    assert!(exceeded(&png.thumbnail(16).unwrap_err()));
+    assert!(png.rows().is_err_and(|e| exceeded(&e)));
+}
+
+/// Malformed deflate streams are errors saying what's wrong, never a panic
+/// or a hang, and a stream cut off anywhere is an error rather than a
+/// shorter output.
+#[test]
+pub fn malformed_deflate() {
+    use chameleon::compression::deflate;
+    use chameleon::compression::inflate::{DeflateError, DeflateStream};
+
+    let inflate = |bytes: &[u8]| DeflateStream::build(bytes).decompress();
+
+    // A fixed block matching 3 bytes from 1 back, before there's output.
+    assert!(matches!(
+        inflate(&[0x03, 0x02]),
+        Err(DeflateError::DistanceTooFarBack {
+            distance: 1,
+            available: 0
+        })
+    ));
+    // A fixed block using literal/length code 286.
+    assert!(matches!(
+        inflate(&[0x1b, 0x03]),
+        Err(DeflateError::InvalidSymbolError(286, _))
+    ));
+    // A dynamic block with an HLIT of 30.
+    assert!(matches!(
+        inflate(&[0xf5, 0, 0]),
+        Err(DeflateError::InvalidCodeLengths(_))
+    ));
+    assert!(matches!(
+        inflate(&[]),
+        Err(DeflateError::InvalidBlockError(_))
+    ));
+
+    let text = "cut it anywhere, it's still an error. ".repeat(40);
+    let noise: Vec<u8> = (0..300u32).map(|i| (i * 97 % 256) as u8).collect();
+    for data in [text.as_bytes(), b"fixed", &noise] {
+        let compressed = deflate::compress(data);
+        assert_eq!(inflate(&compressed).unwrap(), data);
+        for len in 0..compressed.len() {
+            assert!(inflate(&compressed[..len]).is_err(), "{len}");
+        }
+    }
+
+    // A xorshift generator, for streams that are nonsense throughout.
+    let mut state = 0x9e37_79b9u32;
+    for _ in 0..2000 {
+        let bytes: Vec<u8> = (0..48)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        let _ = inflate(&bytes);
+    }
+}
+
+/// Malformed zlib framing is an error, never a panic, just like a malformed
+/// deflate body. Streams cut off anywhere, including partway through the
+/// header or DICTID, are errors, and nonsense after valid headers of every
+/// kind, with and without FDICT, is read without panicking.
+#[test]
+pub fn malformed_zlib() {
+    use chameleon::compression::zlib::{self, ZlibStream, ZlibStreams};
+
+    let inflate = |bytes: &[u8]| match ZlibStream::build(bytes) {
+        Ok(mut zlib) => zlib.decompress().map_err(|_| ()),
+        Err(_) => Err(()),
+    };
+
+    let text = "cut it anywhere, it's still an error. ".repeat(40);
+    for data in [text.as_bytes(), b"fixed", b""] {
+        let compressed = ZlibStream::compress(data);
+        assert_eq!(inflate(&compressed).unwrap(), data);
+        for len in 0..compressed.len() {
+            assert!(inflate(&compressed[..len]).is_err(), "{len}");
+            assert!(zlib::decompress_all(&compressed[..len]).is_err(), "{len}");
+        }
+    }
+
+    // A xorshift generator, for nonsense behind a header that passes its
+    // check, with any window and FDICT set or not.
+    let mut state = 0x9e37_79b9u32;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    };
+    for _ in 0..2000 {
+        let len = next() as usize % 49;
+        let mut bytes: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+        if let [cmf, flg, ..] = &mut bytes[..] {
+            *cmf = 0x08 | (*cmf & 0x70);
+            *flg &= 0xe0;
+            *flg |= ((31 - u16::from_be_bytes([*cmf, *flg]) % 31) % 31) as u8;
+        }
+        let _ = inflate(&bytes);
+        let _ = zlib::decompress_all(&bytes);
+        let _ = ZlibStreams::new(&bytes).count();
+    }
+}
+
+/// Packs (value, count) fields into bytes the way deflate does, each field
+/// least significant bit first.
+fn pack(fields: &[(u32, u32)]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut len = 0;
+    for &(value, count) in fields {
+        for bit in 0..count {
+            if len % 8 == 0 {
+                bytes.push(0);
+            }
+            bytes[len / 8] |= (((value >> bit) & 1) as u8) << (len % 8);
+            len += 1;
+        }
+    }
+    bytes
+}
+
+/// Code lengths that give out more codes than there are, or leave some
+/// unused, are errors holding their Kraft sum, apart from the single one
+/// bit code deflate allows.
+#[test]
+pub fn invalid_prefix_codes() {
+    use chameleon::compression::inflate::{
+        DeflateError, DeflateStream, PrefixError, KRAFT_COMPLETE,
+    };
+
+    let inflate = |bytes: &[u8]| DeflateStream::build(bytes).decompress();
+    // BFINAL, a dynamic BTYPE, 257 literal/length and 1 distance codes.
+    let header = [(1, 1), (2, 2), (0, 5), (0, 5)];
+
+    // Every code length code 1 bit long.
+    let mut fields = header.to_vec();
+    fields.push((15, 4));
+    fields.extend([(1, 3); 19]);
+    let error = inflate(&pack(&fields)).unwrap_err();
+    assert!(matches!(
+        error,
+        DeflateError::InvalidPrefixCode(PrefixError::OverSubscribed { kraft })
+            if kraft == 19 * KRAFT_COMPLETE / 2
+    ));
+    assert_eq!(Error::from(error).code(), 402);
+
+    // Code length codes 16, 17 and 18 2 bits long, leaving one unused.
+    let mut fields = header.to_vec();
+    fields.extend([(0, 4), (2, 3), (2, 3), (2, 3), (0, 3)]);
+    assert!(matches!(
+        inflate(&pack(&fields)),
+        Err(DeflateError::InvalidPrefixCode(PrefixError::Incomplete { kraft }))
+            if kraft == 3 * KRAFT_COMPLETE / 4
+    ));
+
+    // 'a' and the end of block get 1 bit codes, and the only distance code
+    // gets a 1 bit code on its own.
+    let mut fields = header.to_vec();
+    // Code length codes 1 and 18 are 1 bit long, 0 for 1 and 1 for 18.
+    fields.push((14, 4));
+    for symbol in [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1] {
+        fields.push((matches!(symbol, 1 | 18) as u32, 3));
+    }
+    // 97 zeros, 'a', 158 zeros, the end of block, then the distance code.
+    fields.extend([(1, 1), (97 - 11, 7), (0, 1)]);
+    fields.extend([(1, 1), (138 - 11, 7), (1, 1), (20 - 11, 7)]);
+    fields.extend([(0, 1), (0, 1)]);
+    // 'a', 'a', then the end of block.
+    fields.extend([(0, 1), (0, 1), (1, 1)]);
+    assert_eq!(inflate(&pack(&fields)).unwrap(), b"aa");
+}
+
+/// Matches copying from further back than the window a zlib header
+/// declares are rejected, unless the window is ignored to recover the data,
+/// whether the stream is read on its own, joined to others or in a PNG.
+#[test]
+pub fn window_is_enforced() {
+    use chameleon::compression::backend::{Decompressor, Native};
+    use chameleon::compression::checksums::adler::adler32;
+    use chameleon::compression::deflate;
+    use chameleon::compression::inflate::{DeflateError, DeflateStream};
+    use chameleon::compression::zlib::{ZlibStream, ZlibStreams};
+
+    // 300 bytes of noise twice over, so the second copy is a match 300
+    // bytes back.
+    let mut state = 0x2545_f491u32;
+    let mut data: Vec<u8> = (0..300)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect();
+    data.extend_from_within(..);
+
+    // A CINFO of 0, a 256 byte window, then of 1, 512 bytes.
+    let with_header = |header: [u8; 2]| {
+        let mut stream = header.to_vec();
+        stream.extend(deflate::compress(&data));
+        stream.extend_from_slice(&adler32(&data).to_be_bytes());
+        stream
+    };
+    let small = with_header([0x08, 0x1d]);
+    let large = with_header([0x18, 0x19]);
+
+    let mut zlib = ZlibStream::build(&small).unwrap();
+    assert_eq!(zlib.header.window_size(), 256);
+    assert!(matches!(
+        zlib.decompress(),
+        Err(DeflateError::DistanceBeyondWindow {
+            distance: 300,
+            window: 256
+        })
+    ));
+    let mut zlib = ZlibStream::build(&small).unwrap();
+    zlib.ignore_window();
+    assert_eq!(zlib.decompress().unwrap(), data);
+
+    let mut zlib = ZlibStream::build(&large).unwrap();
+    assert_eq!(zlib.header.window_size(), 512);
+    assert_eq!(zlib.decompress().unwrap(), data);
+
+    // Raw deflate data has no header, so the window is whatever it's set to.
+    let mut deflate = DeflateStream::build(&deflate::compress(&data));
+    assert_eq!(deflate.window(), 32 * 1024);
+    deflate.set_window(0);
+    assert_eq!(deflate.window(), 256);
+    assert!(deflate.decompress().is_err());
+
+    // A CINFO of 8 would be a 64K window, more than deflate can use.
+    assert!(ZlibStream::build(&with_header([0x88, 0x1c])).is_err());
+
+    // The check can be relaxed for Native, joined streams and PNG files.
+    let inflate = |check_window: bool| {
+        let mut out = Vec::new();
+        let result = Native.decompress(&small, true, check_window, Vec::new(), &mut |piece| {
+            out.extend_from_slice(piece);
+            Ok(())
+        });
+        result.map(|_| out)
+    };
+    assert!(inflate(true).is_err());
+    assert_eq!(inflate(false).unwrap(), data);
+
+    let joined = [&small[..], &large[..]].concat();
+    assert!(ZlibStreams::new(&joined).next().unwrap().is_err());
+    let streams: Vec<Vec<u8>> = ZlibStreams::new(&joined)
+        .ignore_window()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(streams, [data.clone(), data.clone()]);
+
+    // Two rows of 299 gray pixels, the second a match 300 bytes back.
+    let mut rows = vec![0];
+    rows.extend_from_slice(&data[..299]);
+    rows.extend_from_within(..);
+    let mut idat = vec![0x08, 0x1d];
+    idat.extend(deflate::compress(&rows));
+    idat.extend_from_slice(&adler32(&rows).to_be_bytes());
+    let chunk = |ctype: &[u8; 4], data: &[u8]| {
+        Chunk::with_data(ChunkType::new(*ctype).unwrap(), data.to_vec()).to_bytes()
+    };
+    let mut bytes = vec![137, 80, 78, 71, 13, 10, 26, 10];
+    bytes.extend(chunk(b"IHDR", &[0, 0, 1, 43, 0, 0, 0, 2, 8, 0, 0, 0, 0]));
+    bytes.extend(chunk(b"IDAT", &idat));
+    bytes.extend(chunk(b"IEND", &[]));
+
+    let png = Png::decoder().decode_bytes(&bytes).unwrap();
+    assert!(png.rgb().is_err());
+    assert!(png.rgba_rows(&mut |_, _| Ok(())).is_err());
+    assert!(png.rows().unwrap().any(|row| row.is_err()));
+    let png = Png::decoder()
+        .ignore_window(true)
+        .decode_bytes(&bytes)
+        .unwrap();
+    let gray: Vec<u8> = png.rgb().unwrap().iter().map(|&(r, _, _)| r).collect();
+    assert_eq!(gray, [&data[..299], &data[..299]].concat());
+    let mut count = 0;
+    png.rgba_rows(&mut |_, _| {
+        count += 1;
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(png.rows().unwrap().filter_map(Result::ok).count(), 2);
+}
@@ -0,0 +1,92 @@
+#!/usr/bin/env python3
+"""Generates the broken PNG files checked by tests/corrupt.rs.
+
+Each file is tests/samples/basn2c08.png with one thing wrong, and every other
+checksum fixed up so that only the intended fault is present:
+
+    bad_crc.png         the IDAT chunk's CRC is wrong.
+    truncated_idat.png  the file ends part way through the IDAT chunk.
+    missing_iend.png    the IEND chunk is missing.
+    invalid_filter.png  the second scanline has filter type 5.
+    bad_huffman.png     the dynamic block's code length code lengths are all
+                        zero, so no Huffman codes can be built.
+    bad_adler.png       the zlib stream's adler32 checksum is wrong.
+
+Run it again to regenerate them:
+
+    python3 tests/corrupt/generate.py
+"""
+
+import pathlib
+import struct
+import zlib
+
+HERE = pathlib.Path(__file__).resolve().parent
+SOURCE = HERE.parent / "samples" / "basn2c08.png"
+
+SIGNATURE = b"\x89PNG\r\n\x1a\n"
+# The length of each scanline of the 32x32 RGB source, with its filter byte.
+STRIDE = 32 * 3 + 1
+
+
+def read_chunks(data):
+    chunks, index = [], 8
+    while index < len(data):
+        (length,) = struct.unpack(">I", data[index : index + 4])
+        chunks.append([data[index + 4 : index + 8], data[index + 8 : index + 8 + length]])
+        index += length + 12
+    return chunks
+
+
+def chunk(ctype, body, crc=None):
+    crc = zlib.crc32(ctype + body) if crc is None else crc
+    return struct.pack(">I", len(body)) + ctype + body + struct.pack(">I", crc)
+
+
+def write(name, chunks, crcs=None):
+    crcs = crcs or {}
+    data = SIGNATURE + b"".join(
+        chunk(ctype, body, crcs.get(ctype)) for ctype, body in chunks
+    )
+    (HERE / name).write_bytes(data)
+    return data
+
+
+def with_idat(chunks, idat):
+    return [[ctype, idat if ctype == b"IDAT" else body] for ctype, body in chunks]
+
+
+def main():
+    chunks = read_chunks(SOURCE.read_bytes())
+    idat = next(body for ctype, body in chunks if ctype == b"IDAT")
+    raw = zlib.decompress(idat)
+
+    write("bad_crc.png", chunks, {b"IDAT": zlib.crc32(b"IDAT" + idat) ^ 1})
+
+    whole = write("truncated_idat.png", chunks)
+    end = whole.index(b"IDAT") + 4 + len(idat) // 2
+    (HERE / "truncated_idat.png").write_bytes(whole[:end])
+
+    write("missing_iend.png", [c for c in chunks if c[0] != b"IEND"])
+
+    broken = bytearray(raw)
+    broken[STRIDE] = 5
+    write("invalid_filter.png", with_idat(chunks, zlib.compress(bytes(broken), 9)))
+
+    # After the 2 byte zlib header come BFINAL and BTYPE (3 bits), then HLIT
+    # (5), HDIST (5) and HCLEN (4), then HCLEN + 4 code length code lengths
+    # of 3 bits each, all packed from the least significant bit.
+    stream = bytearray(idat)
+    bits = int.from_bytes(stream[2:12], "little")
+    hclen = (bits >> 13 & 0xF) + 4
+    bits &= ~(((1 << (3 * hclen)) - 1) << 17)
+    stream[2:12] = bits.to_bytes(10, "little")
+    write("bad_huffman.png", with_idat(chunks, bytes(stream)))
+
+    stream = bytearray(idat)
+    stream[-1] ^= 1
+    write("bad_adler.png", with_idat(chunks, bytes(stream)))
+
+
+if __name__ == "__main__":
+    main()
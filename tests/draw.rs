@@ -0,0 +1,249 @@
+#![cfg(feature = "std")]
+
+use chameleon::prelude::*;
+use chameleon::{color, draw};
+
+/// A two glyph BDF font: a 3x3 'A' box offset from the origin, and a
+/// narrower 'i' that moves the pen along by only 2 pixels.
+const BDF: &str = "STARTFONT 2.1
+FONT -test-tiny
+FONTBOUNDINGBOX 4 5 0 -1
+STARTPROPERTIES 1
+DEFAULT_CHAR 65
+ENDPROPERTIES
+CHARS 3
+STARTCHAR A
+ENCODING 65
+DWIDTH 4 0
+BBX 3 3 1 0
+BITMAP
+E0
+A0
+E0
+ENDCHAR
+STARTCHAR i
+ENCODING 105
+DWIDTH 2 0
+BBX 1 4 0 0
+BITMAP
+80
+00
+80
+80
+ENDCHAR
+STARTCHAR unencoded
+ENCODING -1
+DWIDTH 4 0
+BBX 4 5 0 -1
+BITMAP
+F0
+F0
+F0
+F0
+F0
+ENDCHAR
+ENDFONT
+";
+
+/// Renders an image as rows of '#' for white pixels and '.' for the rest.
+fn ascii(image: &Image) -> Vec<String> {
+    image
+        .data
+        .chunks_exact(image.width * 3)
+        .map(|row| {
+            row.chunks_exact(3)
+                .map(|pixel| if pixel == [255, 255, 255] { '#' } else { '.' })
+                .collect()
+        })
+        .collect()
+}
+
+/// Builds a black Rgb8 image.
+fn blank(width: usize, height: usize) -> Image {
+    Image::new(
+        width,
+        height,
+        PixelFormat::Rgb8,
+        vec![0; width * height * 3],
+    )
+    .unwrap()
+}
+
+/// Checks the built in font draws a glyph where it's asked to, and measures
+/// text a cell per character and a line per row.
+#[test]
+pub fn built_in_font_draws_ascii() {
+    let mut image = blank(8, 10);
+    draw::text(&mut image, 1, 0, "H", [255; 4], &Font::fixed()).unwrap();
+
+    assert_eq!(
+        ascii(&image),
+        [
+            "........", ".#...#..", ".#...#..", ".#...#..", ".#####..", ".#...#..", ".#...#..",
+            ".#...#..", "........", "........",
+        ]
+    );
+    assert_eq!(Font::fixed().measure("Hi\nthere"), (30, 20));
+}
+
+/// Checks text running off the edge of the image is cut off there.
+#[test]
+pub fn text_is_clipped_to_the_image() {
+    let mut image = blank(4, 4);
+    draw::text(&mut image, 2, 2, "W\nW", [255; 4], &Font::fixed()).unwrap();
+    draw::text(&mut image, 100, 100, "W", [255; 4], &Font::fixed()).unwrap();
+
+    assert_eq!(ascii(&image), ["....", "....", "....", "..#."]);
+}
+
+/// Checks BDF glyphs are placed in the cell by their bounding boxes, move
+/// the pen along by their own advance, and fall back to DEFAULT_CHAR.
+#[test]
+pub fn bdf_fonts_place_glyphs_on_the_baseline() {
+    let font = Font::from_bdf(BDF).unwrap();
+    assert_eq!((font.width(), font.height()), (4, 5));
+    // The 'i' is 2 wide, the unknown '?' falls back to DEFAULT_CHAR 'A'.
+    assert_eq!(font.measure("Ai?"), (10, 5));
+
+    let mut image = blank(10, 5);
+    draw::text(&mut image, 0, 0, "Ai?", [255; 4], &font).unwrap();
+
+    assert_eq!(
+        ascii(&image),
+        [
+            "....#.....",
+            ".###...###",
+            ".#.##..#.#",
+            ".####..###",
+            "..........",
+        ]
+    );
+}
+
+/// Checks fill writes all four channels of an Rgba8 image, clipped to it.
+#[test]
+pub fn fill_sets_every_channel_of_rgba_images() {
+    let mut image = Image::new(2, 2, PixelFormat::Rgba8, vec![0; 16]).unwrap();
+    draw::fill(&mut image, 1, 0, 5, 1, [1, 2, 3, 4]).unwrap();
+
+    assert_eq!(image.data, [0, 0, 0, 0, 1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0]);
+}
+
+/// Checks a malformed BDF file gives the line the problem is on, including
+/// bounding boxes whose offsets overflow.
+#[test]
+pub fn malformed_bdf_reports_the_line() {
+    let truncated = BDF.replace("A0\nE0\nENDCHAR", "A0\nENDCHAR");
+    assert_eq!(
+        Font::from_bdf(&truncated).unwrap_err(),
+        FontError::InvalidBdf(15, "invalid bitmap row.")
+    );
+
+    let error = Error::from(Font::from_bdf("STARTFONT 2.1\n").unwrap_err());
+    assert_eq!(error.code(), 309);
+
+    let tall = BDF.replace(
+        "FONTBOUNDINGBOX 4 5 0 -1",
+        "FONTBOUNDINGBOX 4 5 0 2147483647",
+    );
+    assert_eq!(
+        Font::from_bdf(&tall).unwrap_err(),
+        FontError::InvalidBdf(3, "the bounding box offset is out of range.")
+    );
+    for bbx in ["BBX 3 3 1 2147483647", "BBX 3 3 2147483647 0"] {
+        let far = BDF.replace("BBX 3 3 1 0", bbx);
+        assert_eq!(
+            Font::from_bdf(&far).unwrap_err(),
+            FontError::InvalidBdf(12, "the glyph's bounding box is out of range."),
+            "{bbx}"
+        );
+    }
+}
+
+/// Checks that every sRGB sample comes back unchanged from linear light,
+/// and that intensities out of range are clamped.
+#[test]
+pub fn srgb_round_trips_through_linear_light() {
+    for value in 0..=255 {
+        assert_eq!(color::linear_to_srgb(color::srgb_to_linear(value)), value);
+    }
+    assert_eq!(color::linear_to_srgb(-1.0), 0);
+    assert_eq!(color::linear_to_srgb(2.0), 255);
+}
+
+/// Checks hex in each length, names in any case, and that Hex writes back
+/// what parse reads.
+#[test]
+pub fn colors_parse_from_hex_and_names() {
+    assert_eq!(color::parse("#ff8800"), Some([255, 136, 0, 255]));
+    assert_eq!(color::parse("FF880080"), Some([255, 136, 0, 128]));
+    assert_eq!(color::parse("#f80"), Some([255, 136, 0, 255]));
+    assert_eq!(color::parse("f808"), Some([255, 136, 0, 136]));
+    assert_eq!(color::parse("rebeccapurple"), Some([102, 51, 153, 255]));
+    assert_eq!(color::parse("AliceBlue"), Some([240, 248, 255, 255]));
+    assert_eq!(color::parse("yellowgreen"), Some([154, 205, 50, 255]));
+    assert_eq!(color::parse("transparent"), Some([0, 0, 0, 0]));
+    for bad in ["", "#", "#ff880", "#gg8800", "notacolor", "#ff8800ff00"] {
+        assert_eq!(color::parse(bad), None, "{bad}");
+    }
+
+    assert_eq!(color::Hex([255, 136, 0, 255]).to_string(), "#ff8800");
+    assert_eq!(color::Hex([0, 0, 0, 128]).to_string(), "#00000080");
+    for rgba in [[1, 2, 3, 255], [250, 0, 16, 7]] {
+        let hex = color::Hex(rgba).to_string();
+        assert_eq!(color::parse(&hex), Some(rgba));
+    }
+}
+
+/// Checks compositing with each Blend, over transparent pixels and past
+/// the edge of the image.
+#[test]
+pub fn composite_blends_in_srgb_or_linear_light() {
+    // White at a quarter opacity over black, once in each blend mode.
+    let top = Image::new(1, 1, PixelFormat::Rgba8, vec![255, 255, 255, 64]).unwrap();
+
+    let mut srgb = blank(2, 1);
+    draw::composite(&mut srgb, &top, 1, 0, Blend::Srgb).unwrap();
+    assert_eq!(srgb.data, [0, 0, 0, 64, 64, 64]);
+
+    // A quarter of the light is about half way up sRGB's curve.
+    let mut linear = blank(2, 1);
+    draw::composite(&mut linear, &top, 1, 0, Blend::Linear).unwrap();
+    assert_eq!(linear.data, [0, 0, 0, 137, 137, 137]);
+
+    // Over a transparent pixel only the top color is left, whatever the
+    // color underneath.
+    let mut clear = Image::new(1, 1, PixelFormat::Rgba8, vec![9, 9, 9, 0]).unwrap();
+    draw::composite(&mut clear, &top, 0, 0, Blend::Linear).unwrap();
+    assert_eq!(clear.data, [255, 255, 255, 64]);
+
+    // Anything past the edge is clipped.
+    let mut small = blank(1, 1);
+    draw::composite(&mut small, &top, 1, 1, Blend::Linear).unwrap();
+    assert_eq!(small.data, [0, 0, 0]);
+}
+
+/// Checks flatten draws transparent pixels over an opaque matte, whatever
+/// the alpha of the pattern, and that the result has no alpha.
+#[test]
+pub fn flatten_draws_over_a_matte() {
+    let image = Image::new(
+        3,
+        1,
+        PixelFormat::Rgba8,
+        vec![255, 0, 0, 0, 255, 0, 0, 255, 0, 0, 255, 128],
+    )
+    .unwrap();
+
+    let flat = draw::flatten(&image, Pattern::Solid([255, 255, 255, 100]), Blend::Srgb).unwrap();
+    assert_eq!(flat.format, PixelFormat::Rgb8);
+    assert_eq!(flat.data, [255, 255, 255, 255, 0, 0, 127, 127, 255]);
+
+    let checkerboard = Pattern::Checkerboard {
+        size: 1,
+        colors: [[0, 0, 0, 255], [255, 255, 255, 255]],
+    };
+    let clear = Image::new(2, 1, PixelFormat::Rgba8, vec![0; 8]).unwrap();
+    let flat = draw::flatten(&clear, checkerboard, Blend::Srgb).unwrap();
+    assert_eq!(flat.data, [0, 0, 0, 255, 255, 255]);
+}
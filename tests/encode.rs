@@ -0,0 +1,410 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use chameleon::formats::png::ChunkType;
+use chameleon::prelude::*;
+
+/// Checks that an image survives being encoded and decoded again with every
+/// filter type.
+#[test]
+pub fn png_round_trip() {
+    let png = Png::from_path("./tests/samples/basn2c08.png").unwrap();
+    let image = Image::try_from(&png).unwrap();
+
+    for filter in [
+        Filters::None,
+        Filters::Sub,
+        Filters::Up,
+        Filters::Average,
+        Filters::Paeth,
+    ] {
+        let bytes = image
+            .encode_png(&EncodeOptions::new().filter(filter))
+            .unwrap();
+        let decoded = Image::try_from(Png::decoder().decode_bytes(&bytes).unwrap()).unwrap();
+
+        assert_eq!(decoded, image, "{:?}", filter);
+    }
+}
+
+/// Checks that the default adaptive filtering survives a round trip, for
+/// images that are written directly and as a palette.
+#[test]
+pub fn adaptive_filter_round_trip() {
+    let options = EncodeOptions::new();
+    assert_eq!(options.filter, FilterStrategy::Adaptive);
+
+    for image in [
+        Image::gradient(40, 8, [0, 10, 20, 255], [255, 200, 100, 0]).unwrap(),
+        Image::noise(16, 16, 7).unwrap(),
+        Image::checkerboard(9, 9, 2, [[0, 0, 0, 255], [255, 0, 0, 255]]).unwrap(),
+    ] {
+        let bytes = image.encode_png(&options).unwrap();
+        let png = Png::decoder().decode_bytes(&bytes).unwrap();
+        let pixels = png.rgba().unwrap();
+
+        assert!(pixels
+            .iter()
+            .zip(image.data.chunks_exact(4))
+            .all(|(&(r, g, b, a), pixel)| [r, g, b, a] == pixel));
+        assert_eq!(pixels.len(), image.width * image.height);
+    }
+}
+
+/// Checks that filtering bands of rows on several threads gives the same
+/// file as filtering them on one, for every filter strategy.
+#[test]
+pub fn threads_give_identical_files() {
+    let image = Image::noise(301, 299, 3).unwrap();
+
+    for filter in [
+        FilterStrategy::Adaptive,
+        FilterStrategy::Fixed(Filters::Paeth),
+        FilterStrategy::Fixed(Filters::Up),
+    ] {
+        let options = EncodeOptions::new().filter(filter);
+        let single = image.encode_png(&options.clone().threads(1)).unwrap();
+        let threaded = image.encode_png(&options.threads(7)).unwrap();
+        assert_eq!(single, threaded, "{filter:?}");
+    }
+
+    let bytes = image.encode_png(&EncodeOptions::new()).unwrap();
+    let pixels = Png::decoder().decode_bytes(&bytes).unwrap().rgba().unwrap();
+    assert!(pixels
+        .iter()
+        .zip(image.data.chunks_exact(4))
+        .all(|(&(r, g, b, a), pixel)| [r, g, b, a] == pixel));
+    assert_eq!(EncodeOptions::new().threads(0).threads, 1);
+}
+
+/// Checks that a buffer of the wrong size is rejected rather than encoded.
+#[test]
+pub fn image_buffer_size_is_checked() {
+    assert_eq!(
+        Image::new(2, 2, PixelFormat::Rgb8, vec![0; 11]),
+        Err(ImageError::BufferSize(12, 11))
+    );
+
+    let mut image = Image::new(2, 2, PixelFormat::Rgb8, vec![0; 12]).unwrap();
+    image.width = 3;
+    assert!(image.encode_png(&EncodeOptions::new()).is_err());
+}
+
+/// Checks that encoding is byte-identical between runs, that no tIME chunk
+/// is written unless asked for, and that a reproducible encode zeroes it.
+#[test]
+pub fn png_encoding_is_reproducible() {
+    let image = Image::try_from(Png::from_path("./tests/samples/basn2c08.png").unwrap()).unwrap();
+    let time = Png::from_path("./tests/samples/cm0n0g04.png")
+        .unwrap()
+        .time();
+    assert_eq!(
+        time,
+        Some(Time {
+            year: 2000,
+            month: 1,
+            day: 1,
+            hour: 12,
+            minute: 34,
+            second: 56,
+        })
+    );
+
+    let options = EncodeOptions::new().filter(Filters::Paeth);
+    let first = image.encode_png(&options).unwrap();
+    assert_eq!(first, image.encode_png(&options).unwrap());
+    assert_eq!(Png::decoder().decode_bytes(&first).unwrap().time(), None);
+
+    let stamped = image.encode_png(&options.clone().time(time)).unwrap();
+    assert_eq!(Png::decoder().decode_bytes(&stamped).unwrap().time(), time);
+
+    let reproducible = image
+        .encode_png(&options.time(time).reproducible(true))
+        .unwrap();
+    assert_eq!(
+        Png::decoder().decode_bytes(&reproducible).unwrap().time(),
+        Some(Time::EPOCH)
+    );
+}
+
+/// Builds a width by 1 Rgba8 image from a function of each pixel's column.
+fn row_image(width: usize, pixel: impl Fn(usize) -> [u8; 4]) -> Image {
+    let data = (0..width).flat_map(pixel).collect();
+    Image::new(width, 1, PixelFormat::Rgba8, data).unwrap()
+}
+
+/// Checks that each kind of image is written with the smallest color type
+/// and bit depth that holds it, and kept as it is when reduce is off.
+#[test]
+pub fn png_color_type_is_reduced() {
+    let gray = |i: usize| [i as u8, i as u8, i as u8, 255];
+    let cases = [
+        // Bit depth and color type in IHDR order.
+        (row_image(256, gray), [8, 0]),
+        (
+            row_image(300, |i| [i as u8, i as u8, i as u8, (i / 2) as u8]),
+            [8, 4],
+        ),
+        (
+            row_image(300, |i| [i as u8, (i / 256) as u8, 0, 255]),
+            [8, 2],
+        ),
+        (row_image(300, |i| [i as u8, (i / 256) as u8, 0, 7]), [8, 6]),
+        (row_image(2, |i| [255 * i as u8, 0, 0, 255]), [1, 3]),
+        (row_image(4, |i| [i as u8, 0, 0, 64 * i as u8]), [2, 3]),
+        (row_image(16, gray), [4, 3]),
+        (row_image(200, |i| [i as u8, 0, 0, 255]), [8, 3]),
+    ];
+
+    for (i, (image, expected)) in cases.iter().enumerate() {
+        let bytes = image.encode_png(&EncodeOptions::new()).unwrap();
+        assert_eq!(bytes[24..26], expected[..], "case {i}");
+
+        let bytes = image
+            .encode_png(&EncodeOptions::new().reduce(false))
+            .unwrap();
+        assert_eq!(bytes[24..26], [8, 6], "case {i}");
+    }
+}
+
+/// Checks that the decoder reads back every color type and bit depth the
+/// encoder reduces to, with the colors it was given.
+#[test]
+pub fn reduced_images_decode() {
+    let gray = |i: usize| [i as u8, i as u8, i as u8, 255];
+    let images = [
+        row_image(256, gray),
+        row_image(300, |i| [i as u8, i as u8, i as u8, (i / 2) as u8]),
+        row_image(2, |i| [255 * i as u8, 0, 0, 255]),
+        row_image(4, |i| [i as u8, 0, 0, 64 * i as u8]),
+        row_image(16, gray),
+        row_image(200, |i| [i as u8, 0, 0, 255]),
+    ];
+
+    for (i, image) in images.iter().enumerate() {
+        let bytes = image.encode_png(&EncodeOptions::new()).unwrap();
+        let decoded = Png::decoder().decode_bytes(&bytes).unwrap();
+        let rgb = image
+            .data
+            .chunks_exact(4)
+            .flat_map(|p| [p[0], p[1], p[2]])
+            .collect::<Vec<_>>();
+        assert_eq!(Image::try_from(decoded).unwrap().data, rgb, "case {i}");
+    }
+}
+
+/// Checks that an opaque Rgba8 image is written as RGB with the same
+/// pixels.
+#[test]
+pub fn opaque_rgba_is_written_as_rgb() {
+    let png = Png::from_path("./tests/samples/basn2c08.png").unwrap();
+    let rgb = Image::try_from(&png).unwrap();
+    let rgba = Image::new(
+        rgb.width,
+        rgb.height,
+        PixelFormat::Rgba8,
+        rgb.data
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+    )
+    .unwrap();
+
+    let bytes = rgba.encode_png(&EncodeOptions::new()).unwrap();
+    let decoded = Png::decoder().decode_bytes(&bytes).unwrap();
+    assert_eq!(decoded.color_type, ColorType::RGB);
+    assert_eq!(Image::try_from(decoded).unwrap(), rgb);
+}
+
+/// Checks that 16 bit and gray images are written at their own depth and
+/// color type, and decode to the same samples.
+#[test]
+pub fn pixel_formats_are_written_directly() {
+    let samples: Vec<u16> = (0..300).map(|i| i * 211).collect();
+    let data = samples
+        .iter()
+        .flat_map(|&s| [s, s / 2, 65535 - s].map(u16::to_be_bytes))
+        .flatten()
+        .collect();
+    let image = Image::new(samples.len(), 1, PixelFormat::Rgb16, data).unwrap();
+
+    let bytes = image.encode_png(&EncodeOptions::new()).unwrap();
+    assert_eq!(bytes[24..26], [16, 2]);
+    let decoded = Png::decoder()
+        .decode_bytes(&bytes)
+        .unwrap()
+        .rgb16()
+        .unwrap();
+    let expected: Vec<_> = samples.iter().map(|&s| (s, s / 2, 65535 - s)).collect();
+    assert_eq!(decoded, expected);
+
+    let gray = Image::new(
+        300,
+        1,
+        PixelFormat::Gray8,
+        (0..300).map(|i| i as u8).collect(),
+    )
+    .unwrap();
+    let bytes = gray
+        .encode_png(&EncodeOptions::new().reduce(false))
+        .unwrap();
+    assert_eq!(bytes[24..26], [8, 0]);
+}
+
+/// Checks that a region and a padded buffer encode to the same file as the
+/// same pixels packed tightly, and that bad strides and regions are refused.
+#[test]
+pub fn image_view_encodes_in_place() {
+    let image = Image::try_from(Png::from_path("./tests/samples/basn2c08.png").unwrap()).unwrap();
+    let options = EncodeOptions::new();
+
+    let region = image.region(8, 4, 16, 20).unwrap();
+    let copied = region.to_image().unwrap();
+    assert_eq!((copied.width, copied.height), (16, 20));
+    assert_eq!(copied.data[..3], image.data[(4 * 32 + 8) * 3..][..3]);
+    assert_eq!(
+        region.encode_png(&options).unwrap(),
+        copied.encode_png(&options).unwrap()
+    );
+
+    let padded = [1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11, 12];
+    let view = ImageView::new(2, 2, PixelFormat::Rgb8, 8, &padded).unwrap();
+    assert_eq!(
+        view.to_image().unwrap().data,
+        [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]
+    );
+
+    assert_eq!(
+        ImageView::new(2, 2, PixelFormat::Rgb8, 5, &padded),
+        Err(ImageError::InvalidStride(5, 6))
+    );
+    assert_eq!(
+        ImageView::new(2, 2, PixelFormat::Rgb8, 8, &padded[..13]),
+        Err(ImageError::BufferSize(14, 13))
+    );
+    assert_eq!(
+        image.region(30, 0, 3, 1),
+        Err(ImageError::InvalidDimensions(3, 1))
+    );
+}
+
+/// Checks that trimming leaves out uniform and transparent margins, within
+/// the threshold, and that an image that's all margin trims to nothing.
+#[test]
+pub fn trim_removes_margins() {
+    #[rustfmt::skip]
+    let gray = Image::new(4, 3, PixelFormat::Gray8, vec![
+        200, 200, 201, 200,
+        200, 10, 20, 198,
+        200, 200, 30, 200,
+    ]).unwrap();
+    let trimmed = gray.trim(0).unwrap();
+    assert_eq!((trimmed.width, trimmed.height), (3, 3));
+    let trimmed = gray.trim(2).unwrap().to_image().unwrap();
+    assert_eq!((trimmed.width, trimmed.height), (2, 2));
+    assert_eq!(trimmed.data, [10, 20, 200, 30]);
+
+    // Transparent pixels match whatever their color.
+    #[rustfmt::skip]
+    let icon = Image::new(3, 3, PixelFormat::Rgba8, vec![
+        0, 0, 0, 0,  255, 0, 0, 0,  0, 0, 0, 0,
+        9, 9, 9, 0,  1, 2, 3, 255,  0, 0, 0, 0,
+        0, 0, 0, 0,  0, 0, 0, 0,    0, 0, 255, 0,
+    ]).unwrap();
+    let trimmed = icon.trim(0).unwrap().to_image().unwrap();
+    assert_eq!((trimmed.width, trimmed.height), (1, 1));
+    assert_eq!(trimmed.data, [1, 2, 3, 255]);
+
+    let blank = Image::new(5, 5, PixelFormat::Rgb16, vec![7; 150]).unwrap();
+    let trimmed = blank.trim(0).unwrap();
+    assert_eq!((trimmed.width, trimmed.height), (0, 0));
+}
+
+/// Checks that a ChunkWriter copies chunks in from readers, splitting image
+/// data over several IDAT chunks, and that a StreamEncoder takes ancillary
+/// chunks the same way until image data has been written.
+#[test]
+pub fn chunks_are_copied_from_readers() {
+    use std::io::Cursor;
+
+    let png = Png::from_path("./tests/samples/basn2c08.png").unwrap();
+    let ihdr = &std::fs::read("./tests/samples/basn2c08.png").unwrap()[16..29];
+    let zlib: Vec<u8> = png.data.idat.iter().flat_map(|c| c.data.clone()).collect();
+    let payload: Vec<u8> = (0..200_000).map(|i| (i % 253) as u8).collect();
+    let private = ChunkType::new(*b"raWd").unwrap();
+
+    let mut writer = ChunkWriter::new(Vec::new());
+    writer.write_signature().unwrap();
+    writer.write_chunk(ChunkType::IHDR, ihdr).unwrap();
+    writer
+        .write_chunk_from(private, Cursor::new(&payload), payload.len() as u32)
+        .unwrap();
+    let chunks = writer
+        .write_chunks_from(ChunkType::IDAT, Cursor::new(&zlib), 1000)
+        .unwrap();
+    assert_eq!(chunks, zlib.len().div_ceil(1000));
+    writer.write_chunk(ChunkType::IEND, &[]).unwrap();
+
+    let copied = Png::decoder().decode_bytes(&writer.into_inner()).unwrap();
+    assert_eq!(copied.data.idat.len(), chunks);
+    assert_eq!(copied.rgba().unwrap(), png.rgba().unwrap());
+    let chunk = &copied.data.ancillary_chunks[0];
+    assert_eq!((chunk.ctype, &chunk.data), (private, &payload));
+
+    let mut writer = ChunkWriter::new(Vec::new());
+    assert!(writer
+        .write_chunk_from(private, Cursor::new(&payload), payload.len() as u32 + 1)
+        .is_err());
+
+    let mut encoder =
+        StreamEncoder::new(Vec::new(), 1, 1, PixelFormat::Gray8, &EncodeOptions::new()).unwrap();
+    assert!(encoder
+        .write_chunk_from(ChunkType::PLTE, &[0u8; 3][..], 3)
+        .is_err());
+    encoder
+        .write_chunk_from(private, &payload[..10], 10)
+        .unwrap();
+    encoder.write_row(&[7]).unwrap();
+    let streamed = Png::decoder()
+        .decode_bytes(&encoder.finish().unwrap())
+        .unwrap();
+    assert_eq!(streamed.data.ancillary_chunks[0].data, payload[..10]);
+    assert_eq!(streamed.rgba().unwrap(), [(7, 7, 7, 255)]);
+}
+
+/// Checks that an image survives a round trip at every compression level
+/// and strategy, and that storing it uncompressed gives the largest file.
+#[test]
+pub fn compression_levels_round_trip() {
+    use chameleon::compression::deflate::{CompressionLevel, Strategy};
+
+    let png = Png::from_path("./tests/samples/basn2c08.png").unwrap();
+    let image = Image::try_from(&png).unwrap();
+    let stored = image
+        .encode_png(&EncodeOptions::new().level(CompressionLevel::None))
+        .unwrap();
+
+    for level in [
+        CompressionLevel::None,
+        CompressionLevel::Fast,
+        CompressionLevel::Default,
+        CompressionLevel::Best,
+    ] {
+        for strategy in [
+            Strategy::Default,
+            Strategy::Filtered,
+            Strategy::HuffmanOnly,
+            Strategy::Rle,
+        ] {
+            let options = EncodeOptions::new().level(level).strategy(strategy);
+            let bytes = image.encode_png(&options).unwrap();
+            let decoded = Image::try_from(Png::decoder().decode_bytes(&bytes).unwrap()).unwrap();
+
+            assert_eq!(decoded, image, "{level:?} {strategy:?}");
+            assert!(bytes.len() <= stored.len(), "{level:?} {strategy:?}");
+        }
+    }
+    assert_ne!(
+        EncodeOptions::new().level(CompressionLevel::Best),
+        EncodeOptions::new()
+    );
+}
@@ -0,0 +1,37 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use chameleon::prelude::*;
+
+/// Checks a few error codes, and that the hundreds of every code is the code
+/// of its kind.
+#[test]
+pub fn error_codes_match_kinds() {
+    let errors: Vec<Error> = vec![
+        Png::decoder()
+            .decode_bytes(b"not a png")
+            .err()
+            .unwrap()
+            .into(),
+        Png::from_path("./tests/samples/does-not-exist.png")
+            .err()
+            .unwrap()
+            .into(),
+        Png::decoder()
+            .limits(Limits::new().max_width(1))
+            .decode("./tests/samples/basn2c08.png")
+            .err()
+            .unwrap()
+            .into(),
+        Image::new(1, 1, PixelFormat::Rgb8, vec![])
+            .err()
+            .unwrap()
+            .into(),
+    ];
+
+    let codes = errors.iter().map(Error::code).collect::<Vec<_>>();
+    assert_eq!(codes, vec![301, 201, 501, 307]);
+
+    for error in &errors {
+        assert_eq!(error.code() / 100, error.kind().code());
+    }
+}
@@ -0,0 +1,78 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use chameleon::formats::png::filter::{self, Filters};
+
+const FILTERS: [Filters; 5] = [
+    Filters::None,
+    Filters::Sub,
+    Filters::Up,
+    Filters::Average,
+    Filters::Paeth,
+];
+
+/// Checks that reconstructing a filtered row gives back the original, for
+/// the first row and for one with a row above it.
+#[test]
+pub fn filter_round_trip() {
+    let previous = (0..24u8).map(|i| i.wrapping_mul(37)).collect::<Vec<_>>();
+    let row = (0..24u8)
+        .map(|i| i.wrapping_mul(91) ^ 0x5a)
+        .collect::<Vec<_>>();
+
+    for filter_type in FILTERS {
+        for above in [&[][..], &previous[..]] {
+            let mut filtered = Vec::new();
+            filter::filter(filter_type, &row, above, 3, &mut filtered);
+
+            let mut restored = vec![0xff];
+            filter::reconstruct(filter_type, &filtered, above, 3, &mut restored);
+
+            assert_eq!(restored[1..], row[..], "{:?}", filter_type);
+        }
+    }
+}
+
+/// Checks that an empty previous row is the same as a row of zeros.
+#[test]
+pub fn missing_previous_row_is_zero() {
+    let row = [10, 20, 30, 40, 50, 60];
+
+    for filter_type in FILTERS {
+        let (mut empty, mut zeros) = (Vec::new(), Vec::new());
+        filter::filter(filter_type, &row, &[], 2, &mut empty);
+        filter::filter(filter_type, &row, &[0; 6], 2, &mut zeros);
+
+        assert_eq!(empty, zeros, "{:?}", filter_type);
+    }
+}
+
+/// Checks the Paeth predictor, and that filter type bytes are read.
+#[test]
+pub fn paeth_and_filter_bytes() {
+    assert_eq!(filter::paeth_predictor(10, 20, 10), 20);
+    assert_eq!(filter::paeth_predictor(20, 10, 10), 20);
+    assert_eq!(filter::paeth_predictor(10, 10, 20), 10);
+    assert_eq!(filter::paeth_predictor(100, 50, 200), 50);
+
+    for (byte, filter_type) in FILTERS.into_iter().enumerate() {
+        assert_eq!(Filters::try_from(byte as u8).unwrap(), filter_type);
+    }
+    assert!(Filters::try_from(5).is_err());
+}
+
+/// Checks the adaptive heuristic picks the filter that predicts the row
+/// best, and the simpler filter on a tie.
+#[test]
+pub fn choose_minimizes_differences() {
+    let ramp = (0..16u8).map(|i| i * 8).collect::<Vec<_>>();
+    let shifted = ramp.iter().map(|byte| byte + 100).collect::<Vec<_>>();
+
+    assert_eq!(filter::choose(&[0; 16], &[], 1), Filters::None);
+    assert_eq!(filter::choose(&ramp, &[], 1), Filters::Sub);
+    assert_eq!(filter::choose(&shifted, &shifted, 1), Filters::Up);
+    assert_eq!(filter::choose(&[], &[], 0), Filters::None);
+
+    // Bytes over 127 are small negative differences, not large positive
+    // ones, so -1 beats -2 rather than 254 beating 255.
+    assert_eq!(filter::choose(&[255; 16], &[1; 16], 16), Filters::None);
+}
@@ -0,0 +1,64 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use chameleon::prelude::*;
+
+/// Decodes every pixel of a PNG file at its full precision.
+fn decode(png: &Png) -> Image {
+    let mut data = Vec::new();
+    png.rgba_rows(&mut |_, row| {
+        data.extend_from_slice(row);
+        Ok(())
+    })
+    .unwrap();
+
+    Image::new(png.dimensions.0, png.dimensions.1, png.rgba_format(), data).unwrap()
+}
+
+fn fingerprint(name: &str) -> Fingerprint {
+    let png = Png::from_path(format!("./tests/samples/{name}.png")).unwrap();
+    Fingerprint::of_png(&png).unwrap()
+}
+
+/// Checks the fingerprint is the documented SHA-256, worked out by another
+/// implementation from the RGBA dump in tests/reference, and that it only
+/// depends on the pixels.
+#[test]
+pub fn fingerprints_ignore_storage() {
+    let rgb = fingerprint("basn2c08");
+    assert_eq!(
+        rgb.to_string(),
+        "b8e3dee2edc60129f57be412083dc326c356078df3782cba34971a010a8f3ea1"
+    );
+    // The same picture interlaced, and as 16 bit samples.
+    assert_eq!(fingerprint("basi2c08"), rgb);
+    assert_eq!(fingerprint("basn0g16"), fingerprint("basi0g16"));
+
+    for name in ["basn2c08", "basn0g16", "tbbn3p08"] {
+        let png = Png::from_path(format!("./tests/samples/{name}.png")).unwrap();
+        let image = decode(&png);
+        let expected = Fingerprint::of_png(&png).unwrap();
+
+        // Reduced to another color type, or kept, with any filter.
+        for options in [
+            EncodeOptions::new(),
+            EncodeOptions::new().reduce(false).filter(Filters::Up),
+        ] {
+            let bytes = image.encode_png(&options).unwrap();
+            let copy = Png::decoder().decode_bytes(&bytes).unwrap();
+            assert_eq!(Fingerprint::of_png(&copy).unwrap(), expected, "{name}");
+        }
+        assert_eq!(Fingerprint::of_image(&image.view()).unwrap(), expected);
+    }
+
+    let mut image = decode(&Png::from_path("./tests/samples/basn2c08.png").unwrap());
+    image.data[40] ^= 1;
+    assert_ne!(Fingerprint::of_image(&image.view()).unwrap(), rgb);
+
+    // The same bytes at another size.
+    let wide = Image::new(4, 1, PixelFormat::Gray8, vec![0; 4]).unwrap();
+    let tall = Image::new(1, 4, PixelFormat::Gray8, vec![0; 4]).unwrap();
+    assert_ne!(
+        Fingerprint::of_image(&wide.view()).unwrap(),
+        Fingerprint::of_image(&tall.view()).unwrap()
+    );
+}
@@ -0,0 +1,80 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use chameleon::prelude::*;
+
+/// Checks a gradient starts and ends exactly on its colors, and is rounded
+/// in between.
+#[test]
+pub fn gradient_reaches_both_colors() {
+    let image = Image::gradient(5, 2, [0, 0, 0, 255], [255, 100, 10, 0]).unwrap();
+    let pixel = |x: usize, y: usize| &image.data[(y * 5 + x) * 4..][..4];
+
+    assert_eq!(image.format, PixelFormat::Rgba8);
+    assert_eq!(pixel(0, 0), [0, 0, 0, 255]);
+    assert_eq!(pixel(4, 1), [255, 100, 10, 0]);
+    assert_eq!(pixel(2, 0), [128, 50, 5, 128]);
+    assert_eq!(pixel(2, 0), pixel(2, 1));
+}
+
+/// Checks the checkerboard alternates squares of the given size, starting
+/// with the first color, and a solid fill covers every pixel.
+#[test]
+pub fn checkerboard_and_solid() {
+    let (a, b) = ([1, 2, 3, 255], [9, 9, 9, 9]);
+    let image = Image::checkerboard(6, 4, 2, [a, b]).unwrap();
+    let pixel = |x: usize, y: usize| &image.data[(y * 6 + x) * 4..][..4];
+
+    assert_eq!(pixel(0, 0), a);
+    assert_eq!(pixel(1, 1), a);
+    assert_eq!(pixel(2, 0), b);
+    assert_eq!(pixel(0, 2), b);
+    assert_eq!(pixel(5, 3), b);
+    assert_eq!(pixel(5, 1), a);
+
+    let solid = Image::solid(3, 3, b).unwrap();
+    assert!(solid.data.chunks_exact(4).all(|pixel| pixel == b));
+}
+
+/// Checks noise is the same for the same seed, different for another, and
+/// opaque.
+#[test]
+pub fn noise_is_seeded() {
+    let first = Image::noise(16, 16, 7).unwrap();
+
+    assert_eq!(first, Image::noise(16, 16, 7).unwrap());
+    assert_ne!(first, Image::noise(16, 16, 8).unwrap());
+    assert!(first.data.chunks_exact(4).all(|pixel| pixel[3] == 255));
+}
+
+/// Checks generated images survive a trip through the PNG encoder and
+/// decoder.
+#[test]
+pub fn patterns_round_trip() {
+    let colors = [[255, 0, 0, 255], [0, 0, 255, 128]];
+    for pattern in [
+        Pattern::Gradient(colors[0], colors[1]),
+        Pattern::Checkerboard { size: 3, colors },
+        Pattern::Noise(1),
+    ] {
+        let image = Image::generate(20, 10, pattern).unwrap();
+        let bytes = image.encode_png(&EncodeOptions::new()).unwrap();
+        let png = Png::decoder().decode_bytes(&bytes).unwrap();
+        let rgba = png
+            .rgba()
+            .unwrap()
+            .into_iter()
+            .flat_map(|(r, g, b, a)| [r, g, b, a])
+            .collect::<Vec<_>>();
+
+        assert_eq!(rgba, image.data, "{pattern:?}");
+    }
+}
+
+/// Checks sizes too large to address are refused rather than overflowing.
+#[test]
+pub fn huge_patterns_are_refused() {
+    assert!(matches!(
+        Image::solid(usize::MAX, 2, [0; 4]),
+        Err(ImageError::InvalidDimensions(_, 2))
+    ));
+}
@@ -0,0 +1,221 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use chameleon::compression::crc;
+use chameleon::compression::deflate;
+use chameleon::compression::gzip::{GzipError, GzipHeader, GzipStream};
+use chameleon::compression::inflate::DeflateError;
+
+/// Builds a gzip file holding data, with every optional header field.
+fn build(data: &[u8]) -> Vec<u8> {
+    // FTEXT, FHCRC, FEXTRA, FNAME and FCOMMENT.
+    let mut bytes = vec![0x1f, 0x8b, 8, 0b1_1111];
+    bytes.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+    bytes.extend_from_slice(&[2, 3]);
+    bytes.extend_from_slice(&4u16.to_le_bytes());
+    bytes.extend_from_slice(b"ab\x00\x00");
+    bytes.extend_from_slice(b"caf\xe9.txt\x00");
+    bytes.extend_from_slice(b"a comment\x00");
+    let header_crc = crc::hash(&bytes) as u16;
+    bytes.extend_from_slice(&header_crc.to_le_bytes());
+
+    bytes.extend(deflate::compress(data));
+    bytes.extend_from_slice(&crc::hash(data).to_le_bytes());
+    bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    bytes
+}
+
+/// Checks every header field is read, and the data decompresses whole or a
+/// piece at a time.
+#[test]
+pub fn gzip_files_are_read() {
+    let data = "gzip wraps deflate. ".repeat(300).into_bytes();
+    let bytes = build(&data);
+
+    let mut gzip = GzipStream::build(&bytes).unwrap();
+    let header = &gzip.header;
+    assert!(header.text);
+    assert_eq!((header.mtime, header.xfl, header.os), (1_700_000_000, 2, 3));
+    assert_eq!(header.extra.as_deref(), Some(&b"ab\x00\x00"[..]));
+    assert_eq!(header.name.as_deref(), Some("café.txt"));
+    assert_eq!(header.comment.as_deref(), Some("a comment"));
+    assert!(header.header_crc.is_some());
+    assert_eq!(gzip.isize as usize, data.len());
+    assert_eq!(gzip.decompress().unwrap(), data);
+
+    let mut pieces = Vec::new();
+    GzipStream::build(&bytes)
+        .unwrap()
+        .decompress_with(Vec::new(), &mut |piece: &[u8]| {
+            pieces.extend_from_slice(piece);
+            Ok::<(), DeflateError>(())
+        })
+        .unwrap();
+    assert_eq!(pieces, data);
+
+    let mut minimal = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255];
+    minimal.extend(deflate::compress(b""));
+    minimal.extend_from_slice(&[0; 8]);
+    let mut gzip = GzipStream::build(&minimal).unwrap();
+    assert_eq!(gzip.header.name, None);
+    assert_eq!(gzip.decompress().unwrap(), b"");
+}
+
+/// Checks damaged headers and trailers are reported.
+#[test]
+pub fn gzip_damage_is_found() {
+    let data = b"some data to damage".to_vec();
+    let bytes = build(&data);
+
+    let header_error =
+        |bytes: &[u8]| matches!(GzipHeader::build(bytes), Err(GzipError::InvalidHeader(_)));
+    assert!(header_error(b"\x1f\x8b\x08"));
+    assert!(header_error(&bytes[..20]));
+    let mut magic = bytes.clone();
+    magic[1] = 0x8c;
+    assert!(header_error(&magic));
+    let mut reserved = bytes.clone();
+    reserved[3] |= 0x80;
+    assert!(header_error(&reserved));
+    let mut renamed = bytes.clone();
+    renamed[20] = b'C';
+    assert!(header_error(&renamed));
+
+    let mut bad_crc = bytes.clone();
+    let crc_idx = bad_crc.len() - 8;
+    bad_crc[crc_idx] ^= 1;
+    let error = GzipStream::build(&bad_crc)
+        .unwrap()
+        .decompress()
+        .unwrap_err();
+    let DeflateError::ChecksumMismatch { expected, actual } = error else {
+        panic!("{error}");
+    };
+    assert_eq!((expected ^ 1, actual), (crc::hash(&data), crc::hash(&data)));
+
+    let mut bad_len = bytes.clone();
+    let len_idx = bad_len.len() - 4;
+    bad_len[len_idx] += 1;
+    assert!(matches!(
+        GzipStream::build(&bad_len).unwrap().decompress(),
+        Err(DeflateError::DecompressionError(_))
+    ));
+}
+
+/// Checks files flate2 writes are read and files written here are read by
+/// flate2, header fields included.
+#[cfg(feature = "zlib")]
+#[test]
+pub fn flate2_gzip_files_are_read() {
+    use std::io::{Read, Write};
+
+    let data = "written by flate2, read by chameleon\n"
+        .repeat(1000)
+        .into_bytes();
+    let mut encoder = flate2::GzBuilder::new()
+        .filename("notes.txt")
+        .comment("from flate2")
+        .extra(vec![1, 2, 3])
+        .mtime(42)
+        .write(Vec::new(), flate2::Compression::best());
+    encoder.write_all(&data).unwrap();
+    let bytes = encoder.finish().unwrap();
+
+    let mut gzip = GzipStream::build(&bytes).unwrap();
+    assert_eq!(gzip.header.name.as_deref(), Some("notes.txt"));
+    assert_eq!(gzip.header.comment.as_deref(), Some("from flate2"));
+    assert_eq!(gzip.header.extra.as_deref(), Some(&[1, 2, 3][..]));
+    assert_eq!(gzip.header.mtime, 42);
+    assert_eq!(gzip.decompress().unwrap(), data);
+
+    let mut header = GzipHeader::new().name("back.txt").mtime(7);
+    header.header_crc = Some(0);
+    let bytes = GzipStream::compress(&data, &header);
+    let mut decoder = flate2::read::GzDecoder::new(&bytes[..]);
+    let mut inflated = Vec::new();
+    decoder.read_to_end(&mut inflated).unwrap();
+    assert_eq!(inflated, data);
+    let read = decoder.header().unwrap();
+    assert_eq!((read.filename(), read.mtime()), (Some(&b"back.txt"[..]), 7));
+}
+
+/// Checks gzip files written by GzipStream::compress read back with their
+/// header, and that the default header is the same every time.
+#[test]
+pub fn gzip_files_round_trip() {
+    let data = "round and round. ".repeat(500).into_bytes();
+
+    let plain = GzipStream::compress(&data, &GzipHeader::new());
+    assert_eq!(plain[..10], [0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255]);
+    assert_eq!(plain, GzipStream::compress(&data, &GzipHeader::default()));
+    assert!(plain.len() < data.len() / 10);
+    assert_eq!(
+        GzipStream::build(&plain).unwrap().decompress().unwrap(),
+        data
+    );
+
+    let mut header = GzipHeader::new()
+        .name("naïve\0.txt")
+        .comment("日本")
+        .mtime(1_234_567);
+    header.text = true;
+    header.extra = Some(vec![9; 3]);
+    header.header_crc = Some(0);
+    let bytes = GzipStream::compress(&data, &header);
+    let mut gzip = GzipStream::build(&bytes).unwrap();
+    assert_eq!(gzip.header.name.as_deref(), Some("naïve.txt"));
+    assert_eq!(gzip.header.comment.as_deref(), Some("??"));
+    assert_eq!(gzip.header.mtime, 1_234_567);
+    assert_eq!(gzip.header.extra, header.extra);
+    assert!(gzip.header.text && gzip.header.header_crc.is_some());
+    assert_eq!(gzip.decompress().unwrap(), data);
+}
+
+/// Checks every member of a file written by joining gzip files is read,
+/// each with its own header, and that damage or junk after the last member
+/// stops the iterator with an error.
+#[test]
+pub fn gzip_members_are_read() {
+    use chameleon::compression::gzip::{self, GzipMembers};
+
+    let first = "the first member. ".repeat(100).into_bytes();
+    let third: Vec<u8> = (0..70_000u32).map(|i| (i % 251 * i % 251) as u8).collect();
+    let mut bytes = build(&first);
+    bytes.extend(GzipStream::compress(b"", &GzipHeader::new()));
+    bytes.extend(GzipStream::compress(
+        &third,
+        &GzipHeader::new().name("third"),
+    ));
+
+    let members: Vec<_> = GzipMembers::new(&bytes).map(Result::unwrap).collect();
+    assert_eq!(members.len(), 3);
+    assert_eq!(members[0].header.name.as_deref(), Some("café.txt"));
+    assert_eq!(members[0].data, first);
+    assert_eq!(members[1].data, b"");
+    assert_eq!(members[2].header.name.as_deref(), Some("third"));
+    assert_eq!(members[2].data, third);
+
+    let joined = [&first[..], &third].concat();
+    assert_eq!(gzip::decompress_all(&bytes).unwrap(), joined);
+    assert_eq!(gzip::decompress_all(&build(&first)).unwrap(), first);
+
+    let mut members = GzipMembers::new(&bytes);
+    members.next();
+    assert_eq!(members.offset(), build(&first).len());
+
+    let mut junk = bytes.clone();
+    junk.extend_from_slice(b"junk");
+    let results: Vec<_> = GzipMembers::new(&junk).collect();
+    assert_eq!(results.len(), 4);
+    assert!(results[3].is_err());
+
+    // The second member's CRC32.
+    let mut damaged = bytes.clone();
+    damaged[build(&first).len() + 15] ^= 1;
+    let results: Vec<_> = GzipMembers::new(&damaged).collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[1].is_err());
+    assert!(gzip::decompress_all(&damaged).is_err());
+
+    assert!(gzip::decompress_all(b"").is_err());
+    assert!(gzip::decompress_all(&bytes[..bytes.len() - 1]).is_err());
+}
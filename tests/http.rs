@@ -0,0 +1,58 @@
+#![cfg(all(feature = "std", feature = "png", feature = "http"))]
+
+use std::{
+    fs,
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+use chameleon::prelude::*;
+
+/// Serves body to a single request on a local port, returning the URL.
+fn serve_once(body: Vec<u8>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/image.png", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0u8; 1024];
+        let _ = stream.read(&mut request);
+
+        let header = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        );
+        let _ = stream.write_all(header.as_bytes());
+        let _ = stream.write_all(&body);
+    });
+
+    url
+}
+
+/// Checks that an image fetched over HTTP matches the same file read from
+/// disk.
+#[test]
+pub fn decodes_from_url() {
+    let bytes = fs::read("./tests/samples/basn2c08.png").unwrap();
+    let url = serve_once(bytes.clone());
+
+    let fetched = Png::from_url(&url).unwrap();
+    let local = Png::decoder().decode_bytes(&bytes).unwrap();
+
+    assert_eq!(fetched.rgb().unwrap(), local.rgb().unwrap());
+}
+
+/// Checks that the limits are applied to images fetched over HTTP.
+#[test]
+pub fn url_limits_are_checked() {
+    let url = serve_once(fs::read("./tests/samples/basn2c08.png").unwrap());
+
+    let error = Png::decoder()
+        .limits(Limits::new().max_width(16))
+        .decode_url(&url)
+        .err()
+        .unwrap();
+
+    assert_eq!(Error::from(error).kind(), ErrorKind::LimitExceeded);
+}
@@ -0,0 +1,143 @@
+#![cfg(all(feature = "std", feature = "png", feature = "ppm"))]
+
+use std::fs;
+use std::io::Cursor;
+
+use chameleon::formats::{
+    png::{DecoderError, Limits, Png, StreamDecoder},
+    ppm::Ppm,
+};
+use chameleon::prelude::Error;
+
+/// Checks that decoding from an in-memory reader or an embedded slice and
+/// writing to an in-memory writer give the same results as going through the
+/// file system.
+#[test]
+pub fn read_and_write_through_cursors() {
+    let path = "./tests/samples/basn2c08.png";
+
+    let from_path = Png::from_path(path).unwrap();
+    let from_reader = Png::from_reader(Cursor::new(fs::read(path).unwrap())).unwrap();
+    let from_bytes = Png::from_bytes(include_bytes!("samples/basn2c08.png")).unwrap();
+
+    let rgb = from_reader.rgb().unwrap();
+    assert_eq!(from_path.rgb().unwrap(), rgb);
+    assert_eq!(from_bytes.rgb().unwrap(), rgb);
+    assert!(Png::from_reader(&b"not a png"[..]).is_err());
+
+    let ppm = Ppm::build(&rgb, from_reader.dimensions.0, from_reader.dimensions.1);
+
+    let mut written = Cursor::new(Vec::new());
+    ppm.write_to(&mut written).unwrap();
+
+    assert_eq!(written.into_inner(), ppm.to_bytes());
+}
+
+/// Checks that pushing a file into a StreamDecoder a byte at a time decodes
+/// the same image, and that an oversized image is rejected at IHDR.
+#[test]
+pub fn stream_decoder_byte_at_a_time() {
+    let bytes = fs::read("./tests/samples/basn2c08.png").unwrap();
+
+    let mut decoder = StreamDecoder::new(Png::decoder());
+    for byte in &bytes {
+        decoder.push(&[*byte]).unwrap();
+    }
+    let png = decoder.finish().unwrap();
+
+    assert_eq!(
+        png.rgb().unwrap(),
+        Png::from_path("./tests/samples/basn2c08.png")
+            .unwrap()
+            .rgb()
+            .unwrap()
+    );
+
+    // IHDR ends at byte 33, long before the image data.
+    let mut decoder = StreamDecoder::new(Png::decoder().limits(Limits::new().max_width(16)));
+    assert!(decoder.push(&bytes[..32]).is_ok());
+    assert!(decoder.push(&bytes[32..33]).is_err());
+}
+
+/// Checks that parsing chunks as they arrive, in pieces or read from a path
+/// on a second thread, gives the same Png or error as parsing a whole file,
+/// strict or not, for damaged files too.
+#[test]
+pub fn chunks_parsed_as_they_arrive() {
+    let summary = |result: Result<Png, DecoderError>| match result {
+        Ok(png) => Ok((png.data.ancillary_chunks.len(), png.rgba().ok())),
+        Err(e) => Err(Error::from(e).code()),
+    };
+
+    for dir in ["./tests/samples", "./tests/corrupt"] {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|e| e != "png") {
+                continue;
+            }
+            let bytes = fs::read(&path).unwrap();
+
+            for strict in [true, false] {
+                let options = Png::decoder().strict(strict);
+                let expected = summary(options.decode_bytes(&bytes));
+
+                let mut decoder = StreamDecoder::new(options.clone());
+                let pushed = bytes.chunks(100).try_for_each(|piece| decoder.push(piece));
+                let streamed = pushed.and_then(|_| decoder.finish());
+                assert_eq!(summary(streamed), expected, "{path:?}");
+                assert_eq!(summary(options.decode(&path)), expected, "{path:?}");
+            }
+        }
+    }
+}
+
+/// Checks that decoding from an AsyncRead that only returns a few bytes per
+/// poll gives the same image as decoding from a path.
+#[cfg(feature = "async")]
+#[test]
+pub fn from_async_reader() {
+    use std::future::Future;
+    use std::pin::{pin, Pin};
+    use std::task::{Context, Poll, Waker};
+
+    use futures_io::AsyncRead;
+
+    struct Trickle(Vec<u8>, bool);
+
+    // Alternates between pending and returning at most 7 bytes.
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.1 = !self.1;
+            if self.1 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let n = buf.len().min(7).min(self.0.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0.drain(..n);
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    let bytes = fs::read("./tests/samples/basn2c08.png").unwrap();
+    let mut future = pin!(Png::from_async_reader(Trickle(bytes, false)));
+    let mut cx = Context::from_waker(Waker::noop());
+
+    let png = loop {
+        if let Poll::Ready(result) = future.as_mut().poll(&mut cx) {
+            break result.unwrap();
+        }
+    };
+
+    assert_eq!(
+        png.rgb().unwrap(),
+        Png::from_path("./tests/samples/basn2c08.png")
+            .unwrap()
+            .rgb()
+            .unwrap()
+    );
+}
@@ -1,6 +1,8 @@
+#![cfg(all(feature = "std", feature = "png", feature = "ppm"))]
+
 use std::fs;
 
-use chameleon::formats::{png::Png, ppm::Ppm};
+use chameleon::prelude::*;
 /// Tests against every image in the png suite data set: www.shaick.com/pngsuite/
 #[test]
 pub fn png_suite() {
@@ -41,3 +43,419 @@ pub fn png_suite() {
         }
     }
 }
+
+/// Tests that a file without any IDAT chunks gives back an error from rgb
+/// instead of panicking.
+#[test]
+pub fn missing_image_data_is_an_error() {
+    let png = Png::from_path("./tests/samples/xdtn0g01.png").unwrap();
+
+    assert!(png.rgb().is_err());
+}
+
+/// Checks the rows iterator gives the same rows as Png::rgba_rows for every
+/// valid image, interlaced, 16 bit and paletted ones included.
+#[test]
+pub fn rows_match_rgba_rows() {
+    for entry in fs::read_dir("./tests/samples").unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        if name.starts_with('x') || path.extension().is_none_or(|e| e != "png") {
+            continue;
+        }
+
+        let png = Png::from_path(&path).unwrap();
+        let mut expected = Vec::new();
+        png.rgba_rows(&mut |_, row| {
+            expected.push(row.to_vec());
+            Ok(())
+        })
+        .unwrap();
+
+        let rows = png.rows().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(rows, expected, "{name}");
+    }
+}
+
+/// Checks that decoding into a caller's buffer gives the pixels Png::rgba
+/// does, with the alpha left out of images that have none, and that a
+/// buffer too small is refused.
+#[test]
+pub fn decode_into_matches_rgba() {
+    let transformations = Transformations::new().flip_vertical(true).bgr(true);
+    for entry in fs::read_dir("./tests/samples").unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        if name.starts_with('x') || path.extension().is_none_or(|e| e != "png") {
+            continue;
+        }
+
+        for options in [
+            Png::decoder(),
+            Png::decoder().transformations(transformations),
+        ] {
+            let png = options.decode(&path).unwrap();
+            let channels = png.pixel_format().bytes_per_pixel();
+            let mut buffer = vec![7; png.dimensions.0 * png.dimensions.1 * channels + 1];
+
+            let view = png.decode_into(&mut buffer).unwrap();
+            assert_eq!((view.width, view.height), png.dimensions, "{name}");
+            let expected = png
+                .rgba()
+                .unwrap()
+                .iter()
+                .flat_map(|&(r, g, b, a)| [r, g, b, a].into_iter().take(channels))
+                .collect::<Vec<_>>();
+            assert_eq!(view.data, expected, "{name}");
+            assert_eq!(buffer.last(), Some(&7));
+        }
+    }
+
+    let png = Png::from_path("./tests/samples/tbrn2c08.png").unwrap();
+    assert_eq!(png.pixel_format(), PixelFormat::Rgba8);
+    let error = png.decode_into(&mut [0; 16]).unwrap_err();
+    assert!(matches!(
+        error,
+        Error::Image(ImageError::BufferSize(4096, 16))
+    ));
+}
+
+/// Checks that padded rows hold the same pixels as packed ones, flipped or
+/// not, with the padding left as it was.
+#[test]
+pub fn decode_into_aligned_pads_rows() {
+    let transformations = Transformations::new().flip_vertical(true).bgr(true);
+    for name in ["basn2c08", "basi3p01", "tbrn2c08", "s35n3p04"] {
+        for options in [
+            Png::decoder(),
+            Png::decoder().transformations(transformations),
+        ] {
+            let png = options
+                .decode(format!("./tests/samples/{name}.png"))
+                .unwrap();
+            let (width, height) = png.dimensions;
+            let row = width * png.pixel_format().bytes_per_pixel();
+            let mut packed = vec![0; row * height];
+            let packed = png.decode_into(&mut packed).unwrap().to_image().unwrap();
+
+            for alignment in [0, 1, 4, 256] {
+                let (stride, len) = png.aligned_layout(alignment).unwrap();
+                assert_eq!(stride, row.next_multiple_of(alignment.max(1)), "{name}");
+                assert_eq!(len, stride * (height - 1) + row);
+
+                let mut buffer = vec![7; len];
+                let view = png.decode_into_aligned(&mut buffer, alignment).unwrap();
+                assert_eq!(view.stride, stride);
+                assert_eq!(view.to_image().unwrap(), packed, "{name} {alignment}");
+                for padding in buffer
+                    .chunks(stride)
+                    .map(|rows| &rows[row.min(rows.len())..])
+                {
+                    assert!(padding.iter().all(|&byte| byte == 7), "{name}");
+                }
+            }
+        }
+    }
+
+    let png = Png::from_path("./tests/samples/basn2c08.png").unwrap();
+    let error = png.decode_into_aligned(&mut [0; 3071], 4).unwrap_err();
+    assert!(matches!(
+        error,
+        Error::Image(ImageError::BufferSize(3072, 3071))
+    ));
+}
+
+/// Checks that an Adam7 interlaced image decodes to the same pixels as the
+/// same image stored without interlacing.
+#[test]
+pub fn adam7_matches_non_interlaced() {
+    let interlaced = Png::from_path("./tests/samples/basi2c08.png").unwrap();
+    let plain = Png::from_path("./tests/samples/basn2c08.png").unwrap();
+
+    assert_eq!(interlaced.interlace, Interlace::Adam7);
+    assert_eq!(interlaced.rgb().unwrap(), plain.rgb().unwrap());
+}
+
+/// Checks that 16 bit samples keep their full precision in Png::rgb16 and
+/// their high byte in Png::rgb, and that 8 bit samples are scaled up.
+#[test]
+pub fn sixteen_bit_samples() {
+    let png = Png::from_path("./tests/samples/basn2c16.png").unwrap();
+    let wide = png.rgb16().unwrap();
+    let narrow = png.rgb().unwrap();
+
+    assert_eq!(wide.len(), 32 * 32);
+    assert!(wide
+        .iter()
+        .any(|&(r, g, b)| [r, g, b].iter().any(|v| v & 0xff != 0)));
+    for (&(r, g, b), &rgb) in wide.iter().zip(&narrow) {
+        assert_eq!(((r >> 8) as u8, (g >> 8) as u8, (b >> 8) as u8), rgb);
+    }
+
+    let png = Png::from_path("./tests/samples/basn2c08.png").unwrap();
+    let scaled = png
+        .rgb()
+        .unwrap()
+        .iter()
+        .map(|&(r, g, b)| (r as u16 * 257, g as u16 * 257, b as u16 * 257))
+        .collect::<Vec<_>>();
+    assert_eq!(png.rgb16().unwrap(), scaled);
+}
+
+/// Checks that 1, 2 and 4 bit grayscale samples are unpacked and scaled to
+/// the gray values of the reference dumps, interlaced or not, and that
+/// palette indices are unpacked without scaling.
+#[test]
+pub fn sub_byte_samples() {
+    for name in [
+        "basn0g01", "basn0g02", "basn0g04", "basi0g01", "basi0g02", "basi0g04",
+    ] {
+        let png = Png::from_path(format!("./tests/samples/{name}.png")).unwrap();
+        let reference = fs::read(format!("./tests/reference/{name}.rgba")).unwrap();
+        let gray = reference
+            .chunks_exact(4)
+            .map(|rgba| rgba[0])
+            .collect::<Vec<_>>();
+
+        assert_eq!(png.samples().unwrap(), gray, "{name}");
+    }
+
+    let png = Png::from_path("./tests/samples/basn3p02.png").unwrap();
+    let samples = png.samples().unwrap();
+    assert_eq!(samples.len(), 32 * 32);
+    assert!(samples.iter().all(|&index| index < 4));
+    assert!(samples.contains(&3));
+
+    for depth in [1, 2, 4] {
+        let interlaced = Png::from_path(format!("./tests/samples/basi3p0{depth}.png")).unwrap();
+        let plain = Png::from_path(format!("./tests/samples/basn3p0{depth}.png")).unwrap();
+        assert_eq!(interlaced.samples().unwrap(), plain.samples().unwrap());
+    }
+}
+
+/// Checks that indexed images are looked up in their palette, and that an
+/// index past the end of the palette is an error rather than a guess.
+#[test]
+pub fn palette_indices_are_checked() {
+    let png = Png::from_path("./tests/samples/basn3p08.png").unwrap();
+    let palette = png.palette().unwrap();
+    let expected = png
+        .samples()
+        .unwrap()
+        .iter()
+        .map(|&index| {
+            let entry = palette.entries[index as usize];
+            (entry[0], entry[1], entry[2])
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(png.rgb().unwrap(), expected);
+
+    let image = Image::new(
+        3,
+        1,
+        PixelFormat::Rgb8,
+        vec![255, 0, 0, 0, 255, 0, 0, 0, 255],
+    )
+    .unwrap();
+    let bytes = image.encode_png(&EncodeOptions::new()).unwrap();
+    let mut png = Png::decoder().decode_bytes(&bytes).unwrap();
+    assert_eq!(png.color_type, ColorType::PalleteIndex);
+
+    png.set_palette(&Palette::new(vec![[0, 0, 0, 255]]))
+        .unwrap();
+    assert!(matches!(
+        png.rgb(),
+        Err(DecoderError::InvalidChunk("palette index is out of range."))
+    ));
+}
+
+/// Checks that gray samples are copied into red, green and blue, at every
+/// precision, and that alpha samples are left out.
+#[test]
+pub fn grayscale_expands_to_rgb() {
+    for name in ["basn0g04", "basn0g08", "basn4a08"] {
+        let png = Png::from_path(format!("./tests/samples/{name}.png")).unwrap();
+        let gray = png
+            .samples()
+            .unwrap()
+            .iter()
+            .step_by(png.color_type.samples())
+            .map(|&gray| (gray, gray, gray))
+            .collect::<Vec<_>>();
+
+        assert_eq!(png.rgb().unwrap(), gray, "{name}");
+    }
+
+    let png = Png::from_path("./tests/samples/basn0g16.png").unwrap();
+    let wide = png.rgb16().unwrap();
+    assert!(wide.iter().all(|&(r, g, b)| r == g && g == b));
+    assert!(wide.iter().any(|&(r, _, _)| r & 0xff != 0));
+}
+
+/// Checks that tRNS makes the color it holds transparent in grayscale and
+/// RGB images, and that images without alpha come out opaque.
+#[test]
+pub fn trns_sets_alpha() {
+    for name in ["tbbn0g04", "tbrn2c08", "tbbn2c16", "tbbn3p08"] {
+        let png = Png::from_path(format!("./tests/samples/{name}.png")).unwrap();
+        let rgba = png.rgba().unwrap();
+        let rgb = png.rgb().unwrap();
+
+        assert!(rgba.iter().any(|&(_, _, _, a)| a == 0), "{name}");
+        assert!(rgba
+            .iter()
+            .zip(&rgb)
+            .all(|(&(r, g, b, _), &expected)| (r, g, b) == expected));
+    }
+
+    let png = Png::from_path("./tests/samples/basn2c08.png").unwrap();
+    assert!(png.rgba().unwrap().iter().all(|&(_, _, _, a)| a == 255));
+}
+
+/// Checks the gAMA chunk is read, and that correcting for it takes 8 and 16
+/// bit samples from the file's gamma to the display's.
+#[test]
+pub fn gamma_is_corrected() {
+    let decode = |name: &str, gamma: bool| {
+        Png::decoder()
+            .transformations(Transformations::new().gamma(gamma))
+            .decode(format!("./tests/samples/{name}.png"))
+            .unwrap()
+    };
+    // Raises a sample to 1 / (2.5 * 2.2), from the 2.5 of g25 to a display.
+    let correct =
+        |sample: u16, max: f64| ((sample as f64 / max).powf(1.0 / 5.5) * max + 0.5) as u16;
+
+    let plain = decode("g25n2c08", false);
+    let corrected = decode("g25n2c08", true);
+    assert_eq!(plain.gamma, Some(2.5));
+    for (&(r, g, b), expected) in corrected.rgb().unwrap().iter().zip(plain.rgb().unwrap()) {
+        let expected = [expected.0, expected.1, expected.2].map(|s| correct(s as u16, 255.0));
+        assert_eq!([r, g, b].map(u16::from), expected);
+    }
+
+    let plain = decode("g25n0g16", false).rgb16().unwrap();
+    let corrected = decode("g25n0g16", true).rgb16().unwrap();
+    for (&(gray, _, _), expected) in corrected.iter().zip(plain) {
+        assert_eq!(gray, correct(expected.0, 65535.0));
+    }
+
+    // Files without gAMA are left as they are.
+    let png = decode("f02n2c08", true);
+    assert_eq!(png.gamma, None);
+    assert_eq!(png.rgb().unwrap(), decode("f02n2c08", false).rgb().unwrap());
+}
+
+/// Checks bKGD is read for every color type, a palette index included, and
+/// that Png::rgb_composited blends each pixel against it by its alpha.
+#[test]
+pub fn background_is_composited() {
+    let cases = [
+        ("bgbn4a08", Some(Background::Gray(0)), [0, 0, 0]),
+        ("bggn4a16", Some(Background::Gray(43908)), [171, 171, 171]),
+        (
+            "bgwn6a08",
+            Some(Background::Rgb(255, 255, 255)),
+            [255, 255, 255],
+        ),
+        (
+            "bgyn6a16",
+            Some(Background::Rgb(65535, 65535, 0)),
+            [255, 255, 0],
+        ),
+        ("tbbn0g04", Some(Background::Gray(0)), [0, 0, 0]),
+        ("tbgn2c16", Some(Background::Rgb(0, 65535, 0)), [0, 255, 0]),
+        ("tbyn3p08", Some(Background::Index(245)), [255, 255, 0]),
+        // Without bKGD the image is blended against white.
+        ("bgai4a08", None, [255, 255, 255]),
+    ];
+
+    for (name, background, rgb) in cases {
+        let png = Png::from_path(format!("./tests/samples/{name}.png")).unwrap();
+        assert_eq!(png.background(), background, "{name}");
+
+        let composited = png.rgb_composited().unwrap();
+        for (&(r, g, b), (red, green, blue, alpha)) in composited.iter().zip(png.rgba().unwrap()) {
+            let blend = |sample: u8, background: u8| {
+                let alpha = alpha as u32;
+                ((sample as u32 * alpha + background as u32 * (255 - alpha) + 127) / 255) as u8
+            };
+            let expected = [
+                blend(red, rgb[0]),
+                blend(green, rgb[1]),
+                blend(blue, rgb[2]),
+            ];
+            assert_eq!([r, g, b], expected, "{name}");
+        }
+    }
+
+    // Opaque images come out the same as Png::rgb.
+    let png = Png::from_path("./tests/samples/basn2c08.png").unwrap();
+    assert_eq!(png.rgb_composited().unwrap(), png.rgb().unwrap());
+}
+
+/// Checks probing reads the same header decoding does, for every PngSuite
+/// image that decodes, and counts every chunk the decoder kept.
+#[test]
+pub fn probe_matches_decode() {
+    for entry in fs::read_dir("./tests/samples").unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let Ok(png) = Png::from_path(&path) else {
+            continue;
+        };
+
+        assert_eq!(Png::probe(&path).unwrap(), png.info(), "{name}");
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(Png::probe_bytes(&bytes[..33]).unwrap(), png.info());
+
+        // Walk the chunk headers by hand, as PngData leaves out chunks the
+        // decoder has no use for.
+        let (mut offset, mut chunks) = (8, 0);
+        while offset + 12 <= bytes.len() {
+            let length = u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap());
+            chunks += 1;
+            if &bytes[offset + 4..offset + 8] == b"IEND" {
+                break;
+            }
+            offset += length as usize + 12;
+        }
+        let info = Png::probe_chunks(fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(info.chunks, Some(chunks), "{name}");
+    }
+
+    let error = Png::probe("./tests/samples/xs1n0g01.png").unwrap_err();
+    assert!(matches!(error.root(), DecoderError::NotPngFile));
+    let error = Png::probe("./tests/samples/xc1n0g08.png").unwrap_err();
+    assert!(matches!(error.root(), DecoderError::InvalidColorType(1)));
+    assert!(Png::probe_bytes(&chameleon::formats::png::PNG_HEADER).is_err());
+}
+
+/// Checks the IHDR and scanline bytes convert to and from their enums,
+/// agreeing with what's read from the files.
+#[test]
+pub fn header_bytes_round_trip() {
+    for byte in 0..=u8::MAX {
+        if let Ok(color_type) = ColorType::try_from(byte) {
+            assert_eq!(u8::from(color_type), byte);
+        }
+        if let Ok(interlace) = Interlace::try_from(byte) {
+            assert_eq!(u8::from(interlace), byte);
+        }
+        if let Ok(filter) = Filters::try_from(byte) {
+            assert_eq!(u8::from(filter), byte);
+        }
+    }
+    assert!(matches!(
+        ColorType::try_from(1),
+        Err(DecoderError::InvalidColorType(1))
+    ));
+    assert!(matches!(
+        Interlace::try_from(2),
+        Err(DecoderError::InvalidInterlace(2))
+    ));
+
+    let png = Png::from_path("./tests/samples/basi6a16.png").unwrap();
+    assert_eq!(png.color_type, ColorType::try_from(6).unwrap());
+    assert_eq!(png.interlace, Interlace::try_from(1).unwrap());
+}
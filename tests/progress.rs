@@ -0,0 +1,33 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use std::ops::ControlFlow;
+
+use chameleon::formats::png::{DecoderError, Png, Progress};
+
+/// Checks that progress is reported every N rows and after the last row, and
+/// that returning Break cancels the decode.
+#[test]
+pub fn progress_and_cancellation() {
+    let png = Png::from_path("./tests/samples/basn2c08.png").unwrap();
+
+    let mut reported = Vec::new();
+    let rgb = png
+        .rgb_with_progress(10, |progress| {
+            reported.push(progress.rows_done);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+    assert_eq!(rgb, png.rgb().unwrap());
+    assert_eq!(reported, vec![10, 20, 30, 32]);
+
+    let result = png.rgb_with_progress(1, |progress: Progress| {
+        if progress.rows_done == 5 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+
+    assert!(matches!(result, Err(DecoderError::Cancelled)));
+}
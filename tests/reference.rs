@@ -0,0 +1,67 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use std::{fs, path::Path};
+
+use chameleon::prelude::*;
+
+/// Samples the decoder doesn't yet get right, every sample decodes
+/// correctly for now. Remove a sample from here once it decodes correctly,
+/// the test fails if one on the list starts matching so the list can't go
+/// stale.
+const KNOWN_FAILURES: &[&str] = &[];
+
+/// Decodes every valid PngSuite image and compares the pixels against the
+/// reference dumps in tests/reference, made by tests/reference/generate.py.
+#[test]
+pub fn matches_reference() {
+    let mut mismatched = Vec::new();
+
+    let mut references = fs::read_dir("./tests/reference/")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ex| ex == "rgba"))
+        .collect::<Vec<_>>();
+    references.sort();
+    assert!(!references.is_empty());
+
+    for reference in references {
+        let name = reference.file_stem().unwrap().to_str().unwrap().to_string();
+        let expected = fs::read(&reference).unwrap();
+
+        if !decodes_to(format!("./tests/samples/{name}.png"), &expected) {
+            mismatched.push(name);
+        }
+    }
+
+    let unexpected = mismatched
+        .iter()
+        .filter(|name| !KNOWN_FAILURES.contains(&name.as_str()))
+        .collect::<Vec<_>>();
+    let fixed = KNOWN_FAILURES
+        .iter()
+        .filter(|name| !mismatched.contains(&name.to_string()))
+        .collect::<Vec<_>>();
+
+    assert!(unexpected.is_empty(), "no longer match: {:?}", unexpected);
+    assert!(
+        fixed.is_empty(),
+        "now match, remove from KNOWN_FAILURES: {:?}",
+        fixed
+    );
+}
+
+/// Whether the sample decodes to the reference RGBA pixels.
+fn decodes_to(path: impl AsRef<Path>, expected: &[u8]) -> bool {
+    let Ok(png) = Png::from_path(path) else {
+        return false;
+    };
+    let Ok(rgba) = png.rgba() else {
+        return false;
+    };
+
+    rgba.len() * 4 == expected.len()
+        && rgba
+            .iter()
+            .zip(expected.chunks_exact(4))
+            .all(|(&(r, g, b, a), expected)| [r, g, b, a] == expected)
+}
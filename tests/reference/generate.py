@@ -0,0 +1,186 @@
+#!/usr/bin/env python3
+"""Generates the reference pixel dumps checked by tests/reference.rs.
+
+Each valid image in tests/samples is decoded by this small, independent PNG
+decoder, built only on the standard library's zlib, and written next to this
+script as <name>.rgba: width * height pixels of 8-bit red, green, blue and
+alpha, from left to right, top to bottom, with no header.
+
+16-bit samples keep their high byte, lower bit depths are scaled up to the
+full 0 to 255 range, and tRNS sets the alpha. Images PngSuite marks as
+corrupt, those starting with 'x', are skipped.
+
+The dumps only need regenerating if a sample is added:
+
+    python3 tests/reference/generate.py
+"""
+
+import pathlib
+import struct
+import zlib
+
+HERE = pathlib.Path(__file__).resolve().parent
+SAMPLES = HERE.parent / "samples"
+
+SIGNATURE = b"\x89PNG\r\n\x1a\n"
+SAMPLES_PER_PIXEL = {0: 1, 2: 3, 3: 1, 4: 2, 6: 4}
+
+# Starting column, starting row, column step and row step of each Adam7 pass.
+ADAM7 = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+]
+
+
+def read_chunks(data):
+    if data[:8] != SIGNATURE:
+        raise ValueError("missing PNG signature")
+
+    chunks, index = [], 8
+    while index < len(data):
+        length, ctype = struct.unpack(">I4s", data[index : index + 8])
+        body = data[index + 8 : index + 8 + length]
+        (crc,) = struct.unpack(">I", data[index + 8 + length : index + 12 + length])
+        if zlib.crc32(ctype + body) != crc:
+            raise ValueError(f"bad CRC in {ctype!r}")
+
+        chunks.append((ctype, body))
+        index += length + 12
+        if ctype == b"IEND":
+            break
+
+    return chunks
+
+
+def paeth(a, b, c):
+    p = a + b - c
+    pa, pb, pc = abs(p - a), abs(p - b), abs(p - c)
+    if pa <= pb and pa <= pc:
+        return a
+    return b if pb <= pc else c
+
+
+def unfilter(data, offset, width, height, bits_per_pixel):
+    """Reconstructs one pass, returning its rows and where the pass ended."""
+    stride = (width * bits_per_pixel + 7) // 8
+    bpp = max(1, bits_per_pixel // 8)
+    rows, previous = [], bytearray(stride)
+
+    for _ in range(height):
+        filter_type = data[offset]
+        row = bytearray(data[offset + 1 : offset + 1 + stride])
+        offset += stride + 1
+
+        for i in range(stride):
+            left = row[i - bpp] if i >= bpp else 0
+            above = previous[i]
+            upper_left = previous[i - bpp] if i >= bpp else 0
+            predicted = [
+                0,
+                left,
+                above,
+                (left + above) // 2,
+                paeth(left, above, upper_left),
+            ][filter_type]
+            row[i] = (row[i] + predicted) & 0xFF
+
+        rows.append(row)
+        previous = row
+
+    return rows, offset
+
+
+def samples(row, count, bit_depth):
+    """Unpacks the first count samples of a row."""
+    if bit_depth == 16:
+        return [row[2 * i] << 8 | row[2 * i + 1] for i in range(count)]
+    if bit_depth == 8:
+        return list(row[:count])
+
+    per_byte = 8 // bit_depth
+    mask = (1 << bit_depth) - 1
+    return [
+        row[i // per_byte] >> (8 - bit_depth * (i % per_byte + 1)) & mask
+        for i in range(count)
+    ]
+
+
+def decode(path):
+    chunks = read_chunks(path.read_bytes())
+    ihdr = dict(chunks)[b"IHDR"]
+    width, height, bit_depth, color_type, _, _, interlace = struct.unpack(
+        ">IIBBBBB", ihdr
+    )
+
+    plte = next((body for ctype, body in chunks if ctype == b"PLTE"), b"")
+    trns = next((body for ctype, body in chunks if ctype == b"tRNS"), None)
+    data = zlib.decompress(b"".join(body for ctype, body in chunks if ctype == b"IDAT"))
+
+    channels = SAMPLES_PER_PIXEL[color_type]
+    max_value = (1 << bit_depth) - 1
+
+    def scale(value):
+        if bit_depth == 16:
+            return value >> 8
+        return value * 255 // max_value
+
+    def rgba(pixel):
+        if color_type == 3:
+            index = pixel[0]
+            r, g, b = plte[3 * index : 3 * index + 3]
+            a = trns[index] if trns is not None and index < len(trns) else 255
+            return (r, g, b, a)
+
+        if color_type in (0, 4):
+            r = g = b = scale(pixel[0])
+            key = pixel[:1]
+        else:
+            r, g, b = (scale(v) for v in pixel[:3])
+            key = pixel[:3]
+
+        if channels in (2, 4):
+            a = scale(pixel[-1])
+        elif trns is not None and list(key) == list(
+            struct.unpack(f">{len(key)}H", trns[: 2 * len(key)])
+        ):
+            a = 0
+        else:
+            a = 255
+        return (r, g, b, a)
+
+    image = [[None] * width for _ in range(height)]
+    passes = ADAM7 if interlace else [(0, 0, 1, 1)]
+    offset = 0
+
+    for x0, y0, dx, dy in passes:
+        pass_width = (width - x0 + dx - 1) // dx
+        pass_height = (height - y0 + dy - 1) // dy
+        if pass_width == 0 or pass_height == 0:
+            continue
+
+        rows, offset = unfilter(
+            data, offset, pass_width, pass_height, channels * bit_depth
+        )
+        for j, row in enumerate(rows):
+            values = samples(row, pass_width * channels, bit_depth)
+            for i in range(pass_width):
+                pixel = values[i * channels : (i + 1) * channels]
+                image[y0 + j * dy][x0 + i * dx] = rgba(pixel)
+
+    return bytes(value for row in image for pixel in row for value in pixel)
+
+
+def main():
+    for path in sorted(SAMPLES.glob("*.png")):
+        if path.name.startswith("x"):
+            continue
+        (HERE / f"{path.stem}.rgba").write_bytes(decode(path))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,56 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use std::fs;
+
+use chameleon::prelude::*;
+use chameleon::roundtrip;
+
+/// Checks every PngSuite image that should decode comes back exactly after
+/// a trip through the encoder, whatever its color type, bit depth or
+/// interlacing.
+#[test]
+pub fn pngsuite_survives_a_round_trip() {
+    for entry in fs::read_dir("./tests/samples").unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        // Files starting with x are corrupt on purpose.
+        if name.starts_with('x') || path.extension().is_none_or(|e| e != "png") {
+            continue;
+        }
+
+        let report = roundtrip::verify(&path).unwrap();
+        assert!(report.is_exact(), "{name}: {report}");
+        assert_eq!(report.difference.psnr, None);
+    }
+
+    let report = roundtrip::verify("./tests/samples/basn6a16.png").unwrap();
+    assert_eq!(report.dimensions, (32, 32));
+    assert_eq!(report.format, PixelFormat::Rgba16);
+}
+
+/// Checks the counts and PSNR of images that differ, at both bit depths,
+/// and that images of different sizes or formats aren't compared.
+#[test]
+pub fn compare_reports_psnr() {
+    let a = Image::new(2, 1, PixelFormat::Gray8, vec![0, 100]).unwrap();
+    let b = Image::new(2, 1, PixelFormat::Gray8, vec![0, 110]).unwrap();
+
+    let same = roundtrip::compare(&a, &a).unwrap();
+    assert_eq!((same.samples, same.max, same.psnr), (0, 0, None));
+
+    // The mean squared error is 100 / 2 = 50.
+    let difference = roundtrip::compare(&a, &b).unwrap();
+    assert_eq!((difference.samples, difference.max), (1, 10));
+    let expected = 10.0 * (255.0f64 * 255.0 / 50.0).log10();
+    assert!((difference.psnr.unwrap() - expected).abs() < 1e-9);
+
+    let a = Image::new(1, 1, PixelFormat::Gray16, vec![0x12, 0x34]).unwrap();
+    let b = Image::new(1, 1, PixelFormat::Gray16, vec![0x12, 0x35]).unwrap();
+    let difference = roundtrip::compare(&a, &b).unwrap();
+    assert_eq!((difference.samples, difference.max), (1, 1));
+    let expected = 10.0 * (65535.0f64 * 65535.0).log10();
+    assert!((difference.psnr.unwrap() - expected).abs() < 1e-9);
+
+    let c = Image::new(1, 1, PixelFormat::Gray8, vec![0]).unwrap();
+    assert!(roundtrip::compare(&a, &c).is_err());
+}
@@ -0,0 +1,198 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use std::fs;
+
+use chameleon::prelude::*;
+use chameleon::{draw, scale};
+
+/// Checks that thumbnails keep the aspect ratio and never grow an image.
+#[test]
+pub fn fit_keeps_the_aspect_ratio() {
+    assert_eq!(scale::fit(4000, 3000, 256), (256, 192));
+    assert_eq!(scale::fit(3000, 4000, 256), (192, 256));
+    assert_eq!(scale::fit(1000, 1, 10), (10, 1));
+    assert_eq!(scale::fit(32, 32, 256), (32, 32));
+    assert_eq!(scale::fit(32, 16, 0), (1, 1));
+}
+
+/// Checks that decoding straight into a thumbnail gives the same pixels as
+/// decoding the whole image and then shrinking it, with and without
+/// transformations and for sizes that don't divide the image evenly.
+#[test]
+pub fn png_thumbnail_matches_downscale() {
+    for transformations in [
+        Transformations::new(),
+        Transformations::new().flip_vertical(true).bgr(true),
+    ] {
+        for name in ["basn2c08", "f04n2c08"] {
+            let png = Png::decoder()
+                .transformations(transformations)
+                .decode(format!("./tests/samples/{name}.png"))
+                .unwrap();
+            let (width, height) = png.dimensions;
+            let rgba = png.rgba().unwrap();
+            let data = rgba.iter().flat_map(|&(r, g, b, a)| [r, g, b, a]).collect();
+            let image = Image::new(width, height, PixelFormat::Rgba8, data).unwrap();
+
+            for max_dim in [1, 7, 8, 32] {
+                assert_eq!(
+                    png.thumbnail(max_dim).unwrap(),
+                    image.thumbnail(max_dim).unwrap(),
+                    "{name} at {max_dim}"
+                );
+            }
+        }
+    }
+}
+
+/// Checks that each output pixel is the average of the block it covers, and
+/// that fully transparent pixels don't change the color of the average.
+#[test]
+pub fn downscale_averages_with_premultiplied_alpha() {
+    let image = Image::new(
+        4,
+        1,
+        PixelFormat::Rgb8,
+        vec![0, 0, 0, 255, 255, 255, 10, 20, 30, 30, 40, 50],
+    )
+    .unwrap();
+    assert_eq!(
+        image.downscale(2, 1).unwrap().data,
+        [128, 128, 128, 20, 30, 40]
+    );
+
+    let image = Image::new(2, 1, PixelFormat::Rgba8, vec![255, 0, 0, 255, 0, 0, 255, 0]).unwrap();
+    assert_eq!(image.downscale(1, 1).unwrap().data, [255, 0, 0, 128]);
+
+    assert_eq!(
+        image.downscale(3, 1),
+        Err(ImageError::InvalidDimensions(3, 1))
+    );
+}
+
+/// Checks that thumbnails of PNG files keep their alpha, and that the
+/// color hidden under transparent pixels doesn't tint a half transparent
+/// edge.
+#[test]
+pub fn png_thumbnail_premultiplies_alpha() {
+    #[rustfmt::skip]
+    let data = vec![
+        255, 0, 0, 255,  255, 0, 0, 255,  0, 255, 0, 0,  0, 0, 255, 128,
+    ];
+    let image = Image::new(4, 1, PixelFormat::Rgba8, data).unwrap();
+    let bytes = image.encode_png(&EncodeOptions::new()).unwrap();
+    let png = Png::decoder().decode_bytes(&bytes).unwrap();
+
+    let thumbnail = png.thumbnail(2).unwrap();
+    assert_eq!(thumbnail.format, PixelFormat::Rgba8);
+    assert_eq!(thumbnail.data, [255, 0, 0, 255, 0, 0, 255, 64]);
+}
+
+/// Checks the single call from a PNG file to a thumbnail PNG file.
+#[test]
+pub fn thumbnail_encodes_a_smaller_png() {
+    let bytes = fs::read("./tests/samples/basn2c08.png").unwrap();
+    let thumbnail = scale::thumbnail(&bytes, 10, &EncodeOptions::new()).unwrap();
+
+    let png = Png::decoder().decode_bytes(&thumbnail).unwrap();
+    assert_eq!(png.dimensions, (10, 10));
+}
+
+/// Checks the policy for images with no pixels: they can be made and drawn
+/// on, which does nothing, but shrinking or encoding them is an
+/// InvalidDimensions error. 1x1 images work everywhere.
+#[test]
+pub fn empty_and_single_pixel_images() {
+    for (width, height) in [(0, 0), (0, 3), (3, 0)] {
+        let mut image = Image::solid(width, height, [1, 2, 3, 4]).unwrap();
+        assert!(image.data.is_empty());
+
+        draw::fill(&mut image, 0, 0, 2, 2, [0; 4]).unwrap();
+        draw::composite(
+            &mut image,
+            &Image::solid(2, 2, [0; 4]).unwrap(),
+            0,
+            0,
+            Blend::Srgb,
+        )
+        .unwrap();
+        assert!(image.data.is_empty());
+
+        let invalid = |result: Result<(), Error>| {
+            matches!(result, Err(Error::Image(ImageError::InvalidDimensions(..))))
+        };
+        assert!(invalid(
+            image.downscale(1, 1).map(drop).map_err(Error::from)
+        ));
+        assert!(invalid(image.thumbnail(4).map(drop).map_err(Error::from)));
+        let options = EncodeOptions::new();
+        assert!(invalid(
+            image.encode_png(&options).map(drop).map_err(Error::from)
+        ));
+        let format = PixelFormat::Rgba8;
+        assert!(invalid(
+            StreamEncoder::new(Vec::new(), width, height, format, &options).map(drop)
+        ));
+    }
+
+    let image = Image::solid(1, 1, [1, 2, 3, 4]).unwrap();
+    assert_eq!(image.downscale(1, 1).unwrap(), image);
+    assert_eq!(image.thumbnail(8).unwrap(), image);
+    let bytes = image.encode_png(&EncodeOptions::new()).unwrap();
+    let png = Png::decoder().decode_bytes(&bytes).unwrap();
+    assert_eq!(png.rgba().unwrap(), [(1, 2, 3, 4)]);
+    assert_eq!(scale::fit(1, 1, 8), (1, 1));
+}
+
+/// Checks that 9-slice scaling keeps the corners, stretches the edges and
+/// center with the nearest pixel, and refuses sizes the insets don't fit.
+#[test]
+pub fn nine_slice_keeps_corners() {
+    #[rustfmt::skip]
+    let image = Image::new(4, 3, PixelFormat::Gray8, vec![
+        1, 2, 3, 4,
+        5, 6, 7, 8,
+        9, 10, 11, 12,
+    ]).unwrap();
+    let insets = Insets {
+        left: 1,
+        top: 1,
+        right: 1,
+        bottom: 1,
+    };
+
+    let grown = image.scale_9slice(insets, 6, 4).unwrap();
+    #[rustfmt::skip]
+    assert_eq!(grown.data, [
+        1, 2, 2, 3, 3, 4,
+        5, 6, 6, 7, 7, 8,
+        5, 6, 6, 7, 7, 8,
+        9, 10, 10, 11, 11, 12,
+    ]);
+
+    let shrunk = image.scale_9slice(insets, 3, 2).unwrap();
+    assert_eq!(shrunk.data, [1, 3, 4, 9, 11, 12]);
+    assert_eq!(image.scale_9slice(Insets::default(), 4, 3).unwrap(), image);
+
+    let rgba = Image::solid(3, 3, [1, 2, 3, 4]).unwrap();
+    let stretched = rgba.scale_9slice(Insets::uniform(1), 5, 7).unwrap();
+    assert_eq!(stretched, Image::solid(5, 7, [1, 2, 3, 4]).unwrap());
+
+    let too_small = image.scale_9slice(Insets::uniform(1), 1, 3);
+    assert_eq!(too_small, Err(ImageError::InvalidDimensions(1, 3)));
+    assert!(image.scale_9slice(Insets::uniform(2), 8, 8).is_err());
+    let no_center = Insets {
+        left: 2,
+        top: 0,
+        right: 2,
+        bottom: 0,
+    };
+    assert!(image.scale_9slice(no_center, 5, 3).is_err());
+    assert!(image.scale_9slice(no_center, 4, 6).is_ok());
+
+    let too_large = rgba.scale_9slice(Insets::uniform(1), usize::MAX / 2, 3);
+    assert_eq!(
+        too_large,
+        Err(ImageError::InvalidDimensions(usize::MAX / 2, 3))
+    );
+}
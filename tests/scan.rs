@@ -0,0 +1,79 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use std::fs;
+
+use chameleon::prelude::*;
+use chameleon::scan;
+
+/// Checks that broken files are reported with warnings rather than failing
+/// the scan, and that their headers are still read.
+#[test]
+pub fn scan_reports_warnings() {
+    let entries = scan::scan_dir("./tests/corrupt").unwrap();
+    let find = |name: &str| {
+        entries
+            .iter()
+            .find(|entry| entry.path.ends_with(name))
+            .unwrap()
+    };
+
+    assert_eq!(entries.len(), 6);
+    assert!(entries.iter().all(|entry| entry.error.is_none()));
+    // The image data is never decompressed, so damage inside it isn't seen.
+    assert!(find("bad_huffman.png").warnings.is_empty());
+
+    let bad_crc = find("bad_crc.png");
+    assert_eq!(bad_crc.warnings.len(), 1);
+    assert!(bad_crc.warnings[0].contains("CRC"));
+
+    let missing_iend = find("missing_iend.png");
+    assert_eq!(
+        missing_iend.warnings,
+        ["no IEND chunk, the file may be truncated."]
+    );
+    let info = missing_iend.info.as_ref().unwrap();
+    assert_eq!(info.dimensions, (32, 32));
+    assert_eq!(info.color_type, ColorType::RGB);
+    assert_eq!(info.chunks, ["gAMA"]);
+}
+
+/// Checks that files which aren't PNG files at all are errors, and that the
+/// JSON report escapes what it writes.
+#[test]
+pub fn scan_report_json() {
+    let dir = std::env::temp_dir().join("chameleon_scan_report");
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    fs::write(dir.join("nested").join("not \"a\" png.PNG"), b"hello").unwrap();
+    fs::write(dir.join("ignored.txt"), b"hello").unwrap();
+
+    let entries = scan::scan_dir(&dir).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].info.is_none());
+    assert!(entries[0].error.is_some());
+
+    let json = scan::to_json(&entries);
+    assert!(json.contains(r#"not \"a\" png.PNG""#));
+    assert!(json.contains(r#""size": 5"#));
+    assert_eq!(scan::to_json(&[]), "[]\n");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+/// Checks that symbolic links to directories aren't followed, so a link
+/// back to the directory being scanned doesn't loop.
+#[cfg(unix)]
+#[test]
+pub fn scan_skips_linked_directories() {
+    let dir = std::env::temp_dir().join("chameleon_scan_links");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy("./tests/samples/basn2c08.png", dir.join("image.png")).unwrap();
+    std::os::unix::fs::symlink(&dir, dir.join("loop.png")).unwrap();
+    std::os::unix::fs::symlink(&dir, dir.join("loop")).unwrap();
+
+    let entries = scan::scan_dir(&dir).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].path.ends_with("image.png"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
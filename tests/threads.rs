@@ -0,0 +1,86 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use chameleon::compression::backend::{Compressor, Native};
+use chameleon::formats::png::{Chunk, ChunkType};
+use chameleon::prelude::*;
+
+/// The (x, y) start and step of each Adam7 pass.
+const PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Builds an Adam7 interlaced RGB file from an Rgb8 image, every scanline
+/// filtered with Sub so reconstructing it has something to do.
+fn interlaced(image: &Image) -> Vec<u8> {
+    let chunk = |ctype: &[u8; 4], data: &[u8]| {
+        Chunk::with_data(ChunkType::new(*ctype).unwrap(), data.to_vec()).to_bytes()
+    };
+
+    let mut data = Vec::new();
+    for (x0, y0, dx, dy) in PASSES {
+        for y in (y0..image.height).step_by(dy) {
+            data.push(1);
+            let mut last = [0; 3];
+            for x in (x0..image.width).step_by(dx) {
+                let pixel = &image.data[(y * image.width + x) * 3..][..3];
+                for c in 0..3 {
+                    data.push(pixel[c].wrapping_sub(last[c]));
+                }
+                last.copy_from_slice(pixel);
+            }
+        }
+    }
+
+    let mut ihdr = (image.width as u32).to_be_bytes().to_vec();
+    ihdr.extend_from_slice(&(image.height as u32).to_be_bytes());
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 1]);
+
+    let mut bytes = vec![137, 80, 78, 71, 13, 10, 26, 10];
+    bytes.extend(chunk(b"IHDR", &ihdr));
+    bytes.extend(chunk(b"IDAT", &Native.compress(&data)));
+    bytes.extend(chunk(b"IEND", &[]));
+    bytes
+}
+
+/// Checks that large images decode to the same pixels on many threads as
+/// on one, interlaced or not, and that damage is still reported.
+#[test]
+pub fn threads_give_identical_pixels() {
+    let image = Image::noise(700, 700, 7).unwrap();
+    let image = Image::new(
+        700,
+        700,
+        PixelFormat::Rgb8,
+        image
+            .data
+            .chunks_exact(4)
+            .flat_map(|p| [p[0], p[1], p[2]])
+            .collect(),
+    )
+    .unwrap();
+    let plain = image
+        .encode_png(&EncodeOptions::new().reduce(false))
+        .unwrap();
+
+    for bytes in [plain, interlaced(&image)] {
+        let single = Png::decoder().threads(1).decode_bytes(&bytes).unwrap();
+        let many = Png::decoder().threads(4).decode_bytes(&bytes).unwrap();
+        let expected = single.rgba().unwrap();
+        assert_eq!(many.rgba().unwrap(), expected);
+        assert_eq!(
+            expected[701],
+            (image.data[2103], image.data[2104], image.data[2105], 255)
+        );
+
+        let mut short = Png::decoder().threads(4).decode_bytes(&bytes).unwrap();
+        let idat = &mut short.data.idat[0].data;
+        idat.truncate(idat.len() / 2);
+        assert!(short.rgba().is_err());
+    }
+}
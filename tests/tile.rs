@@ -0,0 +1,240 @@
+#![cfg(all(feature = "std", feature = "png"))]
+
+use chameleon::color;
+use chameleon::prelude::*;
+use chameleon::tile::{Convolve, Exposure, MapColors, TileProcessor, Vignette, WhiteBalance};
+
+/// An opaque RGB image large enough to span several deflate blocks.
+fn large_image(width: usize, height: usize) -> Image {
+    let data = (0..width * height)
+        .flat_map(|i| [(i % 251) as u8, (i / width) as u8, (i * 7 % 256) as u8])
+        .collect();
+    Image::new(width, height, PixelFormat::Rgb8, data).unwrap()
+}
+
+/// Decodes a PNG file to RGBA bytes.
+fn rgba(bytes: &[u8]) -> Vec<u8> {
+    let png = Png::decoder().decode_bytes(bytes).unwrap();
+    png.rgba()
+        .unwrap()
+        .into_iter()
+        .flat_map(|(r, g, b, a)| [r, g, b, a])
+        .collect()
+}
+
+/// Checks that rows stream out of a file too large to inflate in one block,
+/// and that a StreamEncoder writes the same pixels as Image::encode_png.
+#[test]
+pub fn rows_stream_through() {
+    let image = large_image(300, 200);
+    let bytes = image
+        .encode_png(&EncodeOptions::new().reduce(false))
+        .unwrap();
+    let png = Png::decoder().decode_bytes(&bytes).unwrap();
+
+    let mut encoder = StreamEncoder::new(
+        Vec::new(),
+        300,
+        200,
+        PixelFormat::Rgb8,
+        &EncodeOptions::new().filter(Filters::Paeth),
+    )
+    .unwrap();
+    let mut rows = 0;
+    png.rgba_rows(&mut |row, pixels| {
+        let rgb: Vec<u8> = pixels
+            .chunks_exact(4)
+            .flat_map(|p| [p[0], p[1], p[2]])
+            .collect();
+        assert_eq!(rgb, image.data[row * 900..(row + 1) * 900]);
+        encoder.write_row(&rgb).unwrap();
+        rows += 1;
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(rows, 200);
+
+    let streamed = encoder.finish().unwrap();
+    assert_eq!(rgba(&streamed), rgba(&bytes));
+}
+
+/// Checks that a StreamEncoder refuses rows of the wrong length, and files
+/// missing rows.
+#[test]
+pub fn stream_encoder_checks_rows() {
+    let mut encoder =
+        StreamEncoder::new(Vec::new(), 2, 2, PixelFormat::Gray8, &EncodeOptions::new()).unwrap();
+
+    assert!(encoder.write_row(&[0; 3]).is_err());
+    encoder.write_row(&[0; 2]).unwrap();
+    assert!(encoder.finish().is_err());
+}
+
+/// Checks that processing without any ops gives back the same pixels, at
+/// 8 and 16 bits.
+#[test]
+pub fn no_ops_keeps_pixels() {
+    for name in ["basn2c08", "basn6a16", "tbbn3p08"] {
+        let bytes = std::fs::read(format!("./tests/samples/{name}.png")).unwrap();
+        let png = Png::decoder().decode_bytes(&bytes).unwrap();
+
+        let out = TileProcessor::new()
+            .tile_size(7)
+            .run(&png, Vec::new(), &EncodeOptions::new())
+            .unwrap();
+        assert_eq!(rgba(&out), rgba(&bytes), "{name}");
+
+        let decoded = Png::decoder().decode_bytes(&out).unwrap();
+        assert_eq!(decoded.bit_depth, png.bit_depth.max(8), "{name}");
+    }
+}
+
+/// Checks that tiles line up without seams, so the result doesn't depend
+/// on the tile size, and that colors can be mapped.
+#[test]
+pub fn ops_ignore_tile_size() {
+    let png = Png::from_path("./tests/samples/basn6a08.png").unwrap();
+    let run = |tile_size| {
+        TileProcessor::new()
+            .tile_size(tile_size)
+            .then(Convolve::box_blur(2))
+            .then(MapColors::new(|[r, g, b, a]| [u16::MAX - r, g, b, a]))
+            .run(&png, Vec::new(), &EncodeOptions::new())
+            .unwrap()
+    };
+
+    let whole = rgba(&run(64));
+    assert_eq!(rgba(&run(5)), whole);
+    assert_ne!(
+        whole,
+        rgba(&std::fs::read("./tests/samples/basn6a08.png").unwrap())
+    );
+
+    let center = Convolve::new(3, &[0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0]).unwrap();
+    let out = TileProcessor::new()
+        .tile_size(6)
+        .then(center)
+        .run(&png, Vec::new(), &EncodeOptions::new())
+        .unwrap();
+    assert_eq!(rgba(&out), rgba(&png.to_bytes()));
+
+    assert!(Convolve::new(2, &[0.25; 4]).is_err());
+    assert!(Convolve::new(3, &[0.25; 4]).is_err());
+}
+
+/// Checks that resizing gives the same pixels as Image::downscale, and
+/// that growing the image is refused.
+#[test]
+pub fn resize_matches_downscale() {
+    let image = large_image(300, 200);
+    let bytes = image.encode_png(&EncodeOptions::new()).unwrap();
+    let png = Png::decoder().decode_bytes(&bytes).unwrap();
+
+    let out = TileProcessor::new()
+        .resize(70, 45)
+        .format(PixelFormat::Rgb8)
+        .run(&png, Vec::new(), &EncodeOptions::new())
+        .unwrap();
+    let expected = image.downscale(70, 45).unwrap();
+    assert_eq!(
+        Image::try_from(Png::decoder().decode_bytes(&out).unwrap()).unwrap(),
+        expected
+    );
+
+    assert!(TileProcessor::new()
+        .resize(301, 200)
+        .run(&png, Vec::new(), &EncodeOptions::new())
+        .is_err());
+}
+
+/// Checks every op runs on a 1x1 image, whose tiles and kernels reach past
+/// the edge on every side.
+#[test]
+pub fn single_pixel_image() {
+    let image = Image::solid(1, 1, [10, 20, 30, 255]).unwrap();
+    let png = Png::decoder()
+        .decode_bytes(&image.encode_png(&EncodeOptions::new()).unwrap())
+        .unwrap();
+
+    let bytes = TileProcessor::new()
+        .tile_size(0)
+        .then(Convolve::box_blur(2))
+        .then(MapColors::new(|[r, g, b, a]| [b, g, r, a]))
+        .resize(1, 1)
+        .run(&png, Vec::new(), &EncodeOptions::new())
+        .unwrap();
+    assert_eq!(rgba(&bytes), [30, 20, 10, 255]);
+}
+
+/// Checks exposure and white balance scale light rather than sRGB values,
+/// that nothing changes when they're left at 0, and that alpha is kept.
+#[test]
+pub fn adjustments_scale_light() {
+    let image = Image::new(
+        2,
+        1,
+        PixelFormat::Rgba8,
+        vec![100, 100, 100, 200, 250, 0, 20, 255],
+    )
+    .unwrap();
+    let png = Png::decoder()
+        .decode_bytes(&image.encode_png(&EncodeOptions::new()).unwrap())
+        .unwrap();
+    let run = |processor: TileProcessor| {
+        let mut processor = processor;
+        rgba(
+            &processor
+                .run(&png, Vec::new(), &EncodeOptions::new())
+                .unwrap(),
+        )
+    };
+
+    let unchanged = TileProcessor::new()
+        .then(Exposure::new(0.0))
+        .then(WhiteBalance::new(0.0, 0.0))
+        .then(Vignette::new(0.0));
+    assert_eq!(run(unchanged), image.data);
+
+    // Clipped at white, and black stays black.
+    let brighter = run(TileProcessor::new().then(Exposure::new(1.0)));
+    let doubled = |sample| color::linear_to_srgb(color::srgb_to_linear(sample) * 2.0);
+    let gray = doubled(100);
+    assert_eq!(brighter, [gray, gray, gray, 200, 255, 0, doubled(20), 255]);
+
+    let warmer = run(TileProcessor::new().then(WhiteBalance::new(2.0, 0.0)));
+    let red = doubled(100);
+    let blue = color::linear_to_srgb(color::srgb_to_linear(100) / 2.0);
+    assert_eq!(warmer[..4], [red, 100, blue, 200]);
+
+    let magenta = run(TileProcessor::new().then(WhiteBalance::new(0.0, 1.0)));
+    let green = color::linear_to_srgb(color::srgb_to_linear(100) / 2.0);
+    assert_eq!(magenta[..4], [100, green, 100, 200]);
+}
+
+/// Checks a vignette darkens towards the corners of the whole image,
+/// whatever the tile size, and can brighten them instead.
+#[test]
+pub fn vignette_darkens_corners() {
+    let image = Image::new(9, 9, PixelFormat::Gray8, vec![128; 81]).unwrap();
+    let png = Png::decoder()
+        .decode_bytes(&image.encode_png(&EncodeOptions::new()).unwrap())
+        .unwrap();
+    let run = |tile_size, strength| {
+        let out = TileProcessor::new()
+            .tile_size(tile_size)
+            .then(Vignette::new(strength))
+            .run(&png, Vec::new(), &EncodeOptions::new())
+            .unwrap();
+        rgba(&out).chunks_exact(4).map(|p| p[0]).collect::<Vec<_>>()
+    };
+
+    let darker = run(64, 0.5);
+    assert_eq!(darker, run(2, 0.5));
+    assert_eq!(darker[40], 128);
+    assert!(darker[0] < darker[10] && darker[10] < darker[40]);
+    assert_eq!(darker[0], darker[80]);
+
+    let brighter = run(3, -0.5);
+    assert!(brighter[0] > 128);
+    assert_eq!(run(3, 2.0)[0], 0);
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "std")]
+
+use chameleon::prelude::*;
+use chameleon::window::Window;
+
+/// Checks every pixel gets a window in order, and that blocks repeat the
+/// edge of the image past it.
+#[test]
+pub fn windows_clamp_at_edges() {
+    let data = (0..12).collect::<Vec<u8>>();
+    let image = Image::new(4, 3, PixelFormat::Gray8, data).unwrap();
+    let gray = |value: u16| [value * 257, value * 257, value * 257, 65535];
+
+    let windows = image.windows(1);
+    assert_eq!(windows.len(), 12);
+    let windows = windows.collect::<Vec<_>>();
+    assert_eq!((windows[5].x, windows[5].y), (1, 1));
+    assert_eq!(windows[5].center(), gray(5));
+
+    // The top left corner repeats its row and column outwards.
+    let corner = windows[0].pixels().collect::<Vec<_>>();
+    assert_eq!(corner, [0, 0, 1, 0, 0, 1, 4, 4, 5].map(gray));
+    assert_eq!(windows[11].pixel(5, 5), gray(11));
+    assert_eq!(windows[11].pixel(-9, 0), gray(8));
+
+    let radius = image.windows(2).next().unwrap();
+    assert_eq!(radius.pixels().count(), 25);
+}
+
+/// Checks windows can't be built on empty images or outside the image,
+/// and that one built by hand anyway reads transparent black rather than
+/// panicking.
+#[test]
+pub fn windows_on_empty_images() {
+    let empty = Image::new(0, 3, PixelFormat::Rgba8, Vec::new()).unwrap();
+    assert_eq!(empty.windows(1).count(), 0);
+    assert_eq!(Window::new(empty.view(), 0, 0, 1), None);
+
+    let image = Image::solid(2, 2, [1, 2, 3, 4]).unwrap();
+    assert!(Window::new(image.view(), 1, 1, 1).is_some());
+    assert_eq!(Window::new(image.view(), 2, 0, 1), None);
+    let corner = Window::new(image.view(), 0, 0, 1).unwrap();
+    assert_eq!(corner.pixel(isize::MIN, isize::MAX), [257, 514, 771, 1028]);
+
+    let window = Window {
+        x: 0,
+        y: 0,
+        radius: 1,
+        image: empty.view(),
+    };
+    assert_eq!(window.pixels().collect::<Vec<_>>(), [[0; 4]; 9]);
+}
+
+/// Checks a box blur written with windows matches one averaging by hand, on
+/// a region whose rows are padded by the image around it.
+#[test]
+pub fn windows_over_a_region() {
+    let image = Image::noise(9, 7, 5).unwrap();
+    let region = image.region(2, 1, 5, 4).unwrap();
+
+    for window in region.windows(1) {
+        let sum = window.pixels().map(|[r, ..]| r as u32).sum::<u32>();
+        let mut expected = 0;
+        for dy in -1..=1isize {
+            for dx in -1..=1isize {
+                let x = (window.x as isize + dx).clamp(0, 4) as usize + 2;
+                let y = (window.y as isize + dy).clamp(0, 3) as usize + 1;
+                expected += image.data[(y * 9 + x) * 4] as u32 * 257;
+            }
+        }
+        assert_eq!(sum, expected, "{}, {}", window.x, window.y);
+    }
+
+    let mut short = region;
+    short.data = &short.data[..10];
+    assert_eq!(short.windows(1).count(), 0);
+}